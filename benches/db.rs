@@ -0,0 +1,88 @@
+//! `Db`的读写以及发布/订阅，需要开启`test-util`特性。
+
+use std::thread;
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use my_redis::test_util::DbHandle;
+
+/// 每个线程每轮执行的操作数。
+const OPS_PER_THREAD: usize = 1000;
+
+/// 预先写入的 key 的数量。
+const KEYS: usize = 10_000;
+
+fn key(i: usize) -> String {
+    format!("key:{:06}", i % KEYS)
+}
+
+fn get_set_contention(c: &mut Criterion) {
+    get_set(c, "db/get_set", false);
+    get_set(c, "db/get_set_read_optimized", true);
+}
+
+fn get_set(c: &mut Criterion, name: &str, read_optimized: bool) {
+    // `Db`会开启后台任务，需要 tokio 运行时。
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let _guard = rt.enter();
+    let db = DbHandle::new();
+    db.set_read_optimized(read_optimized);
+    for i in 0..KEYS {
+        db.set(key(i), Bytes::from_static(b"value"), None);
+    }
+
+    let mut group = c.benchmark_group(name);
+    for threads in [1, 2, 4, 8] {
+        group.throughput(Throughput::Elements((threads * OPS_PER_THREAD) as u64));
+        group.bench_with_input(BenchmarkId::new("threads", threads), &threads, |b, &n| {
+            b.iter(|| {
+                thread::scope(|s| {
+                    for t in 0..n {
+                        let db = &db;
+                        s.spawn(move || {
+                            // 读多写少：每 10 次操作中有 1 次写。
+                            for i in 0..OPS_PER_THREAD {
+                                let k = key(t * OPS_PER_THREAD + i);
+                                if i % 10 == 0 {
+                                    db.set(k, Bytes::from_static(b"value"), None);
+                                } else {
+                                    let _ = db.get(&k);
+                                }
+                            }
+                        });
+                    }
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
+fn pubsub_fanout(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let _guard = rt.enter();
+    let db = DbHandle::new();
+    let message = Bytes::from_static(b"hello");
+
+    let mut group = c.benchmark_group("db/pubsub_fanout");
+    for subscribers in [1, 16, 256] {
+        let mut receivers: Vec<_> = (0..subscribers)
+            .map(|_| db.subscribe(format!("channel:{}", subscribers)))
+            .collect();
+        let channel = format!("channel:{}", subscribers);
+
+        group.throughput(Throughput::Elements(subscribers as u64));
+        group.bench_function(BenchmarkId::new("subscribers", subscribers), |b| {
+            b.iter(|| {
+                db.publish(&channel, message.clone());
+                for rx in receivers.iter_mut() {
+                    rx.try_recv().unwrap();
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, get_set_contention, pubsub_fanout);
+criterion_main!(benches);
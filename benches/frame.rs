@@ -0,0 +1,107 @@
+//! `Frame`的解析与编码。
+
+use std::{
+    io::{self, Cursor},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{Bytes, BytesMut};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use my_redis::{Connection, Frame};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// 丢弃所有写入数据的传输层，用于测量编码开销。
+#[derive(Debug)]
+struct Discard;
+
+impl AsyncRead for Discard {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for Discard {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// 生成一个`Set <key> <value>`命令帧，value 的长度为`size`。
+fn set_frame(size: usize) -> Frame {
+    Frame::Array(vec![
+        Frame::Bulk(Bytes::from_static(b"set")),
+        Frame::Bulk(Bytes::from_static(b"key:000001")),
+        Frame::Bulk(Bytes::from(vec![b'x'; size])),
+    ])
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame/parse");
+    for size in [16, 1024, 64 * 1024] {
+        let mut buf = BytesMut::new();
+        set_frame(size).encode(&mut buf);
+        group.throughput(Throughput::Bytes(buf.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &buf[..], |b, buf| {
+            b.iter(|| {
+                let mut cursor = Cursor::new(buf);
+                Frame::check(&mut cursor).unwrap();
+                cursor.set_position(0);
+                Frame::parse(&mut cursor).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame/encode");
+    for size in [16, 1024, 64 * 1024] {
+        let frame = set_frame(size);
+        let mut buf = BytesMut::new();
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &frame, |b, frame| {
+            b.iter(|| {
+                buf.clear();
+                frame.encode(&mut buf);
+            })
+        });
+    }
+    group.finish();
+}
+
+fn encode_frame(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let mut connection = Connection::new(Discard);
+
+    let mut group = c.benchmark_group("frame/write_frame");
+    for size in [16, 1024, 64 * 1024] {
+        let frame = set_frame(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &frame, |b, frame| {
+            b.iter(|| rt.block_on(connection.write_frame(frame)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse, encode, encode_frame);
+criterion_main!(benches);
@@ -0,0 +1,186 @@
+//! 将任意`Frame`交给`Command::from_frame`。
+//!
+//! 运行：`cargo +nightly fuzz run command_from_frame`
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use bytes::Bytes;
+use libfuzzer_sys::fuzz_target;
+use my_redis::{Command, Frame};
+
+/// `Frame`的镜像，用于从任意字节生成`Frame`。
+#[derive(Arbitrary, Debug)]
+enum ArbitraryFrame {
+    Simple(String),
+    Error(String),
+    Integer(i64),
+    Bulk(Vec<u8>),
+    Array(Vec<ArbitraryFrame>),
+    Null,
+    Map(Vec<(ArbitraryFrame, ArbitraryFrame)>),
+    Double(f64),
+    Push(Vec<ArbitraryFrame>),
+    Set(Vec<ArbitraryFrame>),
+    Boolean(bool),
+    BigNumber(String),
+    Verbatim(String, Vec<u8>),
+    // 命令名称是否合法决定了能否进入具体命令的解析，
+    // 所以单独生成一些已知的命令名称。
+    Command(KnownCommand, Vec<ArbitraryFrame>),
+}
+
+#[derive(Arbitrary, Debug, Clone, Copy)]
+enum KnownCommand {
+    Get,
+    Set,
+    SetEx,
+    PSetEx,
+    Publish,
+    Subscribe,
+    Unsubscribe,
+    PSubscribe,
+    PUnsubscribe,
+    SPublish,
+    SSubscribe,
+    SUnsubscribe,
+    Ping,
+    Auth,
+    Hello,
+    Asking,
+    Reset,
+    Config,
+    Acl,
+    Client,
+    Cluster,
+    Command,
+    Eval,
+    EvalSha,
+    Script,
+    Function,
+    FCall,
+    Save,
+    BgSave,
+    LastSave,
+    Slowlog,
+    Latency,
+    Debug,
+    Info,
+    Backup,
+    RestoreFrom,
+    Dump,
+    Restore,
+    ReplicaOf,
+    PSync,
+    Wait,
+    Expire,
+    PExpire,
+    ExpireAt,
+    PExpireAt,
+    Ttl,
+    PTtl,
+    ExpireTime,
+    PExpireTime,
+    Incr,
+    Decr,
+    IncrBy,
+    DecrBy,
+    IncrByFloat,
+    Type,
+    Unlink,
+    Touch,
+    Object,
+    Memory,
+    HSet,
+    HGet,
+    HDel,
+    HGetAll,
+    HRandField,
+    LPush,
+    RPush,
+    LPop,
+    RPop,
+    LRange,
+    LLen,
+    LPos,
+    LInsert,
+    LSet,
+    LRem,
+    BLPop,
+    BRPop,
+    LMPop,
+    BLMPop,
+    SAdd,
+    SRem,
+    SMembers,
+    SIsMember,
+    SCard,
+    SRandMember,
+    SInter,
+    SUnion,
+    SDiff,
+    SInterStore,
+    SUnionStore,
+    SDiffStore,
+    ZAdd,
+    ZScore,
+    ZRange,
+    ZCard,
+    ZRandMember,
+    ZRangeByScore,
+    ZIncrBy,
+    ZRem,
+    ZRank,
+    ZMPop,
+    BZMPop,
+    GeoAdd,
+    GeoPos,
+    GeoDist,
+    GeoSearch,
+    SetBit,
+    GetBit,
+    BitCount,
+    XAdd,
+    XRange,
+    XLen,
+    XRead,
+    XGroup,
+    XReadGroup,
+    XAck,
+    XClaim,
+}
+
+impl From<ArbitraryFrame> for Frame {
+    fn from(frame: ArbitraryFrame) -> Frame {
+        match frame {
+            ArbitraryFrame::Simple(s) => Frame::Simple(s),
+            ArbitraryFrame::Error(s) => Frame::Error(s),
+            ArbitraryFrame::Integer(v) => Frame::Integer(v),
+            ArbitraryFrame::Bulk(data) => Frame::Bulk(Bytes::from(data)),
+            ArbitraryFrame::Array(items) => Frame::Array(items.into_iter().map(Frame::from).collect()),
+            ArbitraryFrame::Null => Frame::Null,
+            ArbitraryFrame::Map(pairs) => Frame::Map(
+                pairs
+                    .into_iter()
+                    .map(|(key, value)| (key.into(), value.into()))
+                    .collect(),
+            ),
+            ArbitraryFrame::Double(v) => Frame::Double(v),
+            ArbitraryFrame::Push(items) => Frame::Push(items.into_iter().map(Frame::from).collect()),
+            ArbitraryFrame::Set(items) => Frame::Set(items.into_iter().map(Frame::from).collect()),
+            ArbitraryFrame::Boolean(v) => Frame::Boolean(v),
+            ArbitraryFrame::BigNumber(s) => Frame::BigNumber(s),
+            ArbitraryFrame::Verbatim(format, data) => Frame::Verbatim(format, Bytes::from(data)),
+            ArbitraryFrame::Command(name, args) => {
+                let name = format!("{:?}", name);
+                let mut items = vec![Frame::Bulk(Bytes::from(name))];
+                items.extend(args.into_iter().map(Frame::from));
+                Frame::Array(items)
+            }
+        }
+    }
+}
+
+fuzz_target!(|frame: ArbitraryFrame| {
+    let _ = Command::from_frame(frame.into());
+});
@@ -0,0 +1,26 @@
+//! 将任意字节交给`Frame::check`和`Frame::parse`。
+//!
+//! 运行：`cargo +nightly fuzz run frame_parse`
+
+#![no_main]
+
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+use my_redis::Frame;
+
+fuzz_target!(|data: &[u8]| {
+    // 与`Connection::parse_frame`一样，先检查再解析。
+    let mut buf = Cursor::new(data);
+    if Frame::check(&mut buf).is_ok() {
+        let len = buf.position();
+        buf.set_position(0);
+        // 通过了检查的数据必须能够解析，并且解析消耗的字节数与检查一致。
+        if Frame::parse(&mut buf).is_ok() {
+            assert_eq!(len, buf.position());
+        }
+    }
+
+    // 不经过检查直接解析也不能崩溃。
+    let _ = Frame::parse(&mut Cursor::new(data));
+});
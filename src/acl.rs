@@ -0,0 +1,463 @@
+//! 访问控制列表（ACL）。
+//!
+//! 每个用户有自己的密码、允许执行的命令以及允许访问的 key。
+//! 连接通过`Auth`认证为某个用户，之后每条命令执行前都会检查权限。
+//!
+//! 用户的规则与 Redis 相同，按照顺序应用：
+//!
+//! - `on`、`off`：启用或禁用用户
+//! - `>password`、`<password`、`#<sha1>`、`nopass`、`resetpass`：添加、删除密码或者不需要密码
+//! - `~pattern`、`allkeys`、`resetkeys`：允许访问匹配的 key
+//! - `+command`、`-command`、`+@category`、`-@category`、`allcommands`、`nocommands`：允许或禁止命令
+//! - `reset`：恢复为新用户的状态
+//!
+//! 命令的类别由`CommandSpec`的标志得到：`@read`、`@write`、`@admin`、`@pubsub`、
+//! `@fast`、`@slow`、`@blocking`，以及包含所有命令的`@all`。
+//! 只检查命令的第一个 key，见`Command::get_key()`。
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{cmd::CommandSpec, glob::glob_match, interceptor::ConnContext, Command};
+
+/// 默认用户的名称，没有认证的连接使用这个用户。
+pub(crate) const DEFAULT_USER: &str = "default";
+
+/// 命令的类别，以及属于这个类别的命令需要有的标志。`slow`表示没有`fast`标志。
+///
+/// 此外，命令的分组（`CommandSpec::group`，例如`string`、`list`）也是类别。
+const CATEGORIES: &[(&str, &str)] = &[
+    ("read", "readonly"),
+    ("write", "write"),
+    ("admin", "admin"),
+    ("pubsub", "pubsub"),
+    ("fast", "fast"),
+    ("blocking", "blocking"),
+];
+
+/// 所有用户，以及加载用户的 ACL 文件。
+#[derive(Debug)]
+pub(crate) struct Acl {
+    users: Mutex<BTreeMap<String, User>>,
+    file: Mutex<Option<PathBuf>>,
+}
+
+/// 一个 ACL 用户。
+#[derive(Debug, Clone)]
+pub(crate) struct User {
+    enabled: bool,
+    nopass: bool,
+    // 密码的 SHA1 摘要，不保存明文。
+    passwords: BTreeSet<String>,
+    // 允许访问的 key 的模式。
+    keys: Vec<String>,
+    // 命令规则，后面的规则优先。
+    commands: Vec<CommandRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CommandRule {
+    allow: bool,
+    target: RuleTarget,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RuleTarget {
+    All,
+    Category(String),
+    Command(String),
+}
+
+impl Acl {
+    pub(crate) fn new() -> Acl {
+        Acl {
+            users: Mutex::new(BTreeMap::from([(
+                DEFAULT_USER.to_string(),
+                User::default_user(),
+            )])),
+            file: Mutex::new(None),
+        }
+    }
+
+    /// 检查连接是否有权限执行命令。
+    ///
+    /// # Errors
+    /// 没有认证、用户不存在或者被禁用、没有执行命令或者访问 key 的权限时，返回错误信息。
+    pub(crate) fn check(&self, ctx: &ConnContext, cmd: &Command) -> Result<(), String> {
+        let name = cmd.get_name();
+        // `Auth`、`Reset`等命令用于切换用户，总是允许执行。
+        if CommandSpec::lookup(name).is_some_and(|spec| spec.has_flag("no-auth")) {
+            return Ok(());
+        }
+        let users = self.users.lock().unwrap();
+        let user = match users.get(ctx.user()) {
+            Some(user) if user.enabled && (ctx.is_authenticated() || user.nopass) => user,
+            _ => return Err("NOAUTH Authentication required.".to_string()),
+        };
+        if !user.can_run(name) {
+            return Err(format!(
+                "NOPERM User {} has no permissions to run the '{}' command",
+                ctx.user(),
+                name
+            ));
+        }
+        match cmd.get_key() {
+            Some(key) if !user.can_access(key) => {
+                Err("NOPERM No permissions to access a key".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 使用用户名和密码认证。
+    ///
+    /// # Errors
+    /// 用户不存在、被禁用或者密码错误时返回错误信息。
+    pub(crate) fn authenticate(&self, username: &str, password: &str) -> Result<(), String> {
+        let users = self.users.lock().unwrap();
+        match users.get(username) {
+            Some(user)
+                if user.enabled && (user.nopass || user.passwords.contains(&hash(password))) =>
+            {
+                Ok(())
+            }
+            _ => Err("WRONGPASS invalid username-password pair or user is disabled.".to_string()),
+        }
+    }
+
+    /// 判断用户是否存在并且没有被禁用。
+    pub(crate) fn is_enabled(&self, username: &str) -> bool {
+        let users = self.users.lock().unwrap();
+        users.get(username).is_some_and(|user| user.enabled)
+    }
+
+    /// 判断用户是否不需要密码。
+    pub(crate) fn is_nopass(&self, username: &str) -> bool {
+        let users = self.users.lock().unwrap();
+        users.get(username).is_some_and(|user| user.nopass)
+    }
+
+    /// 创建或者修改用户，规则按照顺序应用到用户上。
+    ///
+    /// # Errors
+    /// 如果有不合法的规则，返回错误信息，用户保持不变。
+    pub(crate) fn set_user(&self, username: &str, rules: &[String]) -> Result<(), String> {
+        let mut users = self.users.lock().unwrap();
+        let mut user = users.get(username).cloned().unwrap_or_else(User::new);
+        for rule in rules {
+            user.apply_rule(rule)?;
+        }
+        users.insert(username.to_string(), user);
+        Ok(())
+    }
+
+    /// 删除用户，返回删除的个数。
+    ///
+    /// # Errors
+    /// 不能删除默认用户。
+    pub(crate) fn del_users(&self, usernames: &[String]) -> Result<usize, String> {
+        if usernames.iter().any(|name| name == DEFAULT_USER) {
+            return Err("ERR The 'default' user cannot be removed".to_string());
+        }
+        let mut users = self.users.lock().unwrap();
+        Ok(usernames
+            .iter()
+            .filter(|name| users.remove(*name).is_some())
+            .count())
+    }
+
+    /// 获取用户。
+    pub(crate) fn get_user(&self, username: &str) -> Option<User> {
+        self.users.lock().unwrap().get(username).cloned()
+    }
+
+    /// 以`user <name> <rules>`的格式列出所有用户，可以直接写入 ACL 文件。
+    pub(crate) fn list(&self) -> Vec<String> {
+        let users = self.users.lock().unwrap();
+        users
+            .iter()
+            .map(|(name, user)| format!("user {} {}", name, user.describe()))
+            .collect()
+    }
+
+    /// 从 ACL 文件加载所有用户，之后`Acl Load`会重新加载这个文件。
+    ///
+    /// # Errors
+    /// 读取文件失败或者有不合法的规则时返回错误信息，已有的用户保持不变。
+    pub(crate) fn load_file(&self, path: &Path) -> Result<(), String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("ERR Error loading ACL file {}: {}", path.display(), err))?;
+        let mut loaded = BTreeMap::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |msg: &str| format!("ERR {}:{}: {}", path.display(), i + 1, msg);
+            let mut parts = line.split_whitespace();
+            let (Some("user"), Some(name)) = (parts.next(), parts.next()) else {
+                return Err(err("line should start with user keyword"));
+            };
+            let mut user = User::new();
+            for rule in parts {
+                user.apply_rule(rule).map_err(|msg| err(&msg))?;
+            }
+            loaded.insert(name.to_string(), user);
+        }
+        // 文件中没有默认用户时，默认用户不需要密码并且拥有所有权限。
+        loaded
+            .entry(DEFAULT_USER.to_string())
+            .or_insert_with(User::default_user);
+
+        *self.users.lock().unwrap() = loaded;
+        *self.file.lock().unwrap() = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    /// 重新加载 ACL 文件。
+    ///
+    /// # Errors
+    /// 没有配置 ACL 文件，或者加载失败时返回错误信息。
+    pub(crate) fn reload(&self) -> Result<(), String> {
+        let path = self.file.lock().unwrap().clone().ok_or(
+            "ERR This Redis instance is not configured to use an ACL file. \
+             You may want to specify users via the ACL SETUSER command and then issue a CONFIG REWRITE",
+        )?;
+        self.load_file(&path)
+    }
+}
+
+impl User {
+    /// 新用户：禁用，没有密码，不能执行任何命令，不能访问任何 key。
+    fn new() -> User {
+        User {
+            enabled: false,
+            nopass: false,
+            passwords: BTreeSet::new(),
+            keys: vec![],
+            commands: vec![],
+        }
+    }
+
+    /// 默认用户：启用，不需要密码，拥有所有权限。
+    fn default_user() -> User {
+        User {
+            enabled: true,
+            nopass: true,
+            passwords: BTreeSet::new(),
+            keys: vec!["*".to_string()],
+            commands: vec![CommandRule {
+                allow: true,
+                target: RuleTarget::All,
+            }],
+        }
+    }
+
+    /// 应用一条规则。
+    fn apply_rule(&mut self, rule: &str) -> Result<(), String> {
+        let lower = rule.to_lowercase();
+        match &lower[..] {
+            "on" => self.enabled = true,
+            "off" => self.enabled = false,
+            "nopass" => {
+                self.nopass = true;
+                self.passwords.clear();
+            }
+            "resetpass" => {
+                self.nopass = false;
+                self.passwords.clear();
+            }
+            "allkeys" => self.keys = vec!["*".to_string()],
+            "resetkeys" => self.keys.clear(),
+            "allcommands" => self.set_all_commands(true),
+            "nocommands" => self.set_all_commands(false),
+            "reset" => *self = User::new(),
+            _ => {
+                if let Some(password) = rule.strip_prefix('>') {
+                    self.nopass = false;
+                    self.passwords.insert(hash(password));
+                } else if let Some(password) = rule.strip_prefix('<') {
+                    if !self.passwords.remove(&hash(password)) {
+                        return Err(
+                            "ERR Error in ACL SETUSER modifier '<...>': no such password"
+                                .to_string(),
+                        );
+                    }
+                } else if let Some(digest) = rule.strip_prefix('#') {
+                    if digest.len() != 40 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+                        return Err(
+                            "ERR Error in ACL SETUSER modifier '#...': invalid password hash"
+                                .to_string(),
+                        );
+                    }
+                    self.nopass = false;
+                    self.passwords.insert(digest.to_lowercase());
+                } else if let Some(pattern) = rule.strip_prefix('~') {
+                    self.keys.push(pattern.to_string());
+                } else if let Some(target) = lower.strip_prefix('+') {
+                    self.add_command_rule(true, target)?;
+                } else if let Some(target) = lower.strip_prefix('-') {
+                    self.add_command_rule(false, target)?;
+                } else {
+                    return Err(format!(
+                        "ERR Error in ACL SETUSER modifier '{}': Syntax error",
+                        rule
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 允许或禁止所有命令，之前的命令规则都被覆盖，所以直接清除。
+    fn set_all_commands(&mut self, allow: bool) {
+        self.commands.clear();
+        if allow {
+            self.commands.push(CommandRule {
+                allow,
+                target: RuleTarget::All,
+            });
+        }
+    }
+
+    /// 添加`+<target>`或`-<target>`规则。
+    fn add_command_rule(&mut self, allow: bool, target: &str) -> Result<(), String> {
+        let unknown = || "ERR Unknown command or category name in ACL".to_string();
+        let target = match target.strip_prefix('@') {
+            Some("all") => {
+                self.set_all_commands(allow);
+                return Ok(());
+            }
+            Some(category) if is_category(category) => RuleTarget::Category(category.to_string()),
+            Some(_) => return Err(unknown()),
+            None if CommandSpec::lookup(target).is_some() => {
+                RuleTarget::Command(target.to_string())
+            }
+            None => return Err(unknown()),
+        };
+        let rule = CommandRule { allow, target };
+        // 相同的规则只保留最后一条。
+        self.commands.retain(|r| r.target != rule.target);
+        self.commands.push(rule);
+        Ok(())
+    }
+
+    /// 判断是否可以执行命令，后面的规则优先，没有匹配的规则时禁止。
+    fn can_run(&self, name: &str) -> bool {
+        let spec = CommandSpec::lookup(name);
+        self.commands
+            .iter()
+            .rev()
+            .find(|rule| match &rule.target {
+                RuleTarget::All => true,
+                RuleTarget::Command(command) => command == name,
+                RuleTarget::Category(category) => {
+                    spec.is_some_and(|spec| in_category(spec, category))
+                }
+            })
+            .is_some_and(|rule| rule.allow)
+    }
+
+    /// 判断是否可以访问 key。
+    fn can_access(&self, key: &str) -> bool {
+        self.keys
+            .iter()
+            .any(|pattern| glob_match(pattern.as_bytes(), key.as_bytes()))
+    }
+
+    /// 用户的标志：`on`或`off`，以及`nopass`。
+    pub(crate) fn flags(&self) -> Vec<&'static str> {
+        let mut flags = vec![if self.enabled { "on" } else { "off" }];
+        if self.nopass {
+            flags.push("nopass");
+        }
+        flags
+    }
+
+    /// 密码的 SHA1 摘要。
+    pub(crate) fn passwords(&self) -> impl Iterator<Item = &str> {
+        self.passwords.iter().map(String::as_str)
+    }
+
+    /// 命令规则，例如`+@all -set`，没有规则时为`-@all`。
+    pub(crate) fn command_rules(&self) -> String {
+        if self.commands.is_empty() {
+            return "-@all".to_string();
+        }
+        self.commands
+            .iter()
+            .map(|rule| {
+                let sign = if rule.allow { '+' } else { '-' };
+                match &rule.target {
+                    RuleTarget::All => format!("{}@all", sign),
+                    RuleTarget::Category(category) => format!("{}@{}", sign, category),
+                    RuleTarget::Command(command) => format!("{}{}", sign, command),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// key 的规则，例如`~user:* ~cache:*`。
+    pub(crate) fn key_rules(&self) -> String {
+        self.keys
+            .iter()
+            .map(|pattern| format!("~{}", pattern))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// 用户的所有规则，重新应用这些规则可以得到相同的用户。
+    fn describe(&self) -> String {
+        let mut rules = self
+            .flags()
+            .iter()
+            .map(|flag| flag.to_string())
+            .collect::<Vec<_>>();
+        rules.extend(self.passwords().map(|digest| format!("#{}", digest)));
+        rules.extend(self.keys.iter().map(|pattern| format!("~{}", pattern)));
+        rules.push(self.command_rules());
+        rules.join(" ")
+    }
+}
+
+/// 判断是否存在类别`category`。
+fn is_category(category: &str) -> bool {
+    category == "slow"
+        || CATEGORIES.iter().any(|(c, _)| *c == category)
+        || CommandSpec::all().iter().any(|spec| spec.group == category)
+}
+
+/// 判断命令是否属于类别。
+fn in_category(spec: &CommandSpec, category: &str) -> bool {
+    if category == "slow" {
+        return !spec.has_flag("fast");
+    }
+    spec.group == category
+        || CATEGORIES
+            .iter()
+            .any(|(c, flag)| *c == category && spec.has_flag(flag))
+}
+
+/// 获取命令所属的所有类别，例如`@read`、`@string`、`@fast`。
+pub(crate) fn categories(spec: &CommandSpec) -> Vec<String> {
+    let mut categories: Vec<String> = CATEGORIES
+        .iter()
+        .filter(|(_, flag)| spec.has_flag(flag))
+        .map(|(category, _)| format!("@{}", category))
+        .collect();
+    if !spec.has_flag("fast") {
+        categories.push("@slow".to_string());
+    }
+    categories.push(format!("@{}", spec.group));
+    categories
+}
+
+/// 密码的 SHA1 摘要。
+fn hash(password: &str) -> String {
+    sha1_smol::Sha1::from(password).digest().to_string()
+}
@@ -1,8 +1,31 @@
-use bytes::Bytes;
-use clap::{Parser, Subcommand};
-use my_redis::{client::Client, DEFAULT_PORT};
-use std::{convert::Infallible, num::ParseIntError, str, time::Duration};
-use tokio::signal;
+use bytes::{Buf, Bytes, BytesMut};
+use clap::{Parser, Subcommand, ValueEnum};
+use my_redis::{
+    client::{
+        Builder, Client, GeoLocation, Message, Monitored, Redirect, SlowlogEntry, StreamEntry,
+        Subscriber,
+    },
+    cmd::{GeoOrigin, GeoSearch, GeoShape, GeoUnit},
+    tls::ClientTls,
+    Error, Frame, DEFAULT_PORT,
+};
+use std::{
+    collections::HashSet,
+    convert::Infallible,
+    io::{IsTerminal, Write},
+    num::{ParseFloatError, ParseIntError},
+    ops::Bound,
+    path::PathBuf,
+    process::ExitCode,
+    str,
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    fs,
+    io::{AsyncBufReadExt, AsyncReadExt, BufReader},
+    signal,
+};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -12,32 +35,116 @@ use tokio::signal;
     about = "一个自实现的Redis客户端"
 )]
 struct Args {
+    // 没有子命令时，从`--eval-file`或者标准输入中批量读取命令执行，
+    // 标准输入是终端时进入交互模式。
     #[command(subcommand)]
-    command: Command,
+    command: Option<Command>,
     #[arg(name = "hostname", long, default_value = "127.0.0.1")]
     // default_value 接受一个参数 default，类型为 &str
     host: String,
     #[arg(long, default_value_t = DEFAULT_PORT)]
     // default_value_t 类似，参数类型为 &str，但是他会尝试转换为指定类型
     port: u16,
+    // 认证使用的用户名，必须与`--pass`一起使用。
+    #[arg(long, requires = "pass")]
+    user: Option<String>,
+    // 认证使用的密码。
+    #[arg(long)]
+    pass: Option<String>,
+    // 使用 TLS 连接服务器。
+    #[arg(long)]
+    tls: bool,
+    // 用于验证服务器证书的 CA 证书（PEM 格式），设置后自动开启 TLS。
+    #[arg(long)]
+    cacert: Option<PathBuf>,
+    // 双向认证时提供给服务器的客户端证书（PEM 格式），设置后自动开启 TLS。
+    #[arg(long, requires = "key")]
+    cert: Option<PathBuf>,
+    // 客户端证书的私钥（PEM 格式）。
+    #[arg(long, requires = "cert")]
+    key: Option<PathBuf>,
+    // SNI 以及验证服务器证书时使用的名称，默认为`--hostname`，设置后自动开启 TLS。
+    #[arg(long)]
+    sni: Option<String>,
+    // 连接 URL，格式为`redis://[[user]:pass@]host[:port][/db]`，`rediss://`开启 TLS。
+    // 设置后忽略`--hostname`和`--port`，`--user`、`--pass`会覆盖 URL 中的用户名和密码。
+    #[arg(short = 'u', long, conflicts_with_all = ["hostname", "port"])]
+    uri: Option<String>,
+    // 通过 Unix socket 连接服务器，设置后忽略`--hostname`和`--port`。
+    #[arg(short = 's', long)]
+    unixsocket: Option<PathBuf>,
+    // 集群模式，自动跟随服务器返回的`MOVED`/`ASK`重定向。
+    #[arg(short = 'c')]
+    cluster: bool,
+    // 从文件中逐行读取命令并执行，遇到第一个错误就退出。
+    #[arg(long)]
+    eval_file: Option<PathBuf>,
+    // 从标准输入读取命令或者 RESP 数据，流水线发送，最后报告成功和失败的数量，用于批量导入数据。
+    #[arg(long, conflicts_with = "eval_file")]
+    pipe: bool,
+    // 以 JSON 格式输出响应，便于脚本处理。
+    #[arg(long, conflicts_with_all = ["raw", "csv"])]
+    json: bool,
+    // 原样输出响应的内容，不加引号和类型标记，适合二进制 value。
+    #[arg(long, conflicts_with = "csv")]
+    raw: bool,
+    // 以 CSV 格式输出响应，数组的元素以逗号分隔。
+    #[arg(long)]
+    csv: bool,
+}
+
+/// 响应的输出格式，由`--json`、`--raw`和`--csv`选择。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum OutputFormat {
+    /// redis-cli 的风格，带有类型标记，数组的元素带有编号。
+    #[default]
+    Cli,
+    /// 每个响应是一个 JSON 值。
+    Json,
+    /// 原样输出内容，数组的每个元素一行。
+    Raw,
+    /// 数组展开后的所有元素以逗号分隔输出在一行。
+    Csv,
 }
 
-#[derive(Subcommand, Debug)]
+/// 打印响应使用的格式，在`main()`中根据命令行参数设置。
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// `--pipe`模式下每批流水线发送的命令数量。
+const PIPE_BATCH_SIZE: usize = 1000;
+
+/// 集群模式下最多跟随的重定向次数。
+const MAX_REDIRECTS: usize = 16;
+
+#[derive(Subcommand, Debug, Clone)]
 enum Command {
     Get {
         key: String,
+        // 将原始字节写入文件，而不是打印到终端，避免二进制数据被终端编码破坏。
+        #[arg(long)]
+        output: Option<PathBuf>,
     },
     Set {
         key: String,
         // clap 从命令行自动获取的`&str`无法自动转换为`Bytes`，
         // 所以我们要提供一个解析器。
-        #[arg(value_parser = bytes_from_str)]
-        value: Bytes,
+        // 如果设置了`--value-file`，那么就不需要这个参数。
+        #[arg(value_parser = bytes_from_str, required_unless_present = "value_file")]
+        value: Option<Bytes>,
         // 同理提供一个解析器。
         // 如果这个字段没有设置，就为`None`,
         // 否则就调用解析器。
-        #[arg(value_parser = duration_from_ms_str)]
+        #[arg(value_parser = duration_from_ms_str, requires = "value")]
         expires: Option<Duration>,
+        // 从文件中读取原始字节作为 value。
+        #[arg(long, conflicts_with = "value")]
+        value_file: Option<PathBuf>,
+        // 保留原有的过期时间。
+        #[arg(long, conflicts_with = "expires")]
+        keepttl: bool,
+        // 打印原有的值。
+        #[arg(long, conflicts_with_all = ["expires", "keepttl"])]
+        get: bool,
     },
     Publish {
         channel: String,
@@ -48,10 +155,505 @@ enum Command {
         // clap 可以自动收集参数并构造成`Vec`。
         channels: Vec<String>,
     },
+    Psubscribe {
+        // glob 模式，例如`news.*`。
+        patterns: Vec<String>,
+    },
+    Spublish {
+        channel: String,
+        #[arg(value_parser = bytes_from_str)]
+        message: Bytes,
+    },
+    Ssubscribe {
+        #[arg(required = true)]
+        channels: Vec<String>,
+    },
     Ping {
         #[arg(value_parser = bytes_from_str)]
         msg: Option<Bytes>,
     },
+    Hset {
+        key: String,
+        // field 和 value 交替出现：field value [field value ...]。
+        #[arg(value_parser = bytes_from_str, required = true)]
+        field_values: Vec<Bytes>,
+    },
+    Hget {
+        key: String,
+        #[arg(value_parser = bytes_from_str)]
+        field: Bytes,
+    },
+    Hdel {
+        key: String,
+        #[arg(value_parser = bytes_from_str, required = true)]
+        fields: Vec<Bytes>,
+    },
+    Hgetall {
+        key: String,
+    },
+    Hrandfield {
+        key: String,
+        // 为负数时同一个 field 可能出现多次。
+        #[arg(allow_negative_numbers = true)]
+        count: Option<i64>,
+        #[arg(long, requires = "count")]
+        withvalues: bool,
+    },
+    Lpush {
+        key: String,
+        #[arg(value_parser = bytes_from_str, required = true)]
+        values: Vec<Bytes>,
+    },
+    Rpush {
+        key: String,
+        #[arg(value_parser = bytes_from_str, required = true)]
+        values: Vec<Bytes>,
+    },
+    Lpop {
+        key: String,
+    },
+    Rpop {
+        key: String,
+    },
+    Lrange {
+        key: String,
+        // 允许负数下标，例如`lrange list 0 -1`。
+        #[arg(allow_negative_numbers = true)]
+        start: i64,
+        #[arg(allow_negative_numbers = true)]
+        stop: i64,
+    },
+    Llen {
+        key: String,
+    },
+    Lpos {
+        key: String,
+        #[arg(value_parser = bytes_from_str)]
+        element: Bytes,
+        // 为负数时从尾部开始查找。
+        #[arg(long, default_value_t = 1, allow_negative_numbers = true)]
+        rank: i64,
+        // 返回最多`count`个下标，为`0`时返回所有的。
+        #[arg(long)]
+        count: Option<u64>,
+        // 最多比较的元素个数，为`0`时不限制。
+        #[arg(long, default_value_t = 0)]
+        maxlen: u64,
+    },
+    Linsert {
+        key: String,
+        #[arg(value_enum, ignore_case = true)]
+        position: InsertPosition,
+        #[arg(value_parser = bytes_from_str)]
+        pivot: Bytes,
+        #[arg(value_parser = bytes_from_str)]
+        element: Bytes,
+    },
+    Lset {
+        key: String,
+        #[arg(allow_negative_numbers = true)]
+        index: i64,
+        #[arg(value_parser = bytes_from_str)]
+        element: Bytes,
+    },
+    Lrem {
+        key: String,
+        // 为负数时从尾部开始删除，为`0`时删除所有的。
+        #[arg(allow_negative_numbers = true)]
+        count: i64,
+        #[arg(value_parser = bytes_from_str)]
+        element: Bytes,
+    },
+    Sadd {
+        key: String,
+        #[arg(value_parser = bytes_from_str, required = true)]
+        members: Vec<Bytes>,
+    },
+    Srem {
+        key: String,
+        #[arg(value_parser = bytes_from_str, required = true)]
+        members: Vec<Bytes>,
+    },
+    Smembers {
+        key: String,
+    },
+    Sismember {
+        key: String,
+        #[arg(value_parser = bytes_from_str)]
+        member: Bytes,
+    },
+    Scard {
+        key: String,
+    },
+    Srandmember {
+        key: String,
+        // 为负数时同一个成员可能出现多次。
+        #[arg(allow_negative_numbers = true)]
+        count: Option<i64>,
+    },
+    Sinter {
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+    Sunion {
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+    Sdiff {
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+    Sinterstore {
+        destination: String,
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+    Sunionstore {
+        destination: String,
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+    Sdiffstore {
+        destination: String,
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+    Zadd {
+        key: String,
+        // 分数和成员交替出现，分数可以是负数或者`-inf`。
+        #[arg(required = true, allow_hyphen_values = true)]
+        pairs: Vec<String>,
+    },
+    Zscore {
+        key: String,
+        #[arg(value_parser = bytes_from_str)]
+        member: Bytes,
+    },
+    Zrange {
+        key: String,
+        #[arg(allow_negative_numbers = true)]
+        start: i64,
+        #[arg(allow_negative_numbers = true)]
+        stop: i64,
+        #[arg(long)]
+        withscores: bool,
+    },
+    Zcard {
+        key: String,
+    },
+    Zrandmember {
+        key: String,
+        // 为负数时同一个成员可能出现多次。
+        #[arg(allow_negative_numbers = true)]
+        count: Option<i64>,
+        #[arg(long, requires = "count")]
+        withscores: bool,
+    },
+    Setbit {
+        key: String,
+        offset: u64,
+        // 只能是`0`或`1`。
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=1))]
+        value: u8,
+    },
+    Getbit {
+        key: String,
+        offset: u64,
+    },
+    Bitcount {
+        key: String,
+        // 以字节为单位的下标，可以是负数。
+        #[arg(allow_negative_numbers = true, requires = "end")]
+        start: Option<i64>,
+        #[arg(allow_negative_numbers = true)]
+        end: Option<i64>,
+    },
+    Xadd {
+        key: String,
+        // `*`表示自动生成 ID。
+        id: String,
+        // 字段和值交替出现。
+        #[arg(value_parser = bytes_from_str, required = true)]
+        pairs: Vec<Bytes>,
+    },
+    Xrange {
+        key: String,
+        // `-`表示最小的 ID。
+        #[arg(allow_hyphen_values = true)]
+        start: String,
+        // `+`表示最大的 ID。
+        end: String,
+        #[arg(long)]
+        count: Option<u64>,
+    },
+    Xlen {
+        key: String,
+    },
+    Xread {
+        #[arg(long)]
+        count: Option<u64>,
+        // 阻塞的毫秒数，为`0`时一直阻塞。
+        #[arg(long, value_parser = duration_from_ms_str)]
+        block: Option<Duration>,
+        // 先是所有的 key，然后是对应的 ID，`$`表示只读取之后添加的消息。
+        #[arg(required = true)]
+        streams: Vec<String>,
+    },
+    Xgroup {
+        #[command(subcommand)]
+        command: XgroupCommand,
+    },
+    Xreadgroup {
+        group: String,
+        consumer: String,
+        #[arg(long)]
+        count: Option<u64>,
+        // 阻塞的毫秒数，为`0`时一直阻塞。
+        #[arg(long, value_parser = duration_from_ms_str)]
+        block: Option<Duration>,
+        // 先是所有的 key，然后是对应的 ID，`>`表示读取新消息。
+        #[arg(required = true)]
+        streams: Vec<String>,
+    },
+    Xack {
+        key: String,
+        group: String,
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    Xclaim {
+        key: String,
+        group: String,
+        consumer: String,
+        // 最小空闲毫秒数。
+        #[arg(value_parser = duration_from_ms_str)]
+        min_idle: Duration,
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    Zrangebyscore {
+        key: String,
+        // 以`(`开头表示不包含边界，`-inf`和`+inf`表示没有边界。
+        #[arg(value_parser = score_bound_from_str, allow_hyphen_values = true)]
+        min: Bound<f64>,
+        #[arg(value_parser = score_bound_from_str, allow_hyphen_values = true)]
+        max: Bound<f64>,
+        #[arg(long)]
+        withscores: bool,
+    },
+    Zincrby {
+        key: String,
+        #[arg(allow_hyphen_values = true)]
+        increment: f64,
+        #[arg(value_parser = bytes_from_str)]
+        member: Bytes,
+    },
+    Zrem {
+        key: String,
+        #[arg(value_parser = bytes_from_str, required = true)]
+        members: Vec<Bytes>,
+    },
+    Zrank {
+        key: String,
+        #[arg(value_parser = bytes_from_str)]
+        member: Bytes,
+    },
+    Blpop {
+        #[arg(required = true)]
+        keys: Vec<String>,
+        // 超时时间，单位为秒，为`0`时一直阻塞。
+        #[arg(long, default_value_t = 0.0)]
+        timeout: f64,
+    },
+    Brpop {
+        #[arg(required = true)]
+        keys: Vec<String>,
+        // 超时时间，单位为秒，为`0`时一直阻塞。
+        #[arg(long, default_value_t = 0.0)]
+        timeout: f64,
+    },
+    Lmpop {
+        end: ListEnd,
+        #[arg(required = true)]
+        keys: Vec<String>,
+        #[arg(long, default_value_t = 1)]
+        count: u64,
+    },
+    Blmpop {
+        end: ListEnd,
+        #[arg(required = true)]
+        keys: Vec<String>,
+        #[arg(long, default_value_t = 1)]
+        count: u64,
+        // 超时时间，单位为秒，为`0`时一直阻塞。
+        #[arg(long, default_value_t = 0.0)]
+        timeout: f64,
+    },
+    Zmpop {
+        end: ScoreEnd,
+        #[arg(required = true)]
+        keys: Vec<String>,
+        #[arg(long, default_value_t = 1)]
+        count: u64,
+    },
+    Bzmpop {
+        end: ScoreEnd,
+        #[arg(required = true)]
+        keys: Vec<String>,
+        #[arg(long, default_value_t = 1)]
+        count: u64,
+        // 超时时间，单位为秒，为`0`时一直阻塞。
+        #[arg(long, default_value_t = 0.0)]
+        timeout: f64,
+    },
+    Geoadd {
+        key: String,
+        // 经度、纬度和成员依次出现。
+        #[arg(required = true, allow_hyphen_values = true)]
+        items: Vec<String>,
+    },
+    Geopos {
+        key: String,
+        #[arg(value_parser = bytes_from_str, required = true)]
+        members: Vec<Bytes>,
+    },
+    Geodist {
+        key: String,
+        #[arg(value_parser = bytes_from_str)]
+        member1: Bytes,
+        #[arg(value_parser = bytes_from_str)]
+        member2: Bytes,
+        #[arg(value_enum, default_value_t = DistUnit::M)]
+        unit: DistUnit,
+    },
+    Geosearch {
+        key: String,
+        // 以已有的成员为中心。
+        #[arg(long, value_parser = bytes_from_str, conflicts_with = "fromlonlat", required_unless_present = "fromlonlat")]
+        frommember: Option<Bytes>,
+        // 以指定的经度和纬度为中心。
+        #[arg(long, num_args = 2, allow_negative_numbers = true, value_names = ["LONGITUDE", "LATITUDE"])]
+        fromlonlat: Option<Vec<f64>>,
+        #[arg(long, conflicts_with = "bybox", required_unless_present = "bybox")]
+        byradius: Option<f64>,
+        #[arg(long, num_args = 2, value_names = ["WIDTH", "HEIGHT"])]
+        bybox: Option<Vec<f64>>,
+        #[arg(long, value_enum, default_value_t = DistUnit::M)]
+        unit: DistUnit,
+        #[arg(long, conflicts_with = "desc")]
+        asc: bool,
+        #[arg(long)]
+        desc: bool,
+        #[arg(long)]
+        count: Option<u64>,
+        #[arg(long)]
+        withcoord: bool,
+        #[arg(long)]
+        withdist: bool,
+        #[arg(long)]
+        withhash: bool,
+    },
+    // 将命令名称和参数原样发送给服务器，打印返回的响应，用于没有专门子命令的命令。
+    // 实时打印服务器执行的命令，直到按下 Ctrl-C。
+    Monitor,
+    Slowlog {
+        #[command(subcommand)]
+        command: SlowlogCommand,
+    },
+    Info {
+        // 只获取指定的部分，不指定时获取所有部分。
+        sections: Vec<String>,
+        // 每隔多少秒重新获取并打印一次，直到按下 Ctrl-C。
+        #[arg(short = 'i', long)]
+        interval: Option<f64>,
+    },
+    #[command(name = "command", visible_alias = "raw")]
+    Raw {
+        #[arg(
+            value_parser = bytes_from_str,
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true
+        )]
+        args: Vec<Bytes>,
+    },
+}
+
+// `XGroup`的子命令。
+#[derive(Subcommand, Debug, Clone)]
+enum XgroupCommand {
+    Create {
+        key: String,
+        group: String,
+        // `$`表示只读取之后添加的消息。
+        id: String,
+        // key 不存在时创建一个空的流。
+        #[arg(long)]
+        mkstream: bool,
+    },
+    Destroy {
+        key: String,
+        group: String,
+    },
+}
+
+// `Slowlog`的子命令。
+#[derive(Subcommand, Debug, Clone)]
+enum SlowlogCommand {
+    Get {
+        // 最多获取的记录条数，默认为 10 条。
+        count: Option<usize>,
+    },
+    Len,
+    Reset,
+}
+
+// `LInsert`插入的位置。
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum InsertPosition {
+    Before,
+    After,
+}
+
+// `LMPop`从列表的哪一端弹出元素。
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum ListEnd {
+    Left,
+    Right,
+}
+
+// `ZMPop`弹出分数最小还是最大的成员。
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum ScoreEnd {
+    Min,
+    Max,
+}
+
+// 地理位置命令中距离的单位。
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum DistUnit {
+    M,
+    Km,
+    Ft,
+    Mi,
+}
+
+impl From<DistUnit> for GeoUnit {
+    fn from(unit: DistUnit) -> GeoUnit {
+        match unit {
+            DistUnit::M => GeoUnit::Meters,
+            DistUnit::Km => GeoUnit::Kilometers,
+            DistUnit::Ft => GeoUnit::Feet,
+            DistUnit::Mi => GeoUnit::Miles,
+        }
+    }
+}
+
+#[test]
+fn verify_args() {
+    // clap 库提供的测试，可以帮助找出绝大部分的开发错误。
+    use clap::CommandFactory;
+    Args::command().debug_assert();
 }
 
 fn duration_from_ms_str(src: &str) -> Result<Duration, ParseIntError> {
@@ -64,47 +666,714 @@ fn bytes_from_str(src: &str) -> Result<Bytes, Infallible> {
     Ok(Bytes::from(src.to_string()))
 }
 
+/// 解析分数区间的边界，以`(`开头表示不包含边界。
+fn score_bound_from_str(src: &str) -> Result<Bound<f64>, ParseFloatError> {
+    match src.strip_prefix('(') {
+        Some(score) => Ok(Bound::Excluded(score.parse()?)),
+        None => Ok(Bound::Included(src.parse()?)),
+    }
+}
+
 #[tokio::main(flavor = "current_thread")]
-async fn main() -> my_redis::Result<()> {
-    // 获取命令行参数。
-    let args = Args::parse();
+async fn main() -> ExitCode {
+    // 与 redis-cli 一样，出错时将错误信息打印到标准错误输出，以非零状态码退出。
+    match run(Args::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// 根据命令行参数连接服务器并执行命令。
+async fn run(args: Args) -> my_redis::Result<()> {
+    let format = match (args.json, args.raw, args.csv) {
+        (true, _, _) => OutputFormat::Json,
+        (_, true, _) => OutputFormat::Raw,
+        (_, _, true) => OutputFormat::Csv,
+        _ => OutputFormat::Cli,
+    };
+    OUTPUT_FORMAT.get_or_init(|| format);
     let addr = format!("{}:{}", args.host, args.port);
-    // 连接服务端。
-    let mut client = Client::connect(&addr).await?;
-    // 执行命令，解析响应。
-    match args.command {
-        Command::Get { key } => {
+    // 根据命令行参数配置连接选项。
+    let mut builder = match &args.uri {
+        Some(uri) => Builder::from_url(uri)?,
+        None => Client::builder(&addr),
+    };
+    if let Some(pass) = args.pass {
+        builder = builder.auth(args.user, pass);
+    }
+    let tls = args.tls || args.cacert.is_some() || args.cert.is_some() || args.sni.is_some();
+    if tls {
+        let mut config = ClientTls::new();
+        if let Some(cacert) = args.cacert {
+            config = config.ca_cert(cacert);
+        }
+        if let (Some(cert), Some(key)) = (args.cert, args.key) {
+            config = config.client_cert(cert, key);
+        }
+        if let Some(sni) = args.sni {
+            config = config.server_name(sni);
+        }
+        builder = builder.tls_config(config);
+    }
+    // 交互模式的提示符中显示的服务器地址。
+    let prompt_addr = match &args.unixsocket {
+        Some(path) => path.display().to_string(),
+        None => builder.get_addr().to_string(),
+    };
+    if let Some(path) = args.unixsocket {
+        builder = builder.unix_socket(path);
+    }
+
+    let command = match args.command {
+        Some(_) if args.eval_file.is_some() => {
+            return Err("`--eval-file`不能与子命令一起使用".into());
+        }
+        Some(_) if args.pipe => {
+            return Err("`--pipe`不能与子命令一起使用".into());
+        }
+        Some(command) => command,
+        None if args.pipe => {
+            let client = builder.connect().await?;
+            return run_pipe(client).await;
+        }
+        None => {
+            // 没有子命令，批量执行文件或者标准输入中的命令。
+            let script = match args.eval_file {
+                Some(path) => fs::read_to_string(&path).await?,
+                None if !std::io::stdin().is_terminal() => {
+                    let mut script = String::new();
+                    tokio::io::stdin().read_to_string(&mut script).await?;
+                    script
+                }
+                // 标准输入是终端，进入交互模式。
+                None => return repl(builder, &prompt_addr).await,
+            };
+            let client = builder.connect().await?;
+            return run_script(client, &script).await;
+        }
+    };
+
+    if !args.cluster {
+        // 连接服务端，执行命令。
+        let client = builder.connect().await?;
+        return execute(client, command).await;
+    }
+
+    // 集群模式下，如果服务器返回了重定向错误，就连接到对应的节点重新执行命令。
+    let mut client = builder.connect().await?;
+    for _ in 0..MAX_REDIRECTS {
+        let err = match execute(client, command.clone()).await {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        let redirect = match Redirect::from_error(&err) {
+            Some(redirect) => redirect,
+            None => return Err(err),
+        };
+        println!(
+            "-> Redirected to slot [{}] located at {}",
+            redirect.slot(),
+            redirect.addr()
+        );
+        client = builder.clone().addr(redirect.addr()).connect().await?;
+        // `Ask`重定向只对下一条命令有效，需要先发送`Asking`。
+        if let Redirect::Ask { .. } = redirect {
+            client.asking().await?;
+        }
+    }
+
+    Err("重定向次数过多".into())
+}
+
+/// 使用`client`执行命令，解析并打印响应。
+async fn execute(mut client: Client, command: Command) -> my_redis::Result<()> {
+    match command {
+        Command::Get { key, output } => {
             if let Some(value) = client.get(&key).await? {
-                if let Ok(string) = str::from_utf8(&value) {
-                    println!("\"{}\"", string);
+                if let Some(path) = output {
+                    // 原样写入文件，不做任何编码转换。
+                    fs::write(&path, &value).await?;
+                    println!("已写入 {} 字节到 {}", value.len(), path.display());
                 } else {
-                    println!("{:?}", value);
+                    print_reply(&Frame::Bulk(value));
                 }
             } else {
                 // key不存在
-                println!("(nil)");
+                print_reply(&Frame::Null);
             }
         }
         Command::Set {
             key,
             value,
-            expires: None,
+            expires,
+            value_file,
+            keepttl,
+            get,
         } => {
-            client.set(&key, value).await?;
-            println!("OK")
+            // clap 保证了`value`和`--value-file`有且只有一个被设置。
+            let value = match value_file {
+                Some(path) => Bytes::from(fs::read(&path).await?),
+                None => value.unwrap(),
+            };
+            if get {
+                print_optional(client.set_get(&key, value).await?);
+            } else {
+                match expires {
+                    None if keepttl => client.set_keep_ttl(&key, value).await?,
+                    None => client.set(&key, value).await?,
+                    Some(expires) => client.set_expires(&key, value, expires).await?,
+                }
+                print_reply(&Frame::Simple("OK".to_string()));
+            }
         }
-        Command::Set {
+        Command::Ping { msg } => {
+            let msg = client.ping(msg).await?;
+            print_reply(&Frame::Bulk(msg));
+        }
+        Command::Hset { key, field_values } => {
+            if field_values.len() % 2 != 0 {
+                return Err("field 和 value 必须成对出现".into());
+            }
+            let fields = field_values
+                .chunks(2)
+                .map(|pair| (pair[0].clone(), pair[1].clone()))
+                .collect();
+            let added = client.hset(&key, fields).await?;
+            print_reply(&Frame::Integer(added as i64));
+        }
+        Command::Hget { key, field } => print_optional(client.hget(&key, field).await?),
+        Command::Hdel { key, fields } => {
+            let removed = client.hdel(&key, fields).await?;
+            print_reply(&Frame::Integer(removed as i64));
+        }
+        Command::Hgetall { key } => {
+            let items = client
+                .hgetall(&key)
+                .await?
+                .into_iter()
+                .flat_map(|(field, value)| [Frame::Bulk(field), Frame::Bulk(value)])
+                .collect();
+            print_reply(&Frame::Array(items));
+        }
+        Command::Hrandfield {
+            key, count: None, ..
+        } => print_optional(client.hrandfield(&key).await?),
+        Command::Hrandfield {
             key,
-            value,
-            expires: Some(expires),
+            count: Some(count),
+            withvalues,
+        } => {
+            let items = if withvalues {
+                client
+                    .hrandfield_withvalues(&key, count)
+                    .await?
+                    .into_iter()
+                    .flat_map(|(field, value)| [Frame::Bulk(field), Frame::Bulk(value)])
+                    .collect()
+            } else {
+                list_items(client.hrandfield_count(&key, count).await?)
+            };
+            print_reply(&Frame::Array(items));
+        }
+        Command::Lpush { key, values } => {
+            let len = client.lpush(&key, values).await?;
+            print_reply(&Frame::Integer(len as i64));
+        }
+        Command::Rpush { key, values } => {
+            let len = client.rpush(&key, values).await?;
+            print_reply(&Frame::Integer(len as i64));
+        }
+        Command::Lpop { key } => print_optional(client.lpop(&key).await?),
+        Command::Rpop { key } => print_optional(client.rpop(&key).await?),
+        Command::Lrange { key, start, stop } => {
+            let items = client
+                .lrange(&key, start, stop)
+                .await?
+                .into_iter()
+                .map(Frame::Bulk)
+                .collect();
+            print_reply(&Frame::Array(items));
+        }
+        Command::Llen { key } => {
+            let len = client.llen(&key).await?;
+            print_reply(&Frame::Integer(len as i64));
+        }
+        Command::Lpos {
+            key,
+            element,
+            rank,
+            count: Some(count),
+            maxlen,
+        } => {
+            let items = client
+                .lpos_count(&key, element, rank, count, maxlen)
+                .await?
+                .into_iter()
+                .map(|index| Frame::Integer(index as i64))
+                .collect();
+            print_reply(&Frame::Array(items));
+        }
+        Command::Lpos {
+            key,
+            element,
+            rank,
+            count: None,
+            maxlen,
+        } => match client.lpos(&key, element, rank, maxlen).await? {
+            Some(index) => print_reply(&Frame::Integer(index as i64)),
+            None => print_reply(&Frame::Null),
+        },
+        Command::Linsert {
+            key,
+            position,
+            pivot,
+            element,
+        } => {
+            let before = matches!(position, InsertPosition::Before);
+            let len = client.linsert(&key, before, pivot, element).await?;
+            print_reply(&Frame::Integer(len as i64));
+        }
+        Command::Lset {
+            key,
+            index,
+            element,
+        } => {
+            client.lset(&key, index, element).await?;
+            print_reply(&Frame::Simple("OK".to_string()));
+        }
+        Command::Lrem {
+            key,
+            count,
+            element,
+        } => {
+            let removed = client.lrem(&key, count, element).await?;
+            print_reply(&Frame::Integer(removed as i64));
+        }
+        Command::Sadd { key, members } => {
+            let added = client.sadd(&key, members).await?;
+            print_reply(&Frame::Integer(added as i64));
+        }
+        Command::Srem { key, members } => {
+            let removed = client.srem(&key, members).await?;
+            print_reply(&Frame::Integer(removed as i64));
+        }
+        Command::Smembers { key } => print_members(client.smembers(&key).await?),
+        Command::Sismember { key, member } => {
+            let is_member = client.sismember(&key, member).await?;
+            print_reply(&Frame::Integer(is_member as i64));
+        }
+        Command::Scard { key } => {
+            let len = client.scard(&key).await?;
+            print_reply(&Frame::Integer(len as i64));
+        }
+        Command::Srandmember { key, count: None } => {
+            print_optional(client.srandmember(&key).await?)
+        }
+        Command::Srandmember {
+            key,
+            count: Some(count),
+        } => {
+            let items = list_items(client.srandmember_count(&key, count).await?);
+            print_reply(&Frame::Array(items));
+        }
+        Command::Sinter { keys } => print_members(client.sinter(keys).await?),
+        Command::Sunion { keys } => print_members(client.sunion(keys).await?),
+        Command::Sdiff { keys } => print_members(client.sdiff(keys).await?),
+        Command::Sinterstore { destination, keys } => {
+            let len = client.sinterstore(&destination, keys).await?;
+            print_reply(&Frame::Integer(len as i64));
+        }
+        Command::Sunionstore { destination, keys } => {
+            let len = client.sunionstore(&destination, keys).await?;
+            print_reply(&Frame::Integer(len as i64));
+        }
+        Command::Sdiffstore { destination, keys } => {
+            let len = client.sdiffstore(&destination, keys).await?;
+            print_reply(&Frame::Integer(len as i64));
+        }
+        Command::Zadd { key, pairs } => {
+            let added = client.zadd(&key, score_pairs(pairs)?).await?;
+            print_reply(&Frame::Integer(added as i64));
+        }
+        Command::Zscore { key, member } => match client.zscore(&key, member).await? {
+            Some(score) => print_reply(&Frame::Bulk(Bytes::from(score.to_string()))),
+            None => print_reply(&Frame::Null),
+        },
+        Command::Zrange {
+            key,
+            start,
+            stop,
+            withscores,
+        } => {
+            let items = if withscores {
+                client
+                    .zrange_withscores(&key, start, stop)
+                    .await?
+                    .into_iter()
+                    .flat_map(|(member, score)| {
+                        [
+                            Frame::Bulk(member),
+                            Frame::Bulk(Bytes::from(score.to_string())),
+                        ]
+                    })
+                    .collect()
+            } else {
+                client
+                    .zrange(&key, start, stop)
+                    .await?
+                    .into_iter()
+                    .map(Frame::Bulk)
+                    .collect()
+            };
+            print_reply(&Frame::Array(items));
+        }
+        Command::Zcard { key } => {
+            let len = client.zcard(&key).await?;
+            print_reply(&Frame::Integer(len as i64));
+        }
+        Command::Zrandmember {
+            key, count: None, ..
+        } => print_optional(client.zrandmember(&key).await?),
+        Command::Zrandmember {
+            key,
+            count: Some(count),
+            withscores,
+        } => {
+            let items = if withscores {
+                client
+                    .zrandmember_withscores(&key, count)
+                    .await?
+                    .into_iter()
+                    .flat_map(|(member, score)| {
+                        [
+                            Frame::Bulk(member),
+                            Frame::Bulk(Bytes::from(score.to_string())),
+                        ]
+                    })
+                    .collect()
+            } else {
+                list_items(client.zrandmember_count(&key, count).await?)
+            };
+            print_reply(&Frame::Array(items));
+        }
+        Command::Setbit { key, offset, value } => {
+            let prev = client.setbit(&key, offset, value == 1).await?;
+            print_reply(&Frame::Integer(prev as i64));
+        }
+        Command::Getbit { key, offset } => {
+            let bit = client.getbit(&key, offset).await?;
+            print_reply(&Frame::Integer(bit as i64));
+        }
+        Command::Bitcount { key, start, end } => {
+            let count = client.bitcount(&key, start.zip(end)).await?;
+            print_reply(&Frame::Integer(count as i64));
+        }
+        Command::Xadd { key, id, pairs } => {
+            if !pairs.len().is_multiple_of(2) {
+                return Err("字段和值必须成对出现".into());
+            }
+            let fields = pairs
+                .chunks(2)
+                .map(|pair| (pair[0].clone(), pair[1].clone()))
+                .collect();
+            let id = client.xadd(&key, &id, fields).await?;
+            print_reply(&Frame::Bulk(Bytes::from(id)));
+        }
+        Command::Xrange {
+            key,
+            start,
+            end,
+            count,
+        } => {
+            let entries = client.xrange(&key, &start, &end, count).await?;
+            print_reply(&stream_entries_frame(entries));
+        }
+        Command::Xlen { key } => {
+            let len = client.xlen(&key).await?;
+            print_reply(&Frame::Integer(len as i64));
+        }
+        Command::Xread {
+            count,
+            block,
+            mut streams,
+        } => {
+            if !streams.len().is_multiple_of(2) {
+                return Err("每个 key 都必须指定一个 ID".into());
+            }
+            let ids = streams.split_off(streams.len() / 2);
+            let streams = streams.into_iter().zip(ids).collect();
+            let result = client.xread(streams, count, block).await?;
+            print_streams(result);
+        }
+        Command::Xgroup {
+            command:
+                XgroupCommand::Create {
+                    key,
+                    group,
+                    id,
+                    mkstream,
+                },
+        } => {
+            client.xgroup_create(&key, &group, &id, mkstream).await?;
+            print_reply(&Frame::Simple("OK".to_string()));
+        }
+        Command::Xgroup {
+            command: XgroupCommand::Destroy { key, group },
+        } => {
+            let destroyed = client.xgroup_destroy(&key, &group).await?;
+            print_reply(&Frame::Integer(destroyed as i64));
+        }
+        Command::Xreadgroup {
+            group,
+            consumer,
+            count,
+            block,
+            mut streams,
         } => {
-            client.set_expires(&key, value, expires).await?;
+            if !streams.len().is_multiple_of(2) {
+                return Err("每个 key 都必须指定一个 ID".into());
+            }
+            let ids = streams.split_off(streams.len() / 2);
+            let streams = streams.into_iter().zip(ids).collect();
+            let result = client
+                .xreadgroup(&group, &consumer, streams, count, block)
+                .await?;
+            print_streams(result);
+        }
+        Command::Xack { key, group, ids } => {
+            let acked = client.xack(&key, &group, ids).await?;
+            print_reply(&Frame::Integer(acked as i64));
+        }
+        Command::Xclaim {
+            key,
+            group,
+            consumer,
+            min_idle,
+            ids,
+        } => {
+            let entries = client
+                .xclaim(&key, &group, &consumer, min_idle, ids)
+                .await?;
+            print_reply(&stream_entries_frame(entries));
+        }
+        Command::Zrangebyscore {
+            key,
+            min,
+            max,
+            withscores,
+        } => {
+            let items = if withscores {
+                client
+                    .zrangebyscore_withscores(&key, min, max)
+                    .await?
+                    .into_iter()
+                    .flat_map(|(member, score)| {
+                        [
+                            Frame::Bulk(member),
+                            Frame::Bulk(Bytes::from(score.to_string())),
+                        ]
+                    })
+                    .collect()
+            } else {
+                client
+                    .zrangebyscore(&key, min, max)
+                    .await?
+                    .into_iter()
+                    .map(Frame::Bulk)
+                    .collect()
+            };
+            print_reply(&Frame::Array(items));
+        }
+        Command::Zincrby {
+            key,
+            increment,
+            member,
+        } => {
+            let score = client.zincrby(&key, increment, member).await?;
+            print_reply(&Frame::Bulk(Bytes::from(score.to_string())));
+        }
+        Command::Zrem { key, members } => {
+            let removed = client.zrem(&key, members).await?;
+            print_reply(&Frame::Integer(removed as i64));
+        }
+        Command::Zrank { key, member } => match client.zrank(&key, member).await? {
+            Some(rank) => print_reply(&Frame::Integer(rank as i64)),
+            None => print_reply(&Frame::Null),
+        },
+        Command::Blpop { keys, timeout } => {
+            let popped = client.blpop(keys, timeout_from_secs(timeout)?).await?;
+            print_popped(popped);
+        }
+        Command::Brpop { keys, timeout } => {
+            let popped = client.brpop(keys, timeout_from_secs(timeout)?).await?;
+            print_popped(popped);
+        }
+        Command::Lmpop { end, keys, count } => {
+            let front = matches!(end, ListEnd::Left);
+            let popped = client.lmpop(keys, front, count).await?;
+            print_mpopped(popped.map(|(key, values)| (key, list_items(values))));
+        }
+        Command::Blmpop {
+            end,
+            keys,
+            count,
+            timeout,
+        } => {
+            let front = matches!(end, ListEnd::Left);
+            let popped = client
+                .blmpop(keys, front, count, timeout_from_secs(timeout)?)
+                .await?;
+            print_mpopped(popped.map(|(key, values)| (key, list_items(values))));
+        }
+        Command::Zmpop { end, keys, count } => {
+            let min = matches!(end, ScoreEnd::Min);
+            let popped = client.zmpop(keys, min, count).await?;
+            print_mpopped(popped.map(|(key, members)| (key, zset_items(members))));
+        }
+        Command::Bzmpop {
+            end,
+            keys,
+            count,
+            timeout,
+        } => {
+            let min = matches!(end, ScoreEnd::Min);
+            let popped = client
+                .bzmpop(keys, min, count, timeout_from_secs(timeout)?)
+                .await?;
+            print_mpopped(popped.map(|(key, members)| (key, zset_items(members))));
+        }
+        Command::Geoadd { key, items } => {
+            let added = client.geoadd(&key, geo_members(items)?).await?;
+            print_reply(&Frame::Integer(added as i64));
+        }
+        Command::Geopos { key, members } => {
+            let items = client
+                .geopos(&key, members)
+                .await?
+                .into_iter()
+                .map(|pos| match pos {
+                    Some(pos) => coord_frame(pos),
+                    None => Frame::Null,
+                })
+                .collect();
+            print_reply(&Frame::Array(items));
+        }
+        Command::Geodist {
+            key,
+            member1,
+            member2,
+            unit,
+        } => match client.geodist(&key, member1, member2, unit.into()).await? {
+            Some(dist) => print_reply(&Frame::Bulk(Bytes::from(format!("{:.4}", dist)))),
+            None => print_reply(&Frame::Null),
+        },
+        Command::Geosearch {
+            key,
+            frommember,
+            fromlonlat,
+            byradius,
+            bybox,
+            unit,
+            asc,
+            desc,
+            count,
+            withcoord,
+            withdist,
+            withhash,
+        } => {
+            let origin = match (frommember, fromlonlat) {
+                (Some(member), _) => GeoOrigin::Member(member),
+                (None, Some(lonlat)) => GeoOrigin::LonLat(lonlat[0], lonlat[1]),
+                (None, None) => unreachable!(),
+            };
+            let shape = match (byradius, bybox) {
+                (Some(radius), _) => GeoShape::Radius(radius, unit.into()),
+                (None, Some(size)) => GeoShape::Box(size[0], size[1], unit.into()),
+                (None, None) => unreachable!(),
+            };
+            let mut cmd = GeoSearch::new(&key, origin, shape);
+            if asc || desc {
+                cmd = cmd.sort(asc);
+            }
+            if let Some(count) = count {
+                cmd = cmd.count(count);
+            }
+            if withcoord {
+                cmd = cmd.with_coord();
+            }
+            if withdist {
+                cmd = cmd.with_dist();
+            }
+            if withhash {
+                cmd = cmd.with_hash();
+            }
+            let items = client
+                .geosearch(cmd)
+                .await?
+                .into_iter()
+                .map(geo_location_frame)
+                .collect();
+            print_reply(&Frame::Array(items));
+        }
+        Command::Monitor => {
+            let monitored = client.monitor().await?;
             println!("OK");
+            return receive_commands(monitored).await;
         }
-        Command::Ping { msg } => {
-            let msg = client.ping(msg).await?;
-            println!("{:?}", msg);
+        Command::Slowlog {
+            command: SlowlogCommand::Get { count },
+        } => {
+            let entries = client.slowlog_get(count).await?;
+            print_reply(&slowlog_frame(entries));
+        }
+        Command::Slowlog {
+            command: SlowlogCommand::Len,
+        } => {
+            let len = client.slowlog_len().await?;
+            print_reply(&Frame::Integer(len as i64));
+        }
+        Command::Slowlog {
+            command: SlowlogCommand::Reset,
+        } => {
+            client.slowlog_reset().await?;
+            print_reply(&Frame::Simple("OK".to_string()));
+        }
+        Command::Info { sections, interval } => {
+            let sections: Vec<&str> = sections.iter().map(String::as_str).collect();
+            let interval = match interval {
+                Some(secs) => Some(
+                    Duration::try_from_secs_f64(secs)
+                        .ok()
+                        .filter(|interval| !interval.is_zero())
+                        .ok_or("刷新间隔必须是正数")?,
+                ),
+                None => None,
+            };
+            loop {
+                let info = client.info(&sections).await?;
+                print_reply(&Frame::Verbatim("txt".to_string(), Bytes::from(info)));
+                let Some(interval) = interval else {
+                    break;
+                };
+                tokio::select! {
+                    _ = signal::ctrl_c() => break,
+                    _ = tokio::time::sleep(interval) => println!(),
+                }
+            }
         }
+        Command::Raw { args } => match client.call(args).await {
+            Ok(frame) => print_reply(&frame),
+            // 与其他响应一样打印服务器返回的错误，集群的重定向错误交给调用者处理。
+            Err(err @ (Error::Server(_) | Error::Auth(_) | Error::WrongType))
+                if Redirect::from_error(&err).is_none() =>
+            {
+                print_reply(&Frame::Error(err.to_string()))
+            }
+            Err(err) => return Err(err),
+        },
         Command::Publish { channel, message } => {
             client.publish(&channel, message).await?;
             println!("Publish OK");
@@ -113,44 +1382,774 @@ async fn main() -> my_redis::Result<()> {
             if channels.is_empty() {
                 return Err("必须指定至少一个广播信道".into());
             }
-            let mut subscriber = client.subscribe(channels).await?;
-            // 一旦客户端进入`Subscribe`后，他会一直循环等待信息，
-            // 我们应该同时开启一个异步任务，监听客户端的关闭信号。
-            // 当关闭信号来临时，停止接收信息，关闭客户端。
-            //
-            // 但与此同时，服务端的这个连接的`Handler`的`run()`并不会结束，
-            // 因为`cmd.apply()`并没有结束。
-            // 究其原因是`Subscriber`的`apply()`没有执行完成，`StreamMap`中的
-            // 所有异步流都处于`tx.recv()`等待中。
-            //
-            // 所以客户端在关闭信号到来的时候，应该发送一个消息给服务端，
-            // 告诉`Subscriber`的`apply()`你应该结束了。
-            loop {
-                tokio::select! {
-                    _ = signal::ctrl_c() => {
-                        // 客户端发送一个信号帧，告诉服务器客户端关闭了。
-                        subscriber.send_ctrlc_frame().await?;
-                        return Ok(())
-                    }
+            let subscriber = client.subscribe(channels).await?;
+            return receive_messages(subscriber).await;
+        }
+        Command::Psubscribe { patterns } => {
+            if patterns.is_empty() {
+                return Err("必须指定至少一个模式".into());
+            }
+            let subscriber = client.psubscribe(patterns).await?;
+            return receive_messages(subscriber).await;
+        }
+        Command::Spublish { channel, message } => {
+            client.spublish(&channel, message).await?;
+            println!("Publish OK");
+        }
+        Command::Ssubscribe { channels } => {
+            let subscriber = client.ssubscribe(channels).await?;
+            return receive_messages(subscriber).await;
+        }
+    }
+
+    Ok(())
+}
 
-                    res = subscriber.next_message() => {
-                        match res {
-                            Ok(Some(msg)) => {
-                                println!("从信道“{}”中获取到信息：{:?}", msg.channel, msg.content);
-                            },
-                            // 服务端关闭了。
-                            Ok(None) => {
-                                println!("服务器已关闭");
-                                return Ok(());
-                            },
-                            // 出错。
-                            Err(err) => return Err(err),
-                        }
+/// 逐行执行脚本中的命令，并打印每一行的结果。
+///
+/// 空行和以`#`开头的注释行会被忽略。遇到第一个错误时停止执行并返回`Err`，
+/// 这样进程会以非零状态码退出。
+async fn run_script(mut client: Client, script: &str) -> my_redis::Result<()> {
+    for (lineno, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let args = split_args(line).map_err(|err| format!("第 {} 行：{}", lineno + 1, err))?;
+        match client.call(args).await {
+            Ok(frame) => print_reply(&frame),
+            Err(err) => return Err(format!("第 {} 行：(error) {}", lineno + 1, err).into()),
+        }
+    }
+    Ok(())
+}
+
+/// `--pipe`模式，从标准输入读取命令，分批流水线发送，最后打印成功和失败的响应数量。
+///
+/// 输入以`*`开头时按照 RESP 解析，否则每行是一条命令，格式与`--eval-file`相同。
+/// 有命令执行失败时返回`Err`，这样进程会以非零状态码退出。
+async fn run_pipe(mut client: Client) -> my_redis::Result<()> {
+    let mut stdin = BufReader::new(tokio::io::stdin());
+    let raw = stdin.fill_buf().await?.first() == Some(&b'*');
+    let mut buf = BytesMut::new();
+    let mut lineno = 0;
+    let mut batch = Vec::with_capacity(PIPE_BATCH_SIZE);
+    let (mut succeeded, mut failed) = (0u64, 0u64);
+    loop {
+        let frame = match raw {
+            true => next_raw_frame(&mut stdin, &mut buf).await?,
+            false => next_line_frame(&mut stdin, &mut lineno).await?,
+        };
+        let done = frame.is_none();
+        batch.extend(frame);
+        if batch.len() == PIPE_BATCH_SIZE || (done && !batch.is_empty()) {
+            for reply in client.pipeline(&batch).await? {
+                match reply {
+                    Frame::Error(msg) => {
+                        failed += 1;
+                        eprintln!("(error) {}", msg);
                     }
+                    _ => succeeded += 1,
                 }
             }
+            batch.clear();
+        }
+        if done {
+            break;
         }
     }
-
+    println!(
+        "All data transferred. replies: {}, succeeded: {}, failed: {}",
+        succeeded + failed,
+        succeeded,
+        failed
+    );
+    if failed > 0 {
+        return Err(format!("{} 条命令执行失败", failed).into());
+    }
     Ok(())
 }
+
+/// 从标准输入读取下一个 RESP 格式的`Frame`，输入结束时返回`None`。
+async fn next_raw_frame(
+    stdin: &mut BufReader<tokio::io::Stdin>,
+    buf: &mut BytesMut,
+) -> my_redis::Result<Option<Frame>> {
+    loop {
+        // 忽略`Frame`之间的空白，例如文件末尾的换行。
+        while buf.first().is_some_and(u8::is_ascii_whitespace) {
+            buf.advance(1);
+        }
+        if let Some(frame) = Frame::decode(buf)? {
+            return Ok(Some(frame));
+        }
+        if stdin.read_buf(buf).await? == 0 {
+            if buf.is_empty() {
+                return Ok(None);
+            }
+            return Err("标准输入中的 RESP 数据不完整".into());
+        }
+    }
+}
+
+/// 从标准输入读取下一行命令并转换为`Frame`，跳过空行和注释，输入结束时返回`None`。
+async fn next_line_frame(
+    stdin: &mut BufReader<tokio::io::Stdin>,
+    lineno: &mut usize,
+) -> my_redis::Result<Option<Frame>> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if stdin.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        *lineno += 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let args = split_args(line).map_err(|err| format!("第 {} 行：{}", lineno, err))?;
+        return Ok(Some(Frame::Array(
+            args.into_iter().map(Frame::Bulk).collect(),
+        )));
+    }
+}
+
+/// 交互模式，逐行读取命令并在同一个连接上执行，按照 redis-cli 的风格打印响应。
+///
+/// 输入`quit`或`exit`，或者标准输入结束时退出。连接断开后提示符变为`not connected>`，
+/// 下一条命令会重新连接。订阅命令会一直接收信息，直到按下 Ctrl-C。
+async fn repl(builder: Builder, addr: &str) -> my_redis::Result<()> {
+    let mut client = Some(builder.clone().connect().await?);
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        match client {
+            Some(_) => print!("{}> ", addr),
+            None => print!("not connected> "),
+        }
+        std::io::stdout().flush()?;
+        let Some(line) = lines.next_line().await? else {
+            println!();
+            return Ok(());
+        };
+        let args = match split_args(&line) {
+            Ok(args) if args.is_empty() => continue,
+            Ok(args) => args,
+            Err(err) => {
+                println!("(error) {}", err);
+                continue;
+            }
+        };
+        let name = String::from_utf8_lossy(&args[0]).to_lowercase();
+        if name == "quit" || name == "exit" {
+            return Ok(());
+        }
+
+        let mut conn = match client.take() {
+            Some(conn) => conn,
+            None => match builder.clone().connect().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    println!("Error: {}", err);
+                    continue;
+                }
+            },
+        };
+        if matches!(&name[..], "subscribe" | "psubscribe" | "ssubscribe") && args.len() > 1 {
+            let names = args[1..]
+                .iter()
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect();
+            let subscriber = match &name[..] {
+                "subscribe" => conn.subscribe(names).await?,
+                "psubscribe" => conn.psubscribe(names).await?,
+                _ => conn.ssubscribe(names).await?,
+            };
+            println!("Reading messages... (press Ctrl-C to quit)");
+            return receive_messages(subscriber).await;
+        }
+        match conn.call(args).await {
+            Ok(frame) => print_reply(&frame),
+            // 服务器返回的错误不影响连接，继续使用。
+            Err(err @ (Error::Server(_) | Error::Auth(_) | Error::WrongType)) => {
+                print_reply(&Frame::Error(err.to_string()))
+            }
+            // 其他错误说明连接已经不可用，丢弃连接。
+            Err(err) => {
+                println!("Error: {}", err);
+                continue;
+            }
+        }
+        client = Some(conn);
+    }
+}
+
+/// 将一行命令切分为参数，支持单引号、双引号以及双引号内的转义字符。
+fn split_args(line: &str) -> Result<Vec<Bytes>, String> {
+    let mut args = vec![];
+    let mut chars = line.chars().peekable();
+    loop {
+        // 跳过参数之间的空白。
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            return Ok(args);
+        };
+
+        let mut arg = String::new();
+        if first == '"' || first == '\'' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some(c) if c == first => break,
+                    Some('\\') if first == '"' => match chars.next() {
+                        Some('n') => arg.push('\n'),
+                        Some('r') => arg.push('\r'),
+                        Some('t') => arg.push('\t'),
+                        Some(c) => arg.push(c),
+                        None => return Err("引号没有闭合".to_string()),
+                    },
+                    Some(c) => arg.push(c),
+                    None => return Err("引号没有闭合".to_string()),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                arg.push(c);
+            }
+        }
+        args.push(Bytes::from(arg));
+    }
+}
+
+/// 打印一个可能不存在的 value，不存在时打印`(nil)`。
+fn print_optional(value: Option<Bytes>) {
+    match value {
+        Some(value) => print_reply(&Frame::Bulk(value)),
+        None => print_reply(&Frame::Null),
+    }
+}
+
+/// 将以秒为单位的超时时间转换为`Duration`，`0`表示一直阻塞。
+fn timeout_from_secs(secs: f64) -> my_redis::Result<Option<Duration>> {
+    if secs == 0.0 {
+        return Ok(None);
+    }
+    Duration::try_from_secs_f64(secs)
+        .map(Some)
+        .map_err(|_| "超时时间必须是非负数".into())
+}
+
+/// 将交替出现的分数和成员转换为`(分数, 成员)`的列表。
+fn score_pairs(pairs: Vec<String>) -> my_redis::Result<Vec<(f64, Bytes)>> {
+    if !pairs.len().is_multiple_of(2) {
+        return Err("分数和成员必须成对出现".into());
+    }
+    pairs
+        .chunks(2)
+        .map(|pair| {
+            let score = pair[0]
+                .parse::<f64>()
+                .map_err(|_| format!("分数格式错误：{}", pair[0]))?;
+            Ok((score, Bytes::from(pair[1].clone())))
+        })
+        .collect()
+}
+
+/// 将依次出现的经度、纬度和成员转换为`(经度, 纬度, 成员)`的列表。
+fn geo_members(items: Vec<String>) -> my_redis::Result<Vec<(f64, f64, Bytes)>> {
+    if !items.len().is_multiple_of(3) {
+        return Err("经度、纬度和成员必须成组出现".into());
+    }
+    items
+        .chunks(3)
+        .map(|item| {
+            let lon = item[0]
+                .parse::<f64>()
+                .map_err(|_| format!("经度格式错误：{}", item[0]))?;
+            let lat = item[1]
+                .parse::<f64>()
+                .map_err(|_| format!("纬度格式错误：{}", item[1]))?;
+            Ok((lon, lat, Bytes::from(item[2].clone())))
+        })
+        .collect()
+}
+
+/// 将经度和纬度转换为帧，用于打印。
+fn coord_frame((lon, lat): (f64, f64)) -> Frame {
+    Frame::Array(vec![
+        Frame::Bulk(Bytes::from(lon.to_string())),
+        Frame::Bulk(Bytes::from(lat.to_string())),
+    ])
+}
+
+/// 将`GeoSearch`搜索到的成员转换为与服务端响应相同结构的帧，用于打印。
+fn geo_location_frame(location: GeoLocation) -> Frame {
+    if location.dist.is_none() && location.hash.is_none() && location.coord.is_none() {
+        return Frame::Bulk(location.member);
+    }
+    let mut items = vec![Frame::Bulk(location.member)];
+    if let Some(dist) = location.dist {
+        items.push(Frame::Bulk(Bytes::from(format!("{:.4}", dist))));
+    }
+    if let Some(hash) = location.hash {
+        items.push(Frame::Integer(hash as i64));
+    }
+    if let Some(coord) = location.coord {
+        items.push(coord_frame(coord));
+    }
+    Frame::Array(items)
+}
+
+/// 将流中的消息转换为与服务端响应相同结构的帧，用于打印。
+fn stream_entries_frame(entries: Vec<StreamEntry>) -> Frame {
+    let entries = entries
+        .into_iter()
+        .map(|entry| {
+            let fields = entry
+                .fields
+                .into_iter()
+                .flat_map(|(field, value)| [Frame::Bulk(field), Frame::Bulk(value)])
+                .collect();
+            Frame::Array(vec![
+                Frame::Bulk(Bytes::from(entry.id)),
+                Frame::Array(fields),
+            ])
+        })
+        .collect();
+    Frame::Array(entries)
+}
+
+/// 打印`XRead`和`XReadGroup`的结果，没有读取到消息时打印`(nil)`。
+fn print_streams(streams: Vec<(String, Vec<StreamEntry>)>) {
+    if streams.is_empty() {
+        print_reply(&Frame::Null);
+        return;
+    }
+    let items = streams
+        .into_iter()
+        .map(|(key, entries)| {
+            Frame::Array(vec![
+                Frame::Bulk(Bytes::from(key)),
+                stream_entries_frame(entries),
+            ])
+        })
+        .collect();
+    print_reply(&Frame::Array(items));
+}
+
+/// 打印集合中的所有成员。
+fn print_members(members: HashSet<Bytes>) {
+    let items = members.into_iter().map(Frame::Bulk).collect();
+    print_reply(&Frame::Array(items));
+}
+
+/// 打印阻塞式弹出的结果，超时时打印`(nil)`。
+fn print_popped(popped: Option<(String, Bytes)>) {
+    match popped {
+        Some((key, value)) => {
+            let reply = Frame::Array(vec![Frame::Bulk(Bytes::from(key)), Frame::Bulk(value)]);
+            print_reply(&reply);
+        }
+        None => print_reply(&Frame::Null),
+    }
+}
+
+/// 打印`LMPop`和`ZMPop`的结果，`items`为弹出的元素，没有弹出元素时打印`(nil)`。
+fn print_mpopped(popped: Option<(String, Vec<Frame>)>) {
+    match popped {
+        Some((key, items)) => {
+            let reply = Frame::Array(vec![Frame::Bulk(Bytes::from(key)), Frame::Array(items)]);
+            print_reply(&reply);
+        }
+        None => print_reply(&Frame::Null),
+    }
+}
+
+/// 将列表的元素转换为用于打印的帧。
+fn list_items(values: Vec<Bytes>) -> Vec<Frame> {
+    values.into_iter().map(Frame::Bulk).collect()
+}
+
+/// 将有序集合的成员和分数转换为用于打印的帧，每个成员为一个二元数组。
+fn zset_items(members: Vec<(Bytes, f64)>) -> Vec<Frame> {
+    members
+        .into_iter()
+        .map(|(member, score)| {
+            Frame::Array(vec![
+                Frame::Bulk(member),
+                Frame::Bulk(Bytes::from(score.to_string())),
+            ])
+        })
+        .collect()
+}
+
+/// 按照`--json`、`--raw`或`--csv`选择的格式打印一个响应，默认为 redis-cli 的风格。
+fn print_reply(frame: &Frame) {
+    let mut out = vec![];
+    match OUTPUT_FORMAT.get().copied().unwrap_or_default() {
+        OutputFormat::Cli => out.extend_from_slice(format_reply(frame, "").as_bytes()),
+        OutputFormat::Json => write_json(frame, &mut out),
+        OutputFormat::Raw => write_raw(frame, &mut out),
+        OutputFormat::Csv => write_csv(frame, &mut out),
+    }
+    out.push(b'\n');
+    // 二进制的内容也需要原样写入，所以不使用`println!`。
+    let mut stdout = std::io::stdout().lock();
+    let _ = stdout.write_all(&out).and_then(|()| stdout.flush());
+}
+
+/// 将响应编码为 JSON。
+///
+/// `Map`的 key 都是字符串时编码为对象，否则编码为二元数组的数组；错误编码为`{"error": ...}`；
+/// 无法用 JSON 数字表示的大数以及`inf`、`nan`编码为字符串。
+fn write_json(frame: &Frame, out: &mut Vec<u8>) {
+    match frame {
+        Frame::Simple(value) => write_json_string(value.as_bytes(), out),
+        Frame::Bulk(value) | Frame::Verbatim(_, value) => write_json_string(value, out),
+        Frame::BigNumber(value) => write_json_string(value.as_bytes(), out),
+        Frame::Error(msg) => {
+            out.extend_from_slice(b"{\"error\":");
+            write_json_string(msg.as_bytes(), out);
+            out.push(b'}');
+        }
+        Frame::Integer(value) => out.extend_from_slice(value.to_string().as_bytes()),
+        Frame::Double(value) if value.is_finite() => {
+            out.extend_from_slice(value.to_string().as_bytes())
+        }
+        Frame::Double(_) => write_json_string(frame.to_string().as_bytes(), out),
+        Frame::Boolean(value) => out.extend_from_slice(value.to_string().as_bytes()),
+        Frame::Null => out.extend_from_slice(b"null"),
+        Frame::Array(items) | Frame::Set(items) | Frame::Push(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_json(item, out);
+            }
+            out.push(b']');
+        }
+        Frame::Map(pairs) => {
+            let object = pairs
+                .iter()
+                .all(|(key, _)| matches!(key, Frame::Simple(_) | Frame::Bulk(_)));
+            out.push(if object { b'{' } else { b'[' });
+            for (i, (key, value)) in pairs.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                if !object {
+                    out.push(b'[');
+                }
+                write_json(key, out);
+                out.push(if object { b':' } else { b',' });
+                write_json(value, out);
+                if !object {
+                    out.push(b']');
+                }
+            }
+            out.push(if object { b'}' } else { b']' });
+        }
+    }
+}
+
+/// 将字节编码为 JSON 字符串。不是合法的 UTF-8 时，每个字节作为一个`\u00XX`字符。
+fn write_json_string(value: &[u8], out: &mut Vec<u8>) {
+    let utf8 = str::from_utf8(value).is_ok();
+    let chars: Box<dyn Iterator<Item = char>> = match str::from_utf8(value) {
+        Ok(string) => Box::new(string.chars()),
+        Err(_) => Box::new(value.iter().map(|&b| b as char)),
+    };
+    out.push(b'"');
+    for c in chars {
+        match c {
+            '"' => out.extend_from_slice(b"\\\""),
+            '\\' => out.extend_from_slice(b"\\\\"),
+            '\n' => out.extend_from_slice(b"\\n"),
+            '\r' => out.extend_from_slice(b"\\r"),
+            '\t' => out.extend_from_slice(b"\\t"),
+            c if c.is_control() || !utf8 && !c.is_ascii() => {
+                out.extend_from_slice(format!("\\u{:04x}", c as u32).as_bytes())
+            }
+            c => {
+                let mut buf = [0; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    out.push(b'"');
+}
+
+/// 原样输出响应的内容，没有引号和类型标记，数组和`Map`的每个元素一行，`Null`为空行。
+fn write_raw(frame: &Frame, out: &mut Vec<u8>) {
+    match frame {
+        Frame::Simple(value) | Frame::Error(value) | Frame::BigNumber(value) => {
+            out.extend_from_slice(value.as_bytes())
+        }
+        Frame::Bulk(value) | Frame::Verbatim(_, value) => out.extend_from_slice(value),
+        Frame::Integer(_) | Frame::Double(_) | Frame::Boolean(_) => {
+            out.extend_from_slice(frame.to_string().as_bytes())
+        }
+        Frame::Null => {}
+        Frame::Array(items) | Frame::Set(items) | Frame::Push(items) => {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b'\n');
+                }
+                write_raw(item, out);
+            }
+        }
+        Frame::Map(pairs) => {
+            for (i, (key, value)) in pairs.iter().enumerate() {
+                if i > 0 {
+                    out.push(b'\n');
+                }
+                write_raw(key, out);
+                out.push(b'\n');
+                write_raw(value, out);
+            }
+        }
+    }
+}
+
+/// 将响应编码为一行 CSV，嵌套的数组和`Map`被展开。
+///
+/// 字符串按照 RFC 4180 加上双引号，内部的双引号重复一次；`Null`为`NULL`，错误为`ERROR,"<msg>"`。
+fn write_csv(frame: &Frame, out: &mut Vec<u8>) {
+    match frame {
+        Frame::Simple(value) => write_csv_string(value.as_bytes(), out),
+        Frame::Bulk(value) | Frame::Verbatim(_, value) => write_csv_string(value, out),
+        Frame::Error(msg) => {
+            out.extend_from_slice(b"ERROR,");
+            write_csv_string(msg.as_bytes(), out);
+        }
+        Frame::Integer(_) | Frame::Double(_) | Frame::Boolean(_) | Frame::BigNumber(_) => {
+            out.extend_from_slice(frame.to_string().as_bytes())
+        }
+        Frame::Null => out.extend_from_slice(b"NULL"),
+        Frame::Array(items) | Frame::Set(items) | Frame::Push(items) => {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_csv(item, out);
+            }
+        }
+        Frame::Map(pairs) => {
+            for (i, (key, value)) in pairs.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_csv(key, out);
+                out.push(b',');
+                write_csv(value, out);
+            }
+        }
+    }
+}
+
+/// 将字节编码为 CSV 中带双引号的字段。
+fn write_csv_string(value: &[u8], out: &mut Vec<u8>) {
+    out.push(b'"');
+    for &b in value {
+        if b == b'"' {
+            out.push(b'"');
+        }
+        out.push(b);
+    }
+    out.push(b'"');
+}
+
+/// 按照 redis-cli 的风格格式化响应帧。
+///
+/// `indent`是数组元素的缩进，用于嵌套数组。
+fn format_reply(frame: &Frame, indent: &str) -> String {
+    match frame {
+        Frame::Simple(value) => value.clone(),
+        Frame::Error(msg) => format!("(error) {}", msg),
+        Frame::Integer(value) => format!("(integer) {}", value),
+        Frame::Bulk(value) => match str::from_utf8(value) {
+            Ok(string) => format!("{:?}", string),
+            Err(_) => format!("{:?}", value),
+        },
+        Frame::Null => "(nil)".to_string(),
+        Frame::Double(_) => format!("(double) {}", frame),
+        Frame::Boolean(value) => format!("({})", value),
+        Frame::BigNumber(value) => format!("(big number) {}", value),
+        // 带格式的字符串按照原样输出，例如`Info`的文本。
+        Frame::Verbatim(_, value) => String::from_utf8_lossy(value).into_owned(),
+        Frame::Set(items) if items.is_empty() => "(empty set)".to_string(),
+        Frame::Array(items) | Frame::Push(items) if items.is_empty() => "(empty array)".to_string(),
+        Frame::Map(pairs) if pairs.is_empty() => "(empty hash)".to_string(),
+        Frame::Map(pairs) => {
+            let width = pairs.len().to_string().len();
+            let nested = format!("{}{}", indent, " ".repeat(width + 2));
+            pairs
+                .iter()
+                .enumerate()
+                .map(|(i, (key, value))| {
+                    let prefix = if i == 0 { "" } else { indent };
+                    let key = format_reply(key, &nested);
+                    let value = format_reply(value, &nested);
+                    format!("{}{:>width$}# {} => {}", prefix, i + 1, key, value)
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        Frame::Array(items) | Frame::Push(items) | Frame::Set(items) => {
+            let width = items.len().to_string().len();
+            let nested = format!("{}{}", indent, " ".repeat(width + 2));
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    let prefix = if i == 0 { "" } else { indent };
+                    let reply = format_reply(item, &nested);
+                    format!("{}{:>width$}) {}", prefix, i + 1, reply)
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+    }
+}
+
+/// 持续接收并打印订阅到的信息，直到客户端或服务器关闭。
+async fn receive_messages(mut subscriber: Subscriber) -> my_redis::Result<()> {
+    // 一旦客户端进入`Subscribe`后，他会一直循环等待信息，
+    // 我们应该同时开启一个异步任务，监听客户端的关闭信号。
+    // 当关闭信号来临时，停止接收信息，关闭客户端。
+    //
+    // 但与此同时，服务端的这个连接的`Handler`的`run()`并不会结束，
+    // 因为`cmd.apply()`并没有结束。
+    // 究其原因是`Subscriber`的`apply()`没有执行完成，`StreamMap`中的
+    // 所有异步流都处于`tx.recv()`等待中。
+    //
+    // 所以客户端在关闭信号到来的时候，应该发送一个消息给服务端，
+    // 告诉`Subscriber`的`apply()`你应该结束了。
+    loop {
+        tokio::select! {
+            _ = signal::ctrl_c() => {
+                // 客户端发送一个信号帧，告诉服务器客户端关闭了。
+                subscriber.send_ctrlc_frame().await?;
+                return Ok(())
+            }
+
+            res = subscriber.next_message() => {
+                match res {
+                    Ok(Some(msg)) => print_message(&msg),
+                    // 服务端关闭了。
+                    Ok(None) => {
+                        println!("服务器已关闭");
+                        return Ok(());
+                    },
+                    // 出错。
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+    }
+}
+
+/// 打印订阅到的信息。
+///
+/// 选择了`--json`等输出格式时，与 RESP 中推送的信息一样打印为数组，例如`["message", channel, content]`。
+fn print_message(msg: &Message) {
+    if OUTPUT_FORMAT.get().copied().unwrap_or_default() == OutputFormat::Cli {
+        println!("{}", format_message(msg));
+        return;
+    }
+    let mut items = vec![];
+    match &msg.pattern {
+        Some(pattern) => {
+            items.push(Frame::Bulk(Bytes::from_static(b"pmessage")));
+            items.push(Frame::Bulk(Bytes::from(pattern.clone())));
+        }
+        None => items.push(Frame::Bulk(Bytes::from_static(b"message"))),
+    }
+    items.push(Frame::Bulk(Bytes::from(msg.channel.clone())));
+    items.push(Frame::Bulk(msg.content.clone()));
+    print_reply(&Frame::Array(items));
+}
+
+/// 持续打印服务器执行的命令，直到按下 Ctrl-C 或者服务器关闭了连接。
+async fn receive_commands(mut monitored: Monitored) -> my_redis::Result<()> {
+    loop {
+        tokio::select! {
+            // 直接关闭连接就会结束服务端的`Monitor`。
+            _ = signal::ctrl_c() => return Ok(()),
+            res = monitored.next_command() => match res? {
+                Some(line) => print_reply(&Frame::Simple(line)),
+                None => {
+                    println!("服务器已关闭");
+                    return Ok(());
+                }
+            },
+        }
+    }
+}
+
+/// 将慢命令日志转换为与服务端响应相同结构的帧，用于打印。
+fn slowlog_frame(entries: Vec<SlowlogEntry>) -> Frame {
+    let entries = entries
+        .into_iter()
+        .map(|entry| {
+            Frame::Array(vec![
+                Frame::Integer(entry.id as i64),
+                Frame::Integer(entry.timestamp as i64),
+                Frame::Integer(entry.duration.as_micros() as i64),
+                Frame::Array(entry.args.into_iter().map(Frame::Bulk).collect()),
+            ])
+        })
+        .collect();
+    Frame::Array(entries)
+}
+
+/// 将订阅到的信息格式化为一行：时间戳、信道（以及匹配的模式）、内容。
+///
+/// 内容如果是合法的 UTF-8 字符串就原样输出，否则输出十六进制。
+fn format_message(msg: &Message) -> String {
+    let channel = match &msg.pattern {
+        Some(pattern) => format!("{} ({})", msg.channel, pattern),
+        None => msg.channel.clone(),
+    };
+    let content = match str::from_utf8(&msg.content) {
+        Ok(string) => format!("\"{}\"", string),
+        Err(_) => {
+            let hex: String = msg.content.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("0x{}", hex)
+        }
+    };
+    format!("{} {} {}", timestamp(), channel, content)
+}
+
+/// 返回当前的 UTC 时间，格式为`YYYY-MM-DDTHH:MM:SS.mmmZ`。
+fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs();
+    let (days, rem) = (secs / 86400, secs % 86400);
+
+    // 将距离 1970-01-01 的天数转换为年月日，
+    // 算法参考：http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        now.subsec_millis()
+    )
+}
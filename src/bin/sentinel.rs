@@ -0,0 +1,69 @@
+//! my-redis-sentinel
+//!
+//! 监控主节点，主节点下线后将副本提升为主节点。
+//! 客户端可以连接到这个服务器，订阅`+switch-master`信道获取新主节点的地址。
+
+use clap::Parser;
+use my_redis::sentinel::Sentinel;
+use my_redis::server::Server;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::signal;
+use tracing_subscriber::filter::LevelFilter;
+
+/// 默认端口。
+const DEFAULT_SENTINEL_PORT: u16 = 26379;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "my-redis-sentinel",
+    author,
+    version,
+    about = "监控my-redis主节点并自动故障转移"
+)]
+struct Args {
+    // 监控服务器自己的端口，客户端通过它订阅主节点的切换。
+    #[arg(long, default_value_t = DEFAULT_SENTINEL_PORT)]
+    port: u16,
+    // 主节点的地址，格式为`<host>:<port>`。
+    #[arg(long)]
+    primary: String,
+    // 副本的地址，可以指定多次，故障转移时按照指定的顺序选择新的主节点。
+    #[arg(long = "replica")]
+    replicas: Vec<String>,
+    // 连续多少次检测失败后判定主节点下线。
+    #[arg(long, default_value_t = 3)]
+    quorum: u32,
+    // 检测间隔，单位是毫秒。
+    #[arg(long, default_value_t = 1000)]
+    interval_ms: u64,
+    // 日志级别：off、error、warn、info、debug、trace。
+    #[arg(long, default_value_t = LevelFilter::INFO)]
+    log_level: LevelFilter,
+}
+
+#[test]
+fn verify_args() {
+    // clap 库提供的测试，可以帮助找出绝大部分的开发错误。
+    use clap::CommandFactory;
+    Args::command().debug_assert();
+}
+
+#[tokio::main]
+pub async fn main() {
+    let args = Args::parse();
+    tracing_subscriber::fmt()
+        .with_max_level(args.log_level)
+        .init();
+
+    let listener = TcpListener::bind(format!("127.0.0.1:{}", args.port))
+        .await
+        .unwrap();
+    let sentinel = Sentinel::new(args.primary, args.replicas)
+        .quorum(args.quorum)
+        .interval(Duration::from_millis(args.interval_ms));
+    Server::new(listener)
+        .sentinel(sentinel)
+        .run(signal::ctrl_c())
+        .await;
+}
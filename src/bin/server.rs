@@ -1,13 +1,27 @@
 //! my-redis-server
 //!
 //! 这个文件是服务器实现的入口点，使用了 clap 第三方库
-//! 进行命令行参数解析
+//! 进行命令行参数解析。配置按照配置文件、`MY_REDIS_*`环境变量、命令行参数的顺序合并，
+//! 后面的覆盖前面的，见`my_redis::server::Config`。
 
-use clap::Parser;
-use my_redis::server;
-use my_redis::DEFAULT_PORT;
-use tokio::net::TcpListener;
+use std::{
+    io,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
+
+use clap::{Parser, ValueEnum};
+use my_redis::server::{self, Config, Server};
+use my_redis::{cluster, FlushPolicy};
 use tokio::signal;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{
+    filter::LevelFilter,
+    fmt::{format::FmtSpan, writer::BoxMakeWriter},
+    EnvFilter,
+};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -17,9 +31,112 @@ use tokio::signal;
     about = "一个自实现的Redis服务器"
 )]
 struct Args {
-    // 解析参数，获取服务器端口。
-    #[arg(long, default_value_t = DEFAULT_PORT)]
-    port: u16,
+    // 配置文件，每一行为`<配置项> <值>`，配置项与命令行参数的名称相同。
+    // 没有指定时读取环境变量`MY_REDIS_CONFIG`。
+    #[arg(long)]
+    config: Option<PathBuf>,
+    // 解析参数，获取服务器端口，默认为 6379。
+    #[arg(long)]
+    port: Option<u16>,
+    // 侦听的地址，可以是 IP（如`0.0.0.0`、`::1`）或者主机名，可以指定多次，
+    // 主机名解析出的所有地址都会被侦听。默认为`127.0.0.1`。
+    #[arg(long = "bind")]
+    binds: Vec<String>,
+    // 日志级别：off、error、warn、info、debug、trace，默认为 info。
+    #[arg(long)]
+    log_level: Option<LevelFilter>,
+    // 按照模块过滤日志，格式与`RUST_LOG`相同，例如`my_redis::server=debug`，
+    // 没有匹配的模块使用`--log-level`的级别。
+    #[arg(long)]
+    log_filter: Option<String>,
+    // 在连接、命令等 span 结束时输出一条日志，包含持续的时间。
+    #[arg(long)]
+    log_spans: bool,
+    // 日志格式，默认为 text。
+    #[arg(long, value_enum)]
+    log_format: Option<LogFormat>,
+    // 日志文件，设置后日志写入该文件并按天轮转，否则输出到标准输出。
+    #[arg(long, visible_alias = "logfile")]
+    log_file: Option<PathBuf>,
+    // 以守护进程的方式在后台运行，标准输入输出被重定向到`/dev/null`，
+    // 通常与`--log-file`和`--pidfile`一起使用。
+    #[arg(long)]
+    daemonize: bool,
+    // PID 文件，启动时写入进程 ID，关闭时删除。文件中的进程仍在运行时拒绝启动。
+    #[arg(long)]
+    pidfile: Option<PathBuf>,
+    // 快照文件，设置后启动时从该文件恢复数据，`Save`和`BgSave`也写入该文件。
+    #[arg(long)]
+    dbfilename: Option<PathBuf>,
+    // ACL 文件，每一行为`user <username> [<rule> ...]`，设置后启动时加载用户。
+    #[arg(long)]
+    aclfile: Option<PathBuf>,
+    // 数据库的分片数，每个分片有自己的锁，至少为 1，默认为 16。
+    #[arg(long)]
+    db_shards: Option<usize>,
+    // 最大连接数，默认为 250，服务器运行期间可以通过`Config Set maxclients`修改。
+    #[arg(long)]
+    maxclients: Option<usize>,
+    // 读取一个命令的超时时间（毫秒），从收到命令的第一部分数据开始计时。
+    #[arg(long)]
+    read_timeout_ms: Option<u64>,
+    // 写入一个响应的超时时间（毫秒）。
+    #[arg(long)]
+    write_timeout_ms: Option<u64>,
+    // 客户端发送的一个`Bulk`的最大字节数，默认为 512MB。
+    #[arg(long)]
+    proto_max_bulk_len: Option<usize>,
+    // 客户端发送的一个数组的最大元素个数。
+    #[arg(long)]
+    proto_max_array_len: Option<usize>,
+    // 客户端发送的`Frame`的最大嵌套层数，默认为 64。
+    #[arg(long)]
+    proto_max_depth: Option<usize>,
+    // 写入响应后何时刷新：batch 在流水线发送的命令全部执行完后一起写回响应，immediate 每条命令后立即写回。
+    // 默认为 batch。
+    #[arg(long, value_enum)]
+    flush_policy: Option<FlushMode>,
+    // 收到关闭信号后等待连接处理完成的最长时间（毫秒），超时后强制关闭，默认一直等待。
+    // 等待期间再次按下 Ctrl-C 会立即关闭。
+    #[arg(long)]
+    drain_timeout_ms: Option<u64>,
+    // 开启读优化模式，`Get`不需要获取数据库的锁，写操作会变慢。
+    #[arg(long)]
+    read_optimized: bool,
+    // 集群中的节点及其负责的槽，格式为`<host>:<port>=<start>-<end>`，可以指定多次。
+    // 指定后开启集群模式，当前节点的地址为第一个侦听的地址，侦听所有地址时为`127.0.0.1:<port>`。
+    #[arg(long = "cluster-node", value_parser = cluster::parse_node)]
+    cluster_nodes: Vec<(String, RangeInclusive<u16>)>,
+    // TLS 证书链（PEM 格式），与`--tls-key-file`一起设置后开启 TLS。
+    #[arg(long)]
+    tls_cert_file: Option<PathBuf>,
+    // TLS 私钥（PEM 格式）。
+    #[arg(long)]
+    tls_key_file: Option<PathBuf>,
+    // 客户端 CA 证书（PEM 格式），设置后客户端必须提供由该 CA 签发的证书。
+    #[arg(long)]
+    tls_ca_cert_file: Option<PathBuf>,
+    // 将客户端证书的 CN 作为连接的 ACL 用户，不需要再发送`Auth`。
+    #[arg(long)]
+    tls_cn_as_user: bool,
+}
+
+/// 写入响应后何时刷新，对应`FlushPolicy`。
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FlushMode {
+    // 每条命令的响应立即写回。
+    Immediate,
+    // 读缓存中的命令全部执行完后一起写回。
+    Batch,
+}
+
+/// 日志格式，对应`server::LogFormat`。
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LogFormat {
+    // 便于阅读的文本格式。
+    Text,
+    // 每行一个 JSON 对象，便于日志收集系统处理。
+    Json,
 }
 
 #[test]
@@ -29,14 +146,325 @@ fn verify_args() {
     Args::command().debug_assert();
 }
 
-#[tokio::main]
-pub async fn main() {
-    // 获取命令行参数。
-    let args = Args::parse();
-    // 监听。
-    let listener = TcpListener::bind(format!("127.0.0.1:{}", args.port))
-        .await
-        .unwrap();
-    // 运行。
-    server::run(listener, signal::ctrl_c()).await;
+pub fn main() -> ExitCode {
+    // 获取命令行参数，与配置文件、环境变量合并。
+    let config = match load_config(Args::parse()) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("配置错误：{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    // `fork()`只会复制调用它的线程，所以必须在创建运行时和日志的后台线程之前进入后台。
+    if config.is_daemonize() {
+        if let Err(err) = daemonize() {
+            eprintln!("无法进入后台运行：{}", err);
+            return ExitCode::FAILURE;
+        }
+    }
+    // 初始化日志。`guard`被 drop 时会将缓存中的日志全部写入文件，
+    // 所以要保证它存活到`main()`结束。
+    let _guard = init_logging(&config);
+    // 写入 PID 文件，`pidfile`被 drop 时删除文件。
+    let _pidfile = match config.get_pidfile().map(Pidfile::create).transpose() {
+        Ok(pidfile) => pidfile,
+        Err(err) => {
+            tracing::error!(cause = %err, "启动失败");
+            return ExitCode::FAILURE;
+        }
+    };
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("无法创建 tokio 运行时")
+        .block_on(run(config))
+}
+
+/// 依次加载配置文件、环境变量，最后设置命令行参数中指定了的配置。
+fn load_config(args: Args) -> my_redis::Result<Config> {
+    let mut config = Config::new();
+    let path = args
+        .config
+        .or_else(|| std::env::var_os(format!("{}CONFIG", server::ENV_PREFIX)).map(PathBuf::from));
+    if let Some(path) = path {
+        config = config.load_file(path)?;
+    }
+    config = config.load_env()?;
+
+    if let Some(port) = args.port {
+        config = config.port(port);
+    }
+    if !args.binds.is_empty() {
+        config = config.bind(args.binds);
+    }
+    if let Some(level) = args.log_level {
+        config = config.log_level(level);
+    }
+    if let Some(directives) = args.log_filter {
+        config = config.log_filter(directives)?;
+    }
+    if args.log_spans {
+        config = config.log_spans(true);
+    }
+    if let Some(format) = args.log_format {
+        config = config.log_format(match format {
+            LogFormat::Text => server::LogFormat::Text,
+            LogFormat::Json => server::LogFormat::Json,
+        });
+    }
+    if let Some(path) = args.log_file {
+        config = config.log_file(path);
+    }
+    if args.daemonize {
+        config = config.daemonize(true);
+    }
+    if let Some(path) = args.pidfile {
+        config = config.pidfile(path);
+    }
+    if let Some(path) = args.dbfilename {
+        config = config.dbfilename(path);
+    }
+    if let Some(path) = args.aclfile {
+        config = config.aclfile(path);
+    }
+    if let Some(shards) = args.db_shards {
+        config = config.db_shards(shards);
+    }
+    if let Some(max) = args.maxclients {
+        config = config.maxclients(max);
+    }
+    if let Some(ms) = args.read_timeout_ms {
+        config = config.read_timeout(Duration::from_millis(ms));
+    }
+    if let Some(ms) = args.write_timeout_ms {
+        config = config.write_timeout(Duration::from_millis(ms));
+    }
+    // 只覆盖指定了的限制，其余的保留配置文件和环境变量中的值。
+    let mut limits = config.get_frame_limits();
+    if let Some(len) = args.proto_max_bulk_len {
+        limits.max_bulk_len = len;
+    }
+    if let Some(len) = args.proto_max_array_len {
+        limits.max_array_len = len;
+    }
+    if let Some(depth) = args.proto_max_depth {
+        limits.max_depth = depth;
+    }
+    config = config.frame_limits(limits);
+    if let Some(mode) = args.flush_policy {
+        config = config.flush_policy(match mode {
+            FlushMode::Immediate => FlushPolicy::Immediate,
+            FlushMode::Batch => FlushPolicy::Batch,
+        });
+    }
+    if let Some(ms) = args.drain_timeout_ms {
+        config = config.drain_timeout(Duration::from_millis(ms));
+    }
+    if args.read_optimized {
+        config = config.read_optimized(true);
+    }
+    if !args.cluster_nodes.is_empty() {
+        config = config.cluster_nodes(args.cluster_nodes);
+    }
+    if let (Some(cert), Some(key)) = (&args.tls_cert_file, &args.tls_key_file) {
+        config = config.tls(cert, key);
+    } else if args.tls_cert_file.is_some() || args.tls_key_file.is_some() {
+        return Err("--tls-cert-file 和 --tls-key-file 需要同时设置".into());
+    }
+    if let Some(path) = args.tls_ca_cert_file {
+        config = config.tls_ca_cert_file(path);
+    }
+    if args.tls_cn_as_user {
+        config = config.tls_cn_as_user(true);
+    }
+    Ok(config)
+}
+
+/// 按照配置创建并运行服务器，直到收到关闭信号。
+///
+/// 启动失败时返回`ExitCode::FAILURE`，而不是直接退出进程，保证 PID 文件和日志被清理。
+async fn run(config: Config) -> ExitCode {
+    let server = match Server::from_config(config).await {
+        Ok(server) => server,
+        Err(err) => {
+            tracing::error!(cause = %err, "启动失败");
+            return ExitCode::FAILURE;
+        }
+    };
+    // 运行。第一次 Ctrl-C（或者`SIGTERM`）开始关闭，等待连接处理完成；
+    // 第二次立即关闭。
+    let handle = server.handle();
+    let shutdown = async move {
+        shutdown_signal().await;
+        tokio::spawn(async move {
+            shutdown_signal().await;
+            handle.shutdown_now();
+        });
+    };
+    server.run(shutdown).await;
+    ExitCode::SUCCESS
+}
+
+/// 等待关闭信号：Ctrl-C，或者 Unix 上 init 系统发送的`SIGTERM`。
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => tokio::select! {
+                _ = signal::ctrl_c() => {}
+                _ = term.recv() => {}
+            },
+            Err(_) => {
+                let _ = signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = signal::ctrl_c().await;
+    }
+}
+
+/// 以守护进程的方式运行：父进程退出，子进程成为新会话的首进程，脱离终端。
+///
+/// 与 Redis 相同，不改变工作目录，相对路径仍然相对于启动时的目录；
+/// 标准输入输出被重定向到`/dev/null`，日志需要通过`--log-file`写入文件。
+#[cfg(unix)]
+fn daemonize() -> io::Result<()> {
+    // SAFETY: 此时进程只有一个线程，`fork()`之后子进程可以继续安全地运行。
+    match unsafe { libc::fork() } {
+        -1 => return Err(io::Error::last_os_error()),
+        // 子进程。
+        0 => {}
+        // 父进程，直接退出。
+        _ => std::process::exit(0),
+    }
+    // SAFETY: 只调用了没有内存安全要求的系统调用，文件描述符由这里打开和关闭。
+    unsafe {
+        if libc::setsid() == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = libc::open(c"/dev/null".as_ptr(), libc::O_RDWR);
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        for target in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+            libc::dup2(fd, target);
+        }
+        if fd > libc::STDERR_FILENO {
+            libc::close(fd);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn daemonize() -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "只有 Unix 系统支持--daemonize",
+    ))
+}
+
+/// 写入了当前进程 ID 的 PID 文件，被 drop 时删除。
+#[derive(Debug)]
+struct Pidfile {
+    path: PathBuf,
+}
+
+impl Pidfile {
+    /// 将当前进程的 ID 写入`path`。
+    ///
+    /// 文件已经存在时，如果其中记录的进程仍在运行，说明已经有一个服务器在使用它，返回`Err`；
+    /// 否则是上一次没有正常关闭留下的文件，直接覆盖。
+    fn create(path: &Path) -> io::Result<Pidfile> {
+        let pid = std::process::id();
+        if let Ok(content) = std::fs::read_to_string(path) {
+            match content.trim().parse::<u32>() {
+                Ok(old) if old != pid && process_alive(old) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{} 中的进程 {} 仍在运行", path.display(), old),
+                    ));
+                }
+                _ => tracing::warn!(path = %path.display(), "覆盖过期的 PID 文件"),
+            }
+        }
+        std::fs::write(path, format!("{}\n", pid))?;
+        Ok(Pidfile {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for Pidfile {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            tracing::warn!(path = %self.path.display(), cause = %err, "无法删除 PID 文件");
+        }
+    }
+}
+
+/// 进程`pid`是否仍在运行。
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // 信号 0 不会被发送，只检查进程是否存在；没有权限发送信号时进程也是存在的。
+    // SAFETY: `kill()`没有内存安全要求。
+    unsafe {
+        libc::kill(pid, 0) == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+}
+
+/// 无法检查时认为进程仍在运行，避免两个服务器使用同一个 PID 文件。
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+/// 按照配置初始化全局的日志订阅者。
+///
+/// 如果日志写入文件，返回后台写入线程的`WorkerGuard`。
+fn init_logging(config: &Config) -> Option<WorkerGuard> {
+    let (writer, guard) = match config.get_log_file() {
+        Some(path) => {
+            let dir = path
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .unwrap_or_else(|| ".".as_ref());
+            let file_name = path.file_name().expect("日志文件路径必须包含文件名");
+            // 按天轮转，文件名的后缀为日期。
+            let appender = tracing_appender::rolling::daily(dir, file_name);
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (BoxMakeWriter::new(writer), Some(guard))
+        }
+        None => (BoxMakeWriter::new(std::io::stdout), None),
+    };
+
+    // 没有匹配规则的模块使用`log-level`的级别，规则已经在加载配置时检查过了。
+    let filter = EnvFilter::builder()
+        .with_default_directive(config.get_log_level().into())
+        .parse_lossy(config.get_log_filter().unwrap_or_default());
+    // 连接和命令的 span 结束时输出的日志包含持续时间`time.busy`和`time.idle`。
+    let span_events = if config.is_log_spans() {
+        FmtSpan::CLOSE
+    } else {
+        FmtSpan::NONE
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(span_events)
+        .with_writer(writer)
+        // 写入文件时不需要终端颜色。
+        .with_ansi(config.get_log_file().is_none());
+    match config.get_log_format() {
+        server::LogFormat::Text => builder.init(),
+        server::LogFormat::Json => builder.json().init(),
+    }
+
+    guard
 }
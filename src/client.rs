@@ -1,25 +1,135 @@
 use std::{
-    io::{Error, ErrorKind},
-    time::Duration,
+    collections::{HashSet, VecDeque},
+    ops::Bound,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime},
 };
 
 use bytes::Bytes;
-use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    net::{TcpStream, ToSocketAddrs},
+    time,
+};
+use tokio_rustls::{rustls::pki_types::ServerName, TlsConnector};
 
 use crate::{
-    cmd::{Get, Ping, Publish, Set, Subscribe},
-    Connection, Frame,
+    cmd::{
+        AclCommand, Asking, Auth, Backup, BitCount, BlockingPop, ClientCommand, ClusterCommand,
+        CommandCommand, Config, DebugCommand, Dump, Eval, Expire, ExpireAt, ExpireTime, FCall,
+        Function, GeoAdd, GeoDist, GeoPos, GeoSearch, GeoUnit, Get, GetBit, HDel, HGet, HGetAll,
+        HRandField, HSet, Hello, Incr, IncrByFloat, Info, LInsert, LLen, LMPop, LPos, LRange, LRem,
+        LSet, LastSave, Memory, Monitor, Object, PSubscribe, PUnsubscribe, Ping, Pop, Publish,
+        Push, ReplicaOf, Reset, Restore, RestoreFrom, SAdd, SCard, SIsMember, SMembers,
+        SRandMember, SRem, SSubscribe, SUnsubscribe, Save, Script, Set, SetAlgebra, SetBit,
+        Slowlog, Subscribe, Touch, Ttl, Type, Unlink, Unsubscribe, Wait, XAck, XAdd, XClaim,
+        XGroup, XLen, XRange, XRead, XReadGroup, ZAdd, ZCard, ZIncrBy, ZMPop, ZRandMember, ZRange,
+        ZRangeByScore, ZRank, ZRem, ZScore,
+    },
+    tls::ClientTls,
+    Connection, Frame, FromValue, Protocol, StreamedFrame, ToValue, Transport, INVALIDATE_CHANNEL,
 };
 
 /// 负责与Redis服务器建立连接。
 pub struct Client {
     connection: Connection,
+
+    // 等待响应的超时时间，为`None`时一直等待。
+    response_timeout: Option<Duration>,
+
+    // 只对下一次请求生效的超时时间，见`with_timeout()`。
+    next_timeout: Option<Duration>,
+
+    // 这次请求使用的`next_timeout`，发送请求时设置。
+    request_timeout: Option<Duration>,
+
+    // 请求超时后服务器仍然可能返回这次请求的响应，之后的响应都会错位，
+    // 所以连接不再可用，之后的请求都直接返回错误。
+    broken: bool,
+}
+
+/// `Client`的构建器，用于配置认证、TLS、Unix socket 等连接选项。
+///
+/// 通过`Client::builder()`创建。
+#[derive(Debug, Clone)]
+pub struct Builder {
+    // 服务器地址，格式为`host:port`。
+    addr: String,
+    // 连接建立后的认证、协议版本和名称。
+    options: ConnectOptions,
+    // TLS 配置，为`None`时不使用 TLS。
+    tls: Option<ClientTls>,
+    // Unix socket 路径，设置后忽略`addr`。
+    unix_socket: Option<PathBuf>,
+    // 等待响应的超时时间。
+    response_timeout: Option<Duration>,
+}
+
+/// 连接建立后自动完成的握手，见`Client::connect_with()`。
+///
+/// 设置了协议版本时通过一条`Hello`命令完成认证、设置名称和切换协议版本，
+/// 否则依次发送`Auth`和`Client SetName`。
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOptions {
+    // 认证使用的用户名，为`None`时使用默认用户。
+    username: Option<String>,
+    // 认证使用的密码，为`None`时不进行认证。
+    password: Option<String>,
+    // 连接使用的协议版本，为`None`时不发送`Hello`，使用 RESP2。
+    protocol: Option<Protocol>,
+    // 连接的名称。
+    client_name: Option<String>,
 }
 
 /// 一个进入了发布/订阅模式的客户端。
 pub struct Subscriber {
     client: Client,
     subscribed_channels: Vec<String>,
+    subscribed_patterns: Vec<String>,
+    subscribed_shard_channels: Vec<String>,
+    // 等待确认信息期间收到的订阅信息，`next_message()`会先返回它们。
+    pending_messages: VecDeque<Message>,
+}
+
+/// 一个执行了`Monitor`的客户端，通过`next_command()`获取服务器执行的命令。
+pub struct Monitored {
+    client: Client,
+}
+
+/// 集群模式下，服务器返回的重定向错误。
+///
+/// 格式为`MOVED <slot> <host:port>`或`ASK <slot> <host:port>`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Redirect {
+    // 槽已经永久迁移到了另一个节点。
+    Moved { slot: u16, addr: String },
+    // 槽正在迁移中，下一条命令需要发送到另一个节点。
+    Ask { slot: u16, addr: String },
+}
+
+/// `Cluster Slots`返回的一个槽区间及其所属的节点。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotRange {
+    pub start: u16,
+    pub end: u16,
+    // 节点的地址，格式为`<host>:<port>`。
+    pub addr: String,
+}
+
+/// `Command Info`返回的一个命令的信息。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandInfo {
+    pub name: String,
+    // 参数个数，包括命令名称本身。负数表示参数个数至少为它的绝对值。
+    pub arity: i64,
+    pub flags: Vec<String>,
+    // key 在参数中的位置，见`cmd::KeySpec`。
+    pub first_key: i64,
+    pub last_key: i64,
+    pub key_step: i64,
+    // ACL 的命令类别，例如`@read`、`@string`。
+    pub categories: Vec<String>,
 }
 
 /// 从订阅信道中获取到的信息。
@@ -27,213 +137,3754 @@ pub struct Subscriber {
 pub struct Message {
     pub channel: String,
     pub content: Bytes,
+    // 如果信息是通过模式订阅收到的，这里是匹配的模式。
+    pub pattern: Option<String>,
+}
+
+/// 流中的一条消息。
+#[derive(Debug, Clone)]
+pub struct StreamEntry {
+    pub id: String,
+    // 字段和值，按照添加时的顺序排列。
+    pub fields: Vec<(Bytes, Bytes)>,
+}
+
+/// 一条慢命令日志。
+#[derive(Debug, Clone)]
+pub struct SlowlogEntry {
+    pub id: u64,
+    // 记录时的 UNIX 时间戳，单位是秒。
+    pub timestamp: u64,
+    // 命令执行的时间。
+    pub duration: Duration,
+    // 命令名称以及参数。
+    pub args: Vec<Bytes>,
+}
+
+/// 通过`Function Load`注册的函数库。
+#[derive(Debug, Clone)]
+pub struct FunctionLibrary {
+    pub name: String,
+    // 库中可以通过`FCall`调用的函数。
+    pub functions: Vec<String>,
+    // 库的源代码，只有请求了`WITHCODE`时才有。
+    pub code: Option<String>,
+}
+
+/// `GeoSearch`搜索到的一个成员，没有请求的信息为`None`。
+#[derive(Debug, Clone)]
+pub struct GeoLocation {
+    pub member: Bytes,
+    // 到中心的距离，单位与搜索范围的单位相同。
+    pub dist: Option<f64>,
+    pub hash: Option<u64>,
+    // 经度和纬度。
+    pub coord: Option<(f64, f64)>,
 }
 
 impl Client {
     /// 与服务器建立连接，创建`Client`。
     pub async fn connect<T: ToSocketAddrs>(addr: T) -> crate::Result<Client> {
         let socket = TcpStream::connect(addr).await?;
-        let connection = Connection::new(socket);
-        Ok(Client { connection })
+        Ok(Client::new(Connection::new(socket)))
     }
 
-    /// 获取 key 对应的 value。对应`Get`命令。
+    /// 按照连接 URL 与服务器建立连接，创建`Client`，URL 的格式见`Builder::from_url()`。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use my_redis::client::Client;
+    ///
+    /// # async fn example() -> my_redis::Result<()> {
+    /// let url = std::env::var("REDIS_URL").unwrap_or("redis://127.0.0.1:6379".to_string());
+    /// let client = Client::open(&url).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// 如果 URL 不合法，或者连接、TLS 握手或认证失败，返回`Err`。
+    pub async fn open(url: &str) -> crate::Result<Client> {
+        Builder::from_url(url)?.connect().await
+    }
+
+    /// 与服务器建立连接，并按照`options`完成认证和协议版本的切换，创建`Client`。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use my_redis::client::{Client, ConnectOptions};
+    /// use my_redis::Protocol;
+    ///
+    /// # async fn example() -> my_redis::Result<()> {
+    /// let options = ConnectOptions::new()
+    ///     .auth(Some("alice".to_string()), "secret")
+    ///     .protocol(Protocol::Resp3);
+    /// let client = Client::connect_with("127.0.0.1:6379", options).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// 如果连接失败返回`Err`；认证失败时返回`Error::Auth`，
+    /// 服务器不支持指定的协议版本时返回`Error::Server`。
+    pub async fn connect_with(
+        addr: impl ToString,
+        options: ConnectOptions,
+    ) -> crate::Result<Client> {
+        Client::builder(addr).options(options).connect().await
+    }
+
+    /// 通过 TLS 与服务器建立连接，创建`Client`。
+    ///
+    /// 默认使用地址中的主机名作为 SNI 并验证服务器证书，见`ClientTls`。
+    ///
+    /// # Errors
+    /// 如果连接失败、读取证书失败或者 TLS 握手失败，返回`Err`。
+    pub async fn connect_tls(addr: impl ToString, tls: ClientTls) -> crate::Result<Client> {
+        Client::builder(addr).tls_config(tls).connect().await
+    }
+
+    /// 使用已经建立的传输层创建`Client`。
+    ///
+    /// 可以用于 TCP 以外的传输层，例如测试时使用的内存管道。
+    pub fn from_transport(socket: impl Transport + 'static) -> Client {
+        Client::new(Connection::new(socket))
+    }
+
+    fn new(connection: Connection) -> Client {
+        Client {
+            connection,
+            response_timeout: None,
+            next_timeout: None,
+            request_timeout: None,
+            broken: false,
+        }
+    }
+
+    /// 设置等待响应的超时时间，为`None`时一直等待，默认一直等待。
+    ///
+    /// 服务器没有在超时时间内返回完整的响应时，请求返回`Error::Timeout`。
+    /// 阻塞式命令等待的时间同样计算在内，可以通过`with_timeout()`为它们单独设置。
+    /// 超时后服务器可能仍然会返回这次请求的响应，所以连接不再可用，
+    /// 之后的请求都会返回`Err`，需要重新连接。
+    pub fn set_response_timeout(&mut self, timeout: Option<Duration>) {
+        self.response_timeout = timeout;
+    }
+
+    /// 为下一次请求设置等待响应的超时时间，覆盖`set_response_timeout()`的设置。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use my_redis::client::Client;
+    ///
+    /// # async fn example() -> my_redis::Result<()> {
+    /// let mut client = Client::connect("127.0.0.1:6379").await?;
+    /// let value = client
+    ///     .with_timeout(Duration::from_millis(100))
+    ///     .get("foo")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_timeout(&mut self, timeout: Duration) -> &mut Client {
+        self.next_timeout = Some(timeout);
+        self
+    }
+
+    /// 创建一个`Builder`，用于配置更多的连接选项。
+    ///
+    /// `addr`的格式为`host:port`。
+    pub fn builder(addr: impl ToString) -> Builder {
+        Builder {
+            addr: addr.to_string(),
+            options: ConnectOptions::default(),
+            tls: None,
+            unix_socket: None,
+            response_timeout: None,
+        }
+    }
+
+    /// 认证当前连接。对应`Auth`命令。
+    ///
+    /// # Errors
+    /// 如果认证失败，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn auth(&mut self, username: Option<&str>, password: &str) -> crate::Result<()> {
+        let frame = Auth::new(username.map(str::to_string), password).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 切换连接使用的协议版本，返回服务器的信息。对应`Hello`命令。
+    ///
+    /// `protover`为`None`时不切换协议版本。切换为 RESP3 后，订阅的信息以`Push`的形式发送，
+    /// `Subscriber`同样可以处理。
     ///
     /// # Output
-    /// 如果成功获取，返回`Ok(Some(data))`；
-    /// 如果没有对应的 key，返回`Ok(None)`；
-    /// 如果发送请求或读取响应出错，返回`Err`。
-    pub async fn get(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
-        // 创建一个`Get`命令并转化为`Frame`。
-        let frame = Get::new(key).into_frame();
-        // 写入`Get`请求。
-        self.connection.write_frame(&frame).await?;
+    /// 返回`[ (name, value) ]`，包括`server`、`version`、`proto`、`id`、`mode`、`role`等字段。
+    ///
+    /// # Errors
+    /// 如果协议版本不支持，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn hello(&mut self, protover: Option<u64>) -> crate::Result<Vec<(String, Frame)>> {
+        self.hello_cmd(Hello::new(protover), protover).await
+    }
+
+    /// 发送`Hello`命令，成功后将连接的协议版本切换为`protover`。
+    async fn hello_cmd(
+        &mut self,
+        hello: Hello,
+        protover: Option<u64>,
+    ) -> crate::Result<Vec<(String, Frame)>> {
+        self.write_request(&hello.into_frame()).await?;
+
+        // RESP2 的连接中响应是键和值交替出现的数组。
+        let pairs = match self.read_response().await? {
+            Frame::Map(pairs) => pairs,
+            Frame::Array(items) if items.len() % 2 == 0 => {
+                let mut items = items.into_iter();
+                std::iter::from_fn(|| Some((items.next()?, items.next()?))).collect()
+            }
+            frame => return Err(frame.to_error()),
+        };
+        if let Some(protocol) = protover.and_then(Protocol::from_version) {
+            self.connection.set_protocol(protocol);
+        }
+        pairs
+            .into_iter()
+            .map(|(name, value)| match name {
+                Frame::Bulk(name) => Ok((String::from_utf8_lossy(&name).into_owned(), value)),
+                frame => Err(frame.to_error()),
+            })
+            .collect()
+    }
+
+    /// 创建或者修改 ACL 用户，规则按照顺序应用。对应`Acl SetUser`命令。
+    ///
+    /// # Errors
+    /// 如果有不合法的规则，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn acl_setuser(&mut self, username: &str, rules: &[&str]) -> crate::Result<()> {
+        let rules = rules.iter().map(|rule| rule.to_string()).collect();
+        let frame = AclCommand::set_user(username, rules).into_frame();
+        self.write_request(&frame).await?;
 
-        // 等待响应帧。
-        // 处理`Simple`和`Bulk`，`Null`表示 key 不存在。
         match self.read_response().await? {
-            Frame::Simple(value) => Ok(Some(value.into())),
-            Frame::Bulk(value) => Ok(Some(value)),
-            Frame::Null => Ok(None),
+            Frame::Simple(response) if response == "OK" => Ok(()),
             frame => Err(frame.to_error()),
         }
     }
 
-    /// 设置 key-entry，未设置过期时间。对应`Set`命令。
+    /// 删除 ACL 用户，返回删除的个数。对应`Acl DelUser`命令。
+    ///
+    /// # Errors
+    /// 如果删除默认用户，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn acl_deluser(&mut self, usernames: &[&str]) -> crate::Result<u64> {
+        let usernames = usernames.iter().map(|name| name.to_string()).collect();
+        let frame = AclCommand::del_user(usernames).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 列出所有 ACL 用户及其规则。对应`Acl List`命令。
     ///
     /// # Errors
     /// 如果发送请求或读取响应出错，返回`Err`。
-    pub async fn set(&mut self, key: &str, value: Bytes) -> crate::Result<()> {
-        // 创建一个`Set`然后传递给`set_cmd()`，后者负责完成操作。
-        self.set_cmd(Set::new(key, value, None)).await
+    pub async fn acl_list(&mut self) -> crate::Result<Vec<String>> {
+        let frame = AclCommand::list().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(users) => users
+                .into_iter()
+                .map(|user| match user {
+                    Frame::Bulk(user) => Ok(String::from_utf8_lossy(&user).into_owned()),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
     }
 
-    /// 设置 key-entry，设置了过期时间。对应`Set`命令。
+    /// 获取当前连接使用的 ACL 用户名。对应`Acl WhoAmI`命令。
     ///
     /// # Errors
     /// 如果发送请求或读取响应出错，返回`Err`。
-    pub async fn set_expires(
-        &mut self,
-        key: &str,
-        value: Bytes,
-        expiration: Duration,
-    ) -> crate::Result<()> {
-        // 创建一个`Set`然后传递给`set_cmd()`，后者负责完成操作。
-        self.set_cmd(Set::new(key, value, Some(expiration))).await
+    pub async fn acl_whoami(&mut self) -> crate::Result<String> {
+        let frame = AclCommand::whoami().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(user) => Ok(String::from_utf8_lossy(&user).into_owned()),
+            frame => Err(frame.to_error()),
+        }
     }
 
-    /// 真正完成`Set`操作的核心函数。
+    /// 告诉服务器下一条命令是`ASK`重定向过来的。对应`Asking`命令。
     ///
     /// # Errors
     /// 如果发送请求或读取响应出错，返回`Err`。
-    async fn set_cmd(&mut self, cmd: Set) -> crate::Result<()> {
-        // 创建一个`Set`命令并转化为`Frame`。
-        let frame = cmd.into_frame();
-        // 写入`Get`请求。
-        self.connection.write_frame(&frame).await?;
+    pub async fn asking(&mut self) -> crate::Result<()> {
+        let frame = Asking::new().into_frame();
+        self.write_request(&frame).await?;
 
-        // 等待响应帧。
-        // 只处理`Simple`。
         match self.read_response().await? {
             Frame::Simple(response) if response == "OK" => Ok(()),
             frame => Err(frame.to_error()),
         }
     }
 
-    /// 向给定的信道发布信息。对应`Publish`命令。
+    /// 获取集群中所有槽区间及其所属的节点。对应`Cluster Slots`命令。
     ///
-    /// # Output
-    /// 如果成功则返回订阅者的数量。如果发送请求或读取响应出错，返回`Err`。
-    pub async fn publish(&mut self, channel: &str, message: Bytes) -> crate::Result<u64> {
-        // 创建`Publish`并转换为`Frame`
-        let frame = Publish::new(channel, message).into_frame();
-
-        // 写入请求
-        self.connection.write_frame(&frame).await?;
+    /// # Errors
+    /// 如果服务器没有开启集群模式，或者发送请求、读取响应出错，返回`Err`。
+    pub async fn cluster_slots(&mut self) -> crate::Result<Vec<SlotRange>> {
+        let frame = ClusterCommand::slots().into_frame();
+        self.write_request(&frame).await?;
 
-        // 等待响应
         match self.read_response().await? {
-            Frame::Integer(response) => Ok(response),
+            Frame::Array(ranges) => ranges.into_iter().map(slot_range).collect(),
             frame => Err(frame.to_error()),
         }
     }
 
-    /// 订阅指定信道，将`Client`封装为`Subscriber`。对应`Subscribe`命令。
+    /// 计算 key 所在的槽。对应`Cluster KeySlot`命令。
     ///
-    /// # Output
-    /// 如果成功则返回`Subscriber`。如果发送请求或读取响应出错，返回`Err`。
-    pub async fn subscribe(mut self, channels: Vec<String>) -> crate::Result<Subscriber> {
-        // 向客户端发出请求并等待响应
-        self.subscribe_cmd(&channels).await?;
-
-        // 转换为`Subscriber`
-        Ok(Subscriber {
-            client: self,
-            subscribed_channels: channels,
-        })
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn cluster_keyslot(&mut self, key: &str) -> crate::Result<u16> {
+        let frame = ClusterCommand::key_slot(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(slot) => Ok(slot as u16),
+            frame => Err(frame.to_error()),
+        }
     }
 
-    /// 真正完成`Subscribe`操作的核心函数
+    /// 获取服务器支持的命令的数量。对应`Command Count`命令。
     ///
     /// # Errors
     /// 如果发送请求或读取响应出错，返回`Err`。
-    async fn subscribe_cmd(&mut self, channels: &[String]) -> crate::Result<()> {
-        // 转换命令为`Frame`
-        let frame = Subscribe::new(channels.to_vec()).into_frame();
+    pub async fn command_count(&mut self) -> crate::Result<u64> {
+        let frame = CommandCommand::count().into_frame();
+        self.write_request(&frame).await?;
 
-        // 写入请求
-        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
 
-        // 对于每个信道的订阅请求，服务端都会发送一个确认信息
-        for channel in channels {
-            // 读取响应
-            let response = self.read_response().await?;
+    /// 获取命令的信息，`names`为空时获取所有命令。对应`Command Info`命令。
+    ///
+    /// # Output
+    /// 与`names`一一对应，服务器不支持的命令为`None`。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn command_info(
+        &mut self,
+        names: &[&str],
+    ) -> crate::Result<Vec<Option<CommandInfo>>> {
+        let names = names.iter().map(|name| name.to_string()).collect();
+        let frame = CommandCommand::info(names).into_frame();
+        self.write_request(&frame).await?;
 
-            // 验证，要求所有订阅请求都成功
-            match response {
-                Frame::Array(ref frame) => match frame.as_slice() {
-                    // 响应信息格式如下
-                    // [ "subscribe", channel, num-subscribed ]
-                    [subscribe, schannel, ..]
-                        if *subscribe == "subscribe" && *schannel == channel => {}
-                    _ => return Err(response.to_error()),
-                },
-                frame => return Err(frame.to_error()),
-            };
+        match self.read_response().await? {
+            Frame::Array(infos) => infos.into_iter().map(command_info).collect(),
+            frame => Err(frame.to_error()),
         }
+    }
 
-        Ok(())
+    /// 重置连接的状态。对应`Reset`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn reset(&mut self) -> crate::Result<()> {
+        let frame = Reset::new().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "RESET" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
     }
 
-    /// 测试连接。对应`Ping`命令。
+    /// 获取 key 对应的 value。对应`Get`命令。
     ///
     /// # Output
-    /// 如果成功就返回响应数据。如果发送请求或读取响应出错，返回`Err`。
-    pub async fn ping(&mut self, msg: Option<Bytes>) -> crate::Result<Bytes> {
-        let frame = Ping::new(msg).into_frame();
-        self.connection.write_frame(&frame).await?;
+    /// 如果成功获取，返回`Ok(Some(data))`；
+    /// 如果没有对应的 key，返回`Ok(None)`；
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn get(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        // 创建一个`Get`命令并转化为`Frame`。
+        let frame = Get::new(key).into_frame();
+        // 写入`Get`请求。
+        self.write_request(&frame).await?;
 
+        // 等待响应帧。
+        // 处理`Simple`和`Bulk`，`Null`表示 key 不存在。
         match self.read_response().await? {
-            Frame::Simple(value) => Ok(value.into()),
-            Frame::Bulk(value) => Ok(value),
+            Frame::Simple(value) => Ok(Some(value.into())),
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
             frame => Err(frame.to_error()),
         }
     }
 
-    /// 从 socket 中读取响应帧。
+    /// 获取 key 对应的 value，并通过`FromValue`转换为`T`。对应`Get`命令。
     ///
     /// # Output
-    /// 如果成功则返回读取到的响应帧。
-    /// 如果读取响应帧失败，或者读取到`Frame::Error`，返回`Err`。
-    /// 如果服务器关闭了，也返回`Err`。
-    async fn read_response(&mut self) -> crate::Result<Frame> {
-        let response = self.connection.read_frame().await?;
-        match response {
-            // 如果返回`Error Frame`，抛出错误
-            Some(Frame::Error(msg)) => Err(msg.into()),
-            Some(frame) => Ok(frame),
-            None => {
-                let err = Error::new(ErrorKind::ConnectionReset, "服务器关闭了连接");
-                Err(err.into())
-            }
+    /// key 不存在时返回`Ok(None)`。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，或者 value 无法转换为`T`，返回`Err`。
+    pub async fn get_value<T: FromValue>(&mut self, key: &str) -> crate::Result<Option<T>> {
+        match self.get(key).await? {
+            Some(value) => Ok(Some(T::from_value(value)?)),
+            None => Ok(None),
         }
     }
-}
 
-impl Subscriber {
-    pub fn get_subscribed(&self) -> &[String] {
-        &self.subscribed_channels
+    /// 通过`ToValue`将`value`转换为字节并设置 key-entry，未设置过期时间。对应`Set`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn set_value<T: ToValue + ?Sized>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> crate::Result<()> {
+        self.set(key, value.to_value()).await
     }
 
-    /// 获取已订阅的信道的信息，如果没有就等待。
+    /// 获取 key 对应的 JSON 格式的 value，并反序列化为`T`。对应`Get`命令。
     ///
     /// # Output
-    /// 如果成功则返回`Ok(Some(msg))`。
-    /// 返回`Ok(None)`表示`socket`关闭了。
-    pub async fn next_message(&mut self) -> crate::Result<Option<Message>> {
-        match self.client.connection.read_frame().await? {
-            Some(mframe) => match mframe {
-                Frame::Array(ref frame) => match frame.as_slice() {
-                    [message, channel, content] if *message == "message" => Ok(Some(Message {
-                        channel: channel.to_string(),
-                        content: Bytes::from(content.to_string()),
-                    })),
-                    _ => Err(mframe.to_error()),
-                },
-                frame => Err(frame.to_error()),
+    /// key 不存在时返回`Ok(None)`。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，或者 value 不是合法的`T`的 JSON，返回`Err`。
+    #[cfg(feature = "json")]
+    pub async fn get_json<T: serde::de::DeserializeOwned>(
+        &mut self,
+        key: &str,
+    ) -> crate::Result<Option<T>> {
+        match self.get(key).await? {
+            Some(value) => match serde_json::from_slice(&value) {
+                Ok(value) => Ok(Some(value)),
+                Err(err) => Err(crate::Error::Other(err.into())),
             },
             None => Ok(None),
         }
     }
 
-    /// 发送信号帧，告诉服务端客户端已经关闭了，
-    /// 让客户端结束`Subscriber`的`apply()`。
+    /// 将`value`序列化为 JSON 并设置 key-entry，未设置过期时间。对应`Set`命令。
     ///
     /// # Errors
-    /// 如果请求发送失败，返回`Err`。
-    pub async fn send_ctrlc_frame(&mut self) -> crate::Result<()> {
-        let frame = Frame::Simple("shutdown".to_string());
-        self.client.connection.write_frame(&frame).await?;
-        Ok(())
+    /// 如果`value`无法序列化，或者发送请求或读取响应出错，返回`Err`。
+    #[cfg(feature = "json")]
+    pub async fn set_json<T: serde::Serialize + ?Sized>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> crate::Result<()> {
+        let json = serde_json::to_vec(value).map_err(|err| crate::Error::Other(err.into()))?;
+        self.set(key, Bytes::from(json)).await
     }
+
+    /// 获取 key 对应的 value 并写入`dst`，对应`Get`命令。
+    ///
+    /// 大型 value 会分块写入`dst`，不需要将整个 value 放在内存中，见`Connection::read_frame_streaming()`。
+    ///
+    /// # Output
+    /// 返回写入的字节数，key 不存在时返回`Ok(None)`。
+    ///
+    /// # Errors
+    /// 如果发送请求、读取响应或写入`dst`出错，返回`Err`。
+    pub async fn get_to<W>(&mut self, key: &str, dst: &mut W) -> crate::Result<Option<u64>>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let frame = Get::new(key).into_frame();
+        self.write_request(&frame).await?;
+
+        // 超时限制的是整个响应，包括分块读取的数据。
+        match self.request_timeout() {
+            Some(timeout) => {
+                let response = time::timeout(timeout, self.read_value_to(dst)).await;
+                self.check_timeout(response)?
+            }
+            None => self.read_value_to(dst).await,
+        }
+    }
+
+    /// 读取`Get`的响应并写入`dst`，见`get_to()`。
+    async fn read_value_to<W>(&mut self, dst: &mut W) -> crate::Result<Option<u64>>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        match self.connection.read_frame_streaming().await? {
+            Some(StreamedFrame::Bulk(len)) => {
+                let copied = async {
+                    while let Some(chunk) = self.connection.read_bulk_chunk().await? {
+                        dst.write_all(&chunk).await?;
+                    }
+                    Ok::<_, crate::Error>(())
+                }
+                .await;
+                // 数据没有读取完，连接中剩下的部分无法再解析，连接不再可用。
+                if copied.is_err() {
+                    self.broken = true;
+                }
+                copied?;
+                dst.flush().await?;
+                Ok(Some(len as u64))
+            }
+            Some(StreamedFrame::Frame(Frame::Bulk(value))) => {
+                dst.write_all(&value).await?;
+                dst.flush().await?;
+                Ok(Some(value.len() as u64))
+            }
+            Some(StreamedFrame::Frame(Frame::Null)) => Ok(None),
+            Some(StreamedFrame::Frame(Frame::Error(msg))) => Err(crate::Error::from_server(msg)),
+            Some(StreamedFrame::Frame(frame)) => Err(frame.to_error()),
+            None => Err(crate::Error::ConnectionClosed),
+        }
+    }
+
+    /// 设置 key-entry，value 从`src`中读取，共`len`个字节，未设置过期时间。对应`Set`命令。
+    ///
+    /// value 会分块从`src`写入连接，不需要将整个 value 放在内存中。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，或者`src`中的数据少于`len`个字节，返回`Err`。
+    /// `src`中的数据不足时连接中已经写入了不完整的命令，连接不能再使用。
+    pub async fn set_from<R>(&mut self, key: &str, src: &mut R, len: u64) -> crate::Result<()>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        let args = [Bytes::from_static(b"set"), Bytes::from(key.to_string())];
+        self.begin_request()?;
+        self.connection.write_command_from(&args, len, src).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 设置 key-entry，未设置过期时间。对应`Set`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn set(&mut self, key: &str, value: Bytes) -> crate::Result<()> {
+        // 创建一个`Set`然后传递给`set_cmd()`，后者负责完成操作。
+        self.set_cmd(Set::new(key, value, None)).await
+    }
+
+    /// 设置 key-entry，设置了过期时间。对应`Set`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn set_expires(
+        &mut self,
+        key: &str,
+        value: Bytes,
+        expiration: Duration,
+    ) -> crate::Result<()> {
+        // 创建一个`Set`然后传递给`set_cmd()`，后者负责完成操作。
+        self.set_cmd(Set::new(key, value, Some(expiration))).await
+    }
+
+    /// 设置 key-entry，保留原有的过期时间。对应`Set ... KEEPTTL`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn set_keep_ttl(&mut self, key: &str, value: Bytes) -> crate::Result<()> {
+        self.set_cmd(Set::new(key, value, None).keep_ttl()).await
+    }
+
+    /// 设置 key-entry 并返回原有的值。对应`Set ... GET`命令。
+    ///
+    /// # Output
+    /// 如果 key 原来不存在，返回`Ok(None)`。
+    /// 如果原有的值不是字符串，此时不会设置，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn set_get(&mut self, key: &str, value: Bytes) -> crate::Result<Option<Bytes>> {
+        let frame = Set::new(key, value, None).get().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 真正完成`Set`操作的核心函数。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    async fn set_cmd(&mut self, cmd: Set) -> crate::Result<()> {
+        // 创建一个`Set`命令并转化为`Frame`。
+        let frame = cmd.into_frame();
+        // 写入`Get`请求。
+        self.write_request(&frame).await?;
+
+        // 等待响应帧。
+        // 只处理`Simple`。
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 将 key 中存储的数字加一，返回加一后的值。对应`Incr`命令。
+    ///
+    /// # Errors
+    /// 如果 value 不是整数或者结果溢出，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn incr(&mut self, key: &str) -> crate::Result<i64> {
+        self.incr_cmd(Incr::new(key)).await
+    }
+
+    /// 将 key 中存储的数字减一，返回减一后的值。对应`Decr`命令。
+    ///
+    /// # Errors
+    /// 与`incr()`相同。
+    pub async fn decr(&mut self, key: &str) -> crate::Result<i64> {
+        self.incr_cmd(Incr::decr(key)).await
+    }
+
+    /// 将 key 中存储的数字加上`increment`，返回相加后的值。对应`IncrBy`命令。
+    ///
+    /// # Errors
+    /// 与`incr()`相同。
+    pub async fn incr_by(&mut self, key: &str, increment: i64) -> crate::Result<i64> {
+        self.incr_cmd(Incr::incr_by(key, increment)).await
+    }
+
+    /// 将 key 中存储的数字减去`decrement`，返回相减后的值。对应`DecrBy`命令。
+    ///
+    /// # Errors
+    /// 与`incr()`相同。
+    pub async fn decr_by(&mut self, key: &str, decrement: i64) -> crate::Result<i64> {
+        self.incr_cmd(Incr::decr_by(key, decrement)).await
+    }
+
+    /// 将 key 中存储的浮点数加上`increment`，返回相加后的值。对应`IncrByFloat`命令。
+    ///
+    /// # Errors
+    /// 如果 value 不是浮点数、结果为 NaN 或无穷大，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn incr_by_float(&mut self, key: &str, increment: f64) -> crate::Result<f64> {
+        let frame = IncrByFloat::new(key, increment).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(value) => std::str::from_utf8(&value)
+                .ok()
+                .and_then(|value| value.parse::<f64>().ok())
+                .ok_or_else(|| crate::Error::Protocol("浮点数格式错误".to_string())),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 真正完成计数器操作的核心函数。
+    async fn incr_cmd(&mut self, cmd: Incr) -> crate::Result<i64> {
+        let frame = cmd.into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 为已经存在的 key 设置过期时间。对应`PExpire`命令。
+    ///
+    /// # Output
+    /// 如果设置成功返回`true`，如果 key 不存在返回`false`。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn expire(&mut self, key: &str, expiration: Duration) -> crate::Result<bool> {
+        let frame = Expire::new(key, expiration).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(n) => Ok(n == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 为已经存在的 key 设置过期的时刻。对应`PExpireAt`命令。
+    ///
+    /// # Output
+    /// 如果设置成功返回`true`，如果 key 不存在返回`false`。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn expire_at(&mut self, key: &str, when: SystemTime) -> crate::Result<bool> {
+        let frame = ExpireAt::new(key, when).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(n) => Ok(n == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取 key 对应的 value 的类型名称。对应`Type`命令。
+    ///
+    /// # Output
+    /// 返回`string`、`list`、`hash`等类型名称，如果 key 不存在返回`none`。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn key_type(&mut self, key: &str) -> crate::Result<String> {
+        let frame = Type::new(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(type_name) => Ok(type_name),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取 key 对应的 value 的编码名称。对应`Object Encoding`命令。
+    ///
+    /// # Output
+    /// 返回`int`、`embstr`、`hashtable`等编码名称，如果 key 不存在返回`None`。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn object_encoding(&mut self, key: &str) -> crate::Result<Option<String>> {
+        let frame = Object::encoding(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(encoding) => Ok(Some(String::from_utf8_lossy(&encoding).into_owned())),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取 key 距离最后一次被访问的时长，精确到秒。对应`Object IdleTime`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在返回`None`。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn object_idletime(&mut self, key: &str) -> crate::Result<Option<Duration>> {
+        let frame = Object::idletime(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(secs) => Ok(Some(Duration::from_secs(secs as u64))),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取 key 被访问的次数。对应`Object Freq`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在返回`None`。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn object_freq(&mut self, key: &str) -> crate::Result<Option<u64>> {
+        let frame = Object::freq(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(Some(count as u64)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 让服务端等待`duration`后再返回，用于测试超时。对应`Debug Sleep`命令。
+    ///
+    /// # Output
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn debug_sleep(&mut self, duration: Duration) -> crate::Result<()> {
+        let frame = DebugCommand::sleep(duration).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取 key 的内部信息。对应`Debug Object`命令。
+    ///
+    /// # Output
+    /// 返回`type:string encoding:embstr ...`格式的字符串。
+    /// 如果 key 不存在，或者发送请求、读取响应出错，返回`Err`。
+    pub async fn debug_object(&mut self, key: &str) -> crate::Result<String> {
+        let frame = DebugCommand::object(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(info) => Ok(info),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 开启或关闭服务端主动清除过期 key。对应`Debug Set-Active-Expire`命令。
+    ///
+    /// # Output
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn debug_set_active_expire(&mut self, enabled: bool) -> crate::Result<()> {
+        let frame = DebugCommand::set_active_expire(enabled).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 删除 key，很大的 value 会在服务端的后台释放内存。对应`Unlink`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回被删除的 key 的数量。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn unlink(&mut self, keys: Vec<String>) -> crate::Result<u64> {
+        let frame = Unlink::new(keys).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 更新 key 的最后访问时刻。对应`Touch`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回存在的 key 的数量。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn touch(&mut self, keys: Vec<String>) -> crate::Result<u64> {
+        let frame = Touch::new(keys).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取 key 和 value 占用的近似字节数。对应`Memory Usage`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在返回`None`。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn memory_usage(&mut self, key: &str) -> crate::Result<Option<u64>> {
+        let frame = Memory::usage(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(size) => Ok(Some(size as u64)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取服务端内存使用情况的统计。对应`Memory Stats`命令。
+    ///
+    /// # Output
+    /// 返回统计项的名称和值，例如`("dataset.bytes", "1024")`。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn memory_stats(&mut self) -> crate::Result<Vec<(String, String)>> {
+        let frame = Memory::stats().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) => items
+                .chunks(2)
+                .map(|pair| match pair {
+                    [name, value] => Ok((name.to_string(), value.to_string())),
+                    _ => Err(Frame::Array(pair.to_vec()).to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取服务器的信息。对应`Info`命令。
+    ///
+    /// # Output
+    /// 返回`# Section`开头、每行一个`name:value`的文本，`sections`为空时返回所有部分。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn info(&mut self, sections: &[&str]) -> crate::Result<String> {
+        let sections = sections.iter().map(|section| section.to_string()).collect();
+        let frame = Info::new(sections).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(info) | Frame::Verbatim(_, info) => {
+                Ok(String::from_utf8_lossy(&info).into_owned())
+            }
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取最新的慢命令日志。对应`Slowlog Get`命令。
+    ///
+    /// # Output
+    /// 返回最多`count`条记录，新的记录在前面，`count`为`None`时最多返回 10 条。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn slowlog_get(&mut self, count: Option<usize>) -> crate::Result<Vec<SlowlogEntry>> {
+        let frame = Slowlog::get(count).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(entries) => entries.iter().map(parse_slowlog_entry).collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取慢命令日志的条数。对应`Slowlog Len`命令。
+    ///
+    /// # Output
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn slowlog_len(&mut self) -> crate::Result<u64> {
+        let frame = Slowlog::len().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 清空慢命令日志。对应`Slowlog Reset`命令。
+    ///
+    /// # Output
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn slowlog_reset(&mut self) -> crate::Result<()> {
+        let frame = Slowlog::reset().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 实时查看服务器执行的命令，将`Client`封装为`Monitored`。对应`Monitor`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回`Monitored`。如果发送请求或读取响应出错，返回`Err`。
+    pub async fn monitor(mut self) -> crate::Result<Monitored> {
+        let frame = Monitor::new().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(Monitored { client: self }),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 设置哈希表中 field 对应的 value。对应`HSet`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回新增的 field 的数量。
+    /// 如果 key 不是哈希表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn hset(&mut self, key: &str, fields: Vec<(Bytes, Bytes)>) -> crate::Result<u64> {
+        let frame = HSet::new(key, fields).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(added) => Ok(added as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取哈希表中 field 对应的 value。对应`HGet`命令。
+    ///
+    /// # Output
+    /// 如果 key 或者 field 不存在，返回`Ok(None)`。
+    /// 如果 key 不是哈希表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn hget(&mut self, key: &str, field: Bytes) -> crate::Result<Option<Bytes>> {
+        let frame = HGet::new(key, field).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 删除哈希表中的 field。对应`HDel`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回被删除的 field 的数量。
+    /// 如果 key 不是哈希表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn hdel(&mut self, key: &str, fields: Vec<Bytes>) -> crate::Result<u64> {
+        let frame = HDel::new(key, fields).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(removed) => Ok(removed as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取哈希表中所有的 field 和 value。对应`HGetAll`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回空的`Vec`。
+    /// 如果 key 不是哈希表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn hgetall(&mut self, key: &str) -> crate::Result<Vec<(Bytes, Bytes)>> {
+        let frame = HGetAll::new(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            // RESP3 的连接中响应为`Map`。
+            Frame::Map(pairs) => pairs
+                .into_iter()
+                .map(|pair| match pair {
+                    (Frame::Bulk(field), Frame::Bulk(value)) => Ok((field, value)),
+                    (field, value) => Err(Frame::Map(vec![(field, value)]).to_error()),
+                })
+                .collect(),
+            Frame::Array(items) => items
+                .chunks(2)
+                .map(|pair| match pair {
+                    [Frame::Bulk(field), Frame::Bulk(value)] => Ok((field.clone(), value.clone())),
+                    _ => Err(Frame::Array(pair.to_vec()).to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 随机获取哈希表中的一个 field。对应`HRandField`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`Ok(None)`。
+    /// 如果 key 不是哈希表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn hrandfield(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        let frame = HRandField::new(key, None, false).into_frame();
+        self.write_request(&frame).await?;
+        random_one(self.read_response().await?)
+    }
+
+    /// 随机获取哈希表中的多个 field。对应带有`count`的`HRandField`命令。
+    ///
+    /// `count`为正数时返回不重复的 field，为负数时同一个 field 可能出现多次。
+    ///
+    /// # Errors
+    /// 与`hrandfield()`相同。
+    pub async fn hrandfield_count(&mut self, key: &str, count: i64) -> crate::Result<Vec<Bytes>> {
+        let frame = HRandField::new(key, Some(count), false).into_frame();
+        self.write_request(&frame).await?;
+        random_many(self.read_response().await?)
+    }
+
+    /// 与`hrandfield_count()`相同，但同时返回 value。对应`HRandField ... WITHVALUES`命令。
+    pub async fn hrandfield_withvalues(
+        &mut self,
+        key: &str,
+        count: i64,
+    ) -> crate::Result<Vec<(Bytes, Bytes)>> {
+        let frame = HRandField::new(key, Some(count), true).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) => items
+                .chunks(2)
+                .map(|pair| match pair {
+                    [Frame::Bulk(field), Frame::Bulk(value)] => Ok((field.clone(), value.clone())),
+                    _ => Err(Frame::Array(pair.to_vec()).to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 将 value 依次插入到列表的头部。对应`LPush`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回插入后列表的长度。
+    /// 如果 key 不是列表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn lpush(&mut self, key: &str, values: Vec<Bytes>) -> crate::Result<u64> {
+        self.push_cmd(Push::lpush(key, values)).await
+    }
+
+    /// 将 value 依次插入到列表的尾部。对应`RPush`命令。
+    ///
+    /// # Output
+    /// 与`lpush()`相同。
+    pub async fn rpush(&mut self, key: &str, values: Vec<Bytes>) -> crate::Result<u64> {
+        self.push_cmd(Push::rpush(key, values)).await
+    }
+
+    /// 真正完成`LPush`和`RPush`操作的核心函数。
+    async fn push_cmd(&mut self, cmd: Push) -> crate::Result<u64> {
+        let frame = cmd.into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 弹出列表头部的元素。对应`LPop`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`Ok(None)`。
+    /// 如果 key 不是列表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn lpop(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        self.pop_cmd(Pop::lpop(key)).await
+    }
+
+    /// 弹出列表尾部的元素。对应`RPop`命令。
+    ///
+    /// # Output
+    /// 与`lpop()`相同。
+    pub async fn rpop(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        self.pop_cmd(Pop::rpop(key)).await
+    }
+
+    /// 真正完成`LPop`和`RPop`操作的核心函数。
+    async fn pop_cmd(&mut self, cmd: Pop) -> crate::Result<Option<Bytes>> {
+        let frame = cmd.into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 阻塞式地弹出列表头部的元素。对应`BLPop`命令。
+    ///
+    /// 依次检查每个 key，从第一个非空的列表中弹出元素。如果所有列表都为空，
+    /// 等待直到有元素被插入或者超时，`timeout`为`None`时一直等待。
+    ///
+    /// # Output
+    /// 如果成功则返回 key 和弹出的元素，超时返回`Ok(None)`。
+    /// 如果 key 不是列表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn blpop(
+        &mut self,
+        keys: Vec<String>,
+        timeout: Option<Duration>,
+    ) -> crate::Result<Option<(String, Bytes)>> {
+        self.blocking_pop_cmd(BlockingPop::blpop(keys, timeout))
+            .await
+    }
+
+    /// 阻塞式地弹出列表尾部的元素。对应`BRPop`命令。
+    ///
+    /// # Output
+    /// 与`blpop()`相同。
+    pub async fn brpop(
+        &mut self,
+        keys: Vec<String>,
+        timeout: Option<Duration>,
+    ) -> crate::Result<Option<(String, Bytes)>> {
+        self.blocking_pop_cmd(BlockingPop::brpop(keys, timeout))
+            .await
+    }
+
+    /// 真正完成`BLPop`和`BRPop`操作的核心函数。
+    async fn blocking_pop_cmd(
+        &mut self,
+        cmd: BlockingPop,
+    ) -> crate::Result<Option<(String, Bytes)>> {
+        let frame = cmd.into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) => match &items[..] {
+                [key, Frame::Bulk(value)] => Ok(Some((key.to_string(), value.clone()))),
+                _ => Err(Frame::Array(items).to_error()),
+            },
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 依次检查每个 key，从第一个非空的列表中弹出最多`count`个元素。对应`LMPop`命令。
+    ///
+    /// `front`为`true`时从头部弹出。
+    ///
+    /// # Output
+    /// 如果成功则返回 key 和弹出的元素，所有列表都为空时返回`Ok(None)`。
+    /// 如果 key 不是列表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn lmpop(
+        &mut self,
+        keys: Vec<String>,
+        front: bool,
+        count: u64,
+    ) -> crate::Result<Option<(String, Vec<Bytes>)>> {
+        self.lmpop_cmd(LMPop::new(keys, front, count)).await
+    }
+
+    /// 与`lmpop()`相同，但是所有列表都为空时等待直到有元素被插入或者超时。对应`BLMPop`命令。
+    ///
+    /// `timeout`为`None`时一直等待，超时返回`Ok(None)`。
+    pub async fn blmpop(
+        &mut self,
+        keys: Vec<String>,
+        front: bool,
+        count: u64,
+        timeout: Option<Duration>,
+    ) -> crate::Result<Option<(String, Vec<Bytes>)>> {
+        self.lmpop_cmd(LMPop::blocking(keys, front, count, timeout))
+            .await
+    }
+
+    /// 真正完成`LMPop`和`BLMPop`操作的核心函数。
+    async fn lmpop_cmd(&mut self, cmd: LMPop) -> crate::Result<Option<(String, Vec<Bytes>)>> {
+        let frame = cmd.into_frame();
+        self.write_request(&frame).await?;
+
+        let Some((key, items)) = mpop_reply(self.read_response().await?)? else {
+            return Ok(None);
+        };
+        let values = items
+            .into_iter()
+            .map(|item| match item {
+                Frame::Bulk(value) => Ok(value),
+                frame => Err(frame.to_error()),
+            })
+            .collect::<crate::Result<_>>()?;
+        Ok(Some((key, values)))
+    }
+
+    /// 获取列表中下标在`[start, stop]`之间的元素，下标可以是负数。对应`LRange`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回空的`Vec`。
+    /// 如果 key 不是列表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn lrange(&mut self, key: &str, start: i64, stop: i64) -> crate::Result<Vec<Bytes>> {
+        let frame = LRange::new(key, start, stop).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    Frame::Bulk(value) => Ok(value),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取列表的长度。对应`LLen`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`0`。
+    /// 如果 key 不是列表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn llen(&mut self, key: &str) -> crate::Result<u64> {
+        let frame = LLen::new(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 查找列表中第`rank`个等于`element`的元素的下标。对应`LPos`命令。
+    ///
+    /// `rank`为负数时从尾部开始查找，`maxlen`不为`0`时最多比较`maxlen`个元素。
+    ///
+    /// # Output
+    /// 如果找不到，返回`None`。
+    /// 如果 key 不是列表、`rank`为`0`，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn lpos(
+        &mut self,
+        key: &str,
+        element: Bytes,
+        rank: i64,
+        maxlen: u64,
+    ) -> crate::Result<Option<u64>> {
+        let frame = LPos::new(key, element, rank, None, maxlen).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(index) => Ok(Some(index as u64)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 与`lpos()`相同，但是返回最多`count`个匹配的元素的下标，`count`为`0`时返回所有的。
+    /// 对应带有`COUNT`的`LPos`命令。
+    ///
+    /// # Errors
+    /// 与`lpos()`相同。
+    pub async fn lpos_count(
+        &mut self,
+        key: &str,
+        element: Bytes,
+        rank: i64,
+        count: u64,
+        maxlen: u64,
+    ) -> crate::Result<Vec<u64>> {
+        let frame = LPos::new(key, element, rank, Some(count), maxlen).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    Frame::Integer(index) => Ok(index as u64),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 在列表中第一个等于`pivot`的元素的前面（`before`为`true`）或者后面插入`element`。
+    /// 对应`LInsert`命令。
+    ///
+    /// # Output
+    /// 返回插入后列表的长度，如果 key 不存在返回`0`，如果`pivot`不存在返回`-1`。
+    /// 如果 key 不是列表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn linsert(
+        &mut self,
+        key: &str,
+        before: bool,
+        pivot: Bytes,
+        element: Bytes,
+    ) -> crate::Result<i64> {
+        let frame = LInsert::new(key, before, pivot, element).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 将列表中下标为`index`的元素设置为`element`。对应`LSet`命令。
+    ///
+    /// # Errors
+    /// 如果 key 不存在、不是列表、下标越界，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn lset(&mut self, key: &str, index: i64, element: Bytes) -> crate::Result<()> {
+        let frame = LSet::new(key, index, element).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 删除列表中等于`element`的元素。对应`LRem`命令。
+    ///
+    /// `count`为正数时从头部开始删除最多`count`个，为负数时从尾部开始删除，为`0`时删除所有的。
+    ///
+    /// # Output
+    /// 如果成功则返回被删除的元素的数量。
+    /// 如果 key 不是列表，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn lrem(&mut self, key: &str, count: i64, element: Bytes) -> crate::Result<u64> {
+        let frame = LRem::new(key, count, element).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(removed) => Ok(removed as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 向集合中添加成员。对应`SAdd`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回新增的成员的数量。
+    /// 如果 key 不是集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn sadd(&mut self, key: &str, members: Vec<Bytes>) -> crate::Result<u64> {
+        let frame = SAdd::new(key, members).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(added) => Ok(added as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 从集合中删除成员。对应`SRem`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回被删除的成员的数量。
+    /// 如果 key 不是集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn srem(&mut self, key: &str, members: Vec<Bytes>) -> crate::Result<u64> {
+        let frame = SRem::new(key, members).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(removed) => Ok(removed as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取集合中所有的成员。对应`SMembers`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回空的集合。
+    /// 如果 key 不是集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn smembers(&mut self, key: &str) -> crate::Result<HashSet<Bytes>> {
+        let frame = SMembers::new(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) | Frame::Set(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    Frame::Bulk(member) => Ok(member),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 判断`member`是否是集合的成员。对应`SIsMember`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`false`。
+    /// 如果 key 不是集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn sismember(&mut self, key: &str, member: Bytes) -> crate::Result<bool> {
+        let frame = SIsMember::new(key, member).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(n) => Ok(n == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取集合的成员数量。对应`SCard`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`0`。
+    /// 如果 key 不是集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn scard(&mut self, key: &str) -> crate::Result<u64> {
+        let frame = SCard::new(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 随机获取集合中的一个成员。对应`SRandMember`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`Ok(None)`。
+    /// 如果 key 不是集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn srandmember(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        let frame = SRandMember::new(key, None).into_frame();
+        self.write_request(&frame).await?;
+        random_one(self.read_response().await?)
+    }
+
+    /// 随机获取集合中的多个成员。对应带有`count`的`SRandMember`命令。
+    ///
+    /// `count`为正数时返回不重复的成员，为负数时同一个成员可能出现多次。
+    ///
+    /// # Errors
+    /// 与`srandmember()`相同。
+    pub async fn srandmember_count(&mut self, key: &str, count: i64) -> crate::Result<Vec<Bytes>> {
+        let frame = SRandMember::new(key, Some(count)).into_frame();
+        self.write_request(&frame).await?;
+        random_many(self.read_response().await?)
+    }
+
+    /// 获取多个集合的交集。对应`SInter`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回结果集合，不存在的 key 视为空集合。
+    /// 如果某个 key 不是集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn sinter(&mut self, keys: Vec<String>) -> crate::Result<HashSet<Bytes>> {
+        self.set_algebra_cmd(SetAlgebra::inter(keys)).await
+    }
+
+    /// 获取多个集合的并集。对应`SUnion`命令。
+    ///
+    /// # Output
+    /// 与`sinter()`相同。
+    pub async fn sunion(&mut self, keys: Vec<String>) -> crate::Result<HashSet<Bytes>> {
+        self.set_algebra_cmd(SetAlgebra::union(keys)).await
+    }
+
+    /// 获取第一个集合与其余集合的差集。对应`SDiff`命令。
+    ///
+    /// # Output
+    /// 与`sinter()`相同。
+    pub async fn sdiff(&mut self, keys: Vec<String>) -> crate::Result<HashSet<Bytes>> {
+        self.set_algebra_cmd(SetAlgebra::diff(keys)).await
+    }
+
+    /// 真正完成`SInter`、`SUnion`和`SDiff`操作的核心函数。
+    async fn set_algebra_cmd(&mut self, cmd: SetAlgebra) -> crate::Result<HashSet<Bytes>> {
+        let frame = cmd.into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) | Frame::Set(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    Frame::Bulk(member) => Ok(member),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 将多个集合的交集保存到`destination`中。对应`SInterStore`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回结果集合的成员数量。
+    /// 如果某个 key 不是集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn sinterstore(
+        &mut self,
+        destination: &str,
+        keys: Vec<String>,
+    ) -> crate::Result<u64> {
+        self.set_algebra_store_cmd(SetAlgebra::inter_store(destination, keys))
+            .await
+    }
+
+    /// 将多个集合的并集保存到`destination`中。对应`SUnionStore`命令。
+    ///
+    /// # Output
+    /// 与`sinterstore()`相同。
+    pub async fn sunionstore(
+        &mut self,
+        destination: &str,
+        keys: Vec<String>,
+    ) -> crate::Result<u64> {
+        self.set_algebra_store_cmd(SetAlgebra::union_store(destination, keys))
+            .await
+    }
+
+    /// 将第一个集合与其余集合的差集保存到`destination`中。对应`SDiffStore`命令。
+    ///
+    /// # Output
+    /// 与`sinterstore()`相同。
+    pub async fn sdiffstore(&mut self, destination: &str, keys: Vec<String>) -> crate::Result<u64> {
+        self.set_algebra_store_cmd(SetAlgebra::diff_store(destination, keys))
+            .await
+    }
+
+    /// 真正完成`SInterStore`、`SUnionStore`和`SDiffStore`操作的核心函数。
+    async fn set_algebra_store_cmd(&mut self, cmd: SetAlgebra) -> crate::Result<u64> {
+        let frame = cmd.into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 向有序集合中添加成员，`members`中的每一项为分数和成员。对应`ZAdd`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回新增的成员的数量。
+    /// 如果 key 不是有序集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn zadd(&mut self, key: &str, members: Vec<(f64, Bytes)>) -> crate::Result<u64> {
+        let frame = ZAdd::new(key, members).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(added) => Ok(added as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取有序集合中成员的分数。对应`ZScore`命令。
+    ///
+    /// # Output
+    /// 如果 key 或者成员不存在，返回`Ok(None)`。
+    /// 如果 key 不是有序集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn zscore(&mut self, key: &str, member: Bytes) -> crate::Result<Option<f64>> {
+        let frame = ZScore::new(key, member).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(score) => parse_score(&score).map(Some),
+            Frame::Double(score) => Ok(Some(score)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取有序集合中排名在`[start, stop]`之间的成员，下标可以是负数。对应`ZRange`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回空的`Vec`。
+    /// 如果 key 不是有序集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn zrange(&mut self, key: &str, start: i64, stop: i64) -> crate::Result<Vec<Bytes>> {
+        let frame = ZRange::new(key, start, stop, false).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    Frame::Bulk(member) => Ok(member),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 与`zrange()`相同，但同时返回成员的分数。对应`ZRange ... WITHSCORES`命令。
+    pub async fn zrange_withscores(
+        &mut self,
+        key: &str,
+        start: i64,
+        stop: i64,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let frame = ZRange::new(key, start, stop, true).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) => scored_members(items),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取有序集合中分数在`min`和`max`之间的成员。对应`ZRangeByScore`命令。
+    ///
+    /// `Bound::Excluded`表示不包含边界，`Bound::Unbounded`表示没有下界或者上界。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回空的`Vec`。
+    /// 如果 key 不是有序集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn zrangebyscore(
+        &mut self,
+        key: &str,
+        min: Bound<f64>,
+        max: Bound<f64>,
+    ) -> crate::Result<Vec<Bytes>> {
+        let frame = ZRangeByScore::new(key, min, max, false).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    Frame::Bulk(member) => Ok(member),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 与`zrangebyscore()`相同，但同时返回成员的分数。对应`ZRangeByScore ... WITHSCORES`命令。
+    pub async fn zrangebyscore_withscores(
+        &mut self,
+        key: &str,
+        min: Bound<f64>,
+        max: Bound<f64>,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let frame = ZRangeByScore::new(key, min, max, true).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) => scored_members(items),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 将有序集合中成员的分数加上`increment`。对应`ZIncrBy`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回新的分数。
+    /// 如果 key 不是有序集合、结果为 NaN，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn zincrby(
+        &mut self,
+        key: &str,
+        increment: f64,
+        member: Bytes,
+    ) -> crate::Result<f64> {
+        let frame = ZIncrBy::new(key, increment, member).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(score) => parse_score(&score),
+            Frame::Double(score) => Ok(score),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 从有序集合中删除成员。对应`ZRem`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回被删除的成员的数量。
+    /// 如果 key 不是有序集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn zrem(&mut self, key: &str, members: Vec<Bytes>) -> crate::Result<u64> {
+        let frame = ZRem::new(key, members).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(removed) => Ok(removed as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取成员在有序集合中的排名，排名从`0`开始。对应`ZRank`命令。
+    ///
+    /// # Output
+    /// 如果 key 或者成员不存在，返回`Ok(None)`。
+    /// 如果 key 不是有序集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn zrank(&mut self, key: &str, member: Bytes) -> crate::Result<Option<u64>> {
+        let frame = ZRank::new(key, member).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(rank) => Ok(Some(rank as u64)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 依次检查每个 key，从第一个非空的有序集合中弹出最多`count`个成员。对应`ZMPop`命令。
+    ///
+    /// `min`为`true`时弹出分数最小的成员，否则弹出分数最大的成员。
+    ///
+    /// # Output
+    /// 如果成功则返回 key 和弹出的成员和分数，所有有序集合都为空时返回`Ok(None)`。
+    /// 如果 key 不是有序集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn zmpop(
+        &mut self,
+        keys: Vec<String>,
+        min: bool,
+        count: u64,
+    ) -> crate::Result<Option<(String, Vec<(Bytes, f64)>)>> {
+        self.zmpop_cmd(ZMPop::new(keys, min, count)).await
+    }
+
+    /// 与`zmpop()`相同，但是所有有序集合都为空时等待直到有成员被添加或者超时。
+    /// 对应`BZMPop`命令。
+    ///
+    /// `timeout`为`None`时一直等待，超时返回`Ok(None)`。
+    pub async fn bzmpop(
+        &mut self,
+        keys: Vec<String>,
+        min: bool,
+        count: u64,
+        timeout: Option<Duration>,
+    ) -> crate::Result<Option<(String, Vec<(Bytes, f64)>)>> {
+        self.zmpop_cmd(ZMPop::blocking(keys, min, count, timeout))
+            .await
+    }
+
+    /// 真正完成`ZMPop`和`BZMPop`操作的核心函数。
+    async fn zmpop_cmd(
+        &mut self,
+        cmd: ZMPop,
+    ) -> crate::Result<Option<(String, Vec<(Bytes, f64)>)>> {
+        let frame = cmd.into_frame();
+        self.write_request(&frame).await?;
+
+        let Some((key, items)) = mpop_reply(self.read_response().await?)? else {
+            return Ok(None);
+        };
+        let members = items
+            .into_iter()
+            .map(|item| match item {
+                Frame::Array(pair) => match <[Frame; 2]>::try_from(pair) {
+                    Ok([Frame::Bulk(member), Frame::Bulk(score)]) => {
+                        Ok((member, parse_score(&score)?))
+                    }
+                    Ok(pair) => Err(Frame::Array(pair.into()).to_error()),
+                    Err(pair) => Err(Frame::Array(pair).to_error()),
+                },
+                frame => Err(frame.to_error()),
+            })
+            .collect::<crate::Result<_>>()?;
+        Ok(Some((key, members)))
+    }
+
+    /// 获取有序集合的成员数量。对应`ZCard`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`0`。
+    /// 如果 key 不是有序集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn zcard(&mut self, key: &str) -> crate::Result<u64> {
+        let frame = ZCard::new(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 随机获取有序集合中的一个成员。对应`ZRandMember`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`Ok(None)`。
+    /// 如果 key 不是有序集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn zrandmember(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        let frame = ZRandMember::new(key, None, false).into_frame();
+        self.write_request(&frame).await?;
+        random_one(self.read_response().await?)
+    }
+
+    /// 随机获取有序集合中的多个成员。对应带有`count`的`ZRandMember`命令。
+    ///
+    /// `count`为正数时返回不重复的成员，为负数时同一个成员可能出现多次。
+    ///
+    /// # Errors
+    /// 与`zrandmember()`相同。
+    pub async fn zrandmember_count(&mut self, key: &str, count: i64) -> crate::Result<Vec<Bytes>> {
+        let frame = ZRandMember::new(key, Some(count), false).into_frame();
+        self.write_request(&frame).await?;
+        random_many(self.read_response().await?)
+    }
+
+    /// 与`zrandmember_count()`相同，但同时返回分数。对应`ZRandMember ... WITHSCORES`命令。
+    pub async fn zrandmember_withscores(
+        &mut self,
+        key: &str,
+        count: i64,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let frame = ZRandMember::new(key, Some(count), true).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) => scored_members(items),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 向地理位置索引中添加成员。对应`GeoAdd`命令。
+    ///
+    /// `members`中的每一项为经度、纬度和成员。
+    ///
+    /// # Output
+    /// 如果成功则返回新增的成员的数量。
+    /// 如果 key 不是有序集合、坐标超出范围，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn geoadd(
+        &mut self,
+        key: &str,
+        members: Vec<(f64, f64, Bytes)>,
+    ) -> crate::Result<u64> {
+        let frame = GeoAdd::new(key, members).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(added) => Ok(added as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取成员的经度和纬度。对应`GeoPos`命令。
+    ///
+    /// # Output
+    /// 每个成员对应一项，不存在的成员为`None`。
+    /// 如果 key 不是有序集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn geopos(
+        &mut self,
+        key: &str,
+        members: Vec<Bytes>,
+    ) -> crate::Result<Vec<Option<(f64, f64)>>> {
+        let frame = GeoPos::new(key, members).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    Frame::Null => Ok(None),
+                    frame => coord(frame).map(Some),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 计算两个成员之间的距离，单位为`unit`。对应`GeoDist`命令。
+    ///
+    /// # Output
+    /// 如果 key 或者任意一个成员不存在，返回`Ok(None)`。
+    /// 如果 key 不是有序集合，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn geodist(
+        &mut self,
+        key: &str,
+        from: Bytes,
+        to: Bytes,
+        unit: GeoUnit,
+    ) -> crate::Result<Option<f64>> {
+        let frame = GeoDist::new(key, from, to, unit).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(dist) => Ok(Some(parse_score(&dist)?)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 搜索指定范围内的成员。对应`GeoSearch`命令。
+    ///
+    /// 排序、数量和需要返回的信息通过`GeoSearch`的方法设置。
+    ///
+    /// # Output
+    /// 如果 key 不是有序集合、作为中心的成员不存在，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn geosearch(&mut self, cmd: GeoSearch) -> crate::Result<Vec<GeoLocation>> {
+        let frame = cmd.into_frame();
+        self.write_request(&frame).await?;
+
+        let items = match self.read_response().await? {
+            Frame::Array(items) => items,
+            frame => return Err(frame.to_error()),
+        };
+        items.into_iter().map(geo_location).collect()
+    }
+
+    /// 将字符串中第`offset`位设置为`on`。对应`SetBit`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回这一位原来的值。
+    /// 如果 key 不是字符串，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn setbit(&mut self, key: &str, offset: u64, on: bool) -> crate::Result<bool> {
+        let frame = SetBit::new(key, offset, on).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(bit) => Ok(bit == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取字符串中第`offset`位的值。对应`GetBit`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，或者`offset`超出了字符串的长度，返回`Ok(false)`。
+    /// 如果 key 不是字符串，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn getbit(&mut self, key: &str, offset: u64) -> crate::Result<bool> {
+        let frame = GetBit::new(key, offset).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(bit) => Ok(bit == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 统计字符串中值为`1`的位的数量，`range`是以字节为单位的下标范围。对应`BitCount`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`0`。
+    /// 如果 key 不是字符串，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn bitcount(&mut self, key: &str, range: Option<(i64, i64)>) -> crate::Result<u64> {
+        let frame = BitCount::new(key, range).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 向流中添加一条消息，`id`为`*`时自动生成。对应`XAdd`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回消息的 ID。
+    /// 如果 key 不是流、ID 不合法，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn xadd(
+        &mut self,
+        key: &str,
+        id: &str,
+        fields: Vec<(Bytes, Bytes)>,
+    ) -> crate::Result<String> {
+        let frame = XAdd::new(key, id, fields).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(id) => Ok(String::from_utf8_lossy(&id).into_owned()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取流中 ID 在`[start, end]`之间的消息，最多返回`count`条。对应`XRange`命令。
+    ///
+    /// `-`和`+`分别表示最小和最大的 ID。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回空的`Vec`。
+    /// 如果 key 不是流、ID 不合法，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn xrange(
+        &mut self,
+        key: &str,
+        start: &str,
+        end: &str,
+        count: Option<u64>,
+    ) -> crate::Result<Vec<StreamEntry>> {
+        let frame = XRange::new(key, start, end, count).into_frame();
+        self.write_request(&frame).await?;
+
+        let response = self.read_response().await?;
+        stream_entries(response)
+    }
+
+    /// 获取流中消息的数量。对应`XLen`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`0`。
+    /// 如果 key 不是流，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn xlen(&mut self, key: &str) -> crate::Result<u64> {
+        let frame = XLen::new(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 读取一个或多个流中 ID 大于指定 ID 的消息。对应`XRead`命令。
+    ///
+    /// `streams`中的每一项为 key 和 ID，ID 为`$`时只读取之后添加的消息。
+    /// `block`为`None`时不阻塞，为`Some(Duration::ZERO)`时一直阻塞。
+    ///
+    /// # Output
+    /// 返回有新消息的流的 key 和消息。如果没有新消息或者超时，返回空的`Vec`。
+    /// 如果某个 key 不是流、ID 不合法，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn xread(
+        &mut self,
+        streams: Vec<(String, String)>,
+        count: Option<u64>,
+        block: Option<Duration>,
+    ) -> crate::Result<Vec<(String, Vec<StreamEntry>)>> {
+        let frame = XRead::new(streams, count, block).into_frame();
+        self.write_request(&frame).await?;
+
+        let response = self.read_response().await?;
+        streams_reply(response)
+    }
+
+    /// 在流上创建消费者组，`id`为`$`时只读取之后添加的消息。对应`XGroup Create`命令。
+    ///
+    /// `mkstream`为`true`时，如果 key 不存在会先创建一个空的流。
+    ///
+    /// # Errors
+    /// 如果 key 不是流、key 不存在且`mkstream`为`false`、组已经存在，
+    /// 或者发送请求或读取响应出错，返回`Err`。
+    pub async fn xgroup_create(
+        &mut self,
+        key: &str,
+        group: &str,
+        id: &str,
+        mkstream: bool,
+    ) -> crate::Result<()> {
+        let frame = XGroup::create(key, group, id, mkstream).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 删除流上的消费者组。对应`XGroup Destroy`命令。
+    ///
+    /// # Output
+    /// 如果组存在并被删除，返回`true`。
+    /// 如果 key 不是流，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn xgroup_destroy(&mut self, key: &str, group: &str) -> crate::Result<bool> {
+        let frame = XGroup::destroy(key, group).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(destroyed) => Ok(destroyed == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 以消费者组`group`中`consumer`的身份读取流中的消息。对应`XReadGroup`命令。
+    ///
+    /// `streams`中的每一项为 key 和 ID，ID 为`>`时读取新消息，否则读取已经投递给
+    /// 这个消费者但还没有被确认的消息。`block`的规则与`xread()`相同。
+    ///
+    /// # Output
+    /// 返回每个流的 key 和消息。如果没有新消息或者超时，返回空的`Vec`。
+    /// 如果某个 key 不是流、消费者组不存在，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn xreadgroup(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        streams: Vec<(String, String)>,
+        count: Option<u64>,
+        block: Option<Duration>,
+    ) -> crate::Result<Vec<(String, Vec<StreamEntry>)>> {
+        let frame = XReadGroup::new(group, consumer, streams, count, block).into_frame();
+        self.write_request(&frame).await?;
+
+        let response = self.read_response().await?;
+        streams_reply(response)
+    }
+
+    /// 确认消费者组中的消息已经被处理。对应`XAck`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回被确认的消息的数量。
+    /// 如果 key 不是流、ID 不合法，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn xack(&mut self, key: &str, group: &str, ids: Vec<String>) -> crate::Result<u64> {
+        let frame = XAck::new(key, group, ids).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(acked) => Ok(acked as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 将消费者组中空闲时间不少于`min_idle`的待确认消息转移给`consumer`。对应`XClaim`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回被认领的消息。
+    /// 如果 key 不是流、消费者组不存在，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn xclaim(
+        &mut self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle: Duration,
+        ids: Vec<String>,
+    ) -> crate::Result<Vec<StreamEntry>> {
+        let frame = XClaim::new(key, group, consumer, min_idle, ids).into_frame();
+        self.write_request(&frame).await?;
+
+        let response = self.read_response().await?;
+        stream_entries(response)
+    }
+
+    /// 获取 key 剩余的生存时间，单位为秒。对应`Ttl`命令。
+    ///
+    /// # Output
+    /// 如果 key 没有设置过期时间返回`-1`，如果 key 不存在返回`-2`。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn ttl(&mut self, key: &str) -> crate::Result<i64> {
+        self.ttl_cmd(Ttl::new(key)).await
+    }
+
+    /// 获取 key 剩余的生存时间，单位为毫秒。对应`PTtl`命令。
+    ///
+    /// # Output
+    /// 与`ttl()`相同。
+    pub async fn pttl(&mut self, key: &str) -> crate::Result<i64> {
+        self.ttl_cmd(Ttl::pttl(key)).await
+    }
+
+    /// 获取 key 过期的时刻，单位为秒的 UNIX 时间戳。对应`ExpireTime`命令。
+    ///
+    /// # Output
+    /// 与`ttl()`相同。
+    pub async fn expiretime(&mut self, key: &str) -> crate::Result<i64> {
+        self.expire_time_cmd(ExpireTime::new(key)).await
+    }
+
+    /// 获取 key 过期的时刻，单位为毫秒的 UNIX 时间戳。对应`PExpireTime`命令。
+    ///
+    /// # Output
+    /// 与`ttl()`相同。
+    pub async fn pexpiretime(&mut self, key: &str) -> crate::Result<i64> {
+        self.expire_time_cmd(ExpireTime::pexpiretime(key)).await
+    }
+
+    /// 真正完成`ExpireTime`和`PExpireTime`操作的核心函数。
+    async fn expire_time_cmd(&mut self, cmd: ExpireTime) -> crate::Result<i64> {
+        let frame = cmd.into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(timestamp) => Ok(timestamp),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 真正完成`Ttl`和`PTtl`操作的核心函数。
+    async fn ttl_cmd(&mut self, cmd: Ttl) -> crate::Result<i64> {
+        let frame = cmd.into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(ttl) => Ok(ttl),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 向给定的信道发布信息。对应`Publish`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回订阅者的数量。如果发送请求或读取响应出错，返回`Err`。
+    pub async fn publish(&mut self, channel: &str, message: Bytes) -> crate::Result<u64> {
+        // 创建`Publish`并转换为`Frame`
+        let frame = Publish::new(channel, message).into_frame();
+
+        // 写入请求
+        self.write_request(&frame).await?;
+
+        // 等待响应
+        match self.read_response().await? {
+            Frame::Integer(response) => Ok(response as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 向给定的分片信道发布信息。对应`SPublish`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回订阅者的数量。如果发送请求或读取响应出错，返回`Err`。
+    pub async fn spublish(&mut self, channel: &str, message: Bytes) -> crate::Result<u64> {
+        let frame = Publish::sharded(channel, message).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(response) => Ok(response as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 订阅指定信道，将`Client`封装为`Subscriber`。对应`Subscribe`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回`Subscriber`。如果发送请求或读取响应出错，返回`Err`。
+    pub async fn subscribe(self, channels: Vec<String>) -> crate::Result<Subscriber> {
+        let mut subscriber = Subscriber::new(self);
+        subscriber.subscribe(&channels).await?;
+        Ok(subscriber)
+    }
+
+    /// 按照 glob 模式订阅信道，将`Client`封装为`Subscriber`。对应`PSubscribe`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回`Subscriber`。如果发送请求或读取响应出错，返回`Err`。
+    pub async fn psubscribe(self, patterns: Vec<String>) -> crate::Result<Subscriber> {
+        let mut subscriber = Subscriber::new(self);
+        subscriber.psubscribe(&patterns).await?;
+        Ok(subscriber)
+    }
+
+    /// 订阅分片信道，将`Client`封装为`Subscriber`。对应`SSubscribe`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回`Subscriber`。如果发送请求或读取响应出错，返回`Err`。
+    pub async fn ssubscribe(self, channels: Vec<String>) -> crate::Result<Subscriber> {
+        let mut subscriber = Subscriber::new(self);
+        subscriber.ssubscribe(&channels).await?;
+        Ok(subscriber)
+    }
+
+    /// 订阅客户端缓存的失效信息，将`Client`封装为`Subscriber`。
+    ///
+    /// 收到的每条信息的内容为一个被修改的 key，信道为`INVALIDATE_CHANNEL`。
+    /// 需要在另一个连接上通过`tracking()`开启追踪。
+    ///
+    /// # Output
+    /// 如果成功则返回`Subscriber`。如果发送请求或读取响应出错，返回`Err`。
+    pub async fn subscribe_invalidations(self) -> crate::Result<Subscriber> {
+        self.subscribe(vec![INVALIDATE_CHANNEL.to_string()]).await
+    }
+
+    /// 测试连接。对应`Ping`命令。
+    ///
+    /// # Output
+    /// 如果成功就返回响应数据。如果发送请求或读取响应出错，返回`Err`。
+    pub async fn ping(&mut self, msg: Option<Bytes>) -> crate::Result<Bytes> {
+        let frame = Ping::new(msg).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(value.into()),
+            Frame::Bulk(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 读取匹配`pattern`的配置项。对应`Config Get`命令。
+    ///
+    /// # Output
+    /// 如果成功则返回配置项名称和值的列表。如果发送请求或读取响应出错，返回`Err`。
+    pub async fn config_get(&mut self, pattern: &str) -> crate::Result<Vec<(String, Bytes)>> {
+        let frame = Config::get(pattern).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Map(pairs) => pairs
+                .into_iter()
+                .map(|pair| match pair {
+                    (name, Frame::Bulk(value)) => Ok((name.to_string(), value)),
+                    (name, value) => Err(Frame::Map(vec![(name, value)]).to_error()),
+                })
+                .collect(),
+            Frame::Array(items) => items
+                .chunks(2)
+                .map(|pair| match pair {
+                    [name, Frame::Bulk(value)] => Ok((name.to_string(), value.clone())),
+                    _ => Err(Frame::Array(pair.to_vec()).to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 修改配置项。对应`Config Set`命令。
+    ///
+    /// # Errors
+    /// 如果配置项不存在、值不合法，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn config_set(&mut self, parameter: &str, value: &str) -> crate::Result<()> {
+        let frame = Config::set(parameter, value).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 在服务器上执行脚本。对应`Eval`命令。
+    ///
+    /// 服务器需要开启`scripting`特性，脚本使用 Rhai 编写。
+    ///
+    /// # Output
+    /// 返回脚本的返回值。如果脚本出错，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn eval(
+        &mut self,
+        script: &str,
+        keys: &[String],
+        args: Vec<Bytes>,
+    ) -> crate::Result<Frame> {
+        let frame = Eval::new(script, keys, args).into_frame();
+        self.write_request(&frame).await?;
+        self.read_response().await
+    }
+
+    /// 通过 SHA1 摘要执行服务器上缓存的脚本。对应`EvalSha`命令。
+    ///
+    /// # Output
+    /// 返回脚本的返回值。如果脚本不存在、脚本出错，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn evalsha(
+        &mut self,
+        sha1: &str,
+        keys: &[String],
+        args: Vec<Bytes>,
+    ) -> crate::Result<Frame> {
+        let frame = Eval::sha(sha1, keys, args).into_frame();
+        self.write_request(&frame).await?;
+        self.read_response().await
+    }
+
+    /// 缓存脚本但不执行。对应`Script Load`命令。
+    ///
+    /// # Output
+    /// 返回脚本的 SHA1 摘要。如果发送请求或读取响应出错，返回`Err`。
+    pub async fn script_load(&mut self, script: &str) -> crate::Result<String> {
+        let frame = Script::load(script).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(sha) => Ok(String::from_utf8_lossy(&sha).into_owned()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 判断脚本是否已经被缓存。对应`Script Exists`命令。
+    ///
+    /// # Output
+    /// 按照`sha1s`的顺序返回每个脚本是否存在。如果发送请求或读取响应出错，返回`Err`。
+    pub async fn script_exists(&mut self, sha1s: &[String]) -> crate::Result<Vec<bool>> {
+        let frame = Script::exists(sha1s.to_vec()).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    Frame::Integer(exists) => Ok(exists == 1),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 清空服务器上缓存的脚本。对应`Script Flush`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn script_flush(&mut self) -> crate::Result<()> {
+        let frame = Script::flush().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 在服务器上注册函数库。对应`Function Load`命令。
+    ///
+    /// 源代码的第一行必须是`#!rhai name=<library>`，`replace`为`true`时替换同名的库。
+    ///
+    /// # Output
+    /// 返回库的名称。如果库不合法或者已经存在，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn function_load(&mut self, code: &str, replace: bool) -> crate::Result<String> {
+        let frame = Function::load(code, replace).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(name) => Ok(String::from_utf8_lossy(&name).into_owned()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 列出服务器上名称匹配`pattern`的函数库。对应`Function List`命令。
+    ///
+    /// `pattern`为`None`时列出所有的库，`with_code`为`true`时同时返回源代码。
+    ///
+    /// # Output
+    /// 返回按照名称排序的函数库。如果发送请求或读取响应出错，返回`Err`。
+    pub async fn function_list(
+        &mut self,
+        pattern: Option<&str>,
+        with_code: bool,
+    ) -> crate::Result<Vec<FunctionLibrary>> {
+        let frame = Function::list(pattern.map(str::to_string), with_code).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(libraries) => libraries.into_iter().map(function_library).collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 删除服务器上的函数库。对应`Function Delete`命令。
+    ///
+    /// # Errors
+    /// 如果库不存在，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn function_delete(&mut self, library: &str) -> crate::Result<()> {
+        let frame = Function::delete(library).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 调用通过`function_load()`注册的函数。对应`FCall`命令。
+    ///
+    /// # Output
+    /// 返回函数的返回值。如果函数不存在、函数出错，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn fcall(
+        &mut self,
+        function: &str,
+        keys: &[String],
+        args: Vec<Bytes>,
+    ) -> crate::Result<Frame> {
+        let frame = FCall::new(function, keys, args).into_frame();
+        self.write_request(&frame).await?;
+        self.read_response().await
+    }
+
+    /// 开启或关闭当前连接的客户端缓存追踪。对应`Client Tracking`命令。
+    ///
+    /// 开启后，这个连接读取过的 key 被修改、删除或者过期时，服务器会发送失效信息，
+    /// 通过`subscribe_invalidations()`接收。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn tracking(&mut self, on: bool) -> crate::Result<()> {
+        let frame = ClientCommand::tracking(on).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取当前连接的编号。对应`Client Id`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn client_id(&mut self) -> crate::Result<u64> {
+        let frame = ClientCommand::id().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(id) => Ok(id as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 设置当前连接的名称，空字符串表示清除名称。对应`Client SetName`命令。
+    ///
+    /// # Errors
+    /// 如果名称包含空格或者特殊字符，或者发送请求、读取响应出错，返回`Err`。
+    pub async fn client_setname(&mut self, name: &str) -> crate::Result<()> {
+        let frame = ClientCommand::set_name(name).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取当前连接的名称，没有设置时返回`None`。对应`Client GetName`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn client_getname(&mut self) -> crate::Result<Option<String>> {
+        let frame = ClientCommand::get_name().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(name) => Ok(Some(String::from_utf8_lossy(&name).into_owned())),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取所有连接的信息，每个连接一行。对应`Client List`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn client_list(&mut self) -> crate::Result<String> {
+        let frame = ClientCommand::list().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(list) => Ok(String::from_utf8_lossy(&list).into_owned()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 关闭编号为`id`的连接，返回是否关闭了连接。对应`Client Kill ID <id>`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn client_kill(&mut self, id: u64) -> crate::Result<bool> {
+        let frame = ClientCommand::kill_id(id).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(killed) => Ok(killed > 0),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 在`timeout`时间内暂停所有连接的命令，`write_only`为`true`时只暂停写命令。
+    /// 对应`Client Pause`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn client_pause(&mut self, timeout: Duration, write_only: bool) -> crate::Result<()> {
+        let frame = ClientCommand::pause(timeout, write_only).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 提前结束`Client Pause`设置的暂停。对应`Client Unpause`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn client_unpause(&mut self) -> crate::Result<()> {
+        let frame = ClientCommand::unpause().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 将数据库的快照写入服务器配置的快照文件，写入完成后才返回。对应`Save`命令。
+    ///
+    /// # Errors
+    /// 如果服务器正在保存或者写文件失败，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn save(&mut self) -> crate::Result<()> {
+        let frame = Save::new().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 让服务器在后台保存快照。对应`BgSave`命令。
+    ///
+    /// 服务器开始保存后立即返回，通过`lastsave()`判断保存是否完成。
+    ///
+    /// # Errors
+    /// 如果服务器正在保存，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn bgsave(&mut self) -> crate::Result<()> {
+        let frame = Save::background().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(_) => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取服务器上次成功保存快照的 UNIX 时间戳，单位是秒。对应`LastSave`命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn lastsave(&mut self) -> crate::Result<u64> {
+        let frame = LastSave::new().into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(timestamp) => Ok(timestamp as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 让服务器成为`primary`的副本，`primary`为`None`时停止复制。对应`ReplicaOf`命令。
+    ///
+    /// 成为副本后服务器会丢弃现有的数据，从主节点同步，之后拒绝客户端的写命令。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn replicaof(&mut self, primary: Option<(&str, u16)>) -> crate::Result<()> {
+        let primary = primary.map(|(host, port)| (host.to_string(), port));
+        let frame = ReplicaOf::new(primary).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 等待至少`numreplicas`个副本确认当前连接之前的写命令。对应`Wait`命令。
+    ///
+    /// # Output
+    /// 返回确认的副本数，超过`timeout`后即使不够`numreplicas`个也会返回，
+    /// `timeout`为`None`时一直等待。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn wait(
+        &mut self,
+        numreplicas: u64,
+        timeout: Option<Duration>,
+    ) -> crate::Result<u64> {
+        let frame = Wait::new(numreplicas, timeout).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(acked) => Ok(acked as u64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 序列化 key 的 value。对应`Dump`命令。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`Ok(None)`，序列化的结果可以通过`restore()`恢复。
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn dump(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        let frame = Dump::new(key).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(payload) => Ok(Some(payload)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 使用`dump()`序列化的结果创建 key。对应`Restore`命令。
+    ///
+    /// `ttl`为`None`表示没有过期时间，`replace`为`true`时覆盖已经存在的 key。
+    ///
+    /// # Errors
+    /// 如果 key 已经存在且`replace`为`false`、序列化的数据不合法，
+    /// 或者发送请求或读取响应出错，返回`Err`。
+    pub async fn restore(
+        &mut self,
+        key: &str,
+        ttl: Option<Duration>,
+        payload: Bytes,
+        replace: bool,
+    ) -> crate::Result<()> {
+        let frame = Restore::new(key, ttl, payload, replace).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 将数据库的快照写入服务器上的文件`path`。对应`Backup`命令。
+    ///
+    /// # Errors
+    /// 如果服务器写文件失败，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn backup(&mut self, path: &str) -> crate::Result<()> {
+        let frame = Backup::new(path).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 使用服务器上的快照文件`path`替换数据库中的所有数据。对应`RestoreFrom`命令。
+    ///
+    /// # Errors
+    /// 如果文件不存在或者已损坏，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn restore_from(&mut self, path: &str) -> crate::Result<()> {
+        let frame = RestoreFrom::new(path).into_frame();
+        self.write_request(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 发送任意命令，返回服务器的响应帧。
+    ///
+    /// `args`的第一个元素是命令名称，其余为参数，所有元素都会作为`Bulk`发送。
+    /// 适用于客户端还没有提供专门方法的命令。
+    ///
+    /// # Errors
+    /// 如果服务器返回了错误，或者发送请求或读取响应出错，返回`Err`。
+    pub async fn call(&mut self, args: Vec<Bytes>) -> crate::Result<Frame> {
+        let mut frame = Frame::array();
+        for arg in args {
+            frame.push_bulk(arg);
+        }
+        self.write_request(&frame).await?;
+        self.read_response().await
+    }
+
+    /// 流水线发送多条命令，所有命令写入后再依次读取响应，只需要一次往返。
+    ///
+    /// 返回的响应与`frames`一一对应，服务器返回的错误以`Frame::Error`保留在结果中，
+    /// 不会影响其他命令。服务器在读取完所有命令之前就会开始写回响应，
+    /// 命令非常多时应该分批发送，避免双方的 socket 缓存都被填满。
+    /// 设置了响应超时时，限制的是读取所有响应的时间。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，或者连接被关闭，返回`Err`。
+    pub async fn pipeline(&mut self, frames: &[Frame]) -> crate::Result<Vec<Frame>> {
+        self.begin_request()?;
+        self.connection.write_frames(frames).await?;
+        match self.request_timeout() {
+            Some(timeout) => {
+                let responses = time::timeout(timeout, self.read_responses(frames.len())).await;
+                self.check_timeout(responses)?
+            }
+            None => self.read_responses(frames.len()).await,
+        }
+    }
+
+    /// 读取`count`个响应，错误响应不会转换为`Err`，见`pipeline()`。
+    async fn read_responses(&mut self, count: usize) -> crate::Result<Vec<Frame>> {
+        let mut responses = Vec::with_capacity(count);
+        for _ in 0..count {
+            match self.connection.read_frame().await? {
+                Some(frame) => responses.push(frame),
+                None => return Err(crate::Error::ConnectionClosed),
+            }
+        }
+        Ok(responses)
+    }
+
+    /// 按照`options`完成连接建立后的握手。
+    ///
+    /// 认证失败的错误都转换为`Error::Auth`，例如服务器没有设置密码时`Auth`返回的错误。
+    async fn handshake(&mut self, options: &ConnectOptions) -> crate::Result<()> {
+        if let Some(protocol) = options.protocol {
+            // `Hello`的认证需要用户名，没有指定时使用默认用户。
+            let protover = Some(protocol.version() as u64);
+            let mut hello = Hello::new(protover);
+            if let Some(password) = &options.password {
+                let username = options.username.as_deref().unwrap_or("default");
+                hello = hello.auth(username, password);
+            }
+            if let Some(name) = &options.client_name {
+                hello = hello.setname(name);
+            }
+            self.hello_cmd(hello, protover).await?;
+            return Ok(());
+        }
+
+        if let Some(password) = &options.password {
+            self.auth(options.username.as_deref(), password)
+                .await
+                .map_err(|err| match err {
+                    crate::Error::Server(msg) => crate::Error::Auth(msg),
+                    err => err,
+                })?;
+        }
+        if let Some(name) = &options.client_name {
+            self.client_setname(name).await?;
+        }
+        Ok(())
+    }
+
+    /// 开始一次新的请求，在发送请求之前调用。
+    ///
+    /// `with_timeout()`设置的超时时间只对这次请求生效，即使发送失败也不会留给之后的请求。
+    ///
+    /// # Errors
+    /// 如果之前的请求超时，连接已经不可用，返回`Err`。
+    fn begin_request(&mut self) -> crate::Result<()> {
+        self.request_timeout = self.next_timeout.take();
+        if self.broken {
+            return Err("之前的请求超时，连接已经不可用，需要重新连接".into());
+        }
+        Ok(())
+    }
+
+    /// 开始一次新的请求并发送请求帧，见`begin_request()`。
+    async fn write_request(&mut self, frame: &Frame) -> crate::Result<()> {
+        self.begin_request()?;
+        self.connection.write_frame(frame).await?;
+        Ok(())
+    }
+
+    /// 获取这次请求等待响应的超时时间。
+    fn request_timeout(&self) -> Option<Duration> {
+        self.request_timeout.or(self.response_timeout)
+    }
+
+    /// 检查等待响应是否超时，超时后将连接标记为不可用。
+    fn check_timeout<T>(&mut self, result: Result<T, time::error::Elapsed>) -> crate::Result<T> {
+        if result.is_err() {
+            self.broken = true;
+        }
+        Ok(result?)
+    }
+
+    /// 从 socket 中读取响应帧。
+    ///
+    /// # Output
+    /// 如果成功则返回读取到的响应帧。
+    /// 如果读取响应帧失败，或者读取到`Frame::Error`，返回`Err`。
+    /// 如果服务器关闭了，也返回`Err`。
+    /// 如果设置了超时时间，超时后返回`Error::Timeout`，并且连接不再可用。
+    async fn read_response(&mut self) -> crate::Result<Frame> {
+        let response = match self.request_timeout() {
+            Some(timeout) => {
+                let response = time::timeout(timeout, self.connection.read_frame()).await;
+                self.check_timeout(response)??
+            }
+            None => self.connection.read_frame().await?,
+        };
+        match response {
+            // 如果返回`Error Frame`，抛出错误
+            Some(Frame::Error(msg)) => Err(crate::Error::from_server(msg)),
+            Some(frame) => Ok(frame),
+            None => Err(crate::Error::ConnectionClosed),
+        }
+    }
+}
+
+impl Builder {
+    /// 解析连接 URL，创建`Builder`。
+    ///
+    /// 格式为`redis://[[<username>]:<password>@]<host>[:<port>][/<db>][?protocol=resp3]`，
+    /// 使用`rediss://`时开启 TLS。用户名和密码可以使用百分号编码，端口默认为`DEFAULT_PORT`。
+    /// 服务器只有一个数据库，`db`只能为`0`。
+    ///
+    /// # Errors
+    /// 如果 URL 的协议不是`redis`或`rediss`，或者端口、数据库编号、查询参数不合法，返回`Err`。
+    pub fn from_url(url: &str) -> crate::Result<Builder> {
+        let invalid = |reason: &str| format!("不合法的连接 URL：{}，{}", url, reason);
+        let (tls, rest) = if let Some(rest) = url.strip_prefix("redis://") {
+            (false, rest)
+        } else if let Some(rest) = url.strip_prefix("rediss://") {
+            (true, rest)
+        } else {
+            return Err(invalid("协议必须为 redis 或 rediss").into());
+        };
+        let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (authority, db) = rest.split_once('/').unwrap_or((rest, ""));
+
+        // 用户信息中的密码可能包含`@`，所以从右边分割。
+        let (userinfo, hostport) = match authority.rsplit_once('@') {
+            Some((userinfo, hostport)) => (Some(userinfo), hostport),
+            None => (None, authority),
+        };
+        let hostport = match hostport {
+            "" => "127.0.0.1",
+            hostport => hostport,
+        };
+        // IPv6 地址被方括号包裹，端口在方括号之后。
+        let has_port = match hostport.rfind(']') {
+            Some(end) => hostport[end..].contains(':'),
+            None => hostport.contains(':'),
+        };
+        let addr = if has_port {
+            let (_, port) = hostport.rsplit_once(':').unwrap_or_default();
+            port.parse::<u16>().map_err(|_| invalid("端口不合法"))?;
+            hostport.to_string()
+        } else {
+            format!("{}:{}", hostport, crate::DEFAULT_PORT)
+        };
+
+        let mut builder = Client::builder(addr).tls(tls);
+        if let Some(userinfo) = userinfo {
+            let (username, password) = match userinfo.split_once(':') {
+                Some((username, password)) => (username, password),
+                None => return Err(invalid("缺少密码").into()),
+            };
+            let username = match percent_decode(username) {
+                Some(username) if username.is_empty() => None,
+                Some(username) => Some(username),
+                None => return Err(invalid("用户名的编码不合法").into()),
+            };
+            let password = percent_decode(password).ok_or_else(|| invalid("密码的编码不合法"))?;
+            builder = builder.auth(username, password);
+        }
+
+        match db.trim_end_matches('/') {
+            "" | "0" => {}
+            db if db.parse::<u64>().is_ok() => return Err(invalid("只支持 0 号数据库").into()),
+            _ => return Err(invalid("数据库编号不合法").into()),
+        }
+
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            builder = match param.split_once('=') {
+                Some(("protocol", "resp2" | "2")) => builder.protocol(Protocol::Resp2),
+                Some(("protocol", "resp3" | "3")) => builder.protocol(Protocol::Resp3),
+                _ => return Err(invalid(&format!("不支持的参数 {}", param)).into()),
+            };
+        }
+        Ok(builder)
+    }
+
+    /// 设置认证使用的用户名和密码，连接建立后会自动发送`Auth`命令。
+    pub fn auth(mut self, username: Option<String>, password: impl ToString) -> Builder {
+        self.options = self.options.auth(username, password);
+        self
+    }
+
+    /// 设置连接使用的协议版本，连接建立后会自动发送`Hello`命令。
+    pub fn protocol(mut self, protocol: Protocol) -> Builder {
+        self.options = self.options.protocol(protocol);
+        self
+    }
+
+    /// 设置连接的名称，连接建立后会自动设置。
+    pub fn client_name(mut self, name: impl ToString) -> Builder {
+        self.options = self.options.client_name(name);
+        self
+    }
+
+    /// 使用`options`替换认证、协议版本和名称的设置。
+    pub fn options(mut self, options: ConnectOptions) -> Builder {
+        self.options = options;
+        self
+    }
+
+    /// 设置服务器地址，同时清除 Unix socket 设置。
+    ///
+    /// 主要用于集群模式下，使用相同的选项连接到另一个节点。
+    pub fn addr(mut self, addr: impl ToString) -> Builder {
+        self.addr = addr.to_string();
+        self.unix_socket = None;
+        self
+    }
+
+    /// 获取服务器地址，格式为`host:port`。
+    pub fn get_addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// 设置是否使用 TLS，开启时使用默认的`ClientTls`，信任内置的根证书。
+    pub fn tls(mut self, tls: bool) -> Builder {
+        self.tls = match tls {
+            true => self.tls.or_else(|| Some(ClientTls::new())),
+            false => None,
+        };
+        self
+    }
+
+    /// 设置用于验证服务器证书的 CA 证书，设置后会同时开启 TLS。
+    pub fn ca_cert(mut self, path: impl Into<PathBuf>) -> Builder {
+        self.tls = Some(self.tls.unwrap_or_default().ca_cert(path));
+        self
+    }
+
+    /// 使用`tls`配置 TLS 连接，包括 CA 证书、客户端证书以及 SNI 使用的服务器名称。
+    pub fn tls_config(mut self, tls: ClientTls) -> Builder {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// 设置等待响应的超时时间，见`Client::set_response_timeout()`。
+    pub fn response_timeout(mut self, timeout: Duration) -> Builder {
+        self.response_timeout = Some(timeout);
+        self
+    }
+
+    /// 通过 Unix socket 连接服务器，设置后忽略服务器地址。
+    pub fn unix_socket(mut self, path: impl Into<PathBuf>) -> Builder {
+        self.unix_socket = Some(path.into());
+        self
+    }
+
+    /// 按照配置与服务器建立连接，创建`Client`。
+    ///
+    /// # Errors
+    /// 如果连接失败、TLS 握手失败或者认证失败，返回`Err`。
+    pub async fn connect(&self) -> crate::Result<Client> {
+        let connection = self.open_connection().await?;
+        let mut client = Client::new(connection);
+        client.set_response_timeout(self.response_timeout);
+
+        client.handshake(&self.options).await?;
+        Ok(client)
+    }
+
+    /// 根据配置打开底层传输层，并封装为`Connection`。
+    async fn open_connection(&self) -> crate::Result<Connection> {
+        if let Some(path) = &self.unix_socket {
+            return self.open_unix_socket(path).await;
+        }
+
+        let socket = TcpStream::connect(&self.addr).await?;
+        let Some(tls) = &self.tls else {
+            return Ok(Connection::new(socket));
+        };
+
+        // TLS 握手时需要服务器名称，用于 SNI 以及证书验证。
+        let connector = TlsConnector::from(Arc::new(tls.client_config()?));
+        let name = tls.get_server_name().unwrap_or_else(|| self.host());
+        let server_name = ServerName::try_from(name.to_string())?;
+        let stream = connector.connect(server_name, socket).await?;
+        Ok(Connection::new(stream))
+    }
+
+    #[cfg(unix)]
+    async fn open_unix_socket(&self, path: &PathBuf) -> crate::Result<Connection> {
+        let socket = tokio::net::UnixStream::connect(path).await?;
+        Ok(Connection::new(socket))
+    }
+
+    #[cfg(not(unix))]
+    async fn open_unix_socket(&self, _path: &PathBuf) -> crate::Result<Connection> {
+        Err("当前平台不支持 Unix socket".into())
+    }
+
+    /// 从`host:port`格式的地址中提取主机名。
+    fn host(&self) -> &str {
+        let host = match self.addr.rsplit_once(':') {
+            Some((host, _port)) => host,
+            None => &self.addr,
+        };
+        // IPv6 地址会被方括号包裹。
+        host.trim_start_matches('[').trim_end_matches(']')
+    }
+}
+
+impl ConnectOptions {
+    /// 创建连接选项，默认不认证、不发送`Hello`。
+    pub fn new() -> ConnectOptions {
+        ConnectOptions::default()
+    }
+
+    /// 设置认证使用的用户名和密码，`username`为`None`时使用默认用户。
+    pub fn auth(mut self, username: Option<String>, password: impl ToString) -> ConnectOptions {
+        self.username = username;
+        self.password = Some(password.to_string());
+        self
+    }
+
+    /// 设置连接使用的协议版本。
+    pub fn protocol(mut self, protocol: Protocol) -> ConnectOptions {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// 设置连接的名称。
+    pub fn client_name(mut self, name: impl ToString) -> ConnectOptions {
+        self.client_name = Some(name.to_string());
+        self
+    }
+}
+
+impl Redirect {
+    /// 尝试将错误解析为重定向错误。
+    ///
+    /// 如果错误不是`MOVED`或`ASK`，返回`None`。
+    pub fn from_error(err: &crate::Error) -> Option<Redirect> {
+        let msg = match err {
+            crate::Error::Server(msg) => msg,
+            _ => return None,
+        };
+        let mut parts = msg.split_whitespace();
+        let kind = parts.next()?;
+        let slot = parts.next()?.parse().ok()?;
+        let addr = parts.next()?.to_string();
+        match kind {
+            "MOVED" => Some(Redirect::Moved { slot, addr }),
+            "ASK" => Some(Redirect::Ask { slot, addr }),
+            _ => None,
+        }
+    }
+
+    /// 获取槽编号。
+    pub fn slot(&self) -> u16 {
+        match self {
+            Redirect::Moved { slot, .. } | Redirect::Ask { slot, .. } => *slot,
+        }
+    }
+
+    /// 获取应该重定向到的节点地址。
+    pub fn addr(&self) -> &str {
+        match self {
+            Redirect::Moved { addr, .. } | Redirect::Ask { addr, .. } => addr,
+        }
+    }
+}
+
+impl Monitored {
+    /// 获取服务器执行的下一条命令，如果没有就等待。
+    ///
+    /// # Output
+    /// 返回`<时间戳> [0 <客户端地址>] "命令" "参数" ...`格式的一行文本，
+    /// 返回`Ok(None)`表示`socket`关闭了。
+    pub async fn next_command(&mut self) -> crate::Result<Option<String>> {
+        match self.client.connection.read_frame().await? {
+            Some(Frame::Simple(line)) => Ok(Some(line)),
+            Some(frame) => Err(frame.to_error()),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Subscriber {
+    /// 创建一个还没有订阅任何信道的`Subscriber`。
+    fn new(client: Client) -> Subscriber {
+        Subscriber {
+            client,
+            subscribed_channels: vec![],
+            subscribed_patterns: vec![],
+            subscribed_shard_channels: vec![],
+            pending_messages: VecDeque::new(),
+        }
+    }
+
+    /// 获取已订阅的信道。
+    pub fn get_subscribed(&self) -> &[String] {
+        &self.subscribed_channels
+    }
+
+    /// 获取已订阅的模式。
+    pub fn get_subscribed_patterns(&self) -> &[String] {
+        &self.subscribed_patterns
+    }
+
+    /// 获取已订阅的分片信道。
+    pub fn get_subscribed_shard_channels(&self) -> &[String] {
+        &self.subscribed_shard_channels
+    }
+
+    /// 获取已订阅的信道的信息，如果没有就等待。
+    ///
+    /// # Output
+    /// 如果成功则返回`Ok(Some(msg))`。
+    /// 返回`Ok(None)`表示`socket`关闭了。
+    pub async fn next_message(&mut self) -> crate::Result<Option<Message>> {
+        if let Some(message) = self.pending_messages.pop_front() {
+            return Ok(Some(message));
+        }
+        match self.client.connection.read_frame().await? {
+            Some(frame) => match parse_message(&frame) {
+                Some(message) => Ok(Some(message)),
+                None => Err(frame.to_error()),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// 在订阅者模式下继续订阅指定信道，不需要重新建立连接。对应`Subscribe`命令。
+    ///
+    /// 等待确认信息期间收到的已订阅信道的信息会被保留，之后由`next_message()`返回。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，或者确认信息与请求不符，返回`Err`。
+    pub async fn subscribe(&mut self, channels: &[String]) -> crate::Result<()> {
+        let frame = Subscribe::new(channels.to_vec()).into_frame();
+        self.client.connection.write_frame(&frame).await?;
+        read_subscribe_confirms(
+            &mut self.client,
+            &mut self.pending_messages,
+            "subscribe",
+            channels,
+            &mut self.subscribed_channels,
+        )
+        .await
+    }
+
+    /// 在订阅者模式下继续按照 glob 模式订阅信道。对应`PSubscribe`命令。
+    ///
+    /// # Errors
+    /// 与`subscribe()`相同。
+    pub async fn psubscribe(&mut self, patterns: &[String]) -> crate::Result<()> {
+        let frame = PSubscribe::new(patterns.to_vec()).into_frame();
+        self.client.connection.write_frame(&frame).await?;
+        read_subscribe_confirms(
+            &mut self.client,
+            &mut self.pending_messages,
+            "psubscribe",
+            patterns,
+            &mut self.subscribed_patterns,
+        )
+        .await
+    }
+
+    /// 在订阅者模式下继续订阅分片信道。对应`SSubscribe`命令。
+    ///
+    /// # Errors
+    /// 与`subscribe()`相同。
+    pub async fn ssubscribe(&mut self, channels: &[String]) -> crate::Result<()> {
+        let frame = SSubscribe::new(channels.to_vec()).into_frame();
+        self.client.connection.write_frame(&frame).await?;
+        read_subscribe_confirms(
+            &mut self.client,
+            &mut self.pending_messages,
+            "ssubscribe",
+            channels,
+            &mut self.subscribed_shard_channels,
+        )
+        .await
+    }
+
+    /// 退订指定信道，`channels`为空时退订所有信道。对应`Unsubscribe`命令。
+    ///
+    /// 退订了所有信道和模式后，服务端会退出订阅者模式。
+    /// 等待确认信息期间收到的订阅信息会被保留，之后由`next_message()`返回。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，或者确认信息与请求不符，返回`Err`。
+    pub async fn unsubscribe(&mut self, channels: &[String]) -> crate::Result<()> {
+        let frame = Unsubscribe::new(channels.to_vec()).into_frame();
+        self.client.connection.write_frame(&frame).await?;
+        read_unsubscribe_confirms(
+            &mut self.client,
+            &mut self.pending_messages,
+            "unsubscribe",
+            channels,
+            &mut self.subscribed_channels,
+        )
+        .await
+    }
+
+    /// 退订指定模式，`patterns`为空时退订所有模式。对应`PUnsubscribe`命令。
+    ///
+    /// # Errors
+    /// 与`unsubscribe()`相同。
+    pub async fn punsubscribe(&mut self, patterns: &[String]) -> crate::Result<()> {
+        let frame = PUnsubscribe::new(patterns.to_vec()).into_frame();
+        self.client.connection.write_frame(&frame).await?;
+        read_unsubscribe_confirms(
+            &mut self.client,
+            &mut self.pending_messages,
+            "punsubscribe",
+            patterns,
+            &mut self.subscribed_patterns,
+        )
+        .await
+    }
+
+    /// 退订指定分片信道，`channels`为空时退订所有分片信道。对应`SUnsubscribe`命令。
+    ///
+    /// # Errors
+    /// 与`unsubscribe()`相同。
+    pub async fn sunsubscribe(&mut self, channels: &[String]) -> crate::Result<()> {
+        let frame = SUnsubscribe::new(channels.to_vec()).into_frame();
+        self.client.connection.write_frame(&frame).await?;
+        read_unsubscribe_confirms(
+            &mut self.client,
+            &mut self.pending_messages,
+            "sunsubscribe",
+            channels,
+            &mut self.subscribed_shard_channels,
+        )
+        .await
+    }
+
+    /// 退订所有信道、模式和分片信道，退出订阅者模式并返回`Client`。对应`Reset`命令。
+    ///
+    /// 等待确认信息期间收到的订阅信息会被丢弃。
+    ///
+    /// # Errors
+    /// 如果发送请求或读取响应出错，返回`Err`。
+    pub async fn reset(mut self) -> crate::Result<Client> {
+        let frame = Reset::new().into_frame();
+        self.client.connection.write_frame(&frame).await?;
+
+        loop {
+            match self.client.read_response().await? {
+                Frame::Simple(response) if response == "RESET" => return Ok(self.client),
+                // 订阅信息，丢弃。
+                Frame::Array(_) | Frame::Push(_) => {}
+                frame => return Err(frame.to_error()),
+            }
+        }
+    }
+
+    /// 发送信号帧，告诉服务端客户端已经关闭了，
+    /// 让客户端结束`Subscriber`的`apply()`。
+    ///
+    /// # Errors
+    /// 如果请求发送失败，返回`Err`。
+    pub async fn send_ctrlc_frame(&mut self) -> crate::Result<()> {
+        let frame = Frame::Simple("shutdown".to_string());
+        self.client.connection.write_frame(&frame).await?;
+        Ok(())
+    }
+}
+
+/// 读取订阅请求的确认信息，并将新订阅的信道或模式加入`subscribed`。
+///
+/// `kind`为`subscribe`、`psubscribe`或`ssubscribe`，`names`为请求中的信道名称或模式。
+/// 服务端按照请求的顺序发送确认信息，但是确认信息之间可能夹杂着已订阅信道的信息，
+/// 它们会被放入`pending`。
+async fn read_subscribe_confirms(
+    client: &mut Client,
+    pending: &mut VecDeque<Message>,
+    kind: &str,
+    names: &[String],
+    subscribed: &mut Vec<String>,
+) -> crate::Result<()> {
+    for name in names {
+        loop {
+            let response = client.read_response().await?;
+            match response {
+                Frame::Array(ref frame) | Frame::Push(ref frame) => match frame.as_slice() {
+                    // [ kind, name, num-subscribed ]
+                    [skind, sname, Frame::Integer(_)]
+                        if *skind == kind && *sname == name.as_str() =>
+                    {
+                        break
+                    }
+                    _ => match parse_message(&response) {
+                        Some(message) => pending.push_back(message),
+                        None => return Err(response.to_error()),
+                    },
+                },
+                frame => return Err(frame.to_error()),
+            }
+        }
+        // 重复订阅同一个信道不会改变订阅状态。
+        if !subscribed.contains(name) {
+            subscribed.push(name.clone());
+        }
+    }
+    Ok(())
+}
+
+/// 读取退订请求的确认信息，并从`subscribed`中删除被退订的信道或模式。
+///
+/// `kind`为`unsubscribe`、`punsubscribe`或`sunsubscribe`，`names`为请求中的信道名称或模式。
+/// 等待确认信息期间收到的订阅信息会被放入`pending`。
+async fn read_unsubscribe_confirms(
+    client: &mut Client,
+    pending: &mut VecDeque<Message>,
+    kind: &str,
+    names: &[String],
+    subscribed: &mut Vec<String>,
+) -> crate::Result<()> {
+    // 服务端对每个退订的信道或模式都会发送一个确认信息，
+    // 没有指定也没有可以退订的信道或模式时，也会发送一个确认信息。
+    let mut remaining = if names.is_empty() {
+        subscribed.len().max(1)
+    } else {
+        names.len()
+    };
+    while remaining > 0 {
+        let response = client.read_response().await?;
+        match response {
+            Frame::Array(ref frame) | Frame::Push(ref frame) => match frame.as_slice() {
+                // [ kind, name, num-subscribed ]
+                // 指定了信道或模式时，确认信息中的名称必须是其中之一；
+                // 没有指定时，没有可以退订的信道或模式的确认信息中名称为`Null`。
+                [skind, sname, Frame::Integer(_)]
+                    if *skind == kind
+                        && (names.iter().any(|name| *sname == name.as_str())
+                            || names.is_empty()) =>
+                {
+                    subscribed.retain(|name| *sname != name.as_str());
+                    remaining -= 1;
+                }
+                _ => match parse_message(&response) {
+                    Some(message) => pending.push_back(message),
+                    None => return Err(response.to_error()),
+                },
+            },
+            frame => return Err(frame.to_error()),
+        }
+    }
+    Ok(())
+}
+
+/// 将订阅信息解析为`Message`，不是订阅信息时返回`None`。
+fn parse_message(frame: &Frame) -> Option<Message> {
+    let (Frame::Array(items) | Frame::Push(items)) = frame else {
+        return None;
+    };
+    match items.as_slice() {
+        // [ "message", channel, content ]
+        // 分片信道的信息为[ "smessage", shardchannel, content ]
+        [message, channel, Frame::Bulk(content)]
+            if *message == "message" || *message == "smessage" =>
+        {
+            Some(Message {
+                channel: channel.to_string(),
+                content: content.clone(),
+                pattern: None,
+            })
+        }
+        // [ "pmessage", pattern, channel, content ]
+        [message, pattern, channel, Frame::Bulk(content)] if *message == "pmessage" => {
+            Some(Message {
+                channel: channel.to_string(),
+                content: content.clone(),
+                pattern: Some(pattern.to_string()),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// 解析`Slowlog Get`返回的一条记录：`[id, timestamp, duration(us), [command, args...]]`。
+fn parse_slowlog_entry(frame: &Frame) -> crate::Result<SlowlogEntry> {
+    match frame {
+        Frame::Array(items) => match &items[..] {
+            [Frame::Integer(id), Frame::Integer(timestamp), Frame::Integer(duration), Frame::Array(args), ..] =>
+            {
+                let args = args
+                    .iter()
+                    .map(|arg| match arg {
+                        Frame::Bulk(arg) => Ok(arg.clone()),
+                        frame => Err(frame.to_error()),
+                    })
+                    .collect::<crate::Result<_>>()?;
+                Ok(SlowlogEntry {
+                    id: *id as u64,
+                    timestamp: *timestamp as u64,
+                    duration: Duration::from_micros(*duration as u64),
+                    args,
+                })
+            }
+            _ => Err(frame.to_error()),
+        },
+        frame => Err(frame.to_error()),
+    }
+}
+
+/// 解码 URL 中的百分号编码，编码不合法或者解码后不是 UTF-8 时返回`None`。
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(b);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// 解析服务端返回的分数，支持`inf`和`-inf`。
+fn parse_score(score: &[u8]) -> crate::Result<f64> {
+    std::str::from_utf8(score)
+        .ok()
+        .and_then(|score| score.parse::<f64>().ok())
+        .ok_or_else(|| crate::Error::Protocol("分数格式错误".to_string()))
+}
+
+/// 解析成员和分数交替出现的数组，RESP3 的连接中每个元素都是`[member, score]`。
+fn scored_members(items: Vec<Frame>) -> crate::Result<Vec<(Bytes, f64)>> {
+    let mut members = Vec::with_capacity(items.len() / 2);
+    let mut iter = items.into_iter();
+    while let Some(member) = iter.next() {
+        let (member, score) = match member {
+            Frame::Array(pair) => {
+                let mut pair = pair.into_iter();
+                (pair.next(), pair.next())
+            }
+            member => (Some(member), iter.next()),
+        };
+        match (member, score) {
+            (Some(Frame::Bulk(member)), Some(Frame::Bulk(score))) => {
+                members.push((member, parse_score(&score)?));
+            }
+            (Some(Frame::Bulk(member)), Some(Frame::Double(score))) => {
+                members.push((member, score));
+            }
+            (Some(frame), _) => return Err(frame.to_error()),
+            (None, _) => return Err(Frame::Array(vec![]).to_error()),
+        }
+    }
+    Ok(members)
+}
+
+/// 解析没有`count`的`SRandMember`等命令的响应，`(nil)`返回`None`。
+fn random_one(frame: Frame) -> crate::Result<Option<Bytes>> {
+    match frame {
+        Frame::Bulk(member) => Ok(Some(member)),
+        Frame::Null => Ok(None),
+        frame => Err(frame.to_error()),
+    }
+}
+
+/// 解析带有`count`的`SRandMember`等命令的响应。
+fn random_many(frame: Frame) -> crate::Result<Vec<Bytes>> {
+    match frame {
+        Frame::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Frame::Bulk(member) => Ok(member),
+                frame => Err(frame.to_error()),
+            })
+            .collect(),
+        frame => Err(frame.to_error()),
+    }
+}
+
+/// 解析经度和纬度组成的数组。
+fn coord(frame: Frame) -> crate::Result<(f64, f64)> {
+    match frame {
+        Frame::Array(items) => match <[Frame; 2]>::try_from(items) {
+            Ok([Frame::Bulk(lon), Frame::Bulk(lat)]) => {
+                Ok((parse_score(&lon)?, parse_score(&lat)?))
+            }
+            Ok(items) => Err(Frame::Array(items.into()).to_error()),
+            Err(items) => Err(Frame::Array(items).to_error()),
+        },
+        frame => Err(frame.to_error()),
+    }
+}
+
+/// 解析`Cluster Slots`返回的一个槽区间：`[start, end, [host, port, id]]`。
+fn slot_range(frame: Frame) -> crate::Result<SlotRange> {
+    let Frame::Array(items) = frame else {
+        return Err(frame.to_error());
+    };
+    match &items[..] {
+        [Frame::Integer(start), Frame::Integer(end), Frame::Array(node), ..] => match &node[..] {
+            [Frame::Bulk(host), Frame::Integer(port), ..] => Ok(SlotRange {
+                start: *start as u16,
+                end: *end as u16,
+                addr: format!("{}:{}", String::from_utf8_lossy(host), port),
+            }),
+            _ => Err("Cluster Slots 返回的节点格式不正确".into()),
+        },
+        _ => Err("Cluster Slots 返回的槽区间格式不正确".into()),
+    }
+}
+
+/// 解析`Command Info`返回的一个命令的信息。
+fn command_info(frame: Frame) -> crate::Result<Option<CommandInfo>> {
+    let items = match frame {
+        Frame::Null => return Ok(None),
+        Frame::Array(items) => items,
+        frame => return Err(frame.to_error()),
+    };
+    let strings = |frame: &Frame| match frame {
+        Frame::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Frame::Simple(s) => Ok(s.clone()),
+                Frame::Bulk(s) => Ok(String::from_utf8_lossy(s).into_owned()),
+                _ => Err(crate::Error::from("Command Info 返回的标志格式不正确")),
+            })
+            .collect::<crate::Result<Vec<_>>>(),
+        _ => Err("Command Info 返回的标志格式不正确".into()),
+    };
+    match &items[..] {
+        [Frame::Bulk(name), Frame::Integer(arity), flags, Frame::Integer(first_key), Frame::Integer(last_key), Frame::Integer(key_step), categories, ..] => {
+            Ok(Some(CommandInfo {
+                name: String::from_utf8_lossy(name).into_owned(),
+                arity: *arity,
+                flags: strings(flags)?,
+                first_key: *first_key,
+                last_key: *last_key,
+                key_step: *key_step,
+                categories: strings(categories)?,
+            }))
+        }
+        _ => Err("Command Info 返回的命令信息格式不正确".into()),
+    }
+}
+
+/// 解析`Function List`返回的一个函数库，信息为交替排列的名称和值。
+fn function_library(frame: Frame) -> crate::Result<FunctionLibrary> {
+    let items = match frame {
+        Frame::Array(items) => items,
+        frame => return Err(frame.to_error()),
+    };
+    let mut library = FunctionLibrary {
+        name: String::new(),
+        functions: vec![],
+        code: None,
+    };
+    let mut items = items.into_iter();
+    while let Some(field) = items.next() {
+        match (field, items.next()) {
+            (Frame::Bulk(field), Some(Frame::Bulk(value))) => {
+                let value = String::from_utf8_lossy(&value).into_owned();
+                match &field[..] {
+                    b"library_name" => library.name = value,
+                    b"library_code" => library.code = Some(value),
+                    _ => {}
+                }
+            }
+            (Frame::Bulk(field), Some(Frame::Array(functions))) if &field[..] == b"functions" => {
+                library.functions = functions
+                    .into_iter()
+                    .map(|function| match function {
+                        Frame::Bulk(name) => Ok(String::from_utf8_lossy(&name).into_owned()),
+                        frame => Err(frame.to_error()),
+                    })
+                    .collect::<crate::Result<_>>()?;
+            }
+            (frame, _) => return Err(frame.to_error()),
+        }
+    }
+    Ok(library)
+}
+
+/// 解析`GeoSearch`返回的一个成员。
+///
+/// 成员之后的信息依次为距离、geohash 和坐标，类型各不相同，所以可以根据类型区分。
+fn geo_location(frame: Frame) -> crate::Result<GeoLocation> {
+    let items = match frame {
+        Frame::Bulk(member) => {
+            return Ok(GeoLocation {
+                member,
+                dist: None,
+                hash: None,
+                coord: None,
+            })
+        }
+        Frame::Array(items) => items,
+        frame => return Err(frame.to_error()),
+    };
+
+    let mut items = items.into_iter();
+    let mut location = match items.next() {
+        Some(Frame::Bulk(member)) => GeoLocation {
+            member,
+            dist: None,
+            hash: None,
+            coord: None,
+        },
+        Some(frame) => return Err(frame.to_error()),
+        None => return Err(Frame::Array(vec![]).to_error()),
+    };
+    for item in items {
+        match item {
+            Frame::Bulk(dist) => location.dist = Some(parse_score(&dist)?),
+            Frame::Integer(hash) => location.hash = Some(hash as u64),
+            frame => location.coord = Some(coord(frame)?),
+        }
+    }
+    Ok(location)
+}
+
+/// 解析`LMPop`和`ZMPop`的响应，由 key 和弹出的元素组成的数组，`(nil)`返回`None`。
+fn mpop_reply(frame: Frame) -> crate::Result<Option<(String, Vec<Frame>)>> {
+    match frame {
+        Frame::Array(items) => match <[Frame; 2]>::try_from(items) {
+            Ok([Frame::Bulk(key), Frame::Array(items)]) => {
+                Ok(Some((String::from_utf8_lossy(&key).into_owned(), items)))
+            }
+            Ok(items) => Err(Frame::Array(items.into()).to_error()),
+            Err(items) => Err(Frame::Array(items).to_error()),
+        },
+        Frame::Null => Ok(None),
+        frame => Err(frame.to_error()),
+    }
+}
+
+/// 解析`XRead`和`XReadGroup`的响应，每一项为 key 和消息组成的数组，`(nil)`视为空。
+fn streams_reply(frame: Frame) -> crate::Result<Vec<(String, Vec<StreamEntry>)>> {
+    match frame {
+        Frame::Array(streams) => streams
+            .into_iter()
+            .map(|stream| match stream {
+                Frame::Array(items) => match <[Frame; 2]>::try_from(items) {
+                    Ok([Frame::Bulk(key), entries]) => Ok((
+                        String::from_utf8_lossy(&key).into_owned(),
+                        stream_entries(entries)?,
+                    )),
+                    Ok(items) => Err(Frame::Array(items.into()).to_error()),
+                    Err(items) => Err(Frame::Array(items).to_error()),
+                },
+                frame => Err(frame.to_error()),
+            })
+            .collect(),
+        Frame::Null => Ok(vec![]),
+        frame => Err(frame.to_error()),
+    }
+}
+
+/// 解析流中的消息组成的数组，每条消息为 ID 和字段、值交替出现的数组。
+fn stream_entries(frame: Frame) -> crate::Result<Vec<StreamEntry>> {
+    let entries = match frame {
+        Frame::Array(entries) => entries,
+        frame => return Err(frame.to_error()),
+    };
+    entries
+        .into_iter()
+        .map(|entry| {
+            let (id, items) = match entry {
+                Frame::Array(entry) => match <[Frame; 2]>::try_from(entry) {
+                    Ok([Frame::Bulk(id), Frame::Array(items)]) => (id, items),
+                    Ok(entry) => return Err(Frame::Array(entry.into()).to_error()),
+                    Err(entry) => return Err(Frame::Array(entry).to_error()),
+                },
+                frame => return Err(frame.to_error()),
+            };
+            let mut fields = Vec::with_capacity(items.len() / 2);
+            let mut items = items.into_iter();
+            while let Some(field) = items.next() {
+                match (field, items.next()) {
+                    (Frame::Bulk(field), Some(Frame::Bulk(value))) => fields.push((field, value)),
+                    (frame, _) => return Err(frame.to_error()),
+                }
+            }
+            Ok(StreamEntry {
+                id: String::from_utf8_lossy(&id).into_owned(),
+                fields,
+            })
+        })
+        .collect()
 }
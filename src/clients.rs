@@ -0,0 +1,285 @@
+//! 已连接的客户端。
+//!
+//! 每个`Handler`开始运行时登记自己的连接，关闭时注销，`Client List`等命令通过这里
+//! 查看所有的连接。`Client Kill`通过登记时返回的`Notify`通知对应的`Handler`结束运行。
+//!
+//! `Client Pause`暂停所有连接的命令，`Handler`在执行命令之前通过`wait_unpaused()`等待暂停结束。
+//!
+//! 最大连接数也在这里维护，`Listener`接收到连接后通过`try_acquire()`占用一个连接数，
+//! 上限可以通过`Config Set maxclients`在运行期间修改。
+
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    sync::Notify,
+    time::{self, Instant},
+};
+
+/// 默认的最大连接数。
+pub(crate) const DEFAULT_MAXCLIENTS: usize = 250;
+
+/// 所有已连接的客户端，由所有`Handler`共享。
+#[derive(Debug)]
+pub(crate) struct Clients {
+    clients: Mutex<BTreeMap<u64, ClientInfo>>,
+
+    // 下一个连接的编号，从`1`开始递增。
+    next_id: AtomicU64,
+
+    // `Client Pause`设置的暂停，`None`表示没有暂停。
+    pause: Mutex<Option<Pause>>,
+
+    // 暂停被`Client Unpause`取消或者被修改时，通知等待的`Handler`。
+    pause_changed: Notify,
+
+    // 最大连接数。调小时已有的连接不会被关闭，只是新的连接会被拒绝，直到连接数降到上限以下。
+    maxclients: AtomicUsize,
+
+    // 占用了连接数的连接的数量，见`try_acquire()`。
+    connections: AtomicUsize,
+}
+
+/// 命令的暂停。
+#[derive(Debug, Clone, Copy)]
+struct Pause {
+    // 暂停结束的时间。
+    until: Instant,
+    // 是否只暂停写命令。
+    write_only: bool,
+}
+
+/// 一个客户端的信息。
+#[derive(Debug)]
+struct ClientInfo {
+    addr: Option<SocketAddr>,
+    // `Client SetName`设置的名称，默认为空。
+    name: String,
+    user: String,
+    // 最近一次执行的命令的名称。
+    last_cmd: String,
+    // 执行最近一次命令时，连接的读缓存占用的字节数。
+    buffer: usize,
+    created_at: Instant,
+    last_interaction: Instant,
+    // `Client Kill`通过它通知`Handler`关闭连接。
+    kill: Arc<Notify>,
+}
+
+/// `Client Kill`的过滤条件，所有设置了的条件都满足的连接会被关闭。
+#[derive(Debug, Default)]
+pub(crate) struct KillFilter {
+    pub(crate) id: Option<u64>,
+    pub(crate) addr: Option<String>,
+    pub(crate) user: Option<String>,
+    // 不关闭这个编号的连接，用于`SkipMe yes`。
+    pub(crate) skip: Option<u64>,
+}
+
+impl Clients {
+    pub(crate) fn new() -> Clients {
+        Clients {
+            clients: Mutex::new(BTreeMap::new()),
+            next_id: AtomicU64::new(1),
+            pause: Mutex::new(None),
+            pause_changed: Notify::new(),
+            maxclients: AtomicUsize::new(DEFAULT_MAXCLIENTS),
+            connections: AtomicUsize::new(0),
+        }
+    }
+
+    /// 分配一个新的连接编号。
+    pub(crate) fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// 登记一个连接，返回的`Notify`被通知时，连接应该被关闭。
+    pub(crate) fn register(&self, id: u64, addr: Option<SocketAddr>, user: &str) -> Arc<Notify> {
+        let kill = Arc::new(Notify::new());
+        let now = Instant::now();
+        let info = ClientInfo {
+            addr,
+            name: String::new(),
+            user: user.to_string(),
+            last_cmd: "NULL".to_string(),
+            buffer: 0,
+            created_at: now,
+            last_interaction: now,
+            kill: kill.clone(),
+        };
+        self.clients.lock().unwrap().insert(id, info);
+        kill
+    }
+
+    /// 注销连接。
+    pub(crate) fn unregister(&self, id: u64) {
+        self.clients.lock().unwrap().remove(&id);
+    }
+
+    /// 记录连接执行的命令，以及执行命令时连接使用的用户和读缓存的大小。
+    pub(crate) fn touch(&self, id: u64, cmd: &str, user: &str, buffer: usize) {
+        if let Some(info) = self.clients.lock().unwrap().get_mut(&id) {
+            info.last_cmd = cmd.to_string();
+            info.user = user.to_string();
+            info.buffer = buffer;
+            info.last_interaction = Instant::now();
+        }
+    }
+
+    /// 所有连接的读缓存占用的字节数。
+    pub(crate) fn buffer_size(&self) -> usize {
+        let clients = self.clients.lock().unwrap();
+        clients.values().map(|info| info.buffer).sum()
+    }
+
+    /// 获取连接的名称，没有设置时为空字符串。
+    pub(crate) fn name(&self, id: u64) -> String {
+        let clients = self.clients.lock().unwrap();
+        clients
+            .get(&id)
+            .map(|info| info.name.clone())
+            .unwrap_or_default()
+    }
+
+    /// 设置连接的名称，空字符串表示清除名称。
+    pub(crate) fn set_name(&self, id: u64, name: &str) {
+        if let Some(info) = self.clients.lock().unwrap().get_mut(&id) {
+            info.name = name.to_string();
+        }
+    }
+
+    /// 获取所有连接的信息，每个连接一行，格式与 Redis 的`Client List`一致。
+    pub(crate) fn list(&self) -> String {
+        let clients = self.clients.lock().unwrap();
+        let now = Instant::now();
+        clients
+            .iter()
+            .map(|(id, info)| {
+                let addr = info.addr.map(|addr| addr.to_string()).unwrap_or_default();
+                format!(
+                    "id={} addr={} name={} age={} idle={} qbuf={} user={} cmd={}\n",
+                    id,
+                    addr,
+                    info.name,
+                    now.duration_since(info.created_at).as_secs(),
+                    now.duration_since(info.last_interaction).as_secs(),
+                    info.buffer,
+                    info.user,
+                    info.last_cmd,
+                )
+            })
+            .collect()
+    }
+
+    /// 在`timeout`时间内暂停命令，`write_only`为`true`时只暂停写命令。
+    ///
+    /// 已经有暂停时，取两者中更晚的结束时间，并且只要有一个暂停所有命令，就暂停所有命令。
+    pub(crate) fn pause(&self, timeout: Duration, write_only: bool) {
+        let mut pause = self.pause.lock().unwrap();
+        let mut new = Pause {
+            until: Instant::now() + timeout,
+            write_only,
+        };
+        if let Some(old) = pause.filter(|old| old.until > Instant::now()) {
+            new.until = new.until.max(old.until);
+            new.write_only = new.write_only && old.write_only;
+        }
+        *pause = Some(new);
+        self.pause_changed.notify_waiters();
+    }
+
+    /// 取消暂停，等待的命令立即开始执行。
+    pub(crate) fn unpause(&self) {
+        *self.pause.lock().unwrap() = None;
+        self.pause_changed.notify_waiters();
+    }
+
+    /// 命令现在是否被暂停，`write`表示命令是否为写命令。
+    pub(crate) fn is_paused(&self, write: bool) -> bool {
+        matches!(*self.pause.lock().unwrap(),
+            Some(pause) if (write || !pause.write_only) && pause.until > Instant::now())
+    }
+
+    /// 如果命令被暂停了，等待暂停结束。`write`表示命令是否为写命令。
+    pub(crate) async fn wait_unpaused(&self, write: bool) {
+        loop {
+            // 在检查暂停之前创建`Notified`，避免错过检查之后的通知。
+            let changed = self.pause_changed.notified();
+            let until = match *self.pause.lock().unwrap() {
+                Some(pause) if (write || !pause.write_only) && pause.until > Instant::now() => {
+                    pause.until
+                }
+                _ => return,
+            };
+            tokio::select! {
+                _ = time::sleep_until(until) => {}
+                _ = changed => {}
+            }
+        }
+    }
+
+    /// 获取最大连接数。
+    pub(crate) fn maxclients(&self) -> usize {
+        self.maxclients.load(Ordering::Relaxed)
+    }
+
+    /// 修改最大连接数。
+    pub(crate) fn set_maxclients(&self, maxclients: usize) {
+        self.maxclients.store(maxclients, Ordering::Relaxed);
+    }
+
+    /// 如果连接数没有达到上限，占用一个连接数并返回`true`。
+    ///
+    /// 占用的连接数需要在连接关闭时通过`release()`释放。
+    pub(crate) fn try_acquire(&self) -> bool {
+        let maxclients = self.maxclients();
+        self.connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |connections| {
+                (connections < maxclients).then_some(connections + 1)
+            })
+            .is_ok()
+    }
+
+    /// 占用一个连接数，不检查上限，用于不经过`Listener`建立的连接。
+    #[cfg(feature = "test-util")]
+    pub(crate) fn acquire(&self) {
+        self.connections.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// 获取当前的连接数。
+    pub(crate) fn connected(&self) -> usize {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    /// 释放`try_acquire()`占用的连接数。
+    pub(crate) fn release(&self) {
+        self.connections.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    /// 关闭所有满足过滤条件的连接，返回关闭的连接数。
+    pub(crate) fn kill(&self, filter: &KillFilter) -> usize {
+        let clients = self.clients.lock().unwrap();
+        let mut killed = 0;
+        for (id, info) in clients.iter() {
+            let addr = info.addr.map(|addr| addr.to_string());
+            if filter.skip == Some(*id)
+                || filter.id.is_some_and(|target| target != *id)
+                || filter.addr.is_some() && filter.addr != addr
+                || filter.user.as_ref().is_some_and(|user| *user != info.user)
+            {
+                continue;
+            }
+            // `notify_one()`会保存通知，`Handler`还没有开始等待时也不会错过。
+            info.kill.notify_one();
+            killed += 1;
+        }
+        killed
+    }
+}
@@ -0,0 +1,217 @@
+//! 集群模式。
+//!
+//! key 通过 CRC16 映射到`SLOTS`个槽中的一个，每个节点负责一部分槽。
+//! 命令操作的 key 所在的槽不属于当前节点时，返回`-MOVED <slot> <host:port>`，
+//! 客户端应该将命令发送到对应的节点。
+//!
+//! 迁移槽时，通过`Cluster SetSlot`将源节点的槽标记为`MIGRATING`，目标节点标记为`IMPORTING`。
+//! 源节点上不存在的 key 返回`-ASK <slot> <host:port>`，客户端需要先向目标节点发送`Asking`，
+//! 再发送命令。迁移完成后通过`Cluster SetSlot <slot> NODE <host:port>`修改槽的归属。
+//!
+//! 节点之间不会交换配置，每个节点的槽分配需要分别设置。
+
+use std::{collections::HashMap, ops::RangeInclusive, sync::Mutex};
+
+/// 槽的数量。
+pub const SLOTS: u16 = 16384;
+
+/// 计算 key 所在的槽。
+///
+/// 与 Redis 一样，如果 key 中包含非空的`{...}`，只使用第一对花括号中的内容计算，
+/// 这样可以让相关的 key 分配到同一个槽中。
+pub fn key_slot(key: &[u8]) -> u16 {
+    let key = match key.iter().position(|&b| b == b'{') {
+        Some(start) => match key[start + 1..].iter().position(|&b| b == b'}') {
+            Some(len) if len > 0 => &key[start + 1..start + 1 + len],
+            _ => key,
+        },
+        None => key,
+    };
+    crc16(key) % SLOTS
+}
+
+/// CRC16-CCITT (XMODEM)，Redis 集群使用的校验和算法。
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// 节点的 ID，由节点的地址计算得到，所有节点计算的结果相同。
+pub(crate) fn node_id(addr: &str) -> String {
+    sha1_smol::Sha1::from(addr).digest().to_string()
+}
+
+/// 集群的配置，通过`Server::cluster()`开启。
+///
+/// 地址的格式为`<host>:<port>`。
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    myself: String,
+    nodes: Vec<(String, RangeInclusive<u16>)>,
+}
+
+impl Cluster {
+    /// 创建集群的配置，`myself`为当前节点的地址。
+    pub fn new(myself: impl ToString) -> Cluster {
+        Cluster {
+            myself: myself.to_string(),
+            nodes: vec![],
+        }
+    }
+
+    /// 将`slots`分配给`addr`上的节点，后分配的覆盖先分配的。超出范围的槽会被忽略。
+    pub fn node(mut self, addr: impl ToString, slots: RangeInclusive<u16>) -> Cluster {
+        self.nodes.push((addr.to_string(), slots));
+        self
+    }
+}
+
+/// 解析`<host>:<port>=<start>-<end>`格式的节点，`-<end>`可以省略。
+///
+/// # Errors
+/// 格式不对或者槽不是合法的数字时返回`Err`。
+pub fn parse_node(s: &str) -> Result<(String, RangeInclusive<u16>), String> {
+    let (addr, slots) = s
+        .split_once('=')
+        .ok_or("格式应为<host>:<port>=<start>-<end>")?;
+    let (start, end) = slots.split_once('-').unwrap_or((slots, slots));
+    let parse = |slot: &str| {
+        slot.parse::<u16>()
+            .map_err(|_| format!("槽不合法：{}", slot))
+    };
+    Ok((addr.to_string(), parse(start)?..=parse(end)?))
+}
+
+/// 命令应该在哪里执行。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Route {
+    /// 在当前节点执行。
+    Local,
+    /// 槽属于另一个节点。
+    Moved(String),
+    /// 槽正在迁移，key 已经不在当前节点了。
+    Ask(String),
+    /// 槽没有分配给任何节点。
+    Down,
+}
+
+/// 迁移槽时对槽的修改，见`Cluster SetSlot`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SlotState {
+    Migrating(String),
+    Importing(String),
+    Node(String),
+    Stable,
+}
+
+/// 集群的运行时状态。
+#[derive(Debug)]
+pub(crate) struct ClusterState {
+    myself: String,
+    slots: Mutex<Slots>,
+}
+
+#[derive(Debug)]
+struct Slots {
+    // 每个槽所属节点的地址。
+    owners: Vec<Option<String>>,
+    // 正在迁出的槽，value 为目标节点的地址。
+    migrating: HashMap<u16, String>,
+    // 正在迁入的槽，value 为源节点的地址。
+    importing: HashMap<u16, String>,
+}
+
+impl ClusterState {
+    pub(crate) fn new(config: Cluster) -> ClusterState {
+        let mut owners = vec![None; SLOTS as usize];
+        for (addr, slots) in config.nodes {
+            for slot in slots.filter(|&slot| slot < SLOTS) {
+                owners[slot as usize] = Some(addr.clone());
+            }
+        }
+        ClusterState {
+            myself: config.myself,
+            slots: Mutex::new(Slots {
+                owners,
+                migrating: HashMap::new(),
+                importing: HashMap::new(),
+            }),
+        }
+    }
+
+    /// 判断操作`slot`中的 key 的命令应该在哪里执行。
+    ///
+    /// `asking`表示连接在这条命令之前发送了`Asking`，`exists`检查 key 是否存在于当前节点。
+    pub(crate) fn route(&self, slot: u16, asking: bool, exists: impl FnOnce() -> bool) -> Route {
+        let slots = self.slots.lock().unwrap();
+        match &slots.owners[slot as usize] {
+            Some(owner) if *owner == self.myself => match slots.migrating.get(&slot) {
+                Some(target) if !exists() => Route::Ask(target.clone()),
+                _ => Route::Local,
+            },
+            _ if asking && slots.importing.contains_key(&slot) => Route::Local,
+            Some(owner) => Route::Moved(owner.clone()),
+            None => Route::Down,
+        }
+    }
+
+    /// 修改槽的迁移状态或者归属。
+    ///
+    /// # Errors
+    /// 只有拥有槽的节点才能迁出，已经拥有槽的节点不能迁入。
+    pub(crate) fn set_slot(&self, slot: u16, state: SlotState) -> Result<(), String> {
+        let mut slots = self.slots.lock().unwrap();
+        let owned = slots.owners[slot as usize].as_ref() == Some(&self.myself);
+        match state {
+            SlotState::Migrating(target) => {
+                if !owned {
+                    return Err(format!("ERR I'm not the owner of hash slot {}", slot));
+                }
+                slots.migrating.insert(slot, target);
+            }
+            SlotState::Importing(source) => {
+                if owned {
+                    return Err(format!("ERR I'm already the owner of hash slot {}", slot));
+                }
+                slots.importing.insert(slot, source);
+            }
+            SlotState::Node(owner) => {
+                slots.migrating.remove(&slot);
+                slots.importing.remove(&slot);
+                slots.owners[slot as usize] = Some(owner);
+            }
+            SlotState::Stable => {
+                slots.migrating.remove(&slot);
+                slots.importing.remove(&slot);
+            }
+        }
+        Ok(())
+    }
+
+    /// 获取已经分配的槽，相邻且属于同一个节点的槽合并为一个区间，按照槽的顺序返回。
+    pub(crate) fn slot_ranges(&self) -> Vec<(u16, u16, String)> {
+        let slots = self.slots.lock().unwrap();
+        let mut ranges: Vec<(u16, u16, String)> = vec![];
+        for (slot, owner) in slots.owners.iter().enumerate() {
+            let Some(owner) = owner else {
+                continue;
+            };
+            let slot = slot as u16;
+            match ranges.last_mut() {
+                Some((_, end, last)) if *end + 1 == slot && last == owner => *end = slot,
+                _ => ranges.push((slot, slot, owner.clone())),
+            }
+        }
+        ranges
+    }
+}
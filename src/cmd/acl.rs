@@ -0,0 +1,155 @@
+use bytes::Bytes;
+
+use crate::{interceptor::ConnContext, Connection, Db, Frame, Parse};
+
+/// 管理 ACL 用户。
+///
+/// 格式：Acl SetUser <username> [<rule> ...]
+///      Acl GetUser <username>
+///      Acl DelUser <username> [<username> ...]
+///      Acl List
+///      Acl WhoAmI
+///      Acl Load
+///
+/// 规则的格式见`acl`模块。`Acl Load`重新加载启动时通过`--aclfile`指定的文件。
+#[derive(Debug)]
+pub struct AclCommand {
+    op: AclOp,
+}
+
+#[derive(Debug)]
+enum AclOp {
+    SetUser(String, Vec<String>),
+    GetUser(String),
+    DelUser(Vec<String>),
+    List,
+    WhoAmI,
+    Load,
+}
+
+impl AclCommand {
+    /// 创建一个`Acl SetUser`命令。
+    pub fn set_user(username: impl ToString, rules: Vec<String>) -> AclCommand {
+        AclCommand {
+            op: AclOp::SetUser(username.to_string(), rules),
+        }
+    }
+
+    /// 创建一个`Acl DelUser`命令。
+    pub fn del_user(usernames: Vec<String>) -> AclCommand {
+        AclCommand {
+            op: AclOp::DelUser(usernames),
+        }
+    }
+
+    /// 创建一个`Acl List`命令。
+    pub fn list() -> AclCommand {
+        AclCommand { op: AclOp::List }
+    }
+
+    /// 创建一个`Acl WhoAmI`命令。
+    pub fn whoami() -> AclCommand {
+        AclCommand { op: AclOp::WhoAmI }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`AclCommand`命令。
+    ///
+    /// 需要保证字符串`Acl`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<AclCommand> {
+        let op = match &parse.next_string()?.to_lowercase()[..] {
+            "setuser" => {
+                let username = parse.next_string()?;
+                let mut rules = vec![];
+                while parse.remaining() > 0 {
+                    rules.push(parse.next_string()?);
+                }
+                AclOp::SetUser(username, rules)
+            }
+            "getuser" => AclOp::GetUser(parse.next_string()?),
+            "deluser" => {
+                let mut usernames = vec![parse.next_string()?];
+                while parse.remaining() > 0 {
+                    usernames.push(parse.next_string()?);
+                }
+                AclOp::DelUser(usernames)
+            }
+            "list" => AclOp::List,
+            "whoami" => AclOp::WhoAmI,
+            "load" => AclOp::Load,
+            sub => return Err(crate::Error::Parse(format!("未知的 Acl 子命令：'{}'", sub))),
+        };
+        Ok(AclCommand { op })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        ctx: &ConnContext,
+    ) -> crate::Result<()> {
+        let acl = db.acl();
+        let ok = || Frame::Simple("OK".to_string());
+        let bulk = |s: &str| Frame::Bulk(Bytes::from(s.to_string()));
+        let response = match self.op {
+            AclOp::SetUser(username, rules) => match acl.set_user(&username, &rules) {
+                Ok(()) => ok(),
+                Err(msg) => Frame::Error(msg),
+            },
+            AclOp::GetUser(username) => match acl.get_user(&username) {
+                Some(user) => Frame::Array(vec![
+                    bulk("flags"),
+                    Frame::Array(user.flags().into_iter().map(bulk).collect()),
+                    bulk("passwords"),
+                    Frame::Array(user.passwords().map(bulk).collect()),
+                    bulk("commands"),
+                    bulk(&user.command_rules()),
+                    bulk("keys"),
+                    bulk(&user.key_rules()),
+                ]),
+                None => Frame::Null,
+            },
+            AclOp::DelUser(usernames) => match acl.del_users(&usernames) {
+                Ok(count) => Frame::Integer(count as i64),
+                Err(msg) => Frame::Error(msg),
+            },
+            AclOp::List => Frame::Array(acl.list().iter().map(|user| bulk(user)).collect()),
+            AclOp::WhoAmI => bulk(ctx.user()),
+            AclOp::Load => match acl.reload() {
+                Ok(()) => ok(),
+                Err(msg) => Frame::Error(msg),
+            },
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("acl".as_bytes()));
+        match self.op {
+            AclOp::SetUser(username, rules) => {
+                frame.push_bulk(Bytes::from("setuser".as_bytes()));
+                frame.push_bulk(Bytes::from(username.into_bytes()));
+                for rule in rules {
+                    frame.push_bulk(Bytes::from(rule.into_bytes()));
+                }
+            }
+            AclOp::GetUser(username) => {
+                frame.push_bulk(Bytes::from("getuser".as_bytes()));
+                frame.push_bulk(Bytes::from(username.into_bytes()));
+            }
+            AclOp::DelUser(usernames) => {
+                frame.push_bulk(Bytes::from("deluser".as_bytes()));
+                for username in usernames {
+                    frame.push_bulk(Bytes::from(username.into_bytes()));
+                }
+            }
+            AclOp::List => frame.push_bulk(Bytes::from("list".as_bytes())),
+            AclOp::WhoAmI => frame.push_bulk(Bytes::from("whoami".as_bytes())),
+            AclOp::Load => frame.push_bulk(Bytes::from("load".as_bytes())),
+        }
+        frame
+    }
+}
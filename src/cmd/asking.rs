@@ -0,0 +1,51 @@
+use bytes::Bytes;
+
+use crate::{interceptor::ConnContext, Connection, Db, Frame, Parse};
+
+/// 集群模式下，告诉服务器下一条命令是`ASK`重定向过来的。
+///
+/// 格式：Asking
+///
+/// 下一条命令操作的 key 所在的槽正在迁入当前节点时，命令会在当前节点执行，而不是返回`MOVED`。
+/// 没有开启集群模式时返回错误。
+#[derive(Debug, Default)]
+pub struct Asking;
+
+impl Asking {
+    /// 创建一个`Asking`命令。
+    pub fn new() -> Asking {
+        Asking
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Asking`命令。
+    ///
+    /// `Parse`提供了类似迭代器的 API 来解析`Frame`。
+    /// 需要保证字符串`Asking`已经被处理过了。
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Asking> {
+        Ok(Asking)
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        ctx: &mut ConnContext,
+    ) -> crate::Result<()> {
+        let response = if db.cluster().is_some() {
+            ctx.set_asking();
+            Frame::Simple("OK".to_string())
+        } else {
+            Frame::Error(super::cluster::CLUSTER_DISABLED.to_string())
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("asking".as_bytes()));
+        frame
+    }
+}
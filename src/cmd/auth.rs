@@ -0,0 +1,90 @@
+use bytes::Bytes;
+
+use crate::{
+    acl::DEFAULT_USER, interceptor::ConnContext, Connection, Db, Frame, Parse, ParseError,
+};
+
+/// 认证当前连接。
+///
+/// 格式：Auth [username] <password>
+///
+/// 没有指定用户名时认证为默认用户。认证成功后，连接的权限由用户的 ACL 规则决定。
+#[derive(Debug)]
+pub struct Auth {
+    username: Option<String>,
+    password: String,
+}
+
+impl Auth {
+    /// 创建一个`Auth`命令。
+    pub fn new(username: Option<String>, password: impl ToString) -> Auth {
+        Auth {
+            username,
+            password: password.to_string(),
+        }
+    }
+
+    /// 获取用户名。
+    pub fn username(&self) -> Option<&str> {
+        self.username.as_deref()
+    }
+
+    /// 获取密码。
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Auth`命令。
+    ///
+    /// `Parse`提供了类似迭代器的 API 来解析`Frame`。
+    /// 需要保证字符串`Auth`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Auth> {
+        // 至少要有密码。
+        let first = parse.next_string()?;
+        // 如果还有第二个参数，那么第一个参数是用户名。
+        match parse.next_string() {
+            Ok(password) => Ok(Auth::new(Some(first), password)),
+            Err(ParseError::EndOfStream) => Ok(Auth::new(None, first)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 与 Redis 一样，默认用户不需要密码时，只有密码的`Auth`返回错误。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        ctx: &mut ConnContext,
+    ) -> crate::Result<()> {
+        let username = self.username.as_deref().unwrap_or(DEFAULT_USER);
+        let response = if self.username.is_none() && db.acl().is_nopass(DEFAULT_USER) {
+            Frame::Error(
+                "ERR AUTH <password> called without any password configured for the default user"
+                    .to_string(),
+            )
+        } else {
+            match db.acl().authenticate(username, &self.password) {
+                Ok(()) => {
+                    ctx.set_user(username);
+                    Frame::Simple("OK".to_string())
+                }
+                Err(msg) => Frame::Error(msg),
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("auth".as_bytes()));
+        if let Some(username) = self.username {
+            frame.push_bulk(Bytes::from(username.into_bytes()));
+        }
+        frame.push_bulk(Bytes::from(self.password.into_bytes()));
+        frame
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use bytes::Bytes;
+
+use crate::{snapshot, Connection, Db, Frame, Parse};
+
+/// 将数据库的快照写入服务器上的文件。
+///
+/// 格式：Backup <path>
+///
+/// 快照是某一时刻数据库的完整副本，写文件期间服务器可以继续处理其他命令。
+#[derive(Debug)]
+pub struct Backup {
+    path: PathBuf,
+}
+
+/// 使用服务器上的快照文件替换数据库中的所有数据。
+///
+/// 格式：RestoreFrom <path>
+#[derive(Debug)]
+pub struct RestoreFrom {
+    path: PathBuf,
+}
+
+impl Backup {
+    /// 创建一个`Backup`命令。
+    pub fn new(path: impl Into<PathBuf>) -> Backup {
+        Backup { path: path.into() }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Backup`命令。
+    ///
+    /// 需要保证字符串`Backup`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Backup> {
+        let path = parse.next_string()?;
+        Ok(Backup::new(path))
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 写文件失败不会关闭连接，而是向客户端返回错误信息。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let entries = db.snapshot();
+        let response = match snapshot::write_file(&self.path, &entries).await {
+            Ok(()) => Frame::Simple("OK".to_string()),
+            Err(err) => Frame::Error(format!("ERR Backup failed: {}", err)),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("backup".as_bytes()));
+        frame.push_bulk(Bytes::from(self.path.to_string_lossy().into_owned()));
+        frame
+    }
+}
+
+impl RestoreFrom {
+    /// 创建一个`RestoreFrom`命令。
+    pub fn new(path: impl Into<PathBuf>) -> RestoreFrom {
+        RestoreFrom { path: path.into() }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`RestoreFrom`命令。
+    ///
+    /// 需要保证字符串`RestoreFrom`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<RestoreFrom> {
+        let path = parse.next_string()?;
+        Ok(RestoreFrom::new(path))
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 先完整读取并解析文件，成功后才替换数据，因此文件损坏时数据库保持不变。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match snapshot::read_file(&self.path).await {
+            Ok(entries) => {
+                db.restore(entries);
+                Frame::Simple("OK".to_string())
+            }
+            Err(err) => Frame::Error(format!("ERR Restore failed: {}", err)),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("restorefrom".as_bytes()));
+        frame.push_bulk(Bytes::from(self.path.to_string_lossy().into_owned()));
+        frame
+    }
+}
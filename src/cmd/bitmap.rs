@@ -0,0 +1,204 @@
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse, ParseError};
+
+/// 位偏移量的上限，与 Redis 一样，字符串最多为 512MB。
+const MAX_BIT_OFFSET: u64 = (512 * 1024 * 1024 * 8) - 1;
+
+/// 设置字符串中第`offset`位的值，返回这一位原来的值。
+///
+/// 格式：SetBit <key> <offset> <value>
+///
+/// 第`0`位是第一个字节的最高位，`value`只能是`0`或`1`。
+/// 如果`offset`超出了字符串的长度，字符串会用`0`填充到足够的长度。
+#[derive(Debug)]
+pub struct SetBit {
+    key: String,
+    offset: u64,
+    on: bool,
+}
+
+/// 获取字符串中第`offset`位的值。
+///
+/// 格式：GetBit <key> <offset>
+///
+/// 如果 key 不存在，或者`offset`超出了字符串的长度，返回`0`。
+#[derive(Debug)]
+pub struct GetBit {
+    key: String,
+    offset: u64,
+}
+
+/// 统计字符串中值为`1`的位的数量。
+///
+/// 格式：BitCount <key> [<start> <end>]
+///
+/// `start`和`end`是以字节为单位的下标，可以是负数，规则与`LRange`相同。
+#[derive(Debug)]
+pub struct BitCount {
+    key: String,
+    range: Option<(i64, i64)>,
+}
+
+impl SetBit {
+    /// 创建一个`SetBit`命令，`on`为`true`时将这一位设置为`1`。
+    pub fn new(key: impl ToString, offset: u64, on: bool) -> SetBit {
+        SetBit {
+            key: key.to_string(),
+            offset,
+            on,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`SetBit`命令。
+    ///
+    /// 需要保证字符串`SetBit`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SetBit> {
+        let key = parse.next_string()?;
+        let offset = next_offset(parse)?;
+        let on = match &parse.next_string()?[..] {
+            "0" => false,
+            "1" => true,
+            _ => {
+                return Err(crate::Error::invalid(
+                    "bit is not an integer or out of range",
+                ))
+            }
+        };
+        Ok(SetBit { key, offset, on })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = if self.offset > MAX_BIT_OFFSET {
+            Frame::Error("ERR bit offset is not an integer or out of range".to_string())
+        } else {
+            match db.setbit(&self.key, self.offset as usize, self.on) {
+                Ok(prev) => Frame::Integer(prev as i64),
+                Err(err) => Frame::Error(err.to_string()),
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("setbit".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.offset.to_string()));
+        frame.push_bulk(Bytes::from(if self.on { "1" } else { "0" }));
+        frame
+    }
+}
+
+impl GetBit {
+    /// 创建一个`GetBit`命令。
+    pub fn new(key: impl ToString, offset: u64) -> GetBit {
+        GetBit {
+            key: key.to_string(),
+            offset,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`GetBit`命令。
+    ///
+    /// 需要保证字符串`GetBit`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<GetBit> {
+        let key = parse.next_string()?;
+        let offset = next_offset(parse)?;
+        Ok(GetBit { key, offset })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        // 超出上限的位不可能被设置过，直接视为`0`。
+        let offset = usize::try_from(self.offset).unwrap_or(usize::MAX);
+        let response = match db.getbit(&self.key, offset) {
+            Ok(bit) => Frame::Integer(bit as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("getbit".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.offset.to_string()));
+        frame
+    }
+}
+
+impl BitCount {
+    /// 创建一个`BitCount`命令，`range`为`None`时统计整个字符串。
+    pub fn new(key: impl ToString, range: Option<(i64, i64)>) -> BitCount {
+        BitCount {
+            key: key.to_string(),
+            range,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`BitCount`命令。
+    ///
+    /// 需要保证字符串`BitCount`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<BitCount> {
+        let key = parse.next_string()?;
+        let range = match parse.next_signed_int() {
+            Ok(start) => Some((start, parse.next_signed_int()?)),
+            Err(ParseError::EndOfStream) => None,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(BitCount { key, range })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.bitcount(&self.key, self.range) {
+            Ok(count) => Frame::Integer(count as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("bitcount".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        if let Some((start, stop)) = self.range {
+            frame.push_bulk(Bytes::from(start.to_string()));
+            frame.push_bulk(Bytes::from(stop.to_string()));
+        }
+        frame
+    }
+}
+
+/// 读取一个位偏移量，不能是负数，也不能超过`MAX_BIT_OFFSET`。
+fn next_offset(parse: &mut Parse) -> crate::Result<u64> {
+    parse
+        .next_string()?
+        .parse::<u64>()
+        .ok()
+        .filter(|offset| *offset <= MAX_BIT_OFFSET)
+        .ok_or_else(|| crate::Error::invalid("bit offset is not an integer or out of range"))
+}
@@ -0,0 +1,306 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::{
+    clients::KillFilter, interceptor::ConnContext, Connection, Db, Frame, Parse, ParseError,
+};
+
+/// 管理客户端连接。
+///
+/// 格式：Client Tracking <ON | OFF>
+///      Client Id
+///      Client SetName <name>
+///      Client GetName
+///      Client List
+///      Client Kill <ip:port>
+///      Client Kill [ID <id>] [ADDR <ip:port>] [USER <username>] [SKIPME <yes | no>]
+///      Client Pause <timeout> [WRITE | ALL]
+///      Client Unpause
+///
+/// 开启追踪后，连接读取过的 key 被修改、删除或者过期时，
+/// 服务器会向`INVALIDATE_CHANNEL`信道发送一条失效信息，内容为这个 key。
+/// 每次读取只会触发一次失效信息，客户端需要再次读取才会重新追踪。
+///
+/// `Client Kill <ip:port>`关闭一个连接，不存在时返回错误；使用过滤条件时返回关闭的连接数，
+/// 默认不会关闭当前连接。
+///
+/// `Client Pause`在`timeout`毫秒内暂停所有连接的命令，`WRITE`只暂停写命令，默认为`ALL`。
+/// 被暂停的命令等待暂停结束后再执行，`Client`命令本身不会被暂停。
+#[derive(Debug)]
+pub struct ClientCommand {
+    op: ClientOp,
+}
+
+#[derive(Debug)]
+enum ClientOp {
+    Tracking(bool),
+    Id,
+    SetName(String),
+    GetName,
+    List,
+    // 只指定了地址的旧格式。
+    KillAddr(String),
+    Kill { filter: KillFilter, skip_me: bool },
+    Pause { timeout: Duration, write_only: bool },
+    Unpause,
+}
+
+impl ClientCommand {
+    /// 创建一个`Client Tracking`命令。
+    pub fn tracking(on: bool) -> ClientCommand {
+        ClientCommand {
+            op: ClientOp::Tracking(on),
+        }
+    }
+
+    /// 创建一个`Client Id`命令。
+    pub fn id() -> ClientCommand {
+        ClientCommand { op: ClientOp::Id }
+    }
+
+    /// 创建一个`Client SetName`命令。
+    pub fn set_name(name: impl ToString) -> ClientCommand {
+        ClientCommand {
+            op: ClientOp::SetName(name.to_string()),
+        }
+    }
+
+    /// 创建一个`Client GetName`命令。
+    pub fn get_name() -> ClientCommand {
+        ClientCommand {
+            op: ClientOp::GetName,
+        }
+    }
+
+    /// 创建一个`Client List`命令。
+    pub fn list() -> ClientCommand {
+        ClientCommand { op: ClientOp::List }
+    }
+
+    /// 创建一个`Client Kill ID <id>`命令。
+    pub fn kill_id(id: u64) -> ClientCommand {
+        ClientCommand {
+            op: ClientOp::Kill {
+                filter: KillFilter {
+                    id: Some(id),
+                    ..KillFilter::default()
+                },
+                skip_me: true,
+            },
+        }
+    }
+
+    /// 创建一个`Client Pause`命令。
+    pub fn pause(timeout: Duration, write_only: bool) -> ClientCommand {
+        ClientCommand {
+            op: ClientOp::Pause {
+                timeout,
+                write_only,
+            },
+        }
+    }
+
+    /// 创建一个`Client Unpause`命令。
+    pub fn unpause() -> ClientCommand {
+        ClientCommand {
+            op: ClientOp::Unpause,
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ClientCommand`命令。
+    ///
+    /// `Parse`提供了类似迭代器的 API 来解析`Frame`。
+    /// 需要保证字符串`Client`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ClientCommand> {
+        let op = match &parse.next_string()?.to_lowercase()[..] {
+            "tracking" => match &parse.next_string()?.to_lowercase()[..] {
+                "on" => ClientOp::Tracking(true),
+                "off" => ClientOp::Tracking(false),
+                _ => return Err(crate::Error::invalid("syntax error")),
+            },
+            "id" => ClientOp::Id,
+            "setname" => ClientOp::SetName(parse.next_string()?),
+            "getname" => ClientOp::GetName,
+            "list" => ClientOp::List,
+            "kill" if parse.remaining() == 1 => ClientOp::KillAddr(parse.next_string()?),
+            "kill" => {
+                let mut filter = KillFilter::default();
+                let mut skip_me = true;
+                while parse.remaining() > 0 {
+                    let option = parse.next_string()?.to_lowercase();
+                    let value = parse
+                        .next_string()
+                        .map_err(|_| crate::Error::invalid("syntax error"))?;
+                    match &option[..] {
+                        "id" => {
+                            let id = value.parse().map_err(|_| {
+                                crate::Error::invalid("client-id should be greater than 0")
+                            })?;
+                            filter.id = Some(id);
+                        }
+                        "addr" => filter.addr = Some(value),
+                        "user" => filter.user = Some(value),
+                        "skipme" => {
+                            skip_me = match &value.to_lowercase()[..] {
+                                "yes" => true,
+                                "no" => false,
+                                _ => return Err(crate::Error::invalid("syntax error")),
+                            }
+                        }
+                        _ => return Err(crate::Error::invalid("syntax error")),
+                    }
+                }
+                ClientOp::Kill { filter, skip_me }
+            }
+            "pause" => {
+                let timeout = parse.next_int().map_err(|_| {
+                    crate::Error::invalid("timeout is not an integer or out of range")
+                })?;
+                let write_only = match parse.next_string() {
+                    Ok(mode) => match &mode.to_lowercase()[..] {
+                        "write" => true,
+                        "all" => false,
+                        _ => return Err(crate::Error::invalid("syntax error")),
+                    },
+                    Err(ParseError::EndOfStream) => false,
+                    Err(err) => return Err(err.into()),
+                };
+                ClientOp::Pause {
+                    timeout: Duration::from_millis(timeout),
+                    write_only,
+                }
+            }
+            "unpause" => ClientOp::Unpause,
+            sub => {
+                return Err(crate::Error::Parse(format!(
+                    "未知的 Client 子命令：'{}'",
+                    sub
+                )))
+            }
+        };
+        Ok(ClientCommand { op })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 追踪状态同时记录在连接的上下文和`Db`中，前者用于判断是否需要记录读取的 key。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        ctx: &mut ConnContext,
+    ) -> crate::Result<()> {
+        let ok = || Frame::Simple("OK".to_string());
+        let response = match self.op {
+            ClientOp::Tracking(on) => {
+                db.set_tracking(ctx.id(), on);
+                ctx.set_tracking(on);
+                ok()
+            }
+            ClientOp::Id => Frame::Integer(ctx.id() as i64),
+            ClientOp::SetName(name) => {
+                // 名称会出现在`Client List`中，不能包含空格和特殊字符。
+                if name.chars().any(|c| !c.is_ascii_graphic()) {
+                    Frame::Error(
+                        "ERR Client names cannot contain spaces, newlines or special characters."
+                            .to_string(),
+                    )
+                } else {
+                    db.clients().set_name(ctx.id(), &name);
+                    ok()
+                }
+            }
+            ClientOp::GetName => match db.clients().name(ctx.id()) {
+                name if name.is_empty() => Frame::Null,
+                name => Frame::Bulk(Bytes::from(name)),
+            },
+            ClientOp::List => Frame::Bulk(Bytes::from(db.clients().list())),
+            ClientOp::KillAddr(addr) => {
+                let filter = KillFilter {
+                    addr: Some(addr),
+                    ..KillFilter::default()
+                };
+                match db.clients().kill(&filter) {
+                    0 => Frame::Error("ERR No such client".to_string()),
+                    _ => ok(),
+                }
+            }
+            ClientOp::Kill {
+                mut filter,
+                skip_me,
+            } => {
+                if skip_me {
+                    filter.skip = Some(ctx.id());
+                }
+                Frame::Integer(db.clients().kill(&filter) as i64)
+            }
+            ClientOp::Pause {
+                timeout,
+                write_only,
+            } => {
+                db.clients().pause(timeout, write_only);
+                ok()
+            }
+            ClientOp::Unpause => {
+                db.clients().unpause();
+                ok()
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("client".as_bytes()));
+        match self.op {
+            ClientOp::Tracking(on) => {
+                frame.push_bulk(Bytes::from("tracking".as_bytes()));
+                let on: &[u8] = if on { b"on" } else { b"off" };
+                frame.push_bulk(Bytes::from(on));
+            }
+            ClientOp::Id => frame.push_bulk(Bytes::from("id".as_bytes())),
+            ClientOp::SetName(name) => {
+                frame.push_bulk(Bytes::from("setname".as_bytes()));
+                frame.push_bulk(Bytes::from(name.into_bytes()));
+            }
+            ClientOp::GetName => frame.push_bulk(Bytes::from("getname".as_bytes())),
+            ClientOp::List => frame.push_bulk(Bytes::from("list".as_bytes())),
+            ClientOp::KillAddr(addr) => {
+                frame.push_bulk(Bytes::from("kill".as_bytes()));
+                frame.push_bulk(Bytes::from(addr.into_bytes()));
+            }
+            ClientOp::Kill { filter, skip_me } => {
+                frame.push_bulk(Bytes::from("kill".as_bytes()));
+                let options = [
+                    ("id", filter.id.map(|id| id.to_string())),
+                    ("addr", filter.addr),
+                    ("user", filter.user),
+                    (
+                        "skipme",
+                        Some(if skip_me { "yes" } else { "no" }.to_string()),
+                    ),
+                ];
+                for (option, value) in options {
+                    if let Some(value) = value {
+                        frame.push_bulk(Bytes::from(option.as_bytes()));
+                        frame.push_bulk(Bytes::from(value.into_bytes()));
+                    }
+                }
+            }
+            ClientOp::Pause {
+                timeout,
+                write_only,
+            } => {
+                frame.push_bulk(Bytes::from("pause".as_bytes()));
+                frame.push_int(timeout.as_millis() as i64);
+                let mode: &[u8] = if write_only { b"write" } else { b"all" };
+                frame.push_bulk(Bytes::from(mode));
+            }
+            ClientOp::Unpause => frame.push_bulk(Bytes::from("unpause".as_bytes())),
+        }
+        frame
+    }
+}
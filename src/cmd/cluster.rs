@@ -0,0 +1,213 @@
+use bytes::Bytes;
+
+use crate::{
+    cluster::{self, SlotState},
+    Connection, Db, Frame, Parse,
+};
+
+/// 没有开启集群模式时的错误信息。
+pub(super) const CLUSTER_DISABLED: &str = "ERR This instance has cluster support disabled";
+
+/// 查询和修改集群的槽分配。
+///
+/// 格式：Cluster Slots
+///      Cluster Shards
+///      Cluster KeySlot <key>
+///      Cluster SetSlot <slot> <IMPORTING <host:port> | MIGRATING <host:port> | NODE <host:port> | STABLE>
+///
+/// 除了`KeySlot`，都需要开启集群模式。节点由地址标识，节点的 ID 由地址计算得到。
+#[derive(Debug)]
+pub struct ClusterCommand {
+    op: ClusterOp,
+}
+
+#[derive(Debug)]
+enum ClusterOp {
+    Slots,
+    Shards,
+    KeySlot(String),
+    SetSlot(u16, SlotState),
+}
+
+impl ClusterCommand {
+    /// 创建一个`Cluster Slots`命令。
+    pub fn slots() -> ClusterCommand {
+        ClusterCommand {
+            op: ClusterOp::Slots,
+        }
+    }
+
+    /// 创建一个`Cluster Shards`命令。
+    pub fn shards() -> ClusterCommand {
+        ClusterCommand {
+            op: ClusterOp::Shards,
+        }
+    }
+
+    /// 创建一个`Cluster KeySlot`命令。
+    pub fn key_slot(key: impl ToString) -> ClusterCommand {
+        ClusterCommand {
+            op: ClusterOp::KeySlot(key.to_string()),
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ClusterCommand`命令。
+    ///
+    /// 需要保证字符串`Cluster`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ClusterCommand> {
+        let op = match &parse.next_string()?.to_lowercase()[..] {
+            "slots" => ClusterOp::Slots,
+            "shards" => ClusterOp::Shards,
+            "keyslot" => ClusterOp::KeySlot(parse.next_string()?),
+            "setslot" => {
+                let slot = parse
+                    .next_int()
+                    .ok()
+                    .filter(|&slot| slot < cluster::SLOTS as u64)
+                    .ok_or_else(|| crate::Error::invalid("Invalid or out of range slot"))?
+                    as u16;
+                let state = match &parse.next_string()?.to_lowercase()[..] {
+                    "importing" => SlotState::Importing(parse.next_string()?),
+                    "migrating" => SlotState::Migrating(parse.next_string()?),
+                    "node" => SlotState::Node(parse.next_string()?),
+                    "stable" => SlotState::Stable,
+                    _ => {
+                        return Err(crate::Error::invalid(
+                            "Invalid CLUSTER SETSLOT action or number of arguments",
+                        ))
+                    }
+                };
+                ClusterOp::SetSlot(slot, state)
+            }
+            sub => {
+                return Err(crate::Error::Parse(format!(
+                    "未知的 Cluster 子命令：'{}'",
+                    sub
+                )))
+            }
+        };
+        Ok(ClusterCommand { op })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match (self.op, db.cluster()) {
+            (ClusterOp::KeySlot(key), _) => {
+                Frame::Integer(cluster::key_slot(key.as_bytes()) as i64)
+            }
+            (_, None) => Frame::Error(CLUSTER_DISABLED.to_string()),
+            (ClusterOp::Slots, Some(cluster)) => Frame::Array(
+                cluster
+                    .slot_ranges()
+                    .into_iter()
+                    .map(|(start, end, addr)| {
+                        Frame::Array(vec![
+                            Frame::Integer(start as i64),
+                            Frame::Integer(end as i64),
+                            node_frame(&addr),
+                        ])
+                    })
+                    .collect(),
+            ),
+            (ClusterOp::Shards, Some(cluster)) => {
+                // 同一个节点的槽区间放在同一个分片中，分片按照第一个槽的顺序排列。
+                let mut shards: Vec<(String, Vec<Frame>)> = vec![];
+                for (start, end, addr) in cluster.slot_ranges() {
+                    let start = Frame::Integer(start as i64);
+                    let end = Frame::Integer(end as i64);
+                    match shards.iter_mut().find(|(owner, _)| *owner == addr) {
+                        Some((_, ranges)) => ranges.extend([start, end]),
+                        None => shards.push((addr, vec![start, end])),
+                    }
+                }
+                Frame::Array(
+                    shards
+                        .into_iter()
+                        .map(|(addr, slots)| shard_frame(&addr, slots))
+                        .collect(),
+                )
+            }
+            (ClusterOp::SetSlot(slot, state), Some(cluster)) => {
+                match cluster.set_slot(slot, state) {
+                    Ok(()) => Frame::Simple("OK".to_string()),
+                    Err(msg) => Frame::Error(msg),
+                }
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("cluster".as_bytes()));
+        match self.op {
+            ClusterOp::Slots => frame.push_bulk(Bytes::from("slots".as_bytes())),
+            ClusterOp::Shards => frame.push_bulk(Bytes::from("shards".as_bytes())),
+            ClusterOp::KeySlot(key) => {
+                frame.push_bulk(Bytes::from("keyslot".as_bytes()));
+                frame.push_bulk(Bytes::from(key.into_bytes()));
+            }
+            ClusterOp::SetSlot(slot, state) => {
+                frame.push_bulk(Bytes::from("setslot".as_bytes()));
+                frame.push_int(slot as i64);
+                let (action, addr) = match state {
+                    SlotState::Importing(addr) => ("importing", Some(addr)),
+                    SlotState::Migrating(addr) => ("migrating", Some(addr)),
+                    SlotState::Node(addr) => ("node", Some(addr)),
+                    SlotState::Stable => ("stable", None),
+                };
+                frame.push_bulk(Bytes::from(action.as_bytes()));
+                if let Some(addr) = addr {
+                    frame.push_bulk(Bytes::from(addr.into_bytes()));
+                }
+            }
+        }
+        frame
+    }
+}
+
+/// 将地址拆分为主机和端口，端口不合法时为`0`。
+fn split_addr(addr: &str) -> (&str, i64) {
+    match addr.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().unwrap_or(0)),
+        None => (addr, 0),
+    }
+}
+
+/// `Cluster Slots`响应中的节点：`[host, port, id]`。
+fn node_frame(addr: &str) -> Frame {
+    let (host, port) = split_addr(addr);
+    Frame::Array(vec![
+        Frame::Bulk(Bytes::from(host.to_string())),
+        Frame::Integer(port),
+        Frame::Bulk(Bytes::from(cluster::node_id(addr))),
+    ])
+}
+
+/// `Cluster Shards`响应中的一个分片，每个分片只有一个主节点。
+fn shard_frame(addr: &str, slots: Vec<Frame>) -> Frame {
+    let (host, port) = split_addr(addr);
+    let bulk = |s: &str| Frame::Bulk(Bytes::from(s.to_string()));
+    let node = Frame::Array(vec![
+        bulk("id"),
+        Frame::Bulk(Bytes::from(cluster::node_id(addr))),
+        bulk("port"),
+        Frame::Integer(port),
+        bulk("ip"),
+        bulk(host),
+        bulk("endpoint"),
+        bulk(host),
+        bulk("role"),
+        bulk("master"),
+        bulk("health"),
+        bulk("online"),
+    ]);
+    Frame::Array(vec![
+        bulk("slots"),
+        Frame::Array(slots),
+        bulk("nodes"),
+        Frame::Array(vec![node]),
+    ])
+}
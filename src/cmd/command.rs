@@ -0,0 +1,154 @@
+use bytes::Bytes;
+
+use crate::{acl, cmd::CommandSpec, Connection, Frame, Parse};
+
+/// 查询服务器支持的命令。
+///
+/// 格式：Command
+///      Command Count
+///      Command Info [<command-name> ...]
+///      Command Docs [<command-name> ...]
+///      Command List
+///
+/// `Command`和`Command Info`返回命令的信息，每个命令的格式为：
+/// `[ name, arity, [ flag ... ], first-key, last-key, key-step, [ category ... ] ]`，
+/// 不存在的命令返回`nil`。没有指定命令名称时返回所有命令。
+#[derive(Debug)]
+pub struct CommandCommand {
+    op: CommandOp,
+}
+
+#[derive(Debug)]
+enum CommandOp {
+    Info(Vec<String>),
+    Count,
+    Docs(Vec<String>),
+    List,
+}
+
+impl CommandCommand {
+    /// 创建一个`Command Count`命令。
+    pub fn count() -> CommandCommand {
+        CommandCommand {
+            op: CommandOp::Count,
+        }
+    }
+
+    /// 创建一个`Command Info`命令，`names`为空时查询所有命令。
+    pub fn info(names: Vec<String>) -> CommandCommand {
+        CommandCommand {
+            op: CommandOp::Info(names),
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`CommandCommand`命令。
+    ///
+    /// 需要保证字符串`Command`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<CommandCommand> {
+        if parse.remaining() == 0 {
+            return Ok(CommandCommand::info(vec![]));
+        }
+        let op = match &parse.next_string()?.to_lowercase()[..] {
+            "count" => CommandOp::Count,
+            "info" => CommandOp::Info(names(parse)?),
+            "docs" => CommandOp::Docs(names(parse)?),
+            "list" => CommandOp::List,
+            sub => {
+                return Err(crate::Error::Parse(format!(
+                    "未知的 Command 子命令：'{}'",
+                    sub
+                )))
+            }
+        };
+        Ok(CommandCommand { op })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.op {
+            CommandOp::Info(names) if names.is_empty() => {
+                Frame::Array(CommandSpec::all().iter().map(info_frame).collect())
+            }
+            CommandOp::Info(names) => Frame::Array(
+                names
+                    .iter()
+                    .map(|name| CommandSpec::lookup(name).map_or(Frame::Null, info_frame))
+                    .collect(),
+            ),
+            CommandOp::Count => Frame::Integer(CommandSpec::all().len() as i64),
+            CommandOp::Docs(names) => {
+                // 响应格式为：[ name1, docs1, name2, docs2, ... ]，不存在的命令会被忽略。
+                let specs: Vec<_> = if names.is_empty() {
+                    CommandSpec::all().iter().collect()
+                } else {
+                    names
+                        .iter()
+                        .filter_map(|name| CommandSpec::lookup(name))
+                        .collect()
+                };
+                let mut response = vec![];
+                for spec in specs {
+                    response.push(Frame::Bulk(Bytes::from_static(spec.name.as_bytes())));
+                    response.push(Frame::Array(vec![
+                        Frame::Bulk(Bytes::from_static(b"group")),
+                        Frame::Bulk(Bytes::from_static(spec.group.as_bytes())),
+                    ]));
+                }
+                Frame::Array(response)
+            }
+            CommandOp::List => Frame::Array(
+                CommandSpec::all()
+                    .iter()
+                    .map(|spec| Frame::Bulk(Bytes::from_static(spec.name.as_bytes())))
+                    .collect(),
+            ),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("command".as_bytes()));
+        let (sub, names) = match self.op {
+            CommandOp::Info(names) => ("info", names),
+            CommandOp::Count => ("count", vec![]),
+            CommandOp::Docs(names) => ("docs", names),
+            CommandOp::List => ("list", vec![]),
+        };
+        frame.push_bulk(Bytes::from(sub.as_bytes()));
+        for name in names {
+            frame.push_bulk(Bytes::from(name.into_bytes()));
+        }
+        frame
+    }
+}
+
+/// 解析剩余的命令名称，统一转为小写。
+fn names(parse: &mut Parse) -> crate::Result<Vec<String>> {
+    let mut names = vec![];
+    while parse.remaining() > 0 {
+        names.push(parse.next_string()?.to_lowercase());
+    }
+    Ok(names)
+}
+
+/// 一个命令的信息。
+fn info_frame(spec: &CommandSpec) -> Frame {
+    let simple = |s: &str| Frame::Simple(s.to_string());
+    Frame::Array(vec![
+        Frame::Bulk(Bytes::from_static(spec.name.as_bytes())),
+        Frame::Integer(spec.arity as i64),
+        Frame::Array(spec.flags.iter().map(|flag| simple(flag)).collect()),
+        Frame::Integer(spec.keys.first as i64),
+        Frame::Integer(spec.keys.last as i64),
+        Frame::Integer(spec.keys.step as i64),
+        Frame::Array(
+            acl::categories(spec)
+                .iter()
+                .map(|category| simple(category))
+                .collect(),
+        ),
+    ])
+}
@@ -0,0 +1,97 @@
+use bytes::Bytes;
+
+use crate::{config, Connection, Db, Frame, Parse};
+
+/// 在运行时读取或修改服务器配置。
+///
+/// 格式：Config Get <pattern>
+///      Config Set <parameter> <value>
+///
+/// `pattern`是 glob 风格的模式，以`Map`返回所有匹配的配置项及其值，
+/// RESP2 的连接中为名称和值交替出现的数组。支持的配置项见`config`模块。
+#[derive(Debug)]
+pub struct Config {
+    op: ConfigOp,
+}
+
+#[derive(Debug)]
+enum ConfigOp {
+    Get(String),
+    Set(String, String),
+}
+
+impl Config {
+    /// 创建一个`Config Get`命令。
+    pub fn get(pattern: impl ToString) -> Config {
+        Config {
+            op: ConfigOp::Get(pattern.to_string()),
+        }
+    }
+
+    /// 创建一个`Config Set`命令。
+    pub fn set(parameter: impl ToString, value: impl ToString) -> Config {
+        Config {
+            op: ConfigOp::Set(parameter.to_string(), value.to_string()),
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Config`命令。
+    ///
+    /// `Parse`提供了类似迭代器的 API 来解析`Frame`。
+    /// 需要保证字符串`Config`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Config> {
+        let op = match &parse.next_string()?.to_lowercase()[..] {
+            "get" => ConfigOp::Get(parse.next_string()?.to_lowercase()),
+            "set" => ConfigOp::Set(parse.next_string()?.to_lowercase(), parse.next_string()?),
+            sub => {
+                return Err(crate::Error::Parse(format!(
+                    "未知的 Config 子命令：'{}'",
+                    sub
+                )))
+            }
+        };
+        Ok(Config { op })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.op {
+            ConfigOp::Get(pattern) => {
+                // 响应格式为：{ name1: value1, name2: value2, ... }，
+                // RESP2 的连接中为：[ name1, value1, name2, value2, ... ]
+                let pairs = config::get(db, &pattern)
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let name = Frame::Bulk(Bytes::from_static(name.as_bytes()));
+                        (name, Frame::Bulk(Bytes::from(value)))
+                    })
+                    .collect();
+                Frame::Map(pairs)
+            }
+            ConfigOp::Set(name, value) => match config::set(db, &name, &value) {
+                Ok(()) => Frame::Simple("OK".to_string()),
+                Err(msg) => Frame::Error(msg),
+            },
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("config".as_bytes()));
+        match self.op {
+            ConfigOp::Get(pattern) => {
+                frame.push_bulk(Bytes::from("get".as_bytes()));
+                frame.push_bulk(Bytes::from(pattern.into_bytes()));
+            }
+            ConfigOp::Set(name, value) => {
+                frame.push_bulk(Bytes::from("set".as_bytes()));
+                frame.push_bulk(Bytes::from(name.into_bytes()));
+                frame.push_bulk(Bytes::from(value.into_bytes()));
+            }
+        }
+        frame
+    }
+}
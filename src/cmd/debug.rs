@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use tokio::time;
+
+use crate::{snapshot, Connection, Db, Frame, Parse};
+
+/// 用于测试和排查问题的命令。
+///
+/// 格式：Debug Sleep <seconds>
+///      Debug Object <key>
+///      Debug Set-Active-Expire <0|1>
+///
+/// `Sleep`让连接等待指定的秒数后返回`OK`，可以是小数，用于测试超时；
+/// `Object`返回 key 的内部信息，格式为：
+/// `type:<type> encoding:<encoding> serializedlength:<bytes> size:<bytes> lru_seconds_idle:<secs> freq:<count> ttl:<ms>`，
+/// 没有过期时间时`ttl`为`-1`，key 不存在时返回错误；
+/// `Set-Active-Expire`开启或关闭后台任务主动清除过期的 key。
+#[derive(Debug)]
+pub struct DebugCommand {
+    op: DebugOp,
+}
+
+#[derive(Debug)]
+enum DebugOp {
+    Sleep(Duration),
+    Object(String),
+    SetActiveExpire(bool),
+}
+
+impl DebugCommand {
+    /// 创建一个`Debug Sleep`命令。
+    pub fn sleep(duration: Duration) -> DebugCommand {
+        DebugCommand {
+            op: DebugOp::Sleep(duration),
+        }
+    }
+
+    /// 创建一个`Debug Object`命令。
+    pub fn object(key: impl ToString) -> DebugCommand {
+        DebugCommand {
+            op: DebugOp::Object(key.to_string()),
+        }
+    }
+
+    /// 创建一个`Debug Set-Active-Expire`命令。
+    pub fn set_active_expire(enabled: bool) -> DebugCommand {
+        DebugCommand {
+            op: DebugOp::SetActiveExpire(enabled),
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`DebugCommand`命令。
+    ///
+    /// 需要保证字符串`Debug`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<DebugCommand> {
+        let op = match &parse.next_string()?.to_lowercase()[..] {
+            "sleep" => {
+                let secs = parse
+                    .next_string()?
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                    .ok_or_else(|| crate::Error::invalid("value is not a valid float"))?;
+                DebugOp::Sleep(secs)
+            }
+            "object" => DebugOp::Object(parse.next_string()?),
+            "set-active-expire" => DebugOp::SetActiveExpire(parse.next_int()? != 0),
+            sub => {
+                return Err(crate::Error::Parse(format!(
+                    "未知的 Debug 子命令：'{}'",
+                    sub
+                )))
+            }
+        };
+        Ok(DebugCommand { op })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.op {
+            DebugOp::Sleep(duration) => {
+                time::sleep(duration).await;
+                Frame::Simple("OK".to_string())
+            }
+            DebugOp::Object(key) => match db.entry_info(&key) {
+                Some(info) => Frame::Simple(format!(
+                    "type:{} encoding:{} serializedlength:{} size:{} lru_seconds_idle:{} freq:{} ttl:{}",
+                    info.value.type_name(),
+                    info.value.encoding(),
+                    snapshot::dump_value(&info.value).len(),
+                    info.size,
+                    info.idle.as_secs(),
+                    info.access_count,
+                    info.ttl.map_or(-1, |ttl| ttl.as_millis() as i64),
+                )),
+                None => Frame::Error("ERR no such key".to_string()),
+            },
+            DebugOp::SetActiveExpire(enabled) => {
+                db.set_active_expire(enabled);
+                Frame::Simple("OK".to_string())
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("debug".as_bytes()));
+        match self.op {
+            DebugOp::Sleep(duration) => {
+                frame.push_bulk(Bytes::from("sleep".as_bytes()));
+                frame.push_bulk(Bytes::from(duration.as_secs_f64().to_string()));
+            }
+            DebugOp::Object(key) => {
+                frame.push_bulk(Bytes::from("object".as_bytes()));
+                frame.push_bulk(Bytes::from(key.into_bytes()));
+            }
+            DebugOp::SetActiveExpire(enabled) => {
+                frame.push_bulk(Bytes::from("set-active-expire".as_bytes()));
+                frame.push_bulk(Bytes::from(if enabled { "1" } else { "0" }));
+            }
+        }
+        frame
+    }
+}
@@ -0,0 +1,159 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+
+use crate::{snapshot, Connection, Db, Frame, Parse};
+
+/// 序列化 key 的 value，序列化的结果可以通过`Restore`恢复。
+///
+/// 格式：Dump <key>
+///
+/// 序列化的结果包含版本号和校验和，格式见`snapshot`模块，不包含过期时间。
+/// key 不存在时返回`Null`。
+#[derive(Debug)]
+pub struct Dump {
+    key: String,
+}
+
+/// 使用`Dump`序列化的结果创建 key。
+///
+/// 格式：Restore <key> <ttl> <serialized-value> [REPLACE] [ABSTTL]
+///
+/// `ttl`为过期时长，单位是毫秒，为`0`表示没有过期时间；
+/// 设置了`ABSTTL`时，`ttl`为过期时刻的 UNIX 时间戳。
+/// key 已经存在时返回错误，除非设置了`REPLACE`。
+#[derive(Debug)]
+pub struct Restore {
+    key: String,
+    ttl: u64,
+    payload: Bytes,
+    replace: bool,
+    absttl: bool,
+}
+
+impl Dump {
+    /// 创建一个`Dump`命令。
+    pub fn new(key: impl ToString) -> Dump {
+        Dump {
+            key: key.to_string(),
+        }
+    }
+
+    /// 获取 key。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Dump`命令。
+    ///
+    /// 需要保证字符串`Dump`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Dump> {
+        let key = parse.next_string()?;
+        Ok(Dump { key })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.dump(&self.key) {
+            Some(value) => Frame::Bulk(Bytes::from(snapshot::dump_value(&value))),
+            None => Frame::Null,
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("dump".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl Restore {
+    /// 创建一个`Restore`命令，`ttl`为`None`表示没有过期时间。
+    pub fn new(
+        key: impl ToString,
+        ttl: Option<Duration>,
+        payload: Bytes,
+        replace: bool,
+    ) -> Restore {
+        Restore {
+            key: key.to_string(),
+            ttl: ttl.map(|ttl| ttl.as_millis() as u64).unwrap_or(0),
+            payload,
+            replace,
+            absttl: false,
+        }
+    }
+
+    /// 获取 key。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Restore`命令。
+    ///
+    /// 需要保证字符串`Restore`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Restore> {
+        let key = parse.next_string()?;
+        let ttl = parse.next_int()?;
+        let payload = parse.next_bytes()?;
+        let mut replace = false;
+        let mut absttl = false;
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "REPLACE" => replace = true,
+                "ABSTTL" => absttl = true,
+                _ => return Err(crate::Error::invalid("syntax error")),
+            }
+        }
+        Ok(Restore {
+            key,
+            ttl,
+            payload,
+            replace,
+            absttl,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 序列化的数据不合法时不会关闭连接，而是向客户端返回错误信息。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match snapshot::restore_value(&self.payload) {
+            Ok(value) => {
+                let expires_at = match (self.ttl, self.absttl) {
+                    (0, _) => None,
+                    (ms, true) => Some(UNIX_EPOCH + Duration::from_millis(ms)),
+                    (ms, false) => Some(SystemTime::now() + Duration::from_millis(ms)),
+                };
+                if db.restore_key(&self.key, value, expires_at, self.replace) {
+                    Frame::Simple("OK".to_string())
+                } else {
+                    Frame::Error("BUSYKEY Target key name already exists.".to_string())
+                }
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("restore".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_int(self.ttl as i64);
+        frame.push_bulk(self.payload);
+        if self.replace {
+            frame.push_bulk(Bytes::from("replace".as_bytes()));
+        }
+        if self.absttl {
+            frame.push_bulk(Bytes::from("absttl".as_bytes()));
+        }
+        frame
+    }
+}
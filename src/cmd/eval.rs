@@ -0,0 +1,297 @@
+use bytes::Bytes;
+
+use crate::{interceptor::ConnContext, Connection, Db, Frame, Parse};
+
+/// 在服务器上执行脚本。
+///
+/// 格式：Eval <script> <numkeys> [<key> ...] [<arg> ...]
+///      EvalSha <sha1> <numkeys> [<key> ...] [<arg> ...]
+///
+/// 脚本使用 Rhai 编写，需要开启`scripting`特性。脚本中可以通过`KEYS`和`ARGV`读取参数，
+/// 通过`redis.command()`执行命令，`redis.pcommand()`在命令出错时返回`#{ err: 错误信息 }`
+/// 而不是中止脚本。
+/// 脚本执行期间不会执行其他命令。
+///
+/// `Eval`执行的脚本会被缓存，之后可以通过`EvalSha`使用脚本的 SHA1 摘要执行。
+#[derive(Debug)]
+pub struct Eval {
+    // `EvalSha`时为脚本的 SHA1 摘要。
+    script: String,
+    numkeys: u64,
+    // 前`numkeys`个为 key，之后为其他参数。
+    args: Vec<Bytes>,
+    // 是否是`EvalSha`命令。
+    sha: bool,
+}
+
+/// 管理缓存的脚本。
+///
+/// 格式：Script Load <script>
+///      Script Exists <sha1> [<sha1> ...]
+///      Script Flush
+#[derive(Debug)]
+pub struct Script {
+    op: ScriptOp,
+}
+
+#[derive(Debug)]
+enum ScriptOp {
+    Load(String),
+    Exists(Vec<String>),
+    Flush,
+}
+
+impl Eval {
+    /// 创建一个`Eval`命令。
+    pub fn new(script: impl ToString, keys: &[String], args: Vec<Bytes>) -> Eval {
+        Eval::with(script.to_string(), keys, args, false)
+    }
+
+    /// 创建一个`EvalSha`命令。
+    pub fn sha(sha1: impl ToString, keys: &[String], args: Vec<Bytes>) -> Eval {
+        Eval::with(sha1.to_string(), keys, args, true)
+    }
+
+    fn with(script: String, keys: &[String], args: Vec<Bytes>, sha: bool) -> Eval {
+        let mut all = keys
+            .iter()
+            .map(|key| Bytes::from(key.clone().into_bytes()))
+            .collect::<Vec<_>>();
+        all.extend(args);
+        Eval {
+            script,
+            numkeys: keys.len() as u64,
+            args: all,
+            sha,
+        }
+    }
+
+    /// 获取第一个 key，没有 key 或者 key 不是 UTF-8 字符串时返回`None`。
+    pub fn key(&self) -> Option<&str> {
+        if self.numkeys == 0 {
+            return None;
+        }
+        self.args
+            .first()
+            .and_then(|key| std::str::from_utf8(key).ok())
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        if self.sha {
+            "evalsha"
+        } else {
+            "eval"
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Eval`命令。
+    ///
+    /// `sha`为`true`时解析为`EvalSha`命令。
+    /// 需要保证字符串`Eval`或`EvalSha`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse, sha: bool) -> crate::Result<Eval> {
+        let script = parse.next_string()?;
+        let numkeys = parse.next_int()?;
+        let mut args = vec![];
+        while parse.remaining() > 0 {
+            args.push(parse.next_bytes()?);
+        }
+        Ok(Eval {
+            script,
+            numkeys,
+            args,
+            sha,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 脚本在阻塞线程中执行，执行期间持有`Db::script_lock()`的写锁。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        ctx: &ConnContext,
+    ) -> crate::Result<()> {
+        let response = match self.run(db, ctx).await {
+            Ok(frame) => frame,
+            Err(msg) => Frame::Error(msg),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 找到要执行的脚本并执行。
+    ///
+    /// # Errors
+    /// 参数不合法或者找不到脚本时返回错误信息。
+    async fn run(self, db: &Db, ctx: &ConnContext) -> Result<Frame, String> {
+        let script = if self.sha {
+            db.get_script(&self.script)
+                .ok_or("NOSCRIPT No matching script. Please use EVAL.")?
+        } else {
+            db.load_script(&self.script);
+            self.script
+        };
+
+        let (keys, args) = split_keys(self.numkeys, self.args)?;
+        execute(db, ctx, script, None, keys, args).await
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().to_string()));
+        frame.push_bulk(Bytes::from(self.script.into_bytes()));
+        frame.push_int(self.numkeys as i64);
+        for arg in self.args {
+            frame.push_bulk(arg);
+        }
+        frame
+    }
+}
+
+/// 将参数中的前`numkeys`个作为 key，返回 key 和其他参数。
+///
+/// # Errors
+/// 如果`numkeys`大于参数的个数，返回错误信息。
+pub(super) fn split_keys(
+    numkeys: u64,
+    mut args: Vec<Bytes>,
+) -> Result<(Vec<String>, Vec<Bytes>), String> {
+    if numkeys > args.len() as u64 {
+        return Err("ERR Number of keys can't be greater than number of args".to_string());
+    }
+    let rest = args.split_off(numkeys as usize);
+    let keys = args
+        .iter()
+        .map(|key| String::from_utf8_lossy(key).into_owned())
+        .collect();
+    Ok((keys, rest))
+}
+
+/// 在阻塞线程中执行脚本，执行期间不会执行其他命令。
+///
+/// `function`不为`None`时，`script`为函数库的源代码，调用其中的函数，见`script::run()`。
+#[cfg(feature = "scripting")]
+pub(super) async fn execute(
+    db: &Db,
+    ctx: &ConnContext,
+    script: String,
+    function: Option<String>,
+    keys: Vec<String>,
+    args: Vec<Bytes>,
+) -> Result<Frame, String> {
+    let _guard = db.script_lock().write().await;
+    let (db, ctx) = (db.clone(), ctx.clone());
+    tokio::task::spawn_blocking(move || {
+        crate::script::run(&db, &ctx, &script, function.as_deref(), keys, args)
+    })
+    .await
+    .map_err(|_| "ERR Error running script".to_string())
+}
+
+/// 没有开启`scripting`特性时不能执行脚本。
+#[cfg(not(feature = "scripting"))]
+pub(super) async fn execute(
+    _db: &Db,
+    _ctx: &ConnContext,
+    _script: String,
+    _function: Option<String>,
+    _keys: Vec<String>,
+    _args: Vec<Bytes>,
+) -> Result<Frame, String> {
+    Err(SCRIPTING_DISABLED.to_string())
+}
+
+/// 没有开启`scripting`特性时的错误信息。
+#[cfg(not(feature = "scripting"))]
+pub(super) const SCRIPTING_DISABLED: &str =
+    "ERR scripting is not enabled, rebuild with the 'scripting' feature";
+
+impl Script {
+    /// 创建一个`Script Load`命令。
+    pub fn load(script: impl ToString) -> Script {
+        Script {
+            op: ScriptOp::Load(script.to_string()),
+        }
+    }
+
+    /// 创建一个`Script Exists`命令。
+    pub fn exists(sha1s: Vec<String>) -> Script {
+        Script {
+            op: ScriptOp::Exists(sha1s),
+        }
+    }
+
+    /// 创建一个`Script Flush`命令。
+    pub fn flush() -> Script {
+        Script {
+            op: ScriptOp::Flush,
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Script`命令。
+    ///
+    /// 需要保证字符串`Script`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Script> {
+        let op = match &parse.next_string()?.to_lowercase()[..] {
+            "load" => ScriptOp::Load(parse.next_string()?),
+            "exists" => {
+                let mut sha1s = vec![parse.next_string()?];
+                while parse.remaining() > 0 {
+                    sha1s.push(parse.next_string()?);
+                }
+                ScriptOp::Exists(sha1s)
+            }
+            "flush" => ScriptOp::Flush,
+            sub => {
+                return Err(crate::Error::Parse(format!(
+                    "未知的 Script 子命令：'{}'",
+                    sub
+                )))
+            }
+        };
+        Ok(Script { op })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.op {
+            ScriptOp::Load(script) => Frame::Bulk(Bytes::from(db.load_script(&script))),
+            ScriptOp::Exists(sha1s) => {
+                let mut response = Frame::array();
+                for sha in sha1s {
+                    response.push_int(db.get_script(&sha).is_some() as i64);
+                }
+                response
+            }
+            ScriptOp::Flush => {
+                db.flush_scripts();
+                Frame::Simple("OK".to_string())
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("script".as_bytes()));
+        match self.op {
+            ScriptOp::Load(script) => {
+                frame.push_bulk(Bytes::from("load".as_bytes()));
+                frame.push_bulk(Bytes::from(script.into_bytes()));
+            }
+            ScriptOp::Exists(sha1s) => {
+                frame.push_bulk(Bytes::from("exists".as_bytes()));
+                for sha in sha1s {
+                    frame.push_bulk(Bytes::from(sha.into_bytes()));
+                }
+            }
+            ScriptOp::Flush => frame.push_bulk(Bytes::from("flush".as_bytes())),
+        }
+        frame
+    }
+}
@@ -0,0 +1,175 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse};
+
+/// 为已经存在的 key 设置过期时间。
+///
+/// 格式：Expire <key> <seconds>
+///      PExpire <key> <milliseconds>
+///
+/// 如果设置成功返回`1`，如果 key 不存在返回`0`。
+/// 过期时间为`0`时，key 会被立即删除。
+#[derive(Debug)]
+pub struct Expire {
+    key: String,
+    expire: Duration,
+    // 是否是`PExpire`命令，只影响命令名称。
+    millis: bool,
+}
+
+/// 为已经存在的 key 设置过期的时刻。
+///
+/// 格式：ExpireAt <key> <unix-time-seconds>
+///      PExpireAt <key> <unix-time-milliseconds>
+///
+/// 如果设置成功返回`1`，如果 key 不存在返回`0`。
+/// 如果这个时刻已经过去了，key 会被立即删除。
+#[derive(Debug)]
+pub struct ExpireAt {
+    key: String,
+    // UNIX 时间戳，单位由`millis`决定。
+    timestamp: u64,
+    // 是否是`PExpireAt`命令。
+    millis: bool,
+}
+
+impl Expire {
+    /// 创建一个`Expire`命令。
+    ///
+    /// 发送给服务器时会使用毫秒精度的`PExpire`命令。
+    pub fn new(key: impl ToString, expire: Duration) -> Expire {
+        Expire {
+            key: key.to_string(),
+            expire,
+            millis: true,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        if self.millis {
+            "pexpire"
+        } else {
+            "expire"
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Expire`命令。
+    ///
+    /// `millis`为`true`时过期时间的单位是毫秒，否则是秒。
+    /// 需要保证字符串`Expire`或`PExpire`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse, millis: bool) -> crate::Result<Expire> {
+        let key = parse.next_string()?;
+        let n = parse.next_int()?;
+        let expire = if millis {
+            Duration::from_millis(n)
+        } else {
+            Duration::from_secs(n)
+        };
+        Ok(Expire {
+            key,
+            expire,
+            millis,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Integer(db.expire(&self.key, self.expire) as i64);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("pexpire".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.expire.as_millis().to_string()));
+        frame
+    }
+}
+
+impl ExpireAt {
+    /// 创建一个`ExpireAt`命令。
+    ///
+    /// 发送给服务器时会使用毫秒精度的`PExpireAt`命令，早于 UNIX 纪元的时刻视为 UNIX 纪元。
+    pub fn new(key: impl ToString, when: SystemTime) -> ExpireAt {
+        let timestamp = when
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_millis() as u64)
+            .unwrap_or(0);
+        ExpireAt {
+            key: key.to_string(),
+            timestamp,
+            millis: true,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        if self.millis {
+            "pexpireat"
+        } else {
+            "expireat"
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ExpireAt`命令。
+    ///
+    /// `millis`为`true`时时间戳的单位是毫秒，否则是秒。
+    /// 需要保证字符串`ExpireAt`或`PExpireAt`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse, millis: bool) -> crate::Result<ExpireAt> {
+        let key = parse.next_string()?;
+        let timestamp = parse.next_int()?;
+        Ok(ExpireAt {
+            key,
+            timestamp,
+            millis,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 时间戳换算成毫秒后超出`i64`的范围时，向客户端返回错误，连接不会关闭。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let millis = if self.millis {
+            Some(self.timestamp)
+        } else {
+            self.timestamp.checked_mul(1000)
+        };
+        let response = match millis.filter(|millis| *millis <= i64::MAX as u64) {
+            Some(millis) => {
+                let when = UNIX_EPOCH + Duration::from_millis(millis);
+                Frame::Integer(db.expire_at(&self.key, when) as i64)
+            }
+            None => Frame::Error(format!(
+                "ERR invalid expire time in '{}' command",
+                self.get_name()
+            )),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().to_string()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.timestamp.to_string()));
+        frame
+    }
+}
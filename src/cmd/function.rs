@@ -0,0 +1,340 @@
+use bytes::Bytes;
+
+use crate::{interceptor::ConnContext, Connection, Db, Frame, Library, Parse};
+
+/// 管理服务器上的函数库。
+///
+/// 格式：Function Load [REPLACE] <code>
+///      Function List [LIBRARYNAME <pattern>] [WITHCODE]
+///      Function Delete <library>
+///
+/// 函数库使用 Rhai 编写，需要开启`scripting`特性。源代码的第一行必须是`#!rhai name=<library>`，
+/// 库中所有接受两个参数的公开函数都可以通过`FCall`调用，参数为 key 的数组和其他参数的数组。
+/// 与`Eval`不同，库的顶层语句只在加载时检查，调用函数时不执行。
+#[derive(Debug)]
+pub struct Function {
+    op: FunctionOp,
+}
+
+#[derive(Debug)]
+enum FunctionOp {
+    Load {
+        code: String,
+        replace: bool,
+    },
+    List {
+        pattern: Option<String>,
+        with_code: bool,
+    },
+    Delete(String),
+}
+
+/// 调用通过`Function Load`注册的函数。
+///
+/// 格式：FCall <function> <numkeys> [<key> ...] [<arg> ...]
+///
+/// 函数执行期间不会执行其他命令。
+#[derive(Debug)]
+pub struct FCall {
+    function: String,
+    numkeys: u64,
+    // 前`numkeys`个为 key，之后为其他参数。
+    args: Vec<Bytes>,
+}
+
+impl Function {
+    /// 创建一个`Function Load`命令。
+    pub fn load(code: impl ToString, replace: bool) -> Function {
+        Function {
+            op: FunctionOp::Load {
+                code: code.to_string(),
+                replace,
+            },
+        }
+    }
+
+    /// 创建一个`Function List`命令，`pattern`为`None`时列出所有的库。
+    pub fn list(pattern: Option<String>, with_code: bool) -> Function {
+        Function {
+            op: FunctionOp::List { pattern, with_code },
+        }
+    }
+
+    /// 创建一个`Function Delete`命令。
+    pub fn delete(library: impl ToString) -> Function {
+        Function {
+            op: FunctionOp::Delete(library.to_string()),
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Function`命令。
+    ///
+    /// 需要保证字符串`Function`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Function> {
+        let op = match &parse.next_string()?.to_lowercase()[..] {
+            "load" => {
+                let mut code = parse.next_string()?;
+                let mut replace = false;
+                if code.eq_ignore_ascii_case("replace") && parse.remaining() > 0 {
+                    code = parse.next_string()?;
+                    replace = true;
+                }
+                FunctionOp::Load { code, replace }
+            }
+            "list" => {
+                let mut pattern = None;
+                let mut with_code = false;
+                while parse.remaining() > 0 {
+                    match &parse.next_string()?.to_lowercase()[..] {
+                        "libraryname" => pattern = Some(parse.next_string()?),
+                        "withcode" => with_code = true,
+                        _ => return Err(crate::Error::invalid("syntax error")),
+                    }
+                }
+                FunctionOp::List { pattern, with_code }
+            }
+            "delete" => FunctionOp::Delete(parse.next_string()?),
+            sub => {
+                return Err(crate::Error::Parse(format!(
+                    "未知的 Function 子命令：'{}'",
+                    sub
+                )))
+            }
+        };
+        Ok(Function { op })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.op {
+            FunctionOp::Load { code, replace } => match load_library(db, code, replace) {
+                Ok(name) => Frame::Bulk(Bytes::from(name.into_bytes())),
+                Err(msg) => Frame::Error(msg),
+            },
+            FunctionOp::List { pattern, with_code } => {
+                let pattern = pattern.as_deref().unwrap_or("*");
+                Frame::Array(
+                    db.function_list(pattern)
+                        .into_iter()
+                        .map(|library| library_frame(library, with_code))
+                        .collect(),
+                )
+            }
+            FunctionOp::Delete(name) => {
+                if db.function_delete(&name) {
+                    Frame::Simple("OK".to_string())
+                } else {
+                    Frame::Error("ERR Library not found".to_string())
+                }
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("function".as_bytes()));
+        match self.op {
+            FunctionOp::Load { code, replace } => {
+                frame.push_bulk(Bytes::from("load".as_bytes()));
+                if replace {
+                    frame.push_bulk(Bytes::from("replace".as_bytes()));
+                }
+                frame.push_bulk(Bytes::from(code.into_bytes()));
+            }
+            FunctionOp::List { pattern, with_code } => {
+                frame.push_bulk(Bytes::from("list".as_bytes()));
+                if let Some(pattern) = pattern {
+                    frame.push_bulk(Bytes::from("libraryname".as_bytes()));
+                    frame.push_bulk(Bytes::from(pattern.into_bytes()));
+                }
+                if with_code {
+                    frame.push_bulk(Bytes::from("withcode".as_bytes()));
+                }
+            }
+            FunctionOp::Delete(name) => {
+                frame.push_bulk(Bytes::from("delete".as_bytes()));
+                frame.push_bulk(Bytes::from(name.into_bytes()));
+            }
+        }
+        frame
+    }
+}
+
+impl FCall {
+    /// 创建一个`FCall`命令。
+    pub fn new(function: impl ToString, keys: &[String], args: Vec<Bytes>) -> FCall {
+        let mut all = keys
+            .iter()
+            .map(|key| Bytes::from(key.clone().into_bytes()))
+            .collect::<Vec<_>>();
+        all.extend(args);
+        FCall {
+            function: function.to_string(),
+            numkeys: keys.len() as u64,
+            args: all,
+        }
+    }
+
+    /// 获取第一个 key，没有 key 或者 key 不是 UTF-8 字符串时返回`None`。
+    pub fn key(&self) -> Option<&str> {
+        if self.numkeys == 0 {
+            return None;
+        }
+        self.args
+            .first()
+            .and_then(|key| std::str::from_utf8(key).ok())
+    }
+
+    /// 通过`Parse`将`Frame`解析为`FCall`命令。
+    ///
+    /// 需要保证字符串`FCall`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<FCall> {
+        let function = parse.next_string()?;
+        let numkeys = parse.next_int()?;
+        let mut args = vec![];
+        while parse.remaining() > 0 {
+            args.push(parse.next_bytes()?);
+        }
+        Ok(FCall {
+            function,
+            numkeys,
+            args,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 与`Eval`一样，函数在阻塞线程中执行，执行期间持有`Db::script_lock()`的写锁。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        ctx: &ConnContext,
+    ) -> crate::Result<()> {
+        let response = match self.run(db, ctx).await {
+            Ok(frame) => frame,
+            Err(msg) => Frame::Error(msg),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 找到函数所在的库并调用函数。
+    ///
+    /// # Errors
+    /// 参数不合法或者找不到函数时返回错误信息。
+    async fn run(self, db: &Db, ctx: &ConnContext) -> Result<Frame, String> {
+        let library = db
+            .find_function(&self.function)
+            .ok_or("ERR Function not found")?;
+        let (keys, args) = super::eval::split_keys(self.numkeys, self.args)?;
+        let source = library_source(&library.code);
+        super::eval::execute(db, ctx, source, Some(self.function), keys, args).await
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("fcall".as_bytes()));
+        frame.push_bulk(Bytes::from(self.function.into_bytes()));
+        frame.push_int(self.numkeys as i64);
+        for arg in self.args {
+            frame.push_bulk(arg);
+        }
+        frame
+    }
+}
+
+/// 检查并注册函数库，返回库的名称。
+///
+/// # Errors
+/// 元信息不合法、编译失败、没有可调用的函数或者与已有的库冲突时返回错误信息。
+fn load_library(db: &Db, code: String, replace: bool) -> Result<String, String> {
+    let name = parse_metadata(&code)?;
+    let functions = compile_library(&library_source(&code))?;
+    if functions.is_empty() {
+        return Err("ERR No functions registered".to_string());
+    }
+    db.function_load(
+        Library {
+            name: name.clone(),
+            code,
+            functions,
+        },
+        replace,
+    )?;
+    Ok(name)
+}
+
+/// 解析源代码第一行的元信息`#!<engine> name=<library>`，返回库的名称。
+fn parse_metadata(code: &str) -> Result<String, String> {
+    let header = code
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("#!"))
+        .ok_or("ERR Missing library metadata")?;
+    let mut parts = header.split_whitespace();
+    let engine = parts.next().unwrap_or_default();
+    if !engine.eq_ignore_ascii_case("rhai") {
+        return Err(format!("ERR Engine '{}' not found", engine));
+    }
+
+    let mut name = None;
+    for part in parts {
+        match part.split_once('=') {
+            Some(("name", value)) => name = Some(value),
+            _ => return Err(format!("ERR Invalid metadata value given: {}", part)),
+        }
+    }
+    let name = name.ok_or("ERR Library name was not given")?;
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err("ERR Library names can only contain letters, numbers, or underscores(_) and must be at least one character long".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// Rhai 不接受`#!`开头的行，将元信息替换为注释，保持行号不变。
+fn library_source(code: &str) -> String {
+    match code.strip_prefix("#!") {
+        Some(rest) => format!("//{}", rest),
+        None => code.to_string(),
+    }
+}
+
+/// 编译函数库，返回可以调用的函数。
+#[cfg(feature = "scripting")]
+fn compile_library(code: &str) -> Result<Vec<String>, String> {
+    crate::script::library_functions(code)
+}
+
+/// 没有开启`scripting`特性时不能加载函数库。
+#[cfg(not(feature = "scripting"))]
+fn compile_library(_code: &str) -> Result<Vec<String>, String> {
+    Err(super::eval::SCRIPTING_DISABLED.to_string())
+}
+
+/// 将函数库转换为`Function List`响应中的一项。
+fn library_frame(library: Library, with_code: bool) -> Frame {
+    let mut items = vec![
+        Frame::Bulk(Bytes::from("library_name".as_bytes())),
+        Frame::Bulk(Bytes::from(library.name.into_bytes())),
+        Frame::Bulk(Bytes::from("engine".as_bytes())),
+        Frame::Bulk(Bytes::from("rhai".as_bytes())),
+        Frame::Bulk(Bytes::from("functions".as_bytes())),
+        Frame::Array(
+            library
+                .functions
+                .into_iter()
+                .map(|function| Frame::Bulk(Bytes::from(function.into_bytes())))
+                .collect(),
+        ),
+    ];
+    if with_code {
+        items.push(Frame::Bulk(Bytes::from("library_code".as_bytes())));
+        items.push(Frame::Bulk(Bytes::from(library.code.into_bytes())));
+    }
+    Frame::Array(items)
+}
@@ -0,0 +1,586 @@
+use bytes::Bytes;
+
+use crate::{
+    geo::{self, GeoMatch, Origin, Shape},
+    Connection, Db, Frame, Parse,
+};
+
+/// 向地理位置索引中添加成员，返回新增的成员的数量。
+///
+/// 格式：GeoAdd <key> <longitude> <latitude> <member> [<longitude> <latitude> <member> ...]
+///
+/// 地理位置索引就是有序集合，坐标被编码为 geohash 作为成员的分数，可以使用`ZRange`等命令读取。
+/// 已经存在的成员会更新坐标。经度的范围为`[-180, 180]`，纬度的范围为`[-85.05112878, 85.05112878]`。
+#[derive(Debug)]
+pub struct GeoAdd {
+    key: String,
+    members: Vec<(f64, f64, Bytes)>,
+}
+
+/// 获取成员的坐标。
+///
+/// 格式：GeoPos <key> <member> [<member> ...]
+///
+/// 每个成员返回经度和纬度组成的数组，不存在的成员返回`(nil)`。
+/// 坐标是 geohash 所在格子的中心，与添加时的坐标有微小的误差。
+#[derive(Debug)]
+pub struct GeoPos {
+    key: String,
+    members: Vec<Bytes>,
+}
+
+/// 计算两个成员之间的距离。
+///
+/// 格式：GeoDist <key> <member1> <member2> [M|KM|FT|MI]
+///
+/// 距离的单位默认为米，保留 4 位小数。如果任意一个成员不存在，返回`(nil)`。
+#[derive(Debug)]
+pub struct GeoDist {
+    key: String,
+    from: Bytes,
+    to: Bytes,
+    unit: GeoUnit,
+}
+
+/// 搜索指定范围内的成员。
+///
+/// 格式：GeoSearch <key> <FROMMEMBER <member> | FROMLONLAT <longitude> <latitude>>
+///                 <BYRADIUS <radius> <M|KM|FT|MI> | BYBOX <width> <height> <M|KM|FT|MI>>
+///                 [ASC|DESC] [COUNT <count>] [WITHCOORD] [WITHDIST] [WITHHASH]
+///
+/// 中心可以是已有的成员，也可以是指定的坐标；范围可以是圆形，也可以是边与经线和纬线平行的矩形。
+/// 默认不排序，指定了`COUNT`但没有指定顺序时按照距离从近到远排序。
+/// 没有`WITH*`选项时只返回成员，否则每个成员返回一个数组，依次为成员、距离、geohash 和坐标。
+#[derive(Debug)]
+pub struct GeoSearch {
+    key: String,
+    origin: GeoOrigin,
+    shape: GeoShape,
+    // 为`Some(true)`时按照距离从近到远排序。
+    ascending: Option<bool>,
+    count: Option<u64>,
+    with_coord: bool,
+    with_dist: bool,
+    with_hash: bool,
+}
+
+/// 距离的单位。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeoUnit {
+    Meters,
+    Kilometers,
+    Feet,
+    Miles,
+}
+
+/// `GeoSearch`的中心。
+#[derive(Debug, Clone)]
+pub enum GeoOrigin {
+    /// 使用已有成员的坐标。
+    Member(Bytes),
+    /// 使用指定的经度和纬度。
+    LonLat(f64, f64),
+}
+
+/// `GeoSearch`的范围。
+#[derive(Debug, Clone, Copy)]
+pub enum GeoShape {
+    /// 以中心为圆心的圆，参数为半径。
+    Radius(f64, GeoUnit),
+    /// 以中心为中心的矩形，参数为宽和高。
+    Box(f64, f64, GeoUnit),
+}
+
+impl GeoAdd {
+    /// 创建一个`GeoAdd`命令，`members`中的每一项为经度、纬度和成员。
+    pub fn new(key: impl ToString, members: Vec<(f64, f64, Bytes)>) -> GeoAdd {
+        GeoAdd {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`GeoAdd`命令。
+    ///
+    /// 需要保证字符串`GeoAdd`已经被处理过了，并且经度、纬度和成员是成组的。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<GeoAdd> {
+        let key = parse.next_string()?;
+        let mut members = vec![];
+        while parse.remaining() > 0 {
+            let lon = next_float(parse)?;
+            let lat = next_float(parse)?;
+            let member = parse.next_bytes()?;
+            members.push((lon, lat, member));
+        }
+        Ok(GeoAdd { key, members })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let invalid = self
+            .members
+            .iter()
+            .find(|(lon, lat, _)| !geo::is_valid(*lon, *lat));
+        let response = match invalid {
+            Some((lon, lat, _)) => Frame::Error(format!(
+                "ERR invalid longitude,latitude pair {:.6},{:.6}",
+                lon, lat
+            )),
+            None => match db.geoadd(&self.key, self.members) {
+                Ok(added) => Frame::Integer(added as i64),
+                Err(err) => Frame::Error(err.to_string()),
+            },
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("geoadd".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for (lon, lat, member) in self.members {
+            frame.push_bulk(Bytes::from(lon.to_string()));
+            frame.push_bulk(Bytes::from(lat.to_string()));
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl GeoPos {
+    /// 创建一个`GeoPos`命令。
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> GeoPos {
+        GeoPos {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`GeoPos`命令。
+    ///
+    /// 需要保证字符串`GeoPos`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<GeoPos> {
+        let key = parse.next_string()?;
+        let mut members = vec![];
+        while parse.remaining() > 0 {
+            members.push(parse.next_bytes()?);
+        }
+        Ok(GeoPos { key, members })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.geopos(&self.key, &self.members) {
+            Ok(positions) => Frame::Array(
+                positions
+                    .into_iter()
+                    .map(|pos| match pos {
+                        Some(pos) => coord_frame(pos),
+                        None => Frame::Null,
+                    })
+                    .collect(),
+            ),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("geopos".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for member in self.members {
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl GeoDist {
+    /// 创建一个`GeoDist`命令。
+    pub fn new(key: impl ToString, from: Bytes, to: Bytes, unit: GeoUnit) -> GeoDist {
+        GeoDist {
+            key: key.to_string(),
+            from,
+            to,
+            unit,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`GeoDist`命令。
+    ///
+    /// 需要保证字符串`GeoDist`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<GeoDist> {
+        let key = parse.next_string()?;
+        let from = parse.next_bytes()?;
+        let to = parse.next_bytes()?;
+        let unit = if parse.remaining() > 0 {
+            next_unit(parse)?
+        } else {
+            GeoUnit::Meters
+        };
+        Ok(GeoDist {
+            key,
+            from,
+            to,
+            unit,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.geodist(&self.key, &self.from, &self.to) {
+            Ok(Some(dist)) => Frame::Bulk(Bytes::from(format_dist(dist, self.unit))),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("geodist".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.from);
+        frame.push_bulk(self.to);
+        frame.push_bulk(Bytes::from(self.unit.as_str().as_bytes()));
+        frame
+    }
+}
+
+impl GeoSearch {
+    /// 创建一个`GeoSearch`命令，默认不排序、不限制数量，只返回成员。
+    pub fn new(key: impl ToString, origin: GeoOrigin, shape: GeoShape) -> GeoSearch {
+        GeoSearch {
+            key: key.to_string(),
+            origin,
+            shape,
+            ascending: None,
+            count: None,
+            with_coord: false,
+            with_dist: false,
+            with_hash: false,
+        }
+    }
+
+    /// 按照距离排序，`ascending`为`true`时从近到远。
+    pub fn sort(mut self, ascending: bool) -> GeoSearch {
+        self.ascending = Some(ascending);
+        self
+    }
+
+    /// 最多返回`count`个成员。
+    pub fn count(mut self, count: u64) -> GeoSearch {
+        self.count = Some(count);
+        self
+    }
+
+    /// 同时返回成员的坐标。
+    pub fn with_coord(mut self) -> GeoSearch {
+        self.with_coord = true;
+        self
+    }
+
+    /// 同时返回成员到中心的距离，单位与范围的单位相同。
+    pub fn with_dist(mut self) -> GeoSearch {
+        self.with_dist = true;
+        self
+    }
+
+    /// 同时返回成员的 geohash。
+    pub fn with_hash(mut self) -> GeoSearch {
+        self.with_hash = true;
+        self
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`GeoSearch`命令。
+    ///
+    /// 需要保证字符串`GeoSearch`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<GeoSearch> {
+        let key = parse.next_string()?;
+        let mut origin = None;
+        let mut shape = None;
+        let mut ascending = None;
+        let mut count = None;
+        let mut with_coord = false;
+        let mut with_dist = false;
+        let mut with_hash = false;
+
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_lowercase()[..] {
+                "frommember" if origin.is_none() => {
+                    origin = Some(GeoOrigin::Member(parse.next_bytes()?));
+                }
+                "fromlonlat" if origin.is_none() => {
+                    let lon = next_float(parse)?;
+                    let lat = next_float(parse)?;
+                    origin = Some(GeoOrigin::LonLat(lon, lat));
+                }
+                "frommember" | "fromlonlat" => {
+                    return Err(crate::Error::invalid(
+                        "exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH",
+                    ))
+                }
+                "byradius" if shape.is_none() => {
+                    let radius = next_float(parse)?;
+                    shape = Some(GeoShape::Radius(radius, next_unit(parse)?));
+                }
+                "bybox" if shape.is_none() => {
+                    let width = next_float(parse)?;
+                    let height = next_float(parse)?;
+                    shape = Some(GeoShape::Box(width, height, next_unit(parse)?));
+                }
+                "byradius" | "bybox" => {
+                    return Err(crate::Error::invalid(
+                        "exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH",
+                    ))
+                }
+                "asc" => ascending = Some(true),
+                "desc" => ascending = Some(false),
+                "count" => count = Some(parse.next_int()?),
+                "withcoord" => with_coord = true,
+                "withdist" => with_dist = true,
+                "withhash" => with_hash = true,
+                _ => return Err(crate::Error::invalid("syntax error")),
+            }
+        }
+
+        let origin = origin.ok_or_else(|| {
+            crate::Error::invalid(
+                "exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH",
+            )
+        })?;
+        let shape = shape.ok_or_else(|| {
+            crate::Error::invalid(
+                "exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH",
+            )
+        })?;
+        Ok(GeoSearch {
+            key,
+            origin,
+            shape,
+            ascending,
+            count,
+            with_coord,
+            with_dist,
+            with_hash,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.check() {
+            Some(err) => Frame::Error(err.to_string()),
+            None => {
+                let (shape, unit) = match self.shape {
+                    GeoShape::Radius(radius, unit) => (Shape::Radius(radius * unit.meters()), unit),
+                    GeoShape::Box(width, height, unit) => (
+                        Shape::Box {
+                            width: width * unit.meters(),
+                            height: height * unit.meters(),
+                        },
+                        unit,
+                    ),
+                };
+                let origin = match &self.origin {
+                    GeoOrigin::Member(member) => Origin::Member(member.clone()),
+                    GeoOrigin::LonLat(lon, lat) => Origin::LonLat(*lon, *lat),
+                };
+                match db.geosearch(&self.key, &origin, &shape) {
+                    Ok(matches) => self.reply(matches, unit),
+                    Err(err) => Frame::Error(err.to_string()),
+                }
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 检查参数的值，返回错误信息。
+    fn check(&self) -> Option<&'static str> {
+        if let GeoOrigin::LonLat(lon, lat) = self.origin {
+            if !geo::is_valid(lon, lat) {
+                return Some("ERR invalid longitude,latitude pair");
+            }
+        }
+        match self.shape {
+            GeoShape::Radius(radius, _) if radius < 0.0 => {
+                return Some("ERR radius cannot be negative")
+            }
+            GeoShape::Box(width, height, _) if width < 0.0 || height < 0.0 => {
+                return Some("ERR height or width cannot be negative")
+            }
+            _ => {}
+        }
+        if self.count == Some(0) {
+            return Some("ERR COUNT must be > 0");
+        }
+        None
+    }
+
+    /// 对搜索结果排序、截断，并转换为响应数据。
+    fn reply(&self, mut matches: Vec<GeoMatch>, unit: GeoUnit) -> Frame {
+        // 只返回一部分成员时，应该返回最近的那些。
+        let ascending = self.ascending.or(self.count.map(|_| true));
+        match ascending {
+            Some(true) => matches.sort_by(|a, b| a.dist.total_cmp(&b.dist)),
+            Some(false) => matches.sort_by(|a, b| b.dist.total_cmp(&a.dist)),
+            None => {}
+        }
+        if let Some(count) = self.count {
+            matches.truncate(usize::try_from(count).unwrap_or(usize::MAX));
+        }
+
+        let items = matches.into_iter().map(|m| {
+            if !(self.with_coord || self.with_dist || self.with_hash) {
+                return Frame::Bulk(m.member);
+            }
+            let mut item = vec![Frame::Bulk(m.member)];
+            if self.with_dist {
+                item.push(Frame::Bulk(Bytes::from(format_dist(m.dist, unit))));
+            }
+            if self.with_hash {
+                item.push(Frame::Integer(m.hash as i64));
+            }
+            if self.with_coord {
+                item.push(coord_frame(geo::decode(m.hash)));
+            }
+            Frame::Array(item)
+        });
+        Frame::Array(items.collect())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("geosearch".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        match self.origin {
+            GeoOrigin::Member(member) => {
+                frame.push_bulk(Bytes::from("frommember".as_bytes()));
+                frame.push_bulk(member);
+            }
+            GeoOrigin::LonLat(lon, lat) => {
+                frame.push_bulk(Bytes::from("fromlonlat".as_bytes()));
+                frame.push_bulk(Bytes::from(lon.to_string()));
+                frame.push_bulk(Bytes::from(lat.to_string()));
+            }
+        }
+        let unit = match self.shape {
+            GeoShape::Radius(radius, unit) => {
+                frame.push_bulk(Bytes::from("byradius".as_bytes()));
+                frame.push_bulk(Bytes::from(radius.to_string()));
+                unit
+            }
+            GeoShape::Box(width, height, unit) => {
+                frame.push_bulk(Bytes::from("bybox".as_bytes()));
+                frame.push_bulk(Bytes::from(width.to_string()));
+                frame.push_bulk(Bytes::from(height.to_string()));
+                unit
+            }
+        };
+        frame.push_bulk(Bytes::from(unit.as_str().as_bytes()));
+        if let Some(ascending) = self.ascending {
+            let order = if ascending { "asc" } else { "desc" };
+            frame.push_bulk(Bytes::from(order.as_bytes()));
+        }
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_bulk(Bytes::from(count.to_string()));
+        }
+        if self.with_coord {
+            frame.push_bulk(Bytes::from("withcoord".as_bytes()));
+        }
+        if self.with_dist {
+            frame.push_bulk(Bytes::from("withdist".as_bytes()));
+        }
+        if self.with_hash {
+            frame.push_bulk(Bytes::from("withhash".as_bytes()));
+        }
+        frame
+    }
+}
+
+impl GeoUnit {
+    /// 一个单位对应的米数。
+    fn meters(self) -> f64 {
+        match self {
+            GeoUnit::Meters => 1.0,
+            GeoUnit::Kilometers => 1000.0,
+            GeoUnit::Feet => 0.3048,
+            GeoUnit::Miles => 1609.34,
+        }
+    }
+
+    /// 单位在命令中的名称。
+    fn as_str(self) -> &'static str {
+        match self {
+            GeoUnit::Meters => "m",
+            GeoUnit::Kilometers => "km",
+            GeoUnit::Feet => "ft",
+            GeoUnit::Miles => "mi",
+        }
+    }
+}
+
+/// 读取一个经度、纬度或者长度。
+fn next_float(parse: &mut Parse) -> crate::Result<f64> {
+    parse
+        .next_string()?
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| crate::Error::invalid("value is not a valid float"))
+}
+
+/// 读取距离的单位。
+fn next_unit(parse: &mut Parse) -> crate::Result<GeoUnit> {
+    match &parse.next_string()?.to_lowercase()[..] {
+        "m" => Ok(GeoUnit::Meters),
+        "km" => Ok(GeoUnit::Kilometers),
+        "ft" => Ok(GeoUnit::Feet),
+        "mi" => Ok(GeoUnit::Miles),
+        _ => Err(crate::Error::invalid(
+            "unsupported unit provided. please use M, KM, FT, MI",
+        )),
+    }
+}
+
+/// 将以米为单位的距离转换为`unit`，保留 4 位小数。
+fn format_dist(meters: f64, unit: GeoUnit) -> String {
+    format!("{:.4}", meters / unit.meters())
+}
+
+/// 将坐标转换为经度和纬度组成的数组。
+fn coord_frame((lon, lat): (f64, f64)) -> Frame {
+    Frame::Array(vec![
+        Frame::Bulk(Bytes::from(lon.to_string())),
+        Frame::Bulk(Bytes::from(lat.to_string())),
+    ])
+}
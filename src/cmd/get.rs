@@ -34,10 +34,12 @@ impl Get {
     /// 应用命令委派给了`Db`的方法。写回响应数据使用到了`Connection`，
     /// 如果写回响应错出错，返回`Err`。
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
-        let response = if let Some(value) = db.get(&self.key) {
-            Frame::Bulk(value)
-        } else {
-            Frame::Null
+        let response = match db.get(&self.key) {
+            Ok(Some(value)) => Frame::Bulk(value),
+            // key 不存在。
+            Ok(None) => Frame::Null,
+            // value 不是字符串。
+            Err(err) => Frame::Error(err.to_string()),
         };
         // 写入响应信息。
         dst.write_frame(&response).await?;
@@ -0,0 +1,326 @@
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse, ParseError};
+
+/// 设置哈希表中 field 对应的 value，返回新增的 field 的数量。
+///
+/// 格式：HSet <key> <field> <value> [<field> <value> ...]
+///
+/// 如果 key 不存在，会先创建一个空的哈希表；已经存在的 field 会被覆盖。
+#[derive(Debug)]
+pub struct HSet {
+    key: String,
+    fields: Vec<(Bytes, Bytes)>,
+}
+
+/// 获取哈希表中 field 对应的 value。
+///
+/// 格式：HGet <key> <field>
+///
+/// 如果 key 或者 field 不存在，返回`(nil)`。
+#[derive(Debug)]
+pub struct HGet {
+    key: String,
+    field: Bytes,
+}
+
+/// 删除哈希表中的 field，返回被删除的 field 的数量。
+///
+/// 格式：HDel <key> <field> [<field> ...]
+///
+/// 不存在的 field 会被忽略，哈希表被删空后 key 也会被删除。
+#[derive(Debug)]
+pub struct HDel {
+    key: String,
+    fields: Vec<Bytes>,
+}
+
+/// 获取哈希表中所有的 field 和 value。
+///
+/// 格式：HGetAll <key>
+///
+/// 返回 field 到 value 的`Map`，RESP2 的连接中为 field 和 value 交替出现的数组。
+/// 如果 key 不存在返回空的`Map`。
+#[derive(Debug)]
+pub struct HGetAll {
+    key: String,
+}
+
+impl HSet {
+    /// 创建一个`HSet`命令。
+    pub fn new(key: impl ToString, fields: Vec<(Bytes, Bytes)>) -> HSet {
+        HSet {
+            key: key.to_string(),
+            fields,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`HSet`命令。
+    ///
+    /// 需要保证字符串`HSet`已经被处理过了，并且 field 和 value 是成对的。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HSet> {
+        let key = parse.next_string()?;
+        let mut fields = vec![];
+        while parse.remaining() > 0 {
+            let field = parse.next_bytes()?;
+            let value = parse.next_bytes()?;
+            fields.push((field, value));
+        }
+        Ok(HSet { key, fields })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.hset(&self.key, self.fields) {
+            Ok(added) => Frame::Integer(added as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hset".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for (field, value) in self.fields {
+            frame.push_bulk(field);
+            frame.push_bulk(value);
+        }
+        frame
+    }
+}
+
+impl HGet {
+    /// 创建一个`HGet`命令。
+    pub fn new(key: impl ToString, field: Bytes) -> HGet {
+        HGet {
+            key: key.to_string(),
+            field,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`HGet`命令。
+    ///
+    /// 需要保证字符串`HGet`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HGet> {
+        let key = parse.next_string()?;
+        let field = parse.next_bytes()?;
+        Ok(HGet { key, field })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.hget(&self.key, &self.field) {
+            Ok(Some(value)) => Frame::Bulk(value),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hget".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.field);
+        frame
+    }
+}
+
+impl HDel {
+    /// 创建一个`HDel`命令。
+    pub fn new(key: impl ToString, fields: Vec<Bytes>) -> HDel {
+        HDel {
+            key: key.to_string(),
+            fields,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`HDel`命令。
+    ///
+    /// 需要保证字符串`HDel`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HDel> {
+        let key = parse.next_string()?;
+        let mut fields = vec![];
+        while parse.remaining() > 0 {
+            fields.push(parse.next_bytes()?);
+        }
+        Ok(HDel { key, fields })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.hdel(&self.key, &self.fields) {
+            Ok(removed) => Frame::Integer(removed as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hdel".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for field in self.fields {
+            frame.push_bulk(field);
+        }
+        frame
+    }
+}
+
+impl HGetAll {
+    /// 创建一个`HGetAll`命令。
+    pub fn new(key: impl ToString) -> HGetAll {
+        HGetAll {
+            key: key.to_string(),
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`HGetAll`命令。
+    ///
+    /// 需要保证字符串`HGetAll`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HGetAll> {
+        let key = parse.next_string()?;
+        Ok(HGetAll { key })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.hgetall(&self.key) {
+            Ok(fields) => Frame::Map(
+                fields
+                    .into_iter()
+                    .map(|(field, value)| (Frame::Bulk(field), Frame::Bulk(value)))
+                    .collect(),
+            ),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hgetall".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+/// 随机获取哈希表中的 field。
+///
+/// 格式：HRandField <key> [<count> [WITHVALUES]]
+///
+/// 没有`count`时返回一个 field，key 不存在时返回`(nil)`。`count`的含义与`SRandMember`相同。
+/// 指定`WITHVALUES`时，返回的数组中 field 和 value 交替出现。
+#[derive(Debug)]
+pub struct HRandField {
+    key: String,
+    count: Option<i64>,
+    with_values: bool,
+}
+
+impl HRandField {
+    /// 创建一个`HRandField`命令，`count`为`None`时只获取一个 field，此时`with_values`会被忽略。
+    pub fn new(key: impl ToString, count: Option<i64>, with_values: bool) -> HRandField {
+        HRandField {
+            key: key.to_string(),
+            count,
+            with_values: count.is_some() && with_values,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`HRandField`命令。
+    ///
+    /// 需要保证字符串`HRandField`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HRandField> {
+        let key = parse.next_string()?;
+        let count = match parse.next_signed_int() {
+            Ok(count) => Some(count),
+            Err(ParseError::EndOfStream) => None,
+            Err(err) => return Err(err.into()),
+        };
+        let with_values = if parse.remaining() > 0 {
+            match &parse.next_string()?.to_lowercase()[..] {
+                "withvalues" => true,
+                _ => return Err(crate::Error::invalid("syntax error")),
+            }
+        } else {
+            false
+        };
+        Ok(HRandField {
+            key,
+            count,
+            with_values,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.hrandfield(&self.key, self.count.unwrap_or(1)) {
+            Ok(fields) if self.count.is_some() => {
+                let mut frame = Frame::array();
+                for (field, value) in fields {
+                    frame.push_bulk(field);
+                    if self.with_values {
+                        frame.push_bulk(value);
+                    }
+                }
+                frame
+            }
+            Ok(fields) => match fields.into_iter().next() {
+                Some((field, _)) => Frame::Bulk(field),
+                None => Frame::Null,
+            },
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hrandfield".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from(count.to_string()));
+        }
+        if self.with_values {
+            frame.push_bulk(Bytes::from("withvalues".as_bytes()));
+        }
+        frame
+    }
+}
@@ -0,0 +1,161 @@
+use bytes::Bytes;
+
+use crate::{interceptor::ConnContext, Connection, Db, Frame, Parse, Protocol};
+
+/// 切换连接使用的协议版本，返回服务器的信息。
+///
+/// 格式：Hello [protover [Auth <username> <password>] [SetName <clientname>]]
+///
+/// `protover`为`2`或`3`，不指定时不切换协议版本。同时指定了`Auth`时先认证，
+/// 认证失败时不会切换协议版本，也不会设置名称。
+///
+/// 返回一个`Map`，包括服务器名称、版本、连接使用的协议版本、连接编号、运行模式和角色，
+/// RESP2 的连接中编码为键和值交替出现的数组。
+#[derive(Debug, Default)]
+pub struct Hello {
+    protover: Option<u64>,
+    auth: Option<(String, String)>,
+    setname: Option<String>,
+}
+
+impl Hello {
+    /// 创建一个`Hello`命令，`protover`为`None`时不切换协议版本。
+    pub fn new(protover: Option<u64>) -> Hello {
+        Hello {
+            protover,
+            ..Hello::default()
+        }
+    }
+
+    /// 同时使用用户名和密码认证。
+    pub fn auth(mut self, username: impl ToString, password: impl ToString) -> Hello {
+        self.auth = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// 同时设置连接的名称。
+    pub fn setname(mut self, name: impl ToString) -> Hello {
+        self.setname = Some(name.to_string());
+        self
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Hello`命令。
+    ///
+    /// 需要保证字符串`Hello`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Hello> {
+        let mut hello = Hello::default();
+        if parse.remaining() == 0 {
+            return Ok(hello);
+        }
+        hello.protover = Some(parse.next_int()?);
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_lowercase()[..] {
+                "auth" => hello.auth = Some((parse.next_string()?, parse.next_string()?)),
+                "setname" => hello.setname = Some(parse.next_string()?),
+                option => {
+                    return Err(crate::Error::Parse(format!(
+                        "未知的 Hello 选项：'{}'",
+                        option
+                    )))
+                }
+            }
+        }
+        Ok(hello)
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        ctx: &mut ConnContext,
+    ) -> crate::Result<()> {
+        match self.handshake(db, dst, ctx) {
+            Ok(()) => dst.write_frame(&server_info(db, dst, ctx)).await?,
+            Err(msg) => dst.write_frame(&Frame::Error(msg)).await?,
+        }
+        Ok(())
+    }
+
+    /// 检查参数、认证并设置名称，全部成功后才切换协议版本。
+    fn handshake(self, db: &Db, dst: &mut Connection, ctx: &mut ConnContext) -> Result<(), String> {
+        let protocol = match self.protover {
+            Some(version) => Some(
+                Protocol::from_version(version)
+                    .ok_or_else(|| "NOPROTO unsupported protocol version".to_string())?,
+            ),
+            None => None,
+        };
+        // 名称的限制与`Client SetName`相同。
+        if let Some(name) = &self.setname {
+            if name.chars().any(|c| !c.is_ascii_graphic()) {
+                return Err(
+                    "ERR Client names cannot contain spaces, newlines or special characters."
+                        .to_string(),
+                );
+            }
+        }
+        if let Some((username, password)) = &self.auth {
+            db.acl().authenticate(username, password)?;
+            ctx.set_user(username);
+        }
+        if let Some(name) = &self.setname {
+            db.clients().set_name(ctx.id(), name);
+        }
+        if let Some(protocol) = protocol {
+            dst.set_protocol(protocol);
+        }
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hello".as_bytes()));
+        if let Some(protover) = self.protover {
+            frame.push_bulk(Bytes::from(protover.to_string()));
+        }
+        if let Some((username, password)) = self.auth {
+            frame.push_bulk(Bytes::from("auth".as_bytes()));
+            frame.push_bulk(Bytes::from(username.into_bytes()));
+            frame.push_bulk(Bytes::from(password.into_bytes()));
+        }
+        if let Some(name) = self.setname {
+            frame.push_bulk(Bytes::from("setname".as_bytes()));
+            frame.push_bulk(Bytes::from(name.into_bytes()));
+        }
+        frame
+    }
+}
+
+/// 握手的响应，字段与 Redis 一致，没有模块所以`modules`为空数组。
+fn server_info(db: &Db, dst: &Connection, ctx: &ConnContext) -> Frame {
+    let mode = if db.cluster().is_some() {
+        "cluster"
+    } else {
+        "standalone"
+    };
+    let role = if db.replication().is_replica() {
+        "replica"
+    } else {
+        "master"
+    };
+    let fields = [
+        ("server", Frame::Bulk(Bytes::from_static(b"redis"))),
+        (
+            "version",
+            Frame::Bulk(Bytes::from_static(env!("CARGO_PKG_VERSION").as_bytes())),
+        ),
+        ("proto", Frame::Integer(dst.protocol().version() as i64)),
+        ("id", Frame::Integer(ctx.id() as i64)),
+        ("mode", Frame::Bulk(Bytes::from_static(mode.as_bytes()))),
+        ("role", Frame::Bulk(Bytes::from_static(role.as_bytes()))),
+        ("modules", Frame::Array(vec![])),
+    ];
+    Frame::Map(
+        fields
+            .into_iter()
+            .map(|(name, value)| (Frame::Bulk(Bytes::from_static(name.as_bytes())), value))
+            .collect(),
+    )
+}
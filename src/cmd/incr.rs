@@ -0,0 +1,181 @@
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse};
+
+/// 将 key 中存储的数字增加或减少，返回操作后的值。
+///
+/// 格式：Incr <key>
+///      Decr <key>
+///      IncrBy <key> <increment>
+///      DecrBy <key> <decrement>
+///
+/// 如果 key 不存在，会先将其设置为`0`再执行操作。
+/// 如果 value 不能表示为 64 位有符号整数，或者结果溢出，返回错误。
+#[derive(Debug)]
+pub struct Incr {
+    key: String,
+    op: IncrOp,
+}
+
+/// 将 key 中存储的浮点数加上`increment`，返回相加后的值。
+///
+/// 格式：IncrByFloat <key> <increment>
+///
+/// 如果 key 不存在，会先将其设置为`0`再执行操作。`increment`可以是负数。
+/// 返回值是字符串，不使用科学计数法。
+/// 如果 value 不能解析为浮点数，或者结果为 NaN 或无穷大，返回错误。
+#[derive(Debug)]
+pub struct IncrByFloat {
+    key: String,
+    increment: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum IncrOp {
+    Incr,
+    Decr,
+    IncrBy(i64),
+    DecrBy(i64),
+}
+
+impl Incr {
+    /// 创建一个`Incr`命令。
+    pub fn new(key: impl ToString) -> Incr {
+        Incr::with_op(key, IncrOp::Incr)
+    }
+
+    /// 创建一个`Decr`命令。
+    pub fn decr(key: impl ToString) -> Incr {
+        Incr::with_op(key, IncrOp::Decr)
+    }
+
+    /// 创建一个`IncrBy`命令。
+    pub fn incr_by(key: impl ToString, increment: i64) -> Incr {
+        Incr::with_op(key, IncrOp::IncrBy(increment))
+    }
+
+    /// 创建一个`DecrBy`命令。
+    pub fn decr_by(key: impl ToString, decrement: i64) -> Incr {
+        Incr::with_op(key, IncrOp::DecrBy(decrement))
+    }
+
+    fn with_op(key: impl ToString, op: IncrOp) -> Incr {
+        Incr {
+            key: key.to_string(),
+            op,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        match self.op {
+            IncrOp::Incr => "incr",
+            IncrOp::Decr => "decr",
+            IncrOp::IncrBy(_) => "incrby",
+            IncrOp::DecrBy(_) => "decrby",
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Incr`命令，`name`为全小写的命令名称。
+    ///
+    /// 需要保证命令名称已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse, name: &str) -> crate::Result<Incr> {
+        let key = parse.next_string()?;
+        let op = match name {
+            "incr" => IncrOp::Incr,
+            "decr" => IncrOp::Decr,
+            "incrby" => IncrOp::IncrBy(parse.next_signed_int()?),
+            "decrby" => IncrOp::DecrBy(parse.next_signed_int()?),
+            _ => unreachable!(),
+        };
+        Ok(Incr { key, op })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// value 不是整数或者结果溢出时，向客户端返回错误，连接不会关闭。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let delta = match self.op {
+            IncrOp::Incr => Some(1),
+            IncrOp::Decr => Some(-1),
+            IncrOp::IncrBy(n) => Some(n),
+            IncrOp::DecrBy(n) => n.checked_neg(),
+        };
+        let response = match delta {
+            Some(delta) => match db.incr_by(&self.key, delta) {
+                Ok(value) => Frame::Integer(value),
+                Err(msg) => Frame::Error(msg.to_string()),
+            },
+            None => Frame::Error("ERR decrement would overflow".to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().to_string()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        match self.op {
+            IncrOp::IncrBy(n) | IncrOp::DecrBy(n) => frame.push_bulk(Bytes::from(n.to_string())),
+            IncrOp::Incr | IncrOp::Decr => {}
+        }
+        frame
+    }
+}
+
+impl IncrByFloat {
+    /// 创建一个`IncrByFloat`命令。
+    pub fn new(key: impl ToString, increment: f64) -> IncrByFloat {
+        IncrByFloat {
+            key: key.to_string(),
+            increment,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`IncrByFloat`命令。
+    ///
+    /// 需要保证字符串`IncrByFloat`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<IncrByFloat> {
+        let key = parse.next_string()?;
+        let increment = parse
+            .next_string()?
+            .parse::<f64>()
+            .ok()
+            .filter(|increment| !increment.is_nan())
+            .ok_or_else(|| crate::Error::invalid("value is not a valid float"))?;
+        Ok(IncrByFloat { key, increment })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// value 不是浮点数或者结果不是有限值时，向客户端返回错误，连接不会关闭。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.incr_by_float(&self.key, self.increment) {
+            Ok(value) => Frame::Bulk(value),
+            Err(msg) => Frame::Error(msg.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("incrbyfloat".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.increment.to_string()));
+        frame
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt::Write;
+
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse};
+
+/// 查看服务器的信息。
+///
+/// 格式：Info [<section> ...]
+///
+/// 返回`# Section`开头、每行一个`name:value`的文本。支持`clients`、`memory`、`persistence`、
+/// `replication`和`keyspace`部分，没有指定部分或者指定`all`、`default`、`everything`时
+/// 返回所有部分，不存在的部分会被忽略。
+#[derive(Debug)]
+pub struct Info {
+    sections: Vec<String>,
+}
+
+/// 将一个部分的内容写入字符串。
+type WriteSection = fn(&Db, &mut String);
+
+/// 支持的部分，按照这个顺序返回。
+const SECTIONS: &[(&str, WriteSection)] = &[
+    ("clients", clients),
+    ("memory", memory),
+    ("persistence", persistence),
+    ("replication", replication),
+    ("keyspace", keyspace),
+];
+
+impl Info {
+    /// 创建一个`Info`命令，`sections`为空时返回所有部分。
+    pub fn new(sections: Vec<String>) -> Info {
+        Info { sections }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Info`命令。
+    ///
+    /// 需要保证字符串`Info`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Info> {
+        let mut sections = vec![];
+        while parse.remaining() > 0 {
+            sections.push(parse.next_string()?.to_lowercase());
+        }
+        Ok(Info { sections })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let all = self.sections.is_empty()
+            || self
+                .sections
+                .iter()
+                .any(|section| matches!(&section[..], "all" | "default" | "everything"));
+        let mut info = String::new();
+        for (name, write) in SECTIONS {
+            if all || self.sections.iter().any(|section| section == name) {
+                if !info.is_empty() {
+                    info.push_str("\r\n");
+                }
+                write(db, &mut info);
+            }
+        }
+        // RESP3 的连接中以文本格式的`Verbatim`返回。
+        let response = Frame::Verbatim("txt".to_string(), Bytes::from(info));
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("info".as_bytes()));
+        for section in self.sections {
+            frame.push_bulk(Bytes::from(section.into_bytes()));
+        }
+        frame
+    }
+}
+
+/// `clients`部分。
+fn clients(db: &Db, info: &mut String) {
+    info.push_str("# Clients\r\n");
+    let _ = write!(info, "connected_clients:{}\r\n", db.clients().connected());
+    let _ = write!(info, "maxclients:{}\r\n", db.clients().maxclients());
+}
+
+/// `memory`部分。`used_memory`与`maxmemory`比较的值相同，只包括 key 和 value。
+fn memory(db: &Db, info: &mut String) {
+    let stats = db.memory_stats();
+    let maxmemory = db.config().maxmemory() as usize;
+    info.push_str("# Memory\r\n");
+    let fields = [
+        ("used_memory", stats.dataset.to_string()),
+        ("used_memory_human", human_bytes(stats.dataset)),
+        ("used_memory_overhead", stats.overhead().to_string()),
+        ("used_memory_pubsub", stats.pubsub.to_string()),
+        ("used_memory_clients", stats.clients.to_string()),
+        ("used_memory_scripts", stats.scripts.to_string()),
+        ("maxmemory", maxmemory.to_string()),
+        ("maxmemory_human", human_bytes(maxmemory)),
+        (
+            "lazyfree_pending_objects",
+            stats.lazyfree_pending.to_string(),
+        ),
+        ("lazyfreed_objects", stats.lazyfreed.to_string()),
+    ];
+    for (name, value) in fields {
+        let _ = write!(info, "{}:{}\r\n", name, value);
+    }
+}
+
+/// `persistence`部分。
+fn persistence(db: &Db, info: &mut String) {
+    let persistence = db.persistence();
+    info.push_str("# Persistence\r\n");
+    let _ = write!(
+        info,
+        "rdb_changes_since_last_save:{}\r\n",
+        persistence.dirty()
+    );
+    let _ = write!(info, "rdb_last_save_time:{}\r\n", persistence.last_save());
+}
+
+/// `replication`部分，副本同时返回主节点的地址。
+fn replication(db: &Db, info: &mut String) {
+    let replication = db.replication();
+    info.push_str("# Replication\r\n");
+    match replication.primary_addr() {
+        Some(addr) => {
+            let (host, port) = addr.rsplit_once(':').unwrap_or((&addr, ""));
+            let _ = write!(
+                info,
+                "role:slave\r\nmaster_host:{}\r\nmaster_port:{}\r\n",
+                host, port
+            );
+        }
+        None => info.push_str("role:master\r\n"),
+    }
+    let _ = write!(info, "connected_slaves:{}\r\n", replication.replica_count());
+    let _ = write!(info, "master_replid:{}\r\n", replication.replid());
+    let _ = write!(info, "master_repl_offset:{}\r\n", replication.offset());
+}
+
+/// `keyspace`部分。只有一个数据库，与 Redis 一样没有 key 时不返回`db0`。
+fn keyspace(db: &Db, info: &mut String) {
+    let stats = db.memory_stats();
+    info.push_str("# Keyspace\r\n");
+    if stats.keys > 0 {
+        let _ = write!(
+            info,
+            "db0:keys={},expires={}\r\n",
+            stats.keys, stats.expires
+        );
+    }
+}
+
+/// 将字节数转换为易读的格式，与 Redis 一样使用`B`、`K`、`M`、`G`后缀，保留两位小数。
+fn human_bytes(bytes: usize) -> String {
+    const UNITS: &[(f64, &str)] = &[
+        (1024.0 * 1024.0 * 1024.0, "G"),
+        (1024.0 * 1024.0, "M"),
+        (1024.0, "K"),
+    ];
+    let value = bytes as f64;
+    UNITS
+        .iter()
+        .find(|(size, _)| value >= *size)
+        .map(|(size, unit)| format!("{:.2}{}", value / size, unit))
+        .unwrap_or_else(|| format!("{}B", bytes))
+}
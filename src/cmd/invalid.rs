@@ -0,0 +1,54 @@
+use crate::{Connection, Frame};
+
+/// 表示一个参数不合法的命令。
+///
+/// 与`Unknown`类似，它不会执行任何操作，只负责向客户端返回错误信息，
+/// 连接不会因此关闭。
+#[derive(Debug)]
+pub struct Invalid {
+    command_name: String,
+    message: String,
+}
+
+impl Invalid {
+    /// 创建一个参数个数不正确的`Invalid`命令。
+    pub(crate) fn wrong_arity(command_name: impl ToString) -> Invalid {
+        let command_name = command_name.to_string();
+        let message = format!(
+            "ERR wrong number of arguments for '{}' command",
+            command_name
+        );
+        Invalid {
+            command_name,
+            message,
+        }
+    }
+
+    /// 根据解析命令参数时产生的错误创建`Invalid`命令。
+    ///
+    /// 带有错误类型的错误（`Error::Invalid`、`Error::WrongType`）原样返回，
+    /// 其他错误都作为`ERR`类型返回。
+    pub(crate) fn from_error(command_name: impl ToString, err: crate::Error) -> Invalid {
+        let message = match err {
+            err @ (crate::Error::Invalid { .. } | crate::Error::WrongType) => err.to_string(),
+            err => format!("ERR {}", err),
+        };
+        Invalid {
+            command_name: command_name.to_string(),
+            message,
+        }
+    }
+
+    /// 响应客户端，返回错误信息。
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Error(self.message);
+
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 获取 command_name 字段。
+    pub(crate) fn get_name(&self) -> &str {
+        &self.command_name
+    }
+}
@@ -0,0 +1,51 @@
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse};
+
+/// 获取 key 对应的 value 的类型。
+///
+/// 格式：Type <key>
+///
+/// 返回`string`、`list`、`hash`等类型名称，如果 key 不存在返回`none`。
+#[derive(Debug)]
+pub struct Type {
+    key: String,
+}
+
+impl Type {
+    /// 创建一个`Type`命令。
+    pub fn new(key: impl ToString) -> Type {
+        Type {
+            key: key.to_string(),
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Type`命令。
+    ///
+    /// 需要保证字符串`Type`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Type> {
+        let key = parse.next_string()?;
+        Ok(Type { key })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let type_name = db.value_type(&self.key).unwrap_or("none");
+        let response = Frame::Simple(type_name.to_string());
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("type".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
@@ -0,0 +1,86 @@
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse};
+
+/// 查看或清除延迟监控记录的事件。
+///
+/// 格式：Latency Latest
+///      Latency History <event>
+///      Latency Reset [<event> ...]
+///
+/// 配置项`latency-monitor-threshold`大于`0`时，耗时达到阈值的事件才会被记录。
+/// `Latest`返回每类事件最近一次的记录，格式为：`[ event, timestamp, latency(ms), max-latency(ms) ]`；
+/// `History`返回一类事件的所有记录，格式为：`[ timestamp, latency(ms) ]`；
+/// `Reset`没有指定事件时清除所有事件，返回清除的事件类别数。
+#[derive(Debug)]
+pub struct Latency {
+    op: LatencyOp,
+}
+
+#[derive(Debug)]
+enum LatencyOp {
+    Latest,
+    History(String),
+    Reset(Vec<String>),
+}
+
+impl Latency {
+    /// 通过`Parse`将`Frame`解析为`Latency`命令。
+    ///
+    /// 需要保证字符串`Latency`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Latency> {
+        let op = match &parse.next_string()?.to_lowercase()[..] {
+            "latest" => LatencyOp::Latest,
+            "history" => LatencyOp::History(parse.next_string()?.to_lowercase()),
+            "reset" => {
+                let mut events = vec![];
+                while parse.remaining() > 0 {
+                    events.push(parse.next_string()?.to_lowercase());
+                }
+                LatencyOp::Reset(events)
+            }
+            sub => {
+                return Err(crate::Error::Parse(format!(
+                    "未知的 Latency 子命令：'{}'",
+                    sub
+                )))
+            }
+        };
+        Ok(Latency { op })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.op {
+            LatencyOp::Latest => Frame::Array(
+                db.latency()
+                    .latest()
+                    .into_iter()
+                    .map(|latest| {
+                        Frame::Array(vec![
+                            Frame::Bulk(Bytes::from_static(latest.event.as_bytes())),
+                            Frame::Integer(latest.sample.timestamp as i64),
+                            Frame::Integer(latest.sample.latency_ms as i64),
+                            Frame::Integer(latest.max_ms as i64),
+                        ])
+                    })
+                    .collect(),
+            ),
+            LatencyOp::History(event) => Frame::Array(
+                db.latency()
+                    .history(&event)
+                    .into_iter()
+                    .map(|sample| {
+                        Frame::Array(vec![
+                            Frame::Integer(sample.timestamp as i64),
+                            Frame::Integer(sample.latency_ms as i64),
+                        ])
+                    })
+                    .collect(),
+            ),
+            LatencyOp::Reset(events) => Frame::Integer(db.latency().reset(&events) as i64),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,906 @@
+use std::{future, time::Duration};
+
+use bytes::Bytes;
+use tokio::time;
+
+use crate::{shutdown::Shutdown, Connection, Db, Frame, Parse};
+
+/// 将一个或多个 value 插入到列表的头部或者尾部，返回插入后列表的长度。
+///
+/// 格式：LPush <key> <value> [<value> ...]
+///      RPush <key> <value> [<value> ...]
+///
+/// value 按照顺序依次插入，所以`LPush`后列表头部的元素顺序与参数的顺序相反。
+/// 如果 key 不存在，会先创建一个空的列表。
+#[derive(Debug)]
+pub struct Push {
+    key: String,
+    values: Vec<Bytes>,
+    // 为`true`时插入到头部，对应`LPush`。
+    front: bool,
+}
+
+/// 弹出并返回列表头部或者尾部的元素。
+///
+/// 格式：LPop <key>
+///      RPop <key>
+///
+/// 如果 key 不存在，返回`(nil)`。列表被弹空后，key 也会被删除。
+#[derive(Debug)]
+pub struct Pop {
+    key: String,
+    // 为`true`时弹出头部的元素，对应`LPop`。
+    front: bool,
+}
+
+/// 阻塞式地弹出列表头部或者尾部的元素。
+///
+/// 格式：BLPop <key> [<key> ...] <timeout>
+///      BRPop <key> [<key> ...] <timeout>
+///
+/// 依次检查每个 key，从第一个非空的列表中弹出元素，返回 key 和弹出的元素。
+/// 如果所有列表都为空，连接会阻塞直到有元素被插入，或者超过`timeout`秒后返回`(nil)`。
+/// `timeout`可以是小数，为`0`时一直阻塞。
+#[derive(Debug)]
+pub struct BlockingPop {
+    keys: Vec<String>,
+    // 超时时间，为`None`时一直阻塞。
+    timeout: Option<Duration>,
+    // 为`true`时弹出头部的元素，对应`BLPop`。
+    front: bool,
+}
+
+/// 依次检查每个 key，从第一个非空的列表中弹出最多`count`个元素，返回 key 和弹出的元素。
+///
+/// 格式：LMPop <numkeys> <key> [<key> ...] <Left|Right> [Count <count>]
+///      BLMPop <timeout> <numkeys> <key> [<key> ...] <Left|Right> [Count <count>]
+///
+/// `count`默认为`1`。如果所有列表都为空，`LMPop`返回`(nil)`；`BLMPop`会阻塞直到有元素被插入，
+/// 或者超过`timeout`秒后返回`(nil)`，`timeout`的规则与`BLPop`相同。
+#[derive(Debug)]
+pub struct LMPop {
+    keys: Vec<String>,
+    // 为`true`时弹出头部的元素，对应`Left`。
+    front: bool,
+    count: u64,
+    // 为`true`时对应`BLMPop`。
+    blocking: bool,
+    // `BLMPop`的超时时间，为`None`时一直阻塞。
+    timeout: Option<Duration>,
+}
+
+/// 获取列表中下标在`[start, stop]`之间的元素。
+///
+/// 格式：LRange <key> <start> <stop>
+///
+/// 下标可以是负数，`-1`表示最后一个元素，`-2`表示倒数第二个，以此类推。
+#[derive(Debug)]
+pub struct LRange {
+    key: String,
+    start: i64,
+    stop: i64,
+}
+
+/// 获取列表的长度。
+///
+/// 格式：LLen <key>
+///
+/// 如果 key 不存在，返回`0`。
+#[derive(Debug)]
+pub struct LLen {
+    key: String,
+}
+
+/// 查找列表中等于`element`的元素的下标。
+///
+/// 格式：LPos <key> <element> [RANK <rank>] [COUNT <count>] [MAXLEN <len>]
+///
+/// `rank`为正数时从头部开始查找第`rank`个匹配的元素，为负数时从尾部开始查找，默认为`1`。
+/// 没有指定`COUNT`时返回一个下标，找不到时返回`(nil)`；指定了`COUNT`时返回最多`count`个下标
+/// 组成的数组，`count`为`0`时返回所有的。`MAXLEN`限制最多比较的元素个数，为`0`时不限制。
+#[derive(Debug)]
+pub struct LPos {
+    key: String,
+    element: Bytes,
+    rank: i64,
+    count: Option<u64>,
+    maxlen: u64,
+}
+
+/// 在列表中第一个等于`pivot`的元素的前面或者后面插入`element`。
+///
+/// 格式：LInsert <key> BEFORE|AFTER <pivot> <element>
+///
+/// 返回插入后列表的长度，如果 key 不存在返回`0`，如果`pivot`不存在返回`-1`。
+#[derive(Debug)]
+pub struct LInsert {
+    key: String,
+    // 为`true`时插入到`pivot`的前面。
+    before: bool,
+    pivot: Bytes,
+    element: Bytes,
+}
+
+/// 将列表中下标为`index`的元素设置为`element`。
+///
+/// 格式：LSet <key> <index> <element>
+///
+/// 下标的规则与`LRange`相同，如果 key 不存在或者下标越界，返回错误。
+#[derive(Debug)]
+pub struct LSet {
+    key: String,
+    index: i64,
+    element: Bytes,
+}
+
+/// 删除列表中等于`element`的元素，返回被删除的元素的数量。
+///
+/// 格式：LRem <key> <count> <element>
+///
+/// `count`为正数时从头部开始删除最多`count`个，为负数时从尾部开始删除，为`0`时删除所有的。
+#[derive(Debug)]
+pub struct LRem {
+    key: String,
+    count: i64,
+    element: Bytes,
+}
+
+impl Push {
+    /// 创建一个`LPush`命令。
+    pub fn lpush(key: impl ToString, values: Vec<Bytes>) -> Push {
+        Push {
+            key: key.to_string(),
+            values,
+            front: true,
+        }
+    }
+
+    /// 创建一个`RPush`命令。
+    pub fn rpush(key: impl ToString, values: Vec<Bytes>) -> Push {
+        Push {
+            key: key.to_string(),
+            values,
+            front: false,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        if self.front {
+            "lpush"
+        } else {
+            "rpush"
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Push`命令，`front`为`true`时解析为`LPush`。
+    ///
+    /// 需要保证命令名称已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse, front: bool) -> crate::Result<Push> {
+        let key = parse.next_string()?;
+        let mut values = vec![];
+        while parse.remaining() > 0 {
+            values.push(parse.next_bytes()?);
+        }
+        Ok(Push { key, values, front })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.push(&self.key, self.values, self.front) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().to_string()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for value in self.values {
+            frame.push_bulk(value);
+        }
+        frame
+    }
+}
+
+impl Pop {
+    /// 创建一个`LPop`命令。
+    pub fn lpop(key: impl ToString) -> Pop {
+        Pop {
+            key: key.to_string(),
+            front: true,
+        }
+    }
+
+    /// 创建一个`RPop`命令。
+    pub fn rpop(key: impl ToString) -> Pop {
+        Pop {
+            key: key.to_string(),
+            front: false,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        if self.front {
+            "lpop"
+        } else {
+            "rpop"
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Pop`命令，`front`为`true`时解析为`LPop`。
+    ///
+    /// 需要保证命令名称已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse, front: bool) -> crate::Result<Pop> {
+        let key = parse.next_string()?;
+        Ok(Pop { key, front })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.pop(&self.key, self.front) {
+            Ok(Some(value)) => Frame::Bulk(value),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().to_string()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl BlockingPop {
+    /// 创建一个`BLPop`命令，`timeout`为`None`时一直阻塞。
+    pub fn blpop(keys: Vec<String>, timeout: Option<Duration>) -> BlockingPop {
+        BlockingPop {
+            keys,
+            timeout,
+            front: true,
+        }
+    }
+
+    /// 创建一个`BRPop`命令，`timeout`为`None`时一直阻塞。
+    pub fn brpop(keys: Vec<String>, timeout: Option<Duration>) -> BlockingPop {
+        BlockingPop {
+            keys,
+            timeout,
+            front: false,
+        }
+    }
+
+    /// 获取所有 key。
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        if self.front {
+            "blpop"
+        } else {
+            "brpop"
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`BlockingPop`命令，`front`为`true`时解析为`BLPop`。
+    ///
+    /// 需要保证命令名称已经被处理过了，并且至少还有两个参数。
+    pub(crate) fn parse_frames(parse: &mut Parse, front: bool) -> crate::Result<BlockingPop> {
+        let mut keys = vec![];
+        // 最后一个参数是超时时间。
+        while parse.remaining() > 1 {
+            keys.push(parse.next_string()?);
+        }
+        let timeout = next_timeout(parse)?;
+
+        Ok(BlockingPop {
+            keys,
+            timeout,
+            front,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 阻塞期间如果服务器关闭，直接返回，不会写回响应。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let timeout = async {
+            match self.timeout {
+                Some(timeout) => time::sleep(timeout).await,
+                None => future::pending().await,
+            }
+        };
+
+        let response = tokio::select! {
+            res = db.blocking_pop(&self.keys, self.front) => match res {
+                Ok((key, value)) => {
+                    let mut frame = Frame::array();
+                    frame.push_bulk(Bytes::from(key.into_bytes()));
+                    frame.push_bulk(value);
+                    frame
+                }
+                Err(err) => Frame::Error(err.to_string()),
+            },
+            // 超时。
+            _ = timeout => Frame::Null,
+            _ = shutdown.recv() => return Ok(()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().to_string()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        let timeout = self.timeout.map(|t| t.as_secs_f64()).unwrap_or(0.0);
+        frame.push_bulk(Bytes::from(timeout.to_string()));
+        frame
+    }
+}
+
+impl LMPop {
+    /// 创建一个`LMPop`命令，`front`为`true`时从头部弹出。
+    pub fn new(keys: Vec<String>, front: bool, count: u64) -> LMPop {
+        LMPop {
+            keys,
+            front,
+            count,
+            blocking: false,
+            timeout: None,
+        }
+    }
+
+    /// 创建一个`BLMPop`命令，`timeout`为`None`时一直阻塞。
+    pub fn blocking(
+        keys: Vec<String>,
+        front: bool,
+        count: u64,
+        timeout: Option<Duration>,
+    ) -> LMPop {
+        LMPop {
+            keys,
+            front,
+            count,
+            blocking: true,
+            timeout,
+        }
+    }
+
+    /// 获取所有 key。
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        if self.blocking {
+            "blmpop"
+        } else {
+            "lmpop"
+        }
+    }
+
+    /// 是否为阻塞的`BLMPop`。
+    pub(crate) fn is_blocking(&self) -> bool {
+        self.blocking
+    }
+
+    /// 通过`Parse`将`Frame`解析为`LMPop`命令，`blocking`为`true`时解析为`BLMPop`。
+    ///
+    /// 需要保证命令名称已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse, blocking: bool) -> crate::Result<LMPop> {
+        let timeout = if blocking { next_timeout(parse)? } else { None };
+        let keys = next_keys(parse)?;
+        let front = match &parse.next_string()?.to_lowercase()[..] {
+            "left" => true,
+            "right" => false,
+            _ => return Err(crate::Error::invalid("syntax error")),
+        };
+        let count = next_mpop_count(parse)?;
+        Ok(LMPop {
+            keys,
+            front,
+            count,
+            blocking,
+            timeout,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 阻塞期间如果服务器关闭，直接返回，不会写回响应。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let response = if let Some(err) = check_mpop_args(&self.keys, self.count) {
+            Frame::Error(err.to_string())
+        } else {
+            let count = usize::try_from(self.count).unwrap_or(usize::MAX);
+            let result = if self.blocking {
+                let timeout = async {
+                    match self.timeout {
+                        Some(timeout) => time::sleep(timeout).await,
+                        None => future::pending().await,
+                    }
+                };
+                tokio::select! {
+                    res = db.blocking_mpop(&self.keys, self.front, count) => res.map(Some),
+                    // 超时。
+                    _ = timeout => Ok(None),
+                    _ = shutdown.recv() => return Ok(()),
+                }
+            } else {
+                db.mpop(&self.keys, self.front, count)
+            };
+
+            match result {
+                Ok(Some((key, values))) => Frame::Array(vec![
+                    Frame::Bulk(Bytes::from(key.into_bytes())),
+                    Frame::Array(values.into_iter().map(Frame::Bulk).collect()),
+                ]),
+                Ok(None) => Frame::Null,
+                Err(err) => Frame::Error(err.to_string()),
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().to_string()));
+        if self.blocking {
+            let timeout = self.timeout.map(|t| t.as_secs_f64()).unwrap_or(0.0);
+            frame.push_bulk(Bytes::from(timeout.to_string()));
+        }
+        frame.push_bulk(Bytes::from(self.keys.len().to_string()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        let direction = if self.front { "left" } else { "right" };
+        frame.push_bulk(Bytes::from(direction.as_bytes()));
+        frame.push_bulk(Bytes::from("count".as_bytes()));
+        frame.push_bulk(Bytes::from(self.count.to_string()));
+        frame
+    }
+}
+
+impl LRange {
+    /// 创建一个`LRange`命令。
+    pub fn new(key: impl ToString, start: i64, stop: i64) -> LRange {
+        LRange {
+            key: key.to_string(),
+            start,
+            stop,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`LRange`命令。
+    ///
+    /// 需要保证字符串`LRange`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LRange> {
+        let key = parse.next_string()?;
+        let start = parse.next_signed_int()?;
+        let stop = parse.next_signed_int()?;
+        Ok(LRange { key, start, stop })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.lrange(&self.key, self.start, self.stop) {
+            Ok(values) => {
+                let mut frame = Frame::array();
+                for value in values {
+                    frame.push_bulk(value);
+                }
+                frame
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lrange".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.start.to_string()));
+        frame.push_bulk(Bytes::from(self.stop.to_string()));
+        frame
+    }
+}
+
+impl LLen {
+    /// 创建一个`LLen`命令。
+    pub fn new(key: impl ToString) -> LLen {
+        LLen {
+            key: key.to_string(),
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`LLen`命令。
+    ///
+    /// 需要保证字符串`LLen`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LLen> {
+        let key = parse.next_string()?;
+        Ok(LLen { key })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.llen(&self.key) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("llen".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl LPos {
+    /// 创建一个`LPos`命令，`rank`为`1`时从头部开始查找第一个匹配的元素。
+    pub fn new(
+        key: impl ToString,
+        element: Bytes,
+        rank: i64,
+        count: Option<u64>,
+        maxlen: u64,
+    ) -> LPos {
+        LPos {
+            key: key.to_string(),
+            element,
+            rank,
+            count,
+            maxlen,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`LPos`命令。
+    ///
+    /// 需要保证字符串`LPos`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LPos> {
+        let key = parse.next_string()?;
+        let element = parse.next_bytes()?;
+        let mut rank = 1;
+        let mut count = None;
+        let mut maxlen = 0;
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_lowercase()[..] {
+                "rank" => rank = parse.next_signed_int()?,
+                "count" => count = Some(parse.next_int()?),
+                "maxlen" => maxlen = parse.next_int()?,
+                _ => return Err(crate::Error::invalid("syntax error")),
+            }
+        }
+        Ok(LPos {
+            key,
+            element,
+            rank,
+            count,
+            maxlen,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = if self.rank == 0 {
+            Frame::Error("ERR RANK can't be zero: use 1 to start from the first match, 2 from the second ... or use negative to start from the end of the list".to_string())
+        } else {
+            // `COUNT 0`表示返回所有匹配的元素。
+            let count = match self.count {
+                Some(0) => None,
+                Some(count) => Some(count as usize),
+                None => Some(1),
+            };
+            match db.lpos(
+                &self.key,
+                &self.element,
+                self.rank,
+                count,
+                self.maxlen as usize,
+            ) {
+                Ok(indexes) if self.count.is_some() => Frame::Array(
+                    indexes
+                        .into_iter()
+                        .map(|index| Frame::Integer(index as i64))
+                        .collect(),
+                ),
+                Ok(indexes) => match indexes.first() {
+                    Some(index) => Frame::Integer(*index as i64),
+                    None => Frame::Null,
+                },
+                Err(err) => Frame::Error(err.to_string()),
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lpos".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.element);
+        frame.push_bulk(Bytes::from("rank".as_bytes()));
+        frame.push_bulk(Bytes::from(self.rank.to_string()));
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_bulk(Bytes::from(count.to_string()));
+        }
+        frame.push_bulk(Bytes::from("maxlen".as_bytes()));
+        frame.push_bulk(Bytes::from(self.maxlen.to_string()));
+        frame
+    }
+}
+
+impl LInsert {
+    /// 创建一个`LInsert`命令，`before`为`true`时插入到`pivot`的前面。
+    pub fn new(key: impl ToString, before: bool, pivot: Bytes, element: Bytes) -> LInsert {
+        LInsert {
+            key: key.to_string(),
+            before,
+            pivot,
+            element,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`LInsert`命令。
+    ///
+    /// 需要保证字符串`LInsert`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LInsert> {
+        let key = parse.next_string()?;
+        let before = match &parse.next_string()?.to_lowercase()[..] {
+            "before" => true,
+            "after" => false,
+            _ => return Err(crate::Error::invalid("syntax error")),
+        };
+        let pivot = parse.next_bytes()?;
+        let element = parse.next_bytes()?;
+        Ok(LInsert {
+            key,
+            before,
+            pivot,
+            element,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.linsert(&self.key, self.before, &self.pivot, self.element) {
+            Ok(len) => Frame::Integer(len),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("linsert".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        let position = if self.before { "before" } else { "after" };
+        frame.push_bulk(Bytes::from(position.as_bytes()));
+        frame.push_bulk(self.pivot);
+        frame.push_bulk(self.element);
+        frame
+    }
+}
+
+impl LSet {
+    /// 创建一个`LSet`命令。
+    pub fn new(key: impl ToString, index: i64, element: Bytes) -> LSet {
+        LSet {
+            key: key.to_string(),
+            index,
+            element,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`LSet`命令。
+    ///
+    /// 需要保证字符串`LSet`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LSet> {
+        let key = parse.next_string()?;
+        let index = parse.next_signed_int()?;
+        let element = parse.next_bytes()?;
+        Ok(LSet {
+            key,
+            index,
+            element,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.lset(&self.key, self.index, self.element) {
+            Ok(()) => Frame::Simple("OK".to_string()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lset".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.index.to_string()));
+        frame.push_bulk(self.element);
+        frame
+    }
+}
+
+impl LRem {
+    /// 创建一个`LRem`命令。
+    pub fn new(key: impl ToString, count: i64, element: Bytes) -> LRem {
+        LRem {
+            key: key.to_string(),
+            count,
+            element,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`LRem`命令。
+    ///
+    /// 需要保证字符串`LRem`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LRem> {
+        let key = parse.next_string()?;
+        let count = parse.next_signed_int()?;
+        let element = parse.next_bytes()?;
+        Ok(LRem {
+            key,
+            count,
+            element,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.lrem(&self.key, self.count, &self.element) {
+            Ok(removed) => Frame::Integer(removed as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lrem".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.count.to_string()));
+        frame.push_bulk(self.element);
+        frame
+    }
+}
+
+/// 读取阻塞命令的超时时间，单位为秒，可以是小数，为`0`时返回`None`，表示一直阻塞。
+pub(super) fn next_timeout(parse: &mut Parse) -> crate::Result<Option<Duration>> {
+    let timeout = parse
+        .next_string()?
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite())
+        .ok_or_else(|| crate::Error::invalid("timeout is not a float or out of range"))?;
+    if timeout < 0.0 {
+        return Err(crate::Error::invalid("timeout is negative"));
+    }
+    if timeout == 0.0 {
+        Ok(None)
+    } else {
+        Ok(Some(Duration::try_from_secs_f64(timeout).map_err(
+            |_| crate::Error::invalid("timeout is out of range"),
+        )?))
+    }
+}
+
+/// 读取`<numkeys> <key> [<key> ...]`格式的 key 列表。
+pub(super) fn next_keys(parse: &mut Parse) -> crate::Result<Vec<String>> {
+    let numkeys = parse.next_int()?;
+    let mut keys = vec![];
+    for _ in 0..numkeys {
+        keys.push(parse.next_string()?);
+    }
+    Ok(keys)
+}
+
+/// 读取`LMPop`和`ZMPop`中可选的`Count <count>`，默认为`1`。
+pub(super) fn next_mpop_count(parse: &mut Parse) -> crate::Result<u64> {
+    if parse.remaining() == 0 {
+        return Ok(1);
+    }
+    match &parse.next_string()?.to_lowercase()[..] {
+        "count" => Ok(parse.next_int()?),
+        _ => Err(crate::Error::invalid("syntax error")),
+    }
+}
+
+/// 检查`LMPop`和`ZMPop`的参数，`numkeys`和`count`都必须大于`0`。
+pub(super) fn check_mpop_args(keys: &[String], count: u64) -> Option<&'static str> {
+    if keys.is_empty() {
+        Some("ERR numkeys should be greater than 0")
+    } else if count == 0 {
+        Some("ERR count should be greater than 0")
+    } else {
+        None
+    }
+}
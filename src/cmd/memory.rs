@@ -0,0 +1,132 @@
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse};
+
+/// 查看内存的使用情况。
+///
+/// 格式：Memory Usage <key>
+///      Memory Stats
+///
+/// `Usage`返回 key 和 value 占用的近似字节数，计算方式与`Stats::used_memory`相同，
+/// 不包括数据结构的额外开销。如果 key 不存在返回`(nil)`。
+///
+/// `Stats`返回内存使用情况的统计，格式为：`[ name1, value1, name2, value2, ... ]`，
+/// 包括数据、信道、连接的读缓存和脚本分别占用的字节数。
+#[derive(Debug)]
+pub struct Memory {
+    op: MemoryOp,
+}
+
+#[derive(Debug)]
+enum MemoryOp {
+    Usage(String),
+    Stats,
+}
+
+impl Memory {
+    /// 创建一个`Memory Usage`命令。
+    pub fn usage(key: impl ToString) -> Memory {
+        Memory {
+            op: MemoryOp::Usage(key.to_string()),
+        }
+    }
+
+    /// 创建一个`Memory Stats`命令。
+    pub fn stats() -> Memory {
+        Memory {
+            op: MemoryOp::Stats,
+        }
+    }
+
+    /// 获取 key 值，`Memory Stats`没有 key。
+    pub fn key(&self) -> Option<&str> {
+        match &self.op {
+            MemoryOp::Usage(key) => Some(key),
+            MemoryOp::Stats => None,
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Memory`命令。
+    ///
+    /// 需要保证字符串`Memory`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Memory> {
+        let op = match &parse.next_string()?.to_lowercase()[..] {
+            "usage" => MemoryOp::Usage(parse.next_string()?),
+            "stats" => MemoryOp::Stats,
+            sub => {
+                return Err(crate::Error::Parse(format!(
+                    "未知的 Memory 子命令：'{}'",
+                    sub
+                )))
+            }
+        };
+        Ok(Memory { op })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.op {
+            MemoryOp::Usage(key) => match db.memory_usage(&key) {
+                Some(size) => Frame::Integer(size as i64),
+                None => Frame::Null,
+            },
+            MemoryOp::Stats => {
+                let stats = db.memory_stats();
+                let per_key = stats.dataset.checked_div(stats.keys).unwrap_or(0);
+                let percentage = match stats.total() {
+                    0 => 0.0,
+                    total => stats.dataset as f64 * 100.0 / total as f64,
+                };
+                let fields = [
+                    ("total.allocated", Frame::Integer(stats.total() as i64)),
+                    ("overhead.total", Frame::Integer(stats.overhead() as i64)),
+                    ("keys.count", Frame::Integer(stats.keys as i64)),
+                    ("keys.bytes-per-key", Frame::Integer(per_key as i64)),
+                    ("expires.count", Frame::Integer(stats.expires as i64)),
+                    ("dataset.bytes", Frame::Integer(stats.dataset as i64)),
+                    (
+                        "dataset.percentage",
+                        Frame::Bulk(Bytes::from(format!("{:.2}", percentage))),
+                    ),
+                    (
+                        "pubsub.channels",
+                        Frame::Integer(stats.pubsub_channels as i64),
+                    ),
+                    (
+                        "pubsub.patterns",
+                        Frame::Integer(stats.pubsub_patterns as i64),
+                    ),
+                    (
+                        "pubsub.shardchannels",
+                        Frame::Integer(stats.pubsub_shard_channels as i64),
+                    ),
+                    ("pubsub.bytes", Frame::Integer(stats.pubsub as i64)),
+                    ("clients.normal", Frame::Integer(stats.clients as i64)),
+                    ("scripts.bytes", Frame::Integer(stats.scripts as i64)),
+                ];
+                let mut response = vec![];
+                for (name, value) in fields {
+                    response.push(Frame::Bulk(Bytes::from_static(name.as_bytes())));
+                    response.push(value);
+                }
+                Frame::Array(response)
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("memory".as_bytes()));
+        match self.op {
+            MemoryOp::Usage(key) => {
+                frame.push_bulk(Bytes::from("usage".as_bytes()));
+                frame.push_bulk(Bytes::from(key.into_bytes()));
+            }
+            MemoryOp::Stats => frame.push_bulk(Bytes::from("stats".as_bytes())),
+        }
+        frame
+    }
+}
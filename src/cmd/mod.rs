@@ -7,16 +7,129 @@ pub use set::Set;
 mod unknown;
 pub use unknown::Unknown;
 
+mod invalid;
+pub use invalid::Invalid;
+
 mod publish;
 pub use publish::Publish;
 
 mod subscribe;
-pub use subscribe::Subscribe;
+pub use subscribe::{PSubscribe, PUnsubscribe, SSubscribe, SUnsubscribe, Subscribe, Unsubscribe};
 
 mod ping;
 pub use ping::Ping;
 
-use crate::{Connection, Db, Frame, Parse, Shutdown};
+mod auth;
+pub use auth::Auth;
+
+mod hello;
+pub use hello::Hello;
+
+mod asking;
+pub use asking::Asking;
+
+mod reset;
+pub use reset::Reset;
+
+mod config;
+pub use config::Config;
+
+mod acl;
+pub use acl::AclCommand;
+
+mod client;
+pub use client::ClientCommand;
+
+mod cluster;
+pub use cluster::ClusterCommand;
+
+mod command;
+pub use command::CommandCommand;
+
+mod eval;
+pub use eval::{Eval, Script};
+
+mod function;
+pub use function::{FCall, Function};
+
+mod save;
+pub use save::{LastSave, Save};
+
+mod slowlog;
+pub use slowlog::Slowlog;
+
+mod latency;
+pub use latency::Latency;
+
+mod debug;
+pub use debug::DebugCommand;
+
+mod info;
+pub use info::Info;
+
+mod monitor;
+pub use monitor::Monitor;
+
+mod backup;
+pub use backup::{Backup, RestoreFrom};
+
+mod dump;
+pub use dump::{Dump, Restore};
+
+mod replicaof;
+pub use replicaof::{PSync, ReplicaOf, Wait};
+
+mod expire;
+pub use expire::{Expire, ExpireAt};
+
+mod ttl;
+pub use ttl::{ExpireTime, Ttl};
+
+mod incr;
+pub use incr::{Incr, IncrByFloat};
+
+mod key_type;
+pub use key_type::Type;
+
+mod unlink;
+pub use unlink::Unlink;
+
+mod touch;
+pub use touch::Touch;
+
+mod object;
+pub use object::Object;
+
+mod memory;
+pub use memory::Memory;
+
+mod hash;
+pub use hash::{HDel, HGet, HGetAll, HRandField, HSet};
+
+mod list;
+pub use list::{BlockingPop, LInsert, LLen, LMPop, LPos, LRange, LRem, LSet, Pop, Push};
+
+mod sets;
+pub use sets::{SAdd, SCard, SIsMember, SMembers, SRandMember, SRem, SetAlgebra};
+
+mod zset;
+pub use zset::{
+    ZAdd, ZCard, ZIncrBy, ZMPop, ZRandMember, ZRange, ZRangeByScore, ZRank, ZRem, ZScore,
+};
+
+mod geo;
+pub use geo::{GeoAdd, GeoDist, GeoOrigin, GeoPos, GeoSearch, GeoShape, GeoUnit};
+
+mod bitmap;
+pub use bitmap::{BitCount, GetBit, SetBit};
+
+mod stream;
+pub use stream::{XAck, XAdd, XClaim, XGroup, XLen, XRange, XRead, XReadGroup};
+
+mod table;
+pub use table::{CommandSpec, KeySpec};
+
+use crate::{interceptor::ConnContext, Connection, Db, Frame, Parse, Shutdown};
 
 /// 支持的命令的枚举。
 #[derive(Debug)]
@@ -24,9 +137,100 @@ pub enum Command {
     Get(Get),
     Set(Set),
     Unknown(Unknown),
+    Invalid(Invalid),
     Publish(Publish),
     Subscribe(Subscribe),
+    Unsubscribe(Unsubscribe),
+    PSubscribe(PSubscribe),
+    PUnsubscribe(PUnsubscribe),
+    SSubscribe(SSubscribe),
+    SUnsubscribe(SUnsubscribe),
     Ping(Ping),
+    Auth(Auth),
+    Hello(Hello),
+    Asking(Asking),
+    Reset(Reset),
+    Config(Config),
+    Acl(AclCommand),
+    Client(ClientCommand),
+    Cluster(ClusterCommand),
+    Command(CommandCommand),
+    Eval(Eval),
+    Script(Script),
+    Function(Function),
+    FCall(FCall),
+    Save(Save),
+    LastSave(LastSave),
+    Slowlog(Slowlog),
+    Latency(Latency),
+    Debug(DebugCommand),
+    Info(Info),
+    Monitor(Monitor),
+    Backup(Backup),
+    RestoreFrom(RestoreFrom),
+    Dump(Dump),
+    Restore(Restore),
+    ReplicaOf(ReplicaOf),
+    PSync(PSync),
+    Wait(Wait),
+    Expire(Expire),
+    ExpireAt(ExpireAt),
+    Ttl(Ttl),
+    ExpireTime(ExpireTime),
+    Incr(Incr),
+    IncrByFloat(IncrByFloat),
+    Type(Type),
+    Unlink(Unlink),
+    Touch(Touch),
+    Object(Object),
+    Memory(Memory),
+    HSet(HSet),
+    HGet(HGet),
+    HDel(HDel),
+    HGetAll(HGetAll),
+    HRandField(HRandField),
+    Push(Push),
+    Pop(Pop),
+    LRange(LRange),
+    LLen(LLen),
+    LPos(LPos),
+    LInsert(LInsert),
+    LSet(LSet),
+    LRem(LRem),
+    BlockingPop(BlockingPop),
+    LMPop(LMPop),
+    SAdd(SAdd),
+    SRem(SRem),
+    SMembers(SMembers),
+    SIsMember(SIsMember),
+    SCard(SCard),
+    SRandMember(SRandMember),
+    SetAlgebra(SetAlgebra),
+    ZAdd(ZAdd),
+    ZScore(ZScore),
+    ZRange(ZRange),
+    ZCard(ZCard),
+    ZRandMember(ZRandMember),
+    ZRangeByScore(ZRangeByScore),
+    ZIncrBy(ZIncrBy),
+    ZRem(ZRem),
+    ZRank(ZRank),
+    ZMPop(ZMPop),
+    GeoAdd(GeoAdd),
+    GeoPos(GeoPos),
+    GeoDist(GeoDist),
+    GeoSearch(GeoSearch),
+    SetBit(SetBit),
+    GetBit(GetBit),
+    BitCount(BitCount),
+    XAdd(XAdd),
+    XRange(XRange),
+    XLen(XLen),
+    XRead(XRead),
+    XGroup(XGroup),
+    XReadGroup(XReadGroup),
+    XAck(XAck),
+    XClaim(XClaim),
 }
 
 impl Command {
@@ -42,13 +246,145 @@ impl Command {
         // 我们将其转换为全小写用于匹配
         let command_name = parse.next_string()?.to_lowercase();
 
+        // 在解析之前检查参数个数，参数个数不正确的命令直接返回错误信息。
+        if let Some(spec) = CommandSpec::lookup(&command_name) {
+            if !spec.check_arity(parse.remaining() + 1) {
+                return Ok(Command::Invalid(Invalid::wrong_arity(command_name)));
+            }
+        }
+        // `HSet`的 field 和 value、`ZAdd`的分数和成员必须成对出现。
+        if matches!(&command_name[..], "hset" | "zadd") && parse.remaining() % 2 == 0 {
+            return Ok(Command::Invalid(Invalid::wrong_arity(command_name)));
+        }
+        // `XAdd`在 key 和 ID 之后，字段和值也必须成对出现。
+        if command_name == "xadd" && parse.remaining() % 2 == 1 {
+            return Ok(Command::Invalid(Invalid::wrong_arity(command_name)));
+        }
+
+        // 参数不合法时不关闭连接，而是像 Redis 一样向客户端回复错误信息。
+        match Command::parse_args(&command_name, &mut parse) {
+            Ok(command) => Ok(command),
+            Err(err) => Ok(Command::Invalid(Invalid::from_error(command_name, err))),
+        }
+    }
+
+    /// 解析命令名称之后的参数。
+    fn parse_args(command_name: &str, parse: &mut Parse) -> crate::Result<Command> {
         // 匹配命令名称，传递`Parse`用于解析为具体的命令
-        let command = match &command_name[..] {
-            "get" => Command::Get(Get::parse_frame(&mut parse)?),
-            "set" => Command::Set(Set::parse_frame(&mut parse)?),
-            "publish" => Command::Publish(Publish::parse_frames(&mut parse)?),
-            "subscribe" => Command::Subscribe(Subscribe::parse_frames(&mut parse)?),
-            "ping" => Command::Ping(Ping::parse_frames(&mut parse)?),
+        let command = match command_name {
+            "get" => Command::Get(Get::parse_frame(parse)?),
+            "set" => Command::Set(Set::parse_frame(parse)?),
+            "setex" => Command::Set(Set::parse_setex(parse, false)?),
+            "psetex" => Command::Set(Set::parse_setex(parse, true)?),
+            "publish" => Command::Publish(Publish::parse_frames(parse, false)?),
+            "spublish" => Command::Publish(Publish::parse_frames(parse, true)?),
+            "subscribe" => Command::Subscribe(Subscribe::parse_frames(parse)?),
+            "unsubscribe" => Command::Unsubscribe(Unsubscribe::parse_frames(parse)?),
+            "psubscribe" => Command::PSubscribe(PSubscribe::parse_frames(parse)?),
+            "punsubscribe" => Command::PUnsubscribe(PUnsubscribe::parse_frames(parse)?),
+            "ssubscribe" => Command::SSubscribe(SSubscribe::parse_frames(parse)?),
+            "sunsubscribe" => Command::SUnsubscribe(SUnsubscribe::parse_frames(parse)?),
+            "ping" => Command::Ping(Ping::parse_frames(parse)?),
+            "auth" => Command::Auth(Auth::parse_frames(parse)?),
+            "hello" => Command::Hello(Hello::parse_frames(parse)?),
+            "asking" => Command::Asking(Asking::parse_frames(parse)?),
+            "reset" => Command::Reset(Reset::parse_frames(parse)?),
+            "config" => Command::Config(Config::parse_frames(parse)?),
+            "acl" => Command::Acl(AclCommand::parse_frames(parse)?),
+            "client" => Command::Client(ClientCommand::parse_frames(parse)?),
+            "cluster" => Command::Cluster(ClusterCommand::parse_frames(parse)?),
+            "command" => Command::Command(CommandCommand::parse_frames(parse)?),
+            "eval" => Command::Eval(Eval::parse_frames(parse, false)?),
+            "evalsha" => Command::Eval(Eval::parse_frames(parse, true)?),
+            "script" => Command::Script(Script::parse_frames(parse)?),
+            "function" => Command::Function(Function::parse_frames(parse)?),
+            "fcall" => Command::FCall(FCall::parse_frames(parse)?),
+            "save" => Command::Save(Save::parse_frames(parse, false)?),
+            "bgsave" => Command::Save(Save::parse_frames(parse, true)?),
+            "lastsave" => Command::LastSave(LastSave::parse_frames(parse)?),
+            "slowlog" => Command::Slowlog(Slowlog::parse_frames(parse)?),
+            "latency" => Command::Latency(Latency::parse_frames(parse)?),
+            "debug" => Command::Debug(DebugCommand::parse_frames(parse)?),
+            "info" => Command::Info(Info::parse_frames(parse)?),
+            "monitor" => Command::Monitor(Monitor::parse_frames(parse)?),
+            "backup" => Command::Backup(Backup::parse_frames(parse)?),
+            "restorefrom" => Command::RestoreFrom(RestoreFrom::parse_frames(parse)?),
+            "dump" => Command::Dump(Dump::parse_frames(parse)?),
+            "restore" => Command::Restore(Restore::parse_frames(parse)?),
+            "replicaof" => Command::ReplicaOf(ReplicaOf::parse_frames(parse)?),
+            "psync" => Command::PSync(PSync::parse_frames(parse)?),
+            "wait" => Command::Wait(Wait::parse_frames(parse)?),
+            "expire" => Command::Expire(Expire::parse_frames(parse, false)?),
+            "pexpire" => Command::Expire(Expire::parse_frames(parse, true)?),
+            "expireat" => Command::ExpireAt(ExpireAt::parse_frames(parse, false)?),
+            "pexpireat" => Command::ExpireAt(ExpireAt::parse_frames(parse, true)?),
+            "ttl" => Command::Ttl(Ttl::parse_frames(parse, false)?),
+            "pttl" => Command::Ttl(Ttl::parse_frames(parse, true)?),
+            "expiretime" => Command::ExpireTime(ExpireTime::parse_frames(parse, false)?),
+            "pexpiretime" => Command::ExpireTime(ExpireTime::parse_frames(parse, true)?),
+            "type" => Command::Type(Type::parse_frames(parse)?),
+            "unlink" => Command::Unlink(Unlink::parse_frames(parse)?),
+            "touch" => Command::Touch(Touch::parse_frames(parse)?),
+            "object" => Command::Object(Object::parse_frames(parse)?),
+            "memory" => Command::Memory(Memory::parse_frames(parse)?),
+            "hset" => Command::HSet(HSet::parse_frames(parse)?),
+            "hget" => Command::HGet(HGet::parse_frames(parse)?),
+            "hdel" => Command::HDel(HDel::parse_frames(parse)?),
+            "hgetall" => Command::HGetAll(HGetAll::parse_frames(parse)?),
+            "hrandfield" => Command::HRandField(HRandField::parse_frames(parse)?),
+            "lpush" => Command::Push(Push::parse_frames(parse, true)?),
+            "rpush" => Command::Push(Push::parse_frames(parse, false)?),
+            "lpop" => Command::Pop(Pop::parse_frames(parse, true)?),
+            "rpop" => Command::Pop(Pop::parse_frames(parse, false)?),
+            "lrange" => Command::LRange(LRange::parse_frames(parse)?),
+            "llen" => Command::LLen(LLen::parse_frames(parse)?),
+            "lpos" => Command::LPos(LPos::parse_frames(parse)?),
+            "linsert" => Command::LInsert(LInsert::parse_frames(parse)?),
+            "lset" => Command::LSet(LSet::parse_frames(parse)?),
+            "lrem" => Command::LRem(LRem::parse_frames(parse)?),
+            "lmpop" => Command::LMPop(LMPop::parse_frames(parse, false)?),
+            "blmpop" => Command::LMPop(LMPop::parse_frames(parse, true)?),
+            "sadd" => Command::SAdd(SAdd::parse_frames(parse)?),
+            "srem" => Command::SRem(SRem::parse_frames(parse)?),
+            "smembers" => Command::SMembers(SMembers::parse_frames(parse)?),
+            "sismember" => Command::SIsMember(SIsMember::parse_frames(parse)?),
+            "scard" => Command::SCard(SCard::parse_frames(parse)?),
+            "srandmember" => Command::SRandMember(SRandMember::parse_frames(parse)?),
+            "sinter" | "sunion" | "sdiff" | "sinterstore" | "sunionstore" | "sdiffstore" => {
+                Command::SetAlgebra(SetAlgebra::parse_frames(parse, command_name)?)
+            }
+            "zadd" => Command::ZAdd(ZAdd::parse_frames(parse)?),
+            "zscore" => Command::ZScore(ZScore::parse_frames(parse)?),
+            "zrange" => Command::ZRange(ZRange::parse_frames(parse)?),
+            "zcard" => Command::ZCard(ZCard::parse_frames(parse)?),
+            "zrandmember" => Command::ZRandMember(ZRandMember::parse_frames(parse)?),
+            "zrangebyscore" => Command::ZRangeByScore(ZRangeByScore::parse_frames(parse)?),
+            "zincrby" => Command::ZIncrBy(ZIncrBy::parse_frames(parse)?),
+            "zrem" => Command::ZRem(ZRem::parse_frames(parse)?),
+            "zrank" => Command::ZRank(ZRank::parse_frames(parse)?),
+            "zmpop" => Command::ZMPop(ZMPop::parse_frames(parse, false)?),
+            "bzmpop" => Command::ZMPop(ZMPop::parse_frames(parse, true)?),
+            "geoadd" => Command::GeoAdd(GeoAdd::parse_frames(parse)?),
+            "geopos" => Command::GeoPos(GeoPos::parse_frames(parse)?),
+            "geodist" => Command::GeoDist(GeoDist::parse_frames(parse)?),
+            "geosearch" => Command::GeoSearch(GeoSearch::parse_frames(parse)?),
+            "setbit" => Command::SetBit(SetBit::parse_frames(parse)?),
+            "getbit" => Command::GetBit(GetBit::parse_frames(parse)?),
+            "bitcount" => Command::BitCount(BitCount::parse_frames(parse)?),
+            "xadd" => Command::XAdd(XAdd::parse_frames(parse)?),
+            "xrange" => Command::XRange(XRange::parse_frames(parse)?),
+            "xlen" => Command::XLen(XLen::parse_frames(parse)?),
+            "xread" => Command::XRead(XRead::parse_frames(parse)?),
+            "xgroup" => Command::XGroup(XGroup::parse_frames(parse)?),
+            "xreadgroup" => Command::XReadGroup(XReadGroup::parse_frames(parse)?),
+            "xack" => Command::XAck(XAck::parse_frames(parse)?),
+            "xclaim" => Command::XClaim(XClaim::parse_frames(parse)?),
+            "blpop" => Command::BlockingPop(BlockingPop::parse_frames(parse, true)?),
+            "brpop" => Command::BlockingPop(BlockingPop::parse_frames(parse, false)?),
+            "incr" | "decr" | "incrby" | "decrby" => {
+                Command::Incr(Incr::parse_frames(parse, command_name)?)
+            }
+            "incrbyfloat" => Command::IncrByFloat(IncrByFloat::parse_frames(parse)?),
             _ => {
                 // 命令无法被识别
                 return Ok(Command::Unknown(Unknown::new(command_name)));
@@ -62,31 +398,317 @@ impl Command {
         Ok(command)
     }
 
+    /// 执行命令并写回响应数据，返回命令是否执行成功，也就是没有回复错误。
+    ///
+    /// 执行失败的写命令不会修改数据，不需要发送给副本。
     pub(crate) async fn apply(
         self,
         db: &Db,
         dst: &mut Connection,
         shutdown: &mut Shutdown,
-    ) -> crate::Result<()> {
-        use Command::*;
+        ctx: &mut ConnContext,
+    ) -> crate::Result<bool> {
+        use self::Command::*;
+        dst.take_error_written();
         match self {
             Get(cmd) => cmd.apply(db, dst).await,
             Set(cmd) => cmd.apply(db, dst).await,
             Unknown(cmd) => cmd.apply(dst).await,
+            Invalid(cmd) => cmd.apply(dst).await,
             Publish(cmd) => cmd.apply(db, dst).await,
-            Subscribe(cmd) => cmd.apply(db, dst, shutdown).await,
+            Subscribe(cmd) => cmd.apply(db, dst, shutdown, ctx).await,
+            Unsubscribe(cmd) => cmd.apply(dst).await,
+            PSubscribe(cmd) => cmd.apply(db, dst, shutdown, ctx).await,
+            PUnsubscribe(cmd) => cmd.apply(dst).await,
+            SSubscribe(cmd) => cmd.apply(db, dst, shutdown, ctx).await,
+            SUnsubscribe(cmd) => cmd.apply(dst).await,
             Ping(cmd) => cmd.apply(dst).await,
-        }
+            Auth(cmd) => cmd.apply(db, dst, ctx).await,
+            Hello(cmd) => cmd.apply(db, dst, ctx).await,
+            Asking(cmd) => cmd.apply(db, dst, ctx).await,
+            Reset(cmd) => cmd.apply(dst, ctx).await,
+            Config(cmd) => cmd.apply(db, dst).await,
+            Acl(cmd) => cmd.apply(db, dst, ctx).await,
+            Client(cmd) => cmd.apply(db, dst, ctx).await,
+            Cluster(cmd) => cmd.apply(db, dst).await,
+            Self::Command(cmd) => cmd.apply(dst).await,
+            Eval(cmd) => cmd.apply(db, dst, ctx).await,
+            Script(cmd) => cmd.apply(db, dst).await,
+            Function(cmd) => cmd.apply(db, dst).await,
+            FCall(cmd) => cmd.apply(db, dst, ctx).await,
+            Save(cmd) => cmd.apply(db, dst).await,
+            LastSave(cmd) => cmd.apply(db, dst).await,
+            Slowlog(cmd) => cmd.apply(db, dst).await,
+            Latency(cmd) => cmd.apply(db, dst).await,
+            Self::Debug(cmd) => cmd.apply(db, dst).await,
+            Info(cmd) => cmd.apply(db, dst).await,
+            Monitor(cmd) => cmd.apply(db, dst, shutdown).await,
+            Backup(cmd) => cmd.apply(db, dst).await,
+            RestoreFrom(cmd) => cmd.apply(db, dst).await,
+            Dump(cmd) => cmd.apply(db, dst).await,
+            Restore(cmd) => cmd.apply(db, dst).await,
+            ReplicaOf(cmd) => cmd.apply(db, dst).await,
+            PSync(cmd) => cmd.apply(db, dst, shutdown).await,
+            Wait(cmd) => cmd.apply(db, dst, shutdown, ctx).await,
+            Expire(cmd) => cmd.apply(db, dst).await,
+            ExpireAt(cmd) => cmd.apply(db, dst).await,
+            Ttl(cmd) => cmd.apply(db, dst).await,
+            ExpireTime(cmd) => cmd.apply(db, dst).await,
+            Incr(cmd) => cmd.apply(db, dst).await,
+            IncrByFloat(cmd) => cmd.apply(db, dst).await,
+            Type(cmd) => cmd.apply(db, dst).await,
+            Unlink(cmd) => cmd.apply(db, dst).await,
+            Touch(cmd) => cmd.apply(db, dst).await,
+            Object(cmd) => cmd.apply(db, dst).await,
+            Memory(cmd) => cmd.apply(db, dst).await,
+            HSet(cmd) => cmd.apply(db, dst).await,
+            HGet(cmd) => cmd.apply(db, dst).await,
+            HDel(cmd) => cmd.apply(db, dst).await,
+            HGetAll(cmd) => cmd.apply(db, dst).await,
+            HRandField(cmd) => cmd.apply(db, dst).await,
+            Push(cmd) => cmd.apply(db, dst).await,
+            Pop(cmd) => cmd.apply(db, dst).await,
+            LRange(cmd) => cmd.apply(db, dst).await,
+            LLen(cmd) => cmd.apply(db, dst).await,
+            LPos(cmd) => cmd.apply(db, dst).await,
+            LInsert(cmd) => cmd.apply(db, dst).await,
+            LSet(cmd) => cmd.apply(db, dst).await,
+            LRem(cmd) => cmd.apply(db, dst).await,
+            BlockingPop(cmd) => cmd.apply(db, dst, shutdown).await,
+            LMPop(cmd) => cmd.apply(db, dst, shutdown).await,
+            SAdd(cmd) => cmd.apply(db, dst).await,
+            SRem(cmd) => cmd.apply(db, dst).await,
+            SMembers(cmd) => cmd.apply(db, dst).await,
+            SIsMember(cmd) => cmd.apply(db, dst).await,
+            SCard(cmd) => cmd.apply(db, dst).await,
+            SRandMember(cmd) => cmd.apply(db, dst).await,
+            SetAlgebra(cmd) => cmd.apply(db, dst).await,
+            ZAdd(cmd) => cmd.apply(db, dst).await,
+            ZScore(cmd) => cmd.apply(db, dst).await,
+            ZRange(cmd) => cmd.apply(db, dst).await,
+            ZCard(cmd) => cmd.apply(db, dst).await,
+            ZRandMember(cmd) => cmd.apply(db, dst).await,
+            ZRangeByScore(cmd) => cmd.apply(db, dst).await,
+            ZIncrBy(cmd) => cmd.apply(db, dst).await,
+            ZRem(cmd) => cmd.apply(db, dst).await,
+            ZRank(cmd) => cmd.apply(db, dst).await,
+            ZMPop(cmd) => cmd.apply(db, dst, shutdown).await,
+            GeoAdd(cmd) => cmd.apply(db, dst).await,
+            GeoPos(cmd) => cmd.apply(db, dst).await,
+            GeoDist(cmd) => cmd.apply(db, dst).await,
+            GeoSearch(cmd) => cmd.apply(db, dst).await,
+            SetBit(cmd) => cmd.apply(db, dst).await,
+            GetBit(cmd) => cmd.apply(db, dst).await,
+            BitCount(cmd) => cmd.apply(db, dst).await,
+            XAdd(cmd) => cmd.apply(db, dst).await,
+            XRange(cmd) => cmd.apply(db, dst).await,
+            XLen(cmd) => cmd.apply(db, dst).await,
+            XRead(cmd) => cmd.apply(db, dst, shutdown).await,
+            XGroup(cmd) => cmd.apply(db, dst).await,
+            XReadGroup(cmd) => cmd.apply(db, dst, shutdown).await,
+            XAck(cmd) => cmd.apply(db, dst).await,
+            XClaim(cmd) => cmd.apply(db, dst).await,
+        }?;
+        Ok(!dst.take_error_written())
     }
 
-    pub(crate) fn get_name(&self) -> &str {
+    /// 获取命令的名称，名称为全小写。
+    pub fn get_name(&self) -> &str {
         match self {
             Command::Get(_) => "get",
-            Command::Publish(_) => "publish",
-            Command::Set(_) => "set",
+            Command::Publish(cmd) => cmd.get_name(),
+            Command::Set(cmd) => cmd.get_name(),
             Command::Subscribe(_) => "subscribe",
+            Command::Unsubscribe(_) => "unsubscribe",
+            Command::PSubscribe(_) => "psubscribe",
+            Command::PUnsubscribe(_) => "punsubscribe",
+            Command::SSubscribe(_) => "ssubscribe",
+            Command::SUnsubscribe(_) => "sunsubscribe",
             Command::Ping(_) => "ping",
+            Command::Auth(_) => "auth",
+            Command::Hello(_) => "hello",
+            Command::Asking(_) => "asking",
+            Command::Reset(_) => "reset",
+            Command::Config(_) => "config",
+            Command::Acl(_) => "acl",
+            Command::Client(_) => "client",
+            Command::Cluster(_) => "cluster",
+            Command::Command(_) => "command",
+            Command::Eval(cmd) => cmd.get_name(),
+            Command::Script(_) => "script",
+            Command::Function(_) => "function",
+            Command::FCall(_) => "fcall",
+            Command::Save(cmd) => cmd.get_name(),
+            Command::LastSave(_) => "lastsave",
+            Command::Slowlog(_) => "slowlog",
+            Command::Latency(_) => "latency",
+            Command::Debug(_) => "debug",
+            Command::Info(_) => "info",
+            Command::Monitor(_) => "monitor",
+            Command::Backup(_) => "backup",
+            Command::RestoreFrom(_) => "restorefrom",
+            Command::Dump(_) => "dump",
+            Command::Restore(_) => "restore",
+            Command::ReplicaOf(_) => "replicaof",
+            Command::PSync(_) => "psync",
+            Command::Wait(_) => "wait",
+            Command::Expire(cmd) => cmd.get_name(),
+            Command::ExpireAt(cmd) => cmd.get_name(),
+            Command::Ttl(cmd) => cmd.get_name(),
+            Command::ExpireTime(cmd) => cmd.get_name(),
+            Command::Incr(cmd) => cmd.get_name(),
+            Command::IncrByFloat(_) => "incrbyfloat",
+            Command::Type(_) => "type",
+            Command::Unlink(_) => "unlink",
+            Command::Touch(_) => "touch",
+            Command::Object(_) => "object",
+            Command::Memory(_) => "memory",
+            Command::HSet(_) => "hset",
+            Command::HGet(_) => "hget",
+            Command::HDel(_) => "hdel",
+            Command::HGetAll(_) => "hgetall",
+            Command::HRandField(_) => "hrandfield",
+            Command::Push(cmd) => cmd.get_name(),
+            Command::Pop(cmd) => cmd.get_name(),
+            Command::LRange(_) => "lrange",
+            Command::LLen(_) => "llen",
+            Command::LPos(_) => "lpos",
+            Command::LInsert(_) => "linsert",
+            Command::LSet(_) => "lset",
+            Command::LRem(_) => "lrem",
+            Command::BlockingPop(cmd) => cmd.get_name(),
+            Command::LMPop(cmd) => cmd.get_name(),
+            Command::SAdd(_) => "sadd",
+            Command::SRem(_) => "srem",
+            Command::SMembers(_) => "smembers",
+            Command::SIsMember(_) => "sismember",
+            Command::SCard(_) => "scard",
+            Command::SRandMember(_) => "srandmember",
+            Command::SetAlgebra(cmd) => cmd.get_name(),
+            Command::ZAdd(_) => "zadd",
+            Command::ZScore(_) => "zscore",
+            Command::ZRange(_) => "zrange",
+            Command::ZCard(_) => "zcard",
+            Command::ZRandMember(_) => "zrandmember",
+            Command::ZRangeByScore(_) => "zrangebyscore",
+            Command::ZIncrBy(_) => "zincrby",
+            Command::ZRem(_) => "zrem",
+            Command::ZRank(_) => "zrank",
+            Command::ZMPop(cmd) => cmd.get_name(),
+            Command::GeoAdd(_) => "geoadd",
+            Command::GeoPos(_) => "geopos",
+            Command::GeoDist(_) => "geodist",
+            Command::GeoSearch(_) => "geosearch",
+            Command::SetBit(_) => "setbit",
+            Command::GetBit(_) => "getbit",
+            Command::BitCount(_) => "bitcount",
+            Command::XAdd(_) => "xadd",
+            Command::XRange(_) => "xrange",
+            Command::XLen(_) => "xlen",
+            Command::XRead(_) => "xread",
+            Command::XGroup(_) => "xgroup",
+            Command::XReadGroup(_) => "xreadgroup",
+            Command::XAck(_) => "xack",
+            Command::XClaim(_) => "xclaim",
             Command::Unknown(cmd) => cmd.get_name(),
+            Command::Invalid(cmd) => cmd.get_name(),
+        }
+    }
+
+    /// 判断命令是否可能一直等待。
+    ///
+    /// 订阅命令会一直执行到客户端退出订阅，`Monitor`会一直执行到连接关闭，阻塞式命令会等待数据。
+    pub(crate) fn is_blocking(&self) -> bool {
+        match self {
+            Command::Monitor(_)
+            | Command::Subscribe(_)
+            | Command::PSubscribe(_)
+            | Command::SSubscribe(_)
+            | Command::BlockingPop(_)
+            | Command::PSync(_)
+            | Command::Wait(_) => true,
+            Command::XRead(cmd) => cmd.is_blocking(),
+            Command::XReadGroup(cmd) => cmd.is_blocking(),
+            Command::LMPop(cmd) => cmd.is_blocking(),
+            Command::ZMPop(cmd) => cmd.is_blocking(),
+            _ => false,
+        }
+    }
+
+    /// 获取命令操作的 key，如果命令不操作 key，返回`None`。
+    pub fn get_key(&self) -> Option<&str> {
+        match self {
+            Command::Get(cmd) => Some(cmd.key()),
+            Command::Set(cmd) => Some(cmd.key()),
+            // 分片信道与 key 一样属于某个槽。
+            Command::Publish(cmd) if cmd.is_sharded() => Some(cmd.channel()),
+            Command::SSubscribe(cmd) => Some(cmd.key()),
+            Command::Eval(cmd) => cmd.key(),
+            Command::FCall(cmd) => cmd.key(),
+            Command::Expire(cmd) => Some(cmd.key()),
+            Command::ExpireAt(cmd) => Some(cmd.key()),
+            Command::Ttl(cmd) => Some(cmd.key()),
+            Command::Dump(cmd) => Some(cmd.key()),
+            Command::Restore(cmd) => Some(cmd.key()),
+            Command::ExpireTime(cmd) => Some(cmd.key()),
+            Command::Incr(cmd) => Some(cmd.key()),
+            Command::IncrByFloat(cmd) => Some(cmd.key()),
+            Command::Type(cmd) => Some(cmd.key()),
+            Command::Unlink(cmd) => cmd.keys().first().map(String::as_str),
+            Command::Touch(cmd) => cmd.keys().first().map(String::as_str),
+            Command::Object(cmd) => Some(cmd.key()),
+            Command::Memory(cmd) => cmd.key(),
+            Command::HSet(cmd) => Some(cmd.key()),
+            Command::HGet(cmd) => Some(cmd.key()),
+            Command::HDel(cmd) => Some(cmd.key()),
+            Command::HGetAll(cmd) => Some(cmd.key()),
+            Command::HRandField(cmd) => Some(cmd.key()),
+            Command::Push(cmd) => Some(cmd.key()),
+            Command::Pop(cmd) => Some(cmd.key()),
+            Command::LRange(cmd) => Some(cmd.key()),
+            Command::LLen(cmd) => Some(cmd.key()),
+            Command::LPos(cmd) => Some(cmd.key()),
+            Command::LInsert(cmd) => Some(cmd.key()),
+            Command::LSet(cmd) => Some(cmd.key()),
+            Command::LRem(cmd) => Some(cmd.key()),
+            // 阻塞式弹出可以操作多个 key，这里返回第一个。
+            Command::BlockingPop(cmd) => cmd.keys().first().map(String::as_str),
+            Command::LMPop(cmd) => cmd.keys().first().map(String::as_str),
+            Command::SAdd(cmd) => Some(cmd.key()),
+            Command::SRem(cmd) => Some(cmd.key()),
+            Command::SMembers(cmd) => Some(cmd.key()),
+            Command::SIsMember(cmd) => Some(cmd.key()),
+            Command::SCard(cmd) => Some(cmd.key()),
+            Command::SRandMember(cmd) => Some(cmd.key()),
+            Command::SetAlgebra(cmd) => cmd
+                .destination()
+                .or_else(|| cmd.keys().first().map(String::as_str)),
+            Command::ZAdd(cmd) => Some(cmd.key()),
+            Command::ZScore(cmd) => Some(cmd.key()),
+            Command::ZRange(cmd) => Some(cmd.key()),
+            Command::ZCard(cmd) => Some(cmd.key()),
+            Command::ZRandMember(cmd) => Some(cmd.key()),
+            Command::ZRangeByScore(cmd) => Some(cmd.key()),
+            Command::ZIncrBy(cmd) => Some(cmd.key()),
+            Command::ZRem(cmd) => Some(cmd.key()),
+            Command::ZRank(cmd) => Some(cmd.key()),
+            Command::ZMPop(cmd) => cmd.keys().first().map(String::as_str),
+            Command::GeoAdd(cmd) => Some(cmd.key()),
+            Command::GeoPos(cmd) => Some(cmd.key()),
+            Command::GeoDist(cmd) => Some(cmd.key()),
+            Command::GeoSearch(cmd) => Some(cmd.key()),
+            Command::SetBit(cmd) => Some(cmd.key()),
+            Command::GetBit(cmd) => Some(cmd.key()),
+            Command::BitCount(cmd) => Some(cmd.key()),
+            Command::XAdd(cmd) => Some(cmd.key()),
+            Command::XRange(cmd) => Some(cmd.key()),
+            Command::XLen(cmd) => Some(cmd.key()),
+            Command::XRead(cmd) => cmd.keys().first().map(String::as_str),
+            Command::XGroup(cmd) => Some(cmd.key()),
+            Command::XReadGroup(cmd) => cmd.keys().first().map(String::as_str),
+            Command::XAck(cmd) => Some(cmd.key()),
+            Command::XClaim(cmd) => Some(cmd.key()),
+            _ => None,
         }
     }
 }
@@ -0,0 +1,68 @@
+use bytes::Bytes;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{Connection, Db, Frame, Parse, Shutdown};
+
+/// 实时查看服务器执行的命令。
+///
+/// 格式：Monitor
+///
+/// 回复`OK`后，其他连接执行的每条命令都以一个`Simple`发送给客户端，
+/// 格式为`<时间戳> [0 <客户端地址>] "命令" "参数" ...`。管理命令不会被发送。
+/// 客户端关闭连接、发送关闭信号帧，或者服务器关闭时结束。
+#[derive(Debug, Default)]
+pub struct Monitor;
+
+impl Monitor {
+    /// 创建一个`Monitor`命令。
+    pub fn new() -> Monitor {
+        Monitor
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Monitor`命令。
+    ///
+    /// 需要保证字符串`Monitor`已经被处理过了。
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Monitor> {
+        Ok(Monitor)
+    }
+
+    /// 应用命令，持续将执行的命令写回给客户端。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        // 先订阅再回复，客户端收到`OK`之后执行的命令都不会遗漏。
+        let mut commands = db.monitors().subscribe();
+        dst.write_frame(&Frame::Simple("OK".to_string())).await?;
+
+        loop {
+            tokio::select! {
+                res = commands.recv() => match res {
+                    Ok(line) => dst.write_frame(&Frame::Simple(line)).await?,
+                    // 落后太多时跳过丢失的命令，继续监视。
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                frame = dst.read_frame() => match frame? {
+                    // `socket`关闭了，或者客户端发来了关闭信号。
+                    None => return Ok(()),
+                    Some(Frame::Simple(v)) if v == "shutdown" => return Ok(()),
+                    Some(_) => {
+                        let msg = "ERR only closing the connection can leave MONITOR mode";
+                        dst.write_frame(&Frame::Error(msg.to_string())).await?;
+                    }
+                },
+                _ = shutdown.recv() => return Ok(()),
+            }
+        }
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("monitor".as_bytes()));
+        frame
+    }
+}
@@ -0,0 +1,104 @@
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse};
+
+/// 查看 key 对应的 value 的内部信息。
+///
+/// 格式：Object Encoding <key>
+///      Object IdleTime <key>
+///      Object Freq <key>
+///
+/// `Encoding`返回 value 的编码名称，例如`int`、`embstr`、`hashtable`；
+/// `IdleTime`返回距离最后一次被访问的秒数，查询本身不算作一次访问；
+/// `Freq`返回被访问的次数。如果 key 不存在返回`(nil)`。
+#[derive(Debug)]
+pub struct Object {
+    key: String,
+    op: ObjectOp,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ObjectOp {
+    Encoding,
+    IdleTime,
+    Freq,
+}
+
+impl Object {
+    /// 创建一个`Object Encoding`命令。
+    pub fn encoding(key: impl ToString) -> Object {
+        Object::with_op(key, ObjectOp::Encoding)
+    }
+
+    /// 创建一个`Object IdleTime`命令。
+    pub fn idletime(key: impl ToString) -> Object {
+        Object::with_op(key, ObjectOp::IdleTime)
+    }
+
+    /// 创建一个`Object Freq`命令。
+    pub fn freq(key: impl ToString) -> Object {
+        Object::with_op(key, ObjectOp::Freq)
+    }
+
+    fn with_op(key: impl ToString, op: ObjectOp) -> Object {
+        Object {
+            key: key.to_string(),
+            op,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Object`命令。
+    ///
+    /// 需要保证字符串`Object`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Object> {
+        let op = match &parse.next_string()?.to_lowercase()[..] {
+            "encoding" => ObjectOp::Encoding,
+            "idletime" => ObjectOp::IdleTime,
+            "freq" => ObjectOp::Freq,
+            sub => {
+                return Err(crate::Error::Parse(format!(
+                    "未知的 Object 子命令：'{}'",
+                    sub
+                )))
+            }
+        };
+        let key = parse.next_string()?;
+        Ok(Object { key, op })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.op {
+            ObjectOp::Encoding => db
+                .object_encoding(&self.key)
+                .map(|encoding| Frame::Bulk(Bytes::from_static(encoding.as_bytes()))),
+            ObjectOp::IdleTime => db
+                .idle_time(&self.key)
+                .map(|idle| Frame::Integer(idle.as_secs() as i64)),
+            ObjectOp::Freq => db
+                .access_count(&self.key)
+                .map(|count| Frame::Integer(count as i64)),
+        };
+        dst.write_frame(&response.unwrap_or(Frame::Null)).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let sub = match self.op {
+            ObjectOp::Encoding => "encoding",
+            ObjectOp::IdleTime => "idletime",
+            ObjectOp::Freq => "freq",
+        };
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("object".as_bytes()));
+        frame.push_bulk(Bytes::from(sub.as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Connection, Frame, Parse, ParseError};
+use crate::{Connection, Frame, Parse, ParseError, Protocol};
 use bytes::Bytes;
 
 /// 测试连接。
@@ -43,6 +43,22 @@ impl Ping {
         Ok(())
     }
 
+    /// 在订阅者模式下应用命令并写回响应数据。
+    ///
+    /// 与 Redis 一样，响应为`["pong", message]`，没有设置信息时`message`为空字符串。
+    /// RESP3 的连接中订阅信息与响应可以区分开，所以响应与订阅者模式之外相同。
+    pub(crate) async fn apply_subscribed(self, dst: &mut Connection) -> crate::Result<()> {
+        if dst.protocol() == Protocol::Resp3 {
+            return self.apply(dst).await;
+        }
+        let response = Frame::Array(vec![
+            Frame::Bulk(Bytes::from_static(b"pong")),
+            Frame::Bulk(self.msg.unwrap_or_default()),
+        ]);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
     /// 将命令转化为等价的`Frame`。
     pub(crate) fn into_frame(self) -> Frame {
         let mut frame = Frame::array();
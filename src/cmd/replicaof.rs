@@ -0,0 +1,237 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::{
+    interceptor::ConnContext, replication, snapshot, Connection, Db, Frame, Parse, Shutdown,
+};
+
+/// 复制另一个服务器，或者停止复制成为主节点。
+///
+/// 格式：ReplicaOf <host> <port>
+///      ReplicaOf NO ONE
+///
+/// 成为副本后会丢弃现有的数据，与主节点进行全量同步，之后持续执行主节点发送的写命令。
+/// 副本是只读的，客户端的写命令会被拒绝。停止复制后保留已经同步的数据。
+#[derive(Debug)]
+pub struct ReplicaOf {
+    // 主节点的地址，为`None`表示停止复制。
+    primary: Option<(String, u16)>,
+}
+
+/// 副本向主节点请求同步，由副本的复制任务发送。
+///
+/// 格式：PSync <replid> <offset>
+///
+/// 目前总是进行全量同步：回复`+FULLRESYNC <replid> <offset>`和数据库的快照，
+/// 之后连接用于向副本发送写命令，以及接收副本发送的`REPLCONF ACK <offset>`。
+#[derive(Debug)]
+pub struct PSync {
+    replid: String,
+    offset: String,
+}
+
+/// 等待副本确认当前连接之前的写命令。
+///
+/// 格式：Wait <numreplicas> <timeout>
+///
+/// 阻塞直到至少`numreplicas`个副本执行了当前连接的最后一条写命令，或者超过`timeout`毫秒，
+/// 返回已经确认的副本数。`timeout`为`0`时一直阻塞。
+#[derive(Debug)]
+pub struct Wait {
+    numreplicas: u64,
+    timeout: Option<Duration>,
+}
+
+impl ReplicaOf {
+    /// 创建一个`ReplicaOf`命令，`primary`为`None`时表示`ReplicaOf NO ONE`。
+    pub fn new(primary: Option<(String, u16)>) -> ReplicaOf {
+        ReplicaOf { primary }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ReplicaOf`命令。
+    ///
+    /// 需要保证字符串`ReplicaOf`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ReplicaOf> {
+        let host = parse.next_string()?;
+        let port = parse.next_string()?;
+        if host.eq_ignore_ascii_case("no") && port.eq_ignore_ascii_case("one") {
+            return Ok(ReplicaOf { primary: None });
+        }
+        let port = port
+            .parse()
+            .map_err(|_| crate::Error::invalid("Invalid master port"))?;
+        Ok(ReplicaOf {
+            primary: Some((host, port)),
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        match self.primary {
+            Some((host, port)) => replication::replicate(db, format!("{}:{}", host, port)),
+            None => db.replication().set_primary(None),
+        }
+        dst.write_frame(&Frame::Simple("OK".to_string())).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("replicaof".as_bytes()));
+        match self.primary {
+            Some((host, port)) => {
+                frame.push_bulk(Bytes::from(host.into_bytes()));
+                frame.push_bulk(Bytes::from(port.to_string()));
+            }
+            None => {
+                frame.push_bulk(Bytes::from("no".as_bytes()));
+                frame.push_bulk(Bytes::from("one".as_bytes()));
+            }
+        }
+        frame
+    }
+}
+
+impl PSync {
+    /// 创建一个请求全量同步的`PSync`命令。
+    pub(crate) fn full_sync() -> PSync {
+        PSync {
+            replid: "?".to_string(),
+            offset: "-1".to_string(),
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`PSync`命令。
+    ///
+    /// 需要保证字符串`PSync`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<PSync> {
+        let replid = parse.next_string()?;
+        let offset = parse.next_string()?;
+        Ok(PSync { replid, offset })
+    }
+
+    /// 应用命令，之后一直向副本发送写命令，直到副本断开或者服务器关闭。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        // 在生成快照时注册副本，写命令要么在快照中，要么在快照之后分配复制序号，会被发送给副本。
+        // 同时持有脚本锁的写锁，快照中不会包含执行了一半的脚本。
+        let (entries, (offset, ack)) = {
+            let _guard = db.script_lock().write().await;
+            db.snapshot_with(|| db.replication().add_replica(tx))
+        };
+        // 快照之前分配了复制序号的写命令都发送完之后，副本才开始接收写命令。
+        let offset = offset.await.map_err(|_| "副本注册失败")?;
+
+        let reply = format!("FULLRESYNC {} {}", db.replication().replid(), offset);
+        dst.write_frame(&Frame::Simple(reply)).await?;
+        let data = snapshot::encode(&entries);
+        dst.write_frame(&Frame::Bulk(Bytes::from(data))).await?;
+
+        loop {
+            tokio::select! {
+                frame = rx.recv() => match frame {
+                    Some(frame) => dst.write_frame(&frame).await?,
+                    None => return Ok(()),
+                },
+                // 副本只会发送确认。
+                frame = dst.read_frame() => match frame? {
+                    Some(frame) => {
+                        match replication::parse_replconf(&frame) {
+                            Some((sub, offset)) if sub.eq_ignore_ascii_case("ack") => {
+                                if let Ok(offset) = offset.parse() {
+                                    db.replication().ack(&ack, offset);
+                                }
+                            }
+                            _ => warn!("副本发送了无法识别的帧"),
+                        }
+                    }
+                    None => return Ok(()),
+                },
+                _ = shutdown.recv() => return Ok(()),
+            }
+        }
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("psync".as_bytes()));
+        frame.push_bulk(Bytes::from(self.replid.into_bytes()));
+        frame.push_bulk(Bytes::from(self.offset.into_bytes()));
+        frame
+    }
+}
+
+impl Wait {
+    /// 创建一个`Wait`命令，`timeout`为`None`时一直阻塞。
+    pub fn new(numreplicas: u64, timeout: Option<Duration>) -> Wait {
+        Wait {
+            numreplicas,
+            timeout,
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Wait`命令。
+    ///
+    /// 需要保证字符串`Wait`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Wait> {
+        let numreplicas = parse.next_int()?;
+        let timeout = parse
+            .next_string()?
+            .parse::<i64>()
+            .map_err(|_| crate::Error::invalid("timeout is not an integer or out of range"))?;
+        if timeout < 0 {
+            return Err(crate::Error::invalid("timeout is negative"));
+        }
+        let timeout = (timeout > 0).then(|| Duration::from_millis(timeout as u64));
+        Ok(Wait {
+            numreplicas,
+            timeout,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 阻塞期间如果服务器关闭，直接返回，不会写回响应。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+        ctx: &ConnContext,
+    ) -> crate::Result<()> {
+        let replication = db.replication();
+        let response = if replication.is_replica() {
+            Frame::Error("ERR WAIT cannot be used with replica instances".to_string())
+        } else {
+            let numreplicas = self.numreplicas.try_into().unwrap_or(usize::MAX);
+            tokio::select! {
+                acked = replication.wait_acked(numreplicas, ctx.repl_offset(), self.timeout) => {
+                    Frame::Integer(acked as i64)
+                }
+                _ = shutdown.recv() => return Ok(()),
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("wait".as_bytes()));
+        frame.push_int(self.numreplicas as i64);
+        let timeout = self.timeout.map(|t| t.as_millis()).unwrap_or(0);
+        frame.push_bulk(Bytes::from(timeout.to_string()));
+        frame
+    }
+}
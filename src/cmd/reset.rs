@@ -0,0 +1,47 @@
+use bytes::Bytes;
+
+use crate::{interceptor::ConnContext, Connection, Frame, Parse, Protocol};
+
+/// 重置连接的状态。
+///
+/// 格式：Reset
+///
+/// 在订阅者模式下会退订所有信道、模式和分片信道并退出订阅者模式。
+/// 连接会取消认证，恢复为默认用户，协议版本恢复为 RESP2。总是返回`RESET`。
+#[derive(Debug, Default)]
+pub struct Reset;
+
+impl Reset {
+    /// 创建一个`Reset`命令。
+    pub fn new() -> Reset {
+        Reset
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Reset`命令。
+    ///
+    /// 需要保证字符串`Reset`已经被处理过了。
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Reset> {
+        Ok(Reset)
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 订阅者模式之外，只需要取消认证并恢复协议版本。
+    pub(crate) async fn apply(
+        self,
+        dst: &mut Connection,
+        ctx: &mut ConnContext,
+    ) -> crate::Result<()> {
+        ctx.reset_user();
+        dst.set_protocol(Protocol::Resp2);
+        dst.write_frame(&Frame::Simple("RESET".to_string())).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("reset".as_bytes()));
+        frame
+    }
+}
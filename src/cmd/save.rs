@@ -0,0 +1,108 @@
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse};
+
+/// 将数据库的快照写入配置的快照文件。
+///
+/// 格式：Save
+///      BgSave
+///
+/// 快照文件由配置项`dbfilename`决定，默认为`dump.rdb`。
+/// `Save`在快照写入完成后才响应，`BgSave`在后台写入并立即响应。
+/// 配置项`save`设置自动保存的规则，例如`900 1 300 10`表示 900 秒内至少 1 次修改，
+/// 或者 300 秒内至少 10 次修改时在后台保存快照。
+#[derive(Debug, Default)]
+pub struct Save {
+    // 是否是`BgSave`命令。
+    background: bool,
+}
+
+/// 获取上次成功保存快照的 UNIX 时间戳，单位是秒。
+///
+/// 格式：LastSave
+#[derive(Debug, Default)]
+pub struct LastSave;
+
+impl Save {
+    /// 创建一个`Save`命令。
+    pub fn new() -> Save {
+        Save { background: false }
+    }
+
+    /// 创建一个`BgSave`命令。
+    pub fn background() -> Save {
+        Save { background: true }
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        if self.background {
+            "bgsave"
+        } else {
+            "save"
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Save`命令。
+    ///
+    /// `background`为`true`时解析为`BgSave`命令。
+    /// 需要保证字符串`Save`或`BgSave`已经被处理过了。
+    pub(crate) fn parse_frames(_parse: &mut Parse, background: bool) -> crate::Result<Save> {
+        Ok(Save { background })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 保存失败不会关闭连接，而是向客户端返回错误信息。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = if self.background {
+            if db.bgsave() {
+                Frame::Simple("Background saving started".to_string())
+            } else {
+                Frame::Error("ERR Background save already in progress".to_string())
+            }
+        } else {
+            match db.save().await {
+                Ok(()) => Frame::Simple("OK".to_string()),
+                Err(msg) => Frame::Error(msg),
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().to_string()));
+        frame
+    }
+}
+
+impl LastSave {
+    /// 创建一个`LastSave`命令。
+    pub fn new() -> LastSave {
+        LastSave
+    }
+
+    /// 通过`Parse`将`Frame`解析为`LastSave`命令。
+    ///
+    /// 需要保证字符串`LastSave`已经被处理过了。
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<LastSave> {
+        Ok(LastSave)
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Integer(db.persistence().last_save() as i64);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lastsave".as_bytes()));
+        frame
+    }
+}
@@ -1,6 +1,8 @@
 use crate::Db;
 use crate::Frame;
-use std::time::Duration;
+use crate::SetCondition;
+use crate::SetOptions;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use bytes::Bytes;
 
@@ -10,16 +12,49 @@ use crate::ParseError::EndOfStream;
 
 /// 设置 key-value 对
 ///
-/// 格式：Set <key> <value> [milliseconds]
+/// 格式：Set <key> <value> [EX seconds|PX milliseconds|EXAT unix-time-seconds|PXAT unix-time-milliseconds|KEEPTTL] [NX|XX] [GET]
+///      SetEx <key> <seconds> <value>
+///      PSetEx <key> <milliseconds> <value>
 ///
 /// 如果 key 已经有对应的 value 了，覆盖原有值，无论类型。
 /// 在覆盖的同时也会清除原有键值对对应的“过期时间”。
+///
+/// `KEEPTTL`表示保留原有的过期时间。
+///
+/// `NX`表示只在 key 不存在时设置，`XX`表示只在 key 存在时设置。
+/// 设置成功返回`OK`，因条件不满足而没有设置时返回`(nil)`。
+/// 带有`GET`时无论是否设置都返回原有的值，key 不存在时返回`(nil)`，
+/// 原有的值不是字符串时返回错误并且不会设置。
+/// `SetEx`和`PSetEx`等价于带有`EX`和`PX`的`Set`。
 #[derive(Debug)]
 pub struct Set {
     key: String,
     value: Bytes,
     // 过期时间。
-    expire: Option<Duration>,
+    expire: Option<Expiration>,
+    // 写入的条件。
+    condition: SetCondition,
+    // 是否保留原有的过期时间。
+    keep_ttl: bool,
+    // 是否返回原有的值。
+    get: bool,
+    // 命令名称，`set`、`setex`或`psetex`，只影响命令名称和错误信息。
+    name: &'static str,
+}
+
+/// `Set`命令中的过期时间，保留客户端指定的形式和单位。
+///
+/// 数值可能是`0`或负数，在应用命令时才检查。
+#[derive(Debug, Clone, Copy)]
+enum Expiration {
+    /// `EX`，单位为秒。
+    Seconds(i64),
+    /// `PX`，单位为毫秒。
+    Millis(i64),
+    /// `EXAT`，UNIX 时间戳，单位为秒。
+    UnixSeconds(i64),
+    /// `PXAT`，UNIX 时间戳，单位为毫秒。
+    UnixMillis(i64),
 }
 
 impl Set {
@@ -28,10 +63,27 @@ impl Set {
         Set {
             key: key.to_string(),
             value,
-            expire,
+            expire: expire.map(|expire| Expiration::Millis(expire.as_millis() as i64)),
+            condition: SetCondition::Always,
+            keep_ttl: false,
+            get: false,
+            name: "set",
         }
     }
 
+    /// 保留原有的过期时间，对应`KEEPTTL`，会覆盖创建时指定的过期时间。
+    pub fn keep_ttl(mut self) -> Set {
+        self.expire = None;
+        self.keep_ttl = true;
+        self
+    }
+
+    /// 在响应中返回原有的值，对应`GET`。
+    pub fn get(mut self) -> Set {
+        self.get = true;
+        self
+    }
+
     /// 获取 key。
     pub fn key(&self) -> &str {
         &self.key
@@ -43,18 +95,54 @@ impl Set {
     }
 
     /// 获取过期时间。
+    ///
+    /// 使用`EXAT`或`PXAT`指定过期的时刻时，返回从现在到那个时刻的时长。
+    /// 过期时间无效时返回`None`。
     pub fn expire(&self) -> Option<Duration> {
-        self.expire
+        self.expire.and_then(Expiration::duration)
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        self.name
     }
 
     /// 负责应用命令并写回响应数据。
     ///
     /// 应用命令委派给了`Db`的方法。写回响应数据使用到了`Connection`，
     /// 如果写回响应错出错，返回`Err`。
+    /// 过期时间不是正数或者超出范围时，向客户端返回错误，连接不会关闭。
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
-        db.set(self.key, self.value, self.expire);
+        let expire = match self.expire.map(Expiration::duration) {
+            None => None,
+            Some(Some(expire)) => Some(expire),
+            Some(None) => {
+                let response = Frame::Error(format!(
+                    "ERR invalid expire time in '{}' command",
+                    self.name
+                ));
+                dst.write_frame(&response).await?;
+                return Ok(());
+            }
+        };
+
+        let options = SetOptions {
+            condition: self.condition,
+            keep_ttl: self.keep_ttl,
+            get: self.get,
+        };
+        let response = match db.set(self.key, self.value, expire, options) {
+            // 带有`GET`时返回原有的值。
+            Ok((_, prev)) if self.get => match prev {
+                Some(prev) => Frame::Bulk(prev),
+                None => Frame::Null,
+            },
+            Ok((true, _)) => Frame::Simple("OK".to_string()),
+            // 条件不满足，没有写入。
+            Ok((false, _)) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
         // 写入响应信息
-        let response = Frame::Simple("OK".to_string());
         dst.write_frame(&response).await?;
         Ok(())
     }
@@ -69,32 +157,139 @@ impl Set {
         // 获取 value。
         let value = parse.next_bytes()?;
 
-        // 判断过期时间有没有设置。
+        // 解析可选参数，参数的顺序不限。
         let mut expire = None;
-        match parse.next_string() {
-            // 过期时间，单位是毫秒。
-            Ok(_) => {
-                let ms = parse.next_int()?;
-                expire = Some(Duration::from_millis(ms));
+        let mut condition = SetCondition::Always;
+        let mut keep_ttl = false;
+        let mut get = false;
+        loop {
+            match parse.next_string() {
+                Ok(option) => match &option.to_lowercase()[..] {
+                    // 过期时间，单位是秒。
+                    "ex" if expire.is_none() && !keep_ttl => {
+                        expire = Some(Expiration::Seconds(parse.next_signed_int()?));
+                    }
+                    // 过期时间，单位是毫秒。
+                    "px" if expire.is_none() && !keep_ttl => {
+                        expire = Some(Expiration::Millis(parse.next_signed_int()?));
+                    }
+                    // 过期的时刻，单位是秒。
+                    "exat" if expire.is_none() && !keep_ttl => {
+                        expire = Some(Expiration::UnixSeconds(parse.next_signed_int()?));
+                    }
+                    // 过期的时刻，单位是毫秒。
+                    "pxat" if expire.is_none() && !keep_ttl => {
+                        expire = Some(Expiration::UnixMillis(parse.next_signed_int()?));
+                    }
+                    "keepttl" if expire.is_none() && !keep_ttl => keep_ttl = true,
+                    "nx" if condition == SetCondition::Always => {
+                        condition = SetCondition::IfAbsent;
+                    }
+                    "xx" if condition == SetCondition::Always => {
+                        condition = SetCondition::IfPresent;
+                    }
+                    "get" if !get => get = true,
+                    // 未知的参数，或者重复、冲突的参数。
+                    _ => return Err(crate::Error::invalid("syntax error")),
+                },
+                // 没有更多参数了。
+                Err(EndOfStream) => break,
+                // `next_string()`抛出的其他错误，这里直接抛出。
+                Err(err) => return Err(err.into()),
             }
-            // 如果没有设置，就为`None`。
-            Err(EndOfStream) => {}
-            // `next_string()`抛出的其他错误，这里直接抛出。
-            Err(err) => return Err(err.into()),
         }
 
-        Ok(Set { key, value, expire })
+        Ok(Set {
+            key,
+            value,
+            expire,
+            condition,
+            keep_ttl,
+            get,
+            name: "set",
+        })
+    }
+
+    /// 通过`Parse`将`Frame`解析为`SetEx`或`PSetEx`命令。
+    ///
+    /// `millis`为`true`时过期时间的单位是毫秒，否则是秒。
+    /// 需要保证字符串`SetEx`或`PSetEx`已经被处理过了。
+    pub(crate) fn parse_setex(parse: &mut Parse, millis: bool) -> crate::Result<Set> {
+        let key = parse.next_string()?;
+        let n = parse.next_signed_int()?;
+        let value = parse.next_bytes()?;
+        let (expire, name) = if millis {
+            (Expiration::Millis(n), "psetex")
+        } else {
+            (Expiration::Seconds(n), "setex")
+        };
+        Ok(Set {
+            key,
+            value,
+            expire: Some(expire),
+            condition: SetCondition::Always,
+            keep_ttl: false,
+            get: false,
+            name,
+        })
     }
 
     /// 将命令转换为等价的`Frame`
+    ///
+    /// 总是使用`Set`命令，过期时间使用标准的选项形式。
     pub(crate) fn into_frame(self) -> Frame {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("set".as_bytes()));
         frame.push_bulk(Bytes::from(self.key.into_bytes()));
         frame.push_bulk(self.value);
-        if let Some(ms) = self.expire {
-            frame.push_int(ms.as_millis() as u64);
+        if let Some(expire) = self.expire {
+            let (option, n) = match expire {
+                Expiration::Seconds(n) => ("ex", n),
+                Expiration::Millis(n) => ("px", n),
+                Expiration::UnixSeconds(n) => ("exat", n),
+                Expiration::UnixMillis(n) => ("pxat", n),
+            };
+            frame.push_bulk(Bytes::from(option.as_bytes()));
+            frame.push_bulk(Bytes::from(n.to_string()));
+        }
+        match self.condition {
+            SetCondition::IfAbsent => frame.push_bulk(Bytes::from("nx".as_bytes())),
+            SetCondition::IfPresent => frame.push_bulk(Bytes::from("xx".as_bytes())),
+            SetCondition::Always => {}
+        }
+        if self.keep_ttl {
+            frame.push_bulk(Bytes::from("keepttl".as_bytes()));
+        }
+        if self.get {
+            frame.push_bulk(Bytes::from("get".as_bytes()));
         }
         frame
     }
 }
+
+impl Expiration {
+    /// 计算从现在开始的过期时间，过期的时刻已经过去时返回`0`。
+    ///
+    /// # Output
+    /// 如果数值不是正数，或者换算成毫秒后超出`i64`的范围，返回`None`。
+    fn duration(self) -> Option<Duration> {
+        let (n, scale) = match self {
+            Expiration::Seconds(n) | Expiration::UnixSeconds(n) => (n, 1000),
+            Expiration::Millis(n) | Expiration::UnixMillis(n) => (n, 1),
+        };
+        if n <= 0 {
+            return None;
+        }
+        let millis = n.checked_mul(scale)? as u64;
+        match self {
+            Expiration::Seconds(_) | Expiration::Millis(_) => Some(Duration::from_millis(millis)),
+            Expiration::UnixSeconds(_) | Expiration::UnixMillis(_) => {
+                let when = UNIX_EPOCH + Duration::from_millis(millis);
+                Some(
+                    when.duration_since(SystemTime::now())
+                        .unwrap_or(Duration::ZERO),
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,475 @@
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse, ParseError, SetOp};
+
+/// 向集合中添加成员，返回新增的成员的数量。
+///
+/// 格式：SAdd <key> <member> [<member> ...]
+///
+/// 已经存在的成员会被忽略。如果 key 不存在，会先创建一个空的集合。
+#[derive(Debug)]
+pub struct SAdd {
+    key: String,
+    members: Vec<Bytes>,
+}
+
+/// 从集合中删除成员，返回被删除的成员的数量。
+///
+/// 格式：SRem <key> <member> [<member> ...]
+///
+/// 不存在的成员会被忽略，集合被删空后 key 也会被删除。
+#[derive(Debug)]
+pub struct SRem {
+    key: String,
+    members: Vec<Bytes>,
+}
+
+/// 获取集合中所有的成员。
+///
+/// 格式：SMembers <key>
+///
+/// 成员以`Set`返回，RESP2 的连接中为数组。如果 key 不存在，返回空集合。
+#[derive(Debug)]
+pub struct SMembers {
+    key: String,
+}
+
+/// 判断是否是集合的成员，是返回`1`，否则返回`0`。
+///
+/// 格式：SIsMember <key> <member>
+#[derive(Debug)]
+pub struct SIsMember {
+    key: String,
+    member: Bytes,
+}
+
+/// 获取集合的成员数量。
+///
+/// 格式：SCard <key>
+///
+/// 如果 key 不存在，返回`0`。
+#[derive(Debug)]
+pub struct SCard {
+    key: String,
+}
+
+impl SAdd {
+    /// 创建一个`SAdd`命令。
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> SAdd {
+        SAdd {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`SAdd`命令。
+    ///
+    /// 需要保证字符串`SAdd`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SAdd> {
+        let key = parse.next_string()?;
+        let mut members = vec![];
+        while parse.remaining() > 0 {
+            members.push(parse.next_bytes()?);
+        }
+        Ok(SAdd { key, members })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.sadd(&self.key, self.members) {
+            Ok(added) => Frame::Integer(added as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("sadd".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for member in self.members {
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl SRem {
+    /// 创建一个`SRem`命令。
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> SRem {
+        SRem {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`SRem`命令。
+    ///
+    /// 需要保证字符串`SRem`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SRem> {
+        let key = parse.next_string()?;
+        let mut members = vec![];
+        while parse.remaining() > 0 {
+            members.push(parse.next_bytes()?);
+        }
+        Ok(SRem { key, members })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.srem(&self.key, &self.members) {
+            Ok(removed) => Frame::Integer(removed as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("srem".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for member in self.members {
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl SMembers {
+    /// 创建一个`SMembers`命令。
+    pub fn new(key: impl ToString) -> SMembers {
+        SMembers {
+            key: key.to_string(),
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`SMembers`命令。
+    ///
+    /// 需要保证字符串`SMembers`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SMembers> {
+        let key = parse.next_string()?;
+        Ok(SMembers { key })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.smembers(&self.key) {
+            Ok(members) => Frame::Set(members.into_iter().map(Frame::Bulk).collect()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("smembers".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl SIsMember {
+    /// 创建一个`SIsMember`命令。
+    pub fn new(key: impl ToString, member: Bytes) -> SIsMember {
+        SIsMember {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`SIsMember`命令。
+    ///
+    /// 需要保证字符串`SIsMember`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SIsMember> {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+        Ok(SIsMember { key, member })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.sismember(&self.key, &self.member) {
+            Ok(is_member) => Frame::Integer(is_member as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("sismember".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
+
+impl SCard {
+    /// 创建一个`SCard`命令。
+    pub fn new(key: impl ToString) -> SCard {
+        SCard {
+            key: key.to_string(),
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`SCard`命令。
+    ///
+    /// 需要保证字符串`SCard`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SCard> {
+        let key = parse.next_string()?;
+        Ok(SCard { key })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.scard(&self.key) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("scard".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+/// 随机获取集合中的成员。
+///
+/// 格式：SRandMember <key> [<count>]
+///
+/// 没有`count`时返回一个成员，key 不存在时返回`(nil)`。
+/// `count`为正数时返回最多`count`个不重复的成员；为负数时返回`-count`个成员，同一个成员可能出现多次。
+#[derive(Debug)]
+pub struct SRandMember {
+    key: String,
+    count: Option<i64>,
+}
+
+impl SRandMember {
+    /// 创建一个`SRandMember`命令，`count`为`None`时只获取一个成员。
+    pub fn new(key: impl ToString, count: Option<i64>) -> SRandMember {
+        SRandMember {
+            key: key.to_string(),
+            count,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`SRandMember`命令。
+    ///
+    /// 需要保证字符串`SRandMember`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SRandMember> {
+        let key = parse.next_string()?;
+        let count = match parse.next_signed_int() {
+            Ok(count) => Some(count),
+            Err(ParseError::EndOfStream) => None,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(SRandMember { key, count })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.srandmember(&self.key, self.count.unwrap_or(1)) {
+            Ok(members) if self.count.is_some() => {
+                Frame::Array(members.into_iter().map(Frame::Bulk).collect())
+            }
+            Ok(members) => match members.into_iter().next() {
+                Some(member) => Frame::Bulk(member),
+                None => Frame::Null,
+            },
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("srandmember".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from(count.to_string()));
+        }
+        frame
+    }
+}
+
+/// 多个集合之间的交集、并集和差集运算。
+///
+/// 格式：SInter <key> [<key> ...]
+///      SUnion <key> [<key> ...]
+///      SDiff <key> [<key> ...]
+///      SInterStore <destination> <key> [<key> ...]
+///      SUnionStore <destination> <key> [<key> ...]
+///      SDiffStore <destination> <key> [<key> ...]
+///
+/// 不存在的 key 视为空集合。不带`Store`的命令返回结果集合的所有成员；
+/// 带`Store`的命令将结果保存到`destination`中（覆盖原有的 value），返回结果集合的成员数量。
+#[derive(Debug)]
+pub struct SetAlgebra {
+    op: SetOp,
+    keys: Vec<String>,
+    // 保存结果的 key，为`None`时直接返回结果。
+    destination: Option<String>,
+}
+
+impl SetAlgebra {
+    /// 创建一个`SInter`命令。
+    pub fn inter(keys: Vec<String>) -> SetAlgebra {
+        SetAlgebra::with_op(SetOp::Inter, keys, None)
+    }
+
+    /// 创建一个`SUnion`命令。
+    pub fn union(keys: Vec<String>) -> SetAlgebra {
+        SetAlgebra::with_op(SetOp::Union, keys, None)
+    }
+
+    /// 创建一个`SDiff`命令。
+    pub fn diff(keys: Vec<String>) -> SetAlgebra {
+        SetAlgebra::with_op(SetOp::Diff, keys, None)
+    }
+
+    /// 创建一个`SInterStore`命令。
+    pub fn inter_store(destination: impl ToString, keys: Vec<String>) -> SetAlgebra {
+        SetAlgebra::with_op(SetOp::Inter, keys, Some(destination.to_string()))
+    }
+
+    /// 创建一个`SUnionStore`命令。
+    pub fn union_store(destination: impl ToString, keys: Vec<String>) -> SetAlgebra {
+        SetAlgebra::with_op(SetOp::Union, keys, Some(destination.to_string()))
+    }
+
+    /// 创建一个`SDiffStore`命令。
+    pub fn diff_store(destination: impl ToString, keys: Vec<String>) -> SetAlgebra {
+        SetAlgebra::with_op(SetOp::Diff, keys, Some(destination.to_string()))
+    }
+
+    fn with_op(op: SetOp, keys: Vec<String>, destination: Option<String>) -> SetAlgebra {
+        SetAlgebra {
+            op,
+            keys,
+            destination,
+        }
+    }
+
+    /// 获取参与运算的 key。
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// 获取保存结果的 key。
+    pub fn destination(&self) -> Option<&str> {
+        self.destination.as_deref()
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        match (self.op, self.destination.is_some()) {
+            (SetOp::Inter, false) => "sinter",
+            (SetOp::Union, false) => "sunion",
+            (SetOp::Diff, false) => "sdiff",
+            (SetOp::Inter, true) => "sinterstore",
+            (SetOp::Union, true) => "sunionstore",
+            (SetOp::Diff, true) => "sdiffstore",
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`SetAlgebra`命令，`name`为全小写的命令名称。
+    ///
+    /// 需要保证命令名称已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse, name: &str) -> crate::Result<SetAlgebra> {
+        let (op, store) = match name {
+            "sinter" => (SetOp::Inter, false),
+            "sunion" => (SetOp::Union, false),
+            "sdiff" => (SetOp::Diff, false),
+            "sinterstore" => (SetOp::Inter, true),
+            "sunionstore" => (SetOp::Union, true),
+            "sdiffstore" => (SetOp::Diff, true),
+            _ => unreachable!(),
+        };
+        let destination = if store {
+            Some(parse.next_string()?)
+        } else {
+            None
+        };
+        let mut keys = vec![];
+        while parse.remaining() > 0 {
+            keys.push(parse.next_string()?);
+        }
+        Ok(SetAlgebra::with_op(op, keys, destination))
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match &self.destination {
+            Some(destination) => match db.combine_sets_store(self.op, destination, &self.keys) {
+                Ok(len) => Frame::Integer(len as i64),
+                Err(err) => Frame::Error(err.to_string()),
+            },
+            None => match db.combine_sets(self.op, &self.keys) {
+                Ok(members) => Frame::Set(members.into_iter().map(Frame::Bulk).collect()),
+                Err(err) => Frame::Error(err.to_string()),
+            },
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().to_string()));
+        if let Some(destination) = self.destination {
+            frame.push_bulk(Bytes::from(destination.into_bytes()));
+        }
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        frame
+    }
+}
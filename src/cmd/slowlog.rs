@@ -0,0 +1,114 @@
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse, ParseError};
+
+/// 读取或清空慢命令日志。
+///
+/// 格式：Slowlog Get [count]
+///      Slowlog Len
+///      Slowlog Reset
+///
+/// `Get`默认返回最新的 10 条记录，每条记录的格式为：
+/// `[ id, timestamp, duration(us), [ command, args... ] ]`。
+#[derive(Debug)]
+pub struct Slowlog {
+    op: SlowlogOp,
+}
+
+#[derive(Debug)]
+enum SlowlogOp {
+    Get(usize),
+    Len,
+    Reset,
+}
+
+/// `Slowlog Get`默认返回的记录条数。
+const DEFAULT_COUNT: usize = 10;
+
+impl Slowlog {
+    /// 创建一个`Slowlog Get`命令，`count`为`None`时返回最新的 10 条记录。
+    pub fn get(count: Option<usize>) -> Slowlog {
+        Slowlog {
+            op: SlowlogOp::Get(count.unwrap_or(DEFAULT_COUNT)),
+        }
+    }
+
+    /// 创建一个`Slowlog Len`命令。
+    pub fn len() -> Slowlog {
+        Slowlog { op: SlowlogOp::Len }
+    }
+
+    /// 创建一个`Slowlog Reset`命令。
+    pub fn reset() -> Slowlog {
+        Slowlog {
+            op: SlowlogOp::Reset,
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Slowlog`命令。
+    ///
+    /// `Parse`提供了类似迭代器的 API 来解析`Frame`。
+    /// 需要保证字符串`Slowlog`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Slowlog> {
+        let op = match &parse.next_string()?.to_lowercase()[..] {
+            "get" => match parse.next_int() {
+                Ok(count) => SlowlogOp::Get(count as usize),
+                Err(ParseError::EndOfStream) => SlowlogOp::Get(DEFAULT_COUNT),
+                Err(err) => return Err(err.into()),
+            },
+            "len" => SlowlogOp::Len,
+            "reset" => SlowlogOp::Reset,
+            sub => {
+                return Err(crate::Error::Parse(format!(
+                    "未知的 Slowlog 子命令：'{}'",
+                    sub
+                )))
+            }
+        };
+        Ok(Slowlog { op })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.op {
+            SlowlogOp::Get(count) => {
+                let entries = db
+                    .slowlog()
+                    .get(count)
+                    .into_iter()
+                    .map(|entry| {
+                        Frame::Array(vec![
+                            Frame::Integer(entry.id as i64),
+                            Frame::Integer(entry.timestamp as i64),
+                            Frame::Integer(entry.duration.as_micros() as i64),
+                            Frame::Array(entry.args.into_iter().map(Frame::Bulk).collect()),
+                        ])
+                    })
+                    .collect();
+                Frame::Array(entries)
+            }
+            SlowlogOp::Len => Frame::Integer(db.slowlog().len() as i64),
+            SlowlogOp::Reset => {
+                db.slowlog().reset();
+                Frame::Simple("OK".to_string())
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("slowlog".as_bytes()));
+        match self.op {
+            SlowlogOp::Get(count) => {
+                frame.push_bulk(Bytes::from("get".as_bytes()));
+                frame.push_bulk(Bytes::from(count.to_string()));
+            }
+            SlowlogOp::Len => frame.push_bulk(Bytes::from("len".as_bytes())),
+            SlowlogOp::Reset => frame.push_bulk(Bytes::from("reset".as_bytes())),
+        }
+        frame
+    }
+}
@@ -0,0 +1,892 @@
+use std::{future, ops::Bound, time::Duration};
+
+use bytes::Bytes;
+use tokio::time;
+
+use crate::{
+    shutdown::Shutdown,
+    stream::{Entries, GroupRead, NewId, StreamId},
+    Connection, Db, Frame, Parse, ParseError,
+};
+
+/// ID 格式错误时返回的错误信息。
+const INVALID_ID: &str = "ERR Invalid stream ID specified as stream command argument";
+
+/// 向流中添加一条消息，返回消息的 ID。
+///
+/// 格式：XAdd <key> <id> <field> <value> [<field> <value> ...]
+///
+/// `id`为`*`时自动生成，为`<ms>-*`时只自动生成序号，否则必须大于流中最后一条消息的 ID。
+/// 如果 key 不存在，会先创建一个空的流。
+#[derive(Debug)]
+pub struct XAdd {
+    key: String,
+    id: String,
+    fields: Vec<(Bytes, Bytes)>,
+}
+
+/// 获取流中 ID 在`[start, end]`之间的消息。
+///
+/// 格式：XRange <key> <start> <end> [COUNT <count>]
+///
+/// `-`和`+`分别表示最小和最大的 ID，在 ID 前加上`(`表示不包含边界。
+/// 省略序号时，`start`的序号视为`0`，`end`的序号视为最大值。
+#[derive(Debug)]
+pub struct XRange {
+    key: String,
+    start: String,
+    end: String,
+    count: Option<u64>,
+}
+
+/// 获取流中消息的数量。
+///
+/// 格式：XLen <key>
+///
+/// 如果 key 不存在，返回`0`。
+#[derive(Debug)]
+pub struct XLen {
+    key: String,
+}
+
+/// 读取一个或多个流中 ID 大于指定 ID 的消息。
+///
+/// 格式：XRead [COUNT <count>] [BLOCK <milliseconds>] STREAMS <key> [<key> ...] <id> [<id> ...]
+///
+/// `id`为`$`时表示流中当前最后一条消息的 ID，即只读取之后添加的消息。
+/// 如果所有流中都没有新消息，设置了`BLOCK`时会阻塞直到有新消息被添加或者超时，
+/// 超时或者没有设置`BLOCK`时返回`(nil)`。`BLOCK 0`表示一直阻塞。
+#[derive(Debug)]
+pub struct XRead {
+    keys: Vec<String>,
+    ids: Vec<String>,
+    count: Option<u64>,
+    // 阻塞的时间，为`Some(Duration::ZERO)`时一直阻塞。
+    block: Option<Duration>,
+}
+
+/// 管理流上的消费者组。
+///
+/// 格式：XGroup Create <key> <group> <id> [MKSTREAM]
+///      XGroup Destroy <key> <group>
+///
+/// `Create`创建消费者组，组内的消费者从 ID 大于`id`的消息开始读取，`id`为`$`时
+/// 只读取之后添加的消息；设置了`MKSTREAM`时，如果 key 不存在会先创建一个空的流。
+/// `Destroy`删除消费者组，返回删除的组的数量。
+#[derive(Debug)]
+pub struct XGroup {
+    key: String,
+    group: String,
+    op: XGroupOp,
+}
+
+#[derive(Debug)]
+enum XGroupOp {
+    Create { id: String, mkstream: bool },
+    Destroy,
+}
+
+/// 以消费者组中某个消费者的身份读取流中的消息。
+///
+/// 格式：XReadGroup GROUP <group> <consumer> [COUNT <count>] [BLOCK <milliseconds>]
+///      STREAMS <key> [<key> ...] <id> [<id> ...]
+///
+/// `id`为`>`时读取还没有投递给组内任何消费者的新消息，这些消息会被记录到待确认列表中，
+/// 直到被`XAck`确认；否则读取已经投递给这个消费者、ID 大于`id`且还没有被确认的消息，
+/// 消费者重新连接后可以用`0`读取所有还没有处理完的消息。
+/// 只有读取新消息时`BLOCK`才会生效，规则与`XRead`相同。
+#[derive(Debug)]
+pub struct XReadGroup {
+    group: String,
+    consumer: String,
+    keys: Vec<String>,
+    ids: Vec<String>,
+    count: Option<u64>,
+    // 阻塞的时间，为`Some(Duration::ZERO)`时一直阻塞。
+    block: Option<Duration>,
+}
+
+/// 确认消费者组中的消息已经被处理，返回被确认的消息的数量。
+///
+/// 格式：XAck <key> <group> <id> [<id> ...]
+///
+/// 被确认的消息会从待确认列表中删除，不在待确认列表中的 ID 会被忽略。
+#[derive(Debug)]
+pub struct XAck {
+    key: String,
+    group: String,
+    ids: Vec<String>,
+}
+
+/// 将消费者组中空闲时间足够长的待确认消息转移给另一个消费者，返回被认领的消息。
+///
+/// 格式：XClaim <key> <group> <consumer> <min-idle-time> <id> [<id> ...]
+///
+/// `min-idle-time`的单位为毫秒，只有距离上次投递超过这个时间的消息才会被认领，
+/// 被认领的消息的投递次数加一。这可以让其他消费者接手已经下线的消费者的消息。
+#[derive(Debug)]
+pub struct XClaim {
+    key: String,
+    group: String,
+    consumer: String,
+    min_idle: Duration,
+    ids: Vec<String>,
+}
+
+impl XAdd {
+    /// 创建一个`XAdd`命令。
+    pub fn new(key: impl ToString, id: impl ToString, fields: Vec<(Bytes, Bytes)>) -> XAdd {
+        XAdd {
+            key: key.to_string(),
+            id: id.to_string(),
+            fields,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`XAdd`命令。
+    ///
+    /// 需要保证字符串`XAdd`已经被处理过了，并且字段和值是成对的。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XAdd> {
+        let key = parse.next_string()?;
+        let id = parse.next_string()?;
+        let mut fields = vec![];
+        while parse.remaining() > 0 {
+            let field = parse.next_bytes()?;
+            let value = parse.next_bytes()?;
+            fields.push((field, value));
+        }
+        Ok(XAdd { key, id, fields })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match NewId::parse(&self.id) {
+            Some(id) => match db.xadd(&self.key, id, self.fields) {
+                Ok(id) => Frame::Bulk(Bytes::from(id.to_string())),
+                Err(err) => Frame::Error(err.to_string()),
+            },
+            None => Frame::Error(INVALID_ID.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xadd".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.id.into_bytes()));
+        for (field, value) in self.fields {
+            frame.push_bulk(field);
+            frame.push_bulk(value);
+        }
+        frame
+    }
+}
+
+impl XRange {
+    /// 创建一个`XRange`命令，`count`为`None`时返回所有消息。
+    pub fn new(
+        key: impl ToString,
+        start: impl ToString,
+        end: impl ToString,
+        count: Option<u64>,
+    ) -> XRange {
+        XRange {
+            key: key.to_string(),
+            start: start.to_string(),
+            end: end.to_string(),
+            count,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`XRange`命令。
+    ///
+    /// 需要保证字符串`XRange`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XRange> {
+        let key = parse.next_string()?;
+        let start = parse.next_string()?;
+        let end = parse.next_string()?;
+        let count = match parse.next_string() {
+            Ok(option) if option.eq_ignore_ascii_case("count") => Some(parse.next_int()?),
+            Ok(_) => return Err(crate::Error::invalid("syntax error")),
+            Err(ParseError::EndOfStream) => None,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(XRange {
+            key,
+            start,
+            end,
+            count,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let start = parse_bound(&self.start, "-", 0);
+        let end = parse_bound(&self.end, "+", u64::MAX);
+        let response = match (start, end) {
+            (Some(start), Some(end)) => {
+                let count = self.count.map(|count| count as usize);
+                match db.xrange(&self.key, start, end, count) {
+                    Ok(entries) => entries_frame(entries),
+                    Err(err) => Frame::Error(err.to_string()),
+                }
+            }
+            _ => Frame::Error(INVALID_ID.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xrange".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.start.into_bytes()));
+        frame.push_bulk(Bytes::from(self.end.into_bytes()));
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_bulk(Bytes::from(count.to_string()));
+        }
+        frame
+    }
+}
+
+impl XLen {
+    /// 创建一个`XLen`命令。
+    pub fn new(key: impl ToString) -> XLen {
+        XLen {
+            key: key.to_string(),
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`XLen`命令。
+    ///
+    /// 需要保证字符串`XLen`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XLen> {
+        let key = parse.next_string()?;
+        Ok(XLen { key })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.xlen(&self.key) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xlen".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl XRead {
+    /// 创建一个`XRead`命令，`streams`中的每一项为 key 和 ID。
+    ///
+    /// `block`为`None`时不阻塞，为`Some(Duration::ZERO)`时一直阻塞。
+    pub fn new(
+        streams: Vec<(String, String)>,
+        count: Option<u64>,
+        block: Option<Duration>,
+    ) -> XRead {
+        let (keys, ids) = streams.into_iter().unzip();
+        XRead {
+            keys,
+            ids,
+            count,
+            block,
+        }
+    }
+
+    /// 获取所有 key。
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// 是否设置了`BLOCK`。
+    pub(crate) fn is_blocking(&self) -> bool {
+        self.block.is_some()
+    }
+
+    /// 通过`Parse`将`Frame`解析为`XRead`命令。
+    ///
+    /// 需要保证字符串`XRead`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XRead> {
+        let mut count = None;
+        let mut block = None;
+        loop {
+            let option = parse.next_string()?.to_lowercase();
+            match &option[..] {
+                "count" => count = Some(parse.next_int()?),
+                "block" => block = Some(Duration::from_millis(parse.next_int()?)),
+                "streams" => break,
+                _ => return Err(crate::Error::invalid("syntax error")),
+            }
+        }
+
+        let remaining = parse.remaining();
+        if remaining == 0 || !remaining.is_multiple_of(2) {
+            return Err(crate::Error::invalid("Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified."));
+        }
+        let mut keys = vec![];
+        for _ in 0..remaining / 2 {
+            keys.push(parse.next_string()?);
+        }
+        let mut ids = vec![];
+        for _ in 0..remaining / 2 {
+            ids.push(parse.next_string()?);
+        }
+
+        Ok(XRead {
+            keys,
+            ids,
+            count,
+            block,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 阻塞期间如果服务器关闭，直接返回，不会写回响应。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        // 在开始阻塞之前确定`$`对应的 ID。
+        let mut ids = Vec::with_capacity(self.ids.len());
+        for (key, id) in self.keys.iter().zip(&self.ids) {
+            let id = if id == "$" {
+                db.xlast_id(key)
+            } else {
+                StreamId::parse(id, 0).ok_or_else(|| INVALID_ID.into())
+            };
+            match id {
+                Ok(id) => ids.push(id),
+                Err(err) => {
+                    dst.write_frame(&Frame::Error(err.to_string())).await?;
+                    return Ok(());
+                }
+            }
+        }
+        let count = self.count.map(|count| count as usize);
+
+        let result = match self.block {
+            None => db.xread(&self.keys, &ids, count),
+            Some(block) => {
+                let timeout = async {
+                    if block.is_zero() {
+                        future::pending().await
+                    } else {
+                        time::sleep(block).await
+                    }
+                };
+                tokio::select! {
+                    res = db.blocking_xread(&self.keys, &ids, count) => res,
+                    // 超时。
+                    _ = timeout => Ok(vec![]),
+                    _ = shutdown.recv() => return Ok(()),
+                }
+            }
+        };
+
+        let response = match result {
+            Ok(streams) => streams_frame(streams),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xread".as_bytes()));
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_bulk(Bytes::from(count.to_string()));
+        }
+        if let Some(block) = self.block {
+            frame.push_bulk(Bytes::from("block".as_bytes()));
+            frame.push_bulk(Bytes::from(block.as_millis().to_string()));
+        }
+        frame.push_bulk(Bytes::from("streams".as_bytes()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        for id in self.ids {
+            frame.push_bulk(Bytes::from(id.into_bytes()));
+        }
+        frame
+    }
+}
+
+impl XGroup {
+    /// 创建一个`XGroup Create`命令，`id`为`$`时只读取之后添加的消息。
+    pub fn create(
+        key: impl ToString,
+        group: impl ToString,
+        id: impl ToString,
+        mkstream: bool,
+    ) -> XGroup {
+        XGroup {
+            key: key.to_string(),
+            group: group.to_string(),
+            op: XGroupOp::Create {
+                id: id.to_string(),
+                mkstream,
+            },
+        }
+    }
+
+    /// 创建一个`XGroup Destroy`命令。
+    pub fn destroy(key: impl ToString, group: impl ToString) -> XGroup {
+        XGroup {
+            key: key.to_string(),
+            group: group.to_string(),
+            op: XGroupOp::Destroy,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`XGroup`命令。
+    ///
+    /// 需要保证字符串`XGroup`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XGroup> {
+        let sub = parse.next_string()?.to_lowercase();
+        let key = parse.next_string()?;
+        let group = parse.next_string()?;
+        let op = match &sub[..] {
+            "create" => {
+                let id = parse.next_string()?;
+                let mkstream = match parse.next_string() {
+                    Ok(option) if option.eq_ignore_ascii_case("mkstream") => true,
+                    Ok(_) => return Err(crate::Error::invalid("syntax error")),
+                    Err(ParseError::EndOfStream) => false,
+                    Err(err) => return Err(err.into()),
+                };
+                XGroupOp::Create { id, mkstream }
+            }
+            "destroy" => XGroupOp::Destroy,
+            sub => {
+                return Err(crate::Error::Parse(format!(
+                    "未知的 XGroup 子命令：'{}'",
+                    sub
+                )))
+            }
+        };
+        Ok(XGroup { key, group, op })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.op {
+            XGroupOp::Create { id, mkstream } => {
+                let id = if id == "$" {
+                    Ok(None)
+                } else {
+                    StreamId::parse(&id, 0).map(Some).ok_or(INVALID_ID)
+                };
+                match id {
+                    Ok(id) => match db.xgroup_create(&self.key, &self.group, id, mkstream) {
+                        Ok(()) => Frame::Simple("OK".to_string()),
+                        Err(err) => Frame::Error(err.to_string()),
+                    },
+                    Err(err) => Frame::Error(err.to_string()),
+                }
+            }
+            XGroupOp::Destroy => match db.xgroup_destroy(&self.key, &self.group) {
+                Ok(destroyed) => Frame::Integer(destroyed as i64),
+                Err(err) => Frame::Error(err.to_string()),
+            },
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xgroup".as_bytes()));
+        match self.op {
+            XGroupOp::Create { id, mkstream } => {
+                frame.push_bulk(Bytes::from("create".as_bytes()));
+                frame.push_bulk(Bytes::from(self.key.into_bytes()));
+                frame.push_bulk(Bytes::from(self.group.into_bytes()));
+                frame.push_bulk(Bytes::from(id.into_bytes()));
+                if mkstream {
+                    frame.push_bulk(Bytes::from("mkstream".as_bytes()));
+                }
+            }
+            XGroupOp::Destroy => {
+                frame.push_bulk(Bytes::from("destroy".as_bytes()));
+                frame.push_bulk(Bytes::from(self.key.into_bytes()));
+                frame.push_bulk(Bytes::from(self.group.into_bytes()));
+            }
+        }
+        frame
+    }
+}
+
+impl XReadGroup {
+    /// 创建一个`XReadGroup`命令，`streams`中的每一项为 key 和 ID。
+    ///
+    /// `block`为`None`时不阻塞，为`Some(Duration::ZERO)`时一直阻塞。
+    pub fn new(
+        group: impl ToString,
+        consumer: impl ToString,
+        streams: Vec<(String, String)>,
+        count: Option<u64>,
+        block: Option<Duration>,
+    ) -> XReadGroup {
+        let (keys, ids) = streams.into_iter().unzip();
+        XReadGroup {
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            keys,
+            ids,
+            count,
+            block,
+        }
+    }
+
+    /// 获取所有 key。
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// 是否设置了`BLOCK`。
+    pub(crate) fn is_blocking(&self) -> bool {
+        self.block.is_some()
+    }
+
+    /// 通过`Parse`将`Frame`解析为`XReadGroup`命令。
+    ///
+    /// 需要保证字符串`XReadGroup`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XReadGroup> {
+        if !parse.next_string()?.eq_ignore_ascii_case("group") {
+            return Err(crate::Error::invalid("syntax error"));
+        }
+        let group = parse.next_string()?;
+        let consumer = parse.next_string()?;
+
+        let mut count = None;
+        let mut block = None;
+        loop {
+            let option = parse.next_string()?.to_lowercase();
+            match &option[..] {
+                "count" => count = Some(parse.next_int()?),
+                "block" => block = Some(Duration::from_millis(parse.next_int()?)),
+                "streams" => break,
+                _ => return Err(crate::Error::invalid("syntax error")),
+            }
+        }
+
+        let remaining = parse.remaining();
+        if remaining == 0 || !remaining.is_multiple_of(2) {
+            return Err(crate::Error::invalid("Unbalanced 'xreadgroup' list of streams: for each stream key an ID or '>' must be specified."));
+        }
+        let mut keys = vec![];
+        for _ in 0..remaining / 2 {
+            keys.push(parse.next_string()?);
+        }
+        let mut ids = vec![];
+        for _ in 0..remaining / 2 {
+            ids.push(parse.next_string()?);
+        }
+
+        Ok(XReadGroup {
+            group,
+            consumer,
+            keys,
+            ids,
+            count,
+            block,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 阻塞期间如果服务器关闭，直接返回，不会写回响应。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let mut from = Vec::with_capacity(self.ids.len());
+        for id in &self.ids {
+            if id == ">" {
+                from.push(GroupRead::New);
+            } else if let Some(id) = StreamId::parse(id, 0) {
+                from.push(GroupRead::History(id));
+            } else {
+                dst.write_frame(&Frame::Error(INVALID_ID.to_string()))
+                    .await?;
+                return Ok(());
+            }
+        }
+        let count = self.count.map(|count| count as usize);
+        let (group, consumer, keys) = (&self.group, &self.consumer, &self.keys);
+
+        let result = match self.block {
+            None => db.xreadgroup(group, consumer, keys, &from, count),
+            Some(block) => {
+                let timeout = async {
+                    if block.is_zero() {
+                        future::pending().await
+                    } else {
+                        time::sleep(block).await
+                    }
+                };
+                tokio::select! {
+                    res = db.blocking_xreadgroup(group, consumer, keys, &from, count) => res,
+                    // 超时。
+                    _ = timeout => Ok(vec![]),
+                    _ = shutdown.recv() => return Ok(()),
+                }
+            }
+        };
+
+        let response = match result {
+            Ok(streams) => streams_frame(streams),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xreadgroup".as_bytes()));
+        frame.push_bulk(Bytes::from("group".as_bytes()));
+        frame.push_bulk(Bytes::from(self.group.into_bytes()));
+        frame.push_bulk(Bytes::from(self.consumer.into_bytes()));
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_bulk(Bytes::from(count.to_string()));
+        }
+        if let Some(block) = self.block {
+            frame.push_bulk(Bytes::from("block".as_bytes()));
+            frame.push_bulk(Bytes::from(block.as_millis().to_string()));
+        }
+        frame.push_bulk(Bytes::from("streams".as_bytes()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        for id in self.ids {
+            frame.push_bulk(Bytes::from(id.into_bytes()));
+        }
+        frame
+    }
+}
+
+impl XAck {
+    /// 创建一个`XAck`命令。
+    pub fn new(key: impl ToString, group: impl ToString, ids: Vec<String>) -> XAck {
+        XAck {
+            key: key.to_string(),
+            group: group.to_string(),
+            ids,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`XAck`命令。
+    ///
+    /// 需要保证字符串`XAck`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XAck> {
+        let key = parse.next_string()?;
+        let group = parse.next_string()?;
+        let mut ids = vec![];
+        while parse.remaining() > 0 {
+            ids.push(parse.next_string()?);
+        }
+        Ok(XAck { key, group, ids })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match parse_ids(&self.ids) {
+            Some(ids) => match db.xack(&self.key, &self.group, &ids) {
+                Ok(acked) => Frame::Integer(acked as i64),
+                Err(err) => Frame::Error(err.to_string()),
+            },
+            None => Frame::Error(INVALID_ID.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xack".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.group.into_bytes()));
+        for id in self.ids {
+            frame.push_bulk(Bytes::from(id.into_bytes()));
+        }
+        frame
+    }
+}
+
+impl XClaim {
+    /// 创建一个`XClaim`命令。
+    pub fn new(
+        key: impl ToString,
+        group: impl ToString,
+        consumer: impl ToString,
+        min_idle: Duration,
+        ids: Vec<String>,
+    ) -> XClaim {
+        XClaim {
+            key: key.to_string(),
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            min_idle,
+            ids,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`XClaim`命令。
+    ///
+    /// 需要保证字符串`XClaim`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XClaim> {
+        let key = parse.next_string()?;
+        let group = parse.next_string()?;
+        let consumer = parse.next_string()?;
+        let min_idle = Duration::from_millis(parse.next_int()?);
+        let mut ids = vec![];
+        while parse.remaining() > 0 {
+            ids.push(parse.next_string()?);
+        }
+        Ok(XClaim {
+            key,
+            group,
+            consumer,
+            min_idle,
+            ids,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match parse_ids(&self.ids) {
+            Some(ids) => {
+                match db.xclaim(&self.key, &self.group, &self.consumer, self.min_idle, &ids) {
+                    Ok(entries) => entries_frame(entries),
+                    Err(err) => Frame::Error(err.to_string()),
+                }
+            }
+            None => Frame::Error(INVALID_ID.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xclaim".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.group.into_bytes()));
+        frame.push_bulk(Bytes::from(self.consumer.into_bytes()));
+        frame.push_bulk(Bytes::from(self.min_idle.as_millis().to_string()));
+        for id in self.ids {
+            frame.push_bulk(Bytes::from(id.into_bytes()));
+        }
+        frame
+    }
+}
+
+/// 解析多个完整的 ID，如果有任何一个 ID 不合法，返回`None`。
+fn parse_ids(ids: &[String]) -> Option<Vec<StreamId>> {
+    ids.iter().map(|id| StreamId::parse(id, 0)).collect()
+}
+
+/// 解析`XRange`的边界，`unbounded`表示没有边界，省略序号时使用`default_seq`。
+fn parse_bound(src: &str, unbounded: &str, default_seq: u64) -> Option<Bound<StreamId>> {
+    if src == unbounded {
+        return Some(Bound::Unbounded);
+    }
+    match src.strip_prefix('(') {
+        Some(id) => StreamId::parse(id, default_seq).map(Bound::Excluded),
+        None => StreamId::parse(src, default_seq).map(Bound::Included),
+    }
+}
+
+/// 将多个流中的消息编码为数组，每一项为 key 和消息组成的数组。如果没有任何流，返回`(nil)`。
+fn streams_frame(streams: Vec<(String, Entries)>) -> Frame {
+    if streams.is_empty() {
+        return Frame::Null;
+    }
+    Frame::Array(
+        streams
+            .into_iter()
+            .map(|(key, entries)| {
+                Frame::Array(vec![
+                    Frame::Bulk(Bytes::from(key.into_bytes())),
+                    entries_frame(entries),
+                ])
+            })
+            .collect(),
+    )
+}
+
+/// 将消息编码为数组，每条消息为 ID 和字段、值交替出现的数组组成的数组。
+fn entries_frame(entries: Entries) -> Frame {
+    Frame::Array(
+        entries
+            .into_iter()
+            .map(|(id, fields)| {
+                let mut items = Frame::array();
+                for (field, value) in fields {
+                    items.push_bulk(field);
+                    items.push_bulk(value);
+                }
+                Frame::Array(vec![Frame::Bulk(Bytes::from(id.to_string())), items])
+            })
+            .collect(),
+    )
+}
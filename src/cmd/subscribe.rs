@@ -4,23 +4,102 @@ use bytes::Bytes;
 use tokio::sync::broadcast;
 use tokio_stream::{Stream, StreamExt, StreamMap};
 
-use crate::{db::Db, shutdown::Shutdown, Connection, Frame, Parse};
+use crate::{
+    db::Db, interceptor::ConnContext, shutdown::Shutdown, Command, Connection, Frame, Parse,
+    ParseError,
+};
 
 /// 订阅一个或多个广播信道。
 ///
 /// 格式：Subscribe <channel> [<channel> ...]
 ///
-/// 进入订阅者模式后，客户端无法进行除了退出以外的其他操作。
+/// 进入订阅者模式后，客户端只能继续订阅或者退订信道和模式，或者执行`Ping`和`Reset`，
+/// 其他命令会返回错误信息。退订了所有信道和模式，或者执行了`Reset`后，客户端退出订阅者模式。
 #[derive(Debug)]
 pub struct Subscribe {
     channels: Vec<String>,
 }
 
+/// 退订一个或多个广播信道。
+///
+/// 格式：Unsubscribe [<channel> ...]
+///
+/// 没有指定信道时退订所有信道。对每个信道都会响应`["unsubscribe", channel, remaining]`，
+/// 其中`remaining`为剩余的订阅数量，包括订阅的模式。
+#[derive(Debug)]
+pub struct Unsubscribe {
+    channels: Vec<String>,
+}
+
+/// 按照 glob 模式订阅一个或多个广播信道。
+///
+/// 格式：PSubscribe <pattern> [<pattern> ...]
+///
+/// 向名称匹配模式的信道发布的信息会以`["pmessage", pattern, channel, message]`的格式发送给客户端。
+/// 进入订阅者模式后的规则与`Subscribe`相同。
+#[derive(Debug)]
+pub struct PSubscribe {
+    patterns: Vec<String>,
+}
+
+/// 退订一个或多个模式。
+///
+/// 格式：PUnsubscribe [<pattern> ...]
+///
+/// 没有指定模式时退订所有模式，响应的格式与`Unsubscribe`相同。
+#[derive(Debug)]
+pub struct PUnsubscribe {
+    patterns: Vec<String>,
+}
+
+/// 订阅一个或多个分片信道。
+///
+/// 格式：SSubscribe <shardchannel> [<shardchannel> ...]
+///
+/// 分片信道与普通信道互相独立，只能收到`SPublish`发送的信息，
+/// 信息以`["smessage", shardchannel, message]`的格式发送给客户端。
+/// 响应中的订阅数量只统计分片信道。进入订阅者模式后的规则与`Subscribe`相同。
+#[derive(Debug)]
+pub struct SSubscribe {
+    channels: Vec<String>,
+}
+
+/// 退订一个或多个分片信道。
+///
+/// 格式：SUnsubscribe [<shardchannel> ...]
+///
+/// 没有指定分片信道时退订所有分片信道，响应的格式与`Unsubscribe`相同。
+#[derive(Debug)]
+pub struct SUnsubscribe {
+    channels: Vec<String>,
+}
+
 /// 异步信息流，信息的类型是`Bytes`。
 ///
 /// 参考`StreamMap`的 example。
 type Messages = Pin<Box<dyn Stream<Item = Bytes> + Send>>;
 
+/// 模式对应的异步信息流，信息为信道名称和内容。
+type PatternMessages = Pin<Box<dyn Stream<Item = (String, Bytes)> + Send>>;
+
+/// 一个连接在订阅者模式下订阅的所有信道和模式。
+///
+/// 每个信道和模式都使用一个`sync::broadcast`，信息会散布到所有对应的订阅者。
+/// 由于客户端可以订阅多个信道，所以我们使用`StreamMap`合并所有异步信息流进行管理。
+struct Subscriptions {
+    channels: StreamMap<String, Messages>,
+    patterns: StreamMap<String, PatternMessages>,
+    shard_channels: StreamMap<String, Messages>,
+}
+
+/// 订阅者模式下等待订阅的信道、模式和分片信道。
+#[derive(Default)]
+struct Pending {
+    channels: Vec<String>,
+    patterns: Vec<String>,
+    shard_channels: Vec<String>,
+}
+
 impl Subscribe {
     /// 创建一个`Subscribe`命令。
     pub(crate) fn new(channels: Vec<String>) -> Subscribe {
@@ -32,20 +111,10 @@ impl Subscribe {
     /// `Parse`提供了类似迭代器的 API 来解析`Frame`。
     /// 需要保证字符串`Subscribe`已经被处理过了。
     pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Subscribe> {
-        use crate::ParseError::EndOfStream;
         // 至少有一个channel，如果没有，报错。
         let mut channels = vec![parse.next_string()?];
         // 循环获取剩余的channel。
-        loop {
-            match parse.next_string() {
-                // 还有channel。
-                Ok(s) => channels.push(s),
-                // 没有channel了。
-                Err(EndOfStream) => break,
-                // 其他错误。
-                Err(err) => return Err(err.into()),
-            }
-        }
+        channels.extend(next_names(parse)?);
         Ok(Subscribe { channels })
     }
 
@@ -54,87 +123,442 @@ impl Subscribe {
     /// 应用命令委派给了`Db`的方法。写回响应数据使用到了`Connection`，
     /// 如果写回响应错出错，返回`Err`。
     pub(crate) async fn apply(
-        mut self,
+        self,
         db: &Db,
         dst: &mut Connection,
         shutdown: &mut Shutdown,
+        ctx: &mut ConnContext,
     ) -> crate::Result<()> {
-        // 每个信道都使用一个`sync::broadcast`。
-        // 信息会散布到所有对应的订阅者。
-        // 由于客户端可以订阅多个信道，
-        // 所以我们使用`StreamMap`合并所有异步信息流进行管理。
-        let mut subscriptions = StreamMap::new();
+        let pending = Pending {
+            channels: self.channels,
+            ..Pending::default()
+        };
+        run_subscriber(pending, db, dst, shutdown, ctx).await
+    }
 
-        // 对所有订阅了的信道，都生成对应的异步流并加到`StreamMap`并且发送响应信息。
-        // 处理过的信道从`channels`中移除。
-        for channel_name in self.channels.drain(..) {
-            subscribe_to_channel(channel_name, &mut subscriptions, db, dst).await?;
-        }
+    /// 将命令转换为对应的`Frame`
+    pub(crate) fn into_frame(self) -> Frame {
+        names_frame("subscribe", self.channels)
+    }
+}
 
-        loop {
-            // 等待下面三种情况其中之一发生。
-            // - 从订阅了的信道中接收到了信息
-            // - 接收到了客户端的关闭信号
-            // - 接收到了服务端的关闭信号
-            tokio::select! {
-                // 接收信息。
-                // 调用`next()`后，`StreamMap`会对他管理的所有异步流
-                // 调用`next()`，尝试产生值。
-                // 如果成功就返回异步流在`StreamMap`中对应的 key 以及产生的值。
-                Some((channel_name, msg)) = subscriptions.next() => {
-                    dst.write_frame(&make_message_frame(channel_name, msg)).await?;
-                }
-                // 客户端发来了关闭信号，停止接收信息并结束，达到安全状态。
-                ctrlc_frame = dst.read_frame() => {
-                    // 验证`Frame`
-                    match ctrlc_frame {
-                        // 取得了`Frame`
-                        Ok(Some(ctrlc_frame)) => {
-                            match ctrlc_frame{
-                                // 预期的帧，结束
-                                Frame::Simple(v) if v == "shutdown" => {
-                                    return Ok(())
-                                }
-                                // 非预期的帧，忽略，继续循环
-                                _ => {},
-                            }
-                        },
-                        // `socket`关闭了当然也要结束
-                        Ok(None) => return Ok(()),
-                        // 出错也要结束
-                        Err(err) => return Err(err)
-                    }
-                }
-                // 如果接收到服务器的关闭信号，就应该停止接收信息并结束，以达到安全状态。
-                _ = shutdown.recv() => {
-                    return Ok(());
-                }
-            }
-        }
+impl Unsubscribe {
+    /// 创建一个`Unsubscribe`命令，`channels`为空时退订所有信道。
+    pub(crate) fn new(channels: Vec<String>) -> Unsubscribe {
+        Unsubscribe { channels }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Unsubscribe`命令。
+    ///
+    /// 需要保证字符串`Unsubscribe`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Unsubscribe> {
+        Ok(Unsubscribe {
+            channels: next_names(parse)?,
+        })
+    }
+
+    /// 在订阅者模式之外应用命令并写回响应数据。
+    ///
+    /// 此时客户端没有订阅任何信道，剩余的订阅数量总是`0`。
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        reply_unsubscribed("unsubscribe", self.channels, dst).await
+    }
+
+    /// 将命令转换为对应的`Frame`
+    pub(crate) fn into_frame(self) -> Frame {
+        names_frame("unsubscribe", self.channels)
+    }
+}
+
+impl PSubscribe {
+    /// 创建一个`PSubscribe`命令。
+    pub(crate) fn new(patterns: Vec<String>) -> PSubscribe {
+        PSubscribe { patterns }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`PSubscribe`命令。
+    ///
+    /// 需要保证字符串`PSubscribe`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<PSubscribe> {
+        let mut patterns = vec![parse.next_string()?];
+        patterns.extend(next_names(parse)?);
+        Ok(PSubscribe { patterns })
+    }
+
+    /// 应用命令并写回响应数据，与`Subscribe`一样进入订阅者模式。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+        ctx: &mut ConnContext,
+    ) -> crate::Result<()> {
+        let pending = Pending {
+            patterns: self.patterns,
+            ..Pending::default()
+        };
+        run_subscriber(pending, db, dst, shutdown, ctx).await
+    }
+
+    /// 将命令转换为对应的`Frame`
+    pub(crate) fn into_frame(self) -> Frame {
+        names_frame("psubscribe", self.patterns)
+    }
+}
+
+impl PUnsubscribe {
+    /// 创建一个`PUnsubscribe`命令，`patterns`为空时退订所有模式。
+    pub(crate) fn new(patterns: Vec<String>) -> PUnsubscribe {
+        PUnsubscribe { patterns }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`PUnsubscribe`命令。
+    ///
+    /// 需要保证字符串`PUnsubscribe`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<PUnsubscribe> {
+        Ok(PUnsubscribe {
+            patterns: next_names(parse)?,
+        })
+    }
+
+    /// 在订阅者模式之外应用命令并写回响应数据。
+    ///
+    /// 此时客户端没有订阅任何模式，剩余的订阅数量总是`0`。
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        reply_unsubscribed("punsubscribe", self.patterns, dst).await
+    }
+
+    /// 将命令转换为对应的`Frame`
+    pub(crate) fn into_frame(self) -> Frame {
+        names_frame("punsubscribe", self.patterns)
+    }
+}
+
+impl SSubscribe {
+    /// 创建一个`SSubscribe`命令。
+    pub(crate) fn new(channels: Vec<String>) -> SSubscribe {
+        SSubscribe { channels }
+    }
+
+    /// 获取第一个分片信道的名称，集群模式下所有分片信道需要属于同一个槽。
+    pub fn key(&self) -> &str {
+        &self.channels[0]
+    }
+
+    /// 通过`Parse`将`Frame`解析为`SSubscribe`命令。
+    ///
+    /// 需要保证字符串`SSubscribe`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SSubscribe> {
+        let mut channels = vec![parse.next_string()?];
+        channels.extend(next_names(parse)?);
+        Ok(SSubscribe { channels })
+    }
+
+    /// 应用命令并写回响应数据，与`Subscribe`一样进入订阅者模式。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+        ctx: &mut ConnContext,
+    ) -> crate::Result<()> {
+        let pending = Pending {
+            shard_channels: self.channels,
+            ..Pending::default()
+        };
+        run_subscriber(pending, db, dst, shutdown, ctx).await
     }
 
     /// 将命令转换为对应的`Frame`
     pub(crate) fn into_frame(self) -> Frame {
-        let mut frame = Frame::array();
-        frame.push_bulk(Bytes::from("subscribe".as_bytes()));
-        for channel in self.channels {
-            frame.push_bulk(Bytes::from(channel.into_bytes()));
+        names_frame("ssubscribe", self.channels)
+    }
+}
+
+impl SUnsubscribe {
+    /// 创建一个`SUnsubscribe`命令，`channels`为空时退订所有分片信道。
+    pub(crate) fn new(channels: Vec<String>) -> SUnsubscribe {
+        SUnsubscribe { channels }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`SUnsubscribe`命令。
+    ///
+    /// 需要保证字符串`SUnsubscribe`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SUnsubscribe> {
+        Ok(SUnsubscribe {
+            channels: next_names(parse)?,
+        })
+    }
+
+    /// 在订阅者模式之外应用命令并写回响应数据。
+    ///
+    /// 此时客户端没有订阅任何分片信道，剩余的订阅数量总是`0`。
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        reply_unsubscribed("sunsubscribe", self.channels, dst).await
+    }
+
+    /// 将命令转换为对应的`Frame`
+    pub(crate) fn into_frame(self) -> Frame {
+        names_frame("sunsubscribe", self.channels)
+    }
+}
+
+impl Subscriptions {
+    fn new() -> Subscriptions {
+        Subscriptions {
+            channels: StreamMap::new(),
+            patterns: StreamMap::new(),
+            shard_channels: StreamMap::new(),
+        }
+    }
+
+    /// 订阅的信道和模式的总数，不包括分片信道。
+    fn len(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
+
+    /// 判断是否没有订阅任何信道、模式和分片信道。
+    fn is_empty(&self) -> bool {
+        self.len() == 0 && self.shard_channels.is_empty()
+    }
+
+    /// 订阅分片信道，生成异步流并进行管理，同时写回响应信息。
+    async fn ssubscribe(
+        &mut self,
+        channel_name: String,
+        db: &Db,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        let rx = db.ssubscribe(channel_name.clone());
+        self.shard_channels
+            .insert(channel_name.clone(), into_stream(rx));
+
+        let response = make_subscribe_frame("ssubscribe", channel_name, self.shard_channels.len());
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 退订分片信道并写回响应信息，`channels`为空时退订所有分片信道。
+    async fn sunsubscribe(
+        &mut self,
+        mut channels: Vec<String>,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        if channels.is_empty() {
+            channels = self.shard_channels.keys().cloned().collect();
+        }
+        if channels.is_empty() {
+            let response = make_unsubscribe_frame("sunsubscribe", None, 0);
+            dst.write_frame(&response).await?;
+        }
+        for channel_name in channels {
+            self.shard_channels.remove(&channel_name);
+            let response = make_unsubscribe_frame(
+                "sunsubscribe",
+                Some(channel_name),
+                self.shard_channels.len(),
+            );
+            dst.write_frame(&response).await?;
+        }
+        Ok(())
+    }
+
+    /// 订阅信道，生成异步流并进行管理，同时写回响应信息。
+    async fn subscribe(
+        &mut self,
+        channel_name: String,
+        db: &Db,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        // 订阅信道，将异步数据流放入`StreamMap`进行管理。
+        let rx = db.subscribe(channel_name.clone());
+        self.channels.insert(channel_name.clone(), into_stream(rx));
+
+        // 响应客户端。
+        let response = make_subscribe_frame("subscribe", channel_name, self.len());
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 订阅模式，生成异步流并进行管理，同时写回响应信息。
+    async fn psubscribe(
+        &mut self,
+        pattern: String,
+        db: &Db,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        let rx = db.psubscribe(pattern.clone());
+        self.patterns.insert(pattern.clone(), into_stream(rx));
+
+        let response = make_subscribe_frame("psubscribe", pattern, self.len());
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 退订信道并写回响应信息，`channels`为空时退订所有信道。
+    async fn unsubscribe(
+        &mut self,
+        mut channels: Vec<String>,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        if channels.is_empty() {
+            channels = self.channels.keys().cloned().collect();
+        }
+        // 没有订阅任何信道时，仍然需要响应一次。
+        if channels.is_empty() {
+            let response = make_unsubscribe_frame("unsubscribe", None, self.len());
+            dst.write_frame(&response).await?;
+        }
+        for channel_name in channels {
+            self.channels.remove(&channel_name);
+            let response = make_unsubscribe_frame("unsubscribe", Some(channel_name), self.len());
+            dst.write_frame(&response).await?;
+        }
+        Ok(())
+    }
+
+    /// 退订模式并写回响应信息，`patterns`为空时退订所有模式。
+    async fn punsubscribe(
+        &mut self,
+        mut patterns: Vec<String>,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        if patterns.is_empty() {
+            patterns = self.patterns.keys().cloned().collect();
         }
-        frame
+        if patterns.is_empty() {
+            let response = make_unsubscribe_frame("punsubscribe", None, self.len());
+            dst.write_frame(&response).await?;
+        }
+        for pattern in patterns {
+            self.patterns.remove(&pattern);
+            let response = make_unsubscribe_frame("punsubscribe", Some(pattern), self.len());
+            dst.write_frame(&response).await?;
+        }
+        Ok(())
     }
 }
 
-/// 订阅信道，生成异步流并进行管理，同时写回响应信息。
-async fn subscribe_to_channel(
-    channel_name: String,
-    subscriptions: &mut StreamMap<String, Messages>,
+/// 进入订阅者模式，订阅`pending`中的信道、模式和分片信道后持续转发信息，
+/// 直到退订了所有订阅、客户端关闭或者服务器关闭。
+async fn run_subscriber(
+    mut pending: Pending,
     db: &Db,
     dst: &mut Connection,
+    shutdown: &mut Shutdown,
+    ctx: &mut ConnContext,
 ) -> crate::Result<()> {
-    // 订阅信道。
-    let mut rx = db.subscribe(channel_name.clone());
+    let mut subscriptions = Subscriptions::new();
+
+    loop {
+        // 对所有需要订阅的信道、模式和分片信道，都生成对应的异步流并加到`StreamMap`并且发送响应信息。
+        // 处理过的订阅请求被移除，订阅者模式下的订阅命令会再次填充`pending`。
+        for channel_name in pending.channels.drain(..) {
+            subscriptions.subscribe(channel_name, db, dst).await?;
+        }
+        for pattern in pending.patterns.drain(..) {
+            subscriptions.psubscribe(pattern, db, dst).await?;
+        }
+        for channel_name in pending.shard_channels.drain(..) {
+            subscriptions.ssubscribe(channel_name, db, dst).await?;
+        }
 
-    let rx = Box::pin(async_stream::stream! {
+        // 退订了所有订阅，退出订阅者模式。
+        if subscriptions.is_empty() {
+            return Ok(());
+        }
+
+        // 等待下面几种情况其中之一发生。
+        // - 从订阅了的信道、模式或分片信道中接收到了信息
+        // - 接收到了客户端的命令或者关闭信号
+        // - 接收到了服务端的关闭信号
+        tokio::select! {
+            // 接收信息。
+            // 调用`next()`后，`StreamMap`会对他管理的所有异步流
+            // 调用`next()`，尝试产生值。
+            // 如果成功就返回异步流在`StreamMap`中对应的 key 以及产生的值。
+            Some((channel_name, msg)) = subscriptions.channels.next() => {
+                dst.write_frame(&make_message_frame("message", channel_name, msg)).await?;
+            }
+            Some((pattern, (channel_name, msg))) = subscriptions.patterns.next() => {
+                dst.write_frame(&make_pmessage_frame(pattern, channel_name, msg)).await?;
+            }
+            Some((channel_name, msg)) = subscriptions.shard_channels.next() => {
+                dst.write_frame(&make_message_frame("smessage", channel_name, msg)).await?;
+            }
+            // 客户端发来了命令或者关闭信号。
+            frame = dst.read_frame() => {
+                let frame = match frame? {
+                    Some(frame) => frame,
+                    // `socket`关闭了当然也要结束
+                    None => return Ok(()),
+                };
+                match frame {
+                    // 关闭信号，停止接收信息并结束，达到安全状态。
+                    Frame::Simple(v) if v == "shutdown" => return Ok(()),
+                    frame => {
+                        handle_command(frame, &mut pending, &mut subscriptions, dst, ctx).await?;
+                    }
+                }
+            }
+            // 如果接收到服务器的关闭信号，就应该停止接收信息并结束，以达到安全状态。
+            _ = shutdown.recv() => {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// 处理订阅者模式下客户端发来的命令。
+///
+/// 需要订阅的信道、模式和分片信道放入`pending`，由调用者完成订阅；
+/// 退订直接在`subscriptions`中完成。`Reset`会清空所有订阅，使调用者退出订阅者模式。
+/// 其他命令不会被执行，向客户端返回错误信息。
+async fn handle_command(
+    frame: Frame,
+    pending: &mut Pending,
+    subscriptions: &mut Subscriptions,
+    dst: &mut Connection,
+    ctx: &mut ConnContext,
+) -> crate::Result<()> {
+    match Command::from_frame(frame)? {
+        Command::Subscribe(subscribe) => pending.channels.extend(subscribe.channels),
+        Command::PSubscribe(psubscribe) => pending.patterns.extend(psubscribe.patterns),
+        Command::SSubscribe(ssubscribe) => pending.shard_channels.extend(ssubscribe.channels),
+        Command::Unsubscribe(unsubscribe) => {
+            subscriptions.unsubscribe(unsubscribe.channels, dst).await?;
+        }
+        Command::PUnsubscribe(punsubscribe) => {
+            subscriptions
+                .punsubscribe(punsubscribe.patterns, dst)
+                .await?;
+        }
+        Command::SUnsubscribe(sunsubscribe) => {
+            subscriptions
+                .sunsubscribe(sunsubscribe.channels, dst)
+                .await?;
+        }
+        Command::Ping(ping) => ping.apply_subscribed(dst).await?,
+        Command::Reset(reset) => {
+            *subscriptions = Subscriptions::new();
+            reset.apply(dst, ctx).await?;
+        }
+        command => {
+            let response = Frame::Error(format!(
+                "ERR Can't execute '{}': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / RESET are allowed in this context",
+                command.get_name()
+            ));
+            dst.write_frame(&response).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 将广播接收端转换为异步流。
+fn into_stream<T: Clone + Send + 'static>(
+    mut rx: broadcast::Receiver<T>,
+) -> Pin<Box<dyn Stream<Item = T> + Send>> {
+    Box::pin(async_stream::stream! {
         // 使用`stream!`生成异步流。
         // 异步流类似于迭代器，但是它只在需要的时候产生值而不是一次性产生所有值，
         // 特点有：异步产生值、惰性计算、非阻塞、顺序处理。
@@ -147,32 +571,90 @@ async fn subscribe_to_channel(
                 Err(_) => break,
             }
         }
-    });
-
-    // 将异步数据流放入`StreamMap`进行管理。
-    subscriptions.insert(channel_name.clone(), rx);
+    })
+}
 
-    // 响应客户端。
-    let response = make_subscribe_frame(channel_name, subscriptions.len());
-    dst.write_frame(&response).await?;
+/// 读取剩余的所有信道名称或者模式。
+fn next_names(parse: &mut Parse) -> crate::Result<Vec<String>> {
+    let mut names = vec![];
+    loop {
+        match parse.next_string() {
+            Ok(s) => names.push(s),
+            Err(ParseError::EndOfStream) => return Ok(names),
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
 
+/// 在订阅者模式之外响应`Unsubscribe`或`PUnsubscribe`命令，`kind`为命令名称。
+async fn reply_unsubscribed(
+    kind: &'static str,
+    names: Vec<String>,
+    dst: &mut Connection,
+) -> crate::Result<()> {
+    if names.is_empty() {
+        dst.write_frame(&make_unsubscribe_frame(kind, None, 0))
+            .await?;
+    }
+    for name in names {
+        dst.write_frame(&make_unsubscribe_frame(kind, Some(name), 0))
+            .await?;
+    }
     Ok(())
 }
 
-/// 生成`Subscribe`命令的响应帧。
-fn make_subscribe_frame(channel_name: String, num_subs: usize) -> Frame {
-    let mut response = Frame::array();
-    response.push_bulk(Bytes::from_static(b"subscribe"));
-    response.push_bulk(Bytes::from(channel_name));
-    response.push_int(num_subs as u64);
-    response
+/// 生成由命令名称和所有信道名称或模式组成的`Frame`。
+fn names_frame(kind: &'static str, names: Vec<String>) -> Frame {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from(kind.as_bytes()));
+    for name in names {
+        frame.push_bulk(Bytes::from(name.into_bytes()));
+    }
+    frame
+}
+
+// 订阅相关的响应和信息都是`Push`，RESP3 的客户端可以将它们与其他命令的响应区分开，
+// RESP2 的连接中它们仍然编码为`Array`。
+
+/// 生成`Subscribe`或`PSubscribe`命令的响应帧。
+fn make_subscribe_frame(kind: &'static str, name: String, num_subs: usize) -> Frame {
+    Frame::Push(vec![
+        Frame::Bulk(Bytes::from_static(kind.as_bytes())),
+        Frame::Bulk(Bytes::from(name)),
+        Frame::Integer(num_subs as i64),
+    ])
+}
+
+/// 生成`Unsubscribe`或`PUnsubscribe`命令的响应帧，没有可以退订的信道或模式时`name`为`None`。
+fn make_unsubscribe_frame(kind: &'static str, name: Option<String>, num_subs: usize) -> Frame {
+    let name = match name {
+        Some(name) => Frame::Bulk(Bytes::from(name)),
+        None => Frame::Null,
+    };
+    Frame::Push(vec![
+        Frame::Bulk(Bytes::from_static(kind.as_bytes())),
+        name,
+        Frame::Integer(num_subs as i64),
+    ])
 }
 
 /// 生成`Frame`，告知客户端哪个信道发送了什么信息。
-fn make_message_frame(channel_name: String, msg: Bytes) -> Frame {
-    let mut response = Frame::array();
-    response.push_bulk(Bytes::from_static(b"message"));
-    response.push_bulk(Bytes::from(channel_name));
-    response.push_bulk(msg);
-    response
+///
+/// `kind`为`message`，分片信道为`smessage`。
+fn make_message_frame(kind: &'static str, channel_name: String, msg: Bytes) -> Frame {
+    Frame::Push(vec![
+        Frame::Bulk(Bytes::from_static(kind.as_bytes())),
+        Frame::Bulk(Bytes::from(channel_name)),
+        Frame::Bulk(msg),
+    ])
+}
+
+/// 生成`Frame`，告知客户端匹配模式的哪个信道发送了什么信息。
+fn make_pmessage_frame(pattern: String, channel_name: String, msg: Bytes) -> Frame {
+    Frame::Push(vec![
+        Frame::Bulk(Bytes::from_static(b"pmessage")),
+        Frame::Bulk(Bytes::from(pattern)),
+        Frame::Bulk(Bytes::from(channel_name)),
+        Frame::Bulk(msg),
+    ])
 }
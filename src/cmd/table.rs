@@ -0,0 +1,904 @@
+/// 命令的元信息，包括参数个数以及标志。
+///
+/// 在解析命令之前，会先根据`arity`检查参数个数，
+/// 这样参数个数不正确的命令能得到准确的错误信息。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandSpec {
+    /// 命令名称，全小写。
+    pub name: &'static str,
+
+    /// 参数个数，包括命令名称本身。
+    ///
+    /// 正数表示参数个数必须等于该值，负数表示参数个数至少为它的绝对值。
+    pub arity: i32,
+
+    /// 命令的标志，例如`write`、`readonly`、`pubsub`。
+    ///
+    /// 带有`noscript`标志的命令不能在脚本中执行，带有`denyoom`标志的命令
+    /// 在内存超过`maxmemory`时会被拒绝，带有`may-replicate`标志的命令不修改数据，
+    /// 但同样会发送给副本，例如`Publish`。
+    pub flags: &'static [&'static str],
+
+    /// key 在参数中的位置。
+    pub keys: KeySpec,
+
+    /// 命令所属的分组，例如`string`、`list`，同时也是 ACL 的命令类别。
+    pub group: &'static str,
+}
+
+/// key 在参数中的位置，下标从命令名称开始计算，与 Redis 的`Command Info`一致。
+///
+/// `first`为第一个 key 的下标，`last`为最后一个 key 的下标，负数表示从末尾开始计算，
+/// `step`为相邻两个 key 的间隔。没有 key，或者 key 的位置取决于其他参数时（例如`Eval`），
+/// 三者都为`0`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeySpec {
+    pub first: i32,
+    pub last: i32,
+    pub step: i32,
+}
+
+impl KeySpec {
+    /// 没有 key，或者 key 的位置不固定。
+    pub const NONE: KeySpec = KeySpec::new(0, 0, 0);
+    /// 只有第一个参数是 key。
+    pub const FIRST: KeySpec = KeySpec::new(1, 1, 1);
+    /// 所有参数都是 key。
+    pub const ALL: KeySpec = KeySpec::new(1, -1, 1);
+    /// 除了最后一个参数，所有参数都是 key，例如`BLPop`的最后一个参数是超时时间。
+    pub const ALL_BUT_LAST: KeySpec = KeySpec::new(1, -2, 1);
+
+    const fn new(first: i32, last: i32, step: i32) -> KeySpec {
+        KeySpec { first, last, step }
+    }
+}
+
+/// 所有支持的命令的元信息。
+const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "get",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "string",
+    },
+    CommandSpec {
+        name: "set",
+        arity: -3,
+        flags: &["write", "denyoom"],
+        keys: KeySpec::FIRST,
+        group: "string",
+    },
+    CommandSpec {
+        name: "setex",
+        arity: 4,
+        flags: &["write", "denyoom"],
+        keys: KeySpec::FIRST,
+        group: "string",
+    },
+    CommandSpec {
+        name: "psetex",
+        arity: 4,
+        flags: &["write", "denyoom"],
+        keys: KeySpec::FIRST,
+        group: "string",
+    },
+    CommandSpec {
+        name: "publish",
+        arity: 3,
+        flags: &["pubsub", "fast", "may-replicate"],
+        keys: KeySpec::NONE,
+        group: "pubsub",
+    },
+    CommandSpec {
+        name: "subscribe",
+        arity: -2,
+        flags: &["pubsub", "noscript"],
+        keys: KeySpec::NONE,
+        group: "pubsub",
+    },
+    CommandSpec {
+        name: "unsubscribe",
+        arity: -1,
+        flags: &["pubsub", "noscript"],
+        keys: KeySpec::NONE,
+        group: "pubsub",
+    },
+    CommandSpec {
+        name: "psubscribe",
+        arity: -2,
+        flags: &["pubsub", "noscript"],
+        keys: KeySpec::NONE,
+        group: "pubsub",
+    },
+    CommandSpec {
+        name: "punsubscribe",
+        arity: -1,
+        flags: &["pubsub", "noscript"],
+        keys: KeySpec::NONE,
+        group: "pubsub",
+    },
+    CommandSpec {
+        name: "spublish",
+        arity: 3,
+        flags: &["pubsub", "fast", "may-replicate"],
+        keys: KeySpec::NONE,
+        group: "pubsub",
+    },
+    CommandSpec {
+        name: "ssubscribe",
+        arity: -2,
+        flags: &["pubsub", "noscript"],
+        keys: KeySpec::NONE,
+        group: "pubsub",
+    },
+    CommandSpec {
+        name: "sunsubscribe",
+        arity: -1,
+        flags: &["pubsub", "noscript"],
+        keys: KeySpec::NONE,
+        group: "pubsub",
+    },
+    CommandSpec {
+        name: "ping",
+        arity: -1,
+        flags: &["fast"],
+        keys: KeySpec::NONE,
+        group: "connection",
+    },
+    CommandSpec {
+        name: "auth",
+        arity: -2,
+        flags: &["fast", "no-auth", "noscript"],
+        keys: KeySpec::NONE,
+        group: "connection",
+    },
+    CommandSpec {
+        name: "hello",
+        arity: -1,
+        flags: &["fast", "no-auth", "noscript"],
+        keys: KeySpec::NONE,
+        group: "connection",
+    },
+    CommandSpec {
+        name: "asking",
+        arity: 1,
+        flags: &["fast"],
+        keys: KeySpec::NONE,
+        group: "cluster",
+    },
+    CommandSpec {
+        name: "reset",
+        arity: 1,
+        flags: &["fast", "no-auth", "noscript"],
+        keys: KeySpec::NONE,
+        group: "connection",
+    },
+    CommandSpec {
+        name: "command",
+        arity: -1,
+        flags: &[],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "config",
+        arity: -2,
+        flags: &["admin"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "client",
+        arity: -2,
+        flags: &["admin", "noscript"],
+        keys: KeySpec::NONE,
+        group: "connection",
+    },
+    CommandSpec {
+        name: "acl",
+        arity: -2,
+        flags: &["admin", "noscript"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "cluster",
+        arity: -2,
+        flags: &["admin"],
+        keys: KeySpec::NONE,
+        group: "cluster",
+    },
+    CommandSpec {
+        name: "eval",
+        arity: -3,
+        flags: &["noscript"],
+        keys: KeySpec::NONE,
+        group: "scripting",
+    },
+    CommandSpec {
+        name: "evalsha",
+        arity: -3,
+        flags: &["noscript"],
+        keys: KeySpec::NONE,
+        group: "scripting",
+    },
+    CommandSpec {
+        name: "script",
+        arity: -2,
+        flags: &["noscript"],
+        keys: KeySpec::NONE,
+        group: "scripting",
+    },
+    CommandSpec {
+        name: "function",
+        arity: -2,
+        flags: &["noscript"],
+        keys: KeySpec::NONE,
+        group: "scripting",
+    },
+    CommandSpec {
+        name: "fcall",
+        arity: -3,
+        flags: &["noscript"],
+        keys: KeySpec::NONE,
+        group: "scripting",
+    },
+    CommandSpec {
+        name: "save",
+        arity: 1,
+        flags: &["admin", "noscript"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "bgsave",
+        arity: 1,
+        flags: &["admin", "noscript"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "lastsave",
+        arity: 1,
+        flags: &["fast"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "slowlog",
+        arity: -2,
+        flags: &["admin"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "latency",
+        arity: -2,
+        flags: &["admin"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "debug",
+        arity: -2,
+        flags: &["admin", "noscript"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "info",
+        arity: -1,
+        flags: &[],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "monitor",
+        arity: 1,
+        flags: &["admin", "noscript"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "backup",
+        arity: 2,
+        flags: &["admin"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "restorefrom",
+        arity: 2,
+        flags: &["admin", "write"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "dump",
+        arity: 2,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "restore",
+        arity: -4,
+        flags: &["write", "denyoom"],
+        keys: KeySpec::FIRST,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "replicaof",
+        arity: 3,
+        flags: &["admin", "noscript"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "psync",
+        arity: 3,
+        flags: &["admin", "noscript"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "wait",
+        arity: 3,
+        flags: &["noscript"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "expire",
+        arity: 3,
+        flags: &["write", "fast"],
+        keys: KeySpec::FIRST,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "pexpire",
+        arity: 3,
+        flags: &["write", "fast"],
+        keys: KeySpec::FIRST,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "expireat",
+        arity: 3,
+        flags: &["write", "fast"],
+        keys: KeySpec::FIRST,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "pexpireat",
+        arity: 3,
+        flags: &["write", "fast"],
+        keys: KeySpec::FIRST,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "ttl",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "pttl",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "expiretime",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "pexpiretime",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "incr",
+        arity: 2,
+        flags: &["write", "denyoom", "fast"],
+        keys: KeySpec::FIRST,
+        group: "string",
+    },
+    CommandSpec {
+        name: "decr",
+        arity: 2,
+        flags: &["write", "denyoom", "fast"],
+        keys: KeySpec::FIRST,
+        group: "string",
+    },
+    CommandSpec {
+        name: "incrby",
+        arity: 3,
+        flags: &["write", "denyoom", "fast"],
+        keys: KeySpec::FIRST,
+        group: "string",
+    },
+    CommandSpec {
+        name: "decrby",
+        arity: 3,
+        flags: &["write", "denyoom", "fast"],
+        keys: KeySpec::FIRST,
+        group: "string",
+    },
+    CommandSpec {
+        name: "incrbyfloat",
+        arity: 3,
+        flags: &["write", "denyoom", "fast"],
+        keys: KeySpec::FIRST,
+        group: "string",
+    },
+    CommandSpec {
+        name: "type",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "unlink",
+        arity: -2,
+        flags: &["write", "fast"],
+        keys: KeySpec::ALL,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "touch",
+        arity: -2,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::ALL,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "object",
+        arity: -2,
+        flags: &["readonly"],
+        keys: KeySpec::NONE,
+        group: "generic",
+    },
+    CommandSpec {
+        name: "memory",
+        arity: -2,
+        flags: &["readonly"],
+        keys: KeySpec::NONE,
+        group: "server",
+    },
+    CommandSpec {
+        name: "hset",
+        arity: -4,
+        flags: &["write", "denyoom", "fast"],
+        keys: KeySpec::FIRST,
+        group: "hash",
+    },
+    CommandSpec {
+        name: "hget",
+        arity: 3,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "hash",
+    },
+    CommandSpec {
+        name: "hdel",
+        arity: -3,
+        flags: &["write", "fast"],
+        keys: KeySpec::FIRST,
+        group: "hash",
+    },
+    CommandSpec {
+        name: "hgetall",
+        arity: 2,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "hash",
+    },
+    CommandSpec {
+        name: "hrandfield",
+        arity: -2,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "hash",
+    },
+    CommandSpec {
+        name: "lpush",
+        arity: -3,
+        flags: &["write", "denyoom", "fast"],
+        keys: KeySpec::FIRST,
+        group: "list",
+    },
+    CommandSpec {
+        name: "rpush",
+        arity: -3,
+        flags: &["write", "denyoom", "fast"],
+        keys: KeySpec::FIRST,
+        group: "list",
+    },
+    CommandSpec {
+        name: "lpop",
+        arity: 2,
+        flags: &["write", "fast"],
+        keys: KeySpec::FIRST,
+        group: "list",
+    },
+    CommandSpec {
+        name: "rpop",
+        arity: 2,
+        flags: &["write", "fast"],
+        keys: KeySpec::FIRST,
+        group: "list",
+    },
+    CommandSpec {
+        name: "lrange",
+        arity: 4,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "list",
+    },
+    CommandSpec {
+        name: "llen",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "list",
+    },
+    CommandSpec {
+        name: "lpos",
+        arity: -3,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "list",
+    },
+    CommandSpec {
+        name: "linsert",
+        arity: 5,
+        flags: &["write", "denyoom"],
+        keys: KeySpec::FIRST,
+        group: "list",
+    },
+    CommandSpec {
+        name: "lset",
+        arity: 4,
+        flags: &["write", "denyoom"],
+        keys: KeySpec::FIRST,
+        group: "list",
+    },
+    CommandSpec {
+        name: "lrem",
+        arity: 4,
+        flags: &["write"],
+        keys: KeySpec::FIRST,
+        group: "list",
+    },
+    CommandSpec {
+        name: "blpop",
+        arity: -3,
+        flags: &["write", "blocking"],
+        keys: KeySpec::ALL_BUT_LAST,
+        group: "list",
+    },
+    CommandSpec {
+        name: "brpop",
+        arity: -3,
+        flags: &["write", "blocking"],
+        keys: KeySpec::ALL_BUT_LAST,
+        group: "list",
+    },
+    CommandSpec {
+        name: "lmpop",
+        arity: -4,
+        flags: &["write"],
+        keys: KeySpec::NONE,
+        group: "list",
+    },
+    CommandSpec {
+        name: "blmpop",
+        arity: -5,
+        flags: &["write", "blocking"],
+        keys: KeySpec::NONE,
+        group: "list",
+    },
+    CommandSpec {
+        name: "sadd",
+        arity: -3,
+        flags: &["write", "denyoom", "fast"],
+        keys: KeySpec::FIRST,
+        group: "set",
+    },
+    CommandSpec {
+        name: "srem",
+        arity: -3,
+        flags: &["write", "fast"],
+        keys: KeySpec::FIRST,
+        group: "set",
+    },
+    CommandSpec {
+        name: "smembers",
+        arity: 2,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "set",
+    },
+    CommandSpec {
+        name: "sismember",
+        arity: 3,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "set",
+    },
+    CommandSpec {
+        name: "scard",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "set",
+    },
+    CommandSpec {
+        name: "srandmember",
+        arity: -2,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "set",
+    },
+    CommandSpec {
+        name: "sinter",
+        arity: -2,
+        flags: &["readonly"],
+        keys: KeySpec::ALL,
+        group: "set",
+    },
+    CommandSpec {
+        name: "sunion",
+        arity: -2,
+        flags: &["readonly"],
+        keys: KeySpec::ALL,
+        group: "set",
+    },
+    CommandSpec {
+        name: "sdiff",
+        arity: -2,
+        flags: &["readonly"],
+        keys: KeySpec::ALL,
+        group: "set",
+    },
+    CommandSpec {
+        name: "sinterstore",
+        arity: -3,
+        flags: &["write", "denyoom"],
+        keys: KeySpec::ALL,
+        group: "set",
+    },
+    CommandSpec {
+        name: "sunionstore",
+        arity: -3,
+        flags: &["write", "denyoom"],
+        keys: KeySpec::ALL,
+        group: "set",
+    },
+    CommandSpec {
+        name: "sdiffstore",
+        arity: -3,
+        flags: &["write", "denyoom"],
+        keys: KeySpec::ALL,
+        group: "set",
+    },
+    CommandSpec {
+        name: "zadd",
+        arity: -4,
+        flags: &["write", "denyoom", "fast"],
+        keys: KeySpec::FIRST,
+        group: "sorted-set",
+    },
+    CommandSpec {
+        name: "zscore",
+        arity: 3,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "sorted-set",
+    },
+    CommandSpec {
+        name: "zrange",
+        arity: -4,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "sorted-set",
+    },
+    CommandSpec {
+        name: "zcard",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "sorted-set",
+    },
+    CommandSpec {
+        name: "zrandmember",
+        arity: -2,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "sorted-set",
+    },
+    CommandSpec {
+        name: "zrangebyscore",
+        arity: -4,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "sorted-set",
+    },
+    CommandSpec {
+        name: "zincrby",
+        arity: 4,
+        flags: &["write", "denyoom", "fast"],
+        keys: KeySpec::FIRST,
+        group: "sorted-set",
+    },
+    CommandSpec {
+        name: "zrem",
+        arity: -3,
+        flags: &["write", "fast"],
+        keys: KeySpec::FIRST,
+        group: "sorted-set",
+    },
+    CommandSpec {
+        name: "zrank",
+        arity: 3,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "sorted-set",
+    },
+    CommandSpec {
+        name: "zmpop",
+        arity: -4,
+        flags: &["write"],
+        keys: KeySpec::NONE,
+        group: "sorted-set",
+    },
+    CommandSpec {
+        name: "bzmpop",
+        arity: -5,
+        flags: &["write", "blocking"],
+        keys: KeySpec::NONE,
+        group: "sorted-set",
+    },
+    CommandSpec {
+        name: "geoadd",
+        arity: -5,
+        flags: &["write", "denyoom"],
+        keys: KeySpec::FIRST,
+        group: "geo",
+    },
+    CommandSpec {
+        name: "geopos",
+        arity: -2,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "geo",
+    },
+    CommandSpec {
+        name: "geodist",
+        arity: -4,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "geo",
+    },
+    CommandSpec {
+        name: "geosearch",
+        arity: -7,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "geo",
+    },
+    CommandSpec {
+        name: "setbit",
+        arity: 4,
+        flags: &["write", "denyoom"],
+        keys: KeySpec::FIRST,
+        group: "bitmap",
+    },
+    CommandSpec {
+        name: "getbit",
+        arity: 3,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "bitmap",
+    },
+    CommandSpec {
+        name: "bitcount",
+        arity: -2,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "bitmap",
+    },
+    CommandSpec {
+        name: "xadd",
+        arity: -5,
+        flags: &["write", "denyoom", "fast"],
+        keys: KeySpec::FIRST,
+        group: "stream",
+    },
+    CommandSpec {
+        name: "xrange",
+        arity: -4,
+        flags: &["readonly"],
+        keys: KeySpec::FIRST,
+        group: "stream",
+    },
+    CommandSpec {
+        name: "xlen",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        keys: KeySpec::FIRST,
+        group: "stream",
+    },
+    CommandSpec {
+        name: "xread",
+        arity: -4,
+        flags: &["readonly", "blocking"],
+        keys: KeySpec::NONE,
+        group: "stream",
+    },
+    CommandSpec {
+        name: "xgroup",
+        arity: -4,
+        flags: &["write", "denyoom"],
+        keys: KeySpec::NONE,
+        group: "stream",
+    },
+    CommandSpec {
+        name: "xreadgroup",
+        arity: -7,
+        flags: &["write", "blocking"],
+        keys: KeySpec::NONE,
+        group: "stream",
+    },
+    CommandSpec {
+        name: "xack",
+        arity: -4,
+        flags: &["write", "fast"],
+        keys: KeySpec::FIRST,
+        group: "stream",
+    },
+    CommandSpec {
+        name: "xclaim",
+        arity: -6,
+        flags: &["write", "fast"],
+        keys: KeySpec::FIRST,
+        group: "stream",
+    },
+];
+
+impl CommandSpec {
+    /// 根据命令名称查找元信息，`name`必须为全小写。
+    pub fn lookup(name: &str) -> Option<&'static CommandSpec> {
+        COMMANDS.iter().find(|spec| spec.name == name)
+    }
+
+    /// 获取所有命令的元信息。
+    pub fn all() -> &'static [CommandSpec] {
+        COMMANDS
+    }
+
+    /// 检查参数个数`argc`（包括命令名称）是否符合要求。
+    pub fn check_arity(&self, argc: usize) -> bool {
+        let arity = self.arity.unsigned_abs() as usize;
+        if self.arity >= 0 {
+            argc == arity
+        } else {
+            argc >= arity
+        }
+    }
+
+    /// 是否有标志`flag`。
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.contains(&flag)
+    }
+}
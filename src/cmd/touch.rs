@@ -0,0 +1,53 @@
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse};
+
+/// 更新 key 的最后访问时刻，返回存在的 key 的数量。
+///
+/// 格式：Touch <key> [<key> ...]
+///
+/// 不存在的 key 会被忽略，重复的 key 会被重复计算。
+#[derive(Debug)]
+pub struct Touch {
+    keys: Vec<String>,
+}
+
+impl Touch {
+    /// 创建一个`Touch`命令。
+    pub fn new(keys: Vec<String>) -> Touch {
+        Touch { keys }
+    }
+
+    /// 获取所有 key。
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Touch`命令。
+    ///
+    /// 需要保证字符串`Touch`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Touch> {
+        let mut keys = vec![parse.next_string()?];
+        while parse.remaining() > 0 {
+            keys.push(parse.next_string()?);
+        }
+        Ok(Touch { keys })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Integer(db.touch(&self.keys) as i64);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("touch".as_bytes()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        frame
+    }
+}
@@ -0,0 +1,164 @@
+use std::time::UNIX_EPOCH;
+
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse};
+
+/// 获取 key 剩余的生存时间。
+///
+/// 格式：Ttl <key>
+///      PTtl <key>
+///
+/// `Ttl`返回秒数，`PTtl`返回毫秒数。如果 key 没有设置过期时间返回`-1`，
+/// 如果 key 不存在返回`-2`。
+#[derive(Debug)]
+pub struct Ttl {
+    key: String,
+    // 是否是`PTtl`命令。
+    millis: bool,
+}
+
+/// 获取 key 过期的时刻。
+///
+/// 格式：ExpireTime <key>
+///      PExpireTime <key>
+///
+/// `ExpireTime`返回以秒为单位的 UNIX 时间戳，`PExpireTime`返回以毫秒为单位的 UNIX 时间戳。
+/// 如果 key 没有设置过期时间返回`-1`，如果 key 不存在返回`-2`。
+#[derive(Debug)]
+pub struct ExpireTime {
+    key: String,
+    // 是否是`PExpireTime`命令。
+    millis: bool,
+}
+
+impl Ttl {
+    /// 创建一个`Ttl`命令。
+    pub fn new(key: impl ToString) -> Ttl {
+        Ttl {
+            key: key.to_string(),
+            millis: false,
+        }
+    }
+
+    /// 创建一个`PTtl`命令。
+    pub fn pttl(key: impl ToString) -> Ttl {
+        Ttl {
+            key: key.to_string(),
+            millis: true,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        if self.millis {
+            "pttl"
+        } else {
+            "ttl"
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Ttl`命令。
+    ///
+    /// 需要保证字符串`Ttl`或`PTtl`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse, millis: bool) -> crate::Result<Ttl> {
+        let key = parse.next_string()?;
+        Ok(Ttl { key, millis })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let ttl = match db.ttl(&self.key) {
+            None => -2,
+            Some(None) => -1,
+            Some(Some(ttl)) if self.millis => ttl.as_millis() as i64,
+            // 与 Redis 一样四舍五入到秒。
+            Some(Some(ttl)) => ((ttl.as_millis() + 500) / 1000) as i64,
+        };
+        dst.write_frame(&Frame::Integer(ttl)).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().to_string()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl ExpireTime {
+    /// 创建一个`ExpireTime`命令。
+    pub fn new(key: impl ToString) -> ExpireTime {
+        ExpireTime {
+            key: key.to_string(),
+            millis: false,
+        }
+    }
+
+    /// 创建一个`PExpireTime`命令。
+    pub fn pexpiretime(key: impl ToString) -> ExpireTime {
+        ExpireTime {
+            key: key.to_string(),
+            millis: true,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        if self.millis {
+            "pexpiretime"
+        } else {
+            "expiretime"
+        }
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ExpireTime`命令。
+    ///
+    /// 需要保证字符串`ExpireTime`或`PExpireTime`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse, millis: bool) -> crate::Result<ExpireTime> {
+        let key = parse.next_string()?;
+        Ok(ExpireTime { key, millis })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let timestamp = match db.expire_time(&self.key) {
+            None => -2,
+            Some(None) => -1,
+            Some(Some(when)) => {
+                let millis = when
+                    .duration_since(UNIX_EPOCH)
+                    .map(|since| since.as_millis())
+                    .unwrap_or(0);
+                if self.millis {
+                    millis as i64
+                } else {
+                    // 与`Ttl`一样四舍五入到秒。
+                    ((millis + 500) / 1000) as i64
+                }
+            }
+        };
+        dst.write_frame(&Frame::Integer(timestamp)).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().to_string()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
@@ -0,0 +1,54 @@
+use bytes::Bytes;
+
+use crate::{Connection, Db, Frame, Parse};
+
+/// 删除 key，返回被删除的 key 的数量。
+///
+/// 格式：Unlink <key> [<key> ...]
+///
+/// 不存在的 key 会被忽略。key 会被立即删除，但是很大的 value 会在后台释放内存，
+/// 所以删除很大的 value 也不会阻塞其他连接。
+#[derive(Debug)]
+pub struct Unlink {
+    keys: Vec<String>,
+}
+
+impl Unlink {
+    /// 创建一个`Unlink`命令。
+    pub fn new(keys: Vec<String>) -> Unlink {
+        Unlink { keys }
+    }
+
+    /// 获取所有 key。
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// 通过`Parse`将`Frame`解析为`Unlink`命令。
+    ///
+    /// 需要保证字符串`Unlink`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Unlink> {
+        let mut keys = vec![parse.next_string()?];
+        while parse.remaining() > 0 {
+            keys.push(parse.next_string()?);
+        }
+        Ok(Unlink { keys })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Integer(db.unlink(&self.keys) as i64);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("unlink".as_bytes()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        frame
+    }
+}
@@ -0,0 +1,838 @@
+use std::{future, ops::Bound, time::Duration};
+
+use bytes::Bytes;
+use tokio::time;
+
+use super::list::{check_mpop_args, next_keys, next_mpop_count, next_timeout};
+use crate::{
+    shutdown::Shutdown,
+    zset::{format_score, Score},
+    Connection, Db, Frame, Parse, ParseError, Protocol,
+};
+
+/// 向有序集合中添加成员，返回新增的成员的数量。
+///
+/// 格式：ZAdd <key> <score> <member> [<score> <member> ...]
+///
+/// 已经存在的成员会更新分数。如果 key 不存在，会先创建一个空的有序集合。
+/// 分数是双精度浮点数，可以是`inf`、`+inf`或者`-inf`。
+#[derive(Debug)]
+pub struct ZAdd {
+    key: String,
+    members: Vec<(f64, Bytes)>,
+}
+
+/// 获取有序集合中成员的分数。
+///
+/// 格式：ZScore <key> <member>
+///
+/// 分数以`Double`返回，RESP2 的连接中为字符串。如果 key 或者成员不存在，返回`(nil)`。
+#[derive(Debug)]
+pub struct ZScore {
+    key: String,
+    member: Bytes,
+}
+
+/// 获取有序集合中排名在`[start, stop]`之间的成员，按分数从小到大排序。
+///
+/// 格式：ZRange <key> <start> <stop> [WITHSCORES]
+///
+/// 下标可以是负数，规则与`LRange`相同。设置了`WITHSCORES`时，
+/// RESP2 的连接中返回的数组中成员和分数交替出现，
+/// RESP3 的连接中每个元素都是`[member, score]`，分数为`Double`。
+#[derive(Debug)]
+pub struct ZRange {
+    key: String,
+    start: i64,
+    stop: i64,
+    with_scores: bool,
+}
+
+/// 获取有序集合的成员数量。
+///
+/// 格式：ZCard <key>
+///
+/// 如果 key 不存在，返回`0`。
+#[derive(Debug)]
+pub struct ZCard {
+    key: String,
+}
+
+/// 获取有序集合中分数在`min`和`max`之间的成员，按分数从小到大排序。
+///
+/// 格式：ZRangeByScore <key> <min> <max> [WITHSCORES]
+///
+/// 默认包含边界，在分数前加上`(`表示不包含边界，例如`(1`。
+/// `-inf`和`+inf`分别表示没有下界和上界。设置了`WITHSCORES`时，响应格式与`ZRange`相同。
+#[derive(Debug)]
+pub struct ZRangeByScore {
+    key: String,
+    min: Bound<f64>,
+    max: Bound<f64>,
+    with_scores: bool,
+}
+
+/// 将有序集合中成员的分数加上`increment`，返回新的分数。
+///
+/// 格式：ZIncrBy <key> <increment> <member>
+///
+/// 如果成员不存在，视为分数为`0`。`increment`可以是负数。新的分数与`ZScore`一样以`Double`返回。
+#[derive(Debug)]
+pub struct ZIncrBy {
+    key: String,
+    increment: f64,
+    member: Bytes,
+}
+
+/// 从有序集合中删除成员，返回被删除的成员的数量。
+///
+/// 格式：ZRem <key> <member> [<member> ...]
+///
+/// 不存在的成员会被忽略。有序集合被删空后，key 也会被删除。
+#[derive(Debug)]
+pub struct ZRem {
+    key: String,
+    members: Vec<Bytes>,
+}
+
+/// 获取成员在有序集合中的排名，排名从`0`开始，按分数从小到大。
+///
+/// 格式：ZRank <key> <member>
+///
+/// 如果 key 或者成员不存在，返回`(nil)`。
+#[derive(Debug)]
+pub struct ZRank {
+    key: String,
+    member: Bytes,
+}
+
+/// 依次检查每个 key，从第一个非空的有序集合中弹出最多`count`个分数最小或者最大的成员，
+/// 返回 key 和弹出的成员和分数。
+///
+/// 格式：ZMPop <numkeys> <key> [<key> ...] <Min|Max> [Count <count>]
+///      BZMPop <timeout> <numkeys> <key> [<key> ...] <Min|Max> [Count <count>]
+///
+/// `count`默认为`1`。如果所有有序集合都为空，`ZMPop`返回`(nil)`；`BZMPop`会阻塞直到有成员被添加，
+/// 或者超过`timeout`秒后返回`(nil)`，`timeout`的规则与`BLPop`相同。
+#[derive(Debug)]
+pub struct ZMPop {
+    keys: Vec<String>,
+    // 为`true`时弹出分数最小的成员，对应`Min`。
+    min: bool,
+    count: u64,
+    // 为`true`时对应`BZMPop`。
+    blocking: bool,
+    // `BZMPop`的超时时间，为`None`时一直阻塞。
+    timeout: Option<Duration>,
+}
+
+impl ZAdd {
+    /// 创建一个`ZAdd`命令，`members`中的每一项为分数和成员。
+    pub fn new(key: impl ToString, members: Vec<(f64, Bytes)>) -> ZAdd {
+        ZAdd {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ZAdd`命令。
+    ///
+    /// 需要保证字符串`ZAdd`已经被处理过了，并且分数和成员是成对的。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZAdd> {
+        let key = parse.next_string()?;
+        let mut members = vec![];
+        while parse.remaining() > 0 {
+            let score = next_score(parse)?;
+            let member = parse.next_bytes()?;
+            members.push((score, member));
+        }
+        Ok(ZAdd { key, members })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let members: Option<Vec<_>> = self
+            .members
+            .into_iter()
+            .map(|(score, member)| Score::new(score).map(|score| (score, member)))
+            .collect();
+        let response = match members {
+            Some(members) => match db.zadd(&self.key, members) {
+                Ok(added) => Frame::Integer(added as i64),
+                Err(err) => Frame::Error(err.to_string()),
+            },
+            None => Frame::Error("ERR value is not a valid float".to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zadd".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for (score, member) in self.members {
+            frame.push_bulk(Bytes::from(format_score(score)));
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl ZScore {
+    /// 创建一个`ZScore`命令。
+    pub fn new(key: impl ToString, member: Bytes) -> ZScore {
+        ZScore {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ZScore`命令。
+    ///
+    /// 需要保证字符串`ZScore`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZScore> {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+        Ok(ZScore { key, member })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zscore(&self.key, &self.member) {
+            Ok(Some(score)) => Frame::Double(score),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zscore".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
+
+impl ZRange {
+    /// 创建一个`ZRange`命令，`with_scores`为`true`时同时返回分数。
+    pub fn new(key: impl ToString, start: i64, stop: i64, with_scores: bool) -> ZRange {
+        ZRange {
+            key: key.to_string(),
+            start,
+            stop,
+            with_scores,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ZRange`命令。
+    ///
+    /// 需要保证字符串`ZRange`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRange> {
+        let key = parse.next_string()?;
+        let start = parse.next_signed_int()?;
+        let stop = parse.next_signed_int()?;
+        let with_scores = match parse.next_string() {
+            Ok(option) if option.eq_ignore_ascii_case("withscores") => true,
+            Ok(_) => return Err(crate::Error::invalid("syntax error")),
+            Err(ParseError::EndOfStream) => false,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(ZRange {
+            key,
+            start,
+            stop,
+            with_scores,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zrange(&self.key, self.start, self.stop) {
+            Ok(members) => members_frame(members, self.with_scores, dst.protocol()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrange".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.start.to_string()));
+        frame.push_bulk(Bytes::from(self.stop.to_string()));
+        if self.with_scores {
+            frame.push_bulk(Bytes::from("withscores".as_bytes()));
+        }
+        frame
+    }
+}
+
+impl ZCard {
+    /// 创建一个`ZCard`命令。
+    pub fn new(key: impl ToString) -> ZCard {
+        ZCard {
+            key: key.to_string(),
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ZCard`命令。
+    ///
+    /// 需要保证字符串`ZCard`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZCard> {
+        let key = parse.next_string()?;
+        Ok(ZCard { key })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zcard(&self.key) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zcard".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl ZRangeByScore {
+    /// 创建一个`ZRangeByScore`命令，`with_scores`为`true`时同时返回分数。
+    pub fn new(
+        key: impl ToString,
+        min: Bound<f64>,
+        max: Bound<f64>,
+        with_scores: bool,
+    ) -> ZRangeByScore {
+        ZRangeByScore {
+            key: key.to_string(),
+            min,
+            max,
+            with_scores,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ZRangeByScore`命令。
+    ///
+    /// 需要保证字符串`ZRangeByScore`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRangeByScore> {
+        let key = parse.next_string()?;
+        let min = next_score_bound(parse)?;
+        let max = next_score_bound(parse)?;
+        let with_scores = match parse.next_string() {
+            Ok(option) if option.eq_ignore_ascii_case("withscores") => true,
+            Ok(_) => return Err(crate::Error::invalid("syntax error")),
+            Err(ParseError::EndOfStream) => false,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(ZRangeByScore {
+            key,
+            min,
+            max,
+            with_scores,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match (score_bound(self.min), score_bound(self.max)) {
+            (Some(min), Some(max)) => match db.zrangebyscore(&self.key, min, max) {
+                Ok(members) => members_frame(members, self.with_scores, dst.protocol()),
+                Err(err) => Frame::Error(err.to_string()),
+            },
+            _ => Frame::Error("ERR min or max is not a float".to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrangebyscore".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(format_score_bound(self.min, "-inf")));
+        frame.push_bulk(Bytes::from(format_score_bound(self.max, "+inf")));
+        if self.with_scores {
+            frame.push_bulk(Bytes::from("withscores".as_bytes()));
+        }
+        frame
+    }
+}
+
+impl ZIncrBy {
+    /// 创建一个`ZIncrBy`命令。
+    pub fn new(key: impl ToString, increment: f64, member: Bytes) -> ZIncrBy {
+        ZIncrBy {
+            key: key.to_string(),
+            increment,
+            member,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ZIncrBy`命令。
+    ///
+    /// 需要保证字符串`ZIncrBy`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZIncrBy> {
+        let key = parse.next_string()?;
+        let increment = next_score(parse)?;
+        let member = parse.next_bytes()?;
+        Ok(ZIncrBy {
+            key,
+            increment,
+            member,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zincrby(&self.key, self.increment, self.member) {
+            Ok(score) => Frame::Double(score),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zincrby".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(format_score(self.increment)));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
+
+impl ZRem {
+    /// 创建一个`ZRem`命令。
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> ZRem {
+        ZRem {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ZRem`命令。
+    ///
+    /// 需要保证字符串`ZRem`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRem> {
+        let key = parse.next_string()?;
+        let mut members = vec![];
+        while parse.remaining() > 0 {
+            members.push(parse.next_bytes()?);
+        }
+        Ok(ZRem { key, members })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zrem(&self.key, &self.members) {
+            Ok(removed) => Frame::Integer(removed as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrem".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for member in self.members {
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl ZRank {
+    /// 创建一个`ZRank`命令。
+    pub fn new(key: impl ToString, member: Bytes) -> ZRank {
+        ZRank {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ZRank`命令。
+    ///
+    /// 需要保证字符串`ZRank`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRank> {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+        Ok(ZRank { key, member })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zrank(&self.key, &self.member) {
+            Ok(Some(rank)) => Frame::Integer(rank as i64),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrank".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
+
+/// 读取一个分数，支持`inf`、`+inf`和`-inf`，不能是 NaN。
+fn next_score(parse: &mut Parse) -> crate::Result<f64> {
+    parse
+        .next_string()?
+        .parse::<f64>()
+        .ok()
+        .filter(|score| !score.is_nan())
+        .ok_or_else(|| crate::Error::invalid("value is not a valid float"))
+}
+
+/// 读取一个分数区间的边界，以`(`开头表示不包含边界。
+fn next_score_bound(parse: &mut Parse) -> crate::Result<Bound<f64>> {
+    let bound = parse.next_string()?;
+    let (score, exclusive) = match bound.strip_prefix('(') {
+        Some(score) => (score, true),
+        None => (&bound[..], false),
+    };
+    let score = score
+        .parse::<f64>()
+        .ok()
+        .filter(|score| !score.is_nan())
+        .ok_or_else(|| crate::Error::invalid("min or max is not a float"))?;
+    if exclusive {
+        Ok(Bound::Excluded(score))
+    } else {
+        Ok(Bound::Included(score))
+    }
+}
+
+/// 将分数区间的边界转换为`Score`，如果分数是 NaN，返回`None`。
+fn score_bound(bound: Bound<f64>) -> Option<Bound<Score>> {
+    match bound {
+        Bound::Included(score) => Score::new(score).map(Bound::Included),
+        Bound::Excluded(score) => Score::new(score).map(Bound::Excluded),
+        Bound::Unbounded => Some(Bound::Unbounded),
+    }
+}
+
+/// 将分数区间的边界格式化为字符串，`unbounded`是没有边界时使用的字符串。
+fn format_score_bound(bound: Bound<f64>, unbounded: &str) -> String {
+    match bound {
+        Bound::Included(score) => format_score(score),
+        Bound::Excluded(score) => format!("({}", format_score(score)),
+        Bound::Unbounded => unbounded.to_string(),
+    }
+}
+
+/// 将成员（以及分数）编码为数组。
+///
+/// `with_scores`为`true`时，RESP3 的连接中每个元素都是`[member, score]`，
+/// RESP2 的连接中成员和分数交替出现，与 Redis 相同。
+fn members_frame(members: Vec<(Bytes, f64)>, with_scores: bool, protocol: Protocol) -> Frame {
+    let mut items = Vec::with_capacity(members.len());
+    for (member, score) in members {
+        if with_scores && protocol == Protocol::Resp3 {
+            items.push(Frame::Array(vec![
+                Frame::Bulk(member),
+                Frame::Double(score),
+            ]));
+        } else {
+            items.push(Frame::Bulk(member));
+            if with_scores {
+                items.push(Frame::Bulk(Bytes::from(format_score(score))));
+            }
+        }
+    }
+    Frame::Array(items)
+}
+
+impl ZMPop {
+    /// 创建一个`ZMPop`命令，`min`为`true`时弹出分数最小的成员。
+    pub fn new(keys: Vec<String>, min: bool, count: u64) -> ZMPop {
+        ZMPop {
+            keys,
+            min,
+            count,
+            blocking: false,
+            timeout: None,
+        }
+    }
+
+    /// 创建一个`BZMPop`命令，`timeout`为`None`时一直阻塞。
+    pub fn blocking(keys: Vec<String>, min: bool, count: u64, timeout: Option<Duration>) -> ZMPop {
+        ZMPop {
+            keys,
+            min,
+            count,
+            blocking: true,
+            timeout,
+        }
+    }
+
+    /// 获取所有 key。
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// 获取命令名称。
+    pub(crate) fn get_name(&self) -> &str {
+        if self.blocking {
+            "bzmpop"
+        } else {
+            "zmpop"
+        }
+    }
+
+    /// 是否为阻塞的`BZMPop`。
+    pub(crate) fn is_blocking(&self) -> bool {
+        self.blocking
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ZMPop`命令，`blocking`为`true`时解析为`BZMPop`。
+    ///
+    /// 需要保证命令名称已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse, blocking: bool) -> crate::Result<ZMPop> {
+        let timeout = if blocking { next_timeout(parse)? } else { None };
+        let keys = next_keys(parse)?;
+        let min = match &parse.next_string()?.to_lowercase()[..] {
+            "min" => true,
+            "max" => false,
+            _ => return Err(crate::Error::invalid("syntax error")),
+        };
+        let count = next_mpop_count(parse)?;
+        Ok(ZMPop {
+            keys,
+            min,
+            count,
+            blocking,
+            timeout,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    ///
+    /// 阻塞期间如果服务器关闭，直接返回，不会写回响应。
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let response = if let Some(err) = check_mpop_args(&self.keys, self.count) {
+            Frame::Error(err.to_string())
+        } else {
+            let count = usize::try_from(self.count).unwrap_or(usize::MAX);
+            let result = if self.blocking {
+                let timeout = async {
+                    match self.timeout {
+                        Some(timeout) => time::sleep(timeout).await,
+                        None => future::pending().await,
+                    }
+                };
+                tokio::select! {
+                    res = db.blocking_zmpop(&self.keys, self.min, count) => res.map(Some),
+                    // 超时。
+                    _ = timeout => Ok(None),
+                    _ = shutdown.recv() => return Ok(()),
+                }
+            } else {
+                db.zmpop(&self.keys, self.min, count)
+            };
+
+            match result {
+                Ok(Some((key, members))) => Frame::Array(vec![
+                    Frame::Bulk(Bytes::from(key.into_bytes())),
+                    Frame::Array(
+                        members
+                            .into_iter()
+                            .map(|(member, score)| {
+                                Frame::Array(vec![
+                                    Frame::Bulk(member),
+                                    Frame::Bulk(Bytes::from(format_score(score))),
+                                ])
+                            })
+                            .collect(),
+                    ),
+                ]),
+                Ok(None) => Frame::Null,
+                Err(err) => Frame::Error(err.to_string()),
+            }
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().to_string()));
+        if self.blocking {
+            let timeout = self.timeout.map(|t| t.as_secs_f64()).unwrap_or(0.0);
+            frame.push_bulk(Bytes::from(timeout.to_string()));
+        }
+        frame.push_bulk(Bytes::from(self.keys.len().to_string()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        let order = if self.min { "min" } else { "max" };
+        frame.push_bulk(Bytes::from(order.as_bytes()));
+        frame.push_bulk(Bytes::from("count".as_bytes()));
+        frame.push_bulk(Bytes::from(self.count.to_string()));
+        frame
+    }
+}
+
+/// 随机获取有序集合中的成员。
+///
+/// 格式：ZRandMember <key> [<count> [WITHSCORES]]
+///
+/// 没有`count`时返回一个成员，key 不存在时返回`(nil)`。`count`的含义与`SRandMember`相同。
+/// 指定`WITHSCORES`时，返回的数组中成员和分数交替出现。
+#[derive(Debug)]
+pub struct ZRandMember {
+    key: String,
+    count: Option<i64>,
+    with_scores: bool,
+}
+
+impl ZRandMember {
+    /// 创建一个`ZRandMember`命令，`count`为`None`时只获取一个成员，此时`with_scores`会被忽略。
+    pub fn new(key: impl ToString, count: Option<i64>, with_scores: bool) -> ZRandMember {
+        ZRandMember {
+            key: key.to_string(),
+            count,
+            with_scores: count.is_some() && with_scores,
+        }
+    }
+
+    /// 获取 key 值。
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 通过`Parse`将`Frame`解析为`ZRandMember`命令。
+    ///
+    /// 需要保证字符串`ZRandMember`已经被处理过了。
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRandMember> {
+        let key = parse.next_string()?;
+        let count = match parse.next_signed_int() {
+            Ok(count) => Some(count),
+            Err(ParseError::EndOfStream) => None,
+            Err(err) => return Err(err.into()),
+        };
+        let with_scores = if parse.remaining() > 0 {
+            match &parse.next_string()?.to_lowercase()[..] {
+                "withscores" => true,
+                _ => return Err(crate::Error::invalid("syntax error")),
+            }
+        } else {
+            false
+        };
+        Ok(ZRandMember {
+            key,
+            count,
+            with_scores,
+        })
+    }
+
+    /// 应用命令并写回响应数据。
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zrandmember(&self.key, self.count.unwrap_or(1)) {
+            Ok(members) if self.count.is_some() => {
+                let mut frame = Frame::array();
+                for (member, score) in members {
+                    frame.push_bulk(member);
+                    if self.with_scores {
+                        frame.push_bulk(Bytes::from(format_score(score)));
+                    }
+                }
+                frame
+            }
+            Ok(members) => match members.into_iter().next() {
+                Some((member, _)) => Frame::Bulk(member),
+                None => Frame::Null,
+            },
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// 将命令转化为等价的`Frame`。
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrandmember".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from(count.to_string()));
+        }
+        if self.with_scores {
+            frame.push_bulk(Bytes::from("withscores".as_bytes()));
+        }
+        frame
+    }
+}
@@ -0,0 +1,178 @@
+//! 服务器的运行时配置。
+//!
+//! 所有的配置项都登记在`PARAMETERS`中，通过`Config Get`读取、`Config Set`修改，
+//! 修改后立即生效。慢命令日志、延迟监控、快照等模块的配置保存在各自的模块中，
+//! 没有所属模块的配置保存在`ServerConfig`中。
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use crate::{glob::glob_match, Db};
+
+/// 内存超过`maxmemory`时，带有`denyoom`标志的命令返回的错误信息。
+pub(crate) const OOM_ERROR: &str = "OOM command not allowed when used memory > 'maxmemory'.";
+
+/// 没有所属模块的配置，所有连接共享。
+///
+/// 每条命令都可能读取配置，所以使用原子变量，不需要加锁。
+#[derive(Debug)]
+pub(crate) struct ServerConfig {
+    // 最多使用的内存，单位是字节，`0`表示不限制。
+    maxmemory: AtomicU64,
+
+    // 连接空闲多少秒后关闭，`0`表示不关闭。
+    timeout: AtomicU64,
+}
+
+impl ServerConfig {
+    pub(crate) fn new() -> ServerConfig {
+        ServerConfig {
+            maxmemory: AtomicU64::new(0),
+            timeout: AtomicU64::new(0),
+        }
+    }
+
+    /// 获取最多使用的内存，单位是字节，`0`表示不限制。
+    pub(crate) fn maxmemory(&self) -> u64 {
+        self.maxmemory.load(Ordering::Relaxed)
+    }
+
+    /// 获取连接的空闲超时时间，`None`表示不关闭空闲的连接。
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        match self.timeout.load(Ordering::Relaxed) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+}
+
+/// 一个配置项。
+struct Parameter {
+    name: &'static str,
+    get: fn(&Db) -> String,
+    // 值不合法时返回`Err`。
+    set: fn(&Db, &str) -> Result<(), ()>,
+}
+
+/// 支持的配置项，`Config Get`按照这个顺序返回。
+const PARAMETERS: &[Parameter] = &[
+    Parameter {
+        name: "slowlog-log-slower-than",
+        get: |db| db.slowlog().threshold_us().to_string(),
+        set: |db, value| {
+            db.slowlog()
+                .set_threshold_us(value.parse().map_err(|_| ())?);
+            Ok(())
+        },
+    },
+    Parameter {
+        name: "slowlog-max-len",
+        get: |db| db.slowlog().max_len().to_string(),
+        set: |db, value| {
+            db.slowlog().set_max_len(value.parse().map_err(|_| ())?);
+            Ok(())
+        },
+    },
+    Parameter {
+        name: "latency-monitor-threshold",
+        get: |db| db.latency().threshold_ms().to_string(),
+        set: |db, value| {
+            db.latency()
+                .set_threshold_ms(value.parse().map_err(|_| ())?);
+            Ok(())
+        },
+    },
+    Parameter {
+        name: "dbfilename",
+        get: |db| db.persistence().dbfilename().to_string_lossy().into_owned(),
+        set: |db, value| {
+            if value.is_empty() {
+                return Err(());
+            }
+            db.persistence().set_dbfilename(value.into());
+            Ok(())
+        },
+    },
+    Parameter {
+        name: "save",
+        get: |db| db.persistence().rules(),
+        set: |db, value| db.persistence().set_rules(value),
+    },
+    Parameter {
+        name: "maxmemory",
+        get: |db| db.config().maxmemory().to_string(),
+        set: |db, value| {
+            let bytes = parse_memory(value).ok_or(())?;
+            db.config().maxmemory.store(bytes, Ordering::Relaxed);
+            Ok(())
+        },
+    },
+    Parameter {
+        name: "maxclients",
+        get: |db| db.clients().maxclients().to_string(),
+        set: |db, value| {
+            match value.parse() {
+                Ok(maxclients) if maxclients > 0 => db.clients().set_maxclients(maxclients),
+                _ => return Err(()),
+            }
+            Ok(())
+        },
+    },
+    Parameter {
+        name: "timeout",
+        get: |db| db.config().timeout.load(Ordering::Relaxed).to_string(),
+        set: |db, value| {
+            let secs = value.parse().map_err(|_| ())?;
+            db.config().timeout.store(secs, Ordering::Relaxed);
+            Ok(())
+        },
+    },
+];
+
+/// 获取名称匹配 glob 风格的`pattern`的配置项及其值。
+pub(crate) fn get(db: &Db, pattern: &str) -> Vec<(&'static str, String)> {
+    PARAMETERS
+        .iter()
+        .filter(|param| glob_match(pattern.as_bytes(), param.name.as_bytes()))
+        .map(|param| (param.name, (param.get)(db)))
+        .collect()
+}
+
+/// 修改配置项的值。
+///
+/// # Errors
+/// 如果配置项不存在或者值不合法，返回错误信息。
+pub(crate) fn set(db: &Db, name: &str, value: &str) -> Result<(), String> {
+    let param = PARAMETERS
+        .iter()
+        .find(|param| param.name == name)
+        .ok_or_else(|| {
+            format!(
+                "ERR Unknown option or number of arguments for CONFIG SET - '{}'",
+                name
+            )
+        })?;
+    (param.set)(db, value)
+        .map_err(|()| format!("ERR Invalid argument '{}' for CONFIG SET '{}'", value, name))
+}
+
+/// 解析内存大小，与 Redis 一样支持`k`、`kb`、`m`、`mb`、`g`、`gb`后缀，不区分大小写。
+///
+/// `k`表示 1000 字节，`kb`表示 1024 字节，以此类推。
+fn parse_memory(value: &str) -> Option<u64> {
+    let value = value.to_lowercase();
+    let digits = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = match &value[digits.len()..] {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    digits.parse::<u64>().ok()?.checked_mul(unit)
+}
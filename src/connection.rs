@@ -1,37 +1,181 @@
-use std::io::{self, Cursor};
+use std::{fmt, io, io::Cursor, time::Duration};
 
-use bytes::{Buf, BytesMut};
+use bytes::{Buf, Bytes, BytesMut};
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt, BufWriter},
-    net::TcpStream,
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    time::{self, Instant},
 };
 
-use crate::Frame;
+use crate::{frame, Frame, FrameLimits, Protocol};
 
 /// 发送和接收`Frame`值。
 ///
 /// 当实现网络协议的时候，一个协议信息通常是由多个更小的称为帧的信息组成的。
-/// `Connection`的目的就是从底层`TcpStream`中读取`Frame`或向其写入`Frame`。
+/// `Connection`的目的就是从底层传输层中读取`Frame`或向其写入`Frame`。
+///
+/// 底层传输层可以是`TcpStream`、`UnixStream`或者 TLS 流，只要实现了`Transport`即可。
 #[derive(Debug)]
 pub struct Connection {
-    // `Tcpstream`用`BufWriter`封装，目的是提供异步的缓存写。
-    stream: BufWriter<TcpStream>,
+    // 使用特征对象而不是泛型，这样`Handler`、`Client`以及各个命令都不需要关心具体的传输层。
+    stream: Box<dyn Transport>,
 
     // 读取帧时用到的缓存。`BytesMut`实现了 BufMut trait，
     // 它会在需要的时候隐式地扩大空间。
     buffer: BytesMut,
+
+    // 写入帧时用到的缓存，`Frame`先被完整地编码到这里，再一次性写入传输层。
+    write_buffer: BytesMut,
+
+    // 收到一个帧的第一部分数据后，需要在这个时间内收到完整的帧，`None`表示不限制。
+    // 等待下一个帧的开始时不计时，空闲的连接由`Handler`处理。
+    read_timeout: Option<Duration>,
+
+    // 写入并刷新一个帧的最长时间，`None`表示不限制。
+    write_timeout: Option<Duration>,
+
+    // 连接使用的协议版本，决定 RESP3 类型的`Frame`如何编码，由`Hello`切换。
+    protocol: Protocol,
+
+    // 长度不小于这个值的`Bulk`会被流式地读取和写入，见`read_frame_streaming()`。
+    stream_threshold: usize,
+
+    // 正在流式读取的`Bulk`还没有读取的数据的长度，不包括末尾的`\r\n`。
+    bulk_remaining: Option<usize>,
+
+    // 解析`Frame`时的长度和嵌套层数限制。
+    limits: FrameLimits,
+
+    // 写入`Frame`后何时刷新。
+    flush_policy: FlushPolicy,
+
+    // 写缓存中是否有暂时没有写入传输层的`Frame`，见`FlushPolicy::Batch`。
+    deferred: bool,
+
+    // 是否写入过`Frame::Error`，命令通过它判断是否执行失败，见`take_error_written()`。
+    error_written: bool,
 }
 
+/// 写入`Frame`后何时刷新，见`Connection::set_flush_policy()`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushPolicy {
+    /// 每次写入`Frame`后立即刷新。
+    #[default]
+    Immediate,
+
+    /// 读缓存中还有完整的`Frame`时，写入的`Frame`暂时留在写缓存中，与之后的`Frame`一起刷新。
+    /// 流水线发送的多条命令的响应只需要一次写入，积压的数据超过 64KB 时同样会刷新。
+    Batch,
+}
+
+/// `read_frame_streaming()`读取到的内容。
+#[derive(Debug)]
+pub enum StreamedFrame {
+    /// 一个完整的`Frame`。
+    Frame(Frame),
+
+    /// 一个大型`Bulk`的头部，参数为数据的长度。
+    /// 数据需要通过`read_bulk_chunk()`分块读取，读取完之前不能读取下一个`Frame`。
+    Bulk(usize),
+}
+
+/// 默认的流式读写的阈值，见`Connection::set_stream_threshold()`。
+pub const DEFAULT_STREAM_THRESHOLD: usize = 512 * 1024;
+
+/// 写缓存的初始大小，写入很大的`Frame`后，超过`MAX_RETAINED_WRITE_BUFFER`的写缓存会被释放。
+const WRITE_BUFFER_SIZE: usize = 8 * 1024;
+const MAX_RETAINED_WRITE_BUFFER: usize = 64 * 1024;
+
+/// `FlushPolicy::Batch`下写缓存中最多积压的字节数。
+const MAX_DEFERRED_SIZE: usize = 64 * 1024;
+
+/// 流式读取`Bulk`时，每次从 socket 读取的数据块的大小。
+const BULK_CHUNK_SIZE: usize = 64 * 1024;
+
+/// `Connection`可以使用的底层传输层。
+///
+/// 任何可以异步读写的字节流都自动实现了这个 trait。
+pub trait Transport: AsyncRead + AsyncWrite + Unpin + Send + fmt::Debug {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + fmt::Debug> Transport for T {}
+
 impl Connection {
     /// 创建一个`Connection`，同时初始化缓存。
-    pub fn new(socket: TcpStream) -> Connection {
+    pub fn new(socket: impl Transport + 'static) -> Connection {
         Connection {
-            stream: BufWriter::new(socket),
+            stream: Box::new(socket),
             // 使用4KB的读缓存即可，反正它会按照需要自动增长。
             buffer: BytesMut::with_capacity(4 * 1024),
+            write_buffer: BytesMut::with_capacity(WRITE_BUFFER_SIZE),
+            read_timeout: None,
+            write_timeout: None,
+            protocol: Protocol::Resp2,
+            stream_threshold: DEFAULT_STREAM_THRESHOLD,
+            bulk_remaining: None,
+            limits: FrameLimits::default(),
+            flush_policy: FlushPolicy::default(),
+            deferred: false,
+            error_written: false,
         }
     }
 
+    /// 设置写入`Frame`后何时刷新，默认为`FlushPolicy::Immediate`。
+    ///
+    /// 使用`FlushPolicy::Batch`时，写入的`Frame`可能留在写缓存中，
+    /// 在等待读缓存以外的事件之前需要调用`flush()`。
+    pub fn set_flush_policy(&mut self, policy: FlushPolicy) {
+        self.flush_policy = policy;
+    }
+
+    /// 设置解析`Frame`时的限制，超过限制的`Frame`会被拒绝，`read_frame()`返回协议错误。
+    ///
+    /// 流式读取的`Bulk`不会被完整地读入缓存，不受`max_bulk_len`的限制。
+    pub fn set_frame_limits(&mut self, limits: FrameLimits) {
+        self.limits = limits;
+    }
+
+    /// 设置流式读写的阈值，默认为`DEFAULT_STREAM_THRESHOLD`。
+    ///
+    /// `read_frame_streaming()`读取到长度不小于阈值的`Bulk`时分块交给调用者，
+    /// `read_command()`则分块读取这样的参数；
+    /// `write_frame()`写入长度不小于阈值的`Bulk`时直接写入传输层，不会先拷贝到写缓存中。
+    pub fn set_stream_threshold(&mut self, threshold: usize) {
+        self.stream_threshold = threshold;
+    }
+
+    /// 获取连接使用的协议版本。
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    /// 设置连接使用的协议版本，之后写入的`Frame`按照这个版本编码。
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = protocol;
+    }
+
+    /// 设置读取一个帧的超时时间，从收到这个帧的第一部分数据开始计时。
+    ///
+    /// 对端发送了不完整的帧之后停止发送，或者发送得太慢时，`read_frame()`返回`Error::Timeout`。
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    /// 设置写入一个帧的超时时间。
+    ///
+    /// 对端不再读取数据导致写入被阻塞时，`write_frame()`返回`io::ErrorKind::TimedOut`。
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_timeout = timeout;
+    }
+
+    /// 读缓存占用的字节数。
+    pub(crate) fn buffer_capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// 返回上一次调用之后是否写入过`Frame::Error`，并清除这个状态。
+    pub(crate) fn take_error_written(&mut self) -> bool {
+        std::mem::take(&mut self.error_written)
+    }
+
     /// 从连接中读取一个完整的`Frame`。
     ///
     /// 此函数会一直工作直到能读取到完整的`Frame`。假如读取到的数据不足以
@@ -41,157 +185,375 @@ impl Connection {
     /// 如果成功解析出`Frame`，返回`Ok(Some(frame))`；
     /// 如果 socket 正常关闭，没有数据了，返回`Ok(Some(None))`；
     /// 如果 socket 意外关闭，数据不完整，返回`Err`；
-    /// 如果 socket 读写发生错误也会返回`Err`。
+    /// 如果 socket 读写发生错误也会返回`Err`；
+    /// 如果设置了读取超时，没有在超时时间内收到完整的帧，返回`Error::Timeout`。
     pub async fn read_frame(&mut self) -> crate::Result<Option<Frame>> {
+        self.check_no_pending_bulk()?;
+        // 读取超时的截止时间，收到帧的第一部分数据时开始计时。
+        let mut deadline = None;
         loop {
             // 尝试从缓存中解析`Frame`，如果缓存中的数据完整，
             // 那么解析出的`Frame`将会被返回。
             if let Some(frame) = self.parse_frame()? {
                 return Ok(Some(frame));
             }
+            if deadline.is_none() && !self.buffer.is_empty() {
+                deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+            }
 
             // 如果缓存中没有足够的数据，尝试从 socket 中读取更多数据。
-            // 如果返回的值是`0`，表明 socket 中已经没有数据了。
-            if 0 == self.stream.read_buf(&mut self.buffer).await? {
+            if self.read_more(deadline).await? == 0 {
                 // 若已经达到了数据流的末尾，说明对方关闭了 socket。
                 // 如果缓存中没有数据，说明对方是正常关闭的。
                 // 否则说明有数据帧是不完整的，对方在发送的时候意外关闭了。
                 if self.buffer.is_empty() {
                     return Ok(None);
                 } else {
-                    return Err("连接意外关闭".into());
+                    return Err(crate::Error::ConnectionClosed);
                 }
             }
         }
     }
 
-    /// 尝试从缓存中解析`Frame`。
+    /// 读取客户端发送的一条命令，服务器使用这个方法代替`read_frame()`。
+    ///
+    /// 与`read_frame()`相同，但是命令中长度不小于阈值的`Bulk`参数不会被完整地读入读缓存，
+    /// 而是分块读取到一块大小正好的内存中，`Set`几百 MB 的 value 时读缓存不会扩大到同样的大小，
+    /// 也不需要在每次收到数据后重新解析整条命令。
+    ///
+    /// 开始逐个读取参数之后被取消时，剩下的数据无法再解析，连接不能再使用。
     ///
     /// # Errors
-    /// 如果发现是不合法的数据帧，返回`Err`；
-    /// 如果缓存中数据不完整，返回`Ok(None)`；如果解析成功，返回`Ok(Some(frame))`。
-    fn parse_frame(&mut self) -> crate::Result<Option<Frame>> {
-        use crate::frame::Error::Incomplete;
-
-        // `Cursor`顾名思义是一个“光标”，可以看作是缓存的指针，跟踪字节。
-        // `Cursor`实现了`bytes`库中的`Buf`，它提供了很多操作字节的工具。
-        // 我们将缓存用`Cursor`包装，方便使用。
-        let mut buf = Cursor::new(&self.buffer[..]);
+    /// 与`read_frame()`相同。
+    pub(crate) async fn read_command(&mut self) -> crate::Result<Option<Frame>> {
+        self.check_no_pending_bulk()?;
+        let mut deadline = None;
+        loop {
+            if let Some(frame) = self.parse_frame()? {
+                return Ok(Some(frame));
+            }
+            if deadline.is_none() && !self.buffer.is_empty() {
+                deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+            }
+            // 命令还不完整，但是数组的头部已经收到，改为逐个读取参数，
+            // 这样就能在读取到大型`Bulk`的头部时开始分块读取。
+            if let Some((header, len)) = frame::array_header(&self.buffer, &self.limits)? {
+                self.buffer.advance(header);
+                return self.read_args(len, deadline).await.map(Some);
+            }
+            if self.read_more(deadline).await? == 0 {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                } else {
+                    return Err(crate::Error::ConnectionClosed);
+                }
+            }
+        }
+    }
 
-        // 第一步检查是否有足够的数据来解析为一个数据帧。
-        // 这一步比真正的解析快很多，可以提高效率。
-        match Frame::check(&mut buf) {
-            Ok(_) => {
-                // 保留数据帧的字节长度。
-                let len = buf.position() as usize;
+    /// 逐个读取命令的`len`个参数，见`read_command()`。
+    async fn read_args(&mut self, len: usize, deadline: Option<Instant>) -> crate::Result<Frame> {
+        // 元素个数来自对端，不按照它预先分配内存。
+        let mut args = Vec::new();
+        while args.len() < len {
+            if let Some((header, len)) = frame::bulk_header(&self.buffer, &self.limits)? {
+                if len >= self.stream_threshold {
+                    self.buffer.advance(header);
+                    self.bulk_remaining = Some(len);
+                    let mut data = BytesMut::with_capacity(len);
+                    // 数据块复制后立即释放，读缓存可以重复使用同一块内存。
+                    while let Some(chunk) = self.read_bulk_chunk().await? {
+                        data.extend_from_slice(&chunk);
+                    }
+                    args.push(Frame::Bulk(data.freeze()));
+                    continue;
+                }
+            }
+            if let Some(frame) = self.parse_frame()? {
+                args.push(frame);
+                continue;
+            }
+            if self.read_more(deadline).await? == 0 {
+                return Err(crate::Error::ConnectionClosed);
+            }
+        }
+        Ok(Frame::Array(args))
+    }
 
-                // `check()`会将光标移动到帧的末尾，所以我们要在`parse()`
-                // 前将光标位置重置回去。
-                buf.set_position(0);
+    /// 与`read_frame()`相同，但是长度不小于阈值的顶层`Bulk`不会被完整地读入缓存，
+    /// 而是只读取头部，返回`StreamedFrame::Bulk(len)`，数据由调用者通过`read_bulk_chunk()`分块读取。
+    /// 这样读取几百 MB 的 value 时也不需要一块同样大小的连续内存。
+    ///
+    /// # Errors
+    /// 与`read_frame()`相同。上一个流式读取的`Bulk`还没有读取完时返回`Err`。
+    pub async fn read_frame_streaming(&mut self) -> crate::Result<Option<StreamedFrame>> {
+        self.check_no_pending_bulk()?;
+        let mut deadline = None;
+        loop {
+            if let Some((header, len)) = frame::bulk_header(&self.buffer, &self.limits)? {
+                if len >= self.stream_threshold {
+                    self.buffer.advance(header);
+                    self.bulk_remaining = Some(len);
+                    return Ok(Some(StreamedFrame::Bulk(len)));
+                }
+            }
+            if let Some(frame) = self.parse_frame()? {
+                return Ok(Some(StreamedFrame::Frame(frame)));
+            }
+            if deadline.is_none() && !self.buffer.is_empty() {
+                deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+            }
+            if self.read_more(deadline).await? == 0 {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                } else {
+                    return Err(crate::Error::ConnectionClosed);
+                }
+            }
+        }
+    }
 
-                // 真正完成解析任务的函数。。
-                // 如果解析成功，返回`Frame`，
-                // 如果解码的数据帧是不合法的，抛出错误。
-                let frame = Frame::parse(&mut buf)?;
+    /// 读取正在流式读取的`Bulk`的下一块数据。
+    ///
+    /// 每一块的大小取决于已经收到的数据，最多为读缓存的大小。设置了读取超时时，每一块分别计时。
+    ///
+    /// # Output
+    /// 返回`Ok(Some(chunk))`；数据读取完后返回`Ok(None)`，之后可以继续读取下一个`Frame`。
+    ///
+    /// # Errors
+    /// socket 在数据读取完之前关闭，或者数据之后不是`\r\n`时返回`Err`。
+    pub async fn read_bulk_chunk(&mut self) -> crate::Result<Option<Bytes>> {
+        let Some(remaining) = self.bulk_remaining else {
+            return Ok(None);
+        };
+        let deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+        // 数据之后还有`\r\n`。
+        let needed = if remaining == 0 { 2 } else { 1 };
+        // 分离出的数据块与读缓存共享内存，预留空间以免每次只读取很少的数据。
+        if self.buffer.len() < needed {
+            self.buffer.reserve(BULK_CHUNK_SIZE);
+        }
+        while self.buffer.len() < needed {
+            if self.read_more(deadline).await? == 0 {
+                return Err(crate::Error::ConnectionClosed);
+            }
+        }
+        if remaining == 0 {
+            if &self.buffer[..2] != b"\r\n" {
+                return Err(crate::Error::Protocol("不合法的帧格式".to_string()));
+            }
+            self.buffer.advance(2);
+            self.bulk_remaining = None;
+            return Ok(None);
+        }
+        let n = remaining.min(self.buffer.len());
+        self.bulk_remaining = Some(remaining - n);
+        Ok(Some(self.buffer.split_to(n).freeze()))
+    }
 
-                // 将已经处理过的数据从读缓存中移除。
-                // 当`advance()`被调用时，前面`len`长度的数据将被丢弃。
-                // 详细工作由`BytesMut`完成，可能是通过移动内置的光标，
-                // 也可能是通过内存重新分配和数据拷贝。
-                self.buffer.advance(len);
+    /// 流式读取的`Bulk`没有读取完时，不能读取下一个`Frame`。
+    fn check_no_pending_bulk(&self) -> crate::Result<()> {
+        match self.bulk_remaining {
+            Some(_) => Err(crate::Error::Protocol(
+                "流式读取的 Bulk 还没有读取完".to_string(),
+            )),
+            None => Ok(()),
+        }
+    }
 
-                Ok(Some(frame))
-            }
-            // 读缓存中没有足够的数据来解析，等待继续读取数据到缓存中。
-            // 我们不希望返回`Err`，因为它只是一种预期之中的运行状态。
-            Err(Incomplete) => Ok(None),
-            // 解析`Frame`时出现错误，返回`Err`，这最终会使得这个连接开始关闭。
-            Err(e) => Err(e.into()),
+    /// 从 socket 中读取更多数据到读缓存中，返回读取的字节数，`0`表示 socket 中已经没有数据了。
+    ///
+    /// TLS 的对端没有发送 close_notify 就关闭连接时会返回`UnexpectedEof`，同样视为到达末尾。
+    /// 到达`deadline`时返回`Error::Timeout`。
+    async fn read_more(&mut self, deadline: Option<Instant>) -> crate::Result<usize> {
+        let read = self.stream.read_buf(&mut self.buffer);
+        let res = match deadline {
+            Some(deadline) => time::timeout_at(deadline, read).await?,
+            None => read.await,
+        };
+        match res {
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(0),
+            res => Ok(res?),
         }
     }
 
-    /// 向底层`TcpStream`中写入`Frame`，这里是`Array Frame`。
+    /// 尝试从缓存中解析`Frame`，已经解析的数据会从读缓存中移除。
     ///
-    /// 我们使用`AsyncWrite`提供的写函数。之所以不使用`TcpStream`
-    /// 提供的写函数，是因为每次调用都会产生一次系统调用。而使用缓存
-    /// 可以让数据先写入缓存，然后等缓存满后再使用一次系统调用写入。
-    /// 需要注意的是，所有的数据都应该是字节数组，非字节数组的数据需要我们转换。
+    /// # Errors
+    /// 如果发现是不合法的数据帧，返回`Err`；
+    /// 如果缓存中数据不完整，返回`Ok(None)`；如果解析成功，返回`Ok(Some(frame))`。
+    fn parse_frame(&mut self) -> crate::Result<Option<Frame>> {
+        // 解析`Frame`时出现错误，返回`Err`，这最终会使得这个连接开始关闭。
+        Ok(Frame::decode_with(&mut self.buffer, &self.limits)?)
+    }
+
+    /// 向底层传输层中写入`Frame`并刷新。
+    ///
+    /// `Frame`按照连接的协议版本完整地编码到写缓存中，然后一次性写入传输层，
+    /// 这样无论`Frame`有多少个元素，都只需要很少的系统调用。
+    /// 使用`FlushPolicy::Batch`时可能只写入写缓存，见`set_flush_policy()`。
     ///
     /// # Errors
-    /// 异步写可能会出现 I/O 错误。
+    /// 异步写可能会出现 I/O 错误；如果设置了写入超时，超时后返回`io::ErrorKind::TimedOut`。
     pub async fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
-        match frame {
-            // 由于异步方法不支持递归调用，因此只能分开讨论。
-            Frame::Array(val) => {
-                self.stream.write_u8(b'*').await?;
-                self.write_decimal(val.len() as u64).await?;
-                for entry in val.iter() {
-                    self.write_value(entry).await?;
-                }
-            }
-            _ => self.write_value(frame).await?,
+        match self.write_timeout {
+            Some(timeout) => time::timeout(timeout, self.write_frame_inner(frame))
+                .await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "写入超时"))?,
+            None => self.write_frame_inner(frame).await,
         }
-        // 上面的调用实际上只是写入到缓存中。
-        // 下面的调用确保缓存中的数据都写入了 socket 中。
-        self.stream.flush().await
     }
 
-    /// 写入非`Array Frame`。
+    /// 依次写入多个`Frame`，全部写入后才刷新，用于流水线发送多条命令。
+    ///
+    /// 写缓存超过 64KB 时会提前写入传输层，命令很多时也不会占用大量内存。
     ///
     /// # Errors
-    /// 异步写可能会出现 I/O 错误。
-    async fn write_value(&mut self, frame: &Frame) -> io::Result<()> {
-        match frame {
-            Frame::Simple(val) => {
-                self.stream.write_u8(b'+').await?;
-                self.stream.write_all(val.as_bytes()).await?;
-                self.stream.write_all(b"\r\n").await?;
-            }
-            Frame::Error(val) => {
-                self.stream.write_u8(b'-').await?;
-                self.stream.write_all(val.as_bytes()).await?;
-                self.stream.write_all(b"\r\n").await?;
-            }
-            Frame::Integer(val) => {
-                self.stream.write_u8(b':').await?;
-                self.write_decimal(*val).await?;
-            }
-            Frame::Null => {
-                self.stream.write_all(b"_\r\n").await?;
+    /// 与`write_frame()`相同，写入超时限制的是整个操作。
+    pub async fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        match self.write_timeout {
+            Some(timeout) => time::timeout(timeout, self.write_frames_inner(frames))
+                .await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "写入超时"))?,
+            None => self.write_frames_inner(frames).await,
+        }
+    }
+
+    /// 写入多个`Frame`并刷新，不限制时间，见`write_frames()`。
+    async fn write_frames_inner(&mut self, frames: &[Frame]) -> io::Result<()> {
+        self.reset_write_buffer();
+        for frame in frames {
+            frame.encode_as(self.protocol, &mut self.write_buffer);
+            if self.write_buffer.len() >= MAX_DEFERRED_SIZE {
+                self.flush_write_buffer().await?;
             }
-            Frame::Bulk(val) => {
-                let len = val.len();
+        }
+        self.flush_inner().await
+    }
 
-                self.stream.write_u8(b'$').await?;
-                self.write_decimal(len as u64).await?;
-                self.stream.write_all(val).await?;
-                self.stream.write_all(b"\r\n").await?;
+    /// 将写缓存中积压的`Frame`写入传输层并刷新，见`FlushPolicy::Batch`。
+    ///
+    /// # Errors
+    /// 与`write_frame()`相同。
+    pub async fn flush(&mut self) -> io::Result<()> {
+        if !self.deferred {
+            return Ok(());
+        }
+        match self.write_timeout {
+            Some(timeout) => time::timeout(timeout, self.flush_inner())
+                .await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "写入超时"))?,
+            None => self.flush_inner().await,
+        }
+    }
+
+    /// 写入积压的`Frame`并刷新，不限制时间，见`flush()`。
+    async fn flush_inner(&mut self) -> io::Result<()> {
+        self.flush_write_buffer().await?;
+        self.stream.flush().await
+    }
+
+    /// 读缓存中是否已经有一个完整的`Frame`，即下一次`read_frame()`不需要等待 socket。
+    pub(crate) fn has_buffered_frame(&self) -> bool {
+        let mut src = Cursor::new(&self.buffer[..]);
+        Frame::check_with(&mut src, &self.limits).is_ok()
+    }
+
+    /// 写入`Frame`并刷新，不限制时间，见`write_frame()`。
+    async fn write_frame_inner(&mut self, frame: &Frame) -> io::Result<()> {
+        self.error_written |= matches!(frame, Frame::Error(_));
+        // 大型`Bulk`直接写入传输层，不拷贝到写缓存中，例如`Get`获取很大的 value。
+        if let Frame::Bulk(val) = frame {
+            if val.len() >= self.stream_threshold {
+                return self.write_bulk_from(val.len() as u64, &mut &val[..]).await;
             }
-            // 异步函数不支持递归，我们分开讨论`Array Frame`与其他。
-            // 我们的 Redis 也不需要支持嵌套的`Array Frame`，
-            // 所以这里不会执行到。
-            Frame::Array(_val) => unreachable!(),
         }
+        self.reset_write_buffer();
+        frame.encode_as(self.protocol, &mut self.write_buffer);
+        // 马上就能读取下一条命令时，响应留到之后一起刷新。
+        if self.flush_policy == FlushPolicy::Batch
+            && self.write_buffer.len() < MAX_DEFERRED_SIZE
+            && self.has_buffered_frame()
+        {
+            self.deferred = true;
+            return Ok(());
+        }
+        self.flush_inner().await
+    }
 
-        Ok(())
+    /// 在写缓存中编码新的数据之前调用，保留积压的`Frame`。
+    fn reset_write_buffer(&mut self) {
+        // 上一次写入被中断时，写缓存中可能还有数据，它们已经没有意义了。
+        if !self.deferred {
+            self.write_buffer.clear();
+        }
     }
 
-    /// 写入`u64`以及`\r\n`。
+    /// 写入一个`Bulk`并刷新，数据从`src`中读取，共`len`个字节。
+    ///
+    /// 数据分块从`src`拷贝到传输层，不需要将整个 value 放在内存中。这个函数不受写入超时的限制。
     ///
     /// # Errors
-    /// 异步写可能会出现 I/O 错误。
-    async fn write_decimal(&mut self, val: u64) -> io::Result<()> {
-        use std::io::Write;
+    /// 异步写可能会出现 I/O 错误；`src`中的数据少于`len`个字节时返回`io::ErrorKind::UnexpectedEof`。
+    pub async fn write_bulk_from<R>(&mut self, len: u64, src: &mut R) -> io::Result<()>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        self.reset_write_buffer();
+        self.write_streamed_bulk(len, src).await?;
+        self.stream.flush().await
+    }
 
-        let mut buf = [0u8; 20];
-        let mut buf = Cursor::new(&mut buf[..]);
-        // 转换为`String`然后写入到字节数组
-        write!(&mut buf, "{}", val)?;
+    /// 写入一条命令并刷新，命令的参数为`args`，最后一个参数从`src`中读取，共`len`个字节，
+    /// 例如`Set <key> <value>`的 value。
+    ///
+    /// # Errors
+    /// 与`write_bulk_from()`相同。
+    pub async fn write_command_from<R>(
+        &mut self,
+        args: &[Bytes],
+        len: u64,
+        src: &mut R,
+    ) -> io::Result<()>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        self.reset_write_buffer();
+        // 数组的头部和前面的参数先编码到写缓存中，与最后一个参数的头部一起写入。
+        frame::put_header(&mut self.write_buffer, b'*', args.len() as i64 + 1);
+        for arg in args {
+            Frame::Bulk(arg.clone()).encode_as(self.protocol, &mut self.write_buffer);
+        }
+        self.write_streamed_bulk(len, src).await?;
+        self.stream.flush().await
+    }
 
-        let pos = buf.position() as usize;
-        self.stream.write_all(&buf.get_ref()[..pos]).await?;
-        self.stream.write_all(b"\r\n").await?;
+    /// 写入写缓存中已有的数据，再写入`Bulk`的头部、从`src`中拷贝的数据以及末尾的`\r\n`，不会刷新。
+    async fn write_streamed_bulk<R>(&mut self, len: u64, src: &mut R) -> io::Result<()>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        frame::put_header(&mut self.write_buffer, b'$', len as i64);
+        self.flush_write_buffer().await?;
+        let copied = tokio::io::copy(&mut src.take(len), &mut self.stream).await?;
+        if copied < len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "数据少于 Bulk 的长度",
+            ));
+        }
+        self.stream.write_all(b"\r\n").await
+    }
 
+    /// 将写缓存中的数据写入传输层，不会刷新。
+    async fn flush_write_buffer(&mut self) -> io::Result<()> {
+        self.stream.write_all(&self.write_buffer).await?;
+        self.write_buffer.clear();
+        self.deferred = false;
+        // 写入很大的`Frame`之后不保留过大的写缓存。
+        if self.write_buffer.capacity() > MAX_RETAINED_WRITE_BUFFER {
+            self.write_buffer = BytesMut::with_capacity(WRITE_BUFFER_SIZE);
+        }
         Ok(())
     }
 }
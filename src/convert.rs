@@ -0,0 +1,119 @@
+//! 客户端的 value 与 Rust 类型之间的转换。
+//!
+//! `Client::set_value()`通过`ToValue`将数据转换为`Bytes`，`Client::get_value()`通过`FromValue`
+//! 将`Bytes`转换回来。数字按照 Redis 的习惯以十进制文本存储，这样`Incr`等命令也可以操作它们。
+//! 开启`json`特性后，`Client::set_json()`和`Client::get_json()`可以存储任何实现了`serde`的类型。
+
+use bytes::Bytes;
+
+/// 可以作为 value 存储的类型。
+pub trait ToValue {
+    /// 转换为存储的字节。
+    fn to_value(&self) -> Bytes;
+}
+
+/// 可以从存储的 value 转换而来的类型。
+pub trait FromValue: Sized {
+    /// 从存储的字节转换。
+    ///
+    /// # Errors
+    /// 字节无法转换为这个类型时返回`Err`，例如不是合法的 UTF-8 或者不是数字。
+    fn from_value(value: Bytes) -> crate::Result<Self>;
+}
+
+impl ToValue for Bytes {
+    fn to_value(&self) -> Bytes {
+        self.clone()
+    }
+}
+
+impl FromValue for Bytes {
+    fn from_value(value: Bytes) -> crate::Result<Bytes> {
+        Ok(value)
+    }
+}
+
+impl ToValue for [u8] {
+    fn to_value(&self) -> Bytes {
+        Bytes::copy_from_slice(self)
+    }
+}
+
+impl ToValue for Vec<u8> {
+    fn to_value(&self) -> Bytes {
+        Bytes::copy_from_slice(self)
+    }
+}
+
+impl FromValue for Vec<u8> {
+    fn from_value(value: Bytes) -> crate::Result<Vec<u8>> {
+        Ok(value.to_vec())
+    }
+}
+
+impl ToValue for str {
+    fn to_value(&self) -> Bytes {
+        Bytes::copy_from_slice(self.as_bytes())
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self) -> Bytes {
+        self.as_str().to_value()
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Bytes) -> crate::Result<String> {
+        String::from_utf8(value.to_vec()).map_err(|_| "value 不是合法的 UTF-8 字符串".into())
+    }
+}
+
+/// 数字以十进制文本存储，与`Incr`、`IncrByFloat`的格式相同。
+macro_rules! impl_number {
+    ($($ty:ty),*) => {
+        $(
+            impl ToValue for $ty {
+                fn to_value(&self) -> Bytes {
+                    Bytes::from(self.to_string())
+                }
+            }
+
+            impl FromValue for $ty {
+                fn from_value(value: Bytes) -> crate::Result<$ty> {
+                    std::str::from_utf8(&value)
+                        .ok()
+                        .and_then(|value| value.parse().ok())
+                        .ok_or_else(|| {
+                            format!("value 不是合法的 {}：{:?}", stringify!($ty), value).into()
+                        })
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+/// 布尔值与 Redis 的习惯一样存储为`1`和`0`。
+impl ToValue for bool {
+    fn to_value(&self) -> Bytes {
+        Bytes::from_static(if *self { b"1" } else { b"0" })
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Bytes) -> crate::Result<bool> {
+        match &value[..] {
+            b"1" => Ok(true),
+            b"0" => Ok(false),
+            _ => Err(format!("value 不是合法的布尔值：{:?}", value).into()),
+        }
+    }
+}
+
+impl<T: ToValue + ?Sized> ToValue for &T {
+    fn to_value(&self) -> Bytes {
+        (**self).to_value()
+    }
+}
@@ -1,14 +1,68 @@
 use std::{
-    collections::{BTreeSet, HashMap},
-    sync::{Arc, Mutex},
-    time::Duration,
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    mem,
+    ops::{Bound, Deref, DerefMut},
+    str,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, OnceLock,
+    },
+    thread,
+    time::{Duration, SystemTime},
 };
 
+use arc_swap::ArcSwapOption;
 use bytes::Bytes;
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::Rng;
 use tokio::{
-    sync::{broadcast, Notify},
+    sync::{broadcast, mpsc, Notify, RwLock},
     time::{self, Instant},
 };
+use tracing::{debug, debug_span, error, trace, Instrument};
+
+use crate::{
+    clients::Clients,
+    cluster::{self, ClusterState},
+    config::ServerConfig,
+    geo::{self, GeoMatch, Origin, Shape},
+    glob::glob_match,
+    latency::{self, LatencyMonitor},
+    monitor::Monitors,
+    snapshot::{self, SnapshotEntry},
+    storage::{self, StorageEvent, StorageHook},
+    stream::{self, Entries, Fields, GroupRead, NewId, Stream, StreamId},
+    zset::{Score, ScoredMembers, SortedSet},
+    Acl, Persistence, Replication, SlowLog, Value, INVALIDATE_CHANNEL,
+};
+
+/// 被删除或者被覆盖的 value 超过这个字节数时，在后台线程中释放内存。
+const LAZY_FREE_THRESHOLD: usize = 64 * 1024;
+
+/// 主动清除过期 key 时，每次持有分片的锁随机抽样检查的 key 的数量，与 Redis 相同。
+const ACTIVE_EXPIRE_SLICE_KEYS: usize = 20;
+
+/// 抽样中过期的 key 超过这个百分比时，认为分片中还有很多过期的 key，继续抽样。
+const ACTIVE_EXPIRE_STALE_PERCENT: usize = 25;
+
+/// 一轮主动清除最多占用的时间，超过后让出，等待`ACTIVE_EXPIRE_INTERVAL`后继续。
+const ACTIVE_EXPIRE_CYCLE_TIME: Duration = Duration::from_millis(25);
+
+/// 一轮主动清除没有完成时，距离下一轮的间隔。
+const ACTIVE_EXPIRE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 后台任务检查自动保存规则的间隔。
+const AUTO_SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 同一时刻的单调时钟和系统时钟，用于在`Instant`和系统时间之间转换。
+///
+/// 过期时间以`Instant`存储，不受系统时钟调整的影响；
+/// 快照和`ExpireAt`等命令使用的是 UNIX 时间戳，需要通过它来转换。
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    now: Instant,
+    system_now: SystemTime,
+}
 
 /// `Db`实例的包装类，它的创建是为了执行结束时的清理工作。
 ///
@@ -37,10 +91,20 @@ pub(crate) struct Db {
 /// 后台任务其实就是一个负责清理过期`Entry`的任务。
 #[derive(Debug)]
 struct Shared {
-    // 共享的数据状态由`Mutex`包裹，保证数据安全。这是一个`std::sync::Mutex`
-    // 而非 tokio 的`Mutex`，这是因为这里锁不需要在线程中传递（拥有锁的时候没有
-    // 异步操作），并且关键部分很小。
-    state: Mutex<State>,
+    // 数据被分成多个分片，每个分片由自己的`Mutex`包裹，保证数据安全。
+    // key 按照所在的槽分配到分片中，访问不同分片的连接不会互相阻塞。
+    // 这是一个`std::sync::Mutex`而非 tokio 的`Mutex`，这是因为这里锁不需要在线程中
+    // 传递（拥有锁的时候没有异步操作），并且关键部分很小。
+    //
+    // 同时访问多个分片时，按照下标从小到大的顺序加锁，避免死锁，见`ShardGuards`。
+    shards: Box<[Shard]>,
+
+    // 发布/订阅的信道和开启了客户端缓存追踪的连接，与 key 无关，所有分片共享。
+    // 持有分片的锁时可以获取它的锁，反过来则不行。
+    pubsub: Arc<Mutex<PubSub>>,
+
+    // 在所有`Db`都被 drop 的时候，这个值设置为`true`会告知后台任务退出。
+    shutdown: AtomicBool,
 
     // 通知后台任务。
     // 后台任务在下一个要清除的`Entry`的过期时间到来之前都处于休眠状态，
@@ -50,43 +114,301 @@ struct Shared {
     // 等待通知的状态。
     // 我们使用`Notify`不需要获取它的可变引用，不需要加锁。
     background_task: Notify,
+
+    // 慢命令日志，它自己负责同步，不需要`State`的锁。
+    slowlog: SlowLog,
+
+    // 延迟监控，它自己负责同步。
+    latency: LatencyMonitor,
+
+    // 执行了`Monitor`的连接，它自己负责同步。
+    monitors: Monitors,
+
+    // 后台任务是否主动清除过期的 key，通过`Debug Set-Active-Expire`修改。
+    // 关闭后过期的 key 仍然不可见，只是不会被主动删除。
+    active_expire: AtomicBool,
+
+    // 后台线程释放大 value 的统计，与每个分片共享。
+    lazy_free: Arc<LazyFreeStats>,
+
+    // 运行时配置，它自己负责同步。
+    config: ServerConfig,
+
+    // 已连接的客户端，它自己负责同步。
+    clients: Clients,
+
+    // 快照持久化的配置和状态，它自己负责同步。
+    persistence: Persistence,
+
+    // 主从复制的状态，它自己负责同步。
+    replication: Replication,
+
+    // ACL 用户，它自己负责同步。
+    acl: Acl,
+
+    // 集群模式下槽的分配，只能设置一次，没有设置时不开启集群模式。
+    cluster: OnceLock<ClusterState>,
+
+    // 缓存的脚本，key 为脚本的 SHA1 摘要，用于`EvalSha`。
+    scripts: Mutex<HashMap<String, String>>,
+
+    // 注册的函数库，key 为库的名称。
+    libraries: Mutex<HashMap<String, Library>>,
+
+    // 保证脚本原子地执行：执行脚本时持有写锁，执行其他命令时持有读锁。
+    // 脚本中的命令直接访问`Db`，不需要获取这个锁。
+    script_lock: RwLock<()>,
+
+    // 存储钩子的事件发送端，只能设置一次。
+    // 事件在持有分片的锁时发送，保证同一个 key 的事件的顺序与修改的顺序一致。
+    storage: OnceLock<mpsc::UnboundedSender<StorageEvent>>,
 }
 
-/// 数据状态，真正意义上的数据部分。
+/// 一个分片的数据状态，真正意义上的数据部分。
 ///
 /// 数据库会运行一个后台任务，这个后台任务负责清理过期的`Entry`。
 /// 显然我们不能让后台任务一直处于活跃状态，毕竟不是每时每刻都要进行清理工作。
 /// 所以我们让它休眠到下一个要被清理的`Entry`的过期时间，也就是说我们要维护一个
-/// 按照过期时间从小到大排序的集合，所以我们使用一个`BTreeSet`。
+/// 按照过期时间从小到大排序的集合，见`Expirations`。
 #[derive(Debug)]
 struct State {
-    // 用一个`HashMap`来存储 key-entry。
-    entries: HashMap<String, Entry>,
+    // 用一个`im::HashMap`来存储 key-entry。
+    // 这是一个持久化（写时复制）的`HashMap`，`clone()`的开销是 O(1) 的，
+    // 生成快照时只需要在持有锁的时候克隆一份，之后的修改不会影响快照，
+    // 序列化快照的过程也就不会阻塞写操作。
+    entries: im::HashMap<String, Entry>,
 
-    // 用一个`BTreeSet`来保存排好序的过期时间及对应的 key。
+    // 设置了过期时间的 key，按照过期时间排序，同时支持随机抽样。
     // 这能让后台程序方便地查看什么时候该开始清除过期 Entry。
-    expirations: BTreeSet<(Instant, String)>,
+    expirations: Expirations,
+
+    // 分片中所有 key 和 value 占用的近似字节数，在每次修改`entries`时维护。
+    used_memory: usize,
+
+    // 阻塞在列表或者流上的连接，key 为列表或者流的 key。
+    // 向列表中插入元素、向流中添加消息时，唤醒所有等待这个 key 的连接。
+    key_waiters: HashMap<String, Vec<Arc<Notify>>>,
 
+    // 被删除的大 value 通过它交给后台线程释放，见`State::free()`。
+    lazy_free: mpsc::UnboundedSender<Entry>,
+    lazy_free_stats: Arc<LazyFreeStats>,
+
+    // 被追踪的 key 以及读取过它的连接编号。
+    // key 被修改或者过期时从这里删除，并向`INVALIDATE_CHANNEL`发送失效信息，
+    // 连接需要再次读取这个 key 才会重新追踪。
+    tracking_table: HashMap<String, HashSet<u64>>,
+
+    // 与`Shared::pubsub`相同，发送失效信息时使用。
+    pubsub: Arc<Mutex<PubSub>>,
+}
+
+/// 后台线程释放大 value 的统计，见`State::free()`。
+#[derive(Debug, Default)]
+struct LazyFreeStats {
+    // 已经交给后台线程、还没有被释放的 value 的数量。
+    pending: AtomicUsize,
+    // 后台线程释放过的 value 的数量。
+    freed: AtomicU64,
+}
+
+/// 一个分片中设置了过期时间的 key。
+///
+/// `BTreeSet`保存排好序的过期时间及对应的 key，后台任务休眠到最早的过期时间；
+/// 另外用一个`Vec`保存同样的 key，主动清除时按下标随机抽样，与 Redis 一样。
+#[derive(Debug, Default)]
+struct Expirations {
+    by_deadline: BTreeSet<(Instant, String)>,
+
+    // 可以按下标随机访问的 key，删除时与最后一个交换。
+    keys: Vec<String>,
+
+    // key 在`keys`中的下标。
+    index: HashMap<String, usize>,
+}
+
+/// 一轮主动清除的结果，见`Shared::active_expire_cycle()`。
+enum ExpireCycle {
+    // 所有过期的`Entry`都被清除了，包含下一个应该被清除的`Entry`的过期时间。
+    Done(Option<Instant>),
+    // 时间用完了，包含没有完成的分片的下标，下一轮从这个分片开始。
+    Unfinished(usize),
+}
+
+/// 发布/订阅的状态，所有分片共享。
+#[derive(Debug, Default)]
+struct PubSub {
     // 存储信道名称和对应的广播的发送端。
     // 用于实现发布者/订阅者功能。
     pub_sub: HashMap<String, broadcast::Sender<Bytes>>,
 
-    // 在所有`Db`都被 drop 的时候，这个值设置为`true`会告知后台任务退出。
-    shutdown: bool,
+    // 存储 glob 模式和对应的广播的发送端，广播的信息为信道名称和内容。
+    // 发布信息时，发送给所有匹配信道名称的模式。
+    pattern_sub: HashMap<String, broadcast::Sender<(String, Bytes)>>,
+
+    // 存储分片信道名称和对应的广播的发送端，与`pub_sub`互相独立。
+    // 集群模式下分片信道与 key 一样属于某个槽。
+    shard_pub_sub: HashMap<String, broadcast::Sender<Bytes>>,
+
+    // 开启了客户端缓存追踪的连接编号。
+    tracking_clients: HashSet<u64>,
 }
 
-/// `HashMap`中 key-value 中的 value。
+/// 一个分片，包括分片的数据和读优化模式下发布的版本。
 #[derive(Debug)]
+struct Shard {
+    state: Mutex<State>,
+
+    // 读优化模式下发布的`entries`，没有开启时为`None`，见`Db::set_read_optimized()`。
+    // 修改了`entries`的操作在释放锁之前发布新的版本，`Db::get()`直接读取它，不需要获取锁。
+    // `entries`的克隆是 O(1) 的，发布的版本与分片中的数据共享未修改的部分。
+    published: ArcSwapOption<im::HashMap<String, Entry>>,
+}
+
+/// 分片的锁，被 drop 时如果`entries`被修改过，先发布新的版本再释放锁。
+///
+/// 在持有锁时发布，保证发布的顺序与修改的顺序一致。
+struct ShardGuard<'a> {
+    state: MutexGuard<'a, State>,
+    published: &'a ArcSwapOption<im::HashMap<String, Entry>>,
+}
+
+/// 同时持有多个分片的锁，用于涉及多个 key 的操作，见`Shared::lock_keys()`。
+struct ShardGuards<'a> {
+    shared: &'a Shared,
+    // 分片的下标和锁，按照下标从小到大排列。
+    guards: Vec<(usize, ShardGuard<'a>)>,
+}
+
+/// `Db::set()`写入数据的条件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum SetCondition {
+    /// 总是写入。
+    #[default]
+    Always,
+    /// 只在 key 不存在时写入，对应`NX`。
+    IfAbsent,
+    /// 只在 key 存在时写入，对应`XX`。
+    IfPresent,
+}
+
+/// `Db::set()`的选项。
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SetOptions {
+    /// 写入的条件。
+    pub(crate) condition: SetCondition,
+    /// 是否保留原有的过期时间，对应`KEEPTTL`。
+    pub(crate) keep_ttl: bool,
+    /// 是否返回原有的值，对应`GET`。
+    pub(crate) get: bool,
+}
+
+/// 通过`Function Load`注册的函数库。
+#[derive(Debug, Clone)]
+pub(crate) struct Library {
+    /// 库的名称。
+    pub(crate) name: String,
+    /// 库的源代码。
+    pub(crate) code: String,
+    /// 库中可以通过`FCall`调用的函数。
+    pub(crate) functions: Vec<String>,
+}
+
+/// 多个集合之间的运算。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SetOp {
+    /// 交集。
+    Inter,
+    /// 并集。
+    Union,
+    /// 差集，第一个集合减去其余的集合。
+    Diff,
+}
+
+/// 阻塞在一个或多个 key 上的连接，用于阻塞式地弹出列表元素和读取流。
+///
+/// 被 drop 时从`State::key_waiters`中移除自己。
+struct KeyWaiter {
+    db: Db,
+    keys: Vec<String>,
+    notify: Arc<Notify>,
+    // 是否已经注册到`State::key_waiters`中。
+    registered: bool,
+}
+
+/// `HashMap`中 key-value 中的 value。
+///
+/// `im::HashMap`要求 value 实现`Clone`，`Bytes`的复制开销很小。
+#[derive(Debug, Clone)]
 struct Entry {
     // 数据部分。
-    data: Bytes,
+    data: Value,
     // 过期时间。
     expires_at: Option<Instant>,
+    // key 和 value 占用的近似字节数，与`used_memory`同时维护，
+    // 这样删除 key 和`Memory Usage`都不需要遍历整个 value。
+    size: usize,
+    // 最后一次被访问的时刻，创建时为创建的时刻。
+    last_access: Instant,
+    // 被访问的次数。
+    access_count: u64,
+}
+
+/// 一个 key 的内部信息，用于`Debug Object`。
+#[derive(Debug)]
+pub(crate) struct EntryInfo {
+    pub(crate) value: Value,
+    // key 和 value 占用的近似字节数。
+    pub(crate) size: usize,
+    // 距离最后一次被访问的时长。
+    pub(crate) idle: Duration,
+    pub(crate) access_count: u64,
+    // 剩余的生存时间，`None`表示没有过期时间。
+    pub(crate) ttl: Option<Duration>,
+}
+
+/// 内存使用情况的统计，用于`Memory Stats`和`Info Memory`，单位都是字节。
+///
+/// 所有的值都是近似值，只统计数据本身的大小，不包括分配器和数据结构的额外开销。
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MemoryStats {
+    // key 和 value 占用的字节数，与`used_memory()`相同。
+    pub(crate) dataset: usize,
+    pub(crate) keys: usize,
+    // 设置了过期时间的 key 的数量。
+    pub(crate) expires: usize,
+    // 有订阅者的信道、模式和分片信道的数量。
+    pub(crate) pubsub_channels: usize,
+    pub(crate) pubsub_patterns: usize,
+    pub(crate) pubsub_shard_channels: usize,
+    // 上述信道和模式的名称占用的字节数。
+    pub(crate) pubsub: usize,
+    // 所有连接的读缓存占用的字节数。
+    pub(crate) clients: usize,
+    // 缓存的脚本和函数库的源代码占用的字节数。
+    pub(crate) scripts: usize,
+    // 等待后台线程释放的 value 的数量，以及已经释放的数量。
+    pub(crate) lazyfree_pending: usize,
+    pub(crate) lazyfreed: u64,
+}
+
+impl MemoryStats {
+    /// 数据以外的开销。
+    pub(crate) fn overhead(&self) -> usize {
+        self.pubsub + self.clients + self.scripts
+    }
+
+    /// 统计到的总字节数。
+    pub(crate) fn total(&self) -> usize {
+        self.dataset + self.overhead()
+    }
 }
 
 impl DbDropGuard {
-    pub(crate) fn new() -> DbDropGuard {
-        DbDropGuard { db: Db::new() }
+    /// 创建一个有`shards`个分片的数据库。
+    pub(crate) fn with_shards(shards: usize) -> DbDropGuard {
+        DbDropGuard {
+            db: Db::new(shards),
+        }
     }
 
     pub(crate) fn db(&self) -> Db {
@@ -98,24 +420,71 @@ impl Drop for DbDropGuard {
     fn drop(&mut self) {
         // 关闭后台任务。
         self.db.shutdown_purge_task();
+        // 复制任务持有`Db`，需要主动终止。
+        self.db.replication().set_primary(None);
     }
 }
 
 impl Db {
-    /// 创建一个新的、空的`Db`实例。创建共享状态并开启异步后台任务来清除过期 Entry。
-    pub(crate) fn new() -> Db {
+    /// 创建一个新的、空的`Db`实例，数据分为`shards`个分片，至少为 1 个。
+    ///
+    /// 创建共享状态并开启异步后台任务来清除过期 Entry。
+    pub(crate) fn new(shards: usize) -> Db {
+        let (lazy_free, lazy_free_rx) = mpsc::unbounded_channel();
+        let lazy_free_stats = Arc::new(LazyFreeStats::default());
+        let stats = lazy_free_stats.clone();
+        thread::Builder::new()
+            .name("lazy-free".to_string())
+            .spawn(move || lazy_free_thread(lazy_free_rx, stats))
+            .expect("无法创建后台线程");
+
+        let pubsub = Arc::new(Mutex::new(PubSub::default()));
+        let shards = (0..shards.max(1))
+            .map(|_| Shard {
+                state: Mutex::new(State {
+                    entries: im::HashMap::new(),
+                    expirations: Expirations::default(),
+                    used_memory: 0,
+                    key_waiters: HashMap::new(),
+                    lazy_free: lazy_free.clone(),
+                    lazy_free_stats: lazy_free_stats.clone(),
+                    tracking_table: HashMap::new(),
+                    pubsub: pubsub.clone(),
+                }),
+                published: ArcSwapOption::empty(),
+            })
+            .collect();
+
         let shared = Arc::new(Shared {
-            state: Mutex::new(State {
-                entries: HashMap::new(),
-                expirations: BTreeSet::new(),
-                pub_sub: HashMap::new(),
-                shutdown: false,
-            }),
+            shards,
+            pubsub,
+            shutdown: AtomicBool::new(false),
             background_task: Notify::new(),
+            slowlog: SlowLog::new(),
+            latency: LatencyMonitor::new(),
+            monitors: Monitors::new(),
+            active_expire: AtomicBool::new(true),
+            lazy_free: lazy_free_stats,
+            config: ServerConfig::new(),
+            clients: Clients::new(),
+            persistence: Persistence::new(),
+            replication: Replication::new(),
+            acl: Acl::new(),
+            cluster: OnceLock::new(),
+            scripts: Mutex::new(HashMap::new()),
+            libraries: Mutex::new(HashMap::new()),
+            script_lock: RwLock::new(()),
+            storage: OnceLock::new(),
         });
 
         // 开启后台异步任务。
-        tokio::spawn(purge_expired_tasks(shared.clone()));
+        tokio::spawn(purge_expired_tasks(shared.clone()).instrument(debug_span!("purge_expired")));
+        tokio::spawn(
+            auto_save_tasks(Db {
+                shared: shared.clone(),
+            })
+            .instrument(debug_span!("auto_save")),
+        );
 
         Db { shared }
     }
@@ -123,182 +492,2985 @@ impl Db {
     /// 根据 key 获取 value。
     ///
     /// # Output
-    /// 如果 key 不存在，返回`None`；如果存在，返回`Ok(data)`。
-    pub(crate) fn get(&self, key: &str) -> Option<Bytes> {
-        let state = self.shared.state.lock().unwrap();
-        state.entries.get(key).map(|entry| entry.data.clone())
+    /// 如果 key 不存在，返回`Ok(None)`；如果存在，返回`Ok(Some(data))`。
+    ///
+    /// # Errors
+    /// 如果 value 不是字符串，返回`Error::WrongType`。
+    pub(crate) fn get(&self, key: &str) -> crate::Result<Option<Bytes>> {
+        // 读优化模式下读取发布的版本，不获取锁，也不更新最后访问时刻。
+        // 已经过期的 key 视为不存在，由写操作或者后台任务删除。
+        let shard = &self.shared.shards[self.shared.shard_index(key)];
+        if let Some(entries) = &*shard.published.load() {
+            return match entries.get(key) {
+                Some(entry) if !entry.is_expired() => Ok(Some(entry.data.as_string()?.clone())),
+                _ => Ok(None),
+            };
+        }
+
+        let mut state = self.shared.lock_key(key);
+        match state.entries.get_mut(key) {
+            Some(entry) => {
+                entry.touch();
+                Ok(Some(entry.data.as_string()?.clone()))
+            }
+            None => Ok(None),
+        }
     }
 
-    /// 设置 key-entry，这里的 entry 由 value 和一个可选的过期时间组成的。
+    /// 删除`keys`，返回被删除的 key 的数量，不存在的 key 会被忽略。
     ///
-    /// 如果 key 已经被设置过了，那么会覆盖原有数据。
-    pub(crate) fn set(&self, key: String, value: Bytes, expire: Option<Duration>) {
-        let mut state = self.shared.state.lock().unwrap();
-        // 是否应该通知后台任务。
-        let mut notify = false;
+    /// 很大的 value 交给后台线程释放，见`State::free()`。
+    pub(crate) fn unlink(&self, keys: &[String]) -> usize {
+        // 已经过期的 key 在加锁时就被删除了，视为不存在。
+        let mut shards = self.shared.lock_keys(keys);
+        let mut removed = 0;
+        for key in keys {
+            let state = shards.get(key);
+            let Some(entry) = state.remove_entry(key) else {
+                continue;
+            };
+            self.shared.notify_storage(|| StorageEvent::Delete {
+                key: key.to_string(),
+            });
+            removed += 1;
+            state.free(entry);
+        }
+        removed
+    }
 
-        let expires_at = expire.map(|duration| {
-            // 新插入的`Entry`的过期时间。
-            let when = Instant::now() + duration;
-            // 如果新插入的`Entry`的过期时间是最早的，
-            // 那么就要通知后台任务重新载入。
-            notify = state
-                .next_expiration()
-                .map(|expiration| expiration > when)
-                .unwrap_or(true);
-            when
-        });
+    /// 更新`keys`的最后访问时刻，返回存在的 key 的数量，重复的 key 会被重复计算。
+    pub(crate) fn touch(&self, keys: &[String]) -> usize {
+        let mut shards = self.shared.lock_keys(keys);
+        let mut count = 0;
+        for key in keys {
+            if let Some(entry) = shards.get(key).entries.get_mut(key) {
+                entry.touch();
+                count += 1;
+            }
+        }
+        count
+    }
 
-        // 插入到`HashMap`中，返回原有数据。
-        // 原有数据不存在就为`None`。
-        let prev = state.entries.insert(
-            key.clone(),
-            Entry {
-                data: value,
-                expires_at,
-            },
-        );
+    /// 获取 key 对应的 value 的类型名称，如果 key 不存在，返回`None`。
+    pub(crate) fn value_type(&self, key: &str) -> Option<&'static str> {
+        let state = self.shared.lock_shard(key);
+        state.live_entry(key).map(|entry| entry.data.type_name())
+    }
 
-        // 如果存在原有数据且原有数据有设置过期时间，
-        // 将`BTreeSet`中对应的删除。
-        if let Some(prev) = prev {
-            if let Some(when) = prev.expires_at {
-                state.expirations.remove(&(when, key.clone()));
-            }
+    /// 获取 key 对应的 value 的编码名称，如果 key 不存在，返回`None`。
+    pub(crate) fn object_encoding(&self, key: &str) -> Option<&'static str> {
+        let state = self.shared.lock_shard(key);
+        state.live_entry(key).map(|entry| entry.data.encoding())
+    }
+
+    /// 获取 key 的 value 的副本，如果 key 不存在，返回`None`。
+    ///
+    /// value 使用写时复制的容器，复制的开销很小。查询本身不算作一次访问。
+    pub(crate) fn dump(&self, key: &str) -> Option<Value> {
+        let state = self.shared.lock_shard(key);
+        state.live_entry(key).map(|entry| entry.data.clone())
+    }
+
+    /// 将`value`保存到`key`中，`expires_at`为过期时刻。
+    ///
+    /// 如果过期时刻已经过去了，不会保存，原有的 key 仍然会被删除。
+    ///
+    /// # Output
+    /// 如果 key 已经存在且`replace`为`false`，不做任何修改，返回`false`。
+    pub(crate) fn restore_key(
+        &self,
+        key: &str,
+        value: Value,
+        expires_at: Option<SystemTime>,
+        replace: bool,
+    ) -> bool {
+        let mut guard = self.shared.lock_shard(key);
+        let state = &mut *guard;
+
+        if state.live_entry(key).is_some() && !replace {
+            return false;
+        }
+        if let Some(entry) = state.remove_entry(key) {
+            self.shared.notify_storage(|| StorageEvent::Delete {
+                key: key.to_string(),
+            });
+            state.free(entry);
         }
 
-        // 将过期时间插入到`BTreeSet`中。
+        let clock = Clock::now();
+        let expires_at = match expires_at {
+            Some(when) => match clock.to_instant(when) {
+                Some(when) => Some(when),
+                // 已经过期了。
+                None => return true,
+            },
+            None => None,
+        };
+
+        if let Ok(data) = value.as_string() {
+            self.shared.notify_storage(|| StorageEvent::Set {
+                key: key.to_string(),
+                value: data.clone(),
+                expire: expires_at.map(|when| when - clock.now),
+            });
+        }
+        // 如果新的过期时间是最早的，需要通知后台任务重新计算休眠时间。
+        let notify = expires_at.is_some_and(|when| {
+            state
+                .next_expiration()
+                .map(|expiration| expiration > when)
+                .unwrap_or(true)
+        });
         if let Some(when) = expires_at {
-            state.expirations.insert((when, key));
+            state.expirations.insert(when, key);
         }
+        let entry = Entry::new(key, value, expires_at);
+        state.used_memory += entry.size;
+        state.entries.insert(key.to_string(), entry);
+        // 恢复的可能是非空的列表或者流。
+        state.wake_waiters(key);
 
-        // 在通知后台任务前解锁，防止后台任务醒来后还要等待锁。
-        drop(state);
+        drop(guard);
 
-        // 按需要通知后台任务。
         if notify {
             self.shared.background_task.notify_one();
         }
+        true
     }
 
-    /// 根据订阅的信道的名称，返回`Receiver`。
+    /// 获取 key 距离最后一次被访问的时长，如果 key 不存在，返回`None`。
     ///
-    /// 如果订阅的信道不存在，那么会创建这个广播信道。
-    pub(crate) fn subscribe(&self, key: String) -> broadcast::Receiver<Bytes> {
-        use std::collections::hash_map::Entry;
+    /// 查询本身不算作一次访问。
+    pub(crate) fn idle_time(&self, key: &str) -> Option<Duration> {
+        let state = self.shared.lock_shard(key);
+        state
+            .live_entry(key)
+            .map(|entry| entry.last_access.elapsed())
+    }
 
-        let mut state = self.shared.state.lock().unwrap();
-        match state.pub_sub.entry(key) {
-            // 如果请求的信道已经存在，那么就返回广播接收端
-            Entry::Occupied(e) => e.get().subscribe(),
-            // 如果不存在，就新建
-            Entry::Vacant(e) => {
-                // 这个广播信道可以存放`1024`条信息。
-                // 一条信息会一直存放，直到所有订阅者都接受到信息后才被删除
-                // 当信道容量被占满后，旧的信息会被删除
-                let (tx, rx) = broadcast::channel(1024);
-                e.insert(tx);
-                rx
+    /// 获取 key 被访问的次数，如果 key 不存在，返回`None`。
+    pub(crate) fn access_count(&self, key: &str) -> Option<u64> {
+        let state = self.shared.lock_shard(key);
+        state.live_entry(key).map(|entry| entry.access_count)
+    }
+
+    /// 获取 key 的内部信息，如果 key 不存在，返回`None`。查询本身不算作一次访问。
+    pub(crate) fn entry_info(&self, key: &str) -> Option<EntryInfo> {
+        let state = self.shared.lock_shard(key);
+        state.live_entry(key).map(|entry| EntryInfo {
+            value: entry.data.clone(),
+            size: entry.size,
+            idle: entry.last_access.elapsed(),
+            access_count: entry.access_count,
+            ttl: entry
+                .expires_at
+                .map(|when| when.saturating_duration_since(Instant::now())),
+        })
+    }
+
+    /// 获取 key 和 value 占用的近似字节数，如果 key 不存在，返回`None`。
+    ///
+    /// 计算方式与`used_memory()`相同，每个`Entry`都记录了自己的大小，所以是 O(1) 的。
+    pub(crate) fn memory_usage(&self, key: &str) -> Option<usize> {
+        let state = self.shared.lock_shard(key);
+        state.live_entry(key).map(|entry| entry.size)
+    }
+
+    /// 统计内存的使用情况。
+    ///
+    /// 需要遍历所有的分片、信道、连接和脚本，但不需要遍历 key。
+    pub(crate) fn memory_stats(&self) -> MemoryStats {
+        let mut stats = {
+            let pubsub = self.shared.pubsub.lock().unwrap();
+            MemoryStats {
+                pubsub_channels: live_channels(&pubsub.pub_sub).count(),
+                pubsub_patterns: live_channels(&pubsub.pattern_sub).count(),
+                pubsub_shard_channels: live_channels(&pubsub.shard_pub_sub).count(),
+                pubsub: name_bytes(&pubsub.pub_sub)
+                    + name_bytes(&pubsub.pattern_sub)
+                    + name_bytes(&pubsub.shard_pub_sub),
+                ..Default::default()
             }
+        };
+        for shard in self.shared.shards.iter() {
+            let state = shard.lock();
+            stats.dataset += state.used_memory;
+            stats.keys += state.entries.len();
+            stats.expires += state.expirations.len();
         }
+        let lazy_free = &self.shared.lazy_free;
+        stats.lazyfree_pending = lazy_free.pending.load(Ordering::Relaxed);
+        stats.lazyfreed = lazy_free.freed.load(Ordering::Relaxed);
+        stats.clients = self.clients().buffer_size();
+        stats.scripts = self
+            .shared
+            .scripts
+            .lock()
+            .unwrap()
+            .values()
+            .map(String::len)
+            .sum::<usize>()
+            + self
+                .shared
+                .libraries
+                .lock()
+                .unwrap()
+                .values()
+                .map(|library| library.code.len())
+                .sum::<usize>();
+        stats
     }
 
-    /// 向指定信道发送信息，返回信道的订阅者的数量。
-    pub(crate) fn publish(&self, key: &str, value: Bytes) -> usize {
-        let state = self.shared.state.lock().unwrap();
-        state
-            .pub_sub
-            .get(key)
-            // 信道存在，发送信息
-            // 如果没有订阅者，那么`send()`会返回错误，返回`0`
-            .map(|tx| tx.send(value).unwrap_or(0))
-            // 信道不存在，当然也没有订阅者，返回`0`
-            .unwrap_or(0)
+    /// 返回数据库中 key 的数量。
+    ///
+    /// 依次获取每个分片的锁，不会同时持有多个分片的锁，所以结果是近似的。
+    pub(crate) fn len(&self) -> usize {
+        let shards = self.shared.shards.iter();
+        shards.map(|shard| shard.lock().entries.len()).sum()
     }
 
-    /// 通知后台任务关闭。
-    ///
-    /// 这个函数被`DbDropGuard`的`Drop`实现调用。
-    fn shutdown_purge_task(&self) {
-        // 通过修改`State::shutdown`来通知后台任务
-        // 因此需要获取锁
-        let mut state = self.shared.state.lock().unwrap();
-        state.shutdown = true;
-        // 提前释放锁
-        // 不然后台任务被通知后还要等待获取锁
-        drop(state);
+    /// 返回数据库中 key 和 value 占用的近似字节数，获取锁的方式与`len()`相同。
+    pub(crate) fn used_memory(&self) -> usize {
+        let shards = self.shared.shards.iter();
+        shards.map(|shard| shard.lock().used_memory).sum()
+    }
+
+    /// 获取慢命令日志。
+    pub(crate) fn slowlog(&self) -> &SlowLog {
+        &self.shared.slowlog
+    }
+
+    /// 开启或关闭后台任务主动清除过期 key。
+    pub(crate) fn set_active_expire(&self, enabled: bool) {
+        self.shared.active_expire.store(enabled, Ordering::Relaxed);
+        // 重新开启时唤醒后台任务，清除关闭期间过期的 key。
         self.shared.background_task.notify_one();
     }
-}
 
-impl Shared {
-    /// 清除过期`Entry`，返回下一个应该被清除的`Entry`的过期时间，
-    /// 这样后台任务就能知道可以休眠到什么时候再醒来。
+    /// 开启或关闭读优化模式。
     ///
-    /// 如果`BTreeSet`为空或数据库正在关闭，返回`None`。
-    fn purge_expired_keys(&self) -> Option<Instant> {
-        let mut state = self.state.lock().unwrap();
-        if state.shutdown {
-            // 数据库正在关闭，不存在下一个应该被清除的`Entry`的过期时间。
-            return None;
+    /// 开启后`get()`读取每个分片最近发布的版本，不需要获取锁，读多写少时吞吐量更高；
+    /// 代价是每次修改后都要发布新的版本，写操作会变慢。
+    pub(crate) fn set_read_optimized(&self, enabled: bool) {
+        for shard in self.shared.shards.iter() {
+            // 在持有锁时发布，之后的修改会在释放锁之前继续发布。
+            let state = shard.lock();
+            shard
+                .published
+                .store(enabled.then(|| Arc::new(state.entries.clone())));
         }
+    }
 
-        // 我们下面要用到`&mut state`，但是`lock()`只是返回`MutexGuard`,
-        // 由于我们使用了锁，所以同时可变地访问`state.expirations`和`state.entries`
-        // 是安全的，但是编译器还不够聪明，所以我们这里获取了真正的`&mut state`。
-        let state = &mut *state;
+    /// 获取延迟监控。
+    pub(crate) fn latency(&self) -> &LatencyMonitor {
+        &self.shared.latency
+    }
 
-        let now = Instant::now();
-        // `BTreeSet`是从小到大排序的
-        while let Some((when, key)) = state.expirations.iter().next() {
-            if *when > now {
-                // 清除任务已经做完了，返回下一个应该被清除的`Entry`的过期时间。
-                return Some(*when);
+    /// 获取执行了`Monitor`的连接。
+    pub(crate) fn monitors(&self) -> &Monitors {
+        &self.shared.monitors
+    }
+
+    /// 获取运行时配置。
+    pub(crate) fn config(&self) -> &ServerConfig {
+        &self.shared.config
+    }
+
+    /// 获取已连接的客户端。
+    pub(crate) fn clients(&self) -> &Clients {
+        &self.shared.clients
+    }
+
+    /// 判断使用的内存是否超过了`maxmemory`。
+    pub(crate) fn is_over_maxmemory(&self) -> bool {
+        let maxmemory = self.config().maxmemory();
+        maxmemory > 0 && self.used_memory() as u64 > maxmemory
+    }
+
+    /// 获取快照持久化的配置和状态。
+    pub(crate) fn persistence(&self) -> &Persistence {
+        &self.shared.persistence
+    }
+
+    /// 获取主从复制的状态。
+    pub(crate) fn replication(&self) -> &Replication {
+        &self.shared.replication
+    }
+
+    /// 获取 ACL 用户。
+    pub(crate) fn acl(&self) -> &Acl {
+        &self.shared.acl
+    }
+
+    /// 获取集群的状态，没有开启集群模式时返回`None`。
+    pub(crate) fn cluster(&self) -> Option<&ClusterState> {
+        self.shared.cluster.get()
+    }
+
+    /// 开启集群模式。
+    ///
+    /// # Panics
+    /// 只能设置一次，重复设置会 panic。
+    pub(crate) fn set_cluster(&self, cluster: ClusterState) {
+        if self.shared.cluster.set(cluster).is_err() {
+            panic!("集群模式只能开启一次");
+        }
+    }
+
+    /// 将快照写入`Persistence::dbfilename()`，完成后才返回。
+    ///
+    /// 与`snapshot()`一样，只在克隆数据时持有锁，写文件期间不会阻塞其他连接。
+    ///
+    /// # Errors
+    /// 如果已经有保存任务在执行，或者写文件失败，返回错误信息。
+    pub(crate) async fn save(&self) -> Result<(), String> {
+        let persistence = &self.shared.persistence;
+        if !persistence.start_save() {
+            return Err("ERR Background save already in progress".to_string());
+        }
+        let result = self.write_snapshot().await;
+        persistence.finish_save(result.as_ref().ok().copied());
+        result
+            .map(|_| ())
+            .map_err(|err| format!("ERR Save failed: {}", err))
+    }
+
+    /// 在后台任务中将快照写入`Persistence::dbfilename()`，立即返回。
+    ///
+    /// 如果已经有保存任务在执行，返回`false`。
+    pub(crate) fn bgsave(&self) -> bool {
+        if !self.shared.persistence.start_save() {
+            return false;
+        }
+        let db = self.clone();
+        tokio::spawn(async move {
+            let result = db.write_snapshot().await;
+            if let Err(err) = &result {
+                error!(cause = %err, "后台保存快照失败");
             }
-            // 当前时间已经超过了过期时间了，执行清除任务。
-            state.entries.remove(key);
-            state.expirations.remove(&(*when, key.to_string()));
+            db.shared.persistence.finish_save(result.ok());
+        });
+        true
+    }
+
+    /// 写入快照，返回快照包含的修改数。
+    async fn write_snapshot(&self) -> crate::Result<u64> {
+        // 先读取修改数再获取快照，期间的修改会被当作没有保存，下次还会触发保存。
+        let dirty = self.shared.persistence.dirty();
+        let entries = self.snapshot();
+        let path = self.shared.persistence.dbfilename();
+        snapshot::write_file(&path, &entries).await?;
+        Ok(dirty)
+    }
+
+    /// 获取保证脚本原子执行的锁，见`Shared::script_lock`。
+    pub(crate) fn script_lock(&self) -> &RwLock<()> {
+        &self.shared.script_lock
+    }
+
+    /// 缓存脚本，返回脚本的 SHA1 摘要，摘要为小写的十六进制字符串。
+    pub(crate) fn load_script(&self, script: &str) -> String {
+        let sha = sha1_smol::Sha1::from(script).digest().to_string();
+        let mut scripts = self.shared.scripts.lock().unwrap();
+        scripts
+            .entry(sha.clone())
+            .or_insert_with(|| script.to_string());
+        sha
+    }
+
+    /// 根据 SHA1 摘要获取缓存的脚本，`sha`不区分大小写。
+    pub(crate) fn get_script(&self, sha: &str) -> Option<String> {
+        let scripts = self.shared.scripts.lock().unwrap();
+        scripts.get(&sha.to_lowercase()).cloned()
+    }
+
+    /// 清空缓存的脚本。
+    pub(crate) fn flush_scripts(&self) {
+        self.shared.scripts.lock().unwrap().clear();
+    }
+
+    /// 注册函数库，`replace`为`true`时替换同名的库。
+    ///
+    /// # Errors
+    /// 如果同名的库已经存在且`replace`为`false`，或者库中的函数已经在其他库中注册过了，
+    /// 返回错误信息，不会注册。
+    pub(crate) fn function_load(&self, library: Library, replace: bool) -> Result<(), String> {
+        let mut libraries = self.shared.libraries.lock().unwrap();
+        if !replace && libraries.contains_key(&library.name) {
+            return Err(format!("ERR Library '{}' already exists", library.name));
+        }
+        let conflict = libraries
+            .values()
+            .filter(|other| other.name != library.name)
+            .flat_map(|other| &other.functions)
+            .find(|function| library.functions.contains(function));
+        if let Some(function) = conflict {
+            return Err(format!("ERR Function {} already exists", function));
         }
+        libraries.insert(library.name.clone(), library);
+        Ok(())
+    }
 
-        // 不存在下一个应该被清除的`Entry`的过期时间，其实就是`BTreeSet`为空。
-        None
+    /// 获取名称匹配 glob 模式`pattern`的函数库，按照名称排序。
+    pub(crate) fn function_list(&self, pattern: &str) -> Vec<Library> {
+        let libraries = self.shared.libraries.lock().unwrap();
+        let mut result: Vec<_> = libraries
+            .values()
+            .filter(|library| glob_match(pattern.as_bytes(), library.name.as_bytes()))
+            .cloned()
+            .collect();
+        result.sort_by(|a, b| a.name.cmp(&b.name));
+        result
     }
 
-    /// 如果数据库正在关闭，返回`true`。
-    fn is_shutdown(&self) -> bool {
-        self.state.lock().unwrap().shutdown
+    /// 删除函数库，返回库是否存在。
+    pub(crate) fn function_delete(&self, name: &str) -> bool {
+        let mut libraries = self.shared.libraries.lock().unwrap();
+        libraries.remove(name).is_some()
     }
-}
 
-impl State {
-    /// 返回`BTreeSet`中的第一个(Instant,String)中的`Instant`，
-    /// 也就是最小的`Instant`。
-    fn next_expiration(&self) -> Option<Instant> {
-        // `Instant`实现了Copy trait。
-        self.expirations.iter().next().map(|entry| entry.0)
+    /// 找到注册了函数`name`的库。
+    pub(crate) fn find_function(&self, name: &str) -> Option<Library> {
+        let libraries = self.shared.libraries.lock().unwrap();
+        libraries
+            .values()
+            .find(|library| library.functions.iter().any(|function| function == name))
+            .cloned()
     }
-}
 
-/// 异步后台任务，负责清除过期`Entry`。
-///
-/// 它是周期性执行的，毕竟不能一直处于执行状态，它等待被通知。
-/// 当数据更新或收到关闭信号的时候，它会被通知。
-async fn purge_expired_tasks(shared: Arc<Shared>) {
-    // 被通知后会继续循环，如果发现 shutdown 为真，则退出循环。
-    while !shared.is_shutdown() {
-        // 清除过期的`Entry`，函数会返回下一个应该被清除的`Entry`的过期时间。
-        if let Some(when) = shared.purge_expired_keys() {
-            // 我们休眠到上述那个时刻，但是如果该任务在此期间被通知了
-            // (数据有更新)，就要重新循环，重新运行`purge_expired_keys()`，
-            // 毕竟下一个应该被清除的`Entry`的过期时间对应的`Entry`可能被操作了。
-            // 当然也有可能是通知关闭。
-            tokio::select! {
-                _ = time::sleep_until(when) => {}
-                _ = shared.background_task.notified() => {}
+    /// 设置存储钩子，之后数据库的每次修改都会通知钩子。
+    ///
+    /// # Panics
+    /// 只能设置一次，重复设置会 panic。
+    pub(crate) fn set_storage_hook(&self, hook: Arc<dyn StorageHook>) {
+        if self.shared.storage.set(storage::spawn(hook)).is_err() {
+            panic!("存储钩子只能设置一次");
+        }
+    }
+
+    /// 设置 key-entry，这里的 entry 由 value 和一个可选的过期时间组成的。
+    ///
+    /// 如果 key 已经被设置过了，那么会覆盖原有数据。
+    /// 只有满足`options.condition`时才会写入，已经过期但还没有被清除的 key 视为不存在。
+    /// `options.keep_ttl`为`true`时保留原有的过期时间，此时忽略`expire`。
+    ///
+    /// # Output
+    /// 返回是否写入了数据，以及`options.get`为`true`时原有的值，key 不存在时为`None`。
+    ///
+    /// # Errors
+    /// 如果`options.get`为`true`且原有的值不是字符串，返回`Error::WrongType`，不会写入。
+    pub(crate) fn set(
+        &self,
+        key: String,
+        value: Bytes,
+        expire: Option<Duration>,
+        options: SetOptions,
+    ) -> crate::Result<(bool, Option<Bytes>)> {
+        let mut state = self.shared.lock_shard(&key);
+
+        let (exists, prev_expires_at, prev_value) = match state.live_entry(&key) {
+            Some(entry) => {
+                let prev_value = if options.get {
+                    Some(entry.data.as_string()?.clone())
+                } else {
+                    None
+                };
+                (true, entry.expires_at, prev_value)
             }
+            None => (false, None, None),
+        };
+        let allowed = match options.condition {
+            SetCondition::IfAbsent => !exists,
+            SetCondition::IfPresent => exists,
+            SetCondition::Always => true,
+        };
+        if !allowed {
+            return Ok((false, prev_value));
+        }
+
+        // `KEEPTTL`时沿用原有的过期时间，存储层需要的是剩余的时长。
+        let expire = if options.keep_ttl {
+            prev_expires_at.map(|when| when.saturating_duration_since(Instant::now()))
         } else {
-            // 没有要清除的`Entry`了，等待被通知。
-            shared.background_task.notified().await;
+            expire
+        };
+
+        // 是否应该通知后台任务。
+        let mut notify = false;
+
+        let expires_at = if options.keep_ttl {
+            prev_expires_at
+        } else {
+            expire.map(|duration| {
+                // 新插入的`Entry`的过期时间。
+                let when = Instant::now() + duration;
+                // 如果新插入的`Entry`的过期时间是最早的，
+                // 那么就要通知后台任务重新载入。
+                notify = state
+                    .next_expiration()
+                    .map(|expiration| expiration > when)
+                    .unwrap_or(true);
+                when
+            })
+        };
+
+        self.shared.notify_storage(|| StorageEvent::Set {
+            key: key.clone(),
+            value: value.clone(),
+            expire,
+        });
+
+        state.invalidate(&key);
+
+        // 插入到`HashMap`中，返回原有数据。
+        // 原有数据不存在就为`None`。
+        let entry = Entry::new(&key, Value::String(value), expires_at);
+        state.used_memory += entry.size;
+        let prev = state.entries.insert(key.clone(), entry);
+
+        // 如果存在原有数据且原有数据有设置过期时间，
+        // 将`expirations`中对应的删除。
+        if let Some(prev) = prev {
+            state.used_memory -= prev.size;
+            if let Some(when) = prev.expires_at {
+                state.expirations.remove(when, &key);
+            }
+            state.free(prev);
+        }
+
+        // 将过期时间插入到`expirations`中。
+        if let Some(when) = expires_at {
+            state.expirations.insert(when, &key);
+        }
+
+        // 在通知后台任务前解锁，防止后台任务醒来后还要等待锁。
+        drop(state);
+
+        // 按需要通知后台任务。
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+        Ok((true, prev_value))
+    }
+
+    /// 将 key 中存储的数字加上`delta`，返回相加后的值。
+    ///
+    /// 读取、计算和写回都在持有锁的时候完成，所以并发的操作不会互相覆盖。
+    /// 如果 key 不存在，视为`0`；原有的过期时间保持不变。
+    ///
+    /// # Errors
+    /// 如果 value 不能表示为`i64`或者结果溢出，返回错误信息。
+    pub(crate) fn incr_by(&self, key: &str, delta: i64) -> Result<i64, &'static str> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        let current = match state.entries.get(key) {
+            Some(entry) => str::from_utf8(entry.data.as_string().map_err(|_| {
+                "WRONGTYPE Operation against a key holding the wrong kind of value"
+            })?)
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .ok_or("ERR value is not an integer or out of range")?,
+            None => 0,
+        };
+        let value = current
+            .checked_add(delta)
+            .ok_or("ERR increment or decrement would overflow")?;
+        self.store_number(state, key, Bytes::from(value.to_string()));
+
+        Ok(value)
+    }
+
+    /// 将 key 中存储的浮点数加上`delta`，返回相加后的值格式化成的字符串。
+    ///
+    /// 与`incr_by()`一样在持有锁的时候完成，如果 key 不存在，视为`0`；原有的过期时间保持不变。
+    /// 结果不使用科学计数法，整数不带小数部分。
+    ///
+    /// # Errors
+    /// 如果 value 不能解析为浮点数，或者结果为 NaN 或无穷大，返回错误信息。
+    pub(crate) fn incr_by_float(&self, key: &str, delta: f64) -> Result<Bytes, &'static str> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        let current = match state.entries.get(key) {
+            Some(entry) => str::from_utf8(entry.data.as_string().map_err(|_| {
+                "WRONGTYPE Operation against a key holding the wrong kind of value"
+            })?)
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|value| !value.is_nan())
+            .ok_or("ERR value is not a valid float")?,
+            None => 0.0,
+        };
+        let value = current + delta;
+        if !value.is_finite() {
+            return Err("ERR increment would produce NaN or Infinity");
+        }
+        // `f64`的`Display`输出能够精确还原的最短十进制表示，并且不使用科学计数法。
+        let data = Bytes::from(value.to_string());
+        self.store_number(state, key, data.clone());
+
+        Ok(data)
+    }
+
+    /// 将计数器操作的结果作为字符串写回 key，原有的过期时间保持不变，并通知存储层。
+    fn store_number(&self, state: &mut State, key: &str, data: Bytes) {
+        let size = key.len() + data.len();
+        match state.entries.get_mut(key) {
+            Some(entry) => {
+                state.used_memory -= entry.size;
+                entry.data = Value::String(data.clone());
+                entry.size = size;
+            }
+            None => {
+                let entry = Entry::new(key, Value::String(data.clone()), None);
+                state.entries.insert(key.to_string(), entry);
+            }
+        }
+        state.used_memory += size;
+
+        let expire = state.entries[key]
+            .expires_at
+            .map(|when| when.saturating_duration_since(Instant::now()));
+        self.shared.notify_storage(|| StorageEvent::Set {
+            key: key.to_string(),
+            value: data,
+            expire,
+        });
+    }
+
+    /// 将字符串中第`offset`位设置为`on`，返回这一位原来的值。
+    ///
+    /// 第`0`位是第一个字节的最高位。如果`offset`超出了字符串的长度，会用`0`填充字符串；
+    /// 如果 key 不存在，会先创建一个空字符串。字符串是原地修改的，原有的过期时间保持不变。
+    ///
+    /// # Errors
+    /// 如果 value 不是字符串，返回`Error::WrongType`。
+    pub(crate) fn setbit(&self, key: &str, offset: usize, on: bool) -> crate::Result<bool> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        state.insert_if_absent(key, || Value::String(Bytes::new()));
+        let entry = state.entries.get_mut(key).unwrap();
+        let data = entry.data.as_string_mut()?;
+
+        // 如果没有其他地方引用这段数据（例如快照），`Vec::from()`不会复制数据。
+        let mut buf = Vec::from(mem::take(data));
+        let index = offset / 8;
+        if index >= buf.len() {
+            entry.size += index + 1 - buf.len();
+            state.used_memory += index + 1 - buf.len();
+            buf.resize(index + 1, 0);
+        }
+        let mask = 0x80 >> (offset % 8);
+        let prev = buf[index] & mask != 0;
+        if on {
+            buf[index] |= mask;
+        } else {
+            buf[index] &= !mask;
+        }
+        *data = Bytes::from(buf);
+
+        let value = data.clone();
+        let expire = entry
+            .expires_at
+            .map(|when| when.saturating_duration_since(Instant::now()));
+        self.shared.notify_storage(|| StorageEvent::Set {
+            key: key.to_string(),
+            value,
+            expire,
+        });
+
+        Ok(prev)
+    }
+
+    /// 获取字符串中第`offset`位的值，超出字符串长度的位视为`0`。
+    ///
+    /// # Errors
+    /// 如果 value 不是字符串，返回`Error::WrongType`。
+    pub(crate) fn getbit(&self, key: &str, offset: usize) -> crate::Result<bool> {
+        let state = self.shared.lock_key(key);
+        let data = match state.entries.get(key) {
+            Some(entry) => entry.data.as_string()?,
+            None => return Ok(false),
+        };
+        Ok(data
+            .get(offset / 8)
+            .map(|byte| byte & (0x80 >> (offset % 8)) != 0)
+            .unwrap_or(false))
+    }
+
+    /// 统计字符串中值为`1`的位的数量。
+    ///
+    /// `range`是以字节为单位的`[start, stop]`，下标的规则与`lrange()`相同；
+    /// 为`None`时统计整个字符串。
+    ///
+    /// # Errors
+    /// 如果 value 不是字符串，返回`Error::WrongType`。
+    pub(crate) fn bitcount(&self, key: &str, range: Option<(i64, i64)>) -> crate::Result<usize> {
+        let state = self.shared.lock_key(key);
+        let data = match state.entries.get(key) {
+            Some(entry) => entry.data.as_string()?,
+            None => return Ok(0),
+        };
+
+        let bytes = match range {
+            Some((start, stop)) => match index_range(start, stop, data.len()) {
+                Some((start, stop)) => &data[start..=stop],
+                None => return Ok(0),
+            },
+            None => &data[..],
+        };
+        Ok(bytes.iter().map(|byte| byte.count_ones() as usize).sum())
+    }
+
+    /// 设置哈希表中 field 对应的 value，返回新增的 field 的数量。
+    ///
+    /// 如果 key 不存在，会先创建一个空的哈希表；已经存在的 field 会被覆盖。
+    ///
+    /// # Errors
+    /// 如果 value 不是哈希表，返回`Error::WrongType`。
+    pub(crate) fn hset(&self, key: &str, fields: Vec<(Bytes, Bytes)>) -> crate::Result<usize> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        state.insert_if_absent(key, || Value::Hash(im::HashMap::new()));
+        let entry = state.entries.get_mut(key).unwrap();
+        let hash = entry.data.as_hash_mut()?;
+
+        let mut added = 0;
+        for (field, value) in fields {
+            let field_len = field.len();
+            entry.size += field_len + value.len();
+            state.used_memory += field_len + value.len();
+            match hash.insert(field, value) {
+                Some(prev) => {
+                    entry.size -= field_len + prev.len();
+                    state.used_memory -= field_len + prev.len();
+                }
+                None => added += 1,
+            }
+        }
+        Ok(added)
+    }
+
+    /// 获取哈希表中 field 对应的 value。
+    ///
+    /// # Output
+    /// 如果 key 或者 field 不存在，返回`Ok(None)`。
+    ///
+    /// # Errors
+    /// 如果 value 不是哈希表，返回`Error::WrongType`。
+    pub(crate) fn hget(&self, key: &str, field: &[u8]) -> crate::Result<Option<Bytes>> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry.data.as_hash()?.get(field).cloned()),
+            None => Ok(None),
+        }
+    }
+
+    /// 删除哈希表中的 field，返回被删除的 field 的数量，不存在的 field 会被忽略。
+    ///
+    /// 哈希表被删空后，key 也会被删除。
+    ///
+    /// # Errors
+    /// 如果 value 不是哈希表，返回`Error::WrongType`。
+    pub(crate) fn hdel(&self, key: &str, fields: &[Bytes]) -> crate::Result<usize> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(0);
+        };
+        let hash = entry.data.as_hash_mut()?;
+
+        let mut removed = 0;
+        for field in fields {
+            if let Some(value) = hash.remove(field) {
+                entry.size -= field.len() + value.len();
+                state.used_memory -= field.len() + value.len();
+                removed += 1;
+            }
+        }
+
+        if hash.is_empty() {
+            state.remove_entry(key);
+            self.shared.notify_storage(|| StorageEvent::Delete {
+                key: key.to_string(),
+            });
+        }
+        Ok(removed)
+    }
+
+    /// 获取哈希表中所有的 field 和 value，顺序不确定。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回空的`Vec`。
+    ///
+    /// # Errors
+    /// 如果 value 不是哈希表，返回`Error::WrongType`。
+    pub(crate) fn hgetall(&self, key: &str) -> crate::Result<Vec<(Bytes, Bytes)>> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry
+                .data
+                .as_hash()?
+                .iter()
+                .map(|(field, value)| (field.clone(), value.clone()))
+                .collect()),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// 随机获取哈希表中的 field 和 value，`count`的含义见`random_sample()`。
+    ///
+    /// # Errors
+    /// 如果 value 不是哈希表，返回`Error::WrongType`。
+    pub(crate) fn hrandfield(&self, key: &str, count: i64) -> crate::Result<Vec<(Bytes, Bytes)>> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(random_sample(entry.data.as_hash()?.iter(), count)
+                .into_iter()
+                .map(|(field, value)| (field.clone(), value.clone()))
+                .collect()),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// 将`values`依次插入到列表的头部（`front`为`true`）或者尾部，返回插入后列表的长度。
+    ///
+    /// 如果 key 不存在，会先创建一个空的列表。
+    ///
+    /// # Errors
+    /// 如果 value 不是列表，返回`Error::WrongType`。
+    pub(crate) fn push(&self, key: &str, values: Vec<Bytes>, front: bool) -> crate::Result<usize> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        state.insert_if_absent(key, || Value::List(VecDeque::new()));
+        let entry = state.entries.get_mut(key).unwrap();
+        let list = entry.data.as_list_mut()?;
+
+        for value in values {
+            entry.size += value.len();
+            state.used_memory += value.len();
+            if front {
+                list.push_front(value);
+            } else {
+                list.push_back(value);
+            }
+        }
+        let len = list.len();
+
+        // 唤醒所有阻塞在这个 key 上的连接，它们会重新尝试弹出元素，
+        // 没有抢到元素的连接会继续等待。
+        state.wake_waiters(key);
+        Ok(len)
+    }
+
+    /// 弹出列表头部（`front`为`true`）或者尾部的元素。
+    ///
+    /// 列表被弹空后，key 也会被删除。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`Ok(None)`。
+    ///
+    /// # Errors
+    /// 如果 value 不是列表，返回`Error::WrongType`。
+    pub(crate) fn pop(&self, key: &str, front: bool) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.lock_key(key);
+        Ok(self.pop_locked(&mut state, key, front, 1)?.pop())
+    }
+
+    /// 依次检查`keys`，从第一个非空的列表中弹出元素，返回 key 和弹出的元素。
+    ///
+    /// 如果所有列表都为空，等待直到有元素被插入，等待的方式见`block_on_keys()`。
+    ///
+    /// # Errors
+    /// 如果某个 key 的 value 不是列表，返回`Error::WrongType`。
+    pub(crate) async fn blocking_pop(
+        &self,
+        keys: &[String],
+        front: bool,
+    ) -> crate::Result<(String, Bytes)> {
+        self.block_on_keys(keys, |shards| {
+            let popped = self.mpop_locked(shards, keys, front, 1)?;
+            Ok(popped.map(|(key, mut values)| (key, values.remove(0))))
+        })
+        .await
+    }
+
+    /// 依次检查`keys`，从第一个非空的列表中弹出最多`count`个元素，返回 key 和弹出的元素。
+    ///
+    /// # Output
+    /// 如果所有 key 都不存在，返回`Ok(None)`。
+    ///
+    /// # Errors
+    /// 如果在找到非空的列表之前遇到了 value 不是列表的 key，返回`Error::WrongType`。
+    pub(crate) fn mpop(
+        &self,
+        keys: &[String],
+        front: bool,
+        count: usize,
+    ) -> crate::Result<Option<(String, Vec<Bytes>)>> {
+        let mut shards = self.shared.lock_keys(keys);
+        self.mpop_locked(&mut shards, keys, front, count)
+    }
+
+    /// 与`mpop()`相同，但是如果所有列表都为空，等待直到有元素被插入。
+    ///
+    /// 等待的方式见`block_on_keys()`。
+    ///
+    /// # Errors
+    /// 同`mpop()`。
+    pub(crate) async fn blocking_mpop(
+        &self,
+        keys: &[String],
+        front: bool,
+        count: usize,
+    ) -> crate::Result<(String, Vec<Bytes>)> {
+        self.block_on_keys(keys, |shards| self.mpop_locked(shards, keys, front, count))
+            .await
+    }
+
+    /// 在持有锁的情况下依次尝试弹出每个列表的元素，见`mpop()`。
+    fn mpop_locked(
+        &self,
+        shards: &mut ShardGuards,
+        keys: &[String],
+        front: bool,
+        count: usize,
+    ) -> crate::Result<Option<(String, Vec<Bytes>)>> {
+        for key in keys {
+            let values = self.pop_locked(shards.get(key), key, front, count)?;
+            if !values.is_empty() {
+                return Ok(Some((key.clone(), values)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 在持有锁的情况下弹出列表中最多`count`个元素，按照弹出的顺序返回，见`pop()`。
+    fn pop_locked(
+        &self,
+        state: &mut State,
+        key: &str,
+        front: bool,
+        count: usize,
+    ) -> crate::Result<Vec<Bytes>> {
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(vec![]);
+        };
+        let list = entry.data.as_list_mut()?;
+
+        let count = count.min(list.len());
+        let values: Vec<Bytes> = if front {
+            list.drain(..count).collect()
+        } else {
+            list.drain(list.len() - count..).rev().collect()
+        };
+        let len = values.iter().map(Bytes::len).sum::<usize>();
+        entry.size -= len;
+        state.used_memory -= len;
+
+        if list.is_empty() {
+            state.remove_entry(key);
+            self.shared.notify_storage(|| StorageEvent::Delete {
+                key: key.to_string(),
+            });
+        }
+        Ok(values)
+    }
+
+    /// 获取列表中下标在`[start, stop]`之间的元素。
+    ///
+    /// 下标可以是负数，`-1`表示最后一个元素，`-2`表示倒数第二个，以此类推。
+    /// 超出范围的下标会被截断到列表的范围内。
+    ///
+    /// # Output
+    /// 如果 key 不存在或者范围为空，返回空的`Vec`。
+    ///
+    /// # Errors
+    /// 如果 value 不是列表，返回`Error::WrongType`。
+    pub(crate) fn lrange(&self, key: &str, start: i64, stop: i64) -> crate::Result<Vec<Bytes>> {
+        let state = self.shared.lock_key(key);
+        let list = match state.entries.get(key) {
+            Some(entry) => entry.data.as_list()?,
+            None => return Ok(vec![]),
+        };
+
+        let (start, stop) = match index_range(start, stop, list.len()) {
+            Some(range) => range,
+            None => return Ok(vec![]),
+        };
+
+        Ok(list.range(start..=stop).cloned().collect())
+    }
+
+    /// 获取列表的长度，如果 key 不存在，返回`0`。
+    ///
+    /// # Errors
+    /// 如果 value 不是列表，返回`Error::WrongType`。
+    pub(crate) fn llen(&self, key: &str) -> crate::Result<usize> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry.data.as_list()?.len()),
+            None => Ok(0),
+        }
+    }
+
+    /// 查找列表中等于`element`的元素的下标，最多返回`count`个，`count`为`None`时返回所有的。
+    ///
+    /// `rank`不能为`0`，为正数时从头部开始查找，跳过前`rank - 1`个匹配的元素；
+    /// 为负数时从尾部开始查找。`maxlen`不为`0`时最多比较`maxlen`个元素。
+    /// 返回的下标总是从头部开始计算的。
+    ///
+    /// # Errors
+    /// 如果 value 不是列表，返回`Error::WrongType`。
+    pub(crate) fn lpos(
+        &self,
+        key: &str,
+        element: &[u8],
+        rank: i64,
+        count: Option<usize>,
+        maxlen: usize,
+    ) -> crate::Result<Vec<usize>> {
+        let state = self.shared.lock_key(key);
+        let list = match state.entries.get(key) {
+            Some(entry) => entry.data.as_list()?,
+            None => return Ok(vec![]),
+        };
+
+        let maxlen = if maxlen == 0 { usize::MAX } else { maxlen };
+        let skip = (rank.unsigned_abs() - 1) as usize;
+        let count = count.unwrap_or(usize::MAX);
+        let indexed: Box<dyn Iterator<Item = (usize, &Bytes)>> = if rank > 0 {
+            Box::new(list.iter().enumerate())
+        } else {
+            Box::new(list.iter().enumerate().rev())
+        };
+        Ok(indexed
+            .take(maxlen)
+            .filter(|(_, value)| value[..] == *element)
+            .skip(skip)
+            .take(count)
+            .map(|(index, _)| index)
+            .collect())
+    }
+
+    /// 在列表中第一个等于`pivot`的元素的前面（`before`为`true`）或者后面插入`element`。
+    ///
+    /// # Output
+    /// 返回插入后列表的长度；如果 key 不存在，返回`0`；如果`pivot`不存在，返回`-1`。
+    ///
+    /// # Errors
+    /// 如果 value 不是列表，返回`Error::WrongType`。
+    pub(crate) fn linsert(
+        &self,
+        key: &str,
+        before: bool,
+        pivot: &[u8],
+        element: Bytes,
+    ) -> crate::Result<i64> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(0);
+        };
+        let list = entry.data.as_list_mut()?;
+
+        let Some(index) = list.iter().position(|value| value[..] == *pivot) else {
+            return Ok(-1);
+        };
+        let index = if before { index } else { index + 1 };
+        entry.size += element.len();
+        state.used_memory += element.len();
+        list.insert(index, element);
+        Ok(list.len() as i64)
+    }
+
+    /// 将列表中下标为`index`的元素设置为`element`，下标的规则与`lrange()`相同。
+    ///
+    /// # Errors
+    /// 如果 value 不是列表，返回`Error::WrongType`；如果 key 不存在或者下标越界，返回`Err`。
+    pub(crate) fn lset(&self, key: &str, index: i64, element: Bytes) -> crate::Result<()> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Err("ERR no such key".into());
+        };
+        let list = entry.data.as_list_mut()?;
+
+        let index = if index < 0 {
+            index + list.len() as i64
+        } else {
+            index
+        };
+        let Some(value) = usize::try_from(index)
+            .ok()
+            .and_then(|index| list.get_mut(index))
+        else {
+            return Err("ERR index out of range".into());
+        };
+        entry.size = entry.size - value.len() + element.len();
+        state.used_memory = state.used_memory - value.len() + element.len();
+        *value = element;
+        Ok(())
+    }
+
+    /// 删除列表中等于`element`的元素，返回被删除的元素的数量。
+    ///
+    /// `count`为正数时从头部开始删除最多`count`个，为负数时从尾部开始删除，为`0`时删除所有的。
+    /// 列表被删空后，key 也会被删除。
+    ///
+    /// # Errors
+    /// 如果 value 不是列表，返回`Error::WrongType`。
+    pub(crate) fn lrem(&self, key: &str, count: i64, element: &[u8]) -> crate::Result<usize> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(0);
+        };
+        let list = entry.data.as_list_mut()?;
+
+        // 从尾部删除`n`个，相当于从头部开始跳过除了最后`n`个以外的匹配的元素。
+        let total = list.iter().filter(|value| value[..] == *element).count();
+        let limit = match count.unsigned_abs() as usize {
+            0 => total,
+            n => n.min(total),
+        };
+        let mut skip = if count < 0 { total - limit } else { 0 };
+        let mut removed = 0;
+        list.retain(|value| {
+            if value[..] != *element || removed == limit {
+                return true;
+            }
+            if skip > 0 {
+                skip -= 1;
+                return true;
+            }
+            removed += 1;
+            false
+        });
+        entry.size -= removed * element.len();
+        state.used_memory -= removed * element.len();
+
+        if list.is_empty() {
+            state.remove_entry(key);
+            self.shared.notify_storage(|| StorageEvent::Delete {
+                key: key.to_string(),
+            });
+        }
+        Ok(removed)
+    }
+
+    /// 向集合中添加成员，返回新增的成员的数量，已经存在的成员会被忽略。
+    ///
+    /// 如果 key 不存在，会先创建一个空的集合。
+    ///
+    /// # Errors
+    /// 如果 value 不是集合，返回`Error::WrongType`。
+    pub(crate) fn sadd(&self, key: &str, members: Vec<Bytes>) -> crate::Result<usize> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        state.insert_if_absent(key, || Value::Set(im::HashSet::new()));
+        let entry = state.entries.get_mut(key).unwrap();
+        let set = entry.data.as_set_mut()?;
+
+        let mut added = 0;
+        for member in members {
+            let len = member.len();
+            if set.insert(member).is_none() {
+                entry.size += len;
+                state.used_memory += len;
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    /// 从集合中删除成员，返回被删除的成员的数量，不存在的成员会被忽略。
+    ///
+    /// 集合被删空后，key 也会被删除。
+    ///
+    /// # Errors
+    /// 如果 value 不是集合，返回`Error::WrongType`。
+    pub(crate) fn srem(&self, key: &str, members: &[Bytes]) -> crate::Result<usize> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(0);
+        };
+        let set = entry.data.as_set_mut()?;
+
+        let mut removed = 0;
+        for member in members {
+            if set.remove(member).is_some() {
+                entry.size -= member.len();
+                state.used_memory -= member.len();
+                removed += 1;
+            }
+        }
+
+        if set.is_empty() {
+            state.remove_entry(key);
+            self.shared.notify_storage(|| StorageEvent::Delete {
+                key: key.to_string(),
+            });
+        }
+        Ok(removed)
+    }
+
+    /// 获取集合中所有的成员，顺序不确定。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回空的`Vec`。
+    ///
+    /// # Errors
+    /// 如果 value 不是集合，返回`Error::WrongType`。
+    pub(crate) fn smembers(&self, key: &str) -> crate::Result<Vec<Bytes>> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry.data.as_set()?.iter().cloned().collect()),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// 判断`member`是否是集合的成员，如果 key 不存在，返回`false`。
+    ///
+    /// # Errors
+    /// 如果 value 不是集合，返回`Error::WrongType`。
+    pub(crate) fn sismember(&self, key: &str, member: &[u8]) -> crate::Result<bool> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry.data.as_set()?.contains(member)),
+            None => Ok(false),
+        }
+    }
+
+    /// 获取集合的成员数量，如果 key 不存在，返回`0`。
+    ///
+    /// # Errors
+    /// 如果 value 不是集合，返回`Error::WrongType`。
+    pub(crate) fn scard(&self, key: &str) -> crate::Result<usize> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry.data.as_set()?.len()),
+            None => Ok(0),
+        }
+    }
+
+    /// 随机获取集合中的成员，`count`的含义见`random_sample()`。
+    ///
+    /// # Errors
+    /// 如果 value 不是集合，返回`Error::WrongType`。
+    pub(crate) fn srandmember(&self, key: &str, count: i64) -> crate::Result<Vec<Bytes>> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(random_sample(entry.data.as_set()?.iter(), count)
+                .into_iter()
+                .cloned()
+                .collect()),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// 对`keys`对应的集合进行运算，返回结果集合中的成员，不存在的 key 视为空集合。
+    ///
+    /// 所有集合都在同一次持有锁的时候读取，运算的结果不会混杂其他连接的修改。
+    ///
+    /// # Errors
+    /// 如果某个 key 的 value 不是集合，返回`Error::WrongType`。
+    pub(crate) fn combine_sets(&self, op: SetOp, keys: &[String]) -> crate::Result<Vec<Bytes>> {
+        let mut shards = self.shared.lock_keys(keys);
+        let result = shards.combine_sets(op, keys)?;
+        Ok(result.into_iter().collect())
+    }
+
+    /// 对`keys`对应的集合进行运算，将结果保存到`destination`中，返回结果集合的成员数量。
+    ///
+    /// `destination`原有的 value 和过期时间会被覆盖，无论类型。如果结果为空集合，
+    /// `destination`会被删除。读取和写入在同一次持有锁的时候完成。
+    ///
+    /// # Errors
+    /// 如果某个 key 的 value 不是集合，返回`Error::WrongType`。
+    pub(crate) fn combine_sets_store(
+        &self,
+        op: SetOp,
+        destination: &str,
+        keys: &[String],
+    ) -> crate::Result<usize> {
+        let mut locked = keys.to_vec();
+        locked.push(destination.to_string());
+        let mut shards = self.shared.lock_keys(&locked);
+
+        let result = shards.combine_sets(op, keys)?;
+        let len = result.len();
+
+        let state = shards.get(destination);
+        if let Some(entry) = state.remove_entry(destination) {
+            self.shared.notify_storage(|| StorageEvent::Delete {
+                key: destination.to_string(),
+            });
+            state.free(entry);
+        }
+        if !result.is_empty() {
+            state.insert_if_absent(destination, || Value::Set(result));
+        }
+        Ok(len)
+    }
+
+    /// 向有序集合中添加成员，返回新增的成员的数量，已经存在的成员会更新分数。
+    ///
+    /// 如果 key 不存在，会先创建一个空的有序集合。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn zadd(&self, key: &str, members: Vec<(Score, Bytes)>) -> crate::Result<usize> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        state.insert_if_absent(key, || Value::ZSet(SortedSet::new()));
+        let entry = state.entries.get_mut(key).unwrap();
+        let zset = entry.data.as_zset_mut()?;
+
+        let mut added = 0;
+        for (score, member) in members {
+            let len = member.len();
+            if zset.insert(member, score) {
+                entry.size += len + 8;
+                state.used_memory += len + 8;
+                added += 1;
+            }
+        }
+
+        // 唤醒阻塞在`BZMPop`上的连接。
+        state.wake_waiters(key);
+        Ok(added)
+    }
+
+    /// 获取有序集合中成员的分数。
+    ///
+    /// # Output
+    /// 如果 key 或者成员不存在，返回`Ok(None)`。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn zscore(&self, key: &str, member: &[u8]) -> crate::Result<Option<f64>> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry.data.as_zset()?.score(member)),
+            None => Ok(None),
+        }
+    }
+
+    /// 获取有序集合中排名在`[start, stop]`之间的成员和分数，排名从`0`开始，按分数从小到大。
+    ///
+    /// 下标的规则与`lrange()`相同。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn zrange(
+        &self,
+        key: &str,
+        start: i64,
+        stop: i64,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let state = self.shared.lock_key(key);
+        let zset = match state.entries.get(key) {
+            Some(entry) => entry.data.as_zset()?,
+            None => return Ok(vec![]),
+        };
+
+        let (start, stop) = match index_range(start, stop, zset.len()) {
+            Some(range) => range,
+            None => return Ok(vec![]),
+        };
+
+        Ok(zset
+            .iter()
+            .skip(start)
+            .take(stop - start + 1)
+            .map(|(member, score)| (member.clone(), score))
+            .collect())
+    }
+
+    /// 将有序集合中成员的分数加上`increment`，返回新的分数。
+    ///
+    /// 如果成员不存在，视为分数为`0`；如果 key 不存在，会先创建一个空的有序集合。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`；
+    /// 如果结果为 NaN（例如`inf`加上`-inf`），返回`Err`，分数不会被修改。
+    pub(crate) fn zincrby(&self, key: &str, increment: f64, member: Bytes) -> crate::Result<f64> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        let score = match state.entries.get(key) {
+            Some(entry) => entry.data.as_zset()?.score(&member).unwrap_or(0.0),
+            None => 0.0,
+        };
+        let score =
+            Score::new(score + increment).ok_or("ERR resulting score is not a number (NaN)")?;
+
+        state.insert_if_absent(key, || Value::ZSet(SortedSet::new()));
+        let entry = state.entries.get_mut(key).unwrap();
+        let zset = entry.data.as_zset_mut()?;
+        let len = member.len();
+        if zset.insert(member, score) {
+            entry.size += len + 8;
+            state.used_memory += len + 8;
+        }
+
+        state.wake_waiters(key);
+        Ok(score.value())
+    }
+
+    /// 从有序集合中删除成员，返回被删除的成员的数量，不存在的成员会被忽略。
+    ///
+    /// 如果有序集合被删空，key 也会被删除。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn zrem(&self, key: &str, members: &[Bytes]) -> crate::Result<usize> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(0);
+        };
+        let zset = entry.data.as_zset_mut()?;
+
+        let mut removed = 0;
+        for member in members {
+            if zset.remove(member) {
+                entry.size -= member.len() + 8;
+                state.used_memory -= member.len() + 8;
+                removed += 1;
+            }
+        }
+
+        if zset.is_empty() {
+            state.remove_entry(key);
+            self.shared.notify_storage(|| StorageEvent::Delete {
+                key: key.to_string(),
+            });
+        }
+        Ok(removed)
+    }
+
+    /// 依次检查`keys`，从第一个非空的有序集合中弹出最多`count`个分数最小（`min`为`true`）
+    /// 或者最大的成员，返回 key 和弹出的成员和分数。
+    ///
+    /// 有序集合被弹空后，key 也会被删除。
+    ///
+    /// # Output
+    /// 如果所有 key 都不存在，返回`Ok(None)`。
+    ///
+    /// # Errors
+    /// 如果在找到非空的有序集合之前遇到了 value 不是有序集合的 key，返回`Error::WrongType`。
+    pub(crate) fn zmpop(
+        &self,
+        keys: &[String],
+        min: bool,
+        count: usize,
+    ) -> crate::Result<Option<(String, ScoredMembers)>> {
+        let mut shards = self.shared.lock_keys(keys);
+        self.zmpop_locked(&mut shards, keys, min, count)
+    }
+
+    /// 与`zmpop()`相同，但是如果所有有序集合都为空，等待直到有成员被添加。
+    ///
+    /// 等待的方式见`block_on_keys()`。
+    ///
+    /// # Errors
+    /// 同`zmpop()`。
+    pub(crate) async fn blocking_zmpop(
+        &self,
+        keys: &[String],
+        min: bool,
+        count: usize,
+    ) -> crate::Result<(String, ScoredMembers)> {
+        self.block_on_keys(keys, |shards| self.zmpop_locked(shards, keys, min, count))
+            .await
+    }
+
+    /// 在持有锁的情况下依次尝试弹出每个有序集合的成员，见`zmpop()`。
+    fn zmpop_locked(
+        &self,
+        shards: &mut ShardGuards,
+        keys: &[String],
+        min: bool,
+        count: usize,
+    ) -> crate::Result<Option<(String, ScoredMembers)>> {
+        for key in keys {
+            let state = shards.get(key);
+            let Some(entry) = state.entries.get_mut(key) else {
+                continue;
+            };
+            let zset = entry.data.as_zset_mut()?;
+
+            let mut popped = vec![];
+            while popped.len() < count {
+                let Some((member, score)) = zset.pop(min) else {
+                    break;
+                };
+                entry.size -= member.len() + 8;
+                state.used_memory -= member.len() + 8;
+                popped.push((member, score));
+            }
+
+            if zset.is_empty() {
+                state.remove_entry(key);
+                self.shared.notify_storage(|| StorageEvent::Delete {
+                    key: key.to_string(),
+                });
+            }
+            if !popped.is_empty() {
+                return Ok(Some((key.clone(), popped)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 获取有序集合中分数在`min`和`max`之间的成员和分数，按分数从小到大。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn zrangebyscore(
+        &self,
+        key: &str,
+        min: Bound<Score>,
+        max: Bound<Score>,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry
+                .data
+                .as_zset()?
+                .range_by_score(min, max)
+                .map(|(member, score)| (member.clone(), score))
+                .collect()),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// 获取有序集合中成员的排名，排名从`0`开始，按分数从小到大。
+    ///
+    /// # Output
+    /// 如果 key 或者成员不存在，返回`Ok(None)`。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn zrank(&self, key: &str, member: &[u8]) -> crate::Result<Option<usize>> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry.data.as_zset()?.rank(member)),
+            None => Ok(None),
+        }
+    }
+
+    /// 获取有序集合的成员数量，如果 key 不存在，返回`0`。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn zcard(&self, key: &str) -> crate::Result<usize> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry.data.as_zset()?.len()),
+            None => Ok(0),
+        }
+    }
+
+    /// 随机获取有序集合中的成员和分数，`count`的含义见`random_sample()`。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn zrandmember(&self, key: &str, count: i64) -> crate::Result<ScoredMembers> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(random_sample(entry.data.as_zset()?.iter(), count)
+                .into_iter()
+                .map(|(member, score)| (member.clone(), score))
+                .collect()),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// 向地理位置索引中添加成员，返回新增的成员的数量。
+    ///
+    /// 地理位置索引就是有序集合，坐标被编码为 geohash 作为成员的分数，
+    /// 已经存在的成员会更新坐标。`members`中的每一项为经度、纬度和成员，
+    /// 需要保证坐标是有效的，见`geo::is_valid()`。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn geoadd(
+        &self,
+        key: &str,
+        members: Vec<(f64, f64, Bytes)>,
+    ) -> crate::Result<usize> {
+        let members = members
+            .into_iter()
+            .map(|(lon, lat, member)| (geo_score(geo::encode(lon, lat)), member))
+            .collect();
+        self.zadd(key, members)
+    }
+
+    /// 获取成员的坐标，每一项为`(经度, 纬度)`，不存在的成员为`None`。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn geopos(
+        &self,
+        key: &str,
+        members: &[Bytes],
+    ) -> crate::Result<Vec<Option<(f64, f64)>>> {
+        let state = self.shared.lock_key(key);
+        let zset = match state.entries.get(key) {
+            Some(entry) => entry.data.as_zset()?,
+            None => return Ok(vec![None; members.len()]),
+        };
+        Ok(members
+            .iter()
+            .map(|member| zset.score(member).map(|score| geo::decode(score as u64)))
+            .collect())
+    }
+
+    /// 计算两个成员之间的距离，单位为米。
+    ///
+    /// # Output
+    /// 如果 key 或者任意一个成员不存在，返回`Ok(None)`。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn geodist(&self, key: &str, from: &[u8], to: &[u8]) -> crate::Result<Option<f64>> {
+        let state = self.shared.lock_key(key);
+        let zset = match state.entries.get(key) {
+            Some(entry) => entry.data.as_zset()?,
+            None => return Ok(None),
+        };
+        match (zset.score(from), zset.score(to)) {
+            (Some(from), Some(to)) => Ok(Some(geo::distance(
+                geo::decode(from as u64),
+                geo::decode(to as u64),
+            ))),
+            _ => Ok(None),
+        }
+    }
+
+    /// 搜索以`origin`为中心、在`shape`范围内的成员，按照在有序集合中的顺序返回。
+    ///
+    /// 只扫描中心附近的 9 个 geohash 格子对应的分数区间，见`geo::search_ranges()`。
+    ///
+    /// # Errors
+    /// 如果 value 不是有序集合，返回`Error::WrongType`；
+    /// 如果中心是一个不存在的成员，返回`Err`。
+    pub(crate) fn geosearch(
+        &self,
+        key: &str,
+        origin: &Origin,
+        shape: &Shape,
+    ) -> crate::Result<Vec<GeoMatch>> {
+        let state = self.shared.lock_key(key);
+        let zset = match state.entries.get(key) {
+            Some(entry) => entry.data.as_zset()?,
+            None => return Ok(vec![]),
+        };
+        let center = match origin {
+            Origin::Member(member) => match zset.score(member) {
+                Some(score) => geo::decode(score as u64),
+                None => return Err("ERR could not decode requested zset member".into()),
+            },
+            Origin::LonLat(lon, lat) => (*lon, *lat),
+        };
+
+        let mut matches = vec![];
+        for (start, end) in geo::search_ranges(center, shape) {
+            let members = zset.range_by_score(
+                Bound::Included(geo_score(start)),
+                Bound::Excluded(geo_score(end)),
+            );
+            for (member, score) in members {
+                let hash = score as u64;
+                if let Some(dist) = shape.contains(center, geo::decode(hash)) {
+                    matches.push(GeoMatch {
+                        member: member.clone(),
+                        dist,
+                        hash,
+                    });
+                }
+            }
+        }
+        Ok(matches)
+    }
+
+    /// 向流中添加一条消息，返回消息的 ID。
+    ///
+    /// 如果 key 不存在，会先创建一个空的流。添加后唤醒所有阻塞在这个 key 上的连接。
+    ///
+    /// # Errors
+    /// 如果 value 不是流，返回`Error::WrongType`；如果 ID 不大于流中最后一条消息的 ID，返回`Err`。
+    pub(crate) fn xadd(&self, key: &str, id: NewId, fields: Fields) -> crate::Result<StreamId> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        let now_ms = unix_millis();
+        let size = stream::fields_size(&fields);
+
+        let id = match state.entries.get_mut(key) {
+            Some(entry) => {
+                let id = entry.data.as_stream_mut()?.add(id, fields, now_ms)?;
+                entry.size += size;
+                state.used_memory += size;
+                id
+            }
+            None => {
+                // ID 不合法时不应该留下一个空的流，所以先添加消息再插入。
+                let mut stream = Stream::new();
+                let id = stream.add(id, fields, now_ms)?;
+                state.insert_if_absent(key, || Value::Stream(stream));
+                id
+            }
+        };
+
+        state.wake_waiters(key);
+        Ok(id)
+    }
+
+    /// 获取流中 ID 在`start`和`end`之间的消息，最多返回`count`条。
+    ///
+    /// # Errors
+    /// 如果 value 不是流，返回`Error::WrongType`。
+    pub(crate) fn xrange(
+        &self,
+        key: &str,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: Option<usize>,
+    ) -> crate::Result<Entries> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry
+                .data
+                .as_stream()?
+                .range(start, end)
+                .take(count.unwrap_or(usize::MAX))
+                .map(|(id, fields)| (*id, fields.clone()))
+                .collect()),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// 获取流中消息的数量，如果 key 不存在，返回`0`。
+    ///
+    /// # Errors
+    /// 如果 value 不是流，返回`Error::WrongType`。
+    pub(crate) fn xlen(&self, key: &str) -> crate::Result<usize> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry.data.as_stream()?.len()),
+            None => Ok(0),
+        }
+    }
+
+    /// 获取流中最后一条消息的 ID，如果 key 不存在，返回`0-0`。
+    ///
+    /// # Errors
+    /// 如果 value 不是流，返回`Error::WrongType`。
+    pub(crate) fn xlast_id(&self, key: &str) -> crate::Result<StreamId> {
+        let state = self.shared.lock_key(key);
+        match state.entries.get(key) {
+            Some(entry) => Ok(entry.data.as_stream()?.last_id()),
+            None => Ok(StreamId::MIN),
+        }
+    }
+
+    /// 读取每个流中 ID 大于`ids`中对应 ID 的消息，每个流最多返回`count`条。
+    ///
+    /// 只返回有新消息的流。
+    ///
+    /// # Errors
+    /// 如果某个 key 的 value 不是流，返回`Error::WrongType`。
+    pub(crate) fn xread(
+        &self,
+        keys: &[String],
+        ids: &[StreamId],
+        count: Option<usize>,
+    ) -> crate::Result<Vec<(String, Entries)>> {
+        let mut shards = self.shared.lock_keys(keys);
+        shards.xread(keys, ids, count)
+    }
+
+    /// 与`xread()`相同，但是如果所有流中都没有新消息，等待直到有新消息被添加。
+    ///
+    /// 等待的方式与`blocking_pop()`相同，返回的 future 被 drop 时会取消等待。
+    ///
+    /// # Errors
+    /// 如果某个 key 的 value 不是流，返回`Error::WrongType`。
+    pub(crate) async fn blocking_xread(
+        &self,
+        keys: &[String],
+        ids: &[StreamId],
+        count: Option<usize>,
+    ) -> crate::Result<Vec<(String, Entries)>> {
+        self.block_on_keys(keys, |shards| {
+            let result = shards.xread(keys, ids, count)?;
+            Ok((!result.is_empty()).then_some(result))
+        })
+        .await
+    }
+
+    /// 在流上创建消费者组，`id`为`None`时从流中当前最后一条消息之后开始读取。
+    ///
+    /// 如果 key 不存在且`mkstream`为`true`，会先创建一个空的流。
+    ///
+    /// # Errors
+    /// 如果 value 不是流，返回`Error::WrongType`；如果 key 不存在且`mkstream`为`false`，
+    /// 或者组已经存在，返回`Err`。
+    pub(crate) fn xgroup_create(
+        &self,
+        key: &str,
+        group: &str,
+        id: Option<StreamId>,
+        mkstream: bool,
+    ) -> crate::Result<()> {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        if mkstream {
+            state.insert_if_absent(key, || Value::Stream(Stream::new()));
+        }
+        let stream = match state.entries.get_mut(key) {
+            Some(entry) => entry.data.as_stream_mut()?,
+            None => return Err("ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically.".into()),
+        };
+
+        let id = id.unwrap_or_else(|| stream.last_id());
+        if !stream.create_group(group, id) {
+            return Err("BUSYGROUP Consumer Group name already exists".into());
+        }
+        Ok(())
+    }
+
+    /// 删除流上的消费者组，如果组存在，返回`true`。
+    ///
+    /// # Errors
+    /// 如果 value 不是流，返回`Error::WrongType`。
+    pub(crate) fn xgroup_destroy(&self, key: &str, group: &str) -> crate::Result<bool> {
+        let mut state = self.shared.lock_key(key);
+        match state.entries.get_mut(key) {
+            Some(entry) => Ok(entry.data.as_stream_mut()?.destroy_group(group)),
+            None => Ok(false),
+        }
+    }
+
+    /// 以消费者组`group`中`consumer`的身份读取每个流中的消息，每个流最多返回`count`条。
+    ///
+    /// 读取新消息时只返回有新消息的流；读取待确认的消息时总是返回这个流。
+    ///
+    /// # Errors
+    /// 如果某个 key 的 value 不是流，返回`Error::WrongType`；
+    /// 如果某个 key 不存在或者没有这个消费者组，返回`Err`。
+    pub(crate) fn xreadgroup(
+        &self,
+        group: &str,
+        consumer: &str,
+        keys: &[String],
+        from: &[GroupRead],
+        count: Option<usize>,
+    ) -> crate::Result<Vec<(String, Entries)>> {
+        let mut shards = self.shared.lock_keys(keys);
+        shards.xreadgroup(group, consumer, keys, from, count)
+    }
+
+    /// 与`xreadgroup()`相同，但是如果所有流中都没有新消息，等待直到有新消息被添加。
+    ///
+    /// 等待的方式与`blocking_pop()`相同，返回的 future 被 drop 时会取消等待。
+    ///
+    /// # Errors
+    /// 同`xreadgroup()`。
+    pub(crate) async fn blocking_xreadgroup(
+        &self,
+        group: &str,
+        consumer: &str,
+        keys: &[String],
+        from: &[GroupRead],
+        count: Option<usize>,
+    ) -> crate::Result<Vec<(String, Entries)>> {
+        self.block_on_keys(keys, |shards| {
+            let result = shards.xreadgroup(group, consumer, keys, from, count)?;
+            Ok((!result.is_empty()).then_some(result))
+        })
+        .await
+    }
+
+    /// 确认消费者组中的消息已经被处理，返回被确认的消息的数量。
+    ///
+    /// 如果 key 或者消费者组不存在，返回`0`。
+    ///
+    /// # Errors
+    /// 如果 value 不是流，返回`Error::WrongType`。
+    pub(crate) fn xack(&self, key: &str, group: &str, ids: &[StreamId]) -> crate::Result<usize> {
+        let mut state = self.shared.lock_key(key);
+        match state.entries.get_mut(key) {
+            Some(entry) => Ok(entry.data.as_stream_mut()?.ack(group, ids).unwrap_or(0)),
+            None => Ok(0),
+        }
+    }
+
+    /// 将消费者组中空闲时间不少于`min_idle`的待确认消息转移给`consumer`，返回被认领的消息。
+    ///
+    /// # Errors
+    /// 如果 value 不是流，返回`Error::WrongType`；如果 key 或者消费者组不存在，返回`Err`。
+    pub(crate) fn xclaim(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle: Duration,
+        ids: &[StreamId],
+    ) -> crate::Result<Entries> {
+        let mut state = self.shared.lock_key(key);
+        let stream = match state.entries.get_mut(key) {
+            Some(entry) => entry.data.as_stream_mut()?,
+            None => return Err(no_group(key, group).into()),
+        };
+        let min_idle_ms = min_idle.as_millis() as u64;
+        stream
+            .claim(group, consumer, min_idle_ms, ids, unix_millis())
+            .ok_or_else(|| no_group(key, group).into())
+    }
+
+    /// 获取 key 剩余的生存时间。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`None`；如果 key 没有设置过期时间，返回`Some(None)`。
+    pub(crate) fn ttl(&self, key: &str) -> Option<Option<Duration>> {
+        let state = self.shared.lock_key(key);
+        let entry = state.entries.get(key)?;
+        let now = Instant::now();
+        match entry.expires_at {
+            // 已经过期但还没有被后台任务清除，视为不存在。
+            Some(when) if when <= now => None,
+            Some(when) => Some(Some(when - now)),
+            None => Some(None),
+        }
+    }
+
+    /// 获取 key 过期的时刻。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`None`；如果 key 没有设置过期时间，返回`Some(None)`。
+    pub(crate) fn expire_time(&self, key: &str) -> Option<Option<SystemTime>> {
+        let state = self.shared.lock_shard(key);
+        let entry = state.live_entry(key)?;
+        Some(entry.expires_at.map(|when| Clock::now().to_system(when)))
+    }
+
+    /// 为已经存在的 key 设置过期的时刻，会覆盖原有的过期时间。
+    ///
+    /// 过期的时刻已经过去时立即删除 key。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`false`。
+    pub(crate) fn expire_at(&self, key: &str, when: SystemTime) -> bool {
+        let expire = when
+            .duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO);
+        self.expire(key, expire)
+    }
+
+    /// 为已经存在的 key 设置过期时间，会覆盖原有的过期时间。
+    ///
+    /// 过期时间为`0`时立即删除 key。
+    ///
+    /// # Output
+    /// 如果 key 不存在，返回`false`。
+    pub(crate) fn expire(&self, key: &str, expire: Duration) -> bool {
+        let mut guard = self.shared.lock_key(key);
+        let state = &mut *guard;
+
+        // 从`expirations`中删除原有的过期时间。
+        match state.entries.get_mut(key) {
+            Some(entry) => {
+                if let Some(when) = entry.expires_at.take() {
+                    state.expirations.remove(when, key);
+                }
+            }
+            None => return false,
+        }
+
+        if expire.is_zero() {
+            if let Some(entry) = state.entries.remove(key) {
+                state.used_memory -= entry.size;
+                state.free(entry);
+            }
+            state.invalidate(key);
+            self.shared.notify_storage(|| StorageEvent::Delete {
+                key: key.to_string(),
+            });
+            return true;
+        }
+
+        let when = Instant::now() + expire;
+        // 新的过期时间是最早的，需要通知后台任务。
+        let notify = state
+            .next_expiration()
+            .map(|expiration| expiration > when)
+            .unwrap_or(true);
+
+        if let Some(entry) = state.entries.get_mut(key) {
+            entry.expires_at = Some(when);
+            if let Ok(value) = entry.data.as_string() {
+                self.shared.notify_storage(|| StorageEvent::Set {
+                    key: key.to_string(),
+                    value: value.clone(),
+                    expire: Some(expire),
+                });
+            }
+        }
+        state.expirations.insert(when, key);
+
+        drop(guard);
+
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+        true
+    }
+
+    /// 获取数据库的快照，已经过期的 key 不会包含在内。
+    ///
+    /// 同时持有所有分片的锁，时间只有克隆每个分片的`entries`的 O(1) 时间，
+    /// 之后在锁外遍历快照，不会阻塞其他连接的写操作。
+    pub(crate) fn snapshot(&self) -> Vec<SnapshotEntry> {
+        self.snapshot_with(|| ()).0
+    }
+
+    /// 获取数据库的快照，同时在持有所有分片的锁时调用`f`，例如注册一个全量同步的副本。
+    pub(crate) fn snapshot_with<T>(&self, f: impl FnOnce() -> T) -> (Vec<SnapshotEntry>, T) {
+        let (entries, value): (Vec<_>, T) = {
+            let shards = self.shared.lock_all();
+            let entries = shards.iter().map(|state| state.entries.clone()).collect();
+            (entries, f())
+        };
+        let clock = Clock::now();
+
+        let entries = entries
+            .into_iter()
+            .flatten()
+            .filter(|(_, entry)| {
+                entry
+                    .expires_at
+                    .map(|when| when > clock.now)
+                    .unwrap_or(true)
+            })
+            .map(|(key, entry)| SnapshotEntry {
+                expires_at: entry.expires_at.map(|when| clock.to_system(when)),
+                key,
+                value: entry.data,
+            })
+            .collect();
+        (entries, value)
+    }
+
+    /// 使用快照替换数据库中的所有数据，快照中已经过期的 key 会被忽略。
+    ///
+    /// 替换过程持有所有分片的锁，其他连接不会看到只恢复了一部分的数据。
+    pub(crate) fn restore(&self, entries: Vec<SnapshotEntry>) {
+        let mut shards = self.shared.lock_all();
+        let clock = Clock::now();
+
+        for state in shards.iter_mut() {
+            for key in state.entries.keys() {
+                self.shared
+                    .notify_storage(|| StorageEvent::Delete { key: key.clone() });
+            }
+            state.entries.clear();
+            state.expirations.clear();
+            state.used_memory = 0;
+        }
+
+        for entry in entries {
+            let state = &mut *shards[self.shared.shard_index(&entry.key)];
+            let expires_at = match entry.expires_at {
+                Some(when) => match clock.to_instant(when) {
+                    Some(when) => Some(when),
+                    // 已经过期了。
+                    None => continue,
+                },
+                None => None,
+            };
+
+            if let Ok(value) = entry.value.as_string() {
+                self.shared.notify_storage(|| StorageEvent::Set {
+                    key: entry.key.clone(),
+                    value: value.clone(),
+                    expire: expires_at.map(|when| when - clock.now),
+                });
+            }
+            if let Some(when) = expires_at {
+                state.expirations.insert(when, &entry.key);
+            }
+            let new_entry = Entry::new(&entry.key, entry.value, expires_at);
+            state.used_memory += new_entry.size;
+            if let Some(prev) = state.entries.insert(entry.key.clone(), new_entry) {
+                // 快照中出现了重复的 key，以后出现的为准。
+                state.used_memory -= prev.size;
+                if let Some(when) = prev.expires_at {
+                    state.expirations.remove(when, &entry.key);
+                }
+                state.free(prev);
+            }
+        }
+
+        // 恢复的数据中可能有非空的列表或者新的消息，唤醒所有阻塞的连接重新检查。
+        for state in &shards {
+            for waiter in state.key_waiters.values().flatten() {
+                waiter.notify_one();
+            }
+        }
+
+        drop(shards);
+
+        // 过期时间全部改变了，通知后台任务重新计算休眠时间。
+        self.shared.background_task.notify_one();
+    }
+
+    /// 根据订阅的信道的名称，返回`Receiver`。
+    ///
+    /// 如果订阅的信道不存在，那么会创建这个广播信道。
+    pub(crate) fn subscribe(&self, key: String) -> broadcast::Receiver<Bytes> {
+        use std::collections::hash_map::Entry;
+
+        let mut pubsub = self.shared.pubsub.lock().unwrap();
+        match pubsub.pub_sub.entry(key) {
+            // 如果请求的信道已经存在，那么就返回广播接收端
+            Entry::Occupied(e) => e.get().subscribe(),
+            // 如果不存在，就新建
+            Entry::Vacant(e) => {
+                // 这个广播信道可以存放`1024`条信息。
+                // 一条信息会一直存放，直到所有订阅者都接受到信息后才被删除
+                // 当信道容量被占满后，旧的信息会被删除
+                let (tx, rx) = broadcast::channel(1024);
+                e.insert(tx);
+                rx
+            }
+        }
+    }
+
+    /// 根据订阅的分片信道的名称，返回`Receiver`。
+    ///
+    /// 如果订阅的分片信道不存在，那么会创建这个广播信道。
+    pub(crate) fn ssubscribe(&self, channel: String) -> broadcast::Receiver<Bytes> {
+        let mut pubsub = self.shared.pubsub.lock().unwrap();
+        pubsub
+            .shard_pub_sub
+            .entry(channel)
+            .or_insert_with(|| broadcast::channel(1024).0)
+            .subscribe()
+    }
+
+    /// 向指定分片信道发送信息，返回分片信道的订阅者的数量。
+    ///
+    /// 分片信道不会匹配模式订阅。
+    pub(crate) fn spublish(&self, channel: &str, value: Bytes) -> usize {
+        let pubsub = self.shared.pubsub.lock().unwrap();
+        pubsub
+            .shard_pub_sub
+            .get(channel)
+            .map(|tx| tx.send(value).unwrap_or(0))
+            .unwrap_or(0)
+    }
+
+    /// 根据订阅的 glob 模式，返回`Receiver`，接收到的信息为信道名称和内容。
+    ///
+    /// 如果这个模式还没有被订阅过，那么会创建对应的广播信道。
+    pub(crate) fn psubscribe(&self, pattern: String) -> broadcast::Receiver<(String, Bytes)> {
+        let mut pubsub = self.shared.pubsub.lock().unwrap();
+        pubsub
+            .pattern_sub
+            .entry(pattern)
+            .or_insert_with(|| broadcast::channel(1024).0)
+            .subscribe()
+    }
+
+    /// 向指定信道发送信息，返回收到信息的订阅者的数量。
+    ///
+    /// 信息同时会发送给所有匹配信道名称的模式的订阅者，
+    /// 同时通过信道和模式订阅的客户端会被计算多次。
+    pub(crate) fn publish(&self, key: &str, value: Bytes) -> usize {
+        let pubsub = self.shared.pubsub.lock().unwrap();
+        pubsub.publish(key, value)
+    }
+
+    /// 开启或关闭连接`id`的客户端缓存追踪。
+    ///
+    /// 关闭追踪后，这个连接读取过的 key 被修改时不会再发送失效信息。
+    pub(crate) fn set_tracking(&self, id: u64, on: bool) {
+        let mut pubsub = self.shared.pubsub.lock().unwrap();
+        if on {
+            pubsub.tracking_clients.insert(id);
+        } else {
+            pubsub.tracking_clients.remove(&id);
+        }
+    }
+
+    /// 记录连接`id`读取了`key`，没有开启追踪的连接会被忽略。
+    pub(crate) fn track_key(&self, id: u64, key: &str) {
+        let tracking = self
+            .shared
+            .pubsub
+            .lock()
+            .unwrap()
+            .tracking_clients
+            .contains(&id);
+        if !tracking {
+            return;
+        }
+        self.shared
+            .lock_shard(key)
+            .tracking_table
+            .entry(key.to_string())
+            .or_default()
+            .insert(id);
+    }
+
+    /// 通知追踪了`key`的连接，`key`已经被修改了。
+    ///
+    /// 用于`Db`之外修改了 key 的命令，`Db`自身的写入、删除和过期会自动通知。
+    pub(crate) fn invalidate(&self, key: &str) {
+        self.shared.lock_shard(key).invalidate(key);
+    }
+
+    /// 在持有锁的情况下调用`try_once`，直到它返回`Some`，返回其中的结果。
+    ///
+    /// `try_once`返回`None`时，等待直到`keys`中的某个 key 被修改后再重试。等待期间不持有锁，
+    /// 返回的 future 被 drop 时会取消等待，所以超时和关闭可以通过`select!`实现。
+    async fn block_on_keys<T>(
+        &self,
+        keys: &[String],
+        mut try_once: impl FnMut(&mut ShardGuards) -> crate::Result<Option<T>>,
+    ) -> crate::Result<T> {
+        let mut waiter = KeyWaiter::new(self.clone(), keys);
+
+        loop {
+            {
+                let mut shards = self.shared.lock_keys(keys);
+                if let Some(result) = try_once(&mut shards)? {
+                    return Ok(result);
+                }
+                // 在释放锁之前注册，之后对这些 key 的修改一定会唤醒这个连接。
+                waiter.register(&mut shards);
+            }
+            // 这一次没有修改数据，等待期间其他写命令不需要等待这个命令的复制序号。
+            self.shared.replication.unstamp();
+
+            // `notify_one()`会保存一个许可，所以即使在释放锁之后、开始等待之前
+            // 就有修改发生，也不会错过通知。
+            waiter.notify.notified().await;
+        }
+    }
+
+    /// 通知后台任务关闭。
+    ///
+    /// 这个函数被`DbDropGuard`的`Drop`实现调用。
+    fn shutdown_purge_task(&self) {
+        // 通过修改`Shared::shutdown`来通知后台任务。
+        self.shared.shutdown.store(true, Ordering::Release);
+        self.shared.background_task.notify_one();
+    }
+}
+
+impl Shared {
+    /// 执行一轮主动清除，从下标为`start`的分片开始依次清除每个分片中过期的`Entry`。
+    ///
+    /// 与 Redis 一样，每次获取分片的锁随机抽样`ACTIVE_EXPIRE_SLICE_KEYS`个设置了过期时间的 key，
+    /// 清除其中过期的，检查完释放锁。抽样中过期的比例超过`ACTIVE_EXPIRE_STALE_PERCENT`时，
+    /// 说明分片中还有很多过期的 key，继续抽样，否则处理下一个分片，剩下的少量过期 key
+    /// 由下一轮或者访问时的检查清除。
+    /// 一轮最多占用`ACTIVE_EXPIRE_CYCLE_TIME`，超过后返回`ExpireCycle::Unfinished`，
+    /// 后台任务稍后从没有完成的分片继续。
+    fn active_expire_cycle(&self, start: usize) -> ExpireCycle {
+        if self.is_shutdown() {
+            // 数据库正在关闭，不存在下一个应该被清除的`Entry`的过期时间。
+            return ExpireCycle::Done(None);
+        }
+
+        let deadline = Instant::now() + ACTIVE_EXPIRE_CYCLE_TIME;
+        let mut next: Option<Instant> = None;
+        for i in 0..self.shards.len() {
+            let index = (start + i) % self.shards.len();
+            loop {
+                // 语句结束时释放锁。
+                let (sampled, expired, when) = self.expire_slice(&mut self.shards[index].lock());
+                if expired * 100 > sampled * ACTIVE_EXPIRE_STALE_PERCENT {
+                    // 还有很多过期的`Entry`没有清除，时间用完了就让出，否则继续清除。
+                    if Instant::now() >= deadline {
+                        return ExpireCycle::Unfinished(index);
+                    }
+                    continue;
+                }
+                if let Some(when) = when {
+                    next = Some(next.map_or(when, |next| next.min(when)));
+                }
+                break;
+            }
+        }
+        // 没有被抽样到的过期 key 留到`ACTIVE_EXPIRE_INTERVAL`之后的下一轮，不需要立即开始。
+        let now = Instant::now();
+        let next = next.map(|when| {
+            if when <= now {
+                now + ACTIVE_EXPIRE_INTERVAL
+            } else {
+                when
+            }
+        });
+        ExpireCycle::Done(next)
+    }
+
+    /// 随机抽样一个分片中`ACTIVE_EXPIRE_SLICE_KEYS`个设置了过期时间的 key，清除其中过期的`Entry`。
+    ///
+    /// 返回抽样的 key 的数量、其中过期的数量，以及这个分片中剩下的最早的过期时间。
+    fn expire_slice(&self, state: &mut State) -> (usize, usize, Option<Instant>) {
+        let sample = state.expirations.sample(ACTIVE_EXPIRE_SLICE_KEYS);
+        let before = state.expirations.len();
+        for key in &sample {
+            self.expire_if_needed(state, key);
+        }
+        let expired = before - state.expirations.len();
+        (sample.len(), expired, state.next_expiration())
+    }
+
+    /// 返回`key`所在的分片的下标。
+    ///
+    /// 与集群模式一样按照槽分配，使用相同哈希标签的 key 位于同一个分片中。
+    fn shard_index(&self, key: &str) -> usize {
+        cluster::key_slot(key.as_bytes()) as usize % self.shards.len()
+    }
+
+    /// 获取`key`所在的分片的锁，不检查`key`是否过期。
+    ///
+    /// 获取分片的锁时都会为正在执行的写命令分配复制序号，见`Replication::stamp()`。
+    fn lock_shard(&self, key: &str) -> ShardGuard<'_> {
+        let state = self.shards[self.shard_index(key)].lock();
+        self.replication.stamp();
+        state
+    }
+
+    /// 获取`key`所在的分片的锁，如果`key`已经过期，先将它删除。
+    fn lock_key(&self, key: &str) -> ShardGuard<'_> {
+        let mut state = self.lock_shard(key);
+        self.expire_if_needed(&mut state, key);
+        state
+    }
+
+    /// 按照下标从小到大的顺序获取`keys`所在的所有分片的锁，并删除`keys`中已经过期的 key。
+    fn lock_keys(&self, keys: &[String]) -> ShardGuards<'_> {
+        let mut indexes: Vec<usize> = keys.iter().map(|key| self.shard_index(key)).collect();
+        indexes.sort_unstable();
+        indexes.dedup();
+
+        let guards = indexes
+            .into_iter()
+            .map(|index| (index, self.shards[index].lock()))
+            .collect();
+        self.replication.stamp();
+        let mut shards = ShardGuards {
+            shared: self,
+            guards,
+        };
+        for key in keys {
+            self.expire_if_needed(shards.get(key), key);
+        }
+        shards
+    }
+
+    /// 按照下标从小到大的顺序获取所有分片的锁，用于快照这类需要一致地访问所有数据的操作。
+    fn lock_all(&self) -> Vec<ShardGuard<'_>> {
+        let shards = self.shards.iter().map(Shard::lock).collect();
+        self.replication.stamp();
+        shards
+    }
+
+    /// 如果`key`已经过期，将它删除。
+    ///
+    /// 后台任务可能还没有清除过期的 key，也可能被`Debug Set-Active-Expire`关闭了，
+    /// 命令访问 key 之前通过它删除过期的 key，保证不会读到过期的数据。
+    fn expire_if_needed(&self, state: &mut State, key: &str) {
+        let expired = state
+            .entries
+            .get(key)
+            .and_then(|entry| entry.expires_at)
+            .is_some_and(|when| when <= Instant::now());
+        if !expired {
+            return;
+        }
+        if let Some(entry) = state.remove_entry(key) {
+            self.notify_storage(|| StorageEvent::Expire {
+                key: key.to_string(),
+            });
+            state.free(entry);
+        }
+    }
+
+    /// 如果设置了存储钩子，发送修改事件。
+    ///
+    /// 使用闭包创建事件，没有设置钩子时不需要复制 key 和 value。
+    fn notify_storage(&self, event: impl FnOnce() -> StorageEvent) {
+        if let Some(tx) = self.storage.get() {
+            // 后台任务只会在所有发送端都被 drop 后才退出，所以这里不会失败。
+            let _ = tx.send(event());
+        }
+    }
+
+    /// 如果数据库正在关闭，返回`true`。
+    fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::Acquire)
+    }
+}
+
+impl State {
+    /// 返回分片中最早的过期时间。
+    fn next_expiration(&self) -> Option<Instant> {
+        self.expirations.first()
+    }
+
+    /// 获取没有过期的`Entry`，已经过期但还没有被后台任务清除的 key 视为不存在。
+    fn live_entry(&self, key: &str) -> Option<&Entry> {
+        let entry = self.entries.get(key)?;
+        match entry.expires_at {
+            Some(when) if when <= Instant::now() => None,
+            _ => Some(entry),
+        }
+    }
+
+    /// 如果 key 不存在，插入一个由`default`创建的、没有过期时间的 value。
+    fn insert_if_absent(&mut self, key: &str, default: impl FnOnce() -> Value) {
+        if !self.entries.contains_key(key) {
+            let entry = Entry::new(key, default(), None);
+            self.used_memory += entry.size;
+            self.entries.insert(key.to_string(), entry);
+        }
+    }
+
+    /// 唤醒所有阻塞在`key`上的连接。
+    fn wake_waiters(&self, key: &str) {
+        if let Some(waiters) = self.key_waiters.get(key) {
+            for waiter in waiters {
+                waiter.notify_one();
+            }
+        }
+    }
+
+    /// `key`被修改了，如果有仍在追踪它的连接，向`INVALIDATE_CHANNEL`发送失效信息。
+    fn invalidate(&mut self, key: &str) {
+        let Some(ids) = self.tracking_table.remove(key) else {
+            return;
+        };
+        let pubsub = self.pubsub.lock().unwrap();
+        if ids.iter().any(|id| pubsub.tracking_clients.contains(id)) {
+            pubsub.publish(INVALIDATE_CHANNEL, Bytes::from(key.to_string()));
+        }
+    }
+
+    /// 释放被删除或者被覆盖的`Entry`。
+    ///
+    /// 释放一个很大的 value 需要逐个释放其中的元素，所以超过`LAZY_FREE_THRESHOLD`的
+    /// value 交给后台线程去 drop，不会在持有锁的时候或者在`Handler`中释放，避免阻塞其他连接。
+    fn free(&self, entry: Entry) {
+        if entry.size > LAZY_FREE_THRESHOLD {
+            self.lazy_free_stats.pending.fetch_add(1, Ordering::Relaxed);
+            // 后台线程只会在所有发送端都被 drop 后才退出，所以这里不会失败。
+            let _ = self.lazy_free.send(entry);
+        }
+    }
+
+    /// 删除 key，同时维护`expirations`和`used_memory`。
+    fn remove_entry(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.used_memory -= entry.size;
+        self.invalidate(key);
+        if let Some(when) = entry.expires_at {
+            self.expirations.remove(when, key);
+        }
+        Some(entry)
+    }
+}
+
+impl Expirations {
+    /// 记录`key`的过期时间，`key`原有的过期时间需要先通过`remove()`删除。
+    fn insert(&mut self, when: Instant, key: &str) {
+        self.by_deadline.insert((when, key.to_string()));
+        if !self.index.contains_key(key) {
+            self.index.insert(key.to_string(), self.keys.len());
+            self.keys.push(key.to_string());
+        }
+    }
+
+    /// 删除`key`的过期时间`when`。
+    fn remove(&mut self, when: Instant, key: &str) {
+        self.by_deadline.remove(&(when, key.to_string()));
+        if let Some(index) = self.index.remove(key) {
+            self.keys.swap_remove(index);
+            // 原来的最后一个 key 被移动到了`index`。
+            if let Some(moved) = self.keys.get(index) {
+                self.index.insert(moved.clone(), index);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.by_deadline.clear();
+        self.keys.clear();
+        self.index.clear();
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// 返回最早的过期时间。
+    fn first(&self) -> Option<Instant> {
+        self.by_deadline.first().map(|entry| entry.0)
+    }
+
+    /// 随机抽取最多`count`个不重复的 key。
+    fn sample(&self, count: usize) -> Vec<String> {
+        let mut rng = rand::thread_rng();
+        rand::seq::index::sample(&mut rng, self.keys.len(), count.min(self.keys.len()))
+            .into_iter()
+            .map(|index| self.keys[index].clone())
+            .collect()
+    }
+}
+
+impl PubSub {
+    /// 在持有锁的情况下发送信息，见`Db::publish()`。
+    fn publish(&self, key: &str, value: Bytes) -> usize {
+        let channel_subs = self
+            .pub_sub
+            .get(key)
+            // 信道存在，发送信息
+            // 如果没有订阅者，那么`send()`会返回错误，返回`0`
+            .map(|tx| tx.send(value.clone()).unwrap_or(0))
+            // 信道不存在，当然也没有订阅者，返回`0`
+            .unwrap_or(0);
+        let pattern_subs: usize = self
+            .pattern_sub
+            .iter()
+            .filter(|(pattern, _)| glob_match(pattern.as_bytes(), key.as_bytes()))
+            .map(|(_, tx)| tx.send((key.to_string(), value.clone())).unwrap_or(0))
+            .sum();
+        channel_subs + pattern_subs
+    }
+}
+
+impl Shard {
+    /// 获取分片的锁。
+    fn lock(&self) -> ShardGuard<'_> {
+        ShardGuard {
+            state: self.state.lock().unwrap(),
+            published: &self.published,
+        }
+    }
+}
+
+impl Deref for ShardGuard<'_> {
+    type Target = State;
+
+    fn deref(&self) -> &State {
+        &self.state
+    }
+}
+
+impl DerefMut for ShardGuard<'_> {
+    fn deref_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+}
+
+impl Drop for ShardGuard<'_> {
+    fn drop(&mut self) {
+        // 修改`entries`时会复制被发布的版本共享的节点，根节点也随之改变，
+        // 所以根节点相同说明没有被修改过，不需要重新发布。
+        let published = self.published.load();
+        let Some(entries) = &*published else {
+            return;
+        };
+        if !entries.ptr_eq(&self.state.entries) {
+            self.published
+                .store(Some(Arc::new(self.state.entries.clone())));
+        }
+    }
+}
+
+impl ShardGuards<'_> {
+    /// 获取`key`所在的分片，这个分片必须已经被加锁。
+    fn get(&mut self, key: &str) -> &mut State {
+        let index = self.shared.shard_index(key);
+        let pos = self
+            .guards
+            .binary_search_by_key(&index, |(index, _)| *index)
+            .expect("key 所在的分片没有加锁");
+        &mut self.guards[pos].1
+    }
+
+    /// 在持有锁的情况下读取流中的新消息，见`Db::xread()`。
+    fn xread(
+        &mut self,
+        keys: &[String],
+        ids: &[StreamId],
+        count: Option<usize>,
+    ) -> crate::Result<Vec<(String, Entries)>> {
+        let mut result = vec![];
+        for (key, id) in keys.iter().zip(ids) {
+            let stream = match self.get(key).entries.get(key) {
+                Some(entry) => entry.data.as_stream()?,
+                None => continue,
+            };
+            let entries: Vec<_> = stream
+                .range(Bound::Excluded(*id), Bound::Unbounded)
+                .take(count.unwrap_or(usize::MAX))
+                .map(|(id, fields)| (*id, fields.clone()))
+                .collect();
+            if !entries.is_empty() {
+                result.push((key.clone(), entries));
+            }
+        }
+        Ok(result)
+    }
+
+    /// 在持有锁的情况下以消费者组的身份读取消息，见`Db::xreadgroup()`。
+    fn xreadgroup(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        keys: &[String],
+        from: &[GroupRead],
+        count: Option<usize>,
+    ) -> crate::Result<Vec<(String, Entries)>> {
+        let now_ms = unix_millis();
+        // 先检查所有的 key，保证出错时不会投递任何消息。
+        for key in keys {
+            let has_group = match self.get(key).entries.get(key) {
+                Some(entry) => entry.data.as_stream()?.has_group(group),
+                None => false,
+            };
+            if !has_group {
+                return Err(
+                    format!("{} in XREADGROUP with GROUP option", no_group(key, group)).into(),
+                );
+            }
+        }
+
+        let mut result = vec![];
+        for (key, from) in keys.iter().zip(from) {
+            let stream = self
+                .get(key)
+                .entries
+                .get_mut(key)
+                .unwrap()
+                .data
+                .as_stream_mut()?;
+            let entries = stream
+                .read_group(group, consumer, *from, count, now_ms)
+                .unwrap_or_default();
+            if !entries.is_empty() || matches!(from, GroupRead::History(_)) {
+                result.push((key.clone(), entries));
+            }
+        }
+        Ok(result)
+    }
+
+    /// 对`keys`对应的集合进行运算，不存在的 key 视为空集合。
+    fn combine_sets(&mut self, op: SetOp, keys: &[String]) -> crate::Result<im::HashSet<Bytes>> {
+        // 先检查所有的类型，保证任意一个 key 的类型不对时都返回错误。
+        let mut sets = Vec::with_capacity(keys.len());
+        for key in keys {
+            sets.push(match self.get(key).entries.get(key) {
+                Some(entry) => entry.data.as_set()?.clone(),
+                None => im::HashSet::new(),
+            });
+        }
+
+        let mut sets = sets.into_iter();
+        let first = sets.next().unwrap_or_default();
+        Ok(sets.fold(first, |result, set| match op {
+            SetOp::Inter => result.intersection(set),
+            SetOp::Union => result.union(set),
+            SetOp::Diff => result.relative_complement(set),
+        }))
+    }
+}
+
+impl Clock {
+    /// 同时读取单调时钟和系统时钟。
+    fn now() -> Clock {
+        Clock {
+            now: Instant::now(),
+            system_now: SystemTime::now(),
+        }
+    }
+
+    /// 将系统时间转换为`Instant`，如果这个时刻已经过去了，返回`None`。
+    fn to_instant(self, when: SystemTime) -> Option<Instant> {
+        match when.duration_since(self.system_now) {
+            Ok(ttl) if !ttl.is_zero() => self.now.checked_add(ttl),
+            _ => None,
+        }
+    }
+
+    /// 将`Instant`转换为系统时间。
+    fn to_system(self, when: Instant) -> SystemTime {
+        if when >= self.now {
+            self.system_now + (when - self.now)
+        } else {
+            self.system_now - (self.now - when)
+        }
+    }
+}
+
+impl Entry {
+    /// 创建一个`Entry`，同时计算它占用的近似字节数。
+    fn new(key: &str, data: Value, expires_at: Option<Instant>) -> Entry {
+        let size = entry_size(key, &data);
+        Entry {
+            data,
+            expires_at,
+            size,
+            last_access: Instant::now(),
+            access_count: 0,
+        }
+    }
+
+    /// 如果已经过了过期时间，返回`true`。
+    fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|when| when <= Instant::now())
+    }
+
+    /// 记录一次访问。
+    fn touch(&mut self) {
+        self.last_access = Instant::now();
+        self.access_count = self.access_count.saturating_add(1);
+    }
+}
+
+impl KeyWaiter {
+    fn new(db: Db, keys: &[String]) -> KeyWaiter {
+        KeyWaiter {
+            db,
+            keys: keys.to_vec(),
+            notify: Arc::new(Notify::new()),
+            registered: false,
+        }
+    }
+
+    /// 将自己注册到每个 key 的等待列表中，只会注册一次。
+    fn register(&mut self, shards: &mut ShardGuards) {
+        if self.registered {
+            return;
+        }
+        self.registered = true;
+        for key in &self.keys {
+            shards
+                .get(key)
+                .key_waiters
+                .entry(key.clone())
+                .or_default()
+                .push(self.notify.clone());
+        }
+    }
+}
+
+impl Drop for KeyWaiter {
+    fn drop(&mut self) {
+        if !self.registered {
+            return;
+        }
+        for key in &self.keys {
+            let mut state = self.db.shared.lock_shard(key);
+            if let Some(waiters) = state.key_waiters.get_mut(key) {
+                waiters.retain(|waiter| !Arc::ptr_eq(waiter, &self.notify));
+                if waiters.is_empty() {
+                    state.key_waiters.remove(key);
+                }
+            }
+        }
+    }
+}
+
+/// 从`items`中随机抽取元素。
+///
+/// `count`为正数时抽取不重复的元素，最多返回所有元素；为负数时可以重复抽取同一个元素，
+/// 总是返回`-count`个元素（集合为空时除外）。返回的元素的顺序也是随机的。
+///
+/// `im`的容器不支持按下标访问，所以需要遍历一次所有元素，时间复杂度为 O(n)。
+fn random_sample<T: Clone>(items: impl Iterator<Item = T>, count: i64) -> Vec<T> {
+    let mut rng = rand::thread_rng();
+    if count >= 0 {
+        let count = usize::try_from(count).unwrap_or(usize::MAX);
+        // `choose_multiple()`返回的元素的顺序不是随机的。
+        let mut sample = items.choose_multiple(&mut rng, count);
+        sample.shuffle(&mut rng);
+        sample
+    } else {
+        let items: Vec<T> = items.collect();
+        if items.is_empty() {
+            return vec![];
+        }
+        // 不预先分配`-count`大小的内存，`count`过大时`Vec::with_capacity()`会 panic。
+        let mut sample = vec![];
+        for _ in 0..count.unsigned_abs() {
+            sample.push(items[rng.gen_range(0..items.len())].clone());
+        }
+        sample
+    }
+}
+
+/// 将 geohash 转换为有序集合中的分数，52 位的整数可以被`f64`精确表示。
+fn geo_score(hash: u64) -> Score {
+    // 整数不可能是 NaN。
+    Score::new(hash as f64).unwrap()
+}
+
+/// 将`[start, stop]`中的负数下标转换为正数下标，并截断到`[0, len)`的范围内。
+///
+/// 如果范围为空，返回`None`。
+fn index_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        len + stop
+    } else {
+        stop.min(len - 1)
+    };
+    if start > stop {
+        return None;
+    }
+    Some((start as usize, stop as usize))
+}
+
+/// 获取当前时刻距离 UNIX 纪元的毫秒数。
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 流或者消费者组不存在时的错误信息。
+fn no_group(key: &str, group: &str) -> String {
+    format!(
+        "NOGROUP No such key '{}' or consumer group '{}'",
+        key, group
+    )
+}
+
+/// 计算一个 key-value 对占用的近似字节数。
+///
+/// 只计算 key 和 value 本身的长度，不包括`HashMap`等数据结构的额外开销。
+fn entry_size(key: &str, value: &Value) -> usize {
+    key.len() + value.size()
+}
+
+/// 异步后台任务，定期检查自动保存的规则，满足时在后台保存快照。
+async fn auto_save_tasks(db: Db) {
+    let mut interval = time::interval(AUTO_SAVE_INTERVAL);
+    while !db.shared.is_shutdown() {
+        interval.tick().await;
+        if db.shared.persistence.should_save() {
+            db.bgsave();
+        }
+    }
+}
+
+/// 有订阅者的信道或模式的名称。
+///
+/// 所有订阅者都退订后，广播的发送端仍然保留在`PubSub`中，所以需要过滤掉没有订阅者的信道。
+fn live_channels<T>(
+    channels: &HashMap<String, broadcast::Sender<T>>,
+) -> impl Iterator<Item = &String> {
+    channels
+        .iter()
+        .filter(|(_, tx)| tx.receiver_count() > 0)
+        .map(|(name, _)| name)
+}
+
+/// 有订阅者的信道或模式的名称占用的字节数。
+fn name_bytes<T>(channels: &HashMap<String, broadcast::Sender<T>>) -> usize {
+    live_channels(channels).map(String::len).sum()
+}
+
+/// 后台线程，负责释放被删除的大 value，见`State::free()`。
+///
+/// 使用单独的线程而不是异步任务，释放内存时不会占用 tokio 的工作线程。
+/// 所有分片被 drop 后，发送端也被 drop，线程随之退出。
+fn lazy_free_thread(mut rx: mpsc::UnboundedReceiver<Entry>, stats: Arc<LazyFreeStats>) {
+    while let Some(entry) = rx.blocking_recv() {
+        drop(entry);
+        stats.pending.fetch_sub(1, Ordering::Relaxed);
+        stats.freed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// 异步后台任务，负责清除过期`Entry`。
+///
+/// 它是周期性执行的，毕竟不能一直处于执行状态，它等待被通知。
+/// 当数据更新或收到关闭信号的时候，它会被通知。
+async fn purge_expired_tasks(shared: Arc<Shared>) {
+    // 下一轮主动清除开始的分片。
+    let mut start = 0;
+    // 被通知后会继续循环，如果发现 shutdown 为真，则退出循环。
+    while !shared.is_shutdown() {
+        // 关闭了主动清除时，等待被通知，过期的 key 由访问时的检查隐藏。
+        if !shared.active_expire.load(Ordering::Relaxed) {
+            shared.background_task.notified().await;
+            continue;
+        }
+
+        // 清除过期的`Entry`，函数会返回下一个应该被清除的`Entry`的过期时间。
+        let cycle_start = Instant::now();
+        let cycle = shared.active_expire_cycle(start);
+        let elapsed = cycle_start.elapsed();
+        shared.latency.record(latency::EXPIRE_CYCLE, elapsed);
+        trace!(
+            duration_us = elapsed.as_micros() as u64,
+            finished = matches!(cycle, ExpireCycle::Done(_)),
+            "主动清除过期的 key"
+        );
+        match cycle {
+            ExpireCycle::Done(Some(when)) => {
+                // 我们休眠到上述那个时刻，但是如果该任务在此期间被通知了
+                // (数据有更新)，就要重新循环，重新运行`active_expire_cycle()`，
+                // 毕竟下一个应该被清除的`Entry`的过期时间对应的`Entry`可能被操作了。
+                // 当然也有可能是通知关闭。
+                tokio::select! {
+                    _ = time::sleep_until(when) => {}
+                    _ = shared.background_task.notified() => {}
+                }
+            }
+            ExpireCycle::Done(None) => {
+                // 没有要清除的`Entry`了，等待被通知。
+                shared.background_task.notified().await;
+            }
+            ExpireCycle::Unfinished(index) => {
+                // 还有过期的`Entry`没有清除，让出线程，稍后从没有完成的分片继续。
+                // 这期间的通知不会提前唤醒，避免频繁的写入让清除一直占用线程。
+                start = index;
+                time::sleep(ACTIVE_EXPIRE_INTERVAL).await;
+            }
         }
     }
+    debug!("数据库关闭，清除过期 key 的后台任务退出");
 }
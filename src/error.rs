@@ -0,0 +1,136 @@
+use std::{error::Error as StdError, io};
+
+use tokio_rustls::rustls::{self, pki_types::InvalidDnsNameError};
+
+use crate::{frame, ParseError};
+
+/// 自定义的 Error，按照错误的原因分类，调用者可以通过匹配来处理特定的错误。
+///
+/// 为了兼容之前的`Box<dyn std::error::Error + Send + Sync>`，`String`、`&str`
+/// 以及错误特征对象都可以通过`into()`转换为`Error::Other`。
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// 底层 I/O 错误。
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    /// 收到的数据不符合协议，无法解析为`Frame`。
+    #[error("协议错误：{0}")]
+    Protocol(String),
+
+    /// `Frame`无法解析为合法的命令。
+    #[error("{0}")]
+    Parse(String),
+
+    /// 命令的参数不合法，`kind`是回复给客户端的错误类型，例如`ERR`。
+    #[error("{kind} {message}")]
+    Invalid { kind: &'static str, message: String },
+
+    /// 对 key 执行了与其类型不符的操作。
+    #[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
+    WrongType,
+
+    /// 认证失败，或者在执行命令前需要认证。
+    #[error("{0}")]
+    Auth(String),
+
+    /// 服务器返回的其他错误。
+    #[error("{0}")]
+    Server(String),
+
+    /// 对端关闭了连接。
+    #[error("连接已关闭")]
+    ConnectionClosed,
+
+    /// 操作超时。
+    #[error("操作超时")]
+    Timeout,
+
+    /// 其他错误。
+    #[error(transparent)]
+    Other(Box<dyn StdError + Send + Sync>),
+}
+
+impl Error {
+    /// 如果是超时错误，返回`true`，包括`Connection`写入超时产生的 I/O 错误。
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            Error::Timeout => true,
+            Error::Io(err) => err.kind() == io::ErrorKind::TimedOut,
+            _ => false,
+        }
+    }
+
+    /// 创建一个`ERR`类型的参数错误。
+    pub(crate) fn invalid(message: impl Into<String>) -> Error {
+        Error::Invalid {
+            kind: "ERR",
+            message: message.into(),
+        }
+    }
+
+    /// 将服务器返回的错误信息转换为`Error`。
+    ///
+    /// 根据错误信息的前缀区分错误的种类。
+    pub(crate) fn from_server(msg: String) -> Error {
+        let prefix = msg.split_whitespace().next().unwrap_or_default();
+        match prefix {
+            "WRONGTYPE" => Error::WrongType,
+            "NOAUTH" | "WRONGPASS" | "NOPERM" => Error::Auth(msg),
+            _ => Error::Server(msg),
+        }
+    }
+}
+
+impl From<frame::Error> for Error {
+    fn from(value: frame::Error) -> Error {
+        Error::Protocol(value.to_string())
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(value: ParseError) -> Error {
+        match value {
+            // 缺少参数，Redis 将其视为语法错误。
+            ParseError::EndOfStream => Error::invalid("syntax error"),
+            ParseError::Invalid(message) => Error::invalid(message),
+            ParseError::Other(message) => Error::Parse(message),
+        }
+    }
+}
+
+impl From<tokio::time::error::Elapsed> for Error {
+    fn from(_value: tokio::time::error::Elapsed) -> Error {
+        Error::Timeout
+    }
+}
+
+impl From<rustls::Error> for Error {
+    fn from(value: rustls::Error) -> Error {
+        Error::Other(value.into())
+    }
+}
+
+impl From<InvalidDnsNameError> for Error {
+    fn from(value: InvalidDnsNameError) -> Error {
+        Error::Other(value.into())
+    }
+}
+
+impl From<Box<dyn StdError + Send + Sync>> for Error {
+    fn from(value: Box<dyn StdError + Send + Sync>) -> Error {
+        Error::Other(value)
+    }
+}
+
+impl From<String> for Error {
+    fn from(value: String) -> Error {
+        Error::Other(value.into())
+    }
+}
+
+impl From<&str> for Error {
+    fn from(value: &str) -> Error {
+        Error::Other(value.into())
+    }
+}
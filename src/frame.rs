@@ -1,8 +1,13 @@
 //! 提供表示 Redis 协议帧的类型，提供用于解析字节数组中的帧的实用工具。
 
-use std::{fmt, io::Cursor, num::TryFromIntError, string::FromUtf8Error};
+use std::{
+    fmt::{self, Write},
+    io::Cursor,
+    num::TryFromIntError,
+    string::FromUtf8Error,
+};
 
-use bytes::{Buf, Bytes};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 
 /// Redis 协议帧
 /// 官方文档：https://redis.io/docs/reference/protocol-spec/
@@ -19,7 +24,7 @@ pub enum Frame {
     // 整数，64位无符号十进制数，
     // 通常用于表示字节数或数组元素个数。
     // 格式：:<value>\r\n
-    Integer(u64),
+    Integer(i64),
 
     // 大型字符串，通常用于表示字符串数据，长度任意。
     // 格式：$<length>\r\n<data>\r\n
@@ -35,8 +40,122 @@ pub enum Frame {
     Array(Vec<Frame>),
 
     // 空值，表示不存在的值。
+    // RESP2 的连接中编码为`$-1\r\n`，解析时`$-1\r\n`和`*-1\r\n`都视为空值。
     // 格式：_\r\n
     Null,
+
+    // RESP3 的映射类型，由键值对组成，例如`Hello`的响应。
+    // RESP2 的连接中编码为键和值交替出现的`Array`。
+    // 格式：%<number-of-pairs>\r\n<key-1><value-1>...<key-n><value-n>
+    Map(Vec<(Frame, Frame)>),
+
+    // RESP3 的浮点数类型，RESP2 的连接中编码为`Bulk`。
+    // 格式：,<floating-point-number>\r\n，无穷大和 NaN 分别为`inf`、`-inf`和`nan`。
+    Double(f64),
+
+    // RESP3 的推送类型，用于服务器主动发送的数据，例如订阅的信息。
+    // 它与`Array`的区别只在于客户端需要将其与命令的响应区分开，
+    // RESP2 的连接中编码为`Array`。
+    // 格式：><number-of-elements>\r\n<element-1>...<element-n>
+    Push(Vec<Frame>),
+
+    // RESP3 的集合类型，元素不重复，例如`SMembers`的响应。
+    // RESP2 的连接中编码为`Array`。
+    // 格式：~<number-of-elements>\r\n<element-1>...<element-n>
+    Set(Vec<Frame>),
+
+    // RESP3 的布尔类型，RESP2 的连接中编码为整数`1`或`0`。
+    // 格式：#t\r\n 或 #f\r\n
+    Boolean(bool),
+
+    // RESP3 的大数类型，超出 64 位整数范围的整数，以十进制字符串保存。
+    // RESP2 的连接中编码为`Bulk`。
+    // 格式：(<big-number>\r\n
+    BigNumber(String),
+
+    // RESP3 的带格式的字符串，第一个元素为三个字符的格式，例如`txt`和`mkd`，
+    // 客户端可以按照格式展示数据，例如`Info`的响应。RESP2 的连接中只编码数据为`Bulk`。
+    // 格式：=<length>\r\n<format>:<data>\r\n，长度包括格式和冒号
+    Verbatim(String, Bytes),
+}
+
+/// 连接使用的 Redis 协议版本。
+///
+/// 连接默认使用 RESP2。RESP3 的`Frame`按照 RESP2 编码时会被编码为等价的 RESP2 类型：
+/// `Map`编码为键和值交替出现的`Array`，`Push`和`Set`编码为`Array`，
+/// `Double`、`BigNumber`和`Verbatim`编码为`Bulk`，`Boolean`编码为整数`1`或`0`，
+/// `Null`编码为`$-1`。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Protocol {
+    #[default]
+    Resp2,
+    Resp3,
+}
+
+impl Protocol {
+    /// 获取协议版本号。
+    pub fn version(self) -> u8 {
+        match self {
+            Protocol::Resp2 => 2,
+            Protocol::Resp3 => 3,
+        }
+    }
+
+    /// 根据协议版本号获取协议，不支持的版本返回`None`。
+    pub fn from_version(version: u64) -> Option<Protocol> {
+        match version {
+            2 => Some(Protocol::Resp2),
+            3 => Some(Protocol::Resp3),
+            _ => None,
+        }
+    }
+}
+
+/// 解析`Frame`时的限制。
+///
+/// 长度头部来自不可信的数据，`*4294967295`或者`$1000000000`这样的头部会让连接一直缓存数据，
+/// 过深的嵌套会让递归的解析耗尽栈空间。超过限制的数据在读取到头部时就会被拒绝，返回协议错误。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameLimits {
+    /// `Bulk`、块错误和`Verbatim`的最大字节数，默认为 512MB，与 Redis 的`proto-max-bulk-len`相同。
+    pub max_bulk_len: usize,
+
+    /// 数组、`Set`和`Push`的最大元素个数，以及`Map`的最大键值对个数，默认为`i32::MAX`。
+    pub max_array_len: usize,
+
+    /// 最大的嵌套层数，顶层的数组为第`1`层，默认为 64 层。
+    pub max_depth: usize,
+}
+
+impl Default for FrameLimits {
+    fn default() -> FrameLimits {
+        FrameLimits {
+            max_bulk_len: 512 * 1024 * 1024,
+            max_array_len: i32::MAX as usize,
+            max_depth: 64,
+        }
+    }
+}
+
+impl FrameLimits {
+    /// 检查`Bulk`这类带长度的类型的长度。
+    fn bulk_len(&self, len: u64) -> Result<usize, Error> {
+        match usize::try_from(len) {
+            Ok(len) if len <= self.max_bulk_len => Ok(len),
+            _ => Err(format!("Bulk 的长度超过了限制：{}", len).into()),
+        }
+    }
+
+    /// 检查第`depth`层中的数组的元素个数以及数组的嵌套层数。
+    fn array_len(&self, len: u64, depth: usize) -> Result<usize, Error> {
+        if depth >= self.max_depth {
+            return Err(format!("嵌套层数超过了限制：{}", self.max_depth).into());
+        }
+        match usize::try_from(len) {
+            Ok(len) if len <= self.max_array_len => Ok(len),
+            _ => Err(format!("数组的元素个数超过了限制：{}", len).into()),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -45,7 +164,7 @@ pub enum Error {
     Incomplete,
 
     // 其他错误。
-    Other(crate::Error),
+    Other(String),
 }
 
 impl Frame {
@@ -73,7 +192,7 @@ impl Frame {
     /// # Panics
     ///
     /// 如果`self`不是一个数组，程序崩溃。
-    pub(crate) fn push_int(&mut self, value: u64) {
+    pub(crate) fn push_int(&mut self, value: i64) {
         match self {
             Frame::Array(vec) => {
                 vec.push(Frame::Integer(value));
@@ -82,12 +201,29 @@ impl Frame {
         }
     }
 
-    /// 检查是否可以从`src`中解码完整的信息。
+    /// 检查是否可以从`src`中解码完整的信息，使用默认的`FrameLimits`。
     /// 此函数会移动`src`至数据末尾，即`\r\n`后。
     ///
     /// # Errors
     /// 如果`src`中数据不完整，即非`\r\n`结尾，返回`Err`。
     pub fn check(src: &mut Cursor<&[u8]>) -> Result<(), Error> {
+        Frame::check_with(src, &FrameLimits::default())
+    }
+
+    /// 与`check()`相同，使用`limits`限制长度和嵌套层数。
+    ///
+    /// # Errors
+    /// 除了`check()`的错误，长度头部或者嵌套层数超过限制时返回`Err`，此时不需要等待数据完整。
+    pub fn check_with(src: &mut Cursor<&[u8]>, limits: &FrameLimits) -> Result<(), Error> {
+        Frame::check_nested(src, limits, 0)
+    }
+
+    /// 检查`depth`层数组中的一个元素，顶层的`Frame`为第`0`层。
+    fn check_nested(
+        src: &mut Cursor<&[u8]>,
+        limits: &FrameLimits,
+        depth: usize,
+    ) -> Result<(), Error> {
         match get_u8(src)? {
             b'+' => {
                 get_line(src)?;
@@ -98,26 +234,49 @@ impl Frame {
                 Ok(())
             }
             b':' => {
-                let _ = get_decimal(src)?;
+                let _ = get_signed_decimal(src)?;
                 Ok(())
             }
             b'$' => {
-                // 尝试获取`Bulk`的字节个数。
-                let len = TryInto::<usize>::try_into(get_decimal(src)?)?;
+                // 尝试获取`Bulk`的字节个数，RESP2 的空值没有数据。
+                let Some(len) = get_length(src)? else {
+                    return Ok(());
+                };
+                let len = limits.bulk_len(len)?;
                 // 保证光标处于末尾。
-                // +2 表示略过`\r\n`。
-                skip(src, len + 2)
+                // +2 表示略过`\r\n`，长度过大时不能溢出。
+                skip(src, len.saturating_add(2))
             }
-            b'*' => {
-                // 获取`Array`的元素个数。
-                let len = get_decimal(src)?;
+            b'!' => {
+                // 块错误与`Bulk`的格式相同。
+                let len = limits.bulk_len(get_decimal(src)?)?;
+                skip(src, len.saturating_add(2))
+            }
+            b'*' | b'>' | b'~' => {
+                // 获取`Array`的元素个数，RESP2 的空值没有元素。
+                let Some(len) = get_length(src)? else {
+                    return Ok(());
+                };
+                let len = limits.array_len(len, depth)?;
                 // 每个元素都必须是一个完整的`Frame`。
                 for _ in 0..len {
-                    Frame::check(src)?;
+                    Frame::check_nested(src, limits, depth + 1)?;
                 }
                 Ok(())
             }
-            b'_' => {
+            b'%' => {
+                // 每个键值对包含两个`Frame`。
+                let len = limits.array_len(get_decimal(src)?, depth)?;
+                for _ in 0..len.saturating_mul(2) {
+                    Frame::check_nested(src, limits, depth + 1)?;
+                }
+                Ok(())
+            }
+            b'=' => {
+                let len = limits.bulk_len(get_decimal(src)?)?;
+                skip(src, len.saturating_add(2))
+            }
+            b'_' | b',' | b'#' | b'(' => {
                 let _ = get_line(src)?;
                 Ok(())
             }
@@ -125,11 +284,30 @@ impl Frame {
         }
     }
 
-    /// 解析数据为`Frame`，需要保证数据已经通过了`check()`。
+    /// 解析数据为`Frame`，通常数据应该已经通过了`check()`，使用默认的`FrameLimits`。
+    ///
+    /// 即使数据没有通过`check()`，这个函数也不会崩溃，只会返回`Err`。
     ///
     /// # Errors
     /// 如果数据无法解析为`Frame`，返回`Err`。
     pub fn parse(src: &mut Cursor<&[u8]>) -> Result<Frame, Error> {
+        Frame::parse_with(src, &FrameLimits::default())
+    }
+
+    /// 与`parse()`相同，使用`limits`限制长度和嵌套层数。
+    ///
+    /// # Errors
+    /// 除了`parse()`的错误，长度头部或者嵌套层数超过限制时返回`Err`。
+    pub fn parse_with(src: &mut Cursor<&[u8]>, limits: &FrameLimits) -> Result<Frame, Error> {
+        Frame::parse_nested(src, limits, 0)
+    }
+
+    /// 解析`depth`层数组中的一个元素，顶层的`Frame`为第`0`层。
+    fn parse_nested(
+        src: &mut Cursor<&[u8]>,
+        limits: &FrameLimits,
+        depth: usize,
+    ) -> Result<Frame, Error> {
         match get_u8(src)? {
             b'+' => {
                 // 获取行，转化为字节 vec。
@@ -143,15 +321,35 @@ impl Frame {
                 let string = String::from_utf8(line)?;
                 Ok(Frame::Error(string))
             }
+            b'!' => {
+                // RESP3 的块错误，用于包含换行的错误信息，同样解析为`Error`。
+                let len = limits.bulk_len(get_decimal(src)?)?;
+                if src.remaining() < len.saturating_add(2) {
+                    return Err(Error::Incomplete);
+                }
+                let string = String::from_utf8(src.chunk()[0..len].to_vec())?;
+                skip(src, len + 2)?;
+                Ok(Frame::Error(string))
+            }
             b':' => {
-                let len = get_decimal(src)?;
-                Ok(Frame::Integer(len))
+                let value = get_signed_decimal(src)?;
+                Ok(Frame::Integer(value))
+            }
+            b',' => {
+                let value = std::str::from_utf8(get_line(src)?)
+                    .ok()
+                    .and_then(|s| s.parse::<f64>().ok())
+                    .ok_or_else(|| Into::<Error>::into("不合法的帧格式"))?;
+                Ok(Frame::Double(value))
             }
             b'$' => {
-                // 获取`Bulk`的字节个数。
-                let len = TryInto::<usize>::try_into(get_decimal(src)?)?;
+                // 获取`Bulk`的字节个数，`$-1`是 RESP2 的空值。
+                let Some(len) = get_length(src)? else {
+                    return Ok(Frame::Null);
+                };
+                let len = limits.bulk_len(len)?;
                 // `src`中可用的字节数小于应该拥有的字节数。
-                if src.remaining() < len + 2 {
+                if src.remaining() < len.saturating_add(2) {
                     return Err(Error::Incomplete);
                 }
                 let data = Bytes::copy_from_slice(&src.chunk()[0..len]);
@@ -159,34 +357,265 @@ impl Frame {
                 skip(src, len + 2)?;
                 Ok(Frame::Bulk(data))
             }
-            b'*' => {
-                // 获取Array的元素个数。
-                let len = TryInto::<usize>::try_into(get_decimal(src)?)?;
-                let mut result = Vec::with_capacity(len);
+            kind @ (b'*' | b'>' | b'~') => {
+                // 获取Array的元素个数，`*-1`是 RESP2 的空值。
+                let Some(len) = get_length(src)? else {
+                    return Ok(Frame::Null);
+                };
+                let len = limits.array_len(len, depth)?;
+                // 长度来自不可信的数据，预分配的空间不能超过剩余的字节数，
+                // 毕竟每个元素至少占用一个字节。
+                let mut result = Vec::with_capacity(len.min(src.remaining()));
                 // 解析每一个元素。
                 for _ in 0..len {
-                    result.push(Frame::parse(src)?);
+                    result.push(Frame::parse_nested(src, limits, depth + 1)?);
+                }
+                match kind {
+                    b'>' => Ok(Frame::Push(result)),
+                    b'~' => Ok(Frame::Set(result)),
+                    _ => Ok(Frame::Array(result)),
+                }
+            }
+            b'#' => match get_line(src)? {
+                b"t" => Ok(Frame::Boolean(true)),
+                b"f" => Ok(Frame::Boolean(false)),
+                _ => Err("不合法的帧格式".into()),
+            },
+            b'(' => {
+                // 只允许可选的负号加上十进制数字。
+                let line = get_line(src)?;
+                let digits = line.strip_prefix(b"-").unwrap_or(line);
+                if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+                    return Err("不合法的帧格式".into());
+                }
+                Ok(Frame::BigNumber(String::from_utf8(line.to_vec())?))
+            }
+            b'=' => {
+                let len = limits.bulk_len(get_decimal(src)?)?;
+                if src.remaining() < len.saturating_add(2) {
+                    return Err(Error::Incomplete);
+                }
+                // 数据之前是三个字符的格式和一个冒号。
+                let data = &src.chunk()[0..len];
+                if len < 4 || data[3] != b':' {
+                    return Err("不合法的帧格式".into());
+                }
+                let format = String::from_utf8(data[0..3].to_vec())?;
+                let data = Bytes::copy_from_slice(&data[4..]);
+                skip(src, len + 2)?;
+                Ok(Frame::Verbatim(format, data))
+            }
+            b'%' => {
+                let len = limits.array_len(get_decimal(src)?, depth)?;
+                // 每个键值对至少占用两个字节。
+                let mut result = Vec::with_capacity(len.min(src.remaining() / 2));
+                for _ in 0..len {
+                    let key = Frame::parse_nested(src, limits, depth + 1)?;
+                    let value = Frame::parse_nested(src, limits, depth + 1)?;
+                    result.push((key, value));
                 }
-                Ok(Frame::Array(result))
+                Ok(Frame::Map(result))
             }
             b'_' => {
                 let line = get_line(src)?;
-                if 0 == line.len() {
+                if line.is_empty() {
                     return Ok(Frame::Null);
                 }
                 Err("不合法的帧格式".into())
             }
-            // 永远不会到达这里，但是为了通过编译。
-            _ => unreachable!(),
+            invalid => Err(format!("不合法的帧类型符：{}", invalid).into()),
+        }
+    }
+
+    /// 从`buf`的开头解码一个完整的`Frame`，并将解码了的数据从`buf`中移除。
+    ///
+    /// 这个函数不需要`Connection`，可以用于测试、代理或者持久化。
+    ///
+    /// # Output
+    /// 数据不完整时返回`Ok(None)`，`buf`保持不变，可以在追加更多数据后重试。
+    ///
+    /// # Errors
+    /// 如果数据无法解析为`Frame`，返回`Err`。
+    pub fn decode(buf: &mut BytesMut) -> Result<Option<Frame>, Error> {
+        Frame::decode_with(buf, &FrameLimits::default())
+    }
+
+    /// 与`decode()`相同，使用`limits`限制长度和嵌套层数。
+    ///
+    /// # Errors
+    /// 除了`decode()`的错误，长度头部或者嵌套层数超过限制时返回`Err`。
+    pub fn decode_with(buf: &mut BytesMut, limits: &FrameLimits) -> Result<Option<Frame>, Error> {
+        // `Cursor`顾名思义是一个“光标”，可以看作是缓存的指针，跟踪字节。
+        let mut src = Cursor::new(&buf[..]);
+
+        // 第一步检查是否有足够的数据来解析为一个数据帧，这一步比真正的解析快很多。
+        match Frame::check_with(&mut src, limits) {
+            Ok(()) => {
+                // `check()`会将光标移动到帧的末尾，保留帧的字节长度后重置光标。
+                let len = src.position() as usize;
+                src.set_position(0);
+                let frame = Frame::parse_with(&mut src, limits)?;
+                // 将已经处理过的数据移除。
+                buf.advance(len);
+                Ok(Some(frame))
+            }
+            // 没有足够的数据来解析，这是预期之中的情况，不是错误。
+            Err(Error::Incomplete) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 按照 RESP3 将`Frame`编码并追加到`buf`的末尾，与`decode()`互为逆操作。
+    pub fn encode(&self, buf: &mut BytesMut) {
+        self.encode_as(Protocol::Resp3, buf);
+    }
+
+    /// 按照`protocol`将`Frame`编码并追加到`buf`的末尾，
+    /// RESP3 的类型在 RESP2 中编码为等价的类型，见`Protocol`。
+    ///
+    /// 使用一个栈保存还没有编码的`Frame`，聚合类型编码头部后将元素逆序压入栈中，
+    /// 所以任意深度嵌套的`Frame`都不会导致栈溢出。
+    pub fn encode_as(&self, protocol: Protocol, buf: &mut BytesMut) {
+        let resp3 = protocol == Protocol::Resp3;
+        let mut stack = vec![self];
+        while let Some(frame) = stack.pop() {
+            match frame {
+                Frame::Simple(val) => put_line(buf, b'+', val.as_bytes()),
+                // RESP3 中包含换行的错误信息使用块错误，格式与`Bulk`相同。
+                // RESP2 的错误信息只能有一行，换行被替换为空格。
+                Frame::Error(val) if val.contains(['\r', '\n']) => {
+                    if resp3 {
+                        put_blob(buf, b'!', val.as_bytes());
+                    } else {
+                        put_line(buf, b'-', val.replace(['\r', '\n'], " ").as_bytes());
+                    }
+                }
+                Frame::Error(val) => put_line(buf, b'-', val.as_bytes()),
+                Frame::Integer(val) => put_header(buf, b':', *val),
+                Frame::Null if resp3 => buf.put_slice(b"_\r\n"),
+                Frame::Null => buf.put_slice(b"$-1\r\n"),
+                Frame::Bulk(val) => put_blob(buf, b'$', val),
+                Frame::Double(val) if resp3 => put_line(buf, b',', format_double(*val).as_bytes()),
+                Frame::Double(val) => put_blob(buf, b'$', format_double(*val).as_bytes()),
+                Frame::Boolean(val) if resp3 => {
+                    buf.put_slice(if *val { b"#t\r\n" } else { b"#f\r\n" })
+                }
+                Frame::Boolean(val) => put_header(buf, b':', *val as i64),
+                Frame::BigNumber(val) if resp3 => put_line(buf, b'(', val.as_bytes()),
+                Frame::BigNumber(val) => put_blob(buf, b'$', val.as_bytes()),
+                // 长度包括三个字符的格式和一个冒号。
+                Frame::Verbatim(format, val) if resp3 => {
+                    put_header(buf, b'=', (val.len() + 4) as i64);
+                    buf.put_slice(format.as_bytes());
+                    buf.put_u8(b':');
+                    buf.put_slice(val);
+                    buf.put_slice(b"\r\n");
+                }
+                Frame::Verbatim(_, val) => put_blob(buf, b'$', val),
+                Frame::Array(items) | Frame::Push(items) | Frame::Set(items) => {
+                    let kind = match frame {
+                        Frame::Push(_) if resp3 => b'>',
+                        Frame::Set(_) if resp3 => b'~',
+                        _ => b'*',
+                    };
+                    put_header(buf, kind, items.len() as i64);
+                    stack.extend(items.iter().rev());
+                }
+                Frame::Map(pairs) => {
+                    if resp3 {
+                        put_header(buf, b'%', pairs.len() as i64);
+                    } else {
+                        put_header(buf, b'*', pairs.len() as i64 * 2);
+                    }
+                    for (key, value) in pairs.iter().rev() {
+                        stack.push(value);
+                        stack.push(key);
+                    }
+                }
+            }
         }
     }
 
     /// 将`Frame`转换为错误。
     pub(crate) fn to_error(&self) -> crate::Error {
-        format!("预料之外的Frame：{}", self).into()
+        crate::Error::Protocol(format!("预料之外的Frame：{}", self))
+    }
+}
+
+/// 如果`src`以完整的`Bulk`头部开始，返回头部的字节数和数据的长度，用于流式读取大型`Bulk`。
+///
+/// 不是`Bulk`、是 RESP2 的空值，或者头部还不完整时返回`Ok(None)`。
+pub(crate) fn bulk_header(
+    src: &[u8],
+    limits: &FrameLimits,
+) -> Result<Option<(usize, usize)>, Error> {
+    let mut src = Cursor::new(src);
+    if !matches!(get_u8(&mut src), Ok(b'$')) {
+        return Ok(None);
+    }
+    match get_length(&mut src) {
+        Ok(Some(len)) => Ok(Some((src.position() as usize, limits.bulk_len(len)?))),
+        // RESP2 的空值，交给`Frame::parse()`处理。
+        Ok(None) => Ok(None),
+        Err(Error::Incomplete) => Ok(None),
+        Err(err) => Err(err),
     }
 }
 
+/// 如果`src`以完整的数组头部开始，返回头部的字节数和元素的个数，用于逐个读取命令的参数。
+///
+/// 不是数组、是 RESP2 的空值，或者头部还不完整时返回`Ok(None)`。
+pub(crate) fn array_header(
+    src: &[u8],
+    limits: &FrameLimits,
+) -> Result<Option<(usize, usize)>, Error> {
+    let mut src = Cursor::new(src);
+    if !matches!(get_u8(&mut src), Ok(b'*')) {
+        return Ok(None);
+    }
+    match get_length(&mut src) {
+        Ok(Some(len)) => Ok(Some((src.position() as usize, limits.array_len(len, 0)?))),
+        Ok(None) => Ok(None),
+        Err(Error::Incomplete) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// 将浮点数格式化为 Redis 使用的文本，`Double`在 RESP3 和 RESP2 中都使用这个格式。
+///
+/// 整数值不带小数部分，无穷大和 NaN 分别为`inf`、`-inf`和`nan`。
+pub(crate) fn format_double(value: f64) -> String {
+    if value.is_nan() {
+        "nan".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// 写入类型符、十进制数以及`\r\n`，用于`Integer`和各种长度头部。
+pub(crate) fn put_header(buf: &mut BytesMut, kind: u8, val: i64) {
+    buf.put_u8(kind);
+    // 写入`BytesMut`不会失败。
+    let _ = write!(buf, "{}", val);
+    buf.put_slice(b"\r\n");
+}
+
+/// 写入类型符和一行数据，数据中不能包含`\r\n`。
+fn put_line(buf: &mut BytesMut, kind: u8, line: &[u8]) {
+    buf.put_u8(kind);
+    buf.put_slice(line);
+    buf.put_slice(b"\r\n");
+}
+
+/// 写入类型符、长度以及数据，用于`Bulk`这类带长度的类型。
+fn put_blob(buf: &mut BytesMut, kind: u8, data: &[u8]) {
+    put_header(buf, kind, data.len() as i64);
+    buf.put_slice(data);
+    buf.put_slice(b"\r\n");
+}
+
 /// 获取`src`的第一个字节，这会移动光标。
 ///
 /// # Errors
@@ -225,7 +654,7 @@ fn skip(src: &mut Cursor<&[u8]>, n: usize) -> Result<(), Error> {
 fn get_line<'a>(src: &mut Cursor<&'a [u8]>) -> Result<&'a [u8], Error> {
     let start = src.position() as usize;
     // `get_ref()`获取 &&[u8]。
-    let end = src.get_ref().len() - 1;
+    let end = src.get_ref().len().saturating_sub(1);
 
     // 注意循环范围。
     for i in start..end {
@@ -257,7 +686,27 @@ fn get_decimal(src: &mut Cursor<&[u8]>) -> Result<u64, Error> {
     Ok(decimal)
 }
 
-// 为了能将`frame::Error`转化为`Box<dyn std::error::Error + Send + Sync>`，必须实现。
+/// 与`get_decimal()`相同，但是 RESP2 中表示空值的`-1`返回`None`。
+fn get_length(src: &mut Cursor<&[u8]>) -> Result<Option<u64>, Error> {
+    if src.chunk().starts_with(b"-1\r\n") {
+        skip(src, 4)?;
+        return Ok(None);
+    }
+    get_decimal(src).map(Some)
+}
+
+/// 获取一行，然后解析为有符号的十进制数，用于解析`Integer`。
+///
+/// # Errors
+/// 如果数据不完整，或者数据为非 UTF-8 字符，或者无法解析为`i64`，则返回`Err`。
+fn get_signed_decimal(src: &mut Cursor<&[u8]>) -> Result<i64, Error> {
+    let line = get_line(src)?;
+    std::str::from_utf8(line)
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .ok_or_else(|| "不合法的帧格式".into())
+}
+
 impl std::error::Error for Error {}
 
 // 为了`impl std::error::Error for Error`，必须实现。
@@ -273,7 +722,7 @@ impl fmt::Display for Error {
 // 为了能使用`into()`将`String`转换为`frame::Error`。
 impl From<String> for Error {
     fn from(value: String) -> Self {
-        Error::Other(value.into())
+        Error::Other(value)
     }
 }
 
@@ -304,7 +753,7 @@ impl PartialEq<&str> for Frame {
     fn eq(&self, other: &&str) -> bool {
         match self {
             Frame::Simple(s) => s.eq(other),
-            Frame::Bulk(s) => s.eq(other),
+            Frame::Bulk(s) | Frame::Verbatim(_, s) => s.eq(other),
             _ => false,
         }
     }
@@ -323,7 +772,23 @@ impl fmt::Display for Frame {
                 Err(_) => write!(fmt, "{:?}", msg),
             },
             Frame::Null => "(nil)".fmt(fmt),
-            Frame::Array(parts) => {
+            Frame::Double(value) => format_double(*value).fmt(fmt),
+            Frame::Boolean(value) => value.fmt(fmt),
+            Frame::BigNumber(value) => value.fmt(fmt),
+            Frame::Verbatim(_, data) => match str::from_utf8(data) {
+                Ok(string) => string.fmt(fmt),
+                Err(_) => write!(fmt, "{:?}", data),
+            },
+            Frame::Map(pairs) => {
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        write!(fmt, " ")?;
+                    }
+                    write!(fmt, "{}: {}", key, value)?;
+                }
+                Ok(())
+            }
+            Frame::Array(parts) | Frame::Push(parts) | Frame::Set(parts) => {
                 for (i, part) in parts.iter().enumerate() {
                     if i > 0 {
                         // 使用空格作为分隔符。
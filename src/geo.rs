@@ -0,0 +1,202 @@
+use bytes::Bytes;
+
+/// 经度的范围。
+const LON_MIN: f64 = -180.0;
+const LON_MAX: f64 = 180.0;
+
+/// 纬度的范围，与 Redis 一样，使用 Web 墨卡托投影能够表示的范围。
+const LAT_MIN: f64 = -85.05112878;
+const LAT_MAX: f64 = 85.05112878;
+
+/// 地球半径，单位为米，与 Redis 使用的值相同。
+const EARTH_RADIUS: f64 = 6372797.560856;
+
+/// 经度和纬度各自编码的位数，交错后得到 52 位的 geohash，可以被`f64`精确表示。
+const STEP: u32 = 26;
+
+/// 搜索的中心。
+#[derive(Debug, Clone)]
+pub(crate) enum Origin {
+    /// 使用已有成员的坐标。
+    Member(Bytes),
+    /// 使用指定的经度和纬度。
+    LonLat(f64, f64),
+}
+
+/// 搜索到的成员。
+#[derive(Debug, Clone)]
+pub(crate) struct GeoMatch {
+    pub(crate) member: Bytes,
+    // 到中心的距离，单位为米。
+    pub(crate) dist: f64,
+    pub(crate) hash: u64,
+}
+
+/// 搜索的范围，长度的单位为米。
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Shape {
+    /// 以中心为圆心、`radius`为半径的圆。
+    Radius(f64),
+    /// 以中心为中心、宽`width`高`height`的矩形，边与经线和纬线平行。
+    Box { width: f64, height: f64 },
+}
+
+impl Shape {
+    /// 判断`point`是否在以`center`为中心的范围内，坐标为`(经度, 纬度)`。
+    ///
+    /// # Output
+    /// 如果在范围内，返回到中心的距离，否则返回`None`。
+    pub(crate) fn contains(&self, center: (f64, f64), point: (f64, f64)) -> Option<f64> {
+        let dist = distance(center, point);
+        match *self {
+            Shape::Radius(radius) => (dist <= radius).then_some(dist),
+            Shape::Box { width, height } => {
+                let lat_dist = EARTH_RADIUS * (point.1.to_radians() - center.1.to_radians()).abs();
+                if lat_dist > height / 2.0 {
+                    return None;
+                }
+                // 沿着点所在的纬线计算经度方向的距离。
+                let lon_dist = distance((center.0, point.1), point);
+                (lon_dist <= width / 2.0).then_some(dist)
+            }
+        }
+    }
+
+    /// 能够覆盖整个范围的圆的半径。
+    fn radius(&self) -> f64 {
+        match *self {
+            Shape::Radius(radius) => radius,
+            Shape::Box { width, height } => (width / 2.0).hypot(height / 2.0),
+        }
+    }
+}
+
+/// 判断坐标是否在可以被编码的范围内。
+pub(crate) fn is_valid(lon: f64, lat: f64) -> bool {
+    (LON_MIN..=LON_MAX).contains(&lon) && (LAT_MIN..=LAT_MAX).contains(&lat)
+}
+
+/// 将坐标编码为 52 位的 geohash，作为有序集合中的分数。
+///
+/// 需要保证坐标是有效的，见`is_valid()`。
+pub(crate) fn encode(lon: f64, lat: f64) -> u64 {
+    let lon = cell_index(lon, LON_MIN, LON_MAX);
+    let lat = cell_index(lat, LAT_MIN, LAT_MAX);
+    interleave(lon, lat)
+}
+
+/// 将 geohash 解码为坐标，返回所在格子的中心。
+pub(crate) fn decode(hash: u64) -> (f64, f64) {
+    let (lon, lat) = deinterleave(hash);
+    let cells = (1u64 << STEP) as f64;
+    let lon = LON_MIN + (lon as f64 + 0.5) * (LON_MAX - LON_MIN) / cells;
+    let lat = LAT_MIN + (lat as f64 + 0.5) * (LAT_MAX - LAT_MIN) / cells;
+    (lon.clamp(LON_MIN, LON_MAX), lat.clamp(LAT_MIN, LAT_MAX))
+}
+
+/// 使用半正矢公式计算两个坐标之间的距离，单位为米。
+pub(crate) fn distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lon1, lat1) = (from.0.to_radians(), from.1.to_radians());
+    let (lon2, lat2) = (to.0.to_radians(), to.1.to_radians());
+    let u = ((lat2 - lat1) / 2.0).sin();
+    let v = ((lon2 - lon1) / 2.0).sin();
+    2.0 * EARTH_RADIUS * (u * u + lat1.cos() * lat2.cos() * v * v).sqrt().asin()
+}
+
+/// 计算需要扫描的 geohash 区间，每一项为`[start, end)`，范围内的点一定落在这些区间中。
+///
+/// 选择尽可能精细的格子，使得中心所在的格子和周围的 8 个格子能够覆盖整个范围，
+/// 然后将这 9 个格子转换为分数区间，相同的区间只会出现一次。
+pub(crate) fn search_ranges(center: (f64, f64), shape: &Shape) -> Vec<(u64, u64)> {
+    let (lon, lat) = center;
+    let radius = shape.radius();
+
+    // 范围在纬度方向上的跨度，以及在经度方向上的半宽。
+    // 离赤道越远，同样的距离对应的经度差越大，所以使用范围内离赤道最远的纬度计算。
+    let dlat = (radius / EARTH_RADIUS).to_degrees();
+    let lat_lo = (lat - dlat).max(LAT_MIN);
+    let lat_hi = (lat + dlat).min(LAT_MAX);
+    let far_lat = (lat.abs() + dlat).min(90.0).to_radians();
+    let dlon = (radius / (EARTH_RADIUS * far_lat.cos())).to_degrees();
+
+    let mut step = STEP;
+    while step > 1 {
+        let lon_width = (LON_MAX - LON_MIN) / (1u64 << step) as f64;
+        let lat_height = (LAT_MAX - LAT_MIN) / (1u64 << step) as f64;
+        let lon_min =
+            LON_MIN + (cell_index(lon, LON_MIN, LON_MAX) >> (STEP - step)) as f64 * lon_width;
+        let lat_min =
+            LAT_MIN + (cell_index(lat, LAT_MIN, LAT_MAX) >> (STEP - step)) as f64 * lat_height;
+        // 中心格子加上两侧各一个格子。
+        let lon_covered =
+            lon - dlon >= lon_min - lon_width && lon + dlon <= lon_min + 2.0 * lon_width;
+        let lat_covered = lat_lo >= lat_min - lat_height && lat_hi <= lat_min + 2.0 * lat_height;
+        if lon_covered && lat_covered {
+            break;
+        }
+        step -= 1;
+    }
+
+    let cells = 1i64 << step;
+    let lon_cell = (cell_index(lon, LON_MIN, LON_MAX) >> (STEP - step)) as i64;
+    let lat_cell = (cell_index(lat, LAT_MIN, LAT_MAX) >> (STEP - step)) as i64;
+    let shift = 2 * (STEP - step);
+
+    let mut ranges = vec![];
+    for dy in -1..=1 {
+        let lat_cell = lat_cell + dy;
+        // 纬度方向上不会绕回。
+        if !(0..cells).contains(&lat_cell) {
+            continue;
+        }
+        for dx in -1..=1 {
+            // 经度方向上跨过 180 度经线后绕回。
+            let lon_cell = (lon_cell + dx).rem_euclid(cells);
+            let hash = interleave(lon_cell as u32, lat_cell as u32);
+            ranges.push((hash << shift, (hash + 1) << shift));
+        }
+    }
+    ranges.sort_unstable();
+    ranges.dedup();
+    ranges
+}
+
+/// 计算坐标在`STEP`精度下所在格子的下标。
+fn cell_index(value: f64, min: f64, max: f64) -> u32 {
+    let cells = (1u64 << STEP) as f64;
+    let index = ((value - min) / (max - min) * cells) as u64;
+    // 坐标等于上界时落在最后一个格子里。
+    index.min((1 << STEP) - 1) as u32
+}
+
+/// 将经度和纬度的下标按位交错，经度占奇数位，纬度占偶数位。
+fn interleave(lon: u32, lat: u32) -> u64 {
+    spread(lon) << 1 | spread(lat)
+}
+
+/// `interleave()`的逆运算，返回经度和纬度的下标。
+fn deinterleave(hash: u64) -> (u32, u32) {
+    (squash(hash >> 1), squash(hash))
+}
+
+/// 将`value`的每一位移动到两倍的位置上，即在相邻的两位之间插入一个`0`。
+fn spread(value: u32) -> u64 {
+    let mut x = value as u64;
+    x = (x | x << 16) & 0x0000_FFFF_0000_FFFF;
+    x = (x | x << 8) & 0x00FF_00FF_00FF_00FF;
+    x = (x | x << 4) & 0x0F0F_0F0F_0F0F_0F0F;
+    x = (x | x << 2) & 0x3333_3333_3333_3333;
+    x = (x | x << 1) & 0x5555_5555_5555_5555;
+    x
+}
+
+/// `spread()`的逆运算，取出偶数位。
+fn squash(value: u64) -> u32 {
+    let mut x = value & 0x5555_5555_5555_5555;
+    x = (x | x >> 1) & 0x3333_3333_3333_3333;
+    x = (x | x >> 2) & 0x0F0F_0F0F_0F0F_0F0F;
+    x = (x | x >> 4) & 0x00FF_00FF_00FF_00FF;
+    x = (x | x >> 8) & 0x0000_FFFF_0000_FFFF;
+    x = (x | x >> 16) & 0x0000_0000_FFFF_FFFF;
+    x as u32
+}
@@ -0,0 +1,101 @@
+/// 判断`text`是否匹配 glob 风格的`pattern`，规则与 Redis 的`stringmatchlen`一致。
+///
+/// 支持以下语法：
+/// - `*`：匹配任意长度（包括零）的字符
+/// - `?`：匹配任意单个字符
+/// - `[abc]`、`[a-z]`、`[^abc]`：匹配（或不匹配）集合中的单个字符
+/// - `\x`：转义，匹配字符`x`本身
+pub(crate) fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // 最近一次遇到`*`时的位置，用于回溯。
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                b'*' => {
+                    // 先假设`*`匹配零个字符，失败时再回溯。
+                    star = Some((p, t));
+                    p += 1;
+                    continue;
+                }
+                b'?' => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                b'[' => {
+                    if let Some((matched, next)) = match_class(pattern, p, text[t]) {
+                        if matched {
+                            p = next;
+                            t += 1;
+                            continue;
+                        }
+                    }
+                }
+                b'\\' if p + 1 < pattern.len() => {
+                    if pattern[p + 1] == text[t] {
+                        p += 2;
+                        t += 1;
+                        continue;
+                    }
+                }
+                c => {
+                    if c == text[t] {
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        // 当前字符不匹配，回溯到上一个`*`，让它多匹配一个字符。
+        match star {
+            Some((sp, st)) => {
+                p = sp + 1;
+                t = st + 1;
+                star = Some((sp, st + 1));
+            }
+            None => return false,
+        }
+    }
+
+    // 文本已经匹配完了，剩下的模式只能是`*`。
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// 匹配`[...]`字符集合，`start`指向`[`。
+///
+/// 返回是否匹配以及集合之后的位置。如果集合没有闭合，返回`None`。
+fn match_class(pattern: &[u8], start: usize, c: u8) -> Option<(bool, usize)> {
+    let mut i = start + 1;
+    let negate = pattern.get(i) == Some(&b'^');
+    if negate {
+        i += 1;
+    }
+
+    let mut matched = false;
+    while i < pattern.len() && pattern[i] != b']' {
+        if pattern[i] == b'\\' && i + 1 < pattern.len() {
+            matched |= pattern[i + 1] == c;
+            i += 2;
+        } else if i + 2 < pattern.len() && pattern[i + 1] == b'-' && pattern[i + 2] != b']' {
+            let (lo, hi) = if pattern[i] <= pattern[i + 2] {
+                (pattern[i], pattern[i + 2])
+            } else {
+                (pattern[i + 2], pattern[i])
+            };
+            matched |= lo <= c && c <= hi;
+            i += 3;
+        } else {
+            matched |= pattern[i] == c;
+            i += 1;
+        }
+    }
+
+    if i >= pattern.len() {
+        return None;
+    }
+    Some((matched != negate, i + 1))
+}
@@ -0,0 +1,184 @@
+//! 命令拦截器。
+//!
+//! 拦截器可以在命令执行前后插入自定义的逻辑，例如认证、配额、统计以及参数校验，
+//! 不需要修改命令的分发逻辑。通过`Server::interceptor()`注册。
+
+use crate::{acl::DEFAULT_USER, Command};
+use std::{fmt, net::SocketAddr, sync::Arc, time::Duration};
+
+/// 连接的上下文信息，会传递给拦截器。
+#[derive(Debug, Clone)]
+pub struct ConnContext {
+    // 连接编号，由服务器按照连接建立的顺序分配。
+    id: u64,
+
+    // 客户端的地址，不经过 TCP 建立的连接没有地址。
+    peer_addr: Option<SocketAddr>,
+
+    // 是否通过`Client Tracking`开启了客户端缓存追踪。
+    tracking: bool,
+
+    // 连接执行的最后一条写命令的复制偏移量，`Wait`等待副本确认这个偏移量。
+    repl_offset: u64,
+
+    // 集群模式下，上一条命令是否是`Asking`。
+    asking: bool,
+
+    // 连接使用的 ACL 用户，没有认证时为默认用户。
+    user: String,
+
+    // 是否通过`Auth`认证过。
+    authenticated: bool,
+}
+
+/// 拦截器在命令执行前做出的决定。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// 继续执行命令。
+    Continue,
+    /// 拒绝执行命令，向客户端返回错误信息，连接不会被关闭。
+    Reject(String),
+}
+
+/// 命令拦截器。
+///
+/// 对于每条命令，拦截器按照注册的顺序依次调用`before()`，
+/// 一旦有拦截器返回`Decision::Reject`，后续的拦截器不会再被调用，命令也不会执行。
+///
+/// 签名为`Fn(&Command, &ConnContext) -> Decision`的闭包也实现了此特征。
+pub trait Interceptor: Send + Sync + 'static {
+    /// 在命令执行前调用。
+    fn before(&self, cmd: &Command, ctx: &ConnContext) -> Decision {
+        let _ = (cmd, ctx);
+        Decision::Continue
+    }
+
+    /// 在命令成功执行后调用，`duration`为命令的执行时长。
+    fn after(&self, cmd: &str, ctx: &ConnContext, duration: Duration) {
+        let _ = (cmd, ctx, duration);
+    }
+}
+
+impl<F> Interceptor for F
+where
+    F: Fn(&Command, &ConnContext) -> Decision + Send + Sync + 'static,
+{
+    fn before(&self, cmd: &Command, ctx: &ConnContext) -> Decision {
+        self(cmd, ctx)
+    }
+}
+
+/// 已注册的拦截器，由所有`Handler`共享。
+#[derive(Clone, Default)]
+pub(crate) struct Chain {
+    interceptors: Arc<Vec<Box<dyn Interceptor>>>,
+}
+
+impl ConnContext {
+    pub(crate) fn new(id: u64, peer_addr: Option<SocketAddr>) -> ConnContext {
+        ConnContext {
+            id,
+            peer_addr,
+            tracking: false,
+            repl_offset: 0,
+            asking: false,
+            user: DEFAULT_USER.to_string(),
+            authenticated: false,
+        }
+    }
+
+    /// 获取连接编号。
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// 获取客户端的地址。
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// 判断连接是否开启了客户端缓存追踪。
+    pub fn is_tracking(&self) -> bool {
+        self.tracking
+    }
+
+    pub(crate) fn set_tracking(&mut self, on: bool) {
+        self.tracking = on;
+    }
+
+    pub(crate) fn repl_offset(&self) -> u64 {
+        self.repl_offset
+    }
+
+    pub(crate) fn set_repl_offset(&mut self, offset: u64) {
+        self.repl_offset = offset;
+    }
+
+    /// 获取连接使用的 ACL 用户名。
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// 判断连接是否通过`Auth`认证过。没有认证的连接使用默认用户。
+    pub fn is_authenticated(&self) -> bool {
+        self.authenticated
+    }
+
+    /// 认证为`user`。
+    pub(crate) fn set_user(&mut self, user: &str) {
+        self.user = user.to_string();
+        self.authenticated = true;
+    }
+
+    /// 取消认证，恢复为默认用户。
+    pub(crate) fn reset_user(&mut self) {
+        self.user = DEFAULT_USER.to_string();
+        self.authenticated = false;
+    }
+
+    pub(crate) fn set_asking(&mut self) {
+        self.asking = true;
+    }
+
+    /// 获取并清除`Asking`标记，`Asking`只对下一条命令有效。
+    pub(crate) fn take_asking(&mut self) -> bool {
+        std::mem::take(&mut self.asking)
+    }
+}
+
+impl Chain {
+    /// 注册一个拦截器。
+    ///
+    /// # Panics
+    /// 如果`Chain`已经被共享，会 panic，因此只能在服务器启动前调用。
+    pub(crate) fn push(&mut self, interceptor: impl Interceptor) {
+        Arc::get_mut(&mut self.interceptors)
+            .expect("服务器启动后不能再注册拦截器")
+            .push(Box::new(interceptor));
+    }
+
+    /// 依次调用所有拦截器的`before()`，返回第一个`Decision::Reject`。
+    pub(crate) fn before(&self, cmd: &Command, ctx: &ConnContext) -> Decision {
+        for interceptor in self.interceptors.iter() {
+            if let Decision::Reject(msg) = interceptor.before(cmd, ctx) {
+                return Decision::Reject(msg);
+            }
+        }
+        Decision::Continue
+    }
+
+    /// 依次调用所有拦截器的`after()`。
+    pub(crate) fn after(&self, cmd: &str, ctx: &ConnContext, duration: Duration) {
+        for interceptor in self.interceptors.iter() {
+            interceptor.after(cmd, ctx, duration);
+        }
+    }
+}
+
+impl fmt::Debug for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Chain")
+            .field("len", &self.interceptors.len())
+            .finish()
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// 每类事件最多保存的采样数。
+const MAX_SAMPLES: usize = 160;
+
+/// 命令执行的事件，带有`fast`标志的命令记录为`FAST_COMMAND`。
+pub(crate) const COMMAND: &str = "command";
+pub(crate) const FAST_COMMAND: &str = "fast-command";
+
+/// 后台任务清除一次过期 key 的事件。
+pub(crate) const EXPIRE_CYCLE: &str = "expire-cycle";
+
+/// 延迟监控。
+///
+/// 耗时达到阈值的事件按照类别记录下来，同一类别在同一秒内的多次事件只保留耗时最长的一次。
+/// 阈值为`0`时不记录，与 Redis 一样默认关闭。
+#[derive(Debug)]
+pub(crate) struct LatencyMonitor {
+    events: Mutex<BTreeMap<&'static str, EventHistory>>,
+
+    // 阈值，单位是毫秒。每个事件都要读取，所以使用原子变量。
+    threshold_ms: AtomicU64,
+}
+
+/// 一类事件的采样。
+#[derive(Debug, Default)]
+struct EventHistory {
+    // 旧的采样在前面。
+    samples: VecDeque<LatencySample>,
+    // 记录以来耗时最长的一次，单位是毫秒。
+    max_ms: u64,
+}
+
+/// 一次事件的采样。
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LatencySample {
+    // 记录时的 UNIX 时间戳，单位是秒。
+    pub(crate) timestamp: u64,
+    // 事件的耗时，单位是毫秒。
+    pub(crate) latency_ms: u64,
+}
+
+/// 一类事件最近一次的采样，用于`Latency Latest`。
+#[derive(Debug, Clone)]
+pub(crate) struct LatestEvent {
+    pub(crate) event: &'static str,
+    pub(crate) sample: LatencySample,
+    pub(crate) max_ms: u64,
+}
+
+impl LatencyMonitor {
+    pub(crate) fn new() -> LatencyMonitor {
+        LatencyMonitor {
+            events: Mutex::new(BTreeMap::new()),
+            threshold_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// 获取阈值，单位是毫秒。
+    pub(crate) fn threshold_ms(&self) -> u64 {
+        self.threshold_ms.load(Ordering::Relaxed)
+    }
+
+    /// 设置阈值，单位是毫秒，`0`表示不记录。
+    pub(crate) fn set_threshold_ms(&self, threshold: u64) {
+        self.threshold_ms.store(threshold, Ordering::Relaxed);
+    }
+
+    /// 如果耗时达到了阈值，记录一次事件。
+    pub(crate) fn record(&self, event: &'static str, duration: Duration) {
+        let threshold = self.threshold_ms();
+        let latency_ms = duration.as_millis() as u64;
+        if threshold == 0 || latency_ms < threshold {
+            return;
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut events = self.events.lock().unwrap();
+        let history = events.entry(event).or_default();
+        history.max_ms = history.max_ms.max(latency_ms);
+        match history.samples.back_mut() {
+            Some(last) if last.timestamp == timestamp => {
+                last.latency_ms = last.latency_ms.max(latency_ms);
+            }
+            _ => {
+                if history.samples.len() == MAX_SAMPLES {
+                    history.samples.pop_front();
+                }
+                history.samples.push_back(LatencySample {
+                    timestamp,
+                    latency_ms,
+                });
+            }
+        }
+    }
+
+    /// 获取每类事件最近一次的采样。
+    pub(crate) fn latest(&self) -> Vec<LatestEvent> {
+        let events = self.events.lock().unwrap();
+        events
+            .iter()
+            .filter_map(|(event, history)| {
+                Some(LatestEvent {
+                    event,
+                    sample: *history.samples.back()?,
+                    max_ms: history.max_ms,
+                })
+            })
+            .collect()
+    }
+
+    /// 获取一类事件的所有采样，从旧到新排列。
+    pub(crate) fn history(&self, event: &str) -> Vec<LatencySample> {
+        let events = self.events.lock().unwrap();
+        events
+            .get(event)
+            .map(|history| history.samples.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// 清除指定类别的事件，`events`为空时清除所有事件，返回清除的类别数。
+    pub(crate) fn reset(&self, events: &[String]) -> usize {
+        let mut all = self.events.lock().unwrap();
+        if events.is_empty() {
+            let count = all.len();
+            all.clear();
+            return count;
+        }
+        events
+            .iter()
+            .filter(|event| all.remove(event.as_str()).is_some())
+            .count()
+    }
+}
@@ -4,12 +4,28 @@ mod shutdown;
 use shutdown::Shutdown;
 
 mod connection;
-pub use connection::Connection;
+pub use connection::{Connection, FlushPolicy, StreamedFrame, Transport, DEFAULT_STREAM_THRESHOLD};
 
 pub mod server;
 
+pub mod interceptor;
+
+pub mod storage;
+
+pub mod sentinel;
+
+pub mod cluster;
+
+pub mod tls;
+
+mod acl;
+use acl::Acl;
+
+pub mod convert;
+pub use convert::{FromValue, ToValue};
+
 pub mod frame;
-pub use frame::Frame;
+pub use frame::{Frame, FrameLimits, Protocol};
 
 pub mod cmd;
 pub use cmd::Command;
@@ -17,21 +33,60 @@ pub use cmd::Command;
 mod db;
 use db::Db;
 use db::DbDropGuard;
+use db::Library;
+use db::SetCondition;
+use db::SetOp;
+use db::SetOptions;
 
 mod parse;
 use parse::{Parse, ParseError};
 
+mod slowlog;
+
+mod config;
+
+mod clients;
+
+mod latency;
+use slowlog::SlowLog;
+
+mod monitor;
+
+mod persistence;
+use persistence::Persistence;
+
+mod replication;
+use replication::Replication;
+
+mod glob;
+
+mod snapshot;
+
+mod value;
+use value::Value;
+
+mod zset;
+
+mod geo;
+
+#[cfg(feature = "scripting")]
+mod script;
+
+mod stream;
+
+mod error;
+pub use error::Error;
+
+#[cfg(feature = "test-util")]
+pub mod test_util;
+
 /// 默认端口。
 pub const DEFAULT_PORT: u16 = 6379;
 
-/// 自定义的 Error，适配所有错误。
-///
-/// 当编写一个真实的应用的时候，我们可能会考虑一个专门用于处理错误的 crate
-/// 或者将错误类型定义为原因的枚举。我们这里使用`std::error::Error`特征对象
-/// 纯粹是为了方便，因为所有错误都能转换为它。
-pub type Error = Box<dyn std::error::Error + Send + Sync>;
+/// 客户端缓存的失效信息发送到的信道，信息的内容为被修改的 key。见`Client::tracking()`。
+pub const INVALIDATE_CHANNEL: &str = "__redis__:invalidate";
 
 /// 自定义的 Result
 ///
-/// 与我们自定义的 Error 一样，纯粹是为了方便。
+/// 错误类型为`Error`，纯粹是为了方便。
 pub type Result<T> = std::result::Result<T, Error>;
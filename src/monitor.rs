@@ -0,0 +1,92 @@
+use std::{
+    fmt::Write,
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use tokio::sync::broadcast;
+
+use crate::Frame;
+
+/// 广播通道的容量，落后太多的监视器会丢失一部分命令。
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// 执行了`Monitor`的连接，服务器执行的每条命令都会被格式化为一行发送给它们。
+#[derive(Debug)]
+pub(crate) struct Monitors {
+    tx: broadcast::Sender<String>,
+}
+
+impl Monitors {
+    pub(crate) fn new() -> Monitors {
+        let (tx, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Monitors { tx }
+    }
+
+    /// 是否有连接正在监视，没有时不需要格式化命令。
+    pub(crate) fn is_active(&self) -> bool {
+        self.tx.receiver_count() > 0
+    }
+
+    /// 开始监视，返回接收命令的一端。
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.tx.subscribe()
+    }
+
+    /// 将一条命令发送给所有监视器。
+    ///
+    /// 格式与 Redis 相同：`<时间戳> [0 <客户端地址>] "命令" "参数" ...`，
+    /// 参数中的特殊字符会被转义，`Auth`和`Hello`的参数会被隐藏。
+    pub(crate) fn feed(&self, peer_addr: Option<SocketAddr>, frame: &Frame) {
+        let Frame::Array(items) = frame else {
+            return;
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let addr = match peer_addr {
+            Some(addr) => addr.to_string(),
+            None => "unix".to_string(),
+        };
+        let mut line = format!("{}.{:06} [0 {}]", now.as_secs(), now.subsec_micros(), addr);
+        let mut redacted = false;
+        for (i, item) in items.iter().enumerate() {
+            let arg = match item {
+                Frame::Bulk(arg) => arg.clone(),
+                Frame::Simple(arg) => Bytes::from(arg.clone()),
+                Frame::Integer(arg) => Bytes::from(arg.to_string()),
+                _ => continue,
+            };
+            if i == 0 {
+                redacted = arg.eq_ignore_ascii_case(b"auth") || arg.eq_ignore_ascii_case(b"hello");
+            } else if redacted {
+                line.push_str(" \"(redacted)\"");
+                continue;
+            }
+            line.push(' ');
+            push_quoted(&mut line, &arg);
+        }
+        // 没有监视器时发送失败，直接忽略。
+        let _ = self.tx.send(line);
+    }
+}
+
+/// 将参数加上双引号追加到`line`，与 Redis 一样转义引号、反斜杠和不可打印的字符。
+fn push_quoted(line: &mut String, arg: &[u8]) {
+    line.push('"');
+    for &b in arg {
+        match b {
+            b'"' => line.push_str("\\\""),
+            b'\\' => line.push_str("\\\\"),
+            b'\n' => line.push_str("\\n"),
+            b'\r' => line.push_str("\\r"),
+            b'\t' => line.push_str("\\t"),
+            b if b.is_ascii_graphic() || b == b' ' => line.push(b as char),
+            b => {
+                let _ = write!(line, "\\x{:02x}", b);
+            }
+        }
+    }
+    line.push('"');
+}
@@ -13,16 +13,21 @@ pub(crate) struct Parse {
     parts: vec::IntoIter<Frame>,
 }
 
+/// 整数参数不合法时的错误信息，与 Redis 一致。
+const NOT_AN_INTEGER: &str = "value is not an integer or out of range";
+
 /// 当解析`Frame`的时候可能出现的错误。
 ///
-/// 只有`EndOfStream`这个错误是在运行时处理，
-/// 其他错误都会导致连接关闭。
+/// 命令解析失败时，错误信息会作为`-ERR`回复发送给客户端，连接不会关闭。
+/// 因此错误信息尽量与 Redis 保持一致。
 #[derive(Debug)]
 pub(crate) enum ParseError {
     /// 由于数据帧已被消耗完，无法再获取值。
     EndOfStream,
+    /// 参数不合法，作为`ERR`错误回复给客户端。
+    Invalid(String),
     /// 其他错误。
-    Other(crate::Error),
+    Other(String),
 }
 
 impl Parse {
@@ -48,6 +53,11 @@ impl Parse {
         self.parts.next().ok_or(ParseError::EndOfStream)
     }
 
+    /// 获取 Array Frame 中剩余的`Frame`的个数。
+    pub(crate) fn remaining(&self) -> usize {
+        self.parts.len()
+    }
+
     /// 获取Array Frame里的下一个`Frame`并解析为`String`。
     ///
     /// # Errors
@@ -85,20 +95,41 @@ impl Parse {
     pub(crate) fn next_int(&mut self) -> Result<u64, ParseError> {
         match self.next()? {
             // 只处理`Simple`、`Bulk`、`Integer`。
-            Frame::Integer(v) => Ok(v),
+            Frame::Integer(v) => {
+                u64::try_from(v).map_err(|_| ParseError::Invalid(NOT_AN_INTEGER.to_string()))
+            }
             Frame::Simple(s) => s
                 .parse::<u64>()
-                .map_err(|_| Into::<ParseError>::into("不合法的数字")),
+                .map_err(|_| ParseError::Invalid(NOT_AN_INTEGER.to_string())),
             Frame::Bulk(data) => {
                 let s = String::from_utf8(data.chunk().to_vec())
                     .map_err(|_| Into::<ParseError>::into("非UTF-8编码的字符串"))?;
                 s.parse::<u64>()
-                    .map_err(|_| Into::<ParseError>::into("不合法的数字"))
+                    .map_err(|_| ParseError::Invalid(NOT_AN_INTEGER.to_string()))
             }
             frame => Err(format!("预期是Simple、Bulk或Integer类型，实际为：{:?}", frame).into()),
         }
     }
 
+    /// 获取Array Frame里的下一个`Frame`并解析为`i64`。
+    ///
+    /// # Errors
+    /// 如果无法表示为`i64`，返回`Err`。
+    pub(crate) fn next_signed_int(&mut self) -> Result<i64, ParseError> {
+        match self.next()? {
+            // 只处理`Simple`、`Bulk`、`Integer`。
+            Frame::Integer(v) => Ok(v),
+            Frame::Simple(s) => s
+                .parse::<i64>()
+                .map_err(|_| ParseError::Invalid(NOT_AN_INTEGER.to_string())),
+            Frame::Bulk(data) => str::from_utf8(&data[..])
+                .map_err(|_| Into::<ParseError>::into("非UTF-8编码的字符串"))?
+                .parse::<i64>()
+                .map_err(|_| ParseError::Invalid(NOT_AN_INTEGER.to_string())),
+            frame => Err(format!("预期是Simple、Bulk或Integer类型，实际为：{:?}", frame).into()),
+        }
+    }
+
     /// 确保`Array`中已经没有更多元素了。
     ///
     /// # Errors
@@ -107,14 +138,15 @@ impl Parse {
         if self.parts.next().is_none() {
             Ok(())
         } else {
-            Err("预期Array已经没有更多元素了，但实际相反".into())
+            // 多余的参数，Redis 将其视为语法错误。
+            Err(ParseError::Invalid("syntax error".to_string()))
         }
     }
 }
 
 impl From<String> for ParseError {
     fn from(value: String) -> ParseError {
-        ParseError::Other(value.into())
+        ParseError::Other(value)
     }
 }
 
@@ -128,7 +160,7 @@ impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ParseError::EndOfStream => write!(f, "数据流过早结束"),
-            ParseError::Other(err) => write!(f, "{err}"),
+            ParseError::Invalid(err) | ParseError::Other(err) => write!(f, "{err}"),
         }
     }
 }
@@ -0,0 +1,145 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// 默认的快照文件名。
+const DEFAULT_DBFILENAME: &str = "dump.rdb";
+
+/// 快照持久化的配置和状态。
+///
+/// 快照通过`Save`、`BgSave`命令手动触发，或者在满足自动保存规则时由后台任务触发。
+#[derive(Debug)]
+pub(crate) struct Persistence {
+    // 快照文件的路径，相对路径相对于服务器的工作目录。
+    dbfilename: Mutex<PathBuf>,
+
+    // 自动保存的规则，满足任意一条时保存快照。为空表示不自动保存。
+    rules: Mutex<Vec<SaveRule>>,
+
+    // 上次保存之后执行过的写命令数。
+    dirty: AtomicU64,
+
+    // 上次成功保存的 UNIX 时间戳，单位是秒。
+    last_save: AtomicU64,
+
+    // 是否正在后台保存快照，同一时刻只允许一个保存任务。
+    saving: AtomicBool,
+}
+
+/// 自动保存的规则：距离上次保存超过`seconds`秒，并且至少有`changes`次修改。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SaveRule {
+    seconds: u64,
+    changes: u64,
+}
+
+impl Persistence {
+    pub(crate) fn new() -> Persistence {
+        Persistence {
+            dbfilename: Mutex::new(PathBuf::from(DEFAULT_DBFILENAME)),
+            rules: Mutex::new(vec![]),
+            dirty: AtomicU64::new(0),
+            last_save: AtomicU64::new(unix_secs()),
+            saving: AtomicBool::new(false),
+        }
+    }
+
+    /// 获取快照文件的路径。
+    pub(crate) fn dbfilename(&self) -> PathBuf {
+        self.dbfilename.lock().unwrap().clone()
+    }
+
+    /// 设置快照文件的路径。
+    pub(crate) fn set_dbfilename(&self, path: PathBuf) {
+        *self.dbfilename.lock().unwrap() = path;
+    }
+
+    /// 获取自动保存的规则，格式与 Redis 的`save`配置相同，例如`"900 1 300 10"`。
+    pub(crate) fn rules(&self) -> String {
+        let rules = self.rules.lock().unwrap();
+        rules
+            .iter()
+            .map(|rule| format!("{} {}", rule.seconds, rule.changes))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// 设置自动保存的规则，`rules`为空字符串时关闭自动保存。
+    ///
+    /// # Errors
+    /// 如果`rules`不是成对的非负整数，返回`Err`，原有的规则保持不变。
+    pub(crate) fn set_rules(&self, rules: &str) -> Result<(), ()> {
+        let numbers = rules
+            .split_whitespace()
+            .map(|n| n.parse::<u64>().map_err(|_| ()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if numbers.len() % 2 != 0 {
+            return Err(());
+        }
+        *self.rules.lock().unwrap() = numbers
+            .chunks(2)
+            .map(|pair| SaveRule {
+                seconds: pair[0],
+                changes: pair[1],
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// 记录执行了一条写命令。
+    pub(crate) fn incr_dirty(&self) {
+        self.dirty.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 获取上次保存之后执行过的写命令数。
+    pub(crate) fn dirty(&self) -> u64 {
+        self.dirty.load(Ordering::Relaxed)
+    }
+
+    /// 获取上次成功保存的 UNIX 时间戳，单位是秒。
+    pub(crate) fn last_save(&self) -> u64 {
+        self.last_save.load(Ordering::Relaxed)
+    }
+
+    /// 判断是否满足任意一条自动保存的规则。
+    pub(crate) fn should_save(&self) -> bool {
+        let dirty = self.dirty();
+        let elapsed = unix_secs().saturating_sub(self.last_save());
+        let rules = self.rules.lock().unwrap();
+        rules
+            .iter()
+            .any(|rule| dirty > 0 && dirty >= rule.changes && elapsed >= rule.seconds)
+    }
+
+    /// 尝试开始保存，如果已经有保存任务在执行，返回`false`。
+    ///
+    /// 返回`true`后，必须在保存结束后调用`finish_save()`。
+    pub(crate) fn start_save(&self) -> bool {
+        self.saving
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// 结束保存。`dirty`为开始保存前读取的修改数，保存成功时从计数中减去，
+    /// 保存期间的修改留到下一次保存。
+    pub(crate) fn finish_save(&self, dirty: Option<u64>) {
+        if let Some(dirty) = dirty {
+            self.dirty.fetch_sub(dirty, Ordering::Relaxed);
+            self.last_save.store(unix_secs(), Ordering::Relaxed);
+        }
+        self.saving.store(false, Ordering::Release);
+    }
+}
+
+/// 当前的 UNIX 时间戳，单位是秒。
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or(0)
+}
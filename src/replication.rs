@@ -0,0 +1,524 @@
+//! 主从复制。
+//!
+//! 副本连接主节点后发送`PSync`，主节点回复`+FULLRESYNC <replid> <offset>`，
+//! 接着发送一个包含数据库快照的`Bulk`帧（格式见`snapshot`模块），
+//! 之后持续发送主节点执行过的写命令，副本按照收到的顺序执行。
+//! `Publish`和`SPublish`同样会被转发，连接到副本的订阅者也能收到主节点上发布的信息。
+//!
+//! 副本是只读的，客户端的写命令会被拒绝。
+//!
+//! 复制偏移量是主节点发送给副本的写命令和`Publish`的个数。主节点通过`REPLCONF GETACK *`请求副本确认，
+//! 副本回复`REPLCONF ACK <offset>`，`Wait`命令据此判断写命令是否已经被副本执行。
+//!
+//! 写命令在不同的分片上并发执行，为了让副本按照相同的顺序执行，写命令在持有分片的锁时
+//! 获取一个复制序号（见`stamped()`），执行完之后按照序号的顺序发送给副本。
+//! 只有修改同一个分片的写命令之间才有先后顺序，它们的序号与修改的顺序一致。
+
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use bytes::Bytes;
+
+use rand::Rng;
+use tokio::{
+    net::TcpStream,
+    sync::{broadcast, mpsc, oneshot, Notify},
+    task::JoinHandle,
+    time,
+};
+use tracing::{info, warn};
+
+use crate::{
+    cmd::{CommandSpec, PSync},
+    interceptor::ConnContext,
+    snapshot, Command, Connection, Db, Frame, Shutdown,
+};
+
+/// 与主节点的连接断开后，重新连接前等待的时长。
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+tokio::task_local! {
+    // 正在执行的写命令最近一次获取分片的锁时分配的复制序号，见`stamped()`。
+    static STAMP: RefCell<Option<Stamp>>;
+}
+
+/// 复制的状态，作为主节点时记录副本，作为副本时记录复制任务。
+#[derive(Debug)]
+pub(crate) struct Replication {
+    // 复制 ID，副本通过它区分不同的主节点。
+    replid: String,
+
+    // 发送给副本的写命令流，复制序号也持有它，见`Stamp`。
+    stream: Arc<ReplStream>,
+
+    // 收到副本的确认时通知等待中的`Wait`命令。
+    acked: Notify,
+
+    // 作为副本时，主节点的地址以及复制任务。
+    primary: Mutex<Option<(String, JoinHandle<()>)>>,
+}
+
+/// 按照复制序号的顺序将写命令发送给副本。
+#[derive(Debug, Default)]
+struct ReplStream {
+    // 下一个分配的复制序号。
+    next_seq: AtomicU64,
+
+    // 已经发送给副本的写命令数。
+    offset: AtomicU64,
+
+    // 副本的数量，包括等待全量同步的副本。没有副本时写命令不需要获取复制序号。
+    replicas: AtomicUsize,
+
+    state: Mutex<StreamState>,
+}
+
+#[derive(Debug, Default)]
+struct StreamState {
+    // 所有副本，发送写命令时发送给每个副本。副本断开后在下一次发送时移除。
+    replicas: Vec<ReplicaLink>,
+
+    // 下一个应该发送的复制序号，之前的序号都已经处理完了。
+    next: u64,
+
+    // 已经执行完、但前面还有序号没有执行完的写命令，`None`表示命令没有需要发送的内容。
+    pending: BTreeMap<u64, Option<Frame>>,
+
+    // 正在全量同步的副本，只接收序号不小于`from`的写命令，见`Replication::add_replica()`。
+    syncing: Vec<SyncingReplica>,
+}
+
+/// 主节点与一个副本之间的连接。
+#[derive(Debug)]
+struct ReplicaLink {
+    // 发送给副本的写命令，由`PSync::apply()`写入连接。
+    tx: mpsc::UnboundedSender<Frame>,
+
+    // 副本确认已经执行的偏移量。
+    ack: Arc<AtomicU64>,
+}
+
+/// 一个正在全量同步的副本，快照之前分配的序号都处理完之后才开始接收写命令。
+#[derive(Debug)]
+struct SyncingReplica {
+    // 快照之后分配的第一个序号。
+    from: u64,
+    link: ReplicaLink,
+    // 副本开始接收写命令时的偏移量。
+    offset: oneshot::Sender<u64>,
+}
+
+/// 一个写命令的复制序号，在持有分片的锁时分配，没有副本时不分配。
+///
+/// 通过`propagate()`发送写命令，被 drop 时视为命令没有修改数据，后面的写命令不再等待它。
+#[derive(Debug)]
+pub(crate) struct Stamp {
+    seq: Option<u64>,
+    stream: Arc<ReplStream>,
+    // 是否已经通过`propagate()`处理了这个序号。
+    propagated: bool,
+}
+
+/// 执行写命令`fut`，返回它的结果以及它最后一次获取分片的锁时分配的复制序号。
+///
+/// 命令没有访问任何分片时（例如超时的阻塞式命令）返回`None`。
+pub(crate) async fn stamped<F: Future>(fut: F) -> (F::Output, Option<Stamp>) {
+    STAMP
+        .scope(RefCell::new(None), async move {
+            let output = fut.await;
+            (output, STAMP.with(|stamp| stamp.borrow_mut().take()))
+        })
+        .await
+}
+
+impl Replication {
+    pub(crate) fn new() -> Replication {
+        let replid = (0..40)
+            .map(|_| char::from_digit(rand::thread_rng().gen_range(0..16), 16).unwrap())
+            .collect();
+        Replication {
+            replid,
+            stream: Arc::default(),
+            acked: Notify::new(),
+            primary: Mutex::new(None),
+        }
+    }
+
+    /// 获取复制 ID。
+    pub(crate) fn replid(&self) -> &str {
+        &self.replid
+    }
+
+    /// 获取已经发送给副本的写命令数。
+    pub(crate) fn offset(&self) -> u64 {
+        self.stream.offset.load(Ordering::Relaxed)
+    }
+
+    /// 判断是否有副本连接。
+    pub(crate) fn has_replicas(&self) -> bool {
+        self.stream.replicas.load(Ordering::Relaxed) > 0
+    }
+
+    /// 获取副本的数量。
+    pub(crate) fn replica_count(&self) -> usize {
+        self.stream.state.lock().unwrap().replicas.len()
+    }
+
+    /// 为正在执行的写命令分配复制序号，必须在持有分片的锁时调用，见`stamped()`。
+    ///
+    /// 同一个命令之前分配的序号被 drop，命令只在最后一次获取锁时修改数据。
+    pub(crate) fn stamp(&self) {
+        let _ = STAMP.try_with(|stamp| {
+            // 没有副本时不需要排序，之后注册的副本会在快照中得到这次修改。
+            let seq = self
+                .has_replicas()
+                .then(|| self.stream.next_seq.fetch_add(1, Ordering::Relaxed));
+            *stamp.borrow_mut() = Some(Stamp {
+                seq,
+                stream: self.stream.clone(),
+                propagated: false,
+            });
+        });
+    }
+
+    /// 丢弃正在执行的写命令的复制序号，阻塞式命令开始等待之前调用，后面的写命令不需要等待它。
+    pub(crate) fn unstamp(&self) {
+        let _ = STAMP.try_with(|stamp| stamp.borrow_mut().take());
+    }
+
+    /// 注册一个全量同步的副本，必须在生成快照时持有所有分片的锁时调用，见`Db::snapshot_with()`。
+    ///
+    /// 副本只接收之后分配了复制序号的写命令，之前的写命令已经包含在快照中。
+    /// 返回副本开始接收写命令时的偏移量，也就是全量同步的偏移量，
+    /// 以及副本确认的偏移量，通过`ack()`更新。
+    pub(crate) fn add_replica(
+        &self,
+        tx: mpsc::UnboundedSender<Frame>,
+    ) -> (oneshot::Receiver<u64>, Arc<AtomicU64>) {
+        let from = self.stream.next_seq.load(Ordering::Relaxed);
+        let (offset_tx, offset_rx) = oneshot::channel();
+        let ack = Arc::new(AtomicU64::new(0));
+        let mut state = self.stream.state.lock().unwrap();
+        state.syncing.push(SyncingReplica {
+            from,
+            link: ReplicaLink {
+                tx,
+                ack: ack.clone(),
+            },
+            offset: offset_tx,
+        });
+        self.stream.replicas.fetch_add(1, Ordering::Relaxed);
+        self.stream.start_syncing(&mut state);
+        (offset_rx, ack)
+    }
+
+    /// 记录副本确认的偏移量，并唤醒等待中的`Wait`命令。
+    pub(crate) fn ack(&self, ack: &AtomicU64, offset: u64) {
+        ack.fetch_max(offset, Ordering::Relaxed);
+        self.acked.notify_waiters();
+    }
+
+    /// 统计确认的偏移量不小于`offset`的副本数。
+    pub(crate) fn acked_replicas(&self, offset: u64) -> usize {
+        let state = self.stream.state.lock().unwrap();
+        state
+            .replicas
+            .iter()
+            .filter(|replica| !replica.tx.is_closed())
+            .filter(|replica| replica.ack.load(Ordering::Relaxed) >= offset)
+            .count()
+    }
+
+    /// 等待至少`numreplicas`个副本确认了`offset`，返回确认的副本数。
+    ///
+    /// `timeout`为`None`时一直等待，超时后返回当时已经确认的副本数。
+    pub(crate) async fn wait_acked(
+        &self,
+        numreplicas: usize,
+        offset: u64,
+        timeout: Option<Duration>,
+    ) -> usize {
+        let acked = self.acked_replicas(offset);
+        if acked >= numreplicas {
+            return acked;
+        }
+
+        self.request_ack();
+        let wait = async {
+            loop {
+                // 先注册通知再检查，避免错过检查之后、等待之前收到的确认。
+                let notified = self.acked.notified();
+                tokio::pin!(notified);
+                notified.as_mut().enable();
+                if self.acked_replicas(offset) >= numreplicas {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        match timeout {
+            Some(timeout) => {
+                let _ = time::timeout(timeout, wait).await;
+            }
+            None => wait.await,
+        }
+        self.acked_replicas(offset)
+    }
+
+    /// 请求所有副本发送确认。确认请求不是写命令，不增加偏移量。
+    fn request_ack(&self) {
+        let frame = replconf_frame("getack", "*");
+        let state = self.stream.state.lock().unwrap();
+        for replica in state.replicas.iter() {
+            let _ = replica.tx.send(frame.clone());
+        }
+    }
+
+    /// 将没有复制序号的写命令或者`Publish`发送给所有副本，排在已经分配了序号的写命令之后。
+    ///
+    /// 返回值与`Stamp::propagate()`相同。
+    pub(crate) fn propagate(&self, frame: Frame) -> u64 {
+        if !self.has_replicas() {
+            return self.offset();
+        }
+        let seq = self.stream.next_seq.fetch_add(1, Ordering::Relaxed);
+        self.stream.complete(seq, Some(frame))
+    }
+
+    /// 判断是否是副本。
+    pub(crate) fn is_replica(&self) -> bool {
+        self.primary.lock().unwrap().is_some()
+    }
+
+    /// 作为副本时，获取主节点的地址，格式为`host:port`。
+    pub(crate) fn primary_addr(&self) -> Option<String> {
+        let primary = self.primary.lock().unwrap();
+        primary.as_ref().map(|(addr, _)| addr.clone())
+    }
+
+    /// 设置复制的主节点，`None`表示成为主节点。原有的复制任务会被终止。
+    pub(crate) fn set_primary(&self, primary: Option<(String, JoinHandle<()>)>) {
+        let prev = std::mem::replace(&mut *self.primary.lock().unwrap(), primary);
+        if let Some((_, task)) = prev {
+            task.abort();
+        }
+    }
+}
+
+impl ReplStream {
+    /// 序号为`seq`的写命令执行完了，按照序号的顺序发送所有前面的序号都已经处理完的写命令。
+    ///
+    /// 返回`frame`被发送之后的偏移量。前面还有写命令没有执行完时`frame`暂时不会被发送，
+    /// 返回的是一个上限：加上所有等待发送的写命令之后的偏移量。
+    fn complete(&self, seq: u64, frame: Option<Frame>) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        state.pending.insert(seq, frame);
+        loop {
+            let next = state.next;
+            let Some(frame) = state.pending.remove(&next) else {
+                break;
+            };
+            state.next += 1;
+            if let Some(frame) = frame {
+                self.send(&mut state, frame);
+            }
+            self.start_syncing(&mut state);
+        }
+        let waiting = state
+            .pending
+            .values()
+            .filter(|frame| frame.is_some())
+            .count();
+        self.offset.load(Ordering::Relaxed) + waiting as u64
+    }
+
+    /// 将写命令发送给所有副本。
+    fn send(&self, state: &mut StreamState, frame: Frame) {
+        if state.replicas.is_empty() {
+            return;
+        }
+        // 发送失败说明副本已经断开了。
+        let before = state.replicas.len();
+        state
+            .replicas
+            .retain(|replica| replica.tx.send(frame.clone()).is_ok());
+        self.replicas
+            .fetch_sub(before - state.replicas.len(), Ordering::Relaxed);
+        self.offset.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 快照之前分配的序号都处理完之后，正在全量同步的副本开始接收写命令。
+    fn start_syncing(&self, state: &mut StreamState) {
+        let next = state.next;
+        let (ready, syncing) = std::mem::take(&mut state.syncing)
+            .into_iter()
+            .partition(|replica| replica.from <= next);
+        state.syncing = syncing;
+        for replica in ready {
+            let offset = self.offset.load(Ordering::Relaxed);
+            replica.link.ack.store(offset, Ordering::Relaxed);
+            let _ = replica.offset.send(offset);
+            state.replicas.push(replica.link);
+        }
+    }
+}
+
+impl Stamp {
+    /// 将写命令发送给副本，返回值见`ReplStream::complete()`。
+    pub(crate) fn propagate(mut self, frame: Frame) -> u64 {
+        self.propagated = true;
+        match self.seq {
+            Some(seq) => self.stream.complete(seq, Some(frame)),
+            None => self.stream.offset.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for Stamp {
+    fn drop(&mut self) {
+        // 没有调用`propagate()`，命令没有需要发送的内容。
+        if let (Some(seq), false) = (self.seq, self.propagated) {
+            self.stream.complete(seq, None);
+        }
+    }
+}
+
+/// 开始复制`addr`上的主节点，原有的复制会被终止。
+pub(crate) fn replicate(db: &Db, addr: String) {
+    let task = tokio::spawn(run_replica(db.clone(), addr.clone()));
+    db.replication().set_primary(Some((addr, task)));
+}
+
+/// 复制任务，连接断开后会重新连接并重新进行全量同步。
+async fn run_replica(db: Db, addr: String) {
+    loop {
+        match sync_with_primary(&db, &addr).await {
+            Ok(()) => info!(primary = %addr, "主节点关闭了复制连接"),
+            Err(err) => warn!(primary = %addr, cause = %err, "复制失败"),
+        }
+        time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// 连接主节点，进行全量同步后持续执行主节点发送的写命令，直到连接断开。
+async fn sync_with_primary(db: &Db, addr: &str) -> crate::Result<()> {
+    let socket = TcpStream::connect(addr).await?;
+    let mut primary = Connection::new(socket);
+
+    primary
+        .write_frame(&PSync::full_sync().into_frame())
+        .await?;
+
+    // 回复的格式为`FULLRESYNC <replid> <offset>`。
+    let mut offset = match primary.read_frame().await? {
+        Some(Frame::Simple(reply)) if reply.starts_with("FULLRESYNC") => reply
+            .split_whitespace()
+            .nth(2)
+            .and_then(|offset| offset.parse::<u64>().ok())
+            .ok_or("主节点回复的偏移量不合法")?,
+        Some(frame) => return Err(frame.to_error()),
+        None => return Err(crate::Error::ConnectionClosed),
+    };
+    match primary.read_frame().await? {
+        Some(Frame::Bulk(data)) => {
+            let entries = snapshot::decode(&data)?;
+            info!(primary = %addr, keys = entries.len(), "完成全量同步");
+            db.restore(entries);
+        }
+        Some(frame) => return Err(frame.to_error()),
+        None => return Err(crate::Error::ConnectionClosed),
+    }
+
+    // 副本执行命令时不需要响应，写入`sink`中丢弃。
+    let mut sink = Connection::new(tokio::io::join(tokio::io::empty(), tokio::io::sink()));
+    let (_notify, rx) = broadcast::channel(1);
+    let mut shutdown = Shutdown::new(rx);
+    let mut ctx = ConnContext::new(0, None);
+    while let Some(frame) = primary.read_frame().await? {
+        if let Some((sub, _)) = parse_replconf(&frame) {
+            if sub.eq_ignore_ascii_case("getack") {
+                let ack = replconf_frame("ack", &offset.to_string());
+                primary.write_frame(&ack).await?;
+            }
+            continue;
+        }
+        apply_replicated(db, frame, &mut sink, &mut shutdown, &mut ctx).await?;
+        offset += 1;
+    }
+    Ok(())
+}
+
+/// 创建一个`REPLCONF <sub> <arg>`帧。
+pub(crate) fn replconf_frame(sub: &str, arg: &str) -> Frame {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from("replconf".as_bytes()));
+    frame.push_bulk(Bytes::from(sub.to_string()));
+    frame.push_bulk(Bytes::from(arg.to_string()));
+    frame
+}
+
+/// 解析`REPLCONF <sub> <arg>`帧，返回子命令和参数。不是`REPLCONF`时返回`None`。
+pub(crate) fn parse_replconf(frame: &Frame) -> Option<(&str, &str)> {
+    let Frame::Array(items) = frame else {
+        return None;
+    };
+    let [Frame::Bulk(name), Frame::Bulk(sub), Frame::Bulk(arg)] = &items[..] else {
+        return None;
+    };
+    if !name.eq_ignore_ascii_case(b"replconf") {
+        return None;
+    }
+    Some((
+        std::str::from_utf8(sub).ok()?,
+        std::str::from_utf8(arg).ok()?,
+    ))
+}
+
+/// 执行主节点发送的写命令和`Publish`，并继续发送给这个副本自己的副本。
+async fn apply_replicated(
+    db: &Db,
+    frame: Frame,
+    dst: &mut Connection,
+    shutdown: &mut Shutdown,
+    ctx: &mut ConnContext,
+) -> crate::Result<()> {
+    let cmd = Command::from_frame(frame.clone())?;
+    // 主节点转发的`Publish`只发送给这个副本的订阅者，不是写命令，不需要回复。
+    if let Command::Publish(publish) = cmd {
+        publish.deliver(db);
+        db.replication().propagate(frame);
+        return Ok(());
+    }
+    let key = cmd.get_key().map(str::to_string);
+    let write = CommandSpec::lookup(cmd.get_name()).is_some_and(|spec| spec.has_flag("write"))
+        && !matches!(cmd, Command::Invalid(_));
+
+    let applied = if cmd.is_blocking() {
+        // 主节点只会发送弹出了元素的阻塞式命令，副本上只执行一次，不等待：
+        // 副本按照相同的顺序执行写命令，第一次轮询就能完成。
+        time::timeout(Duration::ZERO, cmd.apply(db, dst, shutdown, ctx))
+            .await
+            .unwrap_or(Ok(false))?
+    } else {
+        // 与`Handler`一样持有脚本锁的读锁，见`PSync::apply()`。
+        let _guard = db.script_lock().read().await;
+        cmd.apply(db, dst, shutdown, ctx).await?
+    };
+
+    if write && applied {
+        db.persistence().incr_dirty();
+        if let Some(key) = key {
+            db.invalidate(&key);
+        }
+        db.replication().propagate(frame);
+    }
+    Ok(())
+}
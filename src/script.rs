@@ -0,0 +1,286 @@
+use std::any::TypeId;
+
+use bytes::Bytes;
+use rhai::{
+    Array, Blob, CallFnOptions, Dynamic, Engine, EvalAltResult, FnAccess, Map, NativeCallContext,
+    Position, Scope,
+};
+use tokio::{runtime::Handle, sync::broadcast};
+
+use crate::{
+    cmd::CommandSpec, config, interceptor::ConnContext, replication, Command, Connection, Db,
+    Frame, Shutdown,
+};
+
+/// `redis.command()`和`redis.pcommand()`最多能接受的参数个数，包括命令名称。
+const MAX_CALL_ARGS: usize = 32;
+
+/// 脚本中的`redis`对象，通过它执行命令。
+#[derive(Debug, Clone)]
+struct Redis {
+    db: Db,
+    ctx: ConnContext,
+    // 脚本在阻塞线程中执行，通过运行时的句柄执行异步的命令。
+    runtime: Handle,
+}
+
+/// `redis.command()`中的命令出错时抛出的错误，错误信息会原样返回给客户端。
+#[derive(Debug, Clone)]
+struct CallError(String);
+
+/// 执行脚本，返回脚本的返回值转换得到的`Frame`。
+///
+/// `function`为`None`时执行整个脚本，脚本通过`KEYS`和`ARGV`读取参数；
+/// 否则`script`为函数库的源代码，调用其中的函数`function`，参数为 key 的数组和其他参数的数组。
+///
+/// 必须在 tokio 运行时的阻塞线程中调用，例如`tokio::task::spawn_blocking()`。
+pub(crate) fn run(
+    db: &Db,
+    ctx: &ConnContext,
+    script: &str,
+    function: Option<&str>,
+    keys: Vec<String>,
+    args: Vec<Bytes>,
+) -> Frame {
+    let mut engine = Engine::new();
+    engine.register_type_with_name::<Redis>("Redis");
+    // 对应 Redis 脚本中的`redis.call()`和`redis.pcall()`，`call`在 Rhai 中是保留的方法名。
+    // Rhai 的函数没有可变参数，所以为每个参数个数注册一次。
+    for n in 1..=MAX_CALL_ARGS {
+        let mut arg_types = vec![TypeId::of::<Redis>()];
+        arg_types.extend(std::iter::repeat_n(TypeId::of::<Dynamic>(), n));
+        engine.register_raw_fn(
+            "command",
+            &arg_types,
+            |_: NativeCallContext, args: &mut [&mut Dynamic]| {
+                let redis = args[0].clone_cast::<Redis>();
+                match redis.call(&args[1..]) {
+                    Ok(frame) => Ok(to_dynamic(frame)),
+                    Err(msg) => Err(Box::new(EvalAltResult::ErrorRuntime(
+                        Dynamic::from(CallError(msg)),
+                        Position::NONE,
+                    ))),
+                }
+            },
+        );
+        engine.register_raw_fn(
+            "pcommand",
+            &arg_types,
+            |_: NativeCallContext, args: &mut [&mut Dynamic]| {
+                let redis = args[0].clone_cast::<Redis>();
+                Ok(match redis.call(&args[1..]) {
+                    Ok(frame) => to_dynamic(frame),
+                    Err(msg) => error_map(msg),
+                })
+            },
+        );
+    }
+
+    let redis = Redis {
+        db: db.clone(),
+        ctx: ctx.clone(),
+        runtime: Handle::current(),
+    };
+    let mut scope = Scope::new();
+    scope.push_constant("redis", redis);
+    let keys = keys.into_iter().map(Dynamic::from).collect::<Array>();
+    let args = args.into_iter().map(bytes_to_dynamic).collect::<Array>();
+
+    let result = match function {
+        None => {
+            scope.push_constant("KEYS", keys);
+            scope.push_constant("ARGV", args);
+            engine.eval_with_scope::<Dynamic>(&mut scope, script)
+        }
+        // 函数库的顶层语句只在加载时检查，调用函数时不执行。
+        Some(function) => engine.compile(script).map_err(Into::into).and_then(|ast| {
+            let options = CallFnOptions::new().eval_ast(false);
+            engine.call_fn_with_options(options, &mut scope, &ast, function, (keys, args))
+        }),
+    };
+    match result {
+        Ok(value) => to_frame(value),
+        Err(err) => match err.unwrap_inner() {
+            EvalAltResult::ErrorRuntime(value, _) if value.is::<CallError>() => {
+                Frame::Error(value.clone_cast::<CallError>().0)
+            }
+            err => Frame::Error(format!("ERR Error running script: {}", err)),
+        },
+    }
+}
+
+/// 编译函数库，返回可以通过`FCall`调用的函数，即所有接受两个参数的公开函数。
+///
+/// # Errors
+/// 如果编译失败，返回错误信息。
+pub(crate) fn library_functions(code: &str) -> Result<Vec<String>, String> {
+    let ast = Engine::new()
+        .compile(code)
+        .map_err(|err| format!("ERR Error compiling function: {}", err))?;
+    Ok(ast
+        .iter_functions()
+        .filter(|function| function.access == FnAccess::Public && function.params.len() == 2)
+        .map(|function| function.name.to_string())
+        .collect())
+}
+
+impl Redis {
+    /// 执行一条命令，返回命令的响应。
+    ///
+    /// # Errors
+    /// 参数不合法、命令不允许在脚本中执行，或者命令返回了错误时，返回错误信息。
+    fn call(&self, args: &[&mut Dynamic]) -> Result<Frame, String> {
+        let mut frame = Frame::array();
+        for arg in args {
+            frame.push_bulk(to_bytes(arg)?);
+        }
+        // 写命令需要发送给副本。
+        let replica_frame = frame.clone();
+        let cmd = Command::from_frame(frame).map_err(|err| format!("ERR {}", err))?;
+
+        let spec = CommandSpec::lookup(cmd.get_name());
+        if cmd.is_blocking() || spec.is_some_and(|spec| spec.has_flag("noscript")) {
+            return Err("ERR This command is not allowed from script".to_string());
+        }
+        self.db.acl().check(&self.ctx, &cmd)?;
+        if spec.is_some_and(|spec| spec.has_flag("denyoom")) && self.db.is_over_maxmemory() {
+            return Err(config::OOM_ERROR.to_string());
+        }
+        // 参数不合法的命令只返回错误信息，不需要发送给副本。
+        let invalidate =
+            spec.is_some_and(|spec| spec.has_flag("write")) && !matches!(cmd, Command::Invalid(_));
+        let key = cmd.get_key().map(str::to_string);
+
+        // 命令将响应写入`Connection`，所以通过内存中的管道读取响应。
+        let (client, server) = tokio::io::duplex(4 * 1024);
+        let mut client = Connection::new(client);
+        let mut server = Connection::new(server);
+        let (_notify, rx) = broadcast::channel(1);
+        let mut shutdown = Shutdown::new(rx);
+        let mut ctx = self.ctx.clone();
+        // 与`Handler`一样，写命令在持有分片的锁时获取复制序号，见`replication::stamped()`。
+        let (result, stamp) = self.runtime.block_on(async {
+            let apply = cmd.apply(&self.db, &mut server, &mut shutdown, &mut ctx);
+            let ((applied, stamp), response) =
+                tokio::join!(replication::stamped(apply), client.read_frame());
+            (applied.and_then(|applied| Ok((applied, response?))), stamp)
+        });
+        let (applied, response) = result.map_err(|err| format!("ERR {}", err))?;
+
+        // 执行失败的写命令不会修改数据，不需要发送给副本。
+        if invalidate && applied {
+            self.db.persistence().incr_dirty();
+            match stamp {
+                Some(stamp) => stamp.propagate(replica_frame),
+                None => self.db.replication().propagate(replica_frame),
+            };
+            if let Some(key) = key {
+                self.db.invalidate(&key);
+            }
+        }
+        match response {
+            Some(Frame::Error(msg)) => Err(msg),
+            Some(frame) => Ok(frame),
+            None => Err("ERR no response from command".to_string()),
+        }
+    }
+}
+
+/// 将`redis.command()`的参数转换为字节数组，只接受字符串和数字。
+fn to_bytes(arg: &Dynamic) -> Result<Bytes, String> {
+    if let Some(s) = arg.read_lock::<rhai::ImmutableString>() {
+        Ok(Bytes::from(s.to_string()))
+    } else if let Some(blob) = arg.read_lock::<Blob>() {
+        Ok(Bytes::from(blob.clone()))
+    } else if let Ok(n) = arg.as_int() {
+        Ok(Bytes::from(n.to_string()))
+    } else if let Ok(f) = arg.as_float() {
+        Ok(Bytes::from(f.to_string()))
+    } else {
+        Err("ERR Command arguments must be strings or integers".to_string())
+    }
+}
+
+/// 将字节数组转换为 Rhai 的值，不是 UTF-8 字符串时转换为`Blob`。
+fn bytes_to_dynamic(bytes: Bytes) -> Dynamic {
+    match String::from_utf8(bytes.to_vec()) {
+        Ok(s) => Dynamic::from(s),
+        Err(err) => Dynamic::from_blob(err.into_bytes()),
+    }
+}
+
+/// 将命令的响应转换为 Rhai 的值。
+///
+/// 与 Redis 的 Lua 脚本一样，状态回复转换为`#{ ok: 状态 }`，`Null`转换为`()`。
+fn to_dynamic(frame: Frame) -> Dynamic {
+    match frame {
+        Frame::Simple(status) => {
+            let mut map = Map::new();
+            map.insert("ok".into(), Dynamic::from(status));
+            Dynamic::from_map(map)
+        }
+        Frame::Error(msg) => error_map(msg),
+        Frame::Integer(n) => Dynamic::from_int(n),
+        Frame::Bulk(bytes) => bytes_to_dynamic(bytes),
+        Frame::Null => Dynamic::UNIT,
+        Frame::Double(f) => Dynamic::from_float(f),
+        Frame::Boolean(b) => Dynamic::from_bool(b),
+        Frame::BigNumber(n) => Dynamic::from(n),
+        Frame::Verbatim(_, bytes) => bytes_to_dynamic(bytes),
+        Frame::Array(frames) | Frame::Push(frames) | Frame::Set(frames) => {
+            Dynamic::from_array(frames.into_iter().map(to_dynamic).collect())
+        }
+        // 与 RESP2 一样，映射转换为键和值交替出现的数组。
+        Frame::Map(pairs) => Dynamic::from_array(
+            pairs
+                .into_iter()
+                .flat_map(|(key, value)| [to_dynamic(key), to_dynamic(value)])
+                .collect(),
+        ),
+    }
+}
+
+/// 错误回复在脚本中表示为`#{ err: 错误信息 }`。
+fn error_map(msg: String) -> Dynamic {
+    let mut map = Map::new();
+    map.insert("err".into(), Dynamic::from(msg));
+    Dynamic::from_map(map)
+}
+
+/// 将脚本的返回值转换为`Frame`。
+///
+/// `true`转换为`1`，`false`和`()`转换为`Null`，浮点数向零取整，
+/// `#{ ok: .. }`和`#{ err: .. }`分别转换为状态回复和错误回复。
+fn to_frame(value: Dynamic) -> Frame {
+    if value.is_unit() {
+        return Frame::Null;
+    }
+    if let Ok(b) = value.as_bool() {
+        return if b { Frame::Integer(1) } else { Frame::Null };
+    }
+    if let Ok(n) = value.as_int() {
+        return Frame::Integer(n);
+    }
+    if let Ok(f) = value.as_float() {
+        return Frame::Integer(f as i64);
+    }
+    if value.is_string() || value.is_char() {
+        return Frame::Bulk(Bytes::from(value.to_string()));
+    }
+    if value.is_blob() {
+        return Frame::Bulk(Bytes::from(value.cast::<Blob>()));
+    }
+    if value.is_array() {
+        return Frame::Array(value.cast::<Array>().into_iter().map(to_frame).collect());
+    }
+    if value.is_map() {
+        let map = value.cast::<Map>();
+        if let Some(msg) = map.get("err") {
+            return Frame::Error(msg.to_string());
+        }
+        if let Some(status) = map.get("ok") {
+            return Frame::Simple(status.to_string());
+        }
+    }
+    Frame::Error("ERR Unsupported script return value".to_string())
+}
@@ -0,0 +1,164 @@
+//! 故障转移的监控模式。
+//!
+//! 开启监控的服务器定期向主节点发送`Ping`，连续`quorum`次没有响应时判定主节点下线，
+//! 按照顺序向副本发送`ReplicaOf NO ONE`，第一个成功的副本成为新的主节点，
+//! 其余的副本改为复制新的主节点。
+//!
+//! 切换完成后，向监控服务器自己的`SWITCH_MASTER_CHANNEL`信道发布
+//! `<旧主节点地址> <新主节点地址>`，订阅了这个信道的客户端可以据此重新连接。
+//! 旧的主节点重新上线后会被改为新主节点的副本。
+
+use std::{mem, time::Duration};
+
+use bytes::Bytes;
+use tokio::time;
+use tracing::{info, warn};
+
+use crate::{client::Client, Db};
+
+/// 默认的检测间隔。
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 默认连续多少次检测失败后判定主节点下线。
+const DEFAULT_QUORUM: u32 = 3;
+
+/// 主节点切换后，新主节点的地址发布到的信道。
+pub const SWITCH_MASTER_CHANNEL: &str = "+switch-master";
+
+/// 故障转移的配置，通过`Server::sentinel()`开启。
+///
+/// 地址的格式为`<host>:<port>`。
+#[derive(Debug, Clone)]
+pub struct Sentinel {
+    primary: String,
+    replicas: Vec<String>,
+    quorum: u32,
+    interval: Duration,
+}
+
+impl Sentinel {
+    /// 监控`primary`，主节点下线后从`replicas`中选出新的主节点。
+    pub fn new(primary: impl ToString, replicas: Vec<String>) -> Sentinel {
+        Sentinel {
+            primary: primary.to_string(),
+            replicas,
+            quorum: DEFAULT_QUORUM,
+            interval: DEFAULT_INTERVAL,
+        }
+    }
+
+    /// 设置连续多少次检测失败后判定主节点下线，至少为`1`。
+    pub fn quorum(mut self, quorum: u32) -> Sentinel {
+        self.quorum = quorum.max(1);
+        self
+    }
+
+    /// 设置检测间隔，同时也是每次检测的超时时间。
+    pub fn interval(mut self, interval: Duration) -> Sentinel {
+        self.interval = interval;
+        self
+    }
+
+    /// 持续监控主节点，由`Server`在运行期间执行，不会返回。
+    pub(crate) async fn run(mut self, db: Db) {
+        info!(primary = %self.primary, replicas = ?self.replicas, "开始监控主节点");
+        let mut failures = 0;
+        // 故障转移后等待重新上线的旧主节点。
+        let mut demoted: Option<String> = None;
+        let mut interval = time::interval(self.interval);
+        loop {
+            interval.tick().await;
+
+            if let Some(addr) = &demoted {
+                if self.replicate(addr).await.is_ok() {
+                    info!(replica = %addr, primary = %self.primary, "旧的主节点已改为副本");
+                    self.replicas.push(demoted.take().unwrap());
+                }
+            }
+
+            if self.check(&self.primary).await {
+                failures = 0;
+                continue;
+            }
+            failures += 1;
+            warn!(primary = %self.primary, failures, "主节点没有响应");
+            if failures < self.quorum {
+                continue;
+            }
+
+            match self.failover().await {
+                Some(old) => {
+                    let message = format!("{} {}", old, self.primary);
+                    db.publish(SWITCH_MASTER_CHANNEL, Bytes::from(message));
+                    demoted = Some(old);
+                    failures = 0;
+                }
+                None => warn!(primary = %self.primary, "没有可以提升的副本"),
+            }
+        }
+    }
+
+    /// 检测节点是否能在检测间隔内响应`Ping`。
+    async fn check(&self, addr: &str) -> bool {
+        let ping = async {
+            let mut client = Client::connect(addr).await?;
+            client.ping(None).await
+        };
+        matches!(time::timeout(self.interval, ping).await, Ok(Ok(_)))
+    }
+
+    /// 将第一个能够响应的副本提升为主节点，其余的副本改为复制它，返回旧主节点的地址。
+    ///
+    /// 没有副本能够提升时返回`None`，主节点保持不变。
+    async fn failover(&mut self) -> Option<String> {
+        let position = {
+            let mut position = None;
+            for (i, addr) in self.replicas.iter().enumerate() {
+                if self.promote(addr).await.is_ok() {
+                    position = Some(i);
+                    break;
+                }
+                warn!(replica = %addr, "无法提升副本");
+            }
+            position?
+        };
+
+        let promoted = self.replicas.remove(position);
+        let old = mem::replace(&mut self.primary, promoted);
+        info!(old = %old, new = %self.primary, "主节点切换完成");
+
+        for addr in &self.replicas {
+            if let Err(err) = self.replicate(addr).await {
+                warn!(replica = %addr, cause = %err, "无法让副本复制新的主节点");
+            }
+        }
+        Some(old)
+    }
+
+    /// 向副本发送`ReplicaOf NO ONE`。
+    async fn promote(&self, addr: &str) -> crate::Result<()> {
+        let promote = async {
+            let mut client = Client::connect(addr).await?;
+            client.replicaof(None).await
+        };
+        time::timeout(self.interval, promote)
+            .await
+            .map_err(|_| "提升副本超时")?
+    }
+
+    /// 让节点复制当前的主节点。
+    async fn replicate(&self, addr: &str) -> crate::Result<()> {
+        let (host, port) = self
+            .primary
+            .rsplit_once(':')
+            .and_then(|(host, port)| Some((host, port.parse().ok()?)))
+            .ok_or("主节点的地址不合法")?;
+        let replicate = async {
+            let mut client = Client::connect(addr).await?;
+            client.replicaof(Some((host, port))).await
+        };
+        time::timeout(self.interval, replicate)
+            .await
+            .map_err(|_| "设置复制超时")?
+    }
+}
@@ -1,14 +1,122 @@
 //! my-redis的服务器的实现。
 //!
 //! 提供了异步的`run()`函数来监听到来的连接并为每个连接生成异步作业。
+//! 需要从配置文件、环境变量加载配置时，使用`Config`和`Server::from_config()`。
 
-use crate::{Command, Connection, Db, DbDropGuard, Shutdown};
-use std::{future::Future, sync::Arc, time::Duration};
+mod config;
+pub use config::{Config, LogFormat, ENV_PREFIX};
+
+use crate::{
+    clients::KillFilter,
+    cluster::{self, Cluster, ClusterState, Route},
+    cmd::CommandSpec,
+    interceptor::{Chain, ConnContext, Decision, Interceptor},
+    latency, replication,
+    sentinel::Sentinel,
+    snapshot,
+    storage::StorageHook,
+    tls::{self, Tls},
+    Command, Connection, Db, DbDropGuard, FlushPolicy, Frame, FrameLimits, Shutdown,
+};
+use bytes::Bytes;
+use std::{
+    future::{self, Future},
+    io,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::Poll,
+    time::Duration,
+};
 use tokio::{
     net::{TcpListener, TcpStream},
-    sync::{broadcast, mpsc, Semaphore},
-    time,
+    sync::{broadcast, mpsc, Notify},
+    time::{self, Instant},
 };
+use tokio_rustls::{rustls::ServerConfig, TlsAcceptor};
+use tracing::{debug, debug_span, error, info, info_span, warn, Instrument};
+
+/// 可以嵌入到应用中的 my-redis 服务器。
+///
+/// 与`run()`相比，`Server`允许在启动前获取`Handle`，
+/// 应用可以通过`Handle`在服务器运行期间获取统计信息。
+#[derive(Debug)]
+pub struct Server {
+    // 侦听的所有地址，见`Server::listener()`。
+    listeners: Vec<TcpListener>,
+
+    // 数据库`Db`的包装类，服务器运行时会转交给`Listener`。
+    db_holder: DbDropGuard,
+
+    // 运行时计数器，由所有`Handler`共享。
+    metrics: Arc<Metrics>,
+
+    // 已注册的命令拦截器。
+    interceptors: Chain,
+
+    // 故障转移的配置，设置后服务器运行期间监控主节点。
+    sentinel: Option<Sentinel>,
+
+    // 设置后，所有连接都需要先完成 TLS 握手。
+    tls: Option<TlsContext>,
+
+    // 每个连接读取和写入一个帧的超时时间，见`Connection::set_read_timeout()`。
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+
+    // 每个连接解析`Frame`时的限制，见`Connection::set_frame_limits()`。
+    frame_limits: FrameLimits,
+
+    // 每个连接写入响应后何时刷新，见`Connection::set_flush_policy()`。
+    flush_policy: FlushPolicy,
+
+    // 收到关闭信号后，等待连接处理完成的最长时间，`None`表示一直等待。
+    drain_timeout: Option<Duration>,
+
+    // 通知服务器立即关闭，不再等待连接处理完成，见`Handle::shutdown_now()`。
+    shutdown_now: Arc<Notify>,
+}
+
+/// 服务器的操作句柄，可以被克隆并在服务器运行期间使用。
+#[derive(Debug, Clone)]
+pub struct Handle {
+    db: Db,
+    metrics: Arc<Metrics>,
+    shutdown_now: Arc<Notify>,
+}
+
+/// 服务器运行时统计信息的快照。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// 服务器启动以来执行过的命令数。
+    pub commands_processed: u64,
+    /// 当前的连接数。
+    pub connected_clients: usize,
+    /// 服务器启动以来因为连接数达到上限而被拒绝的连接数。
+    pub rejected_connections: u64,
+    /// 数据库中 key 的数量。
+    pub keys: usize,
+    /// 数据库中 key 和 value 占用的近似字节数。
+    pub used_memory: usize,
+    /// 服务器的运行时长。
+    pub uptime: Duration,
+}
+
+/// 运行时计数器。
+#[derive(Debug)]
+pub(crate) struct Metrics {
+    // 执行过的命令数。
+    commands_processed: AtomicU64,
+    // 当前的连接数。
+    connected_clients: AtomicUsize,
+    // 因为连接数达到上限而被拒绝的连接数。
+    rejected_connections: AtomicU64,
+    // 服务器的创建时间。
+    started_at: Instant,
+}
 
 /// Server Listner，包装了`tokio::net::TcpListener`，
 /// 在`server::run()`方法内被创建。
@@ -16,20 +124,51 @@ use tokio::{
 /// 负责 Tcp 侦听以及连接初始化。
 #[derive(Debug)]
 struct Listener {
-    // `tokio::net::TcpListener`，由`run()`方法提供。
-    listener: TcpListener,
+    // `tokio::net::TcpListener`，由`run()`方法提供，至少有一个。
+    listeners: Vec<TcpListener>,
 
     // 数据库`Db`的包装类，负责在被 drop 的时候通知后台工作程序。
     db_holder: DbDropGuard,
 
-    // 信号量，用于限制最大连接数。
-    limit_connection: Arc<Semaphore>,
-
     // 广播发送端，用于通知所有`Handler`停止运行。
     notify_shutdown: broadcast::Sender<()>,
 
     // mpsc发送端，用于知道何时所有`Handler`都达到了安全状态。
     shutdown_complete_tx: mpsc::Sender<()>,
+
+    // 运行时计数器。
+    metrics: Arc<Metrics>,
+
+    // 命令拦截器，由所有`Handler`共享。
+    interceptors: Chain,
+
+    // 设置后，所有连接都需要先完成 TLS 握手。
+    tls: Option<TlsContext>,
+
+    // 每个连接读取和写入一个帧的超时时间，见`Connection::set_read_timeout()`。
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+
+    // 每个连接解析`Frame`时的限制，见`Connection::set_frame_limits()`。
+    frame_limits: FrameLimits,
+
+    // 每个连接写入响应后何时刷新，见`Connection::set_flush_policy()`。
+    flush_policy: FlushPolicy,
+}
+
+/// 占用的一个连接数，被 drop 时释放，见`Clients::try_acquire()`。
+#[derive(Debug)]
+struct ConnectionPermit {
+    db: Db,
+}
+
+/// 开启 TLS 时，所有连接共享的握手配置。
+#[derive(Debug, Clone)]
+struct TlsContext {
+    config: Arc<ServerConfig>,
+
+    // 是否将客户端证书的 CN 作为连接的用户。
+    cn_as_user: bool,
 }
 
 /// 连接的操作句柄，每一个 Tcp 连接都对应一个`Handler`。
@@ -54,48 +193,434 @@ struct Handler {
     // 当`Handler`离开作用域被丢弃时，这个字段也会被丢弃。
     // 以此表示该`Handler`已完成收尾工作
     _shudown_complete: mpsc::Sender<()>,
+
+    // 运行时计数器，每执行一条命令都会更新。
+    metrics: Arc<Metrics>,
+
+    // 命令拦截器，在命令执行前后调用。
+    interceptors: Chain,
+
+    // 连接的上下文信息，传递给拦截器。
+    ctx: ConnContext,
 }
 
-/// 最大连接数。
-const MAX_CONNECTION: usize = 250;
+/// 连接数达到上限时，回复给新连接的错误信息。
+const MAX_CLIENTS_ERROR: &str = "ERR max number of clients reached";
+
+/// 数据库默认的分片数，见`Server::with_db_shards()`。
+pub const DEFAULT_DB_SHARDS: usize = 16;
 
 /// 启动 my-redis 服务器。
 ///
-/// 他会将传入的`tokio::net::TcpListener`包装为自定义的`Listener`，
-/// 然后同时启动`Listener`以及`shutdown`异步任务，后者用于监听关闭信号。
+/// 等价于`Server::new(listener).run(shutdown)`，如果需要在运行期间
+/// 获取统计信息，请使用`Server`。
 ///
 /// 可以使用`tokio::signal::ctrl_c()`作为`shutdown`参数。
-///
-/// # Errors
-/// 如果`Listener`运行出错，返回`Err`。
 pub async fn run(listener: TcpListener, shutdown: impl Future) {
+    Server::new(listener).run(shutdown).await
+}
+
+impl Server {
+    /// 创建服务器，同时创建数据库。
+    ///
+    /// 数据库会开启后台任务，因此必须在 tokio 运行时中调用。
+    pub fn new(listener: TcpListener) -> Server {
+        Server::with_db_shards(listener, DEFAULT_DB_SHARDS)
+    }
+
+    /// 按照`config`侦听所有地址并创建服务器，同时加载 ACL 文件、快照文件等。
+    ///
+    /// # Errors
+    /// 侦听失败、TLS 配置不完整或者无法加载，以及加载 ACL 文件、快照文件失败时返回`Err`。
+    pub async fn from_config(config: Config) -> crate::Result<Server> {
+        config.build().await
+    }
+
+    /// 创建服务器，数据库的数据分为`shards`个分片，至少为 1 个。
+    ///
+    /// 每个分片有自己的锁，访问不同分片的 key 的连接不会互相阻塞，
+    /// 分片越多，同时访问多个 key 的命令需要获取的锁越多。
+    pub fn with_db_shards(listener: TcpListener, shards: usize) -> Server {
+        Server {
+            listeners: vec![listener],
+            db_holder: DbDropGuard::with_shards(shards),
+            metrics: Arc::new(Metrics::new()),
+            interceptors: Chain::default(),
+            sentinel: None,
+            tls: None,
+            read_timeout: None,
+            write_timeout: None,
+            frame_limits: FrameLimits::default(),
+            flush_policy: FlushPolicy::Batch,
+            drain_timeout: None,
+            shutdown_now: Arc::new(Notify::new()),
+        }
+    }
+
+    /// 同时侦听另一个地址，所有地址上的连接共享同一个数据库和连接数上限。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use my_redis::server::Server;
+    /// use tokio::net::TcpListener;
+    ///
+    /// # async fn dox() {
+    /// let v4 = TcpListener::bind("127.0.0.1:6379").await.unwrap();
+    /// let v6 = TcpListener::bind("[::1]:6379").await.unwrap();
+    /// let server = Server::new(v4).listener(v6);
+    /// server.run(tokio::signal::ctrl_c()).await;
+    /// # }
+    /// ```
+    pub fn listener(mut self, listener: TcpListener) -> Server {
+        self.listeners.push(listener);
+        self
+    }
+
+    /// 注册一个命令拦截器，拦截器按照注册的顺序被调用。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use my_redis::interceptor::Decision;
+    /// use my_redis::server::Server;
+    /// use tokio::net::TcpListener;
+    ///
+    /// # async fn dox() {
+    /// let listener = TcpListener::bind("127.0.0.1:6379").await.unwrap();
+    /// let server = Server::new(listener).interceptor(|cmd: &my_redis::Command, _: &_| {
+    ///     match cmd.get_name() {
+    ///         "config" => Decision::Reject("ERR CONFIG is disabled".to_string()),
+    ///         _ => Decision::Continue,
+    ///     }
+    /// });
+    /// server.run(tokio::signal::ctrl_c()).await;
+    /// # }
+    /// ```
+    pub fn interceptor(mut self, interceptor: impl Interceptor) -> Server {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// 开启读优化模式，`Get`不需要获取数据库的锁，适用于读远多于写的场景。
+    ///
+    /// 每次修改数据后都要发布新的版本，写操作会变慢；读取时不会更新 key 的最后访问时刻。
+    pub fn read_optimized(self) -> Server {
+        self.db_holder.db().set_read_optimized(true);
+        self
+    }
+
+    /// 设置最大连接数，默认为 250，至少为 1。
+    ///
+    /// 连接数达到上限后，新的连接会收到`ERR max number of clients reached`，然后被关闭。
+    /// 服务器运行期间可以通过`Config Set maxclients`修改。
+    pub fn max_connections(self, max: usize) -> Server {
+        self.db_holder.db().clients().set_maxclients(max.max(1));
+        self
+    }
+
+    /// 注册存储钩子，数据库的每次修改都会通知钩子，只能调用一次。
+    ///
+    /// # Panics
+    /// 如果已经注册过存储钩子，会 panic。
+    pub fn storage_hook(self, hook: impl StorageHook) -> Server {
+        self.db_holder.db().set_storage_hook(Arc::new(hook));
+        self
+    }
+
+    /// 在接受连接之前，从快照文件`path`恢复数据，之后`Save`和`BgSave`也会写入这个文件。
+    ///
+    /// 文件不存在时以空数据库启动。恢复的 key 保留剩余的过期时间，已经过期的 key 会被忽略。
+    ///
+    /// # Errors
+    /// 如果读取文件失败或者文件已损坏，返回`Err`，此时数据库保持为空。
+    pub async fn load_snapshot(self, path: impl Into<PathBuf>) -> crate::Result<Server> {
+        let path = path.into();
+        let db = self.db_holder.db();
+        match snapshot::read_file(&path).await {
+            Ok(entries) => {
+                info!(path = %path.display(), keys = entries.len(), "从快照恢复数据");
+                db.restore(entries);
+            }
+            Err(crate::Error::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+                info!(path = %path.display(), "快照文件不存在，以空数据库启动");
+            }
+            Err(err) => {
+                return Err(format!("无法从快照文件 {} 恢复数据：{}", path.display(), err).into())
+            }
+        }
+        db.persistence().set_dbfilename(path);
+        Ok(self)
+    }
+
+    /// 在接受连接之前，从 ACL 文件`path`加载用户，之后`Acl Load`会重新加载这个文件。
+    ///
+    /// 文件的每一行为`user <username> [<rule> ...]`，空行和`#`开头的行会被忽略。
+    /// 文件中没有`default`用户时，默认用户不需要密码并且拥有所有权限。
+    ///
+    /// # Errors
+    /// 如果读取文件失败或者有不合法的规则，返回`Err`。
+    pub fn acl_file(self, path: impl Into<PathBuf>) -> crate::Result<Server> {
+        self.db_holder.db().acl().load_file(&path.into())?;
+        Ok(self)
+    }
+
+    /// 开启集群模式，操作的 key 所在的槽不属于这个节点时，返回`MOVED`或`ASK`重定向。
+    ///
+    /// # Panics
+    /// 只能调用一次，重复调用会 panic。
+    pub fn cluster(self, cluster: Cluster) -> Server {
+        self.db_holder.db().set_cluster(ClusterState::new(cluster));
+        self
+    }
+
+    /// 开启故障转移的监控模式，服务器运行期间监控主节点，下线后将副本提升为主节点。
+    ///
+    /// 主节点切换后，新的地址发布到`sentinel::SWITCH_MASTER_CHANNEL`信道，
+    /// 客户端可以连接到这个服务器订阅。
+    pub fn sentinel(mut self, sentinel: Sentinel) -> Server {
+        self.sentinel = Some(sentinel);
+        self
+    }
+
+    /// 设置读取一个命令的超时时间，从收到命令的第一部分数据开始计时。
+    ///
+    /// 客户端发送了不完整的命令后停止发送或者发送得太慢时，超时后关闭连接。
+    /// 等待下一个命令时不计时，空闲的连接由配置项`timeout`关闭。
+    pub fn read_timeout(mut self, timeout: Duration) -> Server {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// 设置写入一个响应的超时时间，客户端不再读取响应导致写入被阻塞时，超时后关闭连接。
+    pub fn write_timeout(mut self, timeout: Duration) -> Server {
+        self.write_timeout = Some(timeout);
+        self
+    }
+
+    /// 设置解析客户端发送的`Frame`时的限制，默认为`FrameLimits::default()`。
+    ///
+    /// 长度头部或者嵌套层数超过限制时，回复协议错误后关闭连接。
+    pub fn frame_limits(mut self, limits: FrameLimits) -> Server {
+        self.frame_limits = limits;
+        self
+    }
+
+    /// 设置写入响应后何时刷新，默认为`FlushPolicy::Batch`。
+    ///
+    /// 批量刷新时，客户端通过流水线发送的命令全部执行完后才一起写回响应，
+    /// 阻塞的命令执行前会先写回之前的响应。
+    pub fn flush_policy(mut self, policy: FlushPolicy) -> Server {
+        self.flush_policy = policy;
+        self
+    }
+
+    /// 设置收到关闭信号后等待连接处理完成的最长时间，超时后强制关闭剩余的连接。
+    ///
+    /// 默认一直等待。无论是否设置，都可以通过`Handle::shutdown_now()`立即关闭。
+    pub fn drain_timeout(mut self, timeout: Duration) -> Server {
+        self.drain_timeout = Some(timeout);
+        self
+    }
+
+    /// 开启 TLS，所有连接都需要先完成 TLS 握手。
+    ///
+    /// # Errors
+    /// 如果读取证书、私钥或者客户端 CA 证书失败，或者证书与私钥不匹配，返回`Err`。
+    pub fn tls(mut self, tls: Tls) -> crate::Result<Server> {
+        self.tls = Some(TlsContext {
+            config: Arc::new(tls.server_config()?),
+            cn_as_user: tls.maps_cn_to_user(),
+        });
+        Ok(self)
+    }
+
+    /// 获取服务器的操作句柄。
+    pub fn handle(&self) -> Handle {
+        Handle {
+            db: self.db_holder.db(),
+            metrics: self.metrics.clone(),
+            shutdown_now: self.shutdown_now.clone(),
+        }
+    }
+
+    /// 运行服务器，直到`shutdown`完成。
+    ///
+    /// 他会将`tokio::net::TcpListener`包装为自定义的`Listener`，
+    /// 然后同时启动`Listener`以及`shutdown`异步任务，后者用于监听关闭信号。
+    pub async fn run(self, shutdown: impl Future) {
+        run_listener(self, shutdown).await
+    }
+}
+
+impl Handle {
+    /// 获取服务器当前的运行时统计信息。
+    pub fn stats(&self) -> Stats {
+        self.metrics.stats(&self.db)
+    }
+
+    /// 立即关闭服务器，正在执行的命令会被中断，不再等待连接处理完成。
+    ///
+    /// 服务器还没有收到关闭信号时也会开始关闭，例如在第二次收到 Ctrl-C 时调用。
+    pub fn shutdown_now(&self) {
+        self.shutdown_now.notify_one();
+    }
+}
+
+impl ConnectionPermit {
+    /// 如果连接数没有达到上限，占用一个连接数。
+    fn try_acquire(db: &Db) -> Option<ConnectionPermit> {
+        db.clients()
+            .try_acquire()
+            .then(|| ConnectionPermit { db: db.clone() })
+    }
+
+    /// 占用一个连接数，不检查上限。
+    #[cfg(feature = "test-util")]
+    fn acquire(db: &Db) -> ConnectionPermit {
+        db.clients().acquire();
+        ConnectionPermit { db: db.clone() }
+    }
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.db.clients().release();
+    }
+}
+
+impl Metrics {
+    pub(crate) fn new() -> Metrics {
+        Metrics {
+            commands_processed: AtomicU64::new(0),
+            connected_clients: AtomicUsize::new(0),
+            rejected_connections: AtomicU64::new(0),
+            started_at: Instant::now(),
+        }
+    }
+
+    /// 获取运行时统计信息的快照，见`Handle::stats()`。
+    pub(crate) fn stats(&self, db: &Db) -> Stats {
+        Stats {
+            commands_processed: self.commands_processed.load(Ordering::Relaxed),
+            connected_clients: self.connected_clients.load(Ordering::Relaxed),
+            rejected_connections: self.rejected_connections.load(Ordering::Relaxed),
+            keys: db.len(),
+            used_memory: db.used_memory(),
+            uptime: self.started_at.elapsed(),
+        }
+    }
+}
+
+/// 处理一个已经建立的连接，直到连接关闭或者收到关闭信号。
+///
+/// 与`Listener`不同，这里不受最大连接数的限制，但同样计入连接数，
+/// 用于内存管道等不经过`TcpListener`建立的连接。
+/// 连接的命令数和连接数同样记录在`metrics`中。
+#[cfg(feature = "test-util")]
+pub(crate) async fn serve_connection(
+    db: Db,
+    connection: Connection,
+    metrics: Arc<Metrics>,
+    notify_shutdown: broadcast::Receiver<()>,
+    shutdown_complete: mpsc::Sender<()>,
+) {
+    let permit = ConnectionPermit::acquire(&db);
+    let mut handler = Handler {
+        ctx: ConnContext::new(db.clients().next_id(), None),
+        db,
+        connection,
+        shutdown: Shutdown::new(notify_shutdown),
+        _shudown_complete: shutdown_complete,
+        metrics,
+        interceptors: Chain::default(),
+    };
+    handler
+        .metrics
+        .connected_clients
+        .fetch_add(1, Ordering::Relaxed);
+    let span = info_span!("connection", id = handler.ctx.id());
+    if let Err(err) = handler.run().instrument(span.clone()).await {
+        error!(parent: &span, cause = %err, "连接错误");
+    }
+    handler
+        .metrics
+        .connected_clients
+        .fetch_sub(1, Ordering::Relaxed);
+    drop(permit);
+}
+
+/// 连接数达到上限时，向客户端发送错误信息，然后关闭连接。
+///
+/// 开启 TLS 时先完成握手，客户端才能读到错误信息。
+async fn reject_connection(socket: TcpStream, tls: Option<TlsContext>) {
+    let error = Frame::Error(MAX_CLIENTS_ERROR.to_string());
+    let result = match tls {
+        Some(tls) => match TlsAcceptor::from(tls.config).accept(socket).await {
+            Ok(stream) => Connection::new(stream).write_frame(&error).await,
+            Err(err) => Err(err),
+        },
+        None => Connection::new(socket).write_frame(&error).await,
+    };
+    if let Err(err) = result {
+        debug!(cause = %err, "无法发送连接数达到上限的错误信息");
+    }
+}
+
+/// 运行`Listener`并在接收到关闭信号后等待所有`Handler`完成清理工作。
+async fn run_listener(server: Server, shutdown: impl Future) {
     // 我们只获取广播的发送端，因为可以直接订阅广播发送端。
     // 信道的信息容量设置为1即可，毕竟只需要发送一次信息。
     let (notify_shutdown, _) = broadcast::channel(1);
     // 获取mpsc的发送端和接收端。
     let (shutdown_complete_tx, mut shutdown_complete_rx) = mpsc::channel(1);
 
+    let drain_timeout = server.drain_timeout;
+    let shutdown_now = server.shutdown_now;
+    let clients_db = server.db_holder.db();
+
+    // 监控主节点的任务不会结束，随服务器一起关闭。
+    let db = server.db_holder.db();
+    let sentinel = async move {
+        match server.sentinel {
+            Some(sentinel) => sentinel.run(db).await,
+            None => std::future::pending().await,
+        }
+    };
+
     // 创建自定义的 Listner。
     let mut server = Listener {
-        listener,
-        db_holder: DbDropGuard::new(),
-        limit_connection: Arc::new(Semaphore::new(MAX_CONNECTION)),
+        listeners: server.listeners,
+        db_holder: server.db_holder,
         notify_shutdown,
         shutdown_complete_tx,
+        metrics: server.metrics,
+        interceptors: server.interceptors,
+        tls: server.tls,
+        read_timeout: server.read_timeout,
+        write_timeout: server.write_timeout,
+        frame_limits: server.frame_limits,
+        flush_policy: server.flush_policy,
     };
 
     // 运行 server 的同时监听关闭信号。
     // server 只有在出现错误的时候才会结束，因此通常情况下下面的语句
     // 会一直运行，直到 shuntdown 这个`Future`运行完成，即接收到关闭信号。
+    let mut force = false;
     tokio::select! {
         res = server.run() => {
             // 出错，抛出错误。
             if let Err(err) = res{
-                println!("服务器启动失败，原因：{}",err);
+                error!(cause = %err, "服务器启动失败");
             }
         }
+        _ = sentinel => {}
         _ = shutdown => {
-            println!("接收到关闭信号，准备关闭");
+            info!("接收到关闭信号，准备关闭");
+        }
+        _ = shutdown_now.notified() => {
+            info!("接收到立即关闭的请求");
+            force = true;
         }
     }
 
@@ -116,8 +641,27 @@ pub async fn run(listener: TcpListener, shutdown: impl Future) {
     // 等待所有`Handler`完成清理工作，之后所有`Handler`便会因离开作用域而被丢弃，
     // 其内部的`mpsc::Sender`也会被丢弃。
     // 所有的mpsc发送端都被丢弃后，接收端最终返回`None`，服务器关闭。
-    let _ = shutdown_complete_rx.recv().await;
-    println!("服务器已关闭");
+    // 超过`drain_timeout`或者要求立即关闭时，不再等待。
+    if !force {
+        let deadline = async {
+            match drain_timeout {
+                Some(timeout) => time::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = shutdown_complete_rx.recv() => {
+                info!("服务器已关闭");
+                return;
+            }
+            _ = deadline => warn!("等待连接处理完成超时，强制关闭"),
+            _ = shutdown_now.notified() => warn!("接收到立即关闭的请求，强制关闭"),
+        }
+    }
+
+    // 与`Client Kill`相同，通知剩余的`Handler`中断正在执行的命令并退出。
+    let killed = clients_db.clients().kill(&KillFilter::default());
+    info!(killed, "服务器已关闭");
 }
 
 impl Listener {
@@ -132,33 +676,90 @@ impl Listener {
     async fn run(&mut self) -> crate::Result<()> {
         // 这是一个无限循环，除非循环内抛出了错误。
         loop {
-            // 等待信号量可用。
-            // `acquire_owned()`返回一个 permit，当它被 drop 的时候，信号量会自动递增。
-            // `acquire_owned()`返回`Err`的时候表示信号量被关闭了，我们没有关闭信号量，因此`unwrap()`是安全的。
-            let permit = self.limit_connection.clone().acquire_owned().await.unwrap();
-
             // 获取一个新的 socket。由于我们已经在`accept()`内部尝试恢复错误，
             // 所以如果还是抛出了错误，那么这个错误就是不可恢复的。
             // 此时应该退出循环，结束 server。
-            let socket = self.accept().await?;
-
-            // 为每个连接都创建一个`Handler`，由`Handler`负责工作。
-            let mut handler = Handler {
-                db: self.db_holder.db(),
-                connection: Connection::new(socket),
-                shutdown: Shutdown::new(self.notify_shutdown.subscribe()),
-                _shudown_complete: self.shutdown_complete_tx.clone(),
+            let (socket, peer_addr) = self.accept().await?;
+            let db = self.db_holder.db();
+
+            // 占用一个连接数，permit 被 drop 的时候释放。
+            // 连接数已经达到上限时，回复错误后关闭连接，而不是让客户端一直等待。
+            let Some(permit) = ConnectionPermit::try_acquire(&db) else {
+                self.metrics
+                    .rejected_connections
+                    .fetch_add(1, Ordering::Relaxed);
+                warn!(peer = %peer_addr, "连接数已经达到上限，拒绝连接");
+                tokio::spawn(reject_connection(socket, self.tls.clone()));
+                continue;
             };
+            let mut ctx = ConnContext::new(db.clients().next_id(), Some(peer_addr));
+            let shutdown = Shutdown::new(self.notify_shutdown.subscribe());
+            let shutdown_complete = self.shutdown_complete_tx.clone();
+            let metrics = self.metrics.clone();
+            let interceptors = self.interceptors.clone();
+            let tls = self.tls.clone();
+            let (read_timeout, write_timeout) = (self.read_timeout, self.write_timeout);
+            let (frame_limits, flush_policy) = (self.frame_limits, self.flush_policy);
+
+            // 连接中的所有日志都带有连接的 ID 和客户端地址。
+            let span = info_span!("connection", id = ctx.id(), peer = %peer_addr);
 
             // 开启一个异步任务，将`Handler`传入，让其运行。
-            tokio::spawn(async move {
+            // TLS 握手也在任务中进行，避免一个缓慢的客户端阻塞其他连接的接收。
+            let task = async move {
+                let mut connection = match tls {
+                    Some(tls) => match TlsAcceptor::from(tls.config).accept(socket).await {
+                        Ok(stream) => {
+                            if tls.cn_as_user {
+                                // 证书已经通过了验证，CN 对应的用户视为已认证。
+                                match tls::peer_common_name(stream.get_ref().1) {
+                                    Some(cn) if db.acl().is_enabled(&cn) => ctx.set_user(&cn),
+                                    cn => debug!(?cn, "客户端证书没有对应的用户，使用默认用户"),
+                                }
+                            }
+                            Connection::new(stream)
+                        }
+                        Err(err) => {
+                            warn!(cause = %err, "TLS 握手失败");
+                            return;
+                        }
+                    },
+                    None => Connection::new(socket),
+                };
+                connection.set_read_timeout(read_timeout);
+                connection.set_write_timeout(write_timeout);
+                connection.set_frame_limits(frame_limits);
+                connection.set_flush_policy(flush_policy);
+
+                // 为每个连接都创建一个`Handler`，由`Handler`负责工作。
+                let mut handler = Handler {
+                    db,
+                    connection,
+                    shutdown,
+                    _shudown_complete: shutdown_complete,
+                    metrics,
+                    interceptors,
+                    ctx,
+                };
+
+                handler
+                    .metrics
+                    .connected_clients
+                    .fetch_add(1, Ordering::Relaxed);
+                debug!("接受连接");
                 // `Handler`开始工作，处理错误。
                 if let Err(err) = handler.run().await {
-                    println!("连接错误，原因：{}", err);
+                    error!(cause = %err, "连接错误");
                 }
-                // 工作完成，将 permit 丢弃，信号量递增。
+                debug!("连接关闭");
+                handler
+                    .metrics
+                    .connected_clients
+                    .fetch_sub(1, Ordering::Relaxed);
+                // 工作完成，将 permit 丢弃，释放占用的连接数。
                 drop(permit);
-            });
+            };
+            tokio::spawn(task.instrument(span));
         }
     }
 
@@ -170,13 +771,27 @@ impl Listener {
     /// # Errors
     ///
     /// 如果在等待64秒后第6次尝试时仍接收失败，返回`Err`。
-    async fn accept(&mut self) -> crate::Result<TcpStream> {
+    /// 成功时返回 socket 以及客户端的地址。
+    async fn accept(&mut self) -> crate::Result<(TcpStream, SocketAddr)> {
         let mut backoff = 1;
         // 尝试接收连接。
         loop {
-            // 等待连接到来。如果成功，直接返回，否则尝试重试。
-            match self.listener.accept().await {
-                Ok((socket, _)) => return Ok(socket),
+            // 等待任意一个地址上的连接到来。如果成功，直接返回，否则尝试重试。
+            let accepted = future::poll_fn(|cx| {
+                for listener in &self.listeners {
+                    if let Poll::Ready(res) = listener.poll_accept(cx) {
+                        return Poll::Ready(res);
+                    }
+                }
+                Poll::Pending
+            })
+            .await;
+            match accepted {
+                Ok((socket, addr)) => {
+                    // 关闭 Nagle 算法，分多次写回的流水线响应不需要等待客户端的确认。
+                    let _ = socket.set_nodelay(true);
+                    return Ok((socket, addr));
+                }
                 Err(err) => {
                     if backoff > 64 {
                         // 失败太多次了，返回错误。
@@ -203,14 +818,55 @@ impl Handler {
     /// # Errors
     /// 上述任何一个任务出现错误，返回`Err`。
     async fn run(&mut self) -> crate::Result<()> {
+        // 登记连接，`Client Kill`关闭连接时，正在执行的命令会被中断。
+        let killed =
+            self.db
+                .clients()
+                .register(self.ctx.id(), self.ctx.peer_addr(), self.ctx.user());
+        tokio::select! {
+            res = self.serve() => {
+                // 批量刷新时，出错之前执行的命令的响应可能还在写缓存中。
+                let _ = self.connection.flush().await;
+                match res {
+                    // 读写超时视为连接断开。
+                    Err(err) if err.is_timeout() => {
+                        debug!(cause = %err, "连接读写超时，关闭连接");
+                        Ok(())
+                    }
+                    res => res,
+                }
+            },
+            _ = killed.notified() => {
+                debug!("连接被 Client Kill 关闭");
+                Ok(())
+            }
+        }
+    }
+
+    /// 循环读取并执行命令，直到连接关闭或者收到关闭信号。
+    async fn serve(&mut self) -> crate::Result<()> {
         // 只要`Shuntdown`还未接收到关闭信号后，继续循环。
         while !self.shutdown.is_shutdown() {
             // 启动`Shutdown`的 async 函数，等待接收关闭信号，
             // 同时尝试从`Connection`中读取帧。
             // 只要“读取帧”这个行为先于“接收到关闭信号”，那就往下继续执行。
+            // 空闲超时时间在每次读取前获取，`Config Set timeout`对已有的连接也生效。
+            let idle = self.db.config().timeout();
             let maybe_frame = tokio::select! {
                 // 如果读取数据帧出错，抛出错误。
-                res = self.connection.read_frame() => res?,
+                // 协议错误时与 Redis 一样先回复错误信息，再关闭连接。
+                res = self.connection.read_command() => match res {
+                    Err(crate::Error::Protocol(msg)) => {
+                        let reply = Frame::Error(format!("ERR Protocol error: {}", msg));
+                        let _ = self.connection.write_frame(&reply).await;
+                        return Err(crate::Error::Protocol(msg));
+                    }
+                    res => res?,
+                },
+                _ = idle_timeout(idle) => {
+                    debug!("连接空闲超时，关闭连接");
+                    return Ok(())
+                }
                 _ = self.shutdown.recv() => {
                     // 关闭信号被视为是正常的终止，返回的是`Ok`
                     return Ok(())
@@ -224,19 +880,236 @@ impl Handler {
                 None => return Ok(()),
             };
 
+            // 写命令需要发送给副本，在解析前保留一份原始的帧。
+            let spec = frame_spec(&frame);
+            let write = spec.is_some_and(|spec| spec.has_flag("write"));
+            let denyoom = spec.is_some_and(|spec| spec.has_flag("denyoom"));
+            let replica_frame = write.then(|| frame.clone());
+            // `Publish`不修改数据，但同样转发给副本，连接到副本的订阅者也能收到信息。
+            let publish_frame = (spec.is_some_and(|spec| spec.has_flag("may-replicate"))
+                && self.db.replication().has_replicas())
+            .then(|| frame.clone());
+            // 有连接正在监视时同样保留一份，命令执行前发送给监视器，管理命令不会被发送。
+            let monitor_frame = (self.db.monitors().is_active()
+                && spec.is_some_and(|spec| !spec.has_flag("admin")))
+            .then(|| frame.clone());
+
             // 将数据帧转化为`Command`。
-            // 如果转化失败，说明为不合法或无法识别的操作命令，抛出错误。
+            // 参数不合法的命令会被解析为`Invalid`，只有数据帧不是`Array`时才会抛出错误。
             let cmd = Command::from_frame(frame)?;
+            // 参数不合法的命令只返回错误信息，不会修改数据，不需要发送给副本。
+            let (replica_frame, publish_frame) = if matches!(cmd, Command::Invalid(_)) {
+                (None, None)
+            } else {
+                (replica_frame, publish_frame)
+            };
+            self.db.clients().touch(
+                self.ctx.id(),
+                cmd.get_name(),
+                self.ctx.user(),
+                self.connection.buffer_capacity(),
+            );
+
+            // 没有认证或者没有权限的命令，返回错误信息，继续处理下一条命令。
+            if let Err(msg) = self.db.acl().check(&self.ctx, &cmd) {
+                self.connection.write_frame(&Frame::Error(msg)).await?;
+                continue;
+            }
+
+            // 拦截器拒绝执行的命令，返回错误信息，继续处理下一条命令。
+            if let Decision::Reject(msg) = self.interceptors.before(&cmd, &self.ctx) {
+                self.connection.write_frame(&Frame::Error(msg)).await?;
+                continue;
+            }
+
+            // 集群模式下，key 所在的槽不属于这个节点时重定向。
+            let asking = self.ctx.take_asking();
+            if let Some(msg) = self.redirect(&cmd, asking) {
+                self.connection.write_frame(&Frame::Error(msg)).await?;
+                continue;
+            }
+
+            // 内存超过`maxmemory`后，拒绝可能增加内存的命令。
+            if denyoom && self.db.is_over_maxmemory() {
+                self.connection
+                    .write_frame(&Frame::Error(crate::config::OOM_ERROR.to_string()))
+                    .await?;
+                continue;
+            }
+
+            // 副本只执行主节点发送的写命令。
+            if write && self.db.replication().is_replica() {
+                let msg = "READONLY You can't write against a read only replica.";
+                self.connection
+                    .write_frame(&Frame::Error(msg.to_string()))
+                    .await?;
+                continue;
+            }
+
+            // `Client Pause`期间等待暂停结束，脚本可能执行写命令，视为写命令。
+            // `Client`命令不会被暂停，否则无法通过`Client Unpause`提前结束暂停。
+            if !matches!(cmd, Command::Client(_)) {
+                let write = write || matches!(cmd, Command::Eval(_) | Command::FCall(_));
+                // 批量刷新时，等待之前先写回积压的响应。
+                if self.db.clients().is_paused(write) {
+                    self.connection.flush().await?;
+                }
+                tokio::select! {
+                    _ = self.db.clients().wait_unpaused(write) => {}
+                    _ = self.shutdown.recv() => return Ok(()),
+                }
+            }
 
             let cmd_name = cmd.get_name().to_string();
+            let key = cmd.get_key().map(str::to_string);
+            // 订阅命令和阻塞式命令不应该被视为慢命令。
+            let blocking = cmd.is_blocking();
+            // 阻塞式命令可能会等待很久，批量刷新时先写回之前积压的响应。
+            if blocking {
+                self.connection.flush().await?;
+            }
+
+            // 脚本执行期间不能执行其他命令，`Eval`和`FCall`自己会获取写锁。
+            // 阻塞式命令可能会等待很久，不获取锁，否则脚本需要一直等待。
+            let _script_guard = if blocking || matches!(cmd, Command::Eval(_) | Command::FCall(_)) {
+                None
+            } else {
+                Some(self.db.script_lock().read().await)
+            };
+
             // 执行命令，这有可能会更改数据库的状态。
             // `Handler`的“写回响应数据”的任务也委派给了它，因此传入`Connection`。
             // 如果执行出错，抛出错误。
-            cmd.apply(&self.db, &mut self.connection, &mut self.shutdown)
-                .await?;
-            println!("{cmd_name} finished!");
+            if let Some(frame) = &monitor_frame {
+                self.db.monitors().feed(self.ctx.peer_addr(), frame);
+            }
+            // 命令执行期间的日志都带有命令的名称。
+            let span = debug_span!("command", cmd = %cmd_name);
+            let start = Instant::now();
+            // 写命令在持有分片的锁时获取复制序号，按照序号的顺序发送给副本，
+            // 修改同一个分片的写命令在副本上的执行顺序与主节点相同，见`replication::stamped()`。
+            let apply = cmd
+                .apply(
+                    &self.db,
+                    &mut self.connection,
+                    &mut self.shutdown,
+                    &mut self.ctx,
+                )
+                .instrument(span.clone());
+            let (applied, stamp) = if write {
+                replication::stamped(apply).await
+            } else {
+                (apply.await, None)
+            };
+            let applied = applied?;
+            let duration = start.elapsed();
+            self.interceptors.after(&cmd_name, &self.ctx, duration);
+
+            // 只有执行成功的写命令才会修改数据，需要发送给副本。
+            // 超时的阻塞式命令没有复制序号，同样没有修改数据。
+            if let Some(frame) = replica_frame.filter(|_| applied) {
+                let offset = match stamp {
+                    Some(stamp) => Some(stamp.propagate(frame)),
+                    None if !blocking => Some(self.db.replication().propagate(frame)),
+                    None => None,
+                };
+                if let Some(offset) = offset {
+                    self.db.persistence().incr_dirty();
+                    self.ctx.set_repl_offset(offset);
+                }
+            } else if let Some(frame) = publish_frame {
+                self.db.replication().propagate(frame);
+            } else if matches!(&cmd_name[..], "eval" | "evalsha" | "fcall") {
+                // 脚本中的写命令执行时已经发送给副本，保守地使用当前的偏移量。
+                self.ctx.set_repl_offset(self.db.replication().offset());
+            }
+
+            // 客户端缓存追踪：记录开启了追踪的连接读取的 key，写命令修改 key 后发送失效信息。
+            // `Db`中的写入、删除和过期已经会发送失效信息，这里覆盖其他修改了 key 的命令。
+            if let (Some(key), Some(spec)) = (&key, CommandSpec::lookup(&cmd_name)) {
+                if spec.has_flag("write") {
+                    self.db.invalidate(key);
+                } else if self.ctx.is_tracking() && spec.has_flag("readonly") {
+                    self.db.track_key(self.ctx.id(), key);
+                }
+            }
+
+            // 阻塞式命令的执行时间主要是等待，同样不计入延迟监控。
+            if !blocking {
+                let fast = CommandSpec::lookup(&cmd_name).is_some_and(|spec| spec.has_flag("fast"));
+                let event = if fast {
+                    latency::FAST_COMMAND
+                } else {
+                    latency::COMMAND
+                };
+                self.db.latency().record(event, duration);
+            }
+
+            if !blocking && self.db.slowlog().is_slow(duration) {
+                warn!(
+                    cmd = %cmd_name,
+                    key = key.as_deref().unwrap_or(""),
+                    duration_us = duration.as_micros() as u64,
+                    "慢命令"
+                );
+                let mut args = vec![Bytes::from(cmd_name.clone())];
+                args.extend(key.map(Bytes::from));
+                self.db.slowlog().push(duration, args);
+            }
+            self.metrics
+                .commands_processed
+                .fetch_add(1, Ordering::Relaxed);
+            debug!(
+                parent: &span,
+                duration_us = duration.as_micros() as u64,
+                "命令执行完成"
+            );
         }
         // 如果执行到此，说明收到了关闭信号，正常退出循环，返回`Ok`。
         Ok(())
     }
+
+    /// 集群模式下，如果命令操作的 key 不应该在这个节点执行，返回重定向的错误信息。
+    fn redirect(&self, cmd: &Command, asking: bool) -> Option<String> {
+        let cluster = self.db.cluster()?;
+        let key = cmd.get_key()?;
+        let slot = cluster::key_slot(key.as_bytes());
+        match cluster.route(slot, asking, || self.db.value_type(key).is_some()) {
+            Route::Local => None,
+            Route::Moved(addr) => Some(format!("MOVED {} {}", slot, addr)),
+            Route::Ask(addr) => Some(format!("ASK {} {}", slot, addr)),
+            Route::Down => Some("CLUSTERDOWN Hash slot not served".to_string()),
+        }
+    }
+}
+
+/// 等待`idle`时间后完成，`None`表示永远不会完成。
+async fn idle_timeout(idle: Option<Duration>) {
+    match idle {
+        Some(idle) => time::sleep(idle).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// 获取帧中的命令名称对应的`CommandSpec`，用于在解析命令之前判断命令的类型。
+fn frame_spec(frame: &Frame) -> Option<&'static CommandSpec> {
+    let Frame::Array(items) = frame else {
+        return None;
+    };
+    let name = match items.first()? {
+        Frame::Bulk(name) => std::str::from_utf8(name).ok()?,
+        Frame::Simple(name) => name,
+        _ => return None,
+    };
+    CommandSpec::lookup(&name.to_lowercase())
+}
+
+impl Drop for Handler {
+    fn drop(&mut self) {
+        self.db.clients().unregister(self.ctx.id());
+        // 连接关闭后不再需要发送失效信息。
+        if self.ctx.is_tracking() {
+            self.db.set_tracking(self.ctx.id(), false);
+        }
+    }
 }
@@ -0,0 +1,590 @@
+//! 服务器的启动配置。
+//!
+//! 配置可以来自三个地方，后面的覆盖前面的：
+//!
+//! 1. 配置文件，见`Config::load_file()`；
+//! 2. `MY_REDIS_`开头的环境变量，见`Config::load_env()`；
+//! 3. 通过构建方法设置的值，`my-redis-server`将命令行参数设置在这里。
+//!
+//! 配置文件与环境变量中的配置项名称与`my-redis-server`的命令行参数相同，例如`port`、`bind`、`log-file`。
+//! 运行期间可以修改的配置见`Config Set`。
+
+use std::{
+    io,
+    net::SocketAddr,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use tokio::net::{lookup_host, TcpListener};
+use tracing::{info, level_filters::LevelFilter};
+use tracing_subscriber::EnvFilter;
+
+use crate::{
+    cluster::{self, Cluster},
+    server::{Server, DEFAULT_DB_SHARDS},
+    tls::Tls,
+    FlushPolicy, FrameLimits, DEFAULT_PORT,
+};
+
+/// 环境变量的前缀，配置项`log-file`对应环境变量`MY_REDIS_LOG_FILE`。
+pub const ENV_PREFIX: &str = "MY_REDIS_";
+
+/// 支持的配置项，`Config::load_env()`按照这个列表读取环境变量。
+const KEYS: &[&str] = &[
+    "port",
+    "bind",
+    "log-level",
+    "log-format",
+    "log-file",
+    "log-filter",
+    "log-spans",
+    "daemonize",
+    "pidfile",
+    "dbfilename",
+    "aclfile",
+    "db-shards",
+    "maxclients",
+    "read-timeout-ms",
+    "write-timeout-ms",
+    "proto-max-bulk-len",
+    "proto-max-array-len",
+    "proto-max-depth",
+    "flush-policy",
+    "drain-timeout-ms",
+    "read-optimized",
+    "cluster-node",
+    "tls-cert-file",
+    "tls-key-file",
+    "tls-ca-cert-file",
+    "tls-cn-as-user",
+];
+
+/// 日志格式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// 便于阅读的文本格式。
+    #[default]
+    Text,
+    /// 每行一个 JSON 对象，便于日志收集系统处理。
+    Json,
+}
+
+/// 服务器的启动配置，通过`Server::from_config()`创建服务器。
+///
+/// # Examples
+///
+/// 配置文件的每一行为`<配置项> <值>`，空行和`#`开头的行会被忽略：
+///
+/// ```text
+/// port 6380
+/// bind 0.0.0.0 ::1
+/// maxclients 1000
+/// dbfilename dump.rdb
+/// ```
+///
+/// ```no_run
+/// use my_redis::server::{Config, Server};
+///
+/// # async fn dox() -> my_redis::Result<()> {
+/// let config = Config::new()
+///     .load_file("my-redis.conf")?
+///     .load_env()?
+///     .port(6381);
+/// let server = Server::from_config(config).await?;
+/// server.run(tokio::signal::ctrl_c()).await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Config {
+    port: u16,
+    binds: Vec<String>,
+
+    log_level: LevelFilter,
+    log_format: LogFormat,
+    log_file: Option<PathBuf>,
+    log_filter: Option<String>,
+    log_spans: bool,
+    daemonize: bool,
+    pidfile: Option<PathBuf>,
+
+    dbfilename: Option<PathBuf>,
+    aclfile: Option<PathBuf>,
+    db_shards: usize,
+    maxclients: Option<usize>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    frame_limits: FrameLimits,
+    flush_policy: FlushPolicy,
+    drain_timeout: Option<Duration>,
+    read_optimized: bool,
+    cluster_nodes: Vec<(String, RangeInclusive<u16>)>,
+
+    tls_cert_file: Option<PathBuf>,
+    tls_key_file: Option<PathBuf>,
+    tls_ca_cert_file: Option<PathBuf>,
+    tls_cn_as_user: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config::new()
+    }
+}
+
+impl Config {
+    /// 创建默认配置：侦听`127.0.0.1:6379`，不限制读写时间，不开启 TLS、集群和持久化。
+    pub fn new() -> Config {
+        Config {
+            port: DEFAULT_PORT,
+            binds: vec!["127.0.0.1".to_string()],
+            log_level: LevelFilter::INFO,
+            log_format: LogFormat::Text,
+            log_file: None,
+            log_filter: None,
+            log_spans: false,
+            daemonize: false,
+            pidfile: None,
+            dbfilename: None,
+            aclfile: None,
+            db_shards: DEFAULT_DB_SHARDS,
+            maxclients: None,
+            read_timeout: None,
+            write_timeout: None,
+            frame_limits: FrameLimits::default(),
+            flush_policy: FlushPolicy::Batch,
+            drain_timeout: None,
+            read_optimized: false,
+            cluster_nodes: vec![],
+            tls_cert_file: None,
+            tls_key_file: None,
+            tls_ca_cert_file: None,
+            tls_cn_as_user: false,
+        }
+    }
+
+    /// 从配置文件`path`读取配置，覆盖已有的值。
+    ///
+    /// 文件的每一行为`<配置项> <值>`，空行和`#`开头的行会被忽略，
+    /// `bind`和`cluster-node`可以有多个以空格分隔的值，布尔值为`yes`或`no`。
+    ///
+    /// # Errors
+    /// 读取文件失败，或者有未知的配置项、不合法的值时返回`Err`。
+    pub fn load_file(mut self, path: impl AsRef<Path>) -> crate::Result<Config> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| format!("无法读取配置文件 {}：{}", path.display(), err))?;
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            self.set(key, value.trim())
+                .map_err(|err| format!("配置文件 {} 第 {} 行：{}", path.display(), i + 1, err))?;
+        }
+        Ok(self)
+    }
+
+    /// 从`MY_REDIS_`开头的环境变量读取配置，覆盖已有的值。
+    ///
+    /// 配置项名称转为大写、`-`换为`_`后加上前缀即为环境变量的名称，
+    /// 例如`MY_REDIS_PORT=6380`、`MY_REDIS_BIND="0.0.0.0 ::1"`。
+    ///
+    /// # Errors
+    /// 环境变量的值不合法时返回`Err`。
+    pub fn load_env(mut self) -> crate::Result<Config> {
+        for key in KEYS {
+            let name = format!("{}{}", ENV_PREFIX, key.to_uppercase().replace('-', "_"));
+            if let Ok(value) = std::env::var(&name) {
+                self.set(key, value.trim())
+                    .map_err(|err| format!("环境变量 {}：{}", name, err))?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// 以配置文件中的格式设置一个配置项。
+    ///
+    /// # Errors
+    /// 配置项不存在或者值不合法时返回`Err`。
+    pub fn set(&mut self, key: &str, value: &str) -> crate::Result<()> {
+        match key.to_ascii_lowercase().as_str() {
+            "port" => self.port = parse(key, value)?,
+            "bind" => {
+                let binds: Vec<String> = value.split_whitespace().map(String::from).collect();
+                if binds.is_empty() {
+                    return Err(format!("{} 至少需要一个地址", key).into());
+                }
+                self.binds = binds;
+            }
+            "log-level" => self.log_level = parse(key, value)?,
+            "log-format" => {
+                self.log_format = match value.to_ascii_lowercase().as_str() {
+                    "text" => LogFormat::Text,
+                    "json" => LogFormat::Json,
+                    _ => return Err(invalid(key, value)),
+                }
+            }
+            "log-file" | "logfile" => self.log_file = parse_path(value),
+            "log-filter" => {
+                EnvFilter::try_new(value).map_err(|err| format!("{} 不合法：{}", key, err))?;
+                self.log_filter = (!value.is_empty()).then(|| value.to_string());
+            }
+            "log-spans" => self.log_spans = parse_bool(key, value)?,
+            "daemonize" => self.daemonize = parse_bool(key, value)?,
+            "pidfile" => self.pidfile = parse_path(value),
+            "dbfilename" => self.dbfilename = parse_path(value),
+            "aclfile" => self.aclfile = parse_path(value),
+            "db-shards" => self.db_shards = parse(key, value)?,
+            "maxclients" => self.maxclients = Some(parse(key, value)?),
+            "read-timeout-ms" => self.read_timeout = parse_millis(key, value)?,
+            "write-timeout-ms" => self.write_timeout = parse_millis(key, value)?,
+            "proto-max-bulk-len" => self.frame_limits.max_bulk_len = parse(key, value)?,
+            "proto-max-array-len" => self.frame_limits.max_array_len = parse(key, value)?,
+            "proto-max-depth" => self.frame_limits.max_depth = parse(key, value)?,
+            "flush-policy" => {
+                self.flush_policy = match value.to_ascii_lowercase().as_str() {
+                    "immediate" => FlushPolicy::Immediate,
+                    "batch" => FlushPolicy::Batch,
+                    _ => return Err(invalid(key, value)),
+                }
+            }
+            "drain-timeout-ms" => self.drain_timeout = parse_millis(key, value)?,
+            "read-optimized" => self.read_optimized = parse_bool(key, value)?,
+            "cluster-node" => {
+                self.cluster_nodes = value
+                    .split_whitespace()
+                    .map(cluster::parse_node)
+                    .collect::<Result<_, _>>()
+                    .map_err(|err| format!("{} 不合法：{}", key, err))?;
+            }
+            "tls-cert-file" => self.tls_cert_file = parse_path(value),
+            "tls-key-file" => self.tls_key_file = parse_path(value),
+            "tls-ca-cert-file" => self.tls_ca_cert_file = parse_path(value),
+            "tls-cn-as-user" => self.tls_cn_as_user = parse_bool(key, value)?,
+            _ => return Err(format!("未知的配置项 {}", key).into()),
+        }
+        Ok(())
+    }
+
+    /// 设置侦听的端口，默认为`6379`。
+    pub fn port(mut self, port: u16) -> Config {
+        self.port = port;
+        self
+    }
+
+    /// 设置侦听的地址，可以是 IP 或者主机名，替换之前设置的所有地址，默认为`127.0.0.1`。
+    ///
+    /// 主机名解析出的所有地址都会被侦听，IPv6 地址可以带方括号，例如`[::1]`。
+    pub fn bind<S: ToString>(mut self, addrs: impl IntoIterator<Item = S>) -> Config {
+        self.binds = addrs.into_iter().map(|addr| addr.to_string()).collect();
+        self
+    }
+
+    /// 设置日志级别，默认为`INFO`。
+    pub fn log_level(mut self, level: LevelFilter) -> Config {
+        self.log_level = level;
+        self
+    }
+
+    /// 设置日志格式，默认为`LogFormat::Text`。
+    pub fn log_format(mut self, format: LogFormat) -> Config {
+        self.log_format = format;
+        self
+    }
+
+    /// 将日志写入文件`path`并按天轮转，默认输出到标准输出。
+    pub fn log_file(mut self, path: impl Into<PathBuf>) -> Config {
+        self.log_file = Some(path.into());
+        self
+    }
+
+    /// 按照模块过滤日志，格式与`RUST_LOG`相同，例如`my_redis::server=debug,my_redis::db=trace`。
+    ///
+    /// 没有匹配的模块使用`log_level()`设置的级别。
+    ///
+    /// # Errors
+    /// `directives`不合法时返回`Err`。
+    pub fn log_filter(mut self, directives: impl Into<String>) -> crate::Result<Config> {
+        self.set("log-filter", &directives.into())?;
+        Ok(self)
+    }
+
+    /// 设置是否在连接、命令等 span 结束时输出一条日志，包含持续的时间。
+    pub fn log_spans(mut self, enabled: bool) -> Config {
+        self.log_spans = enabled;
+        self
+    }
+
+    /// 设置是否以守护进程的方式在后台运行。
+    pub fn daemonize(mut self, daemonize: bool) -> Config {
+        self.daemonize = daemonize;
+        self
+    }
+
+    /// 启动时将进程 ID 写入文件`path`。
+    pub fn pidfile(mut self, path: impl Into<PathBuf>) -> Config {
+        self.pidfile = Some(path.into());
+        self
+    }
+
+    /// 启动时从快照文件`path`恢复数据，见`Server::load_snapshot()`。
+    pub fn dbfilename(mut self, path: impl Into<PathBuf>) -> Config {
+        self.dbfilename = Some(path.into());
+        self
+    }
+
+    /// 启动时从 ACL 文件`path`加载用户，见`Server::acl_file()`。
+    pub fn aclfile(mut self, path: impl Into<PathBuf>) -> Config {
+        self.aclfile = Some(path.into());
+        self
+    }
+
+    /// 设置数据库的分片数，见`Server::with_db_shards()`。
+    pub fn db_shards(mut self, shards: usize) -> Config {
+        self.db_shards = shards;
+        self
+    }
+
+    /// 设置最大连接数，见`Server::max_connections()`。
+    pub fn maxclients(mut self, max: usize) -> Config {
+        self.maxclients = Some(max);
+        self
+    }
+
+    /// 设置读取一个命令的超时时间，见`Server::read_timeout()`。
+    pub fn read_timeout(mut self, timeout: Duration) -> Config {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// 设置写入一个响应的超时时间，见`Server::write_timeout()`。
+    pub fn write_timeout(mut self, timeout: Duration) -> Config {
+        self.write_timeout = Some(timeout);
+        self
+    }
+
+    /// 设置解析客户端发送的`Frame`时的限制，见`Server::frame_limits()`。
+    pub fn frame_limits(mut self, limits: FrameLimits) -> Config {
+        self.frame_limits = limits;
+        self
+    }
+
+    /// 设置写入响应后何时刷新，见`Server::flush_policy()`。
+    pub fn flush_policy(mut self, policy: FlushPolicy) -> Config {
+        self.flush_policy = policy;
+        self
+    }
+
+    /// 设置收到关闭信号后等待连接处理完成的最长时间，见`Server::drain_timeout()`。
+    pub fn drain_timeout(mut self, timeout: Duration) -> Config {
+        self.drain_timeout = Some(timeout);
+        self
+    }
+
+    /// 设置是否开启读优化模式，见`Server::read_optimized()`。
+    pub fn read_optimized(mut self, enabled: bool) -> Config {
+        self.read_optimized = enabled;
+        self
+    }
+
+    /// 设置集群中的节点及其负责的槽，替换之前设置的所有节点，不为空时开启集群模式。
+    pub fn cluster_nodes(mut self, nodes: Vec<(String, RangeInclusive<u16>)>) -> Config {
+        self.cluster_nodes = nodes;
+        self
+    }
+
+    /// 开启 TLS，`cert`和`key`分别为 PEM 格式的证书链和私钥。
+    pub fn tls(mut self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Config {
+        self.tls_cert_file = Some(cert.into());
+        self.tls_key_file = Some(key.into());
+        self
+    }
+
+    /// 要求客户端提供由`path`中的 CA 签发的证书，见`Tls::client_ca()`。
+    pub fn tls_ca_cert_file(mut self, path: impl Into<PathBuf>) -> Config {
+        self.tls_ca_cert_file = Some(path.into());
+        self
+    }
+
+    /// 设置是否将客户端证书的 CN 作为连接的用户，见`Tls::cn_as_user()`。
+    pub fn tls_cn_as_user(mut self, enabled: bool) -> Config {
+        self.tls_cn_as_user = enabled;
+        self
+    }
+
+    /// 获取日志级别。
+    pub fn get_log_level(&self) -> LevelFilter {
+        self.log_level
+    }
+
+    /// 获取日志格式。
+    pub fn get_log_format(&self) -> LogFormat {
+        self.log_format
+    }
+
+    /// 获取日志文件，`None`表示输出到标准输出。
+    pub fn get_log_file(&self) -> Option<&Path> {
+        self.log_file.as_deref()
+    }
+
+    /// 获取解析客户端发送的`Frame`时的限制。
+    pub fn get_frame_limits(&self) -> FrameLimits {
+        self.frame_limits
+    }
+
+    /// 获取按照模块过滤日志的规则。
+    pub fn get_log_filter(&self) -> Option<&str> {
+        self.log_filter.as_deref()
+    }
+
+    /// 是否在 span 结束时输出日志。
+    pub fn is_log_spans(&self) -> bool {
+        self.log_spans
+    }
+
+    /// 是否以守护进程的方式运行。
+    pub fn is_daemonize(&self) -> bool {
+        self.daemonize
+    }
+
+    /// 获取 PID 文件。
+    pub fn get_pidfile(&self) -> Option<&Path> {
+        self.pidfile.as_deref()
+    }
+
+    /// 侦听所有地址，按照配置创建服务器。
+    pub(super) async fn build(self) -> crate::Result<Server> {
+        let tls = match (self.tls_cert_file, self.tls_key_file) {
+            (Some(cert), Some(key)) => {
+                let mut tls = Tls::new(cert, key).cn_as_user(self.tls_cn_as_user);
+                if let Some(path) = self.tls_ca_cert_file {
+                    tls = tls.client_ca(path);
+                }
+                Some(tls)
+            }
+            (None, None) if self.tls_ca_cert_file.is_none() && !self.tls_cn_as_user => None,
+            _ => return Err("开启 TLS 需要同时设置 tls-cert-file 和 tls-key-file".into()),
+        };
+
+        let mut listeners = bind_listeners(&self.binds, self.port).await?.into_iter();
+        let first = listeners.next().expect("至少侦听一个地址");
+        // 集群中当前节点的地址，侦听所有地址时使用本地回环地址。
+        let node_addr = match first.local_addr() {
+            Ok(addr) if !addr.ip().is_unspecified() => addr.to_string(),
+            _ => format!("127.0.0.1:{}", self.port),
+        };
+        let mut server = listeners.fold(
+            Server::with_db_shards(first, self.db_shards),
+            Server::listener,
+        );
+
+        if let Some(max) = self.maxclients {
+            server = server.max_connections(max);
+        }
+        if let Some(timeout) = self.read_timeout {
+            server = server.read_timeout(timeout);
+        }
+        if let Some(timeout) = self.write_timeout {
+            server = server.write_timeout(timeout);
+        }
+        server = server
+            .frame_limits(self.frame_limits)
+            .flush_policy(self.flush_policy);
+        if let Some(timeout) = self.drain_timeout {
+            server = server.drain_timeout(timeout);
+        }
+        if self.read_optimized {
+            server = server.read_optimized();
+        }
+        if let Some(tls) = tls {
+            server = server.tls(tls)?;
+        }
+        if let Some(path) = self.aclfile {
+            server = server.acl_file(path)?;
+        }
+        if !self.cluster_nodes.is_empty() {
+            let cluster = self
+                .cluster_nodes
+                .into_iter()
+                .fold(Cluster::new(node_addr), |cluster, (addr, slots)| {
+                    cluster.node(addr, slots)
+                });
+            server = server.cluster(cluster);
+        }
+        // 在接受连接之前恢复数据，快照文件损坏时拒绝启动，避免之后的保存覆盖它。
+        if let Some(path) = self.dbfilename {
+            server = server.load_snapshot(path).await?;
+        }
+        Ok(server)
+    }
+}
+
+/// 解析`bind`指定的地址，侦听每一个解析出的地址。
+///
+/// 同一个地址只侦听一次，任意一个地址侦听失败都返回`Err`。
+async fn bind_listeners(hosts: &[String], port: u16) -> io::Result<Vec<TcpListener>> {
+    let mut addrs: Vec<SocketAddr> = Vec::new();
+    for host in hosts {
+        // IPv6 地址可以带方括号，例如`[::1]`。
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let resolved = lookup_host((host, port))
+            .await
+            .map_err(|err| io::Error::new(err.kind(), format!("无法解析地址 {}：{}", host, err)))?;
+        for addr in resolved {
+            if !addrs.contains(&addr) {
+                addrs.push(addr);
+            }
+        }
+    }
+    if addrs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "没有可以侦听的地址",
+        ));
+    }
+
+    let mut listeners = Vec::with_capacity(addrs.len());
+    for addr in addrs {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|err| io::Error::new(err.kind(), format!("无法侦听 {}：{}", addr, err)))?;
+        info!(%addr, "开始侦听");
+        listeners.push(listener);
+    }
+    Ok(listeners)
+}
+
+fn invalid(key: &str, value: &str) -> crate::Error {
+    format!("{} 的值不合法：{:?}", key, value).into()
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> crate::Result<T> {
+    value.parse().map_err(|_| invalid(key, value))
+}
+
+/// 与 Redis 的配置文件相同，布尔值为`yes`或`no`，也接受`true`和`false`。
+fn parse_bool(key: &str, value: &str) -> crate::Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "yes" | "true" => Ok(true),
+        "no" | "false" => Ok(false),
+        _ => Err(invalid(key, value)),
+    }
+}
+
+/// 空值表示取消设置。
+fn parse_path(value: &str) -> Option<PathBuf> {
+    (!value.is_empty()).then(|| PathBuf::from(value))
+}
+
+/// 毫秒数，`0`表示不限制。
+fn parse_millis(key: &str, value: &str) -> crate::Result<Option<Duration>> {
+    match parse(key, value)? {
+        0 => Ok(None),
+        ms => Ok(Some(Duration::from_millis(ms))),
+    }
+}
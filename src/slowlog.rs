@@ -0,0 +1,130 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicI64, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+
+/// 默认的慢命令阈值，单位是微秒。
+const DEFAULT_THRESHOLD_US: i64 = 10_000;
+
+/// 默认最多保存的慢命令条数。
+const DEFAULT_MAX_LEN: usize = 128;
+
+/// 慢命令日志。
+///
+/// 执行时间超过阈值的命令会被记录在一个固定容量的环形缓冲区中，
+/// 缓冲区满了之后最旧的记录会被丢弃。
+#[derive(Debug)]
+pub(crate) struct SlowLog {
+    // 记录本身需要加锁，但是阈值在每条命令执行后都要读取，
+    // 所以使用原子变量，避免每条命令都要获取锁。
+    log: Mutex<Log>,
+
+    // 慢命令阈值，单位是微秒。负数表示不记录，`0`表示记录所有命令。
+    threshold_us: AtomicI64,
+
+    // 最多保存的记录条数。
+    max_len: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Log {
+    // 新的记录在前面。
+    entries: VecDeque<SlowLogEntry>,
+    // 下一条记录的编号，单调递增。
+    next_id: u64,
+}
+
+/// 一条慢命令记录。
+#[derive(Debug, Clone)]
+pub(crate) struct SlowLogEntry {
+    // 记录编号。
+    pub(crate) id: u64,
+    // 记录时的 UNIX 时间戳，单位是秒。
+    pub(crate) timestamp: u64,
+    // 命令执行的时间。
+    pub(crate) duration: Duration,
+    // 命令名称以及参数。
+    pub(crate) args: Vec<Bytes>,
+}
+
+impl SlowLog {
+    pub(crate) fn new() -> SlowLog {
+        SlowLog {
+            log: Mutex::new(Log {
+                entries: VecDeque::new(),
+                next_id: 0,
+            }),
+            threshold_us: AtomicI64::new(DEFAULT_THRESHOLD_US),
+            max_len: AtomicUsize::new(DEFAULT_MAX_LEN),
+        }
+    }
+
+    /// 获取慢命令阈值，单位是微秒。
+    pub(crate) fn threshold_us(&self) -> i64 {
+        self.threshold_us.load(Ordering::Relaxed)
+    }
+
+    /// 设置慢命令阈值，单位是微秒。
+    pub(crate) fn set_threshold_us(&self, threshold: i64) {
+        self.threshold_us.store(threshold, Ordering::Relaxed);
+    }
+
+    /// 获取最多保存的记录条数。
+    pub(crate) fn max_len(&self) -> usize {
+        self.max_len.load(Ordering::Relaxed)
+    }
+
+    /// 设置最多保存的记录条数，多余的旧记录会被立即丢弃。
+    pub(crate) fn set_max_len(&self, max_len: usize) {
+        self.max_len.store(max_len, Ordering::Relaxed);
+        self.log.lock().unwrap().entries.truncate(max_len);
+    }
+
+    /// 判断执行时间为`duration`的命令是否是慢命令。
+    pub(crate) fn is_slow(&self, duration: Duration) -> bool {
+        let threshold = self.threshold_us();
+        threshold >= 0 && duration.as_micros() >= threshold as u128
+    }
+
+    /// 添加一条记录。
+    pub(crate) fn push(&self, duration: Duration, args: Vec<Bytes>) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_secs())
+            .unwrap_or(0);
+        let max_len = self.max_len();
+
+        let mut log = self.log.lock().unwrap();
+        let id = log.next_id;
+        log.next_id += 1;
+        log.entries.push_front(SlowLogEntry {
+            id,
+            timestamp,
+            duration,
+            args,
+        });
+        log.entries.truncate(max_len);
+    }
+
+    /// 获取最新的`count`条记录，新的记录在前面。
+    pub(crate) fn get(&self, count: usize) -> Vec<SlowLogEntry> {
+        let log = self.log.lock().unwrap();
+        log.entries.iter().take(count).cloned().collect()
+    }
+
+    /// 获取当前保存的记录条数。
+    pub(crate) fn len(&self) -> usize {
+        self.log.lock().unwrap().entries.len()
+    }
+
+    /// 清空所有记录。
+    pub(crate) fn reset(&self) {
+        self.log.lock().unwrap().entries.clear();
+    }
+}
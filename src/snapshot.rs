@@ -0,0 +1,457 @@
+//! 数据库快照文件的编码与解码。
+//!
+//! 文件格式：
+//!
+//! ```text
+//! MYREDIS <version: u8>
+//! <type: u8> <expires_at: u64> <key-len: u32> <key> <value>
+//! ...
+//! <EOF: 0xFF>
+//! ```
+//!
+//! 其中`value`的格式由类型决定：
+//!
+//! ```text
+//! 字符串：<len: u32> <data>
+//! 哈希表：<count: u32> (<field-len: u32> <field> <value-len: u32> <value>)...
+//! 列表：<count: u32> (<len: u32> <element>)...
+//! 集合：<count: u32> (<len: u32> <member>)...
+//! 有序集合：<count: u32> (<len: u32> <member> <score: f64>)...
+//! 流：<last-ms: u64> <last-seq: u64> <count: u32>
+//!     (<ms: u64> <seq: u64> <field-count: u32> (<field-len: u32> <field> <value-len: u32> <value>)...)...
+//!     <group-count: u32> (<name-len: u32> <name> <last-delivered-ms: u64> <last-delivered-seq: u64>
+//!     <pending-count: u32> (<ms: u64> <seq: u64> <consumer-len: u32> <consumer>
+//!     <delivered-at: u64> <delivery-count: u64>)...)...
+//! ```
+//!
+//! 所有整数都是大端字节序，`expires_at`是过期时刻距离 UNIX 纪元的毫秒数，
+//! 为 0 表示没有过期时间。
+//!
+//! `Dump`命令序列化单个 value，格式与快照中的 value 相同，并在末尾加上版本号和校验和：
+//!
+//! ```text
+//! <type: u8> <value> <version: u8> <crc64: u64>
+//! ```
+//!
+//! 校验和使用 CRC-64/Jones 计算，覆盖校验和之前的所有字节。
+
+use std::{
+    collections::VecDeque,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::{Buf, BufMut, Bytes};
+use tokio::fs;
+
+use crate::{
+    stream::{ConsumerGroup, PendingEntry, Stream, StreamId},
+    zset::{Score, SortedSet},
+    Value,
+};
+
+/// 文件头。
+const MAGIC: &[u8] = b"MYREDIS";
+
+/// 文件格式的版本号。
+const VERSION: u8 = 1;
+
+/// 字符串类型的 value。
+const TYPE_STRING: u8 = 0;
+
+/// 哈希表类型的 value。
+const TYPE_HASH: u8 = 1;
+
+/// 列表类型的 value。
+const TYPE_LIST: u8 = 2;
+
+/// 集合类型的 value。
+const TYPE_SET: u8 = 3;
+
+/// 有序集合类型的 value。
+const TYPE_ZSET: u8 = 4;
+
+/// 流类型的 value。
+const TYPE_STREAM: u8 = 5;
+
+/// 文件结束标志。
+const EOF: u8 = 0xFF;
+
+/// 快照中的一个 key-value。
+#[derive(Debug, Clone)]
+pub(crate) struct SnapshotEntry {
+    pub(crate) key: String,
+    pub(crate) value: Value,
+    // 过期时刻。快照需要跨进程使用，所以使用系统时间而不是`Instant`。
+    pub(crate) expires_at: Option<SystemTime>,
+}
+
+/// 将快照编码为字节。
+pub(crate) fn encode(entries: &[SnapshotEntry]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.put_slice(MAGIC);
+    buf.put_u8(VERSION);
+
+    for entry in entries {
+        let expires_at = entry
+            .expires_at
+            .map(|when| {
+                // 过期时刻不可能早于 UNIX 纪元，最小记为 1 毫秒，避免与“没有过期时间”混淆。
+                let ms = when
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+                (ms as u64).max(1)
+            })
+            .unwrap_or(0);
+
+        buf.put_u8(value_type(&entry.value));
+        buf.put_u64(expires_at);
+        put_chunk(&mut buf, entry.key.as_bytes());
+        put_value(&mut buf, &entry.value);
+    }
+
+    buf.put_u8(EOF);
+    buf
+}
+
+/// 从字节中解码快照。
+///
+/// # Errors
+/// 如果文件头不正确、数据被截断或者格式不合法，返回`Err`。
+pub(crate) fn decode(mut src: &[u8]) -> crate::Result<Vec<SnapshotEntry>> {
+    if src.len() < MAGIC.len() + 1 || &src[..MAGIC.len()] != MAGIC {
+        return Err("不合法的快照文件".into());
+    }
+    src.advance(MAGIC.len());
+
+    let version = src.get_u8();
+    if version != VERSION {
+        return Err(format!("不支持的快照版本：{}", version).into());
+    }
+
+    let mut entries = Vec::new();
+    loop {
+        if !src.has_remaining() {
+            return Err("快照文件不完整".into());
+        }
+        let ty = match src.get_u8() {
+            EOF => break,
+            ty @ (TYPE_STRING | TYPE_HASH | TYPE_LIST | TYPE_SET | TYPE_ZSET | TYPE_STREAM) => ty,
+            ty => return Err(format!("快照中存在未知的类型：{}", ty).into()),
+        };
+
+        if src.remaining() < 8 {
+            return Err("快照文件不完整".into());
+        }
+        let expires_at = match src.get_u64() {
+            0 => None,
+            ms => Some(UNIX_EPOCH + Duration::from_millis(ms)),
+        };
+        let key = String::from_utf8(read_chunk(&mut src)?.to_vec())
+            .map_err(|_| "快照中的 key 不是合法的 UTF-8 字符串")?;
+
+        let value = read_value(&mut src, ty)?;
+        entries.push(SnapshotEntry {
+            key,
+            value,
+            expires_at,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// 将快照写入文件。
+///
+/// 先写入同目录下的临时文件再重命名，这样文件中的快照要么是完整的旧快照，
+/// 要么是完整的新快照。
+pub(crate) async fn write_file(path: &Path, entries: &[SnapshotEntry]) -> crate::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    fs::write(&tmp, encode(entries)).await?;
+    fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+/// 从文件中读取快照。
+pub(crate) async fn read_file(path: &Path) -> crate::Result<Vec<SnapshotEntry>> {
+    let data = fs::read(path).await?;
+    decode(&data)
+}
+
+/// 序列化单个 value，用于`Dump`命令。
+pub(crate) fn dump_value(value: &Value) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.put_u8(value_type(value));
+    put_value(&mut buf, value);
+    buf.put_u8(VERSION);
+    let checksum = crc64(&buf);
+    buf.put_u64(checksum);
+    buf
+}
+
+/// 反序列化`dump_value()`生成的数据，用于`Restore`命令。
+///
+/// # Errors
+/// 如果版本号或者校验和不正确，或者数据格式不合法，返回`Err`。
+pub(crate) fn restore_value(payload: &[u8]) -> crate::Result<Value> {
+    let invalid = || "ERR DUMP payload version or checksum are wrong".into();
+    if payload.len() < 1 + 1 + 8 {
+        return Err(invalid());
+    }
+    let (data, mut checksum) = payload.split_at(payload.len() - 8);
+    if data[data.len() - 1] != VERSION || crc64(data) != checksum.get_u64() {
+        return Err(invalid());
+    }
+
+    let mut src = &data[..data.len() - 1];
+    let ty = match src.get_u8() {
+        ty @ (TYPE_STRING | TYPE_HASH | TYPE_LIST | TYPE_SET | TYPE_ZSET | TYPE_STREAM) => ty,
+        _ => return Err("ERR Bad data format".into()),
+    };
+    let value = read_value(&mut src, ty).map_err(|_| "ERR Bad data format")?;
+    if src.has_remaining() {
+        return Err("ERR Bad data format".into());
+    }
+    Ok(value)
+}
+
+/// 快照中 value 的类型标志。
+fn value_type(value: &Value) -> u8 {
+    match value {
+        Value::String(_) => TYPE_STRING,
+        Value::Hash(_) => TYPE_HASH,
+        Value::List(_) => TYPE_LIST,
+        Value::Set(_) => TYPE_SET,
+        Value::ZSet(_) => TYPE_ZSET,
+        Value::Stream(_) => TYPE_STREAM,
+    }
+}
+
+/// 按照类型写入 value，格式见模块文档。
+fn put_value(buf: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::String(data) => put_chunk(buf, data),
+        Value::Hash(hash) => {
+            buf.put_u32(hash.len() as u32);
+            for (field, value) in hash {
+                put_chunk(buf, field);
+                put_chunk(buf, value);
+            }
+        }
+        Value::List(list) => {
+            buf.put_u32(list.len() as u32);
+            for element in list {
+                put_chunk(buf, element);
+            }
+        }
+        Value::Set(set) => {
+            buf.put_u32(set.len() as u32);
+            for member in set {
+                put_chunk(buf, member);
+            }
+        }
+        Value::ZSet(zset) => {
+            buf.put_u32(zset.len() as u32);
+            for (member, score) in zset.iter() {
+                put_chunk(buf, member);
+                buf.put_f64(score);
+            }
+        }
+        Value::Stream(stream) => {
+            put_stream_id(buf, stream.last_id());
+            buf.put_u32(stream.len() as u32);
+            for (id, fields) in stream.iter() {
+                put_stream_id(buf, *id);
+                buf.put_u32(fields.len() as u32);
+                for (field, value) in fields {
+                    put_chunk(buf, field);
+                    put_chunk(buf, value);
+                }
+            }
+            let groups: Vec<_> = stream.groups().collect();
+            buf.put_u32(groups.len() as u32);
+            for (name, group) in groups {
+                put_chunk(buf, name.as_bytes());
+                put_stream_id(buf, group.last_delivered);
+                buf.put_u32(group.pending.len() as u32);
+                for (id, pending) in &group.pending {
+                    put_stream_id(buf, *id);
+                    put_chunk(buf, pending.consumer.as_bytes());
+                    buf.put_u64(pending.delivered_at);
+                    buf.put_u64(pending.delivery_count);
+                }
+            }
+        }
+    }
+}
+
+/// 读取类型为`ty`的 value，`ty`必须是合法的类型标志。
+fn read_value(src: &mut &[u8], ty: u8) -> crate::Result<Value> {
+    let value = match ty {
+        TYPE_STRING => Value::String(Bytes::copy_from_slice(read_chunk(src)?)),
+        TYPE_HASH => {
+            if src.remaining() < 4 {
+                return Err("快照文件不完整".into());
+            }
+            let mut hash = im::HashMap::new();
+            for _ in 0..src.get_u32() {
+                let field = Bytes::copy_from_slice(read_chunk(src)?);
+                let value = Bytes::copy_from_slice(read_chunk(src)?);
+                hash.insert(field, value);
+            }
+            Value::Hash(hash)
+        }
+        TYPE_LIST => {
+            if src.remaining() < 4 {
+                return Err("快照文件不完整".into());
+            }
+            let mut list = VecDeque::new();
+            for _ in 0..src.get_u32() {
+                list.push_back(Bytes::copy_from_slice(read_chunk(src)?));
+            }
+            Value::List(list)
+        }
+        TYPE_SET => {
+            if src.remaining() < 4 {
+                return Err("快照文件不完整".into());
+            }
+            let mut set = im::HashSet::new();
+            for _ in 0..src.get_u32() {
+                set.insert(Bytes::copy_from_slice(read_chunk(src)?));
+            }
+            Value::Set(set)
+        }
+        TYPE_ZSET => {
+            if src.remaining() < 4 {
+                return Err("快照文件不完整".into());
+            }
+            let mut zset = SortedSet::new();
+            for _ in 0..src.get_u32() {
+                let member = Bytes::copy_from_slice(read_chunk(src)?);
+                if src.remaining() < 8 {
+                    return Err("快照文件不完整".into());
+                }
+                let score = Score::new(src.get_f64()).ok_or("快照中的分数不合法")?;
+                zset.insert(member, score);
+            }
+            Value::ZSet(zset)
+        }
+        TYPE_STREAM => {
+            let last_id = read_stream_id(src)?;
+            if src.remaining() < 4 {
+                return Err("快照文件不完整".into());
+            }
+            let mut entries = im::OrdMap::new();
+            for _ in 0..src.get_u32() {
+                let id = read_stream_id(src)?;
+                if src.remaining() < 4 {
+                    return Err("快照文件不完整".into());
+                }
+                let mut fields = vec![];
+                for _ in 0..src.get_u32() {
+                    let field = Bytes::copy_from_slice(read_chunk(src)?);
+                    let value = Bytes::copy_from_slice(read_chunk(src)?);
+                    fields.push((field, value));
+                }
+                entries.insert(id, fields);
+            }
+            if src.remaining() < 4 {
+                return Err("快照文件不完整".into());
+            }
+            let mut groups = im::HashMap::new();
+            for _ in 0..src.get_u32() {
+                let name = read_string(src)?;
+                let last_delivered = read_stream_id(src)?;
+                if src.remaining() < 4 {
+                    return Err("快照文件不完整".into());
+                }
+                let mut pending = im::OrdMap::new();
+                for _ in 0..src.get_u32() {
+                    let id = read_stream_id(src)?;
+                    let consumer = read_string(src)?;
+                    if src.remaining() < 16 {
+                        return Err("快照文件不完整".into());
+                    }
+                    let entry = PendingEntry {
+                        consumer,
+                        delivered_at: src.get_u64(),
+                        delivery_count: src.get_u64(),
+                    };
+                    pending.insert(id, entry);
+                }
+                let group = ConsumerGroup {
+                    last_delivered,
+                    pending,
+                };
+                groups.insert(name, group);
+            }
+            Value::Stream(Stream::from_parts(entries, last_id, groups))
+        }
+        _ => unreachable!(),
+    };
+    Ok(value)
+}
+
+/// 计算 CRC-64/Jones 校验和，与 Redis 的`DUMP`使用的算法相同。
+fn crc64(data: &[u8]) -> u64 {
+    // Jones 多项式按位反转后的值。
+    const POLY: u64 = 0x95ac_9329_ac4b_c9b5;
+    let mut crc = 0u64;
+    for &byte in data {
+        crc ^= byte as u64;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ POLY
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+/// 写入一个以`u32`长度为前缀的数据块。
+fn put_chunk(buf: &mut Vec<u8>, chunk: &[u8]) {
+    buf.put_u32(chunk.len() as u32);
+    buf.put_slice(chunk);
+}
+
+/// 读取一个以`u32`长度为前缀的 UTF-8 字符串。
+fn read_string(src: &mut &[u8]) -> crate::Result<String> {
+    String::from_utf8(read_chunk(src)?.to_vec())
+        .map_err(|_| "快照中的字符串不是合法的 UTF-8 字符串".into())
+}
+
+/// 写入流中消息的 ID。
+fn put_stream_id(buf: &mut Vec<u8>, id: StreamId) {
+    buf.put_u64(id.ms);
+    buf.put_u64(id.seq);
+}
+
+/// 读取流中消息的 ID。
+fn read_stream_id(src: &mut &[u8]) -> crate::Result<StreamId> {
+    if src.remaining() < 16 {
+        return Err("快照文件不完整".into());
+    }
+    Ok(StreamId {
+        ms: src.get_u64(),
+        seq: src.get_u64(),
+    })
+}
+
+/// 读取一个以`u32`长度为前缀的数据块。
+fn read_chunk<'a>(src: &mut &'a [u8]) -> crate::Result<&'a [u8]> {
+    if src.remaining() < 4 {
+        return Err("快照文件不完整".into());
+    }
+    let len = src.get_u32() as usize;
+    if src.remaining() < len {
+        return Err("快照文件不完整".into());
+    }
+    let (chunk, rest) = src.split_at(len);
+    *src = rest;
+    Ok(chunk)
+}
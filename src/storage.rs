@@ -0,0 +1,81 @@
+//! 外部存储引擎的写入钩子。
+//!
+//! 嵌入 my-redis 的应用可以实现`StorageHook`，将数据库的修改同步到持久化存储中，
+//! 例如 sled、Postgres 或者 S3。通过`Server::storage_hook()`注册。
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// 数据库修改后被调用的钩子。
+///
+/// 钩子在后台任务中按照修改发生的顺序依次调用，不会阻塞命令的执行。
+/// 钩子返回的错误只会被记录到日志中，不会影响数据库。
+#[async_trait]
+pub trait StorageHook: Send + Sync + 'static {
+    /// 设置 key 之后调用，`expire`为过期时长。
+    ///
+    /// 目前只有字符串类型的 value 会调用此方法，其他类型只会通知删除和过期。
+    async fn on_set(
+        &self,
+        key: &str,
+        value: &Bytes,
+        expire: Option<Duration>,
+    ) -> crate::Result<()> {
+        let _ = (key, value, expire);
+        Ok(())
+    }
+
+    /// 删除 key 之后调用。
+    async fn on_delete(&self, key: &str) -> crate::Result<()> {
+        let _ = key;
+        Ok(())
+    }
+
+    /// key 过期被清除之后调用。
+    async fn on_expire(&self, key: &str) -> crate::Result<()> {
+        let _ = key;
+        Ok(())
+    }
+}
+
+/// 数据库的一次修改，由`Db`发送给后台任务。
+#[derive(Debug)]
+pub(crate) enum StorageEvent {
+    Set {
+        key: String,
+        value: Bytes,
+        expire: Option<Duration>,
+    },
+    Delete {
+        key: String,
+    },
+    Expire {
+        key: String,
+    },
+}
+
+/// 开启调用钩子的后台任务，返回用于发送修改事件的发送端。
+///
+/// 当所有发送端都被 drop 后，后台任务处理完剩余的事件后退出。
+pub(crate) fn spawn(hook: Arc<dyn StorageHook>) -> mpsc::UnboundedSender<StorageEvent> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            let res = match &event {
+                StorageEvent::Set { key, value, expire } => hook.on_set(key, value, *expire).await,
+                StorageEvent::Delete { key } => hook.on_delete(key).await,
+                StorageEvent::Expire { key } => hook.on_expire(key).await,
+            };
+            if let Err(err) = res {
+                warn!(cause = %err, ?event, "存储钩子执行失败");
+            }
+        }
+    });
+
+    tx
+}
@@ -0,0 +1,335 @@
+use std::{fmt, ops::Bound};
+
+use bytes::Bytes;
+
+/// 流中消息的 ID，由毫秒时间戳和序号组成，格式为`<ms>-<seq>`。
+///
+/// 先比较时间戳，再比较序号，流中消息的 ID 是严格递增的。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub(crate) struct StreamId {
+    pub(crate) ms: u64,
+    pub(crate) seq: u64,
+}
+
+/// `XAdd`中指定的新消息的 ID。
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum NewId {
+    /// `*`，时间戳和序号都自动生成。
+    Auto,
+    /// `<ms>-*`，只有序号自动生成。
+    AutoSeq(u64),
+    /// 完整的 ID。
+    Explicit(StreamId),
+}
+
+/// 消息的字段和值，按照添加时的顺序排列。
+pub(crate) type Fields = Vec<(Bytes, Bytes)>;
+
+/// 按照 ID 排序的多条消息。
+pub(crate) type Entries = Vec<(StreamId, Fields)>;
+
+/// 流，消息按照 ID 从小到大排序。
+///
+/// 使用`im::OrdMap`存储，`clone()`的开销是 O(1) 的，并且可以高效地按照 ID 范围查询。
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Stream {
+    entries: im::OrdMap<StreamId, Fields>,
+    // 最后一次添加的消息的 ID，新消息的 ID 必须比它大。
+    last_id: StreamId,
+    // 消费者组，key 为组名。
+    groups: im::HashMap<String, ConsumerGroup>,
+}
+
+/// 消费者组。
+///
+/// 组内的消费者共同消费一个流，每条消息只会被投递给组内的一个消费者。
+/// 已经投递但还没有被确认的消息保存在`pending`中，消费者重新连接后可以再次读取，
+/// 也可以被其他消费者认领。
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConsumerGroup {
+    // 最后一条投递给组内消费者的消息的 ID。
+    pub(crate) last_delivered: StreamId,
+    // 已经投递但还没有被确认的消息。
+    pub(crate) pending: im::OrdMap<StreamId, PendingEntry>,
+}
+
+/// 已经投递但还没有被确认的消息。
+#[derive(Debug, Clone)]
+pub(crate) struct PendingEntry {
+    // 消息当前所属的消费者。
+    pub(crate) consumer: String,
+    // 最后一次投递的时刻，距离 UNIX 纪元的毫秒数。
+    pub(crate) delivered_at: u64,
+    // 投递的次数。
+    pub(crate) delivery_count: u64,
+}
+
+/// `XReadGroup`从哪里开始读取消息。
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum GroupRead {
+    /// `>`，读取还没有投递给组内任何消费者的新消息。
+    New,
+    /// 读取已经投递给这个消费者、ID 大于指定 ID 且还没有被确认的消息。
+    History(StreamId),
+}
+
+impl StreamId {
+    /// 最小的 ID，`0-0`。
+    pub(crate) const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+
+    /// 解析`<ms>-<seq>`或者`<ms>`格式的 ID，省略序号时使用`default_seq`。
+    pub(crate) fn parse(src: &str, default_seq: u64) -> Option<StreamId> {
+        match src.split_once('-') {
+            Some((ms, seq)) => Some(StreamId {
+                ms: ms.parse().ok()?,
+                seq: seq.parse().ok()?,
+            }),
+            None => Some(StreamId {
+                ms: src.parse().ok()?,
+                seq: default_seq,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+impl NewId {
+    /// 解析`*`、`<ms>-*`、`<ms>-<seq>`或者`<ms>`格式的 ID，`<ms>`等价于`<ms>-0`。
+    pub(crate) fn parse(src: &str) -> Option<NewId> {
+        if src == "*" {
+            return Some(NewId::Auto);
+        }
+        match src.strip_suffix("-*") {
+            Some(ms) => ms.parse().ok().map(NewId::AutoSeq),
+            None => StreamId::parse(src, 0).map(NewId::Explicit),
+        }
+    }
+}
+
+impl Stream {
+    /// 创建一个空的流。
+    pub(crate) fn new() -> Stream {
+        Stream::default()
+    }
+
+    /// 使用已有的消息和消费者组创建流，用于从快照中恢复。
+    pub(crate) fn from_parts(
+        entries: im::OrdMap<StreamId, Fields>,
+        last_id: StreamId,
+        groups: im::HashMap<String, ConsumerGroup>,
+    ) -> Stream {
+        Stream {
+            entries,
+            last_id,
+            groups,
+        }
+    }
+
+    /// 获取消息数量。
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 获取最后一次添加的消息的 ID，空的流为`0-0`。
+    pub(crate) fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    /// 添加一条消息，返回消息的 ID。
+    ///
+    /// 自动生成时间戳时使用`now_ms`，如果时钟回拨，沿用上一条消息的时间戳并递增序号。
+    ///
+    /// # Errors
+    /// 如果 ID 不大于上一条消息的 ID，或者 ID 为`0-0`，返回错误信息。
+    pub(crate) fn add(
+        &mut self,
+        id: NewId,
+        fields: Fields,
+        now_ms: u64,
+    ) -> Result<StreamId, &'static str> {
+        let id = match id {
+            NewId::Auto => self.next_id(now_ms.max(self.last_id.ms)),
+            NewId::AutoSeq(ms) => self.next_id(ms),
+            NewId::Explicit(id) => Some(id),
+        };
+        let id = match id {
+            Some(id) if id == StreamId::MIN => {
+                return Err("ERR The ID specified in XADD must be greater than 0-0")
+            }
+            Some(id) if id > self.last_id => id,
+            _ => return Err(
+                "ERR The ID specified in XADD is equal or smaller than the target stream top item",
+            ),
+        };
+
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        Ok(id)
+    }
+
+    /// 按照 ID 从小到大的顺序遍历 ID 在`start`和`end`之间的消息。
+    pub(crate) fn range(
+        &self,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+    ) -> impl Iterator<Item = (&StreamId, &Fields)> {
+        self.entries.range((start, end))
+    }
+
+    /// 按照 ID 从小到大的顺序遍历所有消息。
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&StreamId, &Fields)> {
+        self.entries.iter()
+    }
+
+    /// 遍历所有消费者组。
+    pub(crate) fn groups(&self) -> impl Iterator<Item = (&String, &ConsumerGroup)> {
+        self.groups.iter()
+    }
+
+    /// 判断消费者组是否存在。
+    pub(crate) fn has_group(&self, name: &str) -> bool {
+        self.groups.contains_key(name)
+    }
+
+    /// 创建消费者组，组内的消费者从 ID 大于`last_delivered`的消息开始读取。
+    ///
+    /// # Output
+    /// 如果组已经存在，返回`false`。
+    pub(crate) fn create_group(&mut self, name: &str, last_delivered: StreamId) -> bool {
+        if self.groups.contains_key(name) {
+            return false;
+        }
+        let group = ConsumerGroup {
+            last_delivered,
+            pending: im::OrdMap::new(),
+        };
+        self.groups.insert(name.to_string(), group);
+        true
+    }
+
+    /// 删除消费者组，如果组存在，返回`true`。
+    pub(crate) fn destroy_group(&mut self, name: &str) -> bool {
+        self.groups.remove(name).is_some()
+    }
+
+    /// 以消费者组中`consumer`的身份读取消息，最多返回`count`条。
+    ///
+    /// 读取新消息时，消息会被记录为投递给了`consumer`，投递时刻为`now_ms`。
+    ///
+    /// # Output
+    /// 如果组不存在，返回`None`。
+    pub(crate) fn read_group(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        from: GroupRead,
+        count: Option<usize>,
+        now_ms: u64,
+    ) -> Option<Entries> {
+        let group = self.groups.get_mut(group)?;
+        let count = count.unwrap_or(usize::MAX);
+        let entries = match from {
+            GroupRead::New => {
+                let entries: Entries = self
+                    .entries
+                    .range((Bound::Excluded(group.last_delivered), Bound::Unbounded))
+                    .take(count)
+                    .map(|(id, fields)| (*id, fields.clone()))
+                    .collect();
+                for (id, _) in &entries {
+                    group.last_delivered = *id;
+                    let pending = PendingEntry {
+                        consumer: consumer.to_string(),
+                        delivered_at: now_ms,
+                        delivery_count: 1,
+                    };
+                    group.pending.insert(*id, pending);
+                }
+                entries
+            }
+            GroupRead::History(after) => group
+                .pending
+                .range((Bound::Excluded(after), Bound::Unbounded))
+                .filter(|(_, pending)| pending.consumer == consumer)
+                .filter_map(|(id, _)| self.entries.get(id).map(|fields| (*id, fields.clone())))
+                .take(count)
+                .collect(),
+        };
+        Some(entries)
+    }
+
+    /// 确认消息已经被处理，将它们从消费者组的待确认列表中删除。
+    ///
+    /// # Output
+    /// 返回被确认的消息的数量，如果组不存在，返回`None`。
+    pub(crate) fn ack(&mut self, group: &str, ids: &[StreamId]) -> Option<usize> {
+        let group = self.groups.get_mut(group)?;
+        Some(
+            ids.iter()
+                .filter(|id| group.pending.remove(*id).is_some())
+                .count(),
+        )
+    }
+
+    /// 将空闲时间不少于`min_idle_ms`的待确认消息转移给`consumer`，返回被认领的消息。
+    ///
+    /// 被认领的消息的投递时刻更新为`now_ms`，投递次数加一。
+    ///
+    /// # Output
+    /// 如果组不存在，返回`None`。
+    pub(crate) fn claim(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        min_idle_ms: u64,
+        ids: &[StreamId],
+        now_ms: u64,
+    ) -> Option<Entries> {
+        let group = self.groups.get_mut(group)?;
+        let mut claimed = vec![];
+        for id in ids {
+            let Some(pending) = group.pending.get_mut(id) else {
+                continue;
+            };
+            if now_ms.saturating_sub(pending.delivered_at) < min_idle_ms {
+                continue;
+            }
+            let Some(fields) = self.entries.get(id) else {
+                continue;
+            };
+            pending.consumer = consumer.to_string();
+            pending.delivered_at = now_ms;
+            pending.delivery_count += 1;
+            claimed.push((*id, fields.clone()));
+        }
+        Some(claimed)
+    }
+
+    /// 计算占用的近似字节数，每个 ID 记为 16 字节。
+    pub(crate) fn size(&self) -> usize {
+        self.entries.values().map(fields_size).sum()
+    }
+
+    /// 时间戳为`ms`时下一个可用的 ID，如果没有可用的 ID，返回`None`。
+    fn next_id(&self, ms: u64) -> Option<StreamId> {
+        if ms == self.last_id.ms {
+            let seq = self.last_id.seq.checked_add(1)?;
+            Some(StreamId { ms, seq })
+        } else {
+            Some(StreamId { ms, seq: 0 })
+        }
+    }
+}
+
+/// 计算一条消息占用的近似字节数，ID 记为 16 字节。
+pub(crate) fn fields_size(fields: &Fields) -> usize {
+    16 + fields
+        .iter()
+        .map(|(field, value)| field.len() + value.len())
+        .sum::<usize>()
+}
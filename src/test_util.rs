@@ -0,0 +1,209 @@
+//! 测试工具，需要开启`test-util`特性。
+//!
+//! 提供不需要绑定 TCP 端口就能测试完整命令处理流程的工具。
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use tokio::{
+    io,
+    net::TcpListener,
+    sync::{broadcast, mpsc, oneshot},
+    task::JoinHandle,
+};
+
+use crate::{client::Client, server, Connection, Db, DbDropGuard, SetOptions};
+
+/// 内存管道的缓存大小。
+const DUPLEX_BUFFER_SIZE: usize = 64 * 1024;
+
+/// 在后台任务中运行的服务器的关闭句柄。
+///
+/// 调用`shutdown()`或者 drop 掉这个句柄都会通知服务器关闭。
+#[derive(Debug)]
+pub struct ShutdownHandle {
+    // 被 drop 时服务器收到关闭信号。
+    notify: Option<oneshot::Sender<()>>,
+    // 运行服务器的后台任务。
+    task: JoinHandle<()>,
+}
+
+/// 绑定一个随机端口，在后台任务中运行服务器。
+///
+/// 返回服务器监听的地址以及用于关闭服务器的句柄。
+///
+/// ```no_run
+/// # async fn example() -> my_redis::Result<()> {
+/// use my_redis::{client::Client, test_util};
+///
+/// let (addr, shutdown) = test_util::spawn_server().await;
+/// let mut client = Client::connect(addr).await?;
+/// client.ping(None).await?;
+/// shutdown.shutdown().await;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Panics
+/// 如果无法绑定端口，程序崩溃。
+pub async fn spawn_server() -> (SocketAddr, ShutdownHandle) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // `oneshot::Receiver`本身就是一个`Future`，发送端被 drop 时也会完成。
+    let (notify, shutdown) = oneshot::channel::<()>();
+    let task = tokio::spawn(server::run(listener, shutdown));
+
+    let handle = ShutdownHandle {
+        notify: Some(notify),
+        task,
+    };
+    (addr, handle)
+}
+
+impl ShutdownHandle {
+    /// 通知服务器关闭，并等待所有连接完成清理工作。
+    pub async fn shutdown(mut self) {
+        self.notify.take();
+        let _ = (&mut self.task).await;
+    }
+}
+
+/// 一个只存在于内存中的服务器。
+///
+/// 每个通过`client()`创建的`Client`都通过`tokio::io::duplex`直接与一个`Handler`相连，
+/// 所有`Client`共享同一个数据库。
+///
+/// ```no_run
+/// # async fn example() -> my_redis::Result<()> {
+/// use my_redis::test_util::DuplexServer;
+///
+/// let server = DuplexServer::new();
+/// let mut client = server.client();
+/// client.set("foo", "bar".into()).await?;
+/// assert_eq!(client.get("foo").await?, Some("bar".into()));
+/// server.shutdown().await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DuplexServer {
+    // 数据库，被 drop 时会关闭后台任务。
+    db_holder: DbDropGuard,
+
+    // 所有连接共享的运行时计数器。
+    metrics: Arc<server::Metrics>,
+
+    // 广播发送端，被 drop 时通知所有`Handler`停止运行。
+    notify_shutdown: broadcast::Sender<()>,
+
+    // 每个`Handler`都持有一个发送端，全部被 drop 后接收端会收到`None`。
+    shutdown_complete_tx: mpsc::Sender<()>,
+    shutdown_complete_rx: mpsc::Receiver<()>,
+}
+
+impl DuplexServer {
+    /// 创建服务器，必须在 tokio 运行时中调用。
+    pub fn new() -> DuplexServer {
+        let (notify_shutdown, _) = broadcast::channel(1);
+        let (shutdown_complete_tx, shutdown_complete_rx) = mpsc::channel(1);
+        DuplexServer {
+            db_holder: DbDropGuard::with_shards(server::DEFAULT_DB_SHARDS),
+            metrics: Arc::new(server::Metrics::new()),
+            notify_shutdown,
+            shutdown_complete_tx,
+            shutdown_complete_rx,
+        }
+    }
+
+    /// 创建一个新的`Client`，并在后台任务中运行与之相连的`Handler`。
+    pub fn client(&self) -> Client {
+        let (client_side, server_side) = io::duplex(DUPLEX_BUFFER_SIZE);
+        tokio::spawn(server::serve_connection(
+            self.db_holder.db(),
+            Connection::new(server_side),
+            self.metrics.clone(),
+            self.notify_shutdown.subscribe(),
+            self.shutdown_complete_tx.clone(),
+        ));
+        Client::from_transport(client_side)
+    }
+
+    /// 获取服务器当前的运行时统计信息，与`server::Handle::stats()`相同。
+    pub fn stats(&self) -> server::Stats {
+        self.metrics.stats(&self.db_holder.db())
+    }
+
+    /// 通知所有`Handler`停止运行，并等待它们完成清理工作。
+    pub async fn shutdown(self) {
+        let DuplexServer {
+            notify_shutdown,
+            shutdown_complete_tx,
+            mut shutdown_complete_rx,
+            ..
+        } = self;
+        drop(notify_shutdown);
+        drop(shutdown_complete_tx);
+        let _ = shutdown_complete_rx.recv().await;
+    }
+}
+
+impl Default for DuplexServer {
+    fn default() -> DuplexServer {
+        DuplexServer::new()
+    }
+}
+
+/// 直接操作数据库的句柄，绕过网络层，主要用于基准测试。
+///
+/// 被 drop 时会关闭数据库的后台任务。
+#[derive(Debug)]
+pub struct DbHandle {
+    /// 仅用于在 drop 时关闭后台任务。
+    _db_holder: DbDropGuard,
+    db: Db,
+}
+
+impl DbHandle {
+    /// 创建一个新的、空的数据库，必须在 tokio 运行时中调用。
+    pub fn new() -> DbHandle {
+        let db_holder = DbDropGuard::with_shards(server::DEFAULT_DB_SHARDS);
+        let db = db_holder.db();
+        DbHandle {
+            _db_holder: db_holder,
+            db,
+        }
+    }
+
+    /// 开启或关闭读优化模式，开启后`get()`不需要获取锁。
+    pub fn set_read_optimized(&self, enabled: bool) {
+        self.db.set_read_optimized(enabled);
+    }
+
+    /// 根据 key 获取 value，如果 value 不是字符串，返回`Err`。
+    pub fn get(&self, key: &str) -> crate::Result<Option<Bytes>> {
+        self.db.get(key)
+    }
+
+    /// 设置 key-value，可以设置过期时间。
+    pub fn set(&self, key: String, value: Bytes, expire: Option<Duration>) {
+        // 没有`GET`选项时不会出错。
+        let _ = self.db.set(key, value, expire, SetOptions::default());
+    }
+
+    /// 订阅信道，返回广播接收端。
+    pub fn subscribe(&self, channel: String) -> broadcast::Receiver<Bytes> {
+        self.db.subscribe(channel)
+    }
+
+    /// 向信道发送信息，返回订阅者的数量。
+    pub fn publish(&self, channel: &str, message: Bytes) -> usize {
+        self.db.publish(channel, message)
+    }
+}
+
+impl Default for DbHandle {
+    fn default() -> DbHandle {
+        DbHandle::new()
+    }
+}
@@ -0,0 +1,177 @@
+//! 服务器和客户端的 TLS 配置。
+//!
+//! 设置客户端 CA 证书后开启双向认证，客户端必须提供由该 CA 签发的证书才能完成握手。
+//! 开启`cn_as_user`后，客户端证书的 CN 作为连接的 ACL 用户，连接建立后即为已认证状态，
+//! 不需要再发送`Auth`。
+//!
+//! 客户端通过`ClientTls`配置信任的 CA 证书、双向认证使用的证书以及 SNI 使用的服务器名称。
+
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tokio_rustls::rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    ClientConfig, RootCertStore, ServerConfig, ServerConnection,
+};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// TLS 的配置，通过`Server::tls()`开启。
+///
+/// 证书和私钥均为 PEM 格式。
+#[derive(Debug, Clone)]
+pub struct Tls {
+    cert: PathBuf,
+    key: PathBuf,
+    client_ca: Option<PathBuf>,
+    cn_as_user: bool,
+}
+
+/// 客户端的 TLS 配置，通过`Client::connect_tls()`或者`client::Builder::tls_config()`使用。
+///
+/// 证书和私钥均为 PEM 格式。
+#[derive(Debug, Clone, Default)]
+pub struct ClientTls {
+    ca_cert: Option<PathBuf>,
+    client_cert: Option<(PathBuf, PathBuf)>,
+    server_name: Option<String>,
+}
+
+impl Tls {
+    /// 创建 TLS 配置，`cert`为服务器的证书链，`key`为私钥。
+    pub fn new(cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Tls {
+        Tls {
+            cert: cert.into(),
+            key: key.into(),
+            client_ca: None,
+            cn_as_user: false,
+        }
+    }
+
+    /// 要求客户端提供证书，并使用`path`中的 CA 证书验证。
+    pub fn client_ca(mut self, path: impl Into<PathBuf>) -> Tls {
+        self.client_ca = Some(path.into());
+        self
+    }
+
+    /// 是否将客户端证书的 CN 作为连接的 ACL 用户，只在设置了客户端 CA 证书时有效。
+    ///
+    /// CN 对应的用户不存在或者被禁用时，连接仍然使用默认用户。
+    pub fn cn_as_user(mut self, enabled: bool) -> Tls {
+        self.cn_as_user = enabled;
+        self
+    }
+
+    /// 连接建立后是否需要从客户端证书中获取用户。
+    pub(crate) fn maps_cn_to_user(&self) -> bool {
+        self.client_ca.is_some() && self.cn_as_user
+    }
+
+    /// 读取证书和私钥，创建 rustls 的服务器配置。
+    ///
+    /// # Errors
+    /// 如果读取文件失败、文件中没有私钥或者证书与私钥不匹配，返回`Err`。
+    pub(crate) fn server_config(&self) -> crate::Result<ServerConfig> {
+        let certs = read_certs(&self.cert)?;
+        let key = read_key(&self.key)?;
+
+        let builder = ServerConfig::builder();
+        let builder = match &self.client_ca {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in read_certs(path)? {
+                    roots.add(cert)?;
+                }
+                let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+                    .build()
+                    .map_err(|err| format!("客户端 CA 证书不可用：{}", err))?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+        Ok(builder.with_single_cert(certs, key)?)
+    }
+}
+
+impl ClientTls {
+    /// 创建客户端的 TLS 配置，默认信任内置的根证书，不提供客户端证书。
+    pub fn new() -> ClientTls {
+        ClientTls::default()
+    }
+
+    /// 只信任`path`中的 CA 证书，不再信任内置的根证书。
+    pub fn ca_cert(mut self, path: impl Into<PathBuf>) -> ClientTls {
+        self.ca_cert = Some(path.into());
+        self
+    }
+
+    /// 握手时提供客户端证书，用于服务器开启了双向认证的情况。
+    /// `cert`为证书链，`key`为私钥。
+    pub fn client_cert(mut self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> ClientTls {
+        self.client_cert = Some((cert.into(), key.into()));
+        self
+    }
+
+    /// 设置 SNI 以及验证服务器证书时使用的名称，默认为地址中的主机名。
+    ///
+    /// 通过 IP 地址或者 Unix socket 以外的代理连接服务器时需要设置。
+    pub fn server_name(mut self, name: impl ToString) -> ClientTls {
+        self.server_name = Some(name.to_string());
+        self
+    }
+
+    /// 获取设置的服务器名称。
+    pub(crate) fn get_server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+
+    /// 读取证书和私钥，创建 rustls 的客户端配置。
+    ///
+    /// # Errors
+    /// 如果读取文件失败、文件中没有私钥或者客户端证书与私钥不匹配，返回`Err`。
+    pub(crate) fn client_config(&self) -> crate::Result<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        match &self.ca_cert {
+            Some(path) => {
+                for cert in read_certs(path)? {
+                    roots.add(cert)?;
+                }
+            }
+            None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        }
+
+        let builder = ClientConfig::builder().with_root_certificates(roots);
+        Ok(match &self.client_cert {
+            Some((cert, key)) => {
+                builder.with_client_auth_cert(read_certs(cert)?, read_key(key)?)?
+            }
+            None => builder.with_no_client_auth(),
+        })
+    }
+}
+
+/// 读取 PEM 文件中的第一个私钥。
+fn read_key(path: &Path) -> crate::Result<PrivateKeyDer<'static>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let key = rustls_pemfile::private_key(&mut reader)?
+        .ok_or_else(|| format!("{} 中没有私钥", path.display()))?;
+    Ok(key)
+}
+
+/// 读取 PEM 文件中的所有证书。
+fn read_certs(path: &Path) -> crate::Result<Vec<CertificateDer<'static>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(rustls_pemfile::certs(&mut reader).collect::<Result<_, _>>()?)
+}
+
+/// 获取客户端证书的 CN，客户端没有提供证书或者证书中没有 CN 时返回`None`。
+pub(crate) fn peer_common_name(conn: &ServerConnection) -> Option<String> {
+    let cert = conn.peer_certificates()?.first()?;
+    let (_, cert) = X509Certificate::from_der(cert).ok()?;
+    let cn = cert.subject().iter_common_name().next()?;
+    cn.as_str().ok().map(str::to_string)
+}
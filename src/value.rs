@@ -0,0 +1,216 @@
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+
+use crate::{stream::Stream, zset::SortedSet};
+
+/// Redis 中使用`embstr`编码的字符串的最大长度。
+const EMBSTR_MAX_LEN: usize = 44;
+
+/// 数据库中存储的 value。
+///
+/// 每种数据类型对应一个变体，对类型不符的 value 执行操作时返回`Error::WrongType`。
+#[derive(Debug, Clone)]
+pub(crate) enum Value {
+    /// 字符串，可以是任意字节。
+    String(Bytes),
+    /// 哈希表，field 和 value 都可以是任意字节。
+    ///
+    /// 与`Db`中的`entries`一样使用`im::HashMap`，`clone()`的开销是 O(1) 的，
+    /// 生成快照后修改哈希表不需要复制整个哈希表。
+    Hash(im::HashMap<Bytes, Bytes>),
+    /// 列表，两端的插入和弹出都是 O(1) 的。
+    List(VecDeque<Bytes>),
+    /// 无序集合，成员不会重复。
+    Set(im::HashSet<Bytes>),
+    /// 有序集合，成员按照分数排序。
+    ZSet(SortedSet),
+    /// 流，消息按照 ID 排序。
+    Stream(Stream),
+}
+
+impl Value {
+    /// 获取类型名称，即`Type`命令的返回值。
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Hash(_) => "hash",
+            Value::List(_) => "list",
+            Value::Set(_) => "set",
+            Value::ZSet(_) => "zset",
+            Value::Stream(_) => "stream",
+        }
+    }
+
+    /// 获取编码名称，即`Object Encoding`命令的返回值。
+    ///
+    /// 使用 Redis 中最接近的编码名称，方便已有的工具识别。
+    /// 字符串都以`Bytes`存储，这里按照 Redis 的规则区分`int`、`embstr`和`raw`。
+    pub(crate) fn encoding(&self) -> &'static str {
+        match self {
+            Value::String(data) if is_int(data) => "int",
+            Value::String(data) if data.len() <= EMBSTR_MAX_LEN => "embstr",
+            Value::String(_) => "raw",
+            Value::Hash(_) | Value::Set(_) => "hashtable",
+            Value::List(_) => "quicklist",
+            Value::ZSet(_) => "skiplist",
+            Value::Stream(_) => "stream",
+        }
+    }
+
+    /// 获取字符串的内容。
+    ///
+    /// # Errors
+    /// 如果不是字符串，返回`Error::WrongType`。
+    pub(crate) fn as_string(&self) -> crate::Result<&Bytes> {
+        match self {
+            Value::String(data) => Ok(data),
+            _ => Err(crate::Error::WrongType),
+        }
+    }
+
+    /// 获取字符串的可变引用。
+    ///
+    /// # Errors
+    /// 如果不是字符串，返回`Error::WrongType`。
+    pub(crate) fn as_string_mut(&mut self) -> crate::Result<&mut Bytes> {
+        match self {
+            Value::String(data) => Ok(data),
+            _ => Err(crate::Error::WrongType),
+        }
+    }
+
+    /// 获取哈希表。
+    ///
+    /// # Errors
+    /// 如果不是哈希表，返回`Error::WrongType`。
+    pub(crate) fn as_hash(&self) -> crate::Result<&im::HashMap<Bytes, Bytes>> {
+        match self {
+            Value::Hash(hash) => Ok(hash),
+            _ => Err(crate::Error::WrongType),
+        }
+    }
+
+    /// 获取哈希表的可变引用。
+    ///
+    /// # Errors
+    /// 如果不是哈希表，返回`Error::WrongType`。
+    pub(crate) fn as_hash_mut(&mut self) -> crate::Result<&mut im::HashMap<Bytes, Bytes>> {
+        match self {
+            Value::Hash(hash) => Ok(hash),
+            _ => Err(crate::Error::WrongType),
+        }
+    }
+
+    /// 获取列表。
+    ///
+    /// # Errors
+    /// 如果不是列表，返回`Error::WrongType`。
+    pub(crate) fn as_list(&self) -> crate::Result<&VecDeque<Bytes>> {
+        match self {
+            Value::List(list) => Ok(list),
+            _ => Err(crate::Error::WrongType),
+        }
+    }
+
+    /// 获取列表的可变引用。
+    ///
+    /// # Errors
+    /// 如果不是列表，返回`Error::WrongType`。
+    pub(crate) fn as_list_mut(&mut self) -> crate::Result<&mut VecDeque<Bytes>> {
+        match self {
+            Value::List(list) => Ok(list),
+            _ => Err(crate::Error::WrongType),
+        }
+    }
+
+    /// 获取集合。
+    ///
+    /// # Errors
+    /// 如果不是集合，返回`Error::WrongType`。
+    pub(crate) fn as_set(&self) -> crate::Result<&im::HashSet<Bytes>> {
+        match self {
+            Value::Set(set) => Ok(set),
+            _ => Err(crate::Error::WrongType),
+        }
+    }
+
+    /// 获取集合的可变引用。
+    ///
+    /// # Errors
+    /// 如果不是集合，返回`Error::WrongType`。
+    pub(crate) fn as_set_mut(&mut self) -> crate::Result<&mut im::HashSet<Bytes>> {
+        match self {
+            Value::Set(set) => Ok(set),
+            _ => Err(crate::Error::WrongType),
+        }
+    }
+
+    /// 获取有序集合。
+    ///
+    /// # Errors
+    /// 如果不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn as_zset(&self) -> crate::Result<&SortedSet> {
+        match self {
+            Value::ZSet(zset) => Ok(zset),
+            _ => Err(crate::Error::WrongType),
+        }
+    }
+
+    /// 获取有序集合的可变引用。
+    ///
+    /// # Errors
+    /// 如果不是有序集合，返回`Error::WrongType`。
+    pub(crate) fn as_zset_mut(&mut self) -> crate::Result<&mut SortedSet> {
+        match self {
+            Value::ZSet(zset) => Ok(zset),
+            _ => Err(crate::Error::WrongType),
+        }
+    }
+
+    /// 获取流。
+    ///
+    /// # Errors
+    /// 如果不是流，返回`Error::WrongType`。
+    pub(crate) fn as_stream(&self) -> crate::Result<&Stream> {
+        match self {
+            Value::Stream(stream) => Ok(stream),
+            _ => Err(crate::Error::WrongType),
+        }
+    }
+
+    /// 获取流的可变引用。
+    ///
+    /// # Errors
+    /// 如果不是流，返回`Error::WrongType`。
+    pub(crate) fn as_stream_mut(&mut self) -> crate::Result<&mut Stream> {
+        match self {
+            Value::Stream(stream) => Ok(stream),
+            _ => Err(crate::Error::WrongType),
+        }
+    }
+
+    /// 计算 value 占用的近似字节数。
+    pub(crate) fn size(&self) -> usize {
+        match self {
+            Value::String(data) => data.len(),
+            Value::Hash(hash) => hash
+                .iter()
+                .map(|(field, value)| field.len() + value.len())
+                .sum(),
+            Value::List(list) => list.iter().map(|value| value.len()).sum(),
+            Value::Set(set) => set.iter().map(|member| member.len()).sum(),
+            Value::ZSet(zset) => zset.size(),
+            Value::Stream(stream) => stream.size(),
+        }
+    }
+}
+
+/// 判断字符串是否是 Redis 会以`int`编码存储的整数，即能够原样还原的`i64`。
+fn is_int(data: &[u8]) -> bool {
+    data.len() <= 20
+        && std::str::from_utf8(data)
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .is_some_and(|value| value.to_string().as_bytes() == data)
+}
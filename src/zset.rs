@@ -0,0 +1,173 @@
+use std::{cmp::Ordering, ops::Bound};
+
+use bytes::Bytes;
+
+/// 有序集合，成员按照分数从小到大排序，分数相同时按照成员的字节序排序。
+///
+/// 使用两个结构维护同一份数据：`scores`用于根据成员查找分数，
+/// `ordered`用于按照分数顺序遍历。两者都是持久化的数据结构，`clone()`的开销是 O(1) 的。
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SortedSet {
+    scores: im::HashMap<Bytes, Score>,
+    ordered: im::OrdSet<(Score, Bytes)>,
+}
+
+/// 多个成员和分数，按照弹出或者遍历的顺序排列。
+pub(crate) type ScoredMembers = Vec<(Bytes, f64)>;
+
+/// 有序集合的分数。
+///
+/// `f64`没有实现`Ord`，这里使用`f64::total_cmp()`定义全序。
+/// 分数不能是 NaN，`-0.0`会被转换为`0.0`，所以这个全序与数值的大小一致。
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Score(f64);
+
+impl SortedSet {
+    /// 创建一个空的有序集合。
+    pub(crate) fn new() -> SortedSet {
+        SortedSet::default()
+    }
+
+    /// 获取成员数量。
+    pub(crate) fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// 判断有序集合是否为空。
+    pub(crate) fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// 获取成员的分数，如果成员不存在，返回`None`。
+    pub(crate) fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).map(|score| score.value())
+    }
+
+    /// 插入成员，如果成员已经存在，更新它的分数。
+    ///
+    /// # Output
+    /// 如果是新的成员，返回`true`。
+    pub(crate) fn insert(&mut self, member: Bytes, score: Score) -> bool {
+        match self.scores.insert(member.clone(), score) {
+            Some(prev) => {
+                self.ordered.remove(&(prev, member.clone()));
+                self.ordered.insert((score, member));
+                false
+            }
+            None => {
+                self.ordered.insert((score, member));
+                true
+            }
+        }
+    }
+
+    /// 删除成员，如果成员存在，返回`true`。
+    pub(crate) fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => {
+                self.ordered
+                    .remove(&(score, Bytes::copy_from_slice(member)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 弹出分数最小（`min`为`true`）或者最大的成员，返回成员和分数。
+    ///
+    /// 如果有序集合为空，返回`None`。
+    pub(crate) fn pop(&mut self, min: bool) -> Option<(Bytes, f64)> {
+        let (score, member) = if min {
+            self.ordered.remove_min()?
+        } else {
+            self.ordered.remove_max()?
+        };
+        self.scores.remove(&member);
+        Some((member, score.value()))
+    }
+
+    /// 获取成员的排名，排名从`0`开始，按分数从小到大。如果成员不存在，返回`None`。
+    ///
+    /// 需要数出排在成员前面的元素，时间复杂度为 O(n)。
+    pub(crate) fn rank(&self, member: &[u8]) -> Option<usize> {
+        let score = *self.scores.get(member)?;
+        let end = (score, Bytes::copy_from_slice(member));
+        Some(self.ordered.range(..end).count())
+    }
+
+    /// 按照分数从小到大的顺序遍历分数在`min`和`max`之间的成员和分数。
+    pub(crate) fn range_by_score(
+        &self,
+        min: Bound<Score>,
+        max: Bound<Score>,
+    ) -> impl Iterator<Item = (&Bytes, f64)> {
+        // 分数相同时空的成员排在最前面，所以从`(min, "")`开始就不会漏掉分数等于`min`的成员。
+        let start = match min {
+            Bound::Included(score) | Bound::Excluded(score) => {
+                Bound::Included((score, Bytes::new()))
+            }
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        self.ordered
+            .range((start, Bound::Unbounded))
+            .skip_while(move |(score, _)| matches!(min, Bound::Excluded(min) if *score == min))
+            .take_while(move |(score, _)| match max {
+                Bound::Included(max) => *score <= max,
+                Bound::Excluded(max) => *score < max,
+                Bound::Unbounded => true,
+            })
+            .map(|(score, member)| (member, score.0))
+    }
+
+    /// 按照分数从小到大的顺序遍历成员和分数。
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Bytes, f64)> {
+        self.ordered.iter().map(|(score, member)| (member, score.0))
+    }
+
+    /// 计算占用的近似字节数，每个分数记为 8 字节。
+    pub(crate) fn size(&self) -> usize {
+        self.scores.keys().map(|member| member.len() + 8).sum()
+    }
+}
+
+impl Score {
+    /// 创建分数，如果`value`是 NaN，返回`None`。
+    pub(crate) fn new(value: f64) -> Option<Score> {
+        if value.is_nan() {
+            None
+        } else {
+            // `-0.0 + 0.0`为`0.0`。
+            Some(Score(value + 0.0))
+        }
+    }
+
+    /// 获取分数的值。
+    pub(crate) fn value(self) -> f64 {
+        self.0
+    }
+}
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Score) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Score) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Score) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// 将分数格式化为字符串，整数不带小数部分，无穷大为`inf`和`-inf`。
+pub(crate) fn format_score(score: f64) -> String {
+    score.to_string()
+}
@@ -0,0 +1,63 @@
+//! ACL 用户和权限检查的集成测试。
+
+use bytes::Bytes;
+use my_redis::{client::Client, test_util, Error};
+
+/// 断言请求因为权限被拒绝，错误信息以`prefix`开头。
+fn assert_denied<T: std::fmt::Debug>(result: my_redis::Result<T>, prefix: &str) {
+    match result {
+        Err(Error::Auth(msg)) => assert!(msg.starts_with(prefix), "{}", msg),
+        other => panic!("预期是 {} 错误：{:?}", prefix, other),
+    }
+}
+
+#[tokio::test]
+async fn user_permissions_are_checked() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut admin = Client::connect(addr).await.unwrap();
+    admin
+        .acl_setuser("alice", &["on", ">secret", "~user:*", "+get", "+set"])
+        .await
+        .unwrap();
+
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_denied(client.auth(Some("alice"), "wrong").await, "WRONGPASS");
+    client.auth(Some("alice"), "secret").await.unwrap();
+
+    // 允许的命令和 key。
+    client.set("user:1", Bytes::from("value")).await.unwrap();
+    assert_eq!(
+        client.get("user:1").await.unwrap(),
+        Some(Bytes::from("value"))
+    );
+    // 不匹配的 key 和没有允许的命令都被拒绝，命令没有被执行。
+    assert_denied(client.set("other", Bytes::from("value")).await, "NOPERM");
+    assert_denied(client.incr("user:2").await, "NOPERM");
+    assert_eq!(admin.get("other").await.unwrap(), None);
+    assert_eq!(admin.get("user:2").await.unwrap(), None);
+
+    // 禁用用户后，新的认证失败。
+    admin.acl_setuser("alice", &["off"]).await.unwrap();
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_denied(client.auth(Some("alice"), "secret").await, "WRONGPASS");
+
+    shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn default_user_with_password_requires_auth() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut admin = Client::connect(addr).await.unwrap();
+    admin
+        .acl_setuser("default", &["resetpass", ">secret"])
+        .await
+        .unwrap();
+
+    // 默认用户需要密码后，没有认证的连接不能执行命令。
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_denied(client.get("key").await, "NOAUTH");
+    client.auth(None, "secret").await.unwrap();
+    assert_eq!(client.get("key").await.unwrap(), None);
+
+    shutdown.shutdown().await;
+}
@@ -0,0 +1,74 @@
+//! 阻塞式弹出命令的集成测试。
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use my_redis::{client::Client, test_util};
+use tokio::time;
+
+#[tokio::test]
+async fn blpop_wakes_up_on_push() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut popper = Client::connect(addr).await.unwrap();
+    let mut pusher = Client::connect(addr).await.unwrap();
+
+    let pop = tokio::spawn(async move {
+        popper
+            .blpop(vec!["empty".to_string(), "list".to_string()], None)
+            .await
+    });
+    // 等待`BLPop`开始阻塞。
+    time::sleep(Duration::from_millis(50)).await;
+    pusher
+        .rpush("list", vec![Bytes::from("a"), Bytes::from("b")])
+        .await
+        .unwrap();
+
+    let popped = time::timeout(Duration::from_secs(5), pop)
+        .await
+        .expect("BLPop 没有被唤醒")
+        .unwrap()
+        .unwrap();
+    assert_eq!(popped, Some(("list".to_string(), Bytes::from("a"))));
+    // 只弹出了一个元素。
+    assert_eq!(
+        pusher.lrange("list", 0, -1).await.unwrap(),
+        vec![Bytes::from("b")]
+    );
+
+    shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn brpop_returns_existing_element_immediately() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    client
+        .rpush("list", vec![Bytes::from("a"), Bytes::from("b")])
+        .await
+        .unwrap();
+    let popped = client
+        .brpop(vec!["list".to_string()], Some(Duration::from_secs(5)))
+        .await
+        .unwrap();
+    assert_eq!(popped, Some(("list".to_string(), Bytes::from("b"))));
+
+    shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn blpop_times_out() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let popped = client
+        .blpop(vec!["empty".to_string()], Some(Duration::from_millis(50)))
+        .await
+        .unwrap();
+    assert_eq!(popped, None);
+    // 超时后连接仍然可用。
+    assert_eq!(client.get("empty").await.unwrap(), None);
+
+    shutdown.shutdown().await;
+}
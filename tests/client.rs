@@ -0,0 +1,51 @@
+//! 客户端的集成测试。
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use my_redis::{client::Client, test_util};
+
+#[tokio::test]
+async fn timed_out_connection_is_not_reused() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    // 服务器在超时之后才会返回`BLPop`的响应。
+    let err = client
+        .with_timeout(Duration::from_millis(50))
+        .blpop(vec!["list".to_string()], Some(Duration::from_millis(200)))
+        .await
+        .unwrap_err();
+    assert!(err.is_timeout());
+
+    // 迟到的响应不会被当作之后请求的响应。
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(client.set("key", Bytes::from("value")).await.is_err());
+    assert!(client.get("key").await.is_err());
+
+    // 重新连接后可以继续使用，之前的`Set`没有发送给服务器。
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_eq!(client.get("key").await.unwrap(), None);
+
+    shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn with_timeout_applies_to_one_request() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    client
+        .with_timeout(Duration::from_millis(50))
+        .get("key")
+        .await
+        .unwrap();
+    // 之后的请求没有超时时间。
+    let popped = client
+        .blpop(vec!["list".to_string()], Some(Duration::from_millis(100)))
+        .await
+        .unwrap();
+    assert!(popped.is_none());
+
+    shutdown.shutdown().await;
+}
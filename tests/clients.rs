@@ -0,0 +1,119 @@
+//! 管理连接的`Client`命令的集成测试。
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use my_redis::{
+    client::{Client, Subscriber},
+    test_util, INVALIDATE_CHANNEL,
+};
+use tokio::time::{self, Instant};
+
+#[tokio::test]
+async fn pause_write_only_blocks_writes_until_unpause() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut admin = Client::connect(addr).await.unwrap();
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("key", Bytes::from("old")).await.unwrap();
+
+    admin
+        .client_pause(Duration::from_secs(10), true)
+        .await
+        .unwrap();
+    // 只暂停写命令，读命令立即执行。
+    let value = time::timeout(Duration::from_secs(1), client.get("key"))
+        .await
+        .expect("读命令被暂停了")
+        .unwrap();
+    assert_eq!(value, Some(Bytes::from("old")));
+
+    let write = tokio::spawn(async move {
+        client.set("key", Bytes::from("new")).await.unwrap();
+        client
+    });
+    time::sleep(Duration::from_millis(100)).await;
+    assert!(!write.is_finished(), "写命令没有被暂停");
+    assert_eq!(admin.get("key").await.unwrap(), Some(Bytes::from("old")));
+
+    // 结束暂停后，被暂停的命令继续执行。
+    admin.client_unpause().await.unwrap();
+    time::timeout(Duration::from_secs(5), write)
+        .await
+        .expect("结束暂停后写命令没有执行")
+        .unwrap();
+    assert_eq!(admin.get("key").await.unwrap(), Some(Bytes::from("new")));
+
+    shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn pause_all_ends_after_timeout() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut admin = Client::connect(addr).await.unwrap();
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let start = Instant::now();
+    admin
+        .client_pause(Duration::from_millis(200), false)
+        .await
+        .unwrap();
+    // 读命令同样被暂停，超时后自动恢复。
+    assert_eq!(client.get("key").await.unwrap(), None);
+    assert!(start.elapsed() >= Duration::from_millis(150));
+
+    shutdown.shutdown().await;
+}
+
+/// 等待下一条失效信息，返回被修改的 key。
+async fn next_invalidation(subscriber: &mut Subscriber) -> String {
+    let message = time::timeout(Duration::from_secs(5), subscriber.next_message())
+        .await
+        .expect("没有收到失效信息")
+        .unwrap()
+        .unwrap();
+    assert_eq!(message.channel, INVALIDATE_CHANNEL);
+    String::from_utf8(message.content.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn tracking_sends_invalidations_for_read_keys() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut subscriber = Client::connect(addr)
+        .await
+        .unwrap()
+        .subscribe_invalidations()
+        .await
+        .unwrap();
+    let mut tracker = Client::connect(addr).await.unwrap();
+    let mut writer = Client::connect(addr).await.unwrap();
+
+    tracker.tracking(true).await.unwrap();
+    tracker.get("a").await.unwrap();
+    tracker.get("b").await.unwrap();
+
+    // 每次读取只触发一次失效信息，第二次修改`a`不会再发送。
+    writer.set("a", Bytes::from("1")).await.unwrap();
+    assert_eq!(next_invalidation(&mut subscriber).await, "a");
+    writer.set("a", Bytes::from("2")).await.unwrap();
+    writer.set("b", Bytes::from("1")).await.unwrap();
+    assert_eq!(next_invalidation(&mut subscriber).await, "b");
+
+    // 过期同样会发送失效信息。
+    writer
+        .set_expires("c", Bytes::from("1"), Duration::from_millis(50))
+        .await
+        .unwrap();
+    tracker.get("c").await.unwrap();
+    assert_eq!(next_invalidation(&mut subscriber).await, "c");
+
+    // 关闭追踪后读取的 key 不会被追踪。
+    tracker.tracking(false).await.unwrap();
+    tracker.get("d").await.unwrap();
+    writer.set("d", Bytes::from("1")).await.unwrap();
+    tracker.tracking(true).await.unwrap();
+    tracker.get("e").await.unwrap();
+    writer.set("e", Bytes::from("1")).await.unwrap();
+    assert_eq!(next_invalidation(&mut subscriber).await, "e");
+
+    shutdown.shutdown().await;
+}
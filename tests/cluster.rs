@@ -0,0 +1,140 @@
+//! 集群模式的`MOVED`和`ASK`重定向的集成测试。
+
+use std::net::SocketAddr;
+
+use bytes::Bytes;
+use my_redis::{
+    client::{Client, Redirect},
+    cluster::{self, Cluster},
+    server::Server,
+};
+use tokio::{net::TcpListener, sync::oneshot};
+
+/// 启动两个节点，第一个节点负责前一半的槽，第二个节点负责后一半的槽。
+///
+/// 返回两个节点的地址，以及关闭信号的发送端，被 drop 时节点关闭。
+async fn spawn_cluster() -> ([SocketAddr; 2], Vec<oneshot::Sender<()>>) {
+    let listeners = [
+        TcpListener::bind("127.0.0.1:0").await.unwrap(),
+        TcpListener::bind("127.0.0.1:0").await.unwrap(),
+    ];
+    let addrs = [
+        listeners[0].local_addr().unwrap(),
+        listeners[1].local_addr().unwrap(),
+    ];
+    let half = cluster::SLOTS / 2;
+    let mut notifies = vec![];
+    for (listener, addr) in listeners.into_iter().zip(addrs) {
+        let cluster = Cluster::new(addr)
+            .node(addrs[0], 0..=half - 1)
+            .node(addrs[1], half..=cluster::SLOTS - 1);
+        let server = Server::new(listener).cluster(cluster);
+        let (notify, shutdown) = oneshot::channel::<()>();
+        tokio::spawn(server.run(shutdown));
+        notifies.push(notify);
+    }
+    (addrs, notifies)
+}
+
+/// 找到一个所在的槽属于`[start, end)`的哈希标签。
+fn tag_in(start: u16, end: u16) -> String {
+    (0..)
+        .map(|i| format!("{{tag{}}}", i))
+        .find(|tag| (start..end).contains(&cluster::key_slot(tag.as_bytes())))
+        .unwrap()
+}
+
+fn args(args: &[&str]) -> Vec<Bytes> {
+    args.iter()
+        .map(|arg| Bytes::from(arg.to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn keys_of_other_nodes_are_moved() {
+    let ([a, b], _notifies) = spawn_cluster().await;
+    let half = cluster::SLOTS / 2;
+    let key_a = format!("{}key", tag_in(0, half));
+    let key_b = format!("{}key", tag_in(half, cluster::SLOTS));
+
+    let mut client_a = Client::connect(a).await.unwrap();
+    let mut client_b = Client::connect(b).await.unwrap();
+    client_a.set(&key_a, Bytes::from("a")).await.unwrap();
+    client_b.set(&key_b, Bytes::from("b")).await.unwrap();
+
+    let err = client_a.get(&key_b).await.unwrap_err();
+    let slot = cluster::key_slot(key_b.as_bytes());
+    assert_eq!(
+        Redirect::from_error(&err),
+        Some(Redirect::Moved {
+            slot,
+            addr: b.to_string()
+        })
+    );
+    let err = client_b.set(&key_a, Bytes::from("b")).await.unwrap_err();
+    assert!(matches!(
+        Redirect::from_error(&err),
+        Some(Redirect::Moved { addr, .. }) if addr == a.to_string()
+    ));
+    // 被重定向的命令没有被执行。
+    assert_eq!(client_a.get(&key_a).await.unwrap(), Some(Bytes::from("a")));
+}
+
+#[tokio::test]
+async fn migrating_slot_asks_for_missing_keys() {
+    let ([a, b], _notifies) = spawn_cluster().await;
+    let tag = tag_in(0, cluster::SLOTS / 2);
+    let slot = cluster::key_slot(tag.as_bytes()).to_string();
+    let (existing, missing) = (format!("{}old", tag), format!("{}new", tag));
+
+    let mut client_a = Client::connect(a).await.unwrap();
+    let mut client_b = Client::connect(b).await.unwrap();
+    client_a.set(&existing, Bytes::from("a")).await.unwrap();
+    let (a_addr, b_addr) = (a.to_string(), b.to_string());
+    client_a
+        .call(args(&["CLUSTER", "SETSLOT", &slot, "MIGRATING", &b_addr]))
+        .await
+        .unwrap();
+    client_b
+        .call(args(&["CLUSTER", "SETSLOT", &slot, "IMPORTING", &a_addr]))
+        .await
+        .unwrap();
+
+    // 源节点上还存在的 key 仍然在源节点执行，不存在的 key 返回`ASK`。
+    assert_eq!(
+        client_a.get(&existing).await.unwrap(),
+        Some(Bytes::from("a"))
+    );
+    let err = client_a.get(&missing).await.unwrap_err();
+    assert!(matches!(
+        Redirect::from_error(&err),
+        Some(Redirect::Ask { addr, .. }) if addr == b_addr
+    ));
+
+    // 目标节点只接受`Asking`之后的一条命令。
+    let err = client_b.set(&missing, Bytes::from("b")).await.unwrap_err();
+    assert!(matches!(
+        Redirect::from_error(&err),
+        Some(Redirect::Moved { addr, .. }) if addr == a_addr
+    ));
+    client_b.asking().await.unwrap();
+    client_b.set(&missing, Bytes::from("b")).await.unwrap();
+    assert!(client_b.get(&missing).await.is_err());
+
+    // 迁移完成后，源节点返回`MOVED`。
+    for client in [&mut client_a, &mut client_b] {
+        client
+            .call(args(&["CLUSTER", "SETSLOT", &slot, "NODE", &b_addr]))
+            .await
+            .unwrap();
+    }
+    let err = client_a.get(&existing).await.unwrap_err();
+    assert!(matches!(
+        Redirect::from_error(&err),
+        Some(Redirect::Moved { addr, .. }) if addr == b_addr
+    ));
+    assert_eq!(
+        client_b.get(&missing).await.unwrap(),
+        Some(Bytes::from("b"))
+    );
+}
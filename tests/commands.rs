@@ -0,0 +1,69 @@
+//! 命令解析的集成测试。
+
+use bytes::Bytes;
+use my_redis::{client::Client, test_util};
+
+/// 发送命令，断言服务器返回`expected`错误，并且连接仍然可用。
+async fn assert_error(client: &mut Client, args: &[&str], expected: &str) {
+    let args = args
+        .iter()
+        .map(|arg| Bytes::from(arg.to_string()))
+        .collect();
+    let err = client.call(args).await.unwrap_err();
+    assert_eq!(err.to_string(), expected);
+    assert_eq!(client.ping(None).await.unwrap(), "PONG");
+}
+
+#[tokio::test]
+async fn invalid_arguments_keep_connection_open() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    assert_error(
+        &mut client,
+        &["INCRBY", "a", "abc"],
+        "ERR value is not an integer or out of range",
+    )
+    .await;
+    assert_error(
+        &mut client,
+        &["SET", "k", "v", "EX", "abc"],
+        "ERR value is not an integer or out of range",
+    )
+    .await;
+    assert_error(
+        &mut client,
+        &["EXPIRE", "k", "abc"],
+        "ERR value is not an integer or out of range",
+    )
+    .await;
+    assert_error(
+        &mut client,
+        &["SET", "k", "v", "NX", "XX"],
+        "ERR syntax error",
+    )
+    .await;
+    assert_error(
+        &mut client,
+        &["ZADD", "z", "nan", "m"],
+        "ERR value is not a valid float",
+    )
+    .await;
+    assert_error(
+        &mut client,
+        &["ZRANGEBYSCORE", "z", "0", "1", "LIMIT", "x", "y"],
+        "ERR syntax error",
+    )
+    .await;
+    assert_error(
+        &mut client,
+        &["GET", "a", "b"],
+        "ERR wrong number of arguments for 'get' command",
+    )
+    .await;
+
+    // 参数不合法的写命令不会修改数据。
+    assert_eq!(client.get("k").await.unwrap(), None);
+
+    shutdown.shutdown().await;
+}
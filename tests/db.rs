@@ -0,0 +1,159 @@
+//! 数据库分片等存储层行为的集成测试。
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use my_redis::{client::Client, server::Server, test_util, Frame};
+use tokio::{net::TcpListener, sync::oneshot, time};
+
+#[tokio::test]
+async fn concurrent_writes_to_different_shards() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+
+    // 每个连接修改自己的 key，同时用跨分片的多 key 命令访问其他连接的 key，
+    // 获取多个分片的锁时不能死锁，也不能丢失修改。
+    let tasks: Vec<_> = (0..8)
+        .map(|i| {
+            tokio::spawn(async move {
+                let mut client = Client::connect(addr).await.unwrap();
+                let keys: Vec<String> = (0..4).map(|j| format!("counter:{}:{}", i, j)).collect();
+                let set = format!("set:{}", i);
+                let next = format!("set:{}", (i + 1) % 8);
+                for n in 0..100 {
+                    for key in &keys {
+                        client.incr(key).await.unwrap();
+                    }
+                    let member = n.to_string();
+                    client.call(args(&["SADD", &set, &member])).await.unwrap();
+                    client.call(args(&["SINTER", &next, &set])).await.unwrap();
+                    client
+                        .call(args(&["TOUCH", &next, &keys[0], &set]))
+                        .await
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    time::timeout(Duration::from_secs(30), async {
+        for task in tasks {
+            task.await.unwrap();
+        }
+    })
+    .await
+    .expect("跨分片的命令发生了死锁");
+
+    let mut client = Client::connect(addr).await.unwrap();
+    for i in 0..8 {
+        for j in 0..4 {
+            let key = format!("counter:{}:{}", i, j);
+            assert_eq!(client.get(&key).await.unwrap(), Some(Bytes::from("100")));
+        }
+        let reply = client
+            .call(args(&["SCARD", &format!("set:{}", i)]))
+            .await
+            .unwrap();
+        assert!(matches!(reply, Frame::Integer(100)), "{:?}", reply);
+    }
+    let info = client.info(&["keyspace"]).await.unwrap();
+    assert!(info.contains("db0:keys=40,"), "{}", info);
+
+    shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn read_optimized_get_hides_expired_keys() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::new(listener).read_optimized();
+    let (notify, shutdown) = oneshot::channel::<()>();
+    let task = tokio::spawn(server.run(shutdown));
+
+    let mut client = Client::connect(addr).await.unwrap();
+    // 关闭主动清除，过期的 key 仍然留在发布的版本中。
+    client
+        .call(args(&["DEBUG", "SET-ACTIVE-EXPIRE", "0"]))
+        .await
+        .unwrap();
+    client
+        .set_expires("volatile", Bytes::from("value"), Duration::from_millis(50))
+        .await
+        .unwrap();
+    client.set("other", Bytes::from("value")).await.unwrap();
+    assert_eq!(
+        client.get("volatile").await.unwrap(),
+        Some(Bytes::from("value"))
+    );
+
+    time::sleep(Duration::from_millis(100)).await;
+    // 不获取锁的读取同样不能返回已经过期的 key。
+    assert_eq!(client.get("volatile").await.unwrap(), None);
+    assert_eq!(
+        client.get("other").await.unwrap(),
+        Some(Bytes::from("value"))
+    );
+
+    drop(notify);
+    task.await.unwrap();
+}
+
+/// 获取`Info Memory`中的一项。
+async fn memory_info(client: &mut Client, name: &str) -> String {
+    let info = client.info(&["memory"]).await.unwrap();
+    info.split("\r\n")
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn large_values_are_freed_in_background() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    // 超过阈值的列表和字符串，以及一个小的 value。
+    let items: Vec<Bytes> = (0..1000)
+        .map(|i| Bytes::from(format!("{:0100}", i)))
+        .collect();
+    client.rpush("list", items).await.unwrap();
+    client
+        .set("string", Bytes::from(vec![b'x'; 256 * 1024]))
+        .await
+        .unwrap();
+    client.set("small", Bytes::from("value")).await.unwrap();
+
+    // 删除和覆盖都交给后台线程释放，小的 value 直接释放。
+    let reply = client
+        .call(args(&["UNLINK", "list", "small"]))
+        .await
+        .unwrap();
+    assert!(matches!(reply, Frame::Integer(2)), "{:?}", reply);
+    client.set("string", Bytes::from("value")).await.unwrap();
+
+    time::timeout(Duration::from_secs(5), async {
+        while memory_info(&mut client, "lazyfreed_objects").await != "2" {
+            time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("大 value 没有被后台线程释放");
+    assert_eq!(
+        memory_info(&mut client, "lazyfree_pending_objects").await,
+        "0"
+    );
+    assert_eq!(
+        client.lrange("list", 0, -1).await.unwrap(),
+        Vec::<Bytes>::new()
+    );
+    assert_eq!(
+        client.get("string").await.unwrap(),
+        Some(Bytes::from("value"))
+    );
+
+    shutdown.shutdown().await;
+}
+
+fn args(args: &[&str]) -> Vec<Bytes> {
+    args.iter()
+        .map(|arg| Bytes::from(arg.to_string()))
+        .collect()
+}
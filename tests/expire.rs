@@ -0,0 +1,52 @@
+//! 过期 key 的清除的集成测试。
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use my_redis::{client::Client, test_util};
+use tokio::time::{self, Instant};
+
+/// 获取`Info Keyspace`中设置了过期时间的 key 的数量，没有 key 时为`0`。
+async fn expires(client: &mut Client) -> usize {
+    let info = client.info(&["keyspace"]).await.unwrap();
+    info.split("expires=")
+        .nth(1)
+        .and_then(|rest| rest.split("\r\n").next())
+        .map_or(0, |count| count.parse().unwrap())
+}
+
+#[tokio::test]
+async fn active_expire_removes_expired_keys_without_access() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    // 大量 key 同时过期，另外一些 key 不会在测试期间过期。
+    for i in 0..1000 {
+        let key = format!("short:{}", i);
+        client
+            .set_expires(&key, Bytes::from("value"), Duration::from_millis(50))
+            .await
+            .unwrap();
+    }
+    for i in 0..100 {
+        let key = format!("long:{}", i);
+        client
+            .set_expires(&key, Bytes::from("value"), Duration::from_secs(600))
+            .await
+            .unwrap();
+    }
+
+    // 不访问这些 key，后台任务通过随机抽样把它们全部清除。
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while expires(&mut client).await > 100 {
+        assert!(Instant::now() < deadline, "过期的 key 没有被清除");
+        time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(expires(&mut client).await, 100);
+    assert_eq!(
+        client.get("long:0").await.unwrap(),
+        Some(Bytes::from("value"))
+    );
+
+    shutdown.shutdown().await;
+}
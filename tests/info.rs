@@ -0,0 +1,90 @@
+//! `Info`和`Memory Stats`的集成测试。
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use my_redis::{client::Client, test_util};
+use tokio::time;
+
+/// 获取`Memory Stats`中的一项。
+async fn memory_stat(client: &mut Client, name: &str) -> String {
+    let stats = client.memory_stats().await.unwrap();
+    stats
+        .into_iter()
+        .find(|(stat, _)| stat == name)
+        .map(|(_, value)| value)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn info_returns_all_sections_by_default() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("key", Bytes::from("value")).await.unwrap();
+
+    let info = client.info(&[]).await.unwrap();
+    for section in [
+        "# Clients",
+        "# Memory",
+        "# Persistence",
+        "# Replication",
+        "# Keyspace",
+    ] {
+        assert!(info.contains(section), "缺少 {}：{}", section, info);
+    }
+    assert!(info.contains("role:master\r\n"));
+    assert!(info.contains("db0:keys=1,expires=0\r\n"));
+
+    // 指定部分时只返回这些部分。
+    let info = client.info(&["replication"]).await.unwrap();
+    assert!(info.starts_with("# Replication\r\n"));
+    assert!(!info.contains("# Memory"));
+
+    shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn pubsub_channels_only_counts_subscribed_channels() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let subscriber = Client::connect(addr)
+        .await
+        .unwrap()
+        .subscribe(vec!["news".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(memory_stat(&mut client, "pubsub.channels").await, "1");
+
+    // 订阅者断开后，信道不再被统计。
+    drop(subscriber);
+    time::timeout(Duration::from_secs(5), async {
+        while memory_stat(&mut client, "pubsub.channels").await != "0" {
+            time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("没有订阅者的信道仍然被统计");
+
+    shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn duplex_server_connections_are_counted() {
+    let server = test_util::DuplexServer::new();
+    let mut client = server.client();
+    let mut other = server.client();
+
+    client.set("key", Bytes::from("value")).await.unwrap();
+    other.get("key").await.unwrap();
+    let info = client.info(&["clients"]).await.unwrap();
+    assert!(info.contains("connected_clients:2\r\n"), "{}", info);
+
+    let stats = server.stats();
+    assert_eq!(stats.connected_clients, 2);
+    // `Info`本身还没有执行完，不一定被统计。
+    assert!(stats.commands_processed >= 2);
+    assert_eq!(stats.keys, 1);
+
+    server.shutdown().await;
+}
@@ -0,0 +1,161 @@
+//! RESP2 和 RESP3 编解码的集成测试。
+
+use bytes::{Bytes, BytesMut};
+use my_redis::{client::Client, test_util, Frame, Protocol};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+#[test]
+fn null_is_encoded_by_protocol() {
+    let mut buf = BytesMut::new();
+    Frame::Null.encode_as(Protocol::Resp2, &mut buf);
+    assert_eq!(&buf[..], b"$-1\r\n");
+
+    let mut buf = BytesMut::new();
+    Frame::Null.encode_as(Protocol::Resp3, &mut buf);
+    assert_eq!(&buf[..], b"_\r\n");
+}
+
+#[test]
+fn resp2_null_is_decoded() {
+    for data in [&b"$-1\r\n"[..], b"*-1\r\n", b"_\r\n"] {
+        let mut buf = BytesMut::from(data);
+        assert!(matches!(
+            Frame::decode(&mut buf).unwrap(),
+            Some(Frame::Null)
+        ));
+        assert!(buf.is_empty());
+    }
+
+    // 数组中的空值。
+    let mut buf = BytesMut::from(&b"*2\r\n$-1\r\n:1\r\n"[..]);
+    let Some(Frame::Array(items)) = Frame::decode(&mut buf).unwrap() else {
+        panic!("预期是 Array");
+    };
+    assert!(matches!(items[..], [Frame::Null, Frame::Integer(1)]));
+
+    // 不完整的数据等待更多数据。
+    let mut buf = BytesMut::from(&b"$-1\r"[..]);
+    assert!(Frame::decode(&mut buf).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn resp2_connection_receives_null_bulk() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    stream
+        .write_all(b"*2\r\n$3\r\nGET\r\n$7\r\nmissing\r\n")
+        .await
+        .unwrap();
+    let mut response = [0; 5];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(&response, b"$-1\r\n");
+
+    shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn resp3_replies_use_native_types() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+    client.hello(Some(3)).await.unwrap();
+
+    let field = (Bytes::from("field"), Bytes::from("value"));
+    client.hset("hash", vec![field.clone()]).await.unwrap();
+    let reply = client.call(args(&["HGETALL", "hash"])).await.unwrap();
+    assert!(matches!(reply, Frame::Map(ref pairs) if pairs.len() == 1));
+    assert_eq!(client.hgetall("hash").await.unwrap(), vec![field]);
+
+    let reply = client
+        .call(args(&["CONFIG", "GET", "maxmemory"]))
+        .await
+        .unwrap();
+    assert!(matches!(reply, Frame::Map(ref pairs) if pairs.len() == 1));
+
+    client
+        .zadd(
+            "zset",
+            vec![(1.5, Bytes::from("a")), (2.0, Bytes::from("b"))],
+        )
+        .await
+        .unwrap();
+    let Frame::Array(items) = client
+        .call(args(&["ZRANGE", "zset", "0", "-1", "WITHSCORES"]))
+        .await
+        .unwrap()
+    else {
+        panic!("预期是 Array");
+    };
+    assert_eq!(items.len(), 2);
+    assert!(matches!(&items[0], Frame::Array(pair)
+        if matches!(pair[..], [Frame::Bulk(_), Frame::Double(score)] if score == 1.5)));
+    assert_eq!(
+        client.zrange_withscores("zset", 0, -1).await.unwrap(),
+        vec![(Bytes::from("a"), 1.5), (Bytes::from("b"), 2.0)]
+    );
+
+    shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn resp2_replies_are_flattened() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    client
+        .hset("hash", vec![(Bytes::from("field"), Bytes::from("value"))])
+        .await
+        .unwrap();
+    let reply = client.call(args(&["HGETALL", "hash"])).await.unwrap();
+    assert!(matches!(reply, Frame::Array(ref items) if items.len() == 2));
+
+    client
+        .zadd("zset", vec![(1.5, Bytes::from("a"))])
+        .await
+        .unwrap();
+    let reply = client
+        .call(args(&["ZRANGE", "zset", "0", "-1", "WITHSCORES"]))
+        .await
+        .unwrap();
+    assert!(matches!(reply, Frame::Array(ref items)
+        if matches!(&items[..], [Frame::Bulk(_), Frame::Bulk(score)] if score == "1.5")));
+
+    shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn large_argument_is_streamed() {
+    let (addr, shutdown) = test_util::spawn_server().await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    // 超过流式读取阈值的参数分多次发送，之后还有其他参数。
+    let value = vec![b'x'; 2 * 1024 * 1024];
+    let header = format!("*5\r\n$3\r\nSET\r\n$3\r\nbig\r\n${}\r\n", value.len());
+    stream.write_all(header.as_bytes()).await.unwrap();
+    for chunk in value.chunks(100 * 1024) {
+        stream.write_all(chunk).await.unwrap();
+    }
+    stream
+        .write_all(b"\r\n$2\r\nEX\r\n$3\r\n100\r\n")
+        .await
+        .unwrap();
+    let mut response = [0; 5];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(&response, b"+OK\r\n");
+
+    let mut client = Client::connect(addr).await.unwrap();
+    assert!(client.ttl("big").await.unwrap() > 0);
+    let mut dst = Vec::new();
+    let len = client.get_to("big", &mut dst).await.unwrap();
+    assert_eq!(len, Some(value.len() as u64));
+    assert!(dst == value);
+
+    shutdown.shutdown().await;
+}
+
+fn args(args: &[&str]) -> Vec<Bytes> {
+    args.iter()
+        .map(|arg| Bytes::from(arg.to_string()))
+        .collect()
+}
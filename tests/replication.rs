@@ -0,0 +1,208 @@
+//! 主从复制的集成测试。
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use my_redis::{client::Client, test_util};
+use tokio::time;
+
+/// 等待副本追上主节点：在主节点上递增一个计数器，直到副本上能读到新的值。
+///
+/// 复制连接按顺序发送写命令，所以之前的写命令也都已经到达副本。
+async fn wait_synced(primary: &mut Client, replica: &mut Client) {
+    let marker = primary.incr("sync").await.unwrap();
+    let marker = Some(Bytes::from(marker.to_string()));
+    time::timeout(Duration::from_secs(5), async {
+        while replica.get("sync").await.unwrap() != marker {
+            time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("副本没有完成同步");
+}
+
+#[tokio::test]
+async fn publish_reaches_replica_subscribers() {
+    let (primary_addr, primary_shutdown) = test_util::spawn_server().await;
+    let (replica_addr, replica_shutdown) = test_util::spawn_server().await;
+
+    let mut primary = Client::connect(primary_addr).await.unwrap();
+    let mut replica = Client::connect(replica_addr).await.unwrap();
+    replica
+        .replicaof(Some(("127.0.0.1", primary_addr.port())))
+        .await
+        .unwrap();
+    wait_synced(&mut primary, &mut replica).await;
+
+    let mut subscriber = Client::connect(replica_addr)
+        .await
+        .unwrap()
+        .subscribe(vec!["news".to_string()])
+        .await
+        .unwrap();
+    // 主节点上没有订阅者，信息只会通过复制连接到达副本。
+    assert_eq!(
+        primary.publish("news", Bytes::from("hello")).await.unwrap(),
+        0
+    );
+
+    let message = time::timeout(Duration::from_secs(5), subscriber.next_message())
+        .await
+        .expect("副本的订阅者没有收到信息")
+        .unwrap()
+        .unwrap();
+    assert_eq!(message.channel, "news");
+    assert_eq!(message.content, Bytes::from("hello"));
+
+    primary_shutdown.shutdown().await;
+    replica_shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn writes_reach_replica() {
+    let (primary_addr, primary_shutdown) = test_util::spawn_server().await;
+    let (replica_addr, replica_shutdown) = test_util::spawn_server().await;
+
+    let mut primary = Client::connect(primary_addr).await.unwrap();
+    // 同步之前写入的数据通过全量同步到达副本。
+    primary.set("before", Bytes::from("1")).await.unwrap();
+
+    let mut replica = Client::connect(replica_addr).await.unwrap();
+    replica
+        .replicaof(Some(("127.0.0.1", primary_addr.port())))
+        .await
+        .unwrap();
+    wait_synced(&mut primary, &mut replica).await;
+    assert_eq!(replica.get("before").await.unwrap(), Some(Bytes::from("1")));
+
+    // 之后的写命令通过复制连接到达副本。
+    primary.set("after", Bytes::from("2")).await.unwrap();
+    primary.incr_by("counter", 5).await.unwrap();
+    wait_synced(&mut primary, &mut replica).await;
+    assert_eq!(replica.get("after").await.unwrap(), Some(Bytes::from("2")));
+    assert_eq!(
+        replica.get("counter").await.unwrap(),
+        Some(Bytes::from("5"))
+    );
+
+    // 副本拒绝客户端的写命令。
+    let err = replica.set("after", Bytes::from("3")).await.unwrap_err();
+    assert!(err.to_string().starts_with("READONLY"), "{}", err);
+
+    primary_shutdown.shutdown().await;
+    replica_shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn blocking_pop_is_replicated() {
+    let (primary_addr, primary_shutdown) = test_util::spawn_server().await;
+    let (replica_addr, replica_shutdown) = test_util::spawn_server().await;
+
+    let mut primary = Client::connect(primary_addr).await.unwrap();
+    let mut replica = Client::connect(replica_addr).await.unwrap();
+    replica
+        .replicaof(Some(("127.0.0.1", primary_addr.port())))
+        .await
+        .unwrap();
+    wait_synced(&mut primary, &mut replica).await;
+
+    let mut popper = Client::connect(primary_addr).await.unwrap();
+    let pop = tokio::spawn(async move { popper.blpop(vec!["list".to_string()], None).await });
+    time::sleep(Duration::from_millis(50)).await;
+    primary
+        .rpush("list", vec![Bytes::from("a"), Bytes::from("b")])
+        .await
+        .unwrap();
+    let popped = time::timeout(Duration::from_secs(5), pop)
+        .await
+        .expect("BLPop 没有被唤醒")
+        .unwrap()
+        .unwrap();
+    assert_eq!(popped, Some(("list".to_string(), Bytes::from("a"))));
+
+    // 副本上同样只剩下没有被弹出的元素。
+    wait_synced(&mut primary, &mut replica).await;
+    assert_eq!(
+        replica.lrange("list", 0, -1).await.unwrap(),
+        vec![Bytes::from("b")]
+    );
+
+    primary_shutdown.shutdown().await;
+    replica_shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn concurrent_writes_keep_order() {
+    let (primary_addr, primary_shutdown) = test_util::spawn_server().await;
+    let (replica_addr, replica_shutdown) = test_util::spawn_server().await;
+
+    let mut primary = Client::connect(primary_addr).await.unwrap();
+    let mut replica = Client::connect(replica_addr).await.unwrap();
+    replica
+        .replicaof(Some(("127.0.0.1", primary_addr.port())))
+        .await
+        .unwrap();
+    wait_synced(&mut primary, &mut replica).await;
+
+    // 多个连接同时向同一个列表和不同分片中的列表插入元素，副本上元素的顺序应该与主节点相同。
+    let writers: Vec<_> = (0..4)
+        .map(|writer| {
+            tokio::spawn(async move {
+                let mut client = Client::connect(primary_addr).await.unwrap();
+                for i in 0..50 {
+                    let value = Bytes::from(format!("{}-{}", writer, i));
+                    for key in ["shared", "other"] {
+                        client.rpush(key, vec![value.clone()]).await.unwrap();
+                    }
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.await.unwrap();
+    }
+
+    wait_synced(&mut primary, &mut replica).await;
+    for key in ["shared", "other"] {
+        let expected = primary.lrange(key, 0, -1).await.unwrap();
+        assert_eq!(expected.len(), 200);
+        assert_eq!(replica.lrange(key, 0, -1).await.unwrap(), expected);
+    }
+
+    primary_shutdown.shutdown().await;
+    replica_shutdown.shutdown().await;
+}
+
+#[tokio::test]
+async fn failed_writes_are_not_replicated() {
+    let (primary_addr, primary_shutdown) = test_util::spawn_server().await;
+    let (replica_addr, replica_shutdown) = test_util::spawn_server().await;
+
+    let mut primary = Client::connect(primary_addr).await.unwrap();
+    let mut replica = Client::connect(replica_addr).await.unwrap();
+    replica
+        .replicaof(Some(("127.0.0.1", primary_addr.port())))
+        .await
+        .unwrap();
+    wait_synced(&mut primary, &mut replica).await;
+
+    primary.rpush("list", vec![Bytes::from("a")]).await.unwrap();
+    let offset = repl_offset(&mut primary).await;
+    // 类型不符的写命令回复错误，不会发送给副本。
+    let err = primary.incr("list").await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"), "{}", err);
+    assert_eq!(repl_offset(&mut primary).await, offset);
+
+    primary_shutdown.shutdown().await;
+    replica_shutdown.shutdown().await;
+}
+
+/// 读取`INFO replication`中的复制偏移量。
+async fn repl_offset(client: &mut Client) -> u64 {
+    let info = client.info(&["replication"]).await.unwrap();
+    info.lines()
+        .find_map(|line| line.strip_prefix("master_repl_offset:"))
+        .unwrap()
+        .parse()
+        .unwrap()
+}
@@ -0,0 +1,72 @@
+//! 快照保存与恢复的集成测试。
+
+use std::{net::SocketAddr, path::Path, time::Duration};
+
+use bytes::Bytes;
+use my_redis::{client::Client, server::Server};
+use tokio::{net::TcpListener, sync::oneshot, task::JoinHandle};
+
+/// 从快照文件`path`恢复数据后，在后台任务中运行服务器。
+///
+/// 返回服务器的地址、关闭信号的发送端以及运行服务器的任务。
+async fn spawn_with_snapshot(path: &Path) -> (SocketAddr, oneshot::Sender<()>, JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::new(listener).load_snapshot(path).await.unwrap();
+    let (notify, shutdown) = oneshot::channel::<()>();
+    let task = tokio::spawn(server.run(shutdown));
+    (addr, notify, task)
+}
+
+#[tokio::test]
+async fn save_and_restore() {
+    let dir = std::env::temp_dir().join(format!("my-redis-snapshot-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("dump.rdb");
+    let _ = std::fs::remove_file(&path);
+
+    // 快照文件不存在时以空数据库启动。
+    let (addr, notify, task) = spawn_with_snapshot(&path).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_eq!(client.get("string").await.unwrap(), None);
+
+    client.set("string", Bytes::from("value")).await.unwrap();
+    client
+        .set_expires("volatile", Bytes::from("value"), Duration::from_secs(100))
+        .await
+        .unwrap();
+    client
+        .rpush("list", vec![Bytes::from("a"), Bytes::from("b")])
+        .await
+        .unwrap();
+    client
+        .zadd("zset", vec![(1.5, Bytes::from("member"))])
+        .await
+        .unwrap();
+    client.save().await.unwrap();
+
+    drop(notify);
+    task.await.unwrap();
+
+    // 新的服务器从快照文件恢复数据。
+    let (addr, notify, task) = spawn_with_snapshot(&path).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_eq!(
+        client.get("string").await.unwrap(),
+        Some(Bytes::from("value"))
+    );
+    let ttl = client.ttl("volatile").await.unwrap();
+    assert!((1..=100).contains(&ttl), "剩余的过期时间不正确：{}", ttl);
+    assert_eq!(
+        client.lrange("list", 0, -1).await.unwrap(),
+        vec![Bytes::from("a"), Bytes::from("b")]
+    );
+    assert_eq!(
+        client.zrange_withscores("zset", 0, -1).await.unwrap(),
+        vec![(Bytes::from("member"), 1.5)]
+    );
+
+    drop(notify);
+    task.await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}